@@ -0,0 +1,368 @@
+//! PowerCost Tracker - command line interface
+//!
+//! Runs the monitoring, report and export code of the library without Tauri,
+//! for headless machines (Linux servers) and diagnostics:
+//! - `watch`: live readings from the autodetected power source
+//! - `report`: consumption of the last days against the days before
+//! - `export`: sessions to CSV or JSON
+//! - `dedup`: removes duplicate readings recorded close together
+//!
+//! The app's configuration and database are used unless `--config` / `--db`
+//! point elsewhere.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use powercost_tracker_lib::core::interval::Cadence;
+use powercost_tracker_lib::core::{Config, Result, MAX_DUPLICATE_READING_WINDOW_SECS};
+use powercost_tracker_lib::db::{self, Database, READING_INTERVAL_SECS};
+use powercost_tracker_lib::export::{self, ExportFormat};
+use powercost_tracker_lib::hardware::{MonitorOptions, PowerMonitor};
+use powercost_tracker_lib::pricing::{self, PricingEngine};
+use powercost_tracker_lib::report::{self, ReportPeriod};
+
+const USAGE: &str = "\
+Usage: powercost-cli [--config PATH] [--db PATH] <command> [options]
+
+Commands:
+  watch     Print live power readings
+              --interval SECS   time between readings (default 1)
+              --count N         stop after N readings (default: run until interrupted)
+              --simulate WATTS  read a constant power instead of the hardware
+              --record          store a reading per 10 s in the database
+  report    Print the consumption of the last complete days
+              --days N          number of days (default 7)
+              --json            print the report as JSON
+  export    Export sessions
+              --csv PATH | --json PATH
+              --days N          only sessions started in the last N days
+              --samples         include the power readings of each session
+  dedup     Remove readings closer than the window to the previous one of their source
+              --window SECS     window in seconds (default: the configured one)
+  help      Show this message
+";
+
+/// Shortest accepted `watch` interval
+const MIN_INTERVAL_SECS: f64 = 0.1;
+
+/// How often `watch --record` refreshes today's daily stats
+const STATS_UPDATE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Options shared by all commands
+#[derive(Debug, Default)]
+struct GlobalOptions {
+    config: Option<PathBuf>,
+    db: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+enum Command {
+    Watch { interval_secs: f64, count: Option<u64>, simulate: Option<f64>, record: bool },
+    Report { days: u32, json: bool },
+    Export { format: ExportFormat, path: PathBuf, days: Option<u32>, samples: bool },
+    Dedup { window_secs: Option<u32> },
+    Help,
+}
+
+fn value<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>, flag: &str) -> std::result::Result<T, String> {
+    let raw = args.next().ok_or_else(|| format!("{} expects a value", flag))?;
+    raw.parse().map_err(|_| format!("Invalid value for {}: {}", flag, raw))
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> std::result::Result<(GlobalOptions, Command), String> {
+    let mut args = args.into_iter();
+    let mut global = GlobalOptions::default();
+
+    let name = loop {
+        match args.next().as_deref() {
+            Some("--config") => global.config = Some(value(&mut args, "--config")?),
+            Some("--db") => global.db = Some(value(&mut args, "--db")?),
+            Some("-h" | "--help") | None => return Ok((global, Command::Help)),
+            Some(name) => break name.to_string(),
+        }
+    };
+
+    let command = match name.as_str() {
+        "watch" => {
+            let (mut interval_secs, mut count, mut simulate, mut record) = (1.0_f64, None, None, false);
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--interval" => interval_secs = value(&mut args, "--interval")?,
+                    "--count" => count = Some(value(&mut args, "--count")?),
+                    "--simulate" => simulate = Some(value(&mut args, "--simulate")?),
+                    "--record" => record = true,
+                    other => return Err(format!("Unknown option for watch: {}", other)),
+                }
+            }
+            if !interval_secs.is_finite() || interval_secs < MIN_INTERVAL_SECS {
+                return Err(format!("--interval must be at least {}s", MIN_INTERVAL_SECS));
+            }
+            Command::Watch { interval_secs, count, simulate, record }
+        }
+        "report" => {
+            let (mut days, mut json) = (7, false);
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--days" => days = value(&mut args, "--days")?,
+                    "--json" => json = true,
+                    other => return Err(format!("Unknown option for report: {}", other)),
+                }
+            }
+            if days == 0 {
+                return Err("--days must be at least 1".to_string());
+            }
+            Command::Report { days, json }
+        }
+        "export" => {
+            let (mut target, mut days, mut samples) = (None, None, false);
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--csv" => target = Some((ExportFormat::Csv, value(&mut args, "--csv")?)),
+                    "--json" => target = Some((ExportFormat::Json, value(&mut args, "--json")?)),
+                    "--days" => days = Some(value(&mut args, "--days")?),
+                    "--samples" => samples = true,
+                    other => return Err(format!("Unknown option for export: {}", other)),
+                }
+            }
+            let (format, path) = target.ok_or("export needs --csv PATH or --json PATH")?;
+            Command::Export { format, path, days, samples }
+        }
+        "dedup" => {
+            let mut window_secs = None;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--window" => window_secs = Some(value(&mut args, "--window")?),
+                    other => return Err(format!("Unknown option for dedup: {}", other)),
+                }
+            }
+            if window_secs.is_some_and(|secs| secs == 0 || secs > MAX_DUPLICATE_READING_WINDOW_SECS) {
+                return Err(format!("--window must be between 1 and {}", MAX_DUPLICATE_READING_WINDOW_SECS));
+            }
+            Command::Dedup { window_secs }
+        }
+        "help" => Command::Help,
+        other => return Err(format!("Unknown command: {}", other)),
+    };
+    Ok((global, command))
+}
+
+fn load_config(global: &GlobalOptions) -> Result<Config> {
+    match &global.config {
+        Some(path) => Config::load_from(path),
+        None => Config::load().map(|(config, _)| config),
+    }
+}
+
+fn open_db(global: &GlobalOptions) -> Result<Database> {
+    match &global.db {
+        Some(path) => Database::open(path),
+        None => Database::new(),
+    }
+}
+
+fn pricing_engine(config: &Config) -> PricingEngine {
+    let mut pricing = PricingEngine::new(&config.pricing);
+    pricing.set_fetched_rate(pricing::currency::load_cached());
+    pricing
+}
+
+fn watch(global: &GlobalOptions, interval_secs: f64, count: Option<u64>, simulate: Option<f64>, record: bool) -> Result<()> {
+    let config = load_config(global)?;
+    let db = if record { Some(open_db(global)?) } else { None };
+
+    let monitor = match simulate {
+        Some(watts) => PowerMonitor::simulated(watts),
+        None => PowerMonitor::new(&MonitorOptions::from_config(&config)).unwrap_or_else(|e| {
+            eprintln!("No hardware sensor ({}), using estimation", e);
+            PowerMonitor::estimation_fallback()
+        }),
+    };
+    let pricing = PricingEngine::new(&config.pricing);
+    let symbol = &config.pricing.currency_symbol;
+    println!("Source: {}{}", monitor.get_source_name(), if monitor.is_estimated() { " (estimated)" } else { "" });
+
+    let interval = Duration::from_secs_f64(interval_secs);
+    let started = Instant::now();
+    let mut last_reading = started;
+    let mut last_stats_update = started;
+    // Stored readings each stand for READING_INTERVAL_SECS, not for --interval
+    let mut cadence = Cadence::new(READING_INTERVAL_SECS as f64);
+    let max_due = cadence.max_due_per_tick(interval_secs);
+    let (mut cumulative_wh, mut cost, mut last_watts, mut taken) = (0.0, 0.0, 0.0, 0u64);
+
+    while count.map_or(true, |count| taken < count) {
+        if taken > 0 {
+            thread::sleep(interval);
+        }
+        let reading = monitor.get_reading();
+        let watts = match &reading {
+            Ok(reading) => reading.power_watts,
+            Err(e) => {
+                eprintln!("Failed to read power: {}", e);
+                0.0
+            }
+        };
+
+        let elapsed_secs = last_reading.elapsed().as_secs_f64();
+        let energy_wh = watts * elapsed_secs / 3600.0;
+        last_reading = Instant::now();
+        cumulative_wh += energy_wh;
+        last_watts = watts;
+        cost += pricing.calculate_cost(energy_wh / 1000.0);
+        taken += 1;
+
+        println!(
+            "{}  {:>8.1} W  {:>10.3} Wh  {:>9.5} {}",
+            chrono::Local::now().format("%H:%M:%S"),
+            watts,
+            cumulative_wh,
+            cost,
+            symbol
+        );
+
+        let due = cadence.advance(elapsed_secs, max_due);
+        if let (Some(db), Ok(reading)) = (&db, &reading) {
+            for reading in db::spread_reading(reading, due) {
+                db.insert_reading(&reading)?;
+            }
+            let last = count.is_some_and(|count| taken >= count);
+            if last || last_stats_update.elapsed() >= STATS_UPDATE_INTERVAL {
+                if last {
+                    // The final readings must be counted even within the rate limit
+                    db.invalidate_today_stats();
+                }
+                db.update_today_stats(Some(&config.pricing.mode), Some(pricing.get_current_rate()))?;
+                last_stats_update = Instant::now();
+            }
+        }
+    }
+
+    let duration_secs = started.elapsed().as_secs();
+    let avg_watts = pricing::average_power_watts(cumulative_wh, duration_secs, last_watts);
+    let estimates = pricing.cost_estimates(avg_watts);
+    println!();
+    println!("Readings:     {}", taken);
+    println!("Energy:       {:.3} Wh", cumulative_wh);
+    println!("Cost:         {:.5} {}", cost, symbol);
+    println!("Average:      {:.1} W", avg_watts);
+    println!("Estimates:    {:.4} {s}/h, {:.4} {s}/day, {:.2} {s}/month", estimates.hourly, estimates.daily, estimates.monthly, s = symbol);
+    Ok(())
+}
+
+fn print_report(global: &GlobalOptions, days: u32, json: bool) -> Result<()> {
+    let config = load_config(global)?;
+    let db = open_db(global)?;
+    let today = chrono::Local::now().date_naive();
+    let report = report::build_report(&db, ReportPeriod::Days(days), today, &config.pricing.currency)?
+        .with_exchange_rate(pricing_engine(&config).exchange_rate());
+
+    if json {
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| powercost_tracker_lib::core::Error::Serialization(e.to_string()))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    let symbol = &config.pricing.currency_symbol;
+    let cost = |cost: Option<f64>| cost.map_or("--".to_string(), |c| format!("{:.2} {}", c, symbol));
+    println!("Report {} to {}", report.start_date, report.end_date);
+    println!("Energy:       {:.3} kWh (previous {:.3} kWh)", report.total_kwh, report.previous_total_kwh);
+    println!("Cost:         {} (previous {})", cost(report.total_cost), cost(report.previous_total_cost));
+    if let Some(change) = report.cost_change_percent {
+        println!("Change:       {:+.1}%", change);
+    }
+    if let (Some(rate), Some(converted)) = (&report.exchange_rate, report.converted_total_cost) {
+        println!("Converted:    {:.2} {}{}", converted, rate.currency_symbol, if rate.stale { " (stale rate)" } else { "" });
+    }
+    println!("Sessions:     {}", report.session_count);
+    for session in &report.top_sessions {
+        println!(
+            "  {:<30} {:>10.3} kWh  {:.2} {}",
+            session.label.as_deref().unwrap_or("(unnamed)"),
+            session.surplus_wh / 1000.0,
+            session.surplus_cost,
+            symbol
+        );
+    }
+    for note in &report.day_notes {
+        println!("Note {}: {}", note.date, note.note);
+    }
+    if let Some(lifetime) = report.lifetime.as_ref().filter(|l| l.total_wh > 0.0) {
+        let since = lifetime
+            .first_date
+            .as_deref()
+            .and_then(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            .map_or(String::new(), |d| format!(" since {}", d.format("%B %Y")));
+        println!();
+        println!("You've tracked {:.1} kWh{}, {} sessions", lifetime.total_wh / 1000.0, since, lifetime.session_count);
+    }
+    Ok(())
+}
+
+fn export_sessions(global: &GlobalOptions, format: ExportFormat, path: &std::path::Path, days: Option<u32>, samples: bool) -> Result<()> {
+    let config = load_config(global)?;
+    let db = open_db(global)?;
+    let end = chrono::Utc::now().timestamp();
+    let start = days.map_or(0, |days| end - days as i64 * 86_400);
+
+    let rate = pricing_engine(&config).exchange_rate();
+    let sessions = export::collect(&db, start, end, samples, rate.as_ref(), &chrono::Local)?;
+    let count = export::write(&sessions, format, path)?;
+    println!("Exported {} sessions to {}", count, path.display());
+    Ok(())
+}
+
+fn dedup_readings(global: &GlobalOptions, window_secs: Option<u32>) -> Result<()> {
+    let config = load_config(global)?;
+    let db = open_db(global)?;
+    let window_secs = window_secs.unwrap_or(config.advanced.duplicate_reading_window_secs);
+
+    let outcome = db.dedup_readings(window_secs)?;
+    let pricing = PricingEngine::new(&config.pricing);
+    let rate_at = |ts: i64| pricing.rate_at_timestamp(ts);
+    for date in &outcome.dates {
+        db.rebuild_daily_stats_for_date_at(date, Some(&config.pricing.mode), &rate_at)?;
+    }
+    db.recalculate_sessions_surplus_in_place(&outcome.sessions, &rate_at)?;
+    db.refresh_lifetime_totals()?;
+    println!(
+        "Removed {} duplicate readings, daily stats rebuilt for {} dates and {} sessions",
+        outcome.removed,
+        outcome.dates.len(),
+        outcome.sessions.len()
+    );
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    let (global, command) = match parse_args(std::env::args().skip(1)) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let result = match command {
+        Command::Watch { interval_secs, count, simulate, record } => watch(&global, interval_secs, count, simulate, record),
+        Command::Report { days, json } => print_report(&global, days, json),
+        Command::Export { format, path, days, samples } => export_sessions(&global, format, &path, days, samples),
+        Command::Dedup { window_secs } => dedup_readings(&global, window_secs),
+        Command::Help => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
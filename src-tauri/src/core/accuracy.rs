@@ -0,0 +1,132 @@
+//! Accuracy of the TDP estimator on this machine
+//!
+//! While a measuring source (RAPL, hwmon, powermetrics...) is in use and
+//! tracking is enabled, the estimator runs next to it. Once a minute the
+//! average measured power over that minute is stored with the estimate for
+//! the same minute. Estimates are stored without the correction factor, so
+//! the suggested factor does not depend on the one in effect.
+
+use serde::{Deserialize, Serialize};
+
+/// Time between two samples, in seconds
+pub const SAMPLE_INTERVAL_SECS: u64 = 60;
+
+/// Bounds of the correction factor applied to the estimator
+pub const MIN_CORRECTION: f64 = 0.5;
+pub const MAX_CORRECTION: f64 = 2.0;
+
+/// Samples needed before a correction is suggested (one hour)
+pub const MIN_SAMPLES_FOR_CORRECTION: usize = 60;
+
+/// Most recent samples the summary is computed from (one week)
+pub const SUMMARY_WINDOW: u32 = 7 * 24 * 60;
+
+/// Smallest change of the factor applied automatically
+const AUTO_APPLY_STEP: f64 = 0.01;
+
+/// Measured and estimated power over the same minute
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AccuracySample {
+    pub timestamp: i64,
+    pub measured_watts: f64,
+    pub estimated_watts: f64,
+}
+
+/// Result of `get_estimation_accuracy`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EstimationAccuracy {
+    pub sample_count: usize,
+    /// Mean of |estimated - measured|, None without samples
+    pub mean_absolute_error_watts: Option<f64>,
+    /// Mean of estimated - measured, positive when the estimator reads high
+    pub bias_watts: Option<f64>,
+    /// Factor bringing the estimated energy to the measured one, None below `MIN_SAMPLES_FOR_CORRECTION`
+    pub suggested_correction: Option<f64>,
+    /// Factor the estimator currently applies
+    pub applied_correction: f64,
+    /// Unix timestamps of the oldest and newest sample summarized
+    pub first_sample_at: Option<i64>,
+    pub last_sample_at: Option<i64>,
+}
+
+impl EstimationAccuracy {
+    /// Summary of `samples` (any order) with `applied_correction` in effect
+    pub fn from_samples(samples: &[AccuracySample], applied_correction: f64) -> Self {
+        let mut accuracy = Self {
+            sample_count: samples.len(),
+            applied_correction,
+            ..Self::default()
+        };
+        if samples.is_empty() {
+            return accuracy;
+        }
+
+        let count = samples.len() as f64;
+        let errors = samples.iter().map(|s| s.estimated_watts - s.measured_watts);
+        accuracy.mean_absolute_error_watts = Some(errors.clone().map(f64::abs).sum::<f64>() / count);
+        accuracy.bias_watts = Some(errors.sum::<f64>() / count);
+        accuracy.first_sample_at = samples.iter().map(|s| s.timestamp).min();
+        accuracy.last_sample_at = samples.iter().map(|s| s.timestamp).max();
+
+        let measured: f64 = samples.iter().map(|s| s.measured_watts).sum();
+        let estimated: f64 = samples.iter().map(|s| s.estimated_watts).sum();
+        if samples.len() >= MIN_SAMPLES_FOR_CORRECTION && estimated > 0.0 {
+            accuracy.suggested_correction = Some(clamp_correction(measured / estimated));
+        }
+        accuracy
+    }
+
+    /// Suggested factor if it differs enough from the applied one to replace it
+    pub fn correction_to_apply(&self) -> Option<f64> {
+        self.suggested_correction
+            .filter(|factor| (factor - self.applied_correction).abs() >= AUTO_APPLY_STEP)
+    }
+}
+
+/// `factor` within the correction bounds, 1.0 when it is not a number
+pub fn clamp_correction(factor: f64) -> f64 {
+    if factor.is_finite() {
+        factor.clamp(MIN_CORRECTION, MAX_CORRECTION)
+    } else {
+        1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(count: usize, measured_watts: f64, estimated_watts: f64) -> Vec<AccuracySample> {
+        (0..count)
+            .map(|i| AccuracySample { timestamp: 1_700_000_000 + i as i64 * 60, measured_watts, estimated_watts })
+            .collect()
+    }
+
+    #[test]
+    fn test_accuracy_summary() {
+        let empty = EstimationAccuracy::from_samples(&[], 1.0);
+        assert_eq!(empty.sample_count, 0);
+        assert!(empty.mean_absolute_error_watts.is_none() && empty.suggested_correction.is_none());
+
+        // Too few samples for a suggestion
+        let few = EstimationAccuracy::from_samples(&samples(10, 80.0, 100.0), 1.0);
+        assert_eq!(few.bias_watts, Some(20.0));
+        assert!(few.suggested_correction.is_none());
+
+        let mut mixed = samples(30, 90.0, 100.0);
+        mixed.extend(samples(30, 110.0, 100.0));
+        let accuracy = EstimationAccuracy::from_samples(&mixed, 1.0);
+        assert_eq!(accuracy.mean_absolute_error_watts, Some(10.0));
+        assert_eq!(accuracy.bias_watts, Some(0.0));
+        assert_eq!(accuracy.suggested_correction, Some(1.0));
+        assert!(accuracy.correction_to_apply().is_none());
+        assert_eq!(accuracy.first_sample_at, Some(1_700_000_000));
+
+        // The estimator reading 4x high is only corrected down to the bound
+        let high = EstimationAccuracy::from_samples(&samples(60, 25.0, 100.0), 1.0);
+        assert_eq!(high.suggested_correction, Some(MIN_CORRECTION));
+        assert_eq!(high.correction_to_apply(), Some(MIN_CORRECTION));
+        assert_eq!(clamp_correction(f64::NAN), 1.0);
+        assert_eq!(clamp_correction(3.0), MAX_CORRECTION);
+    }
+}
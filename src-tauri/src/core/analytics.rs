@@ -0,0 +1,487 @@
+//! Current vs previous period comparison
+//!
+//! Buckets are aligned by position: hour of the day for "day", day offset
+//! from the first day for "week" (Monday first) and "month". A bucket without
+//! data is None on that side, so charts show a gap rather than a zero. The
+//! percent change compares the current period so far with the same stretch
+//! of the previous one, not with the whole previous period.
+//!
+//! Also reconciles the energy of a day's sessions with the day's total. The
+//! two are integrated separately: sessions add up every live tick, daily
+//! stats are rebuilt from the stored (sampled) readings, so a small gap is
+//! expected and a large one points at daily stats worth rebuilding.
+
+use crate::core::Session;
+use crate::db::{DailyStats, HourlyStats};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+
+/// Length of the compared periods
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComparisonPeriod {
+    Day,
+    Week,
+    Month,
+}
+
+impl std::str::FromStr for ComparisonPeriod {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "day" => Ok(Self::Day),
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            other => Err(format!("Unknown period '{}', expected day, week or month", other)),
+        }
+    }
+}
+
+impl ComparisonPeriod {
+    /// First and last date of the period containing `today`, and of the one before
+    pub fn ranges(self, today: NaiveDate) -> ((NaiveDate, NaiveDate), (NaiveDate, NaiveDate)) {
+        match self {
+            Self::Day => {
+                let yesterday = today.pred_opt().unwrap_or(today);
+                ((today, today), (yesterday, yesterday))
+            }
+            Self::Week => {
+                let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+                let previous = monday - Duration::days(7);
+                ((monday, monday + Duration::days(6)), (previous, monday - Duration::days(1)))
+            }
+            Self::Month => crate::pricing::month_ranges(today),
+        }
+    }
+}
+
+/// One aligned bucket of both periods
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComparisonBucket {
+    /// Hour of the day (day) or day offset from the period start (week, month)
+    pub position: u32,
+    pub current_kwh: Option<f64>,
+    pub previous_kwh: Option<f64>,
+    pub current_cost: Option<f64>,
+    pub previous_cost: Option<f64>,
+}
+
+/// Aligned series and totals of the current and previous period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodComparison {
+    pub period: ComparisonPeriod,
+    /// Dates as YYYY-MM-DD
+    pub current_start: String,
+    pub current_end: String,
+    pub previous_start: String,
+    pub previous_end: String,
+    pub buckets: Vec<ComparisonBucket>,
+    pub current_kwh: f64,
+    pub previous_kwh: f64,
+    pub current_cost: f64,
+    pub previous_cost: f64,
+    /// Current period so far against the same positions of the previous one, None without previous data
+    pub kwh_change_percent: Option<f64>,
+    pub cost_change_percent: Option<f64>,
+}
+
+/// Energy and cost at one position
+struct Value {
+    position: u32,
+    kwh: f64,
+    cost: Option<f64>,
+}
+
+fn percent_change(current: f64, previous: f64) -> Option<f64> {
+    (previous > 0.0).then(|| (current - previous) / previous * 100.0)
+}
+
+/// Bucket both sides, `elapsed` being the position of the current period we are at
+fn build(period: ComparisonPeriod, today: NaiveDate, bucket_count: u32, elapsed: u32, current: Vec<Value>, previous: Vec<Value>) -> PeriodComparison {
+    let mut buckets: Vec<ComparisonBucket> = (0..bucket_count)
+        .map(|position| ComparisonBucket { position, current_kwh: None, previous_kwh: None, current_cost: None, previous_cost: None })
+        .collect();
+    let add = |slot: &mut Option<f64>, value: f64| *slot = Some(slot.unwrap_or(0.0) + value);
+
+    for value in &current {
+        if let Some(bucket) = buckets.get_mut(value.position as usize) {
+            add(&mut bucket.current_kwh, value.kwh);
+            if let Some(cost) = value.cost {
+                add(&mut bucket.current_cost, cost);
+            }
+        }
+    }
+    for value in &previous {
+        if let Some(bucket) = buckets.get_mut(value.position as usize) {
+            add(&mut bucket.previous_kwh, value.kwh);
+            if let Some(cost) = value.cost {
+                add(&mut bucket.previous_cost, cost);
+            }
+        }
+    }
+
+    let sum = |pick: fn(&ComparisonBucket) -> Option<f64>, up_to: u32| -> f64 {
+        buckets.iter().filter(|b| b.position <= up_to).filter_map(pick).sum()
+    };
+    let current_kwh = sum(|b| b.current_kwh, u32::MAX);
+    let current_cost = sum(|b| b.current_cost, u32::MAX);
+
+    let ((current_start, current_end), (previous_start, previous_end)) = period.ranges(today);
+    let fmt = |d: NaiveDate| d.format("%Y-%m-%d").to_string();
+
+    PeriodComparison {
+        period,
+        current_start: fmt(current_start),
+        current_end: fmt(current_end),
+        previous_start: fmt(previous_start),
+        previous_end: fmt(previous_end),
+        current_kwh,
+        previous_kwh: sum(|b| b.previous_kwh, u32::MAX),
+        current_cost,
+        previous_cost: sum(|b| b.previous_cost, u32::MAX),
+        kwh_change_percent: percent_change(current_kwh, sum(|b| b.previous_kwh, elapsed)),
+        cost_change_percent: percent_change(current_cost, sum(|b| b.previous_cost, elapsed)),
+        buckets,
+    }
+}
+
+/// Compare today with yesterday hour by hour (`now` in local time)
+pub fn compare_hours(now: NaiveDateTime, current: &[HourlyStats], previous: &[HourlyStats]) -> PeriodComparison {
+    let values = |hours: &[HourlyStats]| -> Vec<Value> {
+        hours
+            .iter()
+            .map(|h| Value { position: h.hour, kwh: h.total_wh / 1000.0, cost: Some(h.total_cost) })
+            .collect()
+    };
+    build(ComparisonPeriod::Day, now.date(), 24, now.hour(), values(current), values(previous))
+}
+
+/// Compare this week or month with the previous one day by day (`now` in local time)
+pub fn compare_days(period: ComparisonPeriod, now: NaiveDateTime, current: &[DailyStats], previous: &[DailyStats]) -> PeriodComparison {
+    let today = now.date();
+    let ((current_start, current_end), (previous_start, previous_end)) = period.ranges(today);
+    let values = |days: &[DailyStats], start: NaiveDate, end: NaiveDate| -> Vec<Value> {
+        days.iter()
+            .filter_map(|d| {
+                let date = NaiveDate::parse_from_str(&d.date, "%Y-%m-%d").ok()?;
+                (start..=end).contains(&date).then(|| Value {
+                    position: (date - start).num_days() as u32,
+                    kwh: d.total_wh / 1000.0,
+                    cost: d.total_cost,
+                })
+            })
+            .collect()
+    };
+
+    let bucket_count = ((current_end - current_start).num_days().max((previous_end - previous_start).num_days()) + 1) as u32;
+    let elapsed = (today - current_start).num_days() as u32;
+    build(
+        period,
+        today,
+        bucket_count,
+        elapsed,
+        values(current, current_start, current_end),
+        values(previous, previous_start, previous_end),
+    )
+}
+
+/// Session energy over the day total (in percent of it) above which a rebuild is suggested
+pub const RECONCILIATION_THRESHOLD_PERCENT: f64 = 5.0;
+
+/// How a total was integrated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Integration {
+    /// Every live monitoring tick, as sessions are
+    LiveTicks,
+    /// Readings stored at the sampling interval, as daily stats are
+    SampledReadings,
+}
+
+/// Session energy against the daily total for one local day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reconciliation {
+    /// YYYY-MM-DD
+    pub date: String,
+    /// Daily stats total, None when the day has no stats
+    pub daily_wh: Option<f64>,
+    pub daily_integration: Integration,
+    /// Energy of the sessions within the day, crossing sessions prorated by time
+    pub session_wh: f64,
+    pub session_integration: Integration,
+    pub session_count: usize,
+    /// Sessions crossing a day boundary, only partly counted
+    pub clipped_sessions: usize,
+    /// Time covered by more than one session, counted once per session
+    pub overlap_seconds: i64,
+    /// Most session energy the daily total accounts for: the total plus the energy counted twice in overlaps
+    pub expected_max_session_wh: Option<f64>,
+    /// Session energy beyond `expected_max_session_wh`, in percent of the daily total
+    pub discrepancy_percent: Option<f64>,
+    /// Discrepancy above `RECONCILIATION_THRESHOLD_PERCENT`, rebuilding the day's history should fix it
+    pub suggest_rebuild: bool,
+}
+
+/// Reconcile `sessions` with the day from `day_start` to `day_end` (Unix seconds)
+///
+/// Sessions still running are counted up to `now`. Their energy is assumed
+/// spread evenly over their duration, for both clipping and overlaps.
+pub fn reconcile(date: &str, day_start: i64, day_end: i64, daily: Option<&DailyStats>, sessions: &[Session], now: i64) -> Reconciliation {
+    let mut session_wh = 0.0;
+    let mut session_count = 0;
+    let mut clipped_sessions = 0;
+    let mut clipped_seconds = 0;
+    let mut spans = Vec::new();
+    for session in sessions {
+        let end = session.end_time.unwrap_or(now).max(session.start_time);
+        let (start_in_day, end_in_day) = (session.start_time.max(day_start), end.min(day_end));
+        let in_day = if end == session.start_time { (day_start..day_end).contains(&end) } else { end_in_day > start_in_day };
+        if !in_day {
+            continue;
+        }
+        session_count += 1;
+        let duration = end - session.start_time;
+        if duration == 0 || (start_in_day == session.start_time && end_in_day == end) {
+            session_wh += session.total_wh;
+        } else {
+            clipped_sessions += 1;
+            session_wh += session.total_wh * (end_in_day - start_in_day) as f64 / duration as f64;
+        }
+        clipped_seconds += end_in_day - start_in_day;
+        spans.push((start_in_day, end_in_day));
+    }
+
+    // Seconds covered at least once, the rest of the clipped time is overlap
+    spans.sort_unstable();
+    let mut covered_seconds = 0;
+    let mut reach = i64::MIN;
+    for (start, end) in spans {
+        let start = start.max(reach);
+        if end > start {
+            covered_seconds += end - start;
+        }
+        reach = reach.max(end);
+    }
+    let overlap_seconds = clipped_seconds - covered_seconds;
+    let overlap_wh = if clipped_seconds > 0 { session_wh * overlap_seconds as f64 / clipped_seconds as f64 } else { 0.0 };
+
+    let daily_wh = daily.map(|d| d.total_wh);
+    let expected_max_session_wh = daily_wh.map(|wh| wh + overlap_wh);
+    let discrepancy_percent = daily_wh.zip(expected_max_session_wh).map(|(daily_wh, expected)| {
+        let excess = (session_wh - expected).max(0.0);
+        if daily_wh > 0.0 {
+            excess / daily_wh * 100.0
+        } else if excess > 0.0 {
+            100.0
+        } else {
+            0.0
+        }
+    });
+
+    Reconciliation {
+        date: date.to_string(),
+        daily_wh,
+        daily_integration: Integration::SampledReadings,
+        session_wh,
+        session_integration: Integration::LiveTicks,
+        session_count,
+        clipped_sessions,
+        overlap_seconds,
+        expected_max_session_wh,
+        suggest_rebuild: discrepancy_percent.is_some_and(|p| p > RECONCILIATION_THRESHOLD_PERCENT),
+        discrepancy_percent,
+    }
+}
+
+/// Session returned for a time range, with its energy within the range when clipping was asked
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInRange {
+    #[serde(flatten)]
+    pub session: Session,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range_wh: Option<f64>,
+}
+
+/// Energy of `session` between `start` and `end` (Unix seconds)
+///
+/// Split by the power `samples` (timestamp, watts) taken during the session
+/// when there are some, otherwise prorated by time like `reconcile`.
+/// A session still running is counted up to `now`.
+pub fn clipped_session_wh(session: &Session, start: i64, end: i64, samples: &[(i64, f64)], now: i64) -> f64 {
+    let session_end = session.end_time.unwrap_or(now).max(session.start_time);
+    if session.start_time >= start && session_end <= end {
+        return session.total_wh;
+    }
+
+    let sampled_wh: f64 = samples.iter().map(|&(_, watts)| watts.max(0.0)).sum();
+    if sampled_wh > 0.0 {
+        let in_range_wh: f64 = samples
+            .iter()
+            .filter(|&&(timestamp, _)| (start..end).contains(&timestamp))
+            .map(|&(_, watts)| watts.max(0.0))
+            .sum();
+        return session.total_wh * in_range_wh / sampled_wh;
+    }
+
+    let duration = session_end - session.start_time;
+    let clipped = session_end.min(end) - session.start_time.max(start);
+    if duration > 0 && clipped > 0 {
+        session.total_wh * clipped as f64 / duration as f64
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(date: &str, kwh: f64, cost: Option<f64>) -> DailyStats {
+        DailyStats {
+            date: date.into(),
+            total_wh: kwh * 1000.0,
+            total_cost: cost,
+            avg_watts: 0.0,
+            max_watts: 0.0,
+            pricing_mode: None,
+            usage_seconds: 0,
+            source: None,
+            note: None,
+        }
+    }
+
+    fn at(date: &str, hour: u32) -> NaiveDateTime {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap().and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_week_aligned_by_weekday() {
+        // Wednesday 2024-03-13: this week started Monday 11th, last week Monday 4th
+        let now = at("2024-03-13", 18);
+        let current = [day("2024-03-11", 2.0, Some(0.4)), day("2024-03-13", 1.0, Some(0.2))];
+        let previous = [
+            day("2024-03-04", 1.0, Some(0.2)),
+            day("2024-03-05", 1.0, Some(0.2)),
+            // Same weekday as today, counts towards the change
+            day("2024-03-06", 2.0, Some(0.4)),
+            // Later in the week, only in the total
+            day("2024-03-09", 4.0, Some(0.8)),
+        ];
+        let c = compare_days(ComparisonPeriod::Week, now, &current, &previous);
+
+        assert_eq!((c.current_start.as_str(), c.previous_end.as_str()), ("2024-03-11", "2024-03-10"));
+        assert_eq!(c.buckets.len(), 7);
+        assert_eq!(c.buckets[0].current_kwh, Some(2.0));
+        assert_eq!(c.buckets[0].previous_kwh, Some(1.0));
+        // Tuesday missing this week: a gap, not a zero
+        assert_eq!(c.buckets[1].current_kwh, None);
+        assert_eq!(c.buckets[1].previous_kwh, Some(1.0));
+        assert_eq!(c.buckets[5].previous_kwh, Some(4.0));
+        assert_eq!(c.buckets[6].previous_kwh, None);
+
+        assert!((c.current_kwh - 3.0).abs() < 1e-9);
+        assert!((c.previous_kwh - 8.0).abs() < 1e-9);
+        // 3 kWh so far vs 4 kWh over Monday-Wednesday last week
+        assert!((c.kwh_change_percent.unwrap() + 25.0).abs() < 1e-9);
+        assert!((c.cost_change_percent.unwrap() + 25.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_month_and_day_buckets() {
+        // March (31 days) against February 2024 (29 days)
+        let now = at("2024-03-02", 9);
+        let c = compare_days(ComparisonPeriod::Month, now, &[day("2024-03-01", 1.0, None)], &[day("2024-02-29", 5.0, None)]);
+        assert_eq!(c.buckets.len(), 31);
+        assert_eq!(c.buckets[28].previous_kwh, Some(5.0));
+        assert_eq!(c.buckets[0].current_cost, None);
+        assert_eq!(c.current_cost, 0.0);
+        // Nothing in the first two days of February
+        assert_eq!(c.kwh_change_percent, None);
+
+        let hour = |hour: u32, wh: f64| HourlyStats { hour, total_wh: wh, total_cost: wh / 1000.0 * 0.2 };
+        let c = compare_hours(at("2024-03-13", 1), &[hour(0, 500.0), hour(1, 250.0)], &[hour(0, 1000.0), hour(1, 500.0), hour(23, 100.0)]);
+        assert_eq!(c.buckets.len(), 24);
+        assert_eq!(c.buckets[2].current_kwh, None);
+        assert_eq!(c.buckets[23].previous_kwh, Some(0.1));
+        assert!((c.kwh_change_percent.unwrap() + 50.0).abs() < 1e-9);
+        assert_eq!(c.previous_start, "2024-03-12");
+    }
+
+    fn session(start_time: i64, end_time: Option<i64>, total_wh: f64) -> Session {
+        Session { start_time, end_time, total_wh, ..Session::new(0.0, None) }
+    }
+
+    #[test]
+    fn test_reconcile_clips_sessions_to_the_day() {
+        const DAY: i64 = 86_400;
+        let daily = day("2024-03-10", 1.0, None);
+
+        // One session inside the day, one crossing midnight (half of it counted)
+        let sessions = [session(3600, Some(7200), 300.0), session(DAY - 3600, Some(DAY + 3600), 200.0)];
+        let report = reconcile("2024-03-10", 0, DAY, Some(&daily), &sessions, 2 * DAY);
+        assert_eq!(report.session_wh, 400.0);
+        assert_eq!((report.session_count, report.clipped_sessions, report.overlap_seconds), (2, 1, 0));
+        assert_eq!(report.expected_max_session_wh, Some(1000.0));
+        assert_eq!(report.discrepancy_percent, Some(0.0));
+        assert!(!report.suggest_rebuild);
+        assert_eq!(report.daily_integration, Integration::SampledReadings);
+
+        // Sessions of the day before and a running one counted up to now
+        let sessions = [session(-7200, Some(-3600), 500.0), session(DAY - 1800, None, 100.0)];
+        let report = reconcile("2024-03-10", 0, DAY, Some(&daily), &sessions, DAY + 1800);
+        assert_eq!(report.session_count, 1);
+        assert_eq!(report.session_wh, 50.0);
+    }
+
+    #[test]
+    fn test_clipped_session_wh() {
+        const DAY: i64 = 86_400;
+
+        // Straddling the start and the end of the day, prorated by time
+        assert_eq!(clipped_session_wh(&session(-3600, Some(3600), 200.0), 0, DAY, &[], DAY), 100.0);
+        assert_eq!(clipped_session_wh(&session(DAY - 1800, Some(DAY + 5400), 200.0), 0, DAY, &[], 2 * DAY), 50.0);
+        // Running and inside the range up to now
+        assert_eq!(clipped_session_wh(&session(3600, None, 80.0), 0, DAY, &[], 7200), 80.0);
+
+        // Samples put most of the energy in the evening before
+        let samples = [(-3000, 300.0), (-1200, 300.0), (600, 150.0), (2400, 50.0)];
+        let clipped = clipped_session_wh(&session(-3600, Some(3600), 160.0), 0, DAY, &samples, DAY);
+        assert!((clipped - 40.0).abs() < 1e-9);
+        // No energy in the samples falls back to time
+        let idle = [(-1800, 0.0), (1800, 0.0)];
+        assert_eq!(clipped_session_wh(&session(-3600, Some(3600), 200.0), 0, DAY, &idle, DAY), 100.0);
+    }
+
+    #[test]
+    fn test_reconcile_discrepancy() {
+        const DAY: i64 = 86_400;
+        let daily = day("2024-03-10", 1.0, None);
+
+        // Overlapping sessions may legitimately add up to more than the day
+        let sessions = [session(0, Some(3600), 600.0), session(0, Some(3600), 600.0)];
+        let report = reconcile("2024-03-10", 0, DAY, Some(&daily), &sessions, DAY);
+        assert_eq!(report.overlap_seconds, 3600);
+        assert_eq!(report.expected_max_session_wh, Some(1600.0));
+        assert_eq!(report.discrepancy_percent, Some(0.0));
+
+        // Without overlap the excess is a discrepancy
+        let sessions = [session(0, Some(3600), 600.0), session(3600, Some(7200), 600.0)];
+        let report = reconcile("2024-03-10", 0, DAY, Some(&daily), &sessions, DAY);
+        assert_eq!(report.overlap_seconds, 0);
+        assert!((report.discrepancy_percent.unwrap() - 20.0).abs() < 1e-9);
+        assert!(report.suggest_rebuild);
+
+        // No daily stats: nothing to compare with
+        let report = reconcile("2024-03-10", 0, DAY, None, &sessions, DAY);
+        assert_eq!(report.daily_wh, None);
+        assert_eq!(report.discrepancy_percent, None);
+        assert!(!report.suggest_rebuild);
+    }
+
+    #[test]
+    fn test_parse_period() {
+        assert_eq!("week".parse::<ComparisonPeriod>(), Ok(ComparisonPeriod::Week));
+        assert!("year".parse::<ComparisonPeriod>().is_err());
+    }
+}
@@ -0,0 +1,88 @@
+//! Foreground application time per tracking session
+//!
+//! The focused window's process name is sampled once per detailed tick and
+//! the time since the previous sample is credited to it. Only process names
+//! are kept, never window titles.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Number of applications stored on a session
+pub const TOP_APPS: usize = 3;
+
+/// Time spent in one application during a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppUsage {
+    /// Process name (e.g. "firefox", "Code.exe")
+    pub name: String,
+    pub seconds: f64,
+}
+
+/// Accumulates foreground time per process name
+#[derive(Debug, Clone, Default)]
+pub struct AppUsageTracker {
+    seconds: HashMap<String, f64>,
+}
+
+impl AppUsageTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Credit `seconds` of foreground time to `name`
+    pub fn record(&mut self, name: &str, seconds: f64) {
+        if name.is_empty() || seconds.is_nan() || seconds <= 0.0 {
+            return;
+        }
+        *self.seconds.entry(name.to_string()).or_insert(0.0) += seconds;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seconds.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.seconds.clear();
+    }
+
+    /// The `n` applications with the most time, longest first (ties by name)
+    pub fn top(&self, n: usize) -> Vec<AppUsage> {
+        let mut apps: Vec<AppUsage> = self
+            .seconds
+            .iter()
+            .map(|(name, &seconds)| AppUsage { name: name.clone(), seconds })
+            .collect();
+        apps.sort_by(|a, b| b.seconds.total_cmp(&a.seconds).then_with(|| a.name.cmp(&b.name)));
+        apps.truncate(n);
+        apps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_apps_by_time() {
+        let mut tracker = AppUsageTracker::new();
+        assert!(tracker.top(TOP_APPS).is_empty());
+
+        for _ in 0..3 {
+            tracker.record("code", 5.0);
+        }
+        tracker.record("firefox", 20.0);
+        tracker.record("steam", 5.0);
+        tracker.record("discord", 5.0);
+        tracker.record("", 30.0);
+        tracker.record("ignored", 0.0);
+
+        let top = tracker.top(TOP_APPS);
+        assert_eq!(top.len(), TOP_APPS);
+        assert_eq!(top[0], AppUsage { name: "firefox".to_string(), seconds: 20.0 });
+        assert_eq!(top[1].name, "code");
+        assert_eq!(top[2].name, "discord");
+
+        tracker.clear();
+        assert!(tracker.is_empty());
+    }
+}
@@ -0,0 +1,79 @@
+//! Taskbar badge with today's cost
+//!
+//! Windows overlays a small icon on the taskbar button, drawn with the
+//! receipt's bitmap font on the accent color. Linux (Unity launcher API) and
+//! macOS take a count or a label instead. A cost rounding to zero clears it.
+
+use crate::core::receipt::font;
+use crate::core::receipt::{Image, Role, TextItem};
+
+/// Empty pixels around the text
+const MARGIN: u32 = 2;
+/// Rows of a glyph above the baseline, the badge has no descenders
+const TEXT_ROWS: u32 = 7;
+
+const BACKGROUND: [u8; 3] = [0x22, 0xC5, 0x5E];
+const TEXT: [u8; 3] = [0x10, 0x12, 0x16];
+
+/// Today's cost as shown on the badge, None when it rounds to zero
+///
+/// One decimal below 10 ("0.4"), whole units above, "999+" from 1000 on.
+pub fn label(cost: f64) -> Option<String> {
+    if !cost.is_finite() || cost < 0.05 {
+        return None;
+    }
+    Some(if cost < 9.95 {
+        format!("{:.1}", cost)
+    } else if cost < 999.5 {
+        format!("{:.0}", cost)
+    } else {
+        "999+".to_string()
+    })
+}
+
+/// Count for launchers that only show integers, rounded up so a cost under one unit still shows
+pub fn count(cost: f64) -> Option<i64> {
+    label(cost).map(|_| cost.ceil().min(i64::MAX as f64) as i64)
+}
+
+/// Square badge with `label` centered on it
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn render(label: &str) -> Image {
+    let text_width = font::text_width(label, 1);
+    let size = (text_width + 2 * MARGIN).max(TEXT_ROWS + 2 * MARGIN);
+    let mut image = Image::new(size, size, BACKGROUND);
+    let text = TextItem {
+        text: label.to_string(),
+        role: Role::Value,
+        x: (size - text_width) / 2,
+        y: (size - TEXT_ROWS) / 2,
+        scale: 1,
+    };
+    image.draw_text(&text, TEXT);
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_badge_label_and_render() {
+        assert_eq!(label(0.0), None);
+        assert_eq!(label(0.04), None);
+        assert_eq!(label(f64::NAN), None);
+        assert_eq!(label(0.42).as_deref(), Some("0.4"));
+        assert_eq!(label(9.96).as_deref(), Some("10"));
+        assert_eq!(label(123.4).as_deref(), Some("123"));
+        assert_eq!(label(2500.0).as_deref(), Some("999+"));
+        assert_eq!(count(0.42), Some(1));
+        assert_eq!(count(0.0), None);
+
+        let image = render("0.4");
+        assert_eq!((image.width, image.height), (font::text_width("0.4", 1) + 2 * MARGIN, font::text_width("0.4", 1) + 2 * MARGIN));
+        assert_eq!(image.to_rgba().len(), (image.width * image.height * 4) as usize);
+        // Corner left as background, some text pixels drawn
+        assert_eq!(&image.pixels[0..3], &BACKGROUND);
+        assert!(image.pixels.chunks(3).any(|p| p == TEXT));
+    }
+}
@@ -0,0 +1,153 @@
+//! Wall-clock sanity checks for the monitoring loops
+//!
+//! Energy is integrated over `Instant` deltas, but sessions and readings are
+//! timestamped with the wall clock, which steps on NTP corrections or manual
+//! time changes. Comparing both clocks from one tick to the next detects those
+//! steps. A resume from suspend shows up as a forward step too, the monotonic
+//! clock not advancing while suspended, so only backward steps re-anchor
+//! anything: forward ones are logged and left alone.
+
+use chrono::{NaiveDate, TimeZone};
+use std::time::Instant;
+
+/// Disagreement between the wall and monotonic clocks (seconds) reported as a jump
+pub const CLOCK_JUMP_THRESHOLD_SECS: i64 = 10;
+
+/// Compares the wall clock with the monotonic clock between ticks
+#[derive(Debug, Default)]
+pub struct ClockJumpDetector {
+    last: Option<(Instant, i64)>,
+}
+
+impl ClockJumpDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a tick at `now` / `wall` (Unix seconds), returns how far the wall
+    /// clock jumped since the previous tick (negative when it went backwards)
+    pub fn check(&mut self, now: Instant, wall: i64) -> Option<i64> {
+        let (last_instant, last_wall) = self.last.replace((now, wall))?;
+        let monotonic_secs = now.saturating_duration_since(last_instant).as_secs_f64();
+        let jump = ((wall - last_wall) as f64 - monotonic_secs).round() as i64;
+        (jump.abs() > CLOCK_JUMP_THRESHOLD_SECS).then_some(jump)
+    }
+}
+
+/// Seconds from `from` to `to`, zero when the clock went backwards in between
+pub fn elapsed_secs(from: i64, to: i64) -> i64 {
+    (to - from).max(0)
+}
+
+/// Wall-clock start of something running for `running_secs` (monotonic) at `wall`
+pub fn anchored_start(wall: i64, running_secs: u64) -> i64 {
+    wall - running_secs as i64
+}
+
+/// Minutes since midnight of an "HH:MM" time
+pub fn parse_hhmm(time: &str) -> Option<u32> {
+    let (h, m) = time.trim().split_once(':')?;
+    let (h, m): (u32, u32) = (h.parse().ok()?, m.parse().ok()?);
+    (h < 24 && m < 60).then_some(h * 60 + m)
+}
+
+/// UTC timestamp of the start of `date` in `tz`
+///
+/// When midnight falls in a DST gap, the day starts at the first valid local time.
+pub fn local_day_start<Tz: TimeZone>(date: NaiveDate, tz: &Tz) -> i64 {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+    (0..=2)
+        .find_map(|h| tz.from_local_datetime(&(midnight + chrono::Duration::hours(h))).earliest())
+        .map(|dt| dt.timestamp())
+        .unwrap_or_else(|| midnight.and_utc().timestamp())
+}
+
+/// A tick that ended on a later local day than the one it is counted in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MidnightCrossing {
+    /// UTC timestamp of the start of the new day
+    pub at: i64,
+    pub date: NaiveDate,
+    /// Share of the tick before `at`, 0 to 1
+    pub fraction_before: f64,
+}
+
+/// Midnight crossed by a tick from `tick_start` to `tick_end` (Unix seconds)
+/// counted in a day started on `day`, None while the tick ends on that day
+///
+/// After a long suspend several midnights may have passed: only the last one
+/// is returned, the days slept through being part of the earlier day.
+pub fn midnight_crossing<Tz: TimeZone>(day: NaiveDate, tick_start: f64, tick_end: f64, tz: &Tz) -> Option<MidnightCrossing> {
+    let date = tz.timestamp_opt(tick_end.floor() as i64, 0).earliest()?.date_naive();
+    if date <= day {
+        return None;
+    }
+    let at = local_day_start(date, tz);
+    let fraction_before = if tick_end > tick_start {
+        ((at as f64 - tick_start) / (tick_end - tick_start)).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    Some(MidnightCrossing { at, date, fraction_before })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_backward_clock_jump() {
+        let t0 = Instant::now();
+        let mut detector = ClockJumpDetector::new();
+        assert_eq!(detector.check(t0, 1_700_003_600), None);
+
+        // Regular ticks, a second of rounding is not a jump
+        assert_eq!(detector.check(t0 + Duration::from_secs(2), 1_700_003_602), None);
+        assert_eq!(detector.check(t0 + Duration::from_millis(3900), 1_700_003_603), None);
+        assert_eq!(detector.check(t0 + Duration::from_secs(4), 1_700_003_604), None);
+
+        // Clock set back one hour between two ticks
+        let t1 = t0 + Duration::from_secs(5);
+        assert_eq!(detector.check(t1, 1_700_000_005), Some(-3600));
+        // Regular again, from the new wall time
+        assert_eq!(detector.check(t1 + Duration::from_secs(1), 1_700_000_006), None);
+
+        // A session started before the jump: its stored start is now in the future
+        let stored_start = 1_700_003_000;
+        assert_eq!(elapsed_secs(stored_start, 1_700_000_006), 0);
+        let start = anchored_start(1_700_000_006, 606);
+        assert_eq!(start, 1_699_999_400);
+        assert_eq!(elapsed_secs(start, 1_700_000_006), 606);
+    }
+
+    #[test]
+    fn test_forward_jump() {
+        let t0 = Instant::now();
+        let mut detector = ClockJumpDetector::new();
+        detector.check(t0, 1_000);
+        // Suspended half an hour: wall time moved on, the monotonic clock did not
+        assert_eq!(detector.check(t0 + Duration::from_secs(1), 2_801), Some(1_800));
+    }
+
+    #[test]
+    fn test_midnight_crossing() {
+        let tz = chrono::FixedOffset::east_opt(3600).unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 6, 3).unwrap();
+        // 2024-06-04 00:00 local
+        let midnight = 1_717_455_600;
+        assert_eq!(local_day_start(day.succ_opt().unwrap(), &tz), midnight);
+
+        assert_eq!(midnight_crossing(day, midnight as f64 - 2.0, midnight as f64 - 0.5, &tz), None);
+
+        let crossing = midnight_crossing(day, midnight as f64 - 0.25, midnight as f64 + 0.75, &tz).unwrap();
+        assert_eq!(crossing.at, midnight);
+        assert_eq!(crossing.date, day.succ_opt().unwrap());
+        assert!((crossing.fraction_before - 0.25).abs() < 1e-9);
+
+        // Woken up two days later: the earlier day gets nothing of the tick
+        let crossing = midnight_crossing(day, midnight as f64 + 172_800.0, midnight as f64 + 172_801.0, &tz).unwrap();
+        assert_eq!(crossing.at, midnight + 172_800);
+        assert_eq!(crossing.fraction_before, 0.0);
+    }
+}
@@ -1,9 +1,156 @@
 //! Configuration management
 
+use crate::core::clock;
+use crate::core::locale::{self, SystemLocale};
+use crate::core::notifier::NotificationConfig;
 use crate::core::{Error, Result, SessionCategory};
+use crate::pricing;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Define a config value stored as a snake_case string
+///
+/// Unknown strings (typos in a hand-edited config, values from a newer
+/// version) deserialize to `Other` instead of failing the whole file, and
+/// are written back unchanged.
+macro_rules! config_enum {
+    ($(#[$meta:meta])* $name:ident { $($(#[$vmeta:meta])* $variant:ident => $value:literal,)+ }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+        #[serde(rename_all = "snake_case")]
+        pub enum $name {
+            $($(#[$vmeta])* $variant,)+
+            /// Unrecognized value, kept as written
+            #[serde(untagged)]
+            Other(String),
+        }
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => $value,)+
+                    Self::Other(value) => value,
+                }
+            }
+
+            pub fn is_known(&self) -> bool {
+                !matches!(self, Self::Other(_))
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = std::convert::Infallible;
+
+            fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+                Ok(match s {
+                    $($value => Self::$variant,)+
+                    other => Self::Other(other.to_string()),
+                })
+            }
+        }
+    };
+}
+
+config_enum! {
+    /// Pricing mode
+    PricingMode {
+        Simple => "simple",
+        PeakOffpeak => "peak_offpeak",
+        Seasonal => "seasonal",
+        Tempo => "tempo",
+    }
+}
+
+config_enum! {
+    /// How a dashboard widget shows its value
+    DisplayMode {
+        Text => "text",
+        Bar => "bar",
+        Radial => "radial",
+        Chart => "chart",
+    }
+}
+
+config_enum! {
+    /// Dashboard-wide display density
+    GlobalDisplay {
+        /// Full display with labels and details
+        Normal => "normal",
+        /// Compact display with reduced labels
+        Minimize => "minimize",
+        /// Data-only display, no labels
+        Hard => "hard",
+    }
+}
+
+config_enum! {
+    /// Unit energy is displayed in (unknown values display like auto)
+    EnergyUnit {
+        Wh => "wh",
+        Kwh => "kwh",
+        /// Wh below 1000 Wh, kWh from there
+        Auto => "auto",
+    }
+}
+
+config_enum! {
+    /// When the widget shows the active session instead of its configured items
+    WidgetSessionMode {
+        /// While a session is active
+        Auto => "auto",
+        /// Always, values left out until a session starts
+        Always => "always",
+        /// Never, the configured items only
+        Never => "never",
+    }
+}
+
+config_enum! {
+    /// How keyboard and mouse idleness takes part in session idle detection
+    SessionIdleInput {
+        /// Power near baseline only
+        Off => "off",
+        /// Power near baseline and no recent input
+        And => "and",
+        /// Power near baseline or no recent input
+        Or => "or",
+    }
+}
+
+config_enum! {
+    /// How the process list aggregates processes
+    ProcessGrouping {
+        /// One row per process name
+        Name => "name",
+        /// One row per container (Linux), other processes by name
+        Container => "container",
+    }
+}
+
+/// Decimals costs can be displayed with
+pub const MIN_COST_DECIMALS: usize = 2;
+pub const MAX_COST_DECIMALS: usize = 4;
+
+config_enum! {
+    /// Screen corner the widget window opens in
+    WidgetPosition {
+        TopLeft => "top_left",
+        TopRight => "top_right",
+        BottomLeft => "bottom_left",
+        BottomRight => "bottom_right",
+        Custom => "custom",
+    }
+}
 
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +165,8 @@ pub struct Config {
     pub advanced: AdvancedConfig,
     #[serde(default)]
     pub dashboard: DashboardConfig,
+    #[serde(default)]
+    pub webhook: WebhookConfig,
 }
 
 impl Default for Config {
@@ -28,6 +177,7 @@ impl Default for Config {
             widget: WidgetConfig::default(),
             advanced: AdvancedConfig::default(),
             dashboard: DashboardConfig::default(),
+            webhook: WebhookConfig::default(),
         }
     }
 }
@@ -47,27 +197,156 @@ impl Config {
         Ok(app_config_dir.join("config.toml"))
     }
 
-    /// Load configuration from disk
-    pub fn load() -> Result<Self> {
+    /// Load configuration from disk, recovering from an unreadable file
+    pub fn load() -> Result<(Self, Option<ConfigRecovery>)> {
         let path = Self::config_path()?;
 
         if !path.exists() {
-            let config = Self::default();
+            let mut config = Self::default();
+            if let Some(locale) = locale::system_locale() {
+                config.apply_region_defaults(&locale);
+            }
             config.save()?;
-            return Ok(config);
+            return Ok((config, None));
+        }
+
+        Self::load_or_recover(&path)
+    }
+
+    /// Load the configuration file at `path`, defaults when it does not exist (nothing is written)
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
         }
 
-        let content = fs::read_to_string(&path)?;
-        let mut config: Config = toml::from_str(&content)
+        let content = fs::read_to_string(path)?;
+        Self::parse(&content)
+    }
+
+    /// Load the configuration file at `path`, falling back to its backup when it cannot be parsed
+    ///
+    /// The unreadable file is kept aside for inspection and the recovered
+    /// config (the backup, or defaults without one) is written in its place.
+    pub fn load_or_recover(path: &Path) -> Result<(Self, Option<ConfigRecovery>)> {
+        if !path.exists() {
+            return Ok((Self::default(), None));
+        }
+
+        // Invalid UTF-8 is as corrupt as invalid TOML, other read errors are not
+        let bytes = fs::read(path)?;
+        let error = match std::str::from_utf8(&bytes) {
+            Ok(content) => match Self::parse(content) {
+                Ok(config) => return Ok((config, None)),
+                Err(e) => e.to_string(),
+            },
+            Err(e) => e.to_string(),
+        };
+        log::warn!("Config file {} is unreadable: {}", path.display(), error);
+
+        let corrupt_path = sibling_path(path, &format!("corrupt-{}", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+        fs::rename(path, &corrupt_path)?;
+        let backup_path = sibling_path(path, "bak");
+        let backup = if backup_path.exists() {
+            match Self::load_from(&backup_path) {
+                Ok(config) => Some(config),
+                Err(e) => {
+                    log::warn!("Config backup {} is unreadable too: {}", backup_path.display(), e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let from_backup = backup.is_some();
+        let config = backup.unwrap_or_default();
+        config.save_to(path)?;
+        log::warn!(
+            "Config restored from {}, the unreadable file was kept as {}",
+            if from_backup { "its backup" } else { "defaults" },
+            corrupt_path.display()
+        );
+
+        Ok((config, Some(ConfigRecovery { corrupt_path, from_backup })))
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let mut config: Config = toml::from_str(content)
             .map_err(|e| Error::Config(format!("Failed to parse config: {}", e)))?;
 
+        config.warn_unknown_values();
+        config.normalize();
+
+        Ok(config)
+    }
+
+    /// Fill in what every config must have, for loaded and frontend-sent ones alike
+    pub fn normalize(&mut self) {
         // Merge missing widgets from defaults
-        config.merge_missing_widgets();
+        self.merge_missing_widgets();
 
         // Ensure the "Default" profile always exists
-        config.ensure_default_profile();
+        self.ensure_default_profile();
 
-        Ok(config)
+        // Older versions accepted any process list limit
+        self.advanced.process_list_limit = self.advanced.process_list_limit.clamp(MIN_PROCESS_LIST_LIMIT, MAX_PROCESS_LIST_LIMIT);
+        self.general.cost_decimals = self.general.cost_decimals.clamp(MIN_COST_DECIMALS, MAX_COST_DECIMALS);
+        // An empty hook path from the settings page means none
+        if self.advanced.session_end_hook.as_deref().is_some_and(|hook| hook.trim().is_empty()) {
+            self.advanced.session_end_hook = None;
+        }
+    }
+
+    /// Top-level sections (`general`, `pricing`, ...) that differ from `other`
+    pub fn changed_sections(&self, other: &Config) -> Vec<String> {
+        let (Ok(serde_json::Value::Object(ours)), Ok(serde_json::Value::Object(theirs))) =
+            (serde_json::to_value(self), serde_json::to_value(other))
+        else {
+            return Vec::new();
+        };
+        ours.into_iter()
+            .filter(|(section, value)| theirs.get(section) != Some(value))
+            .map(|(section, _)| section)
+            .collect()
+    }
+
+    /// Save `staged` to `path`, then make it the current config
+    ///
+    /// Nothing changes in memory when the file cannot be written. Returns the
+    /// sections that changed.
+    pub fn replace_saved(&mut self, staged: Config, path: &Path) -> Result<Vec<String>> {
+        staged.save_to(path)?;
+        let changed = staged.changed_sections(self);
+        *self = staged;
+        Ok(changed)
+    }
+
+    /// Pricing preset of the user's region for a new config, remembered until confirmed
+    pub fn apply_region_defaults(&mut self, locale: &SystemLocale) {
+        let Some(preset) = locale.pricing_preset() else {
+            return;
+        };
+        pricing::apply_preset(&mut self.pricing, preset);
+        self.pricing.auto_region = Some(preset.country_code.to_string());
+        log::info!("Pricing defaults set for region {}", preset.country_code);
+    }
+
+    /// Log values that were not recognized and fall back to defaults
+    fn warn_unknown_values(&self) {
+        if !self.pricing.mode.is_known() {
+            log::warn!("Unknown pricing mode '{}', using simple pricing", self.pricing.mode);
+        }
+        if !self.general.energy_unit.is_known() {
+            log::warn!("Unknown energy unit '{}', using auto", self.general.energy_unit);
+        }
+        if !self.widget.position.is_known() {
+            log::warn!("Unknown widget position '{}'", self.widget.position);
+        }
+        if !self.dashboard.global_display.is_known() {
+            log::warn!("Unknown dashboard display mode '{}'", self.dashboard.global_display);
+        }
+        for widget in self.dashboard.widgets.iter().filter(|w| !w.display_mode.is_known()) {
+            log::warn!("Unknown display mode '{}' for widget '{}'", widget.display_mode, widget.id);
+        }
     }
 
     /// Merge any missing widgets from default config into current config
@@ -113,14 +392,65 @@ impl Config {
 
     /// Save configuration to disk
     pub fn save(&self) -> Result<()> {
-        let path = Self::config_path()?;
+        self.save_to(&Self::config_path()?)
+    }
+
+    /// Save configuration to `path`, keeping the previous version as `<path>.bak`
+    ///
+    /// The file is written aside, synced and renamed over `path`, so a crash
+    /// mid-write leaves either the old or the new config, never half of one.
+    pub fn save_to(&self, path: &Path) -> Result<()> {
         let content = toml::to_string_pretty(self)
             .map_err(|e| Error::Serialization(e.to_string()))?;
-        fs::write(path, content)?;
+
+        let tmp_path = sibling_path(path, "tmp");
+        let written = fs::File::create(&tmp_path).and_then(|mut file| {
+            file.write_all(content.as_bytes())?;
+            file.sync_all()
+        });
+        if let Err(e) = written {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e.into());
+        }
+
+        // A file that no longer parses would replace a good backup
+        let previous_parses = fs::read_to_string(path).is_ok_and(|old| toml::from_str::<Config>(&old).is_ok());
+        if previous_parses {
+            if let Err(e) = fs::copy(path, sibling_path(path, "bak")) {
+                log::warn!("Failed to back up the previous config: {}", e);
+            }
+        }
+
+        if let Err(e) = fs::rename(&tmp_path, path) {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e.into());
+        }
+        // The rename itself only survives a power loss once the directory is synced
+        #[cfg(unix)]
+        if let Some(dir) = path.parent() {
+            let _ = fs::File::open(dir).and_then(|dir| dir.sync_all());
+        }
         Ok(())
     }
 }
 
+/// How an unreadable config file was replaced at load time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigRecovery {
+    /// Where the unreadable file was moved
+    pub corrupt_path: PathBuf,
+    /// Whether the backup was used, defaults were otherwise
+    pub from_backup: bool,
+}
+
+/// `<path>.<suffix>`, next to `path`
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
 /// General application settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GeneralConfig {
@@ -151,9 +481,30 @@ pub struct GeneralConfig {
     /// Run as administrator on startup (Windows only)
     #[serde(default)]
     pub run_as_admin: bool,
+    /// When the startup elevation prompt was last denied (Unix timestamp)
+    #[serde(default)]
+    pub elevation_denied_at: Option<i64>,
+    /// Show emoji next to session categories (off for fonts without emoji)
+    #[serde(default = "default_true")]
+    pub use_emoji: bool,
     /// Check for updates at startup
     #[serde(default)]
     pub check_updates_at_startup: bool,
+    /// Show today's cost on the taskbar (dock) icon
+    #[serde(default)]
+    pub show_taskbar_badge: bool,
+    /// Per-kind muting and quiet hours of alerts
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    /// Zone history days and hours are bucketed in: "local", "utc", a fixed offset or an IANA name
+    #[serde(default = "default_history_timezone")]
+    pub history_timezone: String,
+    /// Unit displayed energy is shown in
+    #[serde(default = "default_energy_unit")]
+    pub energy_unit: EnergyUnit,
+    /// Decimals of displayed costs (`MIN_COST_DECIMALS` to `MAX_COST_DECIMALS`)
+    #[serde(default = "default_cost_decimals")]
+    pub cost_decimals: usize,
     /// Saved window X position
     #[serde(default)]
     pub window_x: Option<f64>,
@@ -170,6 +521,9 @@ pub struct GeneralConfig {
 
 fn default_language() -> String { "auto".to_string() }
 fn default_theme() -> String { "dark".to_string() }
+fn default_history_timezone() -> String { "local".to_string() }
+fn default_energy_unit() -> EnergyUnit { EnergyUnit::Auto }
+fn default_cost_decimals() -> usize { MAX_COST_DECIMALS }
 fn default_refresh_rate() -> u64 { 1000 }
 fn default_slow_refresh_rate() -> u64 { 5000 }
 
@@ -185,7 +539,14 @@ impl Default for GeneralConfig {
             start_with_system: false,
             remember_window_position: true,
             run_as_admin: false,
+            elevation_denied_at: None,
+            use_emoji: true,
             check_updates_at_startup: false,
+            show_taskbar_badge: false,
+            notifications: NotificationConfig::default(),
+            history_timezone: default_history_timezone(),
+            energy_unit: default_energy_unit(),
+            cost_decimals: default_cost_decimals(),
             window_x: None,
             window_y: None,
             window_width: None,
@@ -197,9 +558,9 @@ impl Default for GeneralConfig {
 /// Pricing configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PricingConfig {
-    /// Pricing mode: "simple", "peak_offpeak", "seasonal", "tempo"
+    /// Pricing mode (unknown values price like simple)
     #[serde(default = "default_pricing_mode")]
-    pub mode: String,
+    pub mode: PricingMode,
     /// Currency code (EUR, USD, GBP, etc.)
     #[serde(default = "default_currency")]
     pub currency: String,
@@ -218,9 +579,15 @@ pub struct PricingConfig {
     /// Tempo (EDF-style) settings
     #[serde(default)]
     pub tempo: TempoPricing,
+    /// Optional second currency, for converted amounts in reports and exports
+    #[serde(default)]
+    pub secondary: SecondaryCurrency,
+    /// Region whose preset was picked on first run, cleared once the user confirms it
+    #[serde(default)]
+    pub auto_region: Option<String>,
 }
 
-fn default_pricing_mode() -> String { "simple".to_string() }
+fn default_pricing_mode() -> PricingMode { PricingMode::Simple }
 fn default_currency() -> String { "EUR".to_string() }
 fn default_currency_symbol() -> String { "\u{20AC}".to_string() } // Euro sign
 
@@ -234,6 +601,41 @@ impl Default for PricingConfig {
             peak_offpeak: PeakOffpeakPricing::default(),
             seasonal: SeasonalPricing::default(),
             tempo: TempoPricing::default(),
+            secondary: SecondaryCurrency::default(),
+            auto_region: None,
+        }
+    }
+}
+
+/// Second currency amounts are converted to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecondaryCurrency {
+    /// Currency code, conversion is off when empty
+    #[serde(default)]
+    pub currency: String,
+    #[serde(default)]
+    pub currency_symbol: String,
+    /// Units of the secondary currency per unit of the primary one, set by hand
+    #[serde(default)]
+    pub manual_rate: Option<f64>,
+    /// Fetch the rate once a day from `rate_url` (the manual rate is the fallback until the first fetch)
+    #[serde(default)]
+    pub auto_fetch: bool,
+    /// Rate source, `{base}` and `{target}` are replaced by the currency codes
+    #[serde(default = "default_rate_url")]
+    pub rate_url: String,
+}
+
+fn default_rate_url() -> String { "https://api.frankfurter.app/latest?from={base}&to={target}".to_string() }
+
+impl Default for SecondaryCurrency {
+    fn default() -> Self {
+        Self {
+            currency: String::new(),
+            currency_symbol: String::new(),
+            manual_rate: None,
+            auto_fetch: false,
+            rate_url: default_rate_url(),
         }
     }
 }
@@ -361,7 +763,7 @@ impl Default for TempoPricing {
 }
 
 /// Widget configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WidgetConfig {
     /// Widget enabled
     #[serde(default = "default_true")]
@@ -369,9 +771,9 @@ pub struct WidgetConfig {
     /// Show cost (true) or consumption only (false)
     #[serde(default = "default_true")]
     pub show_cost: bool,
-    /// Position: "top_left", "top_right", "bottom_left", "bottom_right", or "custom"
+    /// Screen corner the widget opens in
     #[serde(default = "default_position")]
-    pub position: String,
+    pub position: WidgetPosition,
     /// Widget opacity (0.0 - 1.0)
     #[serde(default = "default_opacity")]
     pub opacity: f64,
@@ -384,14 +786,29 @@ pub struct WidgetConfig {
     /// Widget theme: "default", "minimal", "detailed"
     #[serde(default = "default_widget_theme")]
     pub theme: String,
+    /// Persist `enabled` when the widget is opened or closed manually
+    #[serde(default)]
+    pub remember_open_state: bool,
+    /// Widget update rate, None to follow the critical refresh rate
+    #[serde(default)]
+    pub refresh_rate_ms: Option<u64>,
+    /// When the session items replace `display_items`
+    #[serde(default = "default_widget_session_mode")]
+    pub session_mode: WidgetSessionMode,
+    /// Position the widget was closed at (physical pixels), reopened there instead of `position`
+    #[serde(default)]
+    pub window_x: Option<f64>,
+    #[serde(default)]
+    pub window_y: Option<f64>,
 }
 
 fn default_true() -> bool { true }
-fn default_position() -> String { "bottom_right".to_string() }
+fn default_position() -> WidgetPosition { WidgetPosition::BottomRight }
 fn default_opacity() -> f64 { 0.9 }
 fn default_display_items() -> Vec<String> { vec!["power".to_string(), "cost".to_string()] }
 fn default_widget_size() -> String { "normal".to_string() }
 fn default_widget_theme() -> String { "default".to_string() }
+fn default_widget_session_mode() -> WidgetSessionMode { WidgetSessionMode::Auto }
 
 impl Default for WidgetConfig {
     fn default() -> Self {
@@ -403,6 +820,45 @@ impl Default for WidgetConfig {
             display_items: default_display_items(),
             size: default_widget_size(),
             theme: default_widget_theme(),
+            remember_open_state: false,
+            refresh_rate_ms: None,
+            session_mode: default_widget_session_mode(),
+            window_x: None,
+            window_y: None,
+        }
+    }
+}
+
+/// Scheduled report push to a webhook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Endpoint receiving the report as a JSON POST
+    #[serde(default)]
+    pub url: String,
+    /// Report period: "daily" or "weekly" (sent once the period is complete)
+    #[serde(default = "default_webhook_schedule")]
+    pub schedule: String,
+    /// Local hour (0-23) from which the report is sent
+    #[serde(default = "default_webhook_hour")]
+    pub hour: u32,
+    /// Name of a stored secret sent as a bearer token, if the endpoint needs one
+    #[serde(default)]
+    pub auth_secret: Option<String>,
+}
+
+fn default_webhook_schedule() -> String { "weekly".to_string() }
+fn default_webhook_hour() -> u32 { 9 }
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            schedule: default_webhook_schedule(),
+            hour: default_webhook_hour(),
+            auth_secret: None,
         }
     }
 }
@@ -416,33 +872,197 @@ pub struct AdvancedConfig {
     /// Auto-detect baseline
     #[serde(default = "default_true")]
     pub baseline_auto: bool,
+    /// Separate baselines by time of day (off = one baseline all day)
+    #[serde(default)]
+    pub baseline_schedule: BaselineSchedule,
     /// Active hardware profile
     #[serde(default = "default_profile")]
     pub active_profile: String,
     /// Pinned process names for tracking
     #[serde(default)]
     pub pinned_processes: Vec<String>,
-    /// Number of processes to show in widget (default 10)
+    /// Process names hidden from the top list (case-insensitive, `*` wildcard)
+    #[serde(default)]
+    pub ignored_processes: Vec<String>,
+    /// Number of processes to show in widget (default 10, `MIN_PROCESS_LIST_LIMIT`-`MAX_PROCESS_LIST_LIMIT`)
     #[serde(default = "default_process_limit")]
     pub process_list_limit: usize,
     /// CPU/GPU load threshold (%) to collect extended metrics (per-core freq, fans)
     #[serde(default = "default_extended_threshold")]
     pub extended_metrics_threshold: f64,
     /// Session categories for organizing tracking sessions
-    #[serde(default = "default_session_categories")]
+    /// Empty until seeded from translated defaults on first run
+    #[serde(default)]
     pub session_categories: Vec<SessionCategory>,
+    /// Whether the default categories were already seeded (so deleting them all sticks)
+    #[serde(default)]
+    pub session_categories_seeded: bool,
+    /// Readings above this many watts are discarded as implausible (0 = no limit)
+    #[serde(default = "default_max_power_watts")]
+    pub max_power_watts: f64,
+    /// Readings above this multiple of the recent median are discarded unless sustained (0 = disabled)
+    #[serde(default = "default_spike_filter_factor")]
+    pub spike_filter_factor: f64,
+    /// Readings of a source stored closer than this many seconds to the previous one are skipped (0 = keep all)
+    #[serde(default = "default_duplicate_reading_window_secs")]
+    pub duplicate_reading_window_secs: u32,
+    /// Subtract the tracker's own estimated consumption from session surplus
+    #[serde(default)]
+    pub exclude_self_from_surplus: bool,
+    /// Saved session presets, unique by name
+    #[serde(default)]
+    pub session_templates: Vec<SessionTemplate>,
+    /// End sessions whose surplus stays near baseline this many minutes (0 = never)
+    #[serde(default)]
+    pub auto_end_after_idle_minutes: u32,
+    /// Combine input idleness with the power test (power only where input can't be read)
+    #[serde(default = "default_session_idle_input")]
+    pub session_idle_input: SessionIdleInput,
+    /// Rows of the process list: by process name, or by container where one is known
+    #[serde(default = "default_process_grouping")]
+    pub process_grouping: ProcessGrouping,
+    /// Executable run with the session JSON on stdin when a session ends, see `session_hook`
+    #[serde(default)]
+    pub session_end_hook: Option<String>,
+    /// Record the foreground app's process name during sessions (off for privacy)
+    #[serde(default)]
+    pub track_active_window: bool,
+    /// End the active session at local midnight and continue it in a new one
+    #[serde(default)]
+    pub split_sessions_at_midnight: bool,
+    /// Alert when VRAM usage stays above this percentage for a minute (0 = off)
+    #[serde(default)]
+    pub vram_alert_percent: u32,
+    /// Timeout of GPU and PowerShell commands on Windows (NVML is not affected)
+    #[serde(default = "default_gpu_command_timeout_ms")]
+    pub gpu_command_timeout_ms: u64,
+    /// Never spawn PowerShell probes (some antivirus software flags them)
+    #[serde(default)]
+    pub disable_powershell_probes: bool,
+    /// Never spawn nvidia-smi, rocm-smi or amd-smi (NVML still works)
+    #[serde(default)]
+    pub disable_gpu_cli: bool,
+    /// Skip the GPU command line tools while a fullscreen app is focused (NVML still works)
+    #[serde(default = "default_true")]
+    pub pause_gpu_polling_in_game: bool,
+    /// Idle power of a discrete GPU that cannot be read, added to estimated readings
+    #[serde(default)]
+    pub gpu_idle_watts: Option<f64>,
+    /// Full-load power of that GPU, the estimate follows CPU load up to it
+    #[serde(default)]
+    pub gpu_tdp_watts: Option<f64>,
+    /// Sample the estimator next to a measuring source once a minute
+    #[serde(default)]
+    pub accuracy_tracking: bool,
+    /// Replace `estimation_correction` with the factor suggested by the samples
+    #[serde(default)]
+    pub auto_apply_correction: bool,
+    /// Multiplier of the estimator's readings (0.5-2.0)
+    #[serde(default = "default_estimation_correction")]
+    pub estimation_correction: f64,
 }
 
 fn default_profile() -> String { "default".to_string() }
 fn default_process_limit() -> usize { 10 }
+
+/// Accepted range of `advanced.process_list_limit`, the list is sent with every detailed update
+pub const MIN_PROCESS_LIST_LIMIT: usize = 1;
+pub const MAX_PROCESS_LIST_LIMIT: usize = 100;
+
+/// Longest `advanced.duplicate_reading_window_secs`, readings are stored about every 10 seconds
+pub const MAX_DUPLICATE_READING_WINDOW_SECS: u32 = 9;
 fn default_extended_threshold() -> f64 { 15.0 }
-fn default_session_categories() -> Vec<SessionCategory> {
-    vec![
-        SessionCategory { emoji: "\u{1F3AE}".to_string(), name: "Gaming".to_string() },
-        SessionCategory { emoji: "\u{1F4BB}".to_string(), name: "Work".to_string() },
-        SessionCategory { emoji: "\u{1F916}".to_string(), name: "AI".to_string() },
-        SessionCategory { emoji: "\u{1F310}".to_string(), name: "Browsing".to_string() },
-    ]
+fn default_max_power_watts() -> f64 { 2000.0 }
+fn default_spike_filter_factor() -> f64 { 4.0 }
+fn default_duplicate_reading_window_secs() -> u32 { 2 }
+fn default_estimation_correction() -> f64 { 1.0 }
+fn default_gpu_command_timeout_ms() -> u64 { 1500 }
+fn default_session_idle_input() -> SessionIdleInput { SessionIdleInput::Or }
+fn default_process_grouping() -> ProcessGrouping { ProcessGrouping::Name }
+
+/// Default session categories as (emoji, i18n key of the name)
+const DEFAULT_SESSION_CATEGORIES: [(&str, &str); 4] = [
+    ("\u{1F3AE}", "session.category.gaming"),
+    ("\u{1F4BB}", "session.category.work"),
+    ("\u{1F916}", "session.category.ai"),
+    ("\u{1F310}", "session.category.browsing"),
+];
+
+impl AdvancedConfig {
+    /// Seed the default categories with translated names, once
+    ///
+    /// Configs that already have categories (from older versions) are kept as is.
+    /// Returns true if the config changed and should be saved.
+    pub fn seed_session_categories(&mut self, translate: impl Fn(&str) -> String) -> bool {
+        if self.session_categories_seeded {
+            return false;
+        }
+        if self.session_categories.is_empty() {
+            self.session_categories = DEFAULT_SESSION_CATEGORIES
+                .iter()
+                .map(|(emoji, key)| SessionCategory { emoji: emoji.to_string(), name: translate(key), baseline_watts: None })
+                .collect();
+        }
+        self.session_categories_seeded = true;
+        true
+    }
+
+    /// Rename a session category, rejecting empty or duplicate names
+    pub fn rename_session_category(&mut self, old_name: &str, new_name: &str) -> Result<()> {
+        let new_name = new_name.trim();
+        if new_name.is_empty() {
+            return Err(Error::Config("Category name cannot be empty".to_string()));
+        }
+        if new_name != old_name && self.session_categories.iter().any(|c| c.name == new_name) {
+            return Err(Error::Config(format!("A category named '{}' already exists", new_name)));
+        }
+        let category = self
+            .session_categories
+            .iter_mut()
+            .find(|c| c.name == old_name)
+            .ok_or_else(|| Error::Config(format!("Unknown category '{}'", old_name)))?;
+        category.name = new_name.to_string();
+        Ok(())
+    }
+
+    /// Set or clear (None) the baseline override of a session category
+    pub fn set_session_category_baseline(&mut self, name: &str, baseline_watts: Option<f64>) -> Result<()> {
+        validate_category_baseline(baseline_watts)?;
+        let category = self
+            .session_categories
+            .iter_mut()
+            .find(|c| c.name == name)
+            .ok_or_else(|| Error::Config(format!("Unknown category '{}'", name)))?;
+        category.baseline_watts = baseline_watts;
+        Ok(())
+    }
+
+    /// Baseline override of the named category, if it has one
+    pub fn category_baseline(&self, category: Option<&str>) -> Option<f64> {
+        category
+            .and_then(|name| self.session_categories.iter().find(|c| c.name == name))
+            .and_then(|c| c.baseline_watts)
+    }
+
+    /// Baseline a new session starts with
+    ///
+    /// The category's override wins, then the detected baseline in auto mode,
+    /// then the schedule bucket's manual value and the global manual one.
+    pub fn session_baseline(&self, category: Option<&str>, detected: Option<f64>, bucket_manual: Option<f64>) -> f64 {
+        match self.category_baseline(category) {
+            Some(watts) => watts,
+            None if self.baseline_auto => detected.unwrap_or(0.0),
+            None => bucket_manual.unwrap_or(self.baseline_watts),
+        }
+    }
+}
+
+/// A category baseline override must be a finite, non-negative wattage
+pub fn validate_category_baseline(baseline_watts: Option<f64>) -> Result<()> {
+    if baseline_watts.is_some_and(|w| !w.is_finite() || w < 0.0) {
+        return Err(Error::Config("Category baseline must be zero or more watts".to_string()));
+    }
+    Ok(())
 }
 
 impl Default for AdvancedConfig {
@@ -450,22 +1070,122 @@ impl Default for AdvancedConfig {
         Self {
             baseline_watts: 0.0,
             baseline_auto: true,
+            baseline_schedule: BaselineSchedule::default(),
             active_profile: default_profile(),
             pinned_processes: Vec::new(),
+            ignored_processes: Vec::new(),
             process_list_limit: default_process_limit(),
             extended_metrics_threshold: default_extended_threshold(),
-            session_categories: default_session_categories(),
+            session_categories: Vec::new(),
+            session_categories_seeded: false,
+            max_power_watts: default_max_power_watts(),
+            spike_filter_factor: default_spike_filter_factor(),
+            duplicate_reading_window_secs: default_duplicate_reading_window_secs(),
+            exclude_self_from_surplus: false,
+            session_templates: Vec::new(),
+            auto_end_after_idle_minutes: 0,
+            session_idle_input: default_session_idle_input(),
+            process_grouping: default_process_grouping(),
+            session_end_hook: None,
+            track_active_window: false,
+            split_sessions_at_midnight: false,
+            vram_alert_percent: 0,
+            gpu_command_timeout_ms: default_gpu_command_timeout_ms(),
+            disable_powershell_probes: false,
+            disable_gpu_cli: false,
+            pause_gpu_polling_in_game: true,
+            gpu_idle_watts: None,
+            gpu_tdp_watts: None,
+            accuracy_tracking: false,
+            auto_apply_correction: false,
+            estimation_correction: default_estimation_correction(),
         }
     }
 }
 
+/// Time-of-day baselines, each bucket running from its start to the next one's
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BaselineSchedule {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_baseline_buckets")]
+    pub buckets: Vec<BaselineBucket>,
+}
+
+/// One time-of-day baseline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BaselineBucket {
+    pub name: String,
+    /// Local "HH:MM" time the bucket starts at
+    pub start: String,
+    /// Baseline of this bucket, overriding detection and the global manual baseline
+    #[serde(default)]
+    pub manual_watts: Option<f64>,
+}
+
+fn default_baseline_buckets() -> Vec<BaselineBucket> {
+    [("day", "07:00"), ("night", "23:00")]
+        .iter()
+        .map(|(name, start)| BaselineBucket { name: name.to_string(), start: start.to_string(), manual_watts: None })
+        .collect()
+}
+
+impl Default for BaselineSchedule {
+    fn default() -> Self {
+        Self { enabled: false, buckets: default_baseline_buckets() }
+    }
+}
+
+impl BaselineSchedule {
+    /// Check bucket names, start times and manual values (when enabled)
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.buckets.is_empty() {
+            return Err("A baseline schedule needs at least one bucket".to_string());
+        }
+        let mut names = std::collections::HashSet::new();
+        let mut starts = std::collections::HashSet::new();
+        for bucket in &self.buckets {
+            if bucket.name.trim().is_empty() || !names.insert(bucket.name.as_str()) {
+                return Err(format!("Baseline bucket names must be unique and not empty ('{}')", bucket.name));
+            }
+            let start = clock::parse_hhmm(&bucket.start)
+                .ok_or_else(|| format!("Invalid start time '{}' for baseline bucket '{}', expected HH:MM", bucket.start, bucket.name))?;
+            if !starts.insert(start) {
+                return Err(format!("Two baseline buckets start at {}", bucket.start));
+            }
+            if bucket.manual_watts.is_some_and(|w| !(w.is_finite() && w >= 0.0)) {
+                return Err(format!("Manual baseline of '{}' must be zero or more", bucket.name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A named preset for starting tracking sessions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub target_cost: Option<f64>,
+    /// End the session automatically after this many minutes
+    #[serde(default)]
+    pub auto_end_minutes: Option<u32>,
+}
+
 /// A named layout profile
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LayoutProfile {
     pub name: String,
     pub widgets: Vec<DashboardWidget>,
     #[serde(default = "default_global_display")]
-    pub global_display: String,
+    pub global_display: GlobalDisplay,
 }
 
 /// Dashboard layout configuration
@@ -474,12 +1194,9 @@ pub struct DashboardConfig {
     /// Layout type: "default" or "custom"
     #[serde(default = "default_layout")]
     pub layout: String,
-    /// Global display mode: "normal", "minimize", "hard"
-    /// - "normal": Full display with labels and details
-    /// - "minimize": Compact display with reduced labels
-    /// - "hard": Data-only display, no labels
+    /// Global display mode
     #[serde(default = "default_global_display")]
-    pub global_display: String,
+    pub global_display: GlobalDisplay,
     /// Widget configurations
     #[serde(default = "default_dashboard_widgets")]
     pub widgets: Vec<DashboardWidget>,
@@ -492,28 +1209,30 @@ pub struct DashboardConfig {
 }
 
 fn default_layout() -> String { "default".to_string() }
-fn default_global_display() -> String { "normal".to_string() }
+fn default_global_display() -> GlobalDisplay { GlobalDisplay::Normal }
 
 fn default_dashboard_widgets() -> Vec<DashboardWidget> {
     vec![
         // Row 1-3: CPU, GPU, RAM radials (4x3 each, fills 12 cols)
-        DashboardWidget { id: "cpu".to_string(), visible: true, size: "large".to_string(), position: 0, col: 1, row: 1, col_span: 4, row_span: 3, display_mode: "radial".to_string(), show_wh: true, ..Default::default() },
-        DashboardWidget { id: "gpu".to_string(), visible: true, size: "large".to_string(), position: 1, col: 5, row: 1, col_span: 4, row_span: 3, display_mode: "radial".to_string(), show_wh: true, ..Default::default() },
-        DashboardWidget { id: "ram".to_string(), visible: true, size: "large".to_string(), position: 2, col: 9, row: 1, col_span: 4, row_span: 3, display_mode: "radial".to_string(), show_wh: true, ..Default::default() },
+        DashboardWidget { id: "cpu".to_string(), visible: true, size: "large".to_string(), position: 0, col: 1, row: 1, col_span: 4, row_span: 3, display_mode: DisplayMode::Radial, show_wh: true, ..Default::default() },
+        DashboardWidget { id: "gpu".to_string(), visible: true, size: "large".to_string(), position: 1, col: 5, row: 1, col_span: 4, row_span: 3, display_mode: DisplayMode::Radial, show_wh: true, ..Default::default() },
+        DashboardWidget { id: "ram".to_string(), visible: true, size: "large".to_string(), position: 2, col: 9, row: 1, col_span: 4, row_span: 3, display_mode: DisplayMode::Radial, show_wh: true, ..Default::default() },
         // Row 4-6: Power, Processes, Surplus + Session Controls
-        DashboardWidget { id: "power".to_string(), visible: true, size: "large".to_string(), position: 3, col: 1, row: 4, col_span: 4, row_span: 3, display_mode: "text".to_string(), show_wh: true, ..Default::default() },
-        DashboardWidget { id: "processes".to_string(), visible: true, size: "large".to_string(), position: 4, col: 5, row: 4, col_span: 4, row_span: 3, display_mode: "text".to_string(), show_wh: true, ..Default::default() },
-        DashboardWidget { id: "surplus".to_string(), visible: true, size: "medium".to_string(), position: 5, col: 9, row: 4, col_span: 4, row_span: 2, display_mode: "text".to_string(), show_wh: true, ..Default::default() },
-        DashboardWidget { id: "session_controls".to_string(), visible: true, size: "small".to_string(), position: 6, col: 9, row: 6, col_span: 4, row_span: 1, display_mode: "text".to_string(), show_wh: true, ..Default::default() },
+        DashboardWidget { id: "power".to_string(), visible: true, size: "large".to_string(), position: 3, col: 1, row: 4, col_span: 4, row_span: 3, display_mode: DisplayMode::Text, show_wh: true, ..Default::default() },
+        DashboardWidget { id: "processes".to_string(), visible: true, size: "large".to_string(), position: 4, col: 5, row: 4, col_span: 4, row_span: 3, display_mode: DisplayMode::Text, show_wh: true, ..Default::default() },
+        DashboardWidget { id: "surplus".to_string(), visible: true, size: "medium".to_string(), position: 5, col: 9, row: 4, col_span: 4, row_span: 2, display_mode: DisplayMode::Text, show_wh: true, ..Default::default() },
+        DashboardWidget { id: "session_controls".to_string(), visible: true, size: "small".to_string(), position: 6, col: 9, row: 6, col_span: 4, row_span: 1, display_mode: DisplayMode::Text, show_wh: true, ..Default::default() },
         // Row 7: Session stats + Hourly estimate (3x1 each, fills 12 cols)
-        DashboardWidget { id: "session_energy".to_string(), visible: true, size: "small".to_string(), position: 7, col: 1, row: 7, col_span: 3, row_span: 1, display_mode: "text".to_string(), show_wh: true, ..Default::default() },
-        DashboardWidget { id: "session_cost".to_string(), visible: true, size: "small".to_string(), position: 8, col: 4, row: 7, col_span: 3, row_span: 1, display_mode: "text".to_string(), show_wh: true, ..Default::default() },
-        DashboardWidget { id: "session_duration".to_string(), visible: true, size: "small".to_string(), position: 9, col: 7, row: 7, col_span: 3, row_span: 1, display_mode: "text".to_string(), show_wh: true, ..Default::default() },
-        DashboardWidget { id: "hourly_estimate".to_string(), visible: true, size: "small".to_string(), position: 10, col: 10, row: 7, col_span: 3, row_span: 1, display_mode: "text".to_string(), show_wh: true, ..Default::default() },
+        DashboardWidget { id: "session_energy".to_string(), visible: true, size: "small".to_string(), position: 7, col: 1, row: 7, col_span: 3, row_span: 1, display_mode: DisplayMode::Text, show_wh: true, ..Default::default() },
+        DashboardWidget { id: "session_cost".to_string(), visible: true, size: "small".to_string(), position: 8, col: 4, row: 7, col_span: 3, row_span: 1, display_mode: DisplayMode::Text, show_wh: true, ..Default::default() },
+        DashboardWidget { id: "session_duration".to_string(), visible: true, size: "small".to_string(), position: 9, col: 7, row: 7, col_span: 3, row_span: 1, display_mode: DisplayMode::Text, show_wh: true, ..Default::default() },
+        DashboardWidget { id: "hourly_estimate".to_string(), visible: true, size: "small".to_string(), position: 10, col: 10, row: 7, col_span: 3, row_span: 1, display_mode: DisplayMode::Text, show_wh: true, ..Default::default() },
         // Row 8: Daily + Monthly estimates (6x1 each, fills 12 cols)
-        DashboardWidget { id: "daily_estimate".to_string(), visible: true, size: "medium".to_string(), position: 11, col: 1, row: 8, col_span: 6, row_span: 1, display_mode: "text".to_string(), show_wh: true, ..Default::default() },
-        DashboardWidget { id: "monthly_estimate".to_string(), visible: true, size: "medium".to_string(), position: 12, col: 7, row: 8, col_span: 6, row_span: 1, display_mode: "text".to_string(), show_wh: true, ..Default::default() },
-        DashboardWidget { id: "datetime".to_string(), visible: false, size: "small".to_string(), position: 13, col: 1, row: 9, col_span: 3, row_span: 1, display_mode: "text".to_string(), show_wh: true, ..Default::default() },
+        DashboardWidget { id: "daily_estimate".to_string(), visible: true, size: "medium".to_string(), position: 11, col: 1, row: 8, col_span: 6, row_span: 1, display_mode: DisplayMode::Text, show_wh: true, ..Default::default() },
+        DashboardWidget { id: "monthly_estimate".to_string(), visible: true, size: "medium".to_string(), position: 12, col: 7, row: 8, col_span: 6, row_span: 1, display_mode: DisplayMode::Text, show_wh: true, ..Default::default() },
+        DashboardWidget { id: "datetime".to_string(), visible: false, size: "small".to_string(), position: 13, col: 1, row: 9, col_span: 3, row_span: 1, display_mode: DisplayMode::Text, show_wh: true, ..Default::default() },
+        DashboardWidget { id: "insights".to_string(), visible: false, size: "medium".to_string(), position: 14, col: 4, row: 9, col_span: 6, row_span: 2, display_mode: DisplayMode::Text, show_wh: true, ..Default::default() },
+        DashboardWidget { id: "pinned".to_string(), visible: false, size: "medium".to_string(), position: 15, col: 10, row: 9, col_span: 3, row_span: 2, display_mode: DisplayMode::Text, show_wh: true, ..Default::default() },
     ]
 }
 
@@ -558,9 +1277,9 @@ pub struct DashboardWidget {
     /// Row span (1-2 for height)
     #[serde(default = "default_row_span")]
     pub row_span: u32,
-    /// Display mode: "text", "bar", "radial", "chart"
+    /// Display mode
     #[serde(default = "default_display_mode")]
-    pub display_mode: String,
+    pub display_mode: DisplayMode,
     /// Whether to show Wh line in estimation widgets (default true)
     #[serde(default = "default_true")]
     pub show_wh: bool,
@@ -615,4 +1334,214 @@ fn default_col() -> u32 { 1 }
 fn default_row() -> u32 { 1 }
 fn default_col_span() -> u32 { 2 }
 fn default_row_span() -> u32 { 1 }
-fn default_display_mode() -> String { "text".to_string() }
+fn default_display_mode() -> DisplayMode { DisplayMode::Text }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_baseline_schedule_validation() {
+        let mut schedule = BaselineSchedule { enabled: true, ..Default::default() };
+        assert!(schedule.validate().is_ok());
+        schedule.buckets[1].start = "07:00".into();
+        assert!(schedule.validate().is_err());
+        schedule.buckets[1].start = "25:00".into();
+        assert!(schedule.validate().is_err());
+        schedule.buckets[1].start = "22:00".into();
+        schedule.buckets[1].name = "day".into();
+        assert!(schedule.validate().is_err());
+        schedule.buckets[1].name = "night".into();
+        schedule.buckets[1].manual_watts = Some(-5.0);
+        assert!(schedule.validate().is_err());
+
+        // Missing from older configs: off, with the day/night split ready
+        let config: Config = toml::from_str("[advanced]\nbaseline_auto = true").unwrap();
+        assert!(!config.advanced.baseline_schedule.enabled);
+        assert_eq!(config.advanced.baseline_schedule.buckets.len(), 2);
+    }
+
+    #[test]
+    fn test_session_baseline_precedence() {
+        let mut advanced = AdvancedConfig { baseline_watts: 40.0, ..Default::default() };
+        advanced.seed_session_categories(|key| key.to_string());
+        advanced.set_session_category_baseline("session.category.ai", Some(25.0)).unwrap();
+        assert!(advanced.set_session_category_baseline("session.category.ai", Some(-1.0)).is_err());
+        assert!(advanced.set_session_category_baseline("missing", Some(10.0)).is_err());
+
+        // Category override over the detected and manual baselines
+        let ai = Some("session.category.ai");
+        assert_eq!(advanced.session_baseline(ai, Some(60.0), Some(55.0)), 25.0);
+        // Categories without one, or no category, use the global baseline
+        let gaming = Some("session.category.gaming");
+        assert_eq!(advanced.session_baseline(gaming, Some(60.0), Some(55.0)), 60.0);
+        assert_eq!(advanced.session_baseline(None, None, Some(55.0)), 0.0);
+
+        advanced.baseline_auto = false;
+        assert_eq!(advanced.session_baseline(ai, Some(60.0), Some(55.0)), 25.0);
+        assert_eq!(advanced.session_baseline(gaming, Some(60.0), Some(55.0)), 55.0);
+        assert_eq!(advanced.session_baseline(Some("unknown"), None, None), 40.0);
+
+        advanced.set_session_category_baseline("session.category.ai", None).unwrap();
+        assert_eq!(advanced.session_baseline(ai, None, None), 40.0);
+    }
+
+    #[test]
+    fn test_region_defaults() {
+        let mut config = Config::default();
+        config.apply_region_defaults(&SystemLocale::parse("en_US.UTF-8").unwrap());
+        assert_eq!((config.pricing.currency.as_str(), config.pricing.currency_symbol.as_str()), ("USD", "$"));
+        assert_eq!(config.pricing.simple.rate_per_kwh, 0.16);
+        assert_eq!(config.pricing.auto_region.as_deref(), Some("US"));
+
+        // No preset for the region, the defaults stay and nothing needs confirming
+        let mut config = Config::default();
+        config.apply_region_defaults(&SystemLocale::parse("en_IN").unwrap());
+        assert_eq!(config.pricing.currency, "EUR");
+        assert_eq!(config.pricing.auto_region, None);
+    }
+
+    #[test]
+    fn test_known_values_round_trip() {
+        let toml_str = r#"
+            [pricing]
+            mode = "peak_offpeak"
+
+            [widget]
+            position = "top_left"
+
+            [dashboard]
+            global_display = "minimize"
+            widgets = [{ id = "cpu", visible = true, display_mode = "radial" }]
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.pricing.mode, PricingMode::PeakOffpeak);
+        assert_eq!(config.widget.position, WidgetPosition::TopLeft);
+        assert_eq!(config.dashboard.global_display, GlobalDisplay::Minimize);
+        assert_eq!(config.dashboard.widgets[0].display_mode, DisplayMode::Radial);
+
+        let written = toml::to_string(&config).unwrap();
+        assert!(written.contains(r#"mode = "peak_offpeak""#));
+        assert!(written.contains(r#"position = "top_left""#));
+        assert_eq!("tempo".parse::<PricingMode>().unwrap(), PricingMode::Tempo);
+        assert_eq!(PricingMode::PeakOffpeak.to_string(), "peak_offpeak");
+    }
+
+    #[test]
+    fn test_legacy_config_with_unknown_values() {
+        let toml_str = r#"
+            [pricing]
+            mode = "peak_offpeack"
+
+            [widget]
+            position = "middle"
+
+            [dashboard]
+            global_display = "compact"
+            widgets = [{ id = "gpu", visible = true, display_mode = "sparkline" }]
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.pricing.mode, PricingMode::Other("peak_offpeack".to_string()));
+        assert!(!config.pricing.mode.is_known());
+        assert_eq!(config.widget.position, WidgetPosition::Other("middle".to_string()));
+        assert_eq!(config.dashboard.global_display.as_str(), "compact");
+        assert_eq!(config.dashboard.widgets[0].display_mode, DisplayMode::Other("sparkline".to_string()));
+
+        // Written back as found, not replaced by a default
+        let written = toml::to_string(&config).unwrap();
+        assert!(written.contains(r#"mode = "peak_offpeack""#));
+        assert!(written.contains(r#"display_mode = "sparkline""#));
+    }
+
+    #[test]
+    fn test_serialized_names_match_display() {
+        for mode in [PricingMode::Simple, PricingMode::PeakOffpeak, PricingMode::Seasonal, PricingMode::Tempo] {
+            assert_eq!(serde_json::to_string(&mode).unwrap(), format!("\"{}\"", mode));
+        }
+        for position in [WidgetPosition::TopLeft, WidgetPosition::TopRight, WidgetPosition::BottomLeft, WidgetPosition::BottomRight, WidgetPosition::Custom] {
+            assert_eq!(serde_json::to_string(&position).unwrap(), format!("\"{}\"", position));
+        }
+    }
+
+    #[test]
+    fn test_changed_sections() {
+        let current = Config::default();
+        let mut staged = current.clone();
+        assert!(staged.changed_sections(&current).is_empty());
+        staged.pricing.simple.rate_per_kwh += 0.01;
+        staged.general.theme = "light".into();
+        assert_eq!(staged.changed_sections(&current), vec!["general".to_string(), "pricing".to_string()]);
+    }
+
+    #[test]
+    fn test_load_recovers_from_backup() {
+        let dir = std::env::temp_dir().join(format!("powercost-config-recover-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+
+        // The second save keeps the first as the backup, nothing is left aside
+        let mut config = Config::default();
+        config.pricing.simple.rate_per_kwh = 0.31;
+        config.save_to(&path).unwrap();
+        config.pricing.simple.rate_per_kwh = 0.42;
+        config.save_to(&path).unwrap();
+        assert!(!dir.join("config.toml.tmp").exists());
+        assert_eq!(Config::load_from(&dir.join("config.toml.bak")).unwrap().pricing.simple.rate_per_kwh, 0.31);
+
+        // Cut mid-write
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(&path, &content[..content.len() / 2]).unwrap();
+        assert!(Config::load_from(&path).is_err());
+
+        let (recovered, recovery) = Config::load_or_recover(&path).unwrap();
+        let recovery = recovery.unwrap();
+        assert!(recovery.from_backup);
+        assert_eq!(recovered.pricing.simple.rate_per_kwh, 0.31);
+        assert_eq!(fs::read_to_string(&recovery.corrupt_path).unwrap(), content[..content.len() / 2]);
+        // Written back in place, and now loads normally
+        let (reloaded, none) = Config::load_or_recover(&path).unwrap();
+        assert!(none.is_none());
+        assert_eq!(reloaded.pricing.simple.rate_per_kwh, 0.31);
+
+        // Without a usable backup it comes back with defaults
+        fs::write(dir.join("config.toml.bak"), "not [toml").unwrap();
+        fs::write(&path, [0xff, 0xfe, 0x00]).unwrap();
+        let (defaults, recovery) = Config::load_or_recover(&path).unwrap();
+        assert!(!recovery.unwrap().from_backup);
+        assert_eq!(defaults.pricing.simple.rate_per_kwh, Config::default().pricing.simple.rate_per_kwh);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_replace_saved_keeps_config_when_save_fails() {
+        let dir = std::env::temp_dir().join(format!("powercost-config-ro-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut permissions = fs::metadata(&dir).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&dir, permissions.clone()).unwrap();
+        // Root ignores the permission bits, a file where the directory should be fails for everyone
+        let path = if fs::write(dir.join("blocker"), "").is_ok() {
+            dir.join("blocker").join("config.toml")
+        } else {
+            dir.join("config.toml")
+        };
+
+        let mut current = Config::default();
+        let mut staged = current.clone();
+        staged.general.theme = "light".into();
+        assert!(current.replace_saved(staged.clone(), &path).is_err());
+        assert_eq!(current.general.theme, Config::default().general.theme);
+
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(&dir, permissions).unwrap();
+        let _ = fs::remove_file(dir.join("blocker"));
+        let path = dir.join("config.toml");
+        assert_eq!(current.replace_saved(staged, &path).unwrap(), vec!["general".to_string()]);
+        assert_eq!(current.general.theme, "light");
+        assert_eq!(Config::load_from(&path).unwrap().general.theme, "light");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,272 @@
+//! Config fields the monitoring loops read on every tick, shared through watch channels
+//!
+//! The `Config` behind its mutex stays the authoritative copy. Each change to
+//! it is published here, and the loops subscribe to the snapshots they need
+//! instead of taking the config lock every iteration. A receiver only sees a
+//! change when one of its fields actually changed.
+
+use crate::core::{Config, EnergyUnit, PricingMode, SessionIdleInput, WidgetConfig};
+use crate::hardware::DetailedOptions;
+use tokio::sync::watch;
+
+/// Configured loop rates, in milliseconds
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefreshSettings {
+    pub refresh_rate_ms: u64,
+    pub slow_refresh_rate_ms: u64,
+    /// None follows the critical loop
+    pub widget_refresh_rate_ms: Option<u64>,
+}
+
+/// Pricing settings the loops store their figures with
+#[derive(Debug, Clone, PartialEq)]
+pub struct PricingSnapshot {
+    pub mode: PricingMode,
+}
+
+/// How the critical loop formats its figures for the dashboard and the widget
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplaySettings {
+    pub energy_unit: EnergyUnit,
+    pub cost_decimals: usize,
+    pub currency_symbol: String,
+    pub show_taskbar_badge: bool,
+    /// Changes the number format and unit names
+    pub language: String,
+}
+
+/// What the detailed loop collects besides the process list
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetailedSettings {
+    /// Collectors needed by the dashboard alone, and with the widget window open
+    dashboard_only: DetailedOptions,
+    with_widget: DetailedOptions,
+    pub extended_metrics_threshold: f64,
+    pub pause_gpu_polling_in_game: bool,
+    pub track_active_window: bool,
+    pub vram_alert_percent: u32,
+}
+
+/// What the detailed loop collects of the process list
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessSettings {
+    pub limit: usize,
+    pub pinned: Vec<String>,
+    pub ignored: Vec<String>,
+}
+
+/// How the critical loop filters readings and tracks sessions
+#[derive(Debug, Clone, PartialEq)]
+pub struct SamplingSettings {
+    pub max_power_watts: f64,
+    pub spike_filter_factor: f64,
+    pub split_sessions_at_midnight: bool,
+    pub exclude_self_from_surplus: bool,
+    pub session_idle_input: SessionIdleInput,
+}
+
+impl RefreshSettings {
+    fn from_config(config: &Config) -> Self {
+        Self {
+            refresh_rate_ms: config.general.refresh_rate_ms,
+            slow_refresh_rate_ms: config.general.slow_refresh_rate_ms,
+            widget_refresh_rate_ms: config.widget.refresh_rate_ms,
+        }
+    }
+}
+
+impl PricingSnapshot {
+    fn from_config(config: &Config) -> Self {
+        Self { mode: config.pricing.mode.clone() }
+    }
+}
+
+impl DisplaySettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            energy_unit: config.general.energy_unit.clone(),
+            cost_decimals: config.general.cost_decimals,
+            currency_symbol: config.pricing.currency_symbol.clone(),
+            show_taskbar_badge: config.general.show_taskbar_badge,
+            language: config.general.language.clone(),
+        }
+    }
+}
+
+impl DetailedSettings {
+    fn from_config(config: &Config) -> Self {
+        Self {
+            dashboard_only: DetailedOptions::for_layout(config, false, false),
+            with_widget: DetailedOptions::for_layout(config, true, false),
+            extended_metrics_threshold: config.advanced.extended_metrics_threshold,
+            pause_gpu_polling_in_game: config.advanced.pause_gpu_polling_in_game,
+            track_active_window: config.advanced.track_active_window,
+            vram_alert_percent: config.advanced.vram_alert_percent,
+        }
+    }
+
+    /// Collectors for the current layout, see `DetailedOptions::for_layout`
+    pub fn options(&self, widget_open: bool, extended: bool) -> DetailedOptions {
+        let options = if widget_open { self.with_widget } else { self.dashboard_only };
+        DetailedOptions { extended, ..options }
+    }
+}
+
+impl ProcessSettings {
+    fn from_config(config: &Config) -> Self {
+        Self {
+            limit: config.advanced.process_list_limit,
+            pinned: config.advanced.pinned_processes.clone(),
+            ignored: config.advanced.ignored_processes.clone(),
+        }
+    }
+}
+
+impl SamplingSettings {
+    fn from_config(config: &Config) -> Self {
+        Self {
+            max_power_watts: config.advanced.max_power_watts,
+            spike_filter_factor: config.advanced.spike_filter_factor,
+            split_sessions_at_midnight: config.advanced.split_sessions_at_midnight,
+            exclude_self_from_surplus: config.advanced.exclude_self_from_surplus,
+            session_idle_input: config.advanced.session_idle_input.clone(),
+        }
+    }
+}
+
+/// Senders of the hot config snapshots
+pub struct ConfigWatch {
+    refresh: watch::Sender<RefreshSettings>,
+    pricing: watch::Sender<PricingSnapshot>,
+    processes: watch::Sender<ProcessSettings>,
+    sampling: watch::Sender<SamplingSettings>,
+    display: watch::Sender<DisplaySettings>,
+    widget: watch::Sender<WidgetConfig>,
+    detailed: watch::Sender<DetailedSettings>,
+}
+
+impl ConfigWatch {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            refresh: watch::channel(RefreshSettings::from_config(config)).0,
+            pricing: watch::channel(PricingSnapshot::from_config(config)).0,
+            processes: watch::channel(ProcessSettings::from_config(config)).0,
+            sampling: watch::channel(SamplingSettings::from_config(config)).0,
+            display: watch::channel(DisplaySettings::from_config(config)).0,
+            widget: watch::channel(config.widget.clone()).0,
+            detailed: watch::channel(DetailedSettings::from_config(config)).0,
+        }
+    }
+
+    /// Publish the config after a change, receivers are only notified of the snapshots that differ
+    pub fn publish(&self, config: &Config) {
+        replace_if_changed(&self.refresh, RefreshSettings::from_config(config));
+        replace_if_changed(&self.pricing, PricingSnapshot::from_config(config));
+        replace_if_changed(&self.processes, ProcessSettings::from_config(config));
+        replace_if_changed(&self.sampling, SamplingSettings::from_config(config));
+        replace_if_changed(&self.display, DisplaySettings::from_config(config));
+        replace_if_changed(&self.widget, config.widget.clone());
+        replace_if_changed(&self.detailed, DetailedSettings::from_config(config));
+    }
+
+    pub fn refresh(&self) -> watch::Receiver<RefreshSettings> {
+        self.refresh.subscribe()
+    }
+
+    pub fn pricing(&self) -> watch::Receiver<PricingSnapshot> {
+        self.pricing.subscribe()
+    }
+
+    pub fn processes(&self) -> watch::Receiver<ProcessSettings> {
+        self.processes.subscribe()
+    }
+
+    pub fn sampling(&self) -> watch::Receiver<SamplingSettings> {
+        self.sampling.subscribe()
+    }
+
+    pub fn display(&self) -> watch::Receiver<DisplaySettings> {
+        self.display.subscribe()
+    }
+
+    pub fn widget(&self) -> watch::Receiver<WidgetConfig> {
+        self.widget.subscribe()
+    }
+
+    pub fn detailed(&self) -> watch::Receiver<DetailedSettings> {
+        self.detailed.subscribe()
+    }
+}
+
+fn replace_if_changed<T: PartialEq>(sender: &watch::Sender<T>, value: T) {
+    sender.send_if_modified(|current| {
+        if *current == value {
+            return false;
+        }
+        *current = value;
+        true
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::{interval, Duration};
+
+    #[test]
+    fn test_only_changed_snapshots_notify() {
+        let mut config = Config::default();
+        let watch = ConfigWatch::new(&config);
+        let refresh = watch.refresh();
+        let mut processes = watch.processes();
+
+        config.advanced.pinned_processes.push("blender".to_string());
+        watch.publish(&config);
+        assert!(!refresh.has_changed().unwrap());
+        assert!(processes.has_changed().unwrap());
+        assert_eq!(processes.borrow_and_update().pinned, vec!["blender".to_string()]);
+
+        // Same values again: nothing to see
+        watch.publish(&config);
+        assert!(!processes.has_changed().unwrap());
+
+        // Layout changes reach the detailed loop's collectors
+        let mut detailed = watch.detailed();
+        let display = watch.display();
+        config.dashboard.widgets.iter_mut().for_each(|w| w.visible = false);
+        config.advanced.vram_alert_percent = 0;
+        watch.publish(&config);
+        assert!(!display.has_changed().unwrap());
+        let options = detailed.borrow_and_update().options(false, true);
+        assert!(options.extended && !options.processes && !options.gpu);
+    }
+
+    #[tokio::test]
+    async fn test_running_loop_sees_rate_change_next_tick() {
+        let mut config = Config::default();
+        config.general.refresh_rate_ms = 1000;
+        let watch = ConfigWatch::new(&config);
+        let mut refresh = watch.refresh();
+
+        // Reads the snapshot every tick, like the monitoring loops
+        let (seen_tx, mut seen) = tokio::sync::mpsc::unbounded_channel();
+        let running = tokio::spawn(async move {
+            let mut ticks = interval(Duration::from_millis(10));
+            loop {
+                ticks.tick().await;
+                let rate = refresh.borrow_and_update().refresh_rate_ms;
+                if seen_tx.send(rate).is_err() {
+                    break;
+                }
+            }
+        });
+
+        assert_eq!(seen.recv().await, Some(1000));
+        config.general.refresh_rate_ms = 250;
+        watch.publish(&config);
+        assert_eq!(seen.recv().await, Some(250));
+
+        drop(seen);
+        running.await.unwrap();
+    }
+}
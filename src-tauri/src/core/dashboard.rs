@@ -0,0 +1,322 @@
+//! Dashboard widget catalog and layout validation
+//!
+//! The dashboard is a 12-column grid. Each known widget has a span range and
+//! a set of display modes; a saved layout must keep every widget inside the
+//! grid and visible widgets must not share cells. Single widgets can be
+//! changed with a `WidgetPatch` instead of saving the whole layout.
+
+use crate::core::{DashboardConfig, DashboardWidget, DisplayMode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Number of columns of the dashboard grid
+pub const GRID_COLUMNS: u32 = 12;
+
+/// Tallest span a widget can be resized to
+const MAX_ROW_SPAN: u32 = 5;
+
+/// Modes of widgets that only render as text
+const TEXT_ONLY: &[DisplayMode] = &[DisplayMode::Text];
+
+/// Modes of the CPU, GPU and RAM gauges
+const ALL_MODES: &[DisplayMode] = &[DisplayMode::Text, DisplayMode::Bar, DisplayMode::Radial, DisplayMode::Chart];
+
+/// A widget the dashboard knows how to render
+#[derive(Debug, Clone, Serialize)]
+pub struct WidgetSpec {
+    pub id: &'static str,
+    pub display_modes: &'static [DisplayMode],
+    pub min_col_span: u32,
+    pub max_col_span: u32,
+    pub min_row_span: u32,
+    pub max_row_span: u32,
+}
+
+const fn spec(id: &'static str, display_modes: &'static [DisplayMode], min_col_span: u32, min_row_span: u32) -> WidgetSpec {
+    WidgetSpec {
+        id,
+        display_modes,
+        min_col_span,
+        max_col_span: GRID_COLUMNS,
+        min_row_span,
+        max_row_span: MAX_ROW_SPAN,
+    }
+}
+
+/// Every widget id accepted in a dashboard layout (minimums match the frontend registry)
+pub const WIDGET_CATALOG: &[WidgetSpec] = &[
+    spec("power", TEXT_ONLY, 3, 2),
+    spec("session_energy", TEXT_ONLY, 2, 1),
+    spec("session_cost", TEXT_ONLY, 2, 1),
+    spec("hourly_estimate", TEXT_ONLY, 2, 1),
+    spec("daily_estimate", TEXT_ONLY, 2, 1),
+    spec("monthly_estimate", TEXT_ONLY, 2, 1),
+    spec("session_duration", TEXT_ONLY, 2, 1),
+    spec("cpu", ALL_MODES, 2, 2),
+    spec("gpu", ALL_MODES, 2, 2),
+    spec("ram", ALL_MODES, 2, 2),
+    spec("surplus", TEXT_ONLY, 2, 1),
+    spec("session_controls", TEXT_ONLY, 2, 1),
+    spec("processes", TEXT_ONLY, 3, 2),
+    spec("datetime", TEXT_ONLY, 2, 1),
+    spec("insights", TEXT_ONLY, 3, 2),
+    spec("pinned", TEXT_ONLY, 3, 2),
+];
+
+/// Look up a widget of the catalog
+pub fn widget_spec(id: &str) -> Option<&'static WidgetSpec> {
+    WIDGET_CATALOG.iter().find(|w| w.id == id)
+}
+
+/// Kind of layout problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    UnknownWidget,
+    DuplicateId,
+    UnsupportedDisplayMode,
+    SpanOutOfRange,
+    OutOfGrid,
+    Overlap,
+}
+
+/// One problem found in a dashboard layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayoutViolation {
+    pub kind: ViolationKind,
+    pub widget_id: String,
+    /// Other widget involved (overlaps only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub other_widget_id: Option<String>,
+    pub message: String,
+}
+
+impl LayoutViolation {
+    fn new(kind: ViolationKind, widget: &DashboardWidget, message: String) -> Self {
+        Self {
+            kind,
+            widget_id: widget.id.clone(),
+            other_widget_id: None,
+            message,
+        }
+    }
+}
+
+fn overlaps(a: &DashboardWidget, b: &DashboardWidget) -> bool {
+    a.col < b.col + b.col_span
+        && b.col < a.col + a.col_span
+        && a.row < b.row + b.row_span
+        && b.row < a.row + a.row_span
+}
+
+/// Check a dashboard layout, returns every violation found (empty = valid)
+///
+/// Hidden widgets keep their last position, so only visible widgets are
+/// checked for overlaps.
+pub fn validate_dashboard(config: &DashboardConfig) -> Vec<LayoutViolation> {
+    let mut violations = Vec::new();
+    let mut seen = HashSet::new();
+
+    for widget in &config.widgets {
+        if !seen.insert(widget.id.as_str()) {
+            violations.push(LayoutViolation::new(
+                ViolationKind::DuplicateId,
+                widget,
+                format!("Widget '{}' appears more than once", widget.id),
+            ));
+        }
+
+        let Some(spec) = widget_spec(&widget.id) else {
+            violations.push(LayoutViolation::new(
+                ViolationKind::UnknownWidget,
+                widget,
+                format!("Unknown widget '{}'", widget.id),
+            ));
+            continue;
+        };
+
+        if !spec.display_modes.contains(&widget.display_mode) {
+            violations.push(LayoutViolation::new(
+                ViolationKind::UnsupportedDisplayMode,
+                widget,
+                format!("Widget '{}' does not support display mode '{}'", widget.id, widget.display_mode),
+            ));
+        }
+
+        if !(spec.min_col_span..=spec.max_col_span).contains(&widget.col_span)
+            || !(spec.min_row_span..=spec.max_row_span).contains(&widget.row_span)
+        {
+            violations.push(LayoutViolation::new(
+                ViolationKind::SpanOutOfRange,
+                widget,
+                format!(
+                    "Widget '{}' spans {}x{}, allowed {}-{} columns and {}-{} rows",
+                    widget.id, widget.col_span, widget.row_span,
+                    spec.min_col_span, spec.max_col_span, spec.min_row_span, spec.max_row_span
+                ),
+            ));
+        }
+
+        if widget.col < 1 || widget.row < 1 || widget.col + widget.col_span.max(1) - 1 > GRID_COLUMNS {
+            violations.push(LayoutViolation::new(
+                ViolationKind::OutOfGrid,
+                widget,
+                format!(
+                    "Widget '{}' at column {} row {} does not fit the {}-column grid",
+                    widget.id, widget.col, widget.row, GRID_COLUMNS
+                ),
+            ));
+        }
+    }
+
+    let visible: Vec<&DashboardWidget> = config.widgets.iter().filter(|w| w.visible).collect();
+    for (i, a) in visible.iter().enumerate() {
+        for b in &visible[i + 1..] {
+            if a.id != b.id && overlaps(a, b) {
+                violations.push(LayoutViolation {
+                    other_widget_id: Some(b.id.clone()),
+                    ..LayoutViolation::new(
+                        ViolationKind::Overlap,
+                        a,
+                        format!("Widgets '{}' and '{}' overlap", a.id, b.id),
+                    )
+                });
+            }
+        }
+    }
+
+    violations
+}
+
+/// Widget fields changed by a patch, None keeps the current value
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WidgetPatch {
+    pub visible: Option<bool>,
+    pub display_mode: Option<DisplayMode>,
+    pub show_wh: Option<bool>,
+    pub col_span: Option<u32>,
+    pub row_span: Option<u32>,
+    pub col: Option<u32>,
+    pub row: Option<u32>,
+    pub position: Option<u32>,
+}
+
+impl WidgetPatch {
+    fn apply(&self, widget: &mut DashboardWidget) {
+        fn set<T: Clone>(field: &mut T, value: &Option<T>) {
+            if let Some(value) = value {
+                *field = value.clone();
+            }
+        }
+        set(&mut widget.visible, &self.visible);
+        set(&mut widget.display_mode, &self.display_mode);
+        set(&mut widget.show_wh, &self.show_wh);
+        set(&mut widget.col_span, &self.col_span);
+        set(&mut widget.row_span, &self.row_span);
+        set(&mut widget.col, &self.col);
+        set(&mut widget.row, &self.row);
+        set(&mut widget.position, &self.position);
+    }
+}
+
+/// Apply `patch` to widget `id`, and to the active profile's copy of it
+///
+/// Nothing changes when the patched layout has a violation involving the
+/// widget; an id that is not in the layout gives an `UnknownWidget` one.
+pub fn patch_widget(config: &mut DashboardConfig, id: &str, patch: &WidgetPatch) -> Result<DashboardWidget, Vec<LayoutViolation>> {
+    let Some(index) = config.widgets.iter().position(|w| w.id == id) else {
+        let unknown = DashboardWidget { id: id.to_string(), ..Default::default() };
+        return Err(vec![LayoutViolation::new(ViolationKind::UnknownWidget, &unknown, format!("Unknown widget '{}'", id))]);
+    };
+
+    let mut staged = DashboardConfig { widgets: config.widgets.clone(), ..Default::default() };
+    patch.apply(&mut staged.widgets[index]);
+    let violations: Vec<LayoutViolation> = validate_dashboard(&staged)
+        .into_iter()
+        .filter(|v| v.widget_id == id || v.other_widget_id.as_deref() == Some(id))
+        .collect();
+    if !violations.is_empty() {
+        return Err(violations);
+    }
+
+    config.widgets = staged.widgets;
+    let active = config.active_profile.as_str();
+    let profile = config.profiles.iter_mut().find(|p| !active.is_empty() && p.name == active);
+    if let Some(widget) = profile.and_then(|p| p.widgets.iter_mut().find(|w| w.id == id)) {
+        patch.apply(widget);
+    }
+    Ok(config.widgets[index].clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn widget(id: &str, col: u32, row: u32, col_span: u32, row_span: u32) -> DashboardWidget {
+        DashboardWidget { id: id.to_string(), visible: true, col, row, col_span, row_span, ..Default::default() }
+    }
+
+    fn kinds(widgets: Vec<DashboardWidget>) -> Vec<ViolationKind> {
+        let config = DashboardConfig { widgets, ..Default::default() };
+        validate_dashboard(&config).into_iter().map(|v| v.kind).collect()
+    }
+
+    #[test]
+    fn test_default_layout_is_valid() {
+        assert!(validate_dashboard(&DashboardConfig::default()).is_empty());
+        // Every default widget is in the catalog
+        for w in DashboardConfig::default().widgets {
+            assert!(widget_spec(&w.id).is_some(), "{}", w.id);
+        }
+    }
+
+    #[test]
+    fn test_layout_violations() {
+        assert_eq!(kinds(vec![widget("weather", 1, 1, 2, 1)]), vec![ViolationKind::UnknownWidget]);
+        assert_eq!(
+            kinds(vec![widget("cpu", 1, 1, 4, 3), widget("cpu", 5, 1, 4, 3)]),
+            vec![ViolationKind::DuplicateId]
+        );
+        assert_eq!(kinds(vec![widget("power", 11, 1, 4, 2)]), vec![ViolationKind::OutOfGrid]);
+        assert_eq!(kinds(vec![widget("processes", 1, 1, 2, 2)]), vec![ViolationKind::SpanOutOfRange]);
+
+        let mut charted = widget("power", 1, 1, 4, 2);
+        charted.display_mode = DisplayMode::Chart;
+        assert_eq!(kinds(vec![charted]), vec![ViolationKind::UnsupportedDisplayMode]);
+
+        // The layout that once exploded: full-width widgets stacked on the same cells
+        assert_eq!(
+            kinds(vec![widget("cpu", 1, 1, 12, 3), widget("gpu", 1, 2, 12, 3)]),
+            vec![ViolationKind::Overlap]
+        );
+
+        // Hidden widgets may keep a stale position
+        let mut hidden = widget("gpu", 1, 2, 12, 3);
+        hidden.visible = false;
+        assert!(kinds(vec![widget("cpu", 1, 1, 12, 3), hidden]).is_empty());
+    }
+
+    #[test]
+    fn test_patch_widget() {
+        let mut config = DashboardConfig { active_profile: "Default".to_string(), ..Default::default() };
+        let patch = WidgetPatch { display_mode: Some(DisplayMode::Bar), show_wh: Some(false), ..Default::default() };
+        let updated = patch_widget(&mut config, "cpu", &patch).unwrap();
+        assert_eq!(updated.display_mode, DisplayMode::Bar);
+        assert!(!updated.show_wh);
+        // The active profile follows, the other fields are kept
+        let profile_cpu = config.profiles[0].widgets.iter().find(|w| w.id == "cpu").unwrap();
+        assert_eq!(profile_cpu.display_mode, DisplayMode::Bar);
+        assert_eq!(updated.col_span, 4);
+
+        let kinds = |result: Result<DashboardWidget, Vec<LayoutViolation>>| -> Vec<ViolationKind> {
+            result.unwrap_err().into_iter().map(|v| v.kind).collect()
+        };
+        assert_eq!(kinds(patch_widget(&mut config, "weather", &WidgetPatch::default())), vec![ViolationKind::UnknownWidget]);
+        let chart = WidgetPatch { display_mode: Some(DisplayMode::Chart), ..Default::default() };
+        assert_eq!(kinds(patch_widget(&mut config, "power", &chart)), vec![ViolationKind::UnsupportedDisplayMode]);
+        let wide = WidgetPatch { col_span: Some(8), ..Default::default() };
+        assert_eq!(kinds(patch_widget(&mut config, "cpu", &wide)), vec![ViolationKind::Overlap]);
+        assert_eq!(config.widgets.iter().find(|w| w.id == "power").unwrap().display_mode, DisplayMode::Text);
+    }
+}
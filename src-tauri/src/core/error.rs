@@ -1,5 +1,6 @@
 //! Error types for the application
 
+use serde::Serialize;
 use thiserror::Error;
 
 /// Application-wide error type
@@ -11,6 +12,12 @@ pub enum Error {
     #[error("Database error: {0}")]
     Database(#[from] rusqlite::Error),
 
+    #[error("Database is in use by another process: {0}")]
+    DatabaseBusy(String),
+
+    #[error("A session is already active (session {0})")]
+    SessionActive(i64),
+
     #[error("Power monitoring error: {0}")]
     PowerMonitor(String),
 
@@ -25,7 +32,171 @@ pub enum Error {
 
     #[error("Permission denied: {0}")]
     PermissionDenied(String),
+
+    #[error("Network error: {0}")]
+    Network(String),
 }
 
 /// Result type alias using our Error
 pub type Result<T> = std::result::Result<T, Error>;
+
+/// Stable error codes sent to the frontend, localized through `error.code.*` keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    HardwareNotSupported,
+    PermissionDenied,
+    DbError,
+    ConfigInvalid,
+    SessionActive,
+    NotFound,
+    InvalidInput,
+    Internal,
+}
+
+/// Error returned by Tauri commands, serialized as `{ code, message, details }`
+/// plus `subject` and `elevation_hint` when set
+#[derive(Error, Debug, Clone, Serialize)]
+#[error("{message}")]
+pub struct CommandError {
+    pub code: ErrorCode,
+    /// English description, for logs and as a fallback when the code is unknown
+    pub message: String,
+    /// Underlying cause, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    /// What the error is about (a process name), for the frontend's localized message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    /// Running as administrator would avoid the error
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub elevation_hint: bool,
+}
+
+impl CommandError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: None,
+            subject: None,
+            elevation_hint: false,
+        }
+    }
+
+    pub fn with_details(mut self, details: impl Into<String>) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+
+    pub fn with_subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+
+    pub fn with_elevation_hint(mut self, elevation_hint: bool) -> Self {
+        self.elevation_hint = elevation_hint;
+        self
+    }
+
+    pub fn permission_denied(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::PermissionDenied, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn session_active(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::SessionActive, message)
+    }
+}
+
+impl From<Error> for CommandError {
+    fn from(e: Error) -> Self {
+        let code = match &e {
+            Error::Config(_) => ErrorCode::ConfigInvalid,
+            Error::Database(_) | Error::DatabaseBusy(_) => ErrorCode::DbError,
+            Error::SessionActive(_) => ErrorCode::SessionActive,
+            Error::HardwareNotSupported(_) => ErrorCode::HardwareNotSupported,
+            Error::PermissionDenied(_) => ErrorCode::PermissionDenied,
+            Error::Io(io) if io.kind() == std::io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+            Error::PowerMonitor(_) | Error::Io(_) | Error::Serialization(_) | Error::Network(_) => ErrorCode::Internal,
+        };
+        let details = match &e {
+            Error::Database(db) => db.to_string(),
+            Error::Io(io) => io.to_string(),
+            Error::SessionActive(id) => id.to_string(),
+            Error::Config(m)
+            | Error::DatabaseBusy(m)
+            | Error::PowerMonitor(m)
+            | Error::Serialization(m)
+            | Error::HardwareNotSupported(m)
+            | Error::PermissionDenied(m)
+            | Error::Network(m) => m.clone(),
+        };
+        Self::new(code, e.to_string()).with_details(details)
+    }
+}
+
+/// Plain messages from not yet migrated code paths
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+}
+
+impl From<CommandError> for String {
+    fn from(e: CommandError) -> Self {
+        e.message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_error_codes() {
+        let e = CommandError::from(Error::PermissionDenied("/sys/class/powercap".to_string()));
+        assert_eq!(e.code, ErrorCode::PermissionDenied);
+        assert_eq!(e.details.as_deref(), Some("/sys/class/powercap"));
+
+        let io = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "energy_uj");
+        assert_eq!(CommandError::from(Error::Io(io)).code, ErrorCode::PermissionDenied);
+        assert_eq!(CommandError::from(Error::Config("bad".to_string())).code, ErrorCode::ConfigInvalid);
+        assert_eq!(CommandError::from(Error::SessionActive(3)).code, ErrorCode::SessionActive);
+        assert_eq!(CommandError::from("oops").code, ErrorCode::Internal);
+    }
+
+    #[test]
+    fn test_command_error_serialization() {
+        let json = serde_json::to_value(CommandError::session_active("A session is already active")).unwrap();
+        assert_eq!(json["code"], "SESSION_ACTIVE");
+        assert_eq!(json["message"], "A session is already active");
+        assert!(json.get("details").is_none());
+
+        let json = serde_json::to_value(CommandError::from(Error::Database(rusqlite::Error::InvalidQuery))).unwrap();
+        assert_eq!(json["code"], "DB_ERROR");
+        assert!(json["details"].is_string());
+        assert!(json.get("subject").is_none() && json.get("elevation_hint").is_none());
+
+        let json = serde_json::to_value(
+            CommandError::permission_denied("Access denied").with_subject("game.exe").with_elevation_hint(true),
+        )
+        .unwrap();
+        assert_eq!(json["code"], "PERMISSION_DENIED");
+        assert_eq!(json["subject"], "game.exe");
+        assert_eq!(json["elevation_hint"], true);
+    }
+}
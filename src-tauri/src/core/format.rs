@@ -0,0 +1,259 @@
+//! Locale-aware formatting of numbers, dates and durations
+//!
+//! Keyed off the I18n language code: "fr" uses French rules, anything else
+//! falls back to English.
+
+use crate::core::EnergyUnit;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+
+/// Non-breaking space, used by French as thousands separator and before units
+const NBSP: char = '\u{00A0}';
+
+const MONTHS_EN: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+const MONTHS_FR: [&str; 12] = [
+    "janvier", "f\u{00E9}vrier", "mars", "avril", "mai", "juin",
+    "juillet", "ao\u{00FB}t", "septembre", "octobre", "novembre", "d\u{00E9}cembre",
+];
+
+const WEEKDAYS_EN: [&str; 7] = ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
+
+const WEEKDAYS_FR: [&str; 7] = ["lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche"];
+
+pub(crate) fn is_french(lang: &str) -> bool {
+    lang == "fr"
+}
+
+/// Format a number with grouped thousands and a fixed number of decimals
+///
+/// English: `1,234.56`, French: `1 234,56` (non-breaking space).
+pub fn format_number(value: f64, decimals: usize, lang: &str) -> String {
+    if !value.is_finite() {
+        return "-".to_string();
+    }
+
+    let (group_sep, decimal_sep) = if is_french(lang) { (NBSP, ',') } else { (',', '.') };
+
+    let formatted = format!("{:.*}", decimals, value.abs());
+    let (int_part, frac_part) = match formatted.split_once('.') {
+        Some((i, f)) => (i, Some(f)),
+        None => (formatted.as_str(), None),
+    };
+
+    let mut result = String::with_capacity(formatted.len() + int_part.len() / 3 + 1);
+    // Don't print "-0.00" for values that round to zero
+    if value < 0.0 && formatted.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        result.push('-');
+    }
+    for (i, c) in int_part.chars().enumerate() {
+        if i > 0 && (int_part.len() - i) % 3 == 0 {
+            result.push(group_sep);
+        }
+        result.push(c);
+    }
+    if let Some(frac) = frac_part {
+        result.push(decimal_sep);
+        result.push_str(frac);
+    }
+    result
+}
+
+/// Format a date/time according to a style
+///
+/// Styles: "short" (`01/15/2024` / `15/01/2024`), "long" (`January 15, 2024` /
+/// `15 janvier 2024`), "time" (`14:30`), "datetime" (short + time).
+/// Unknown styles fall back to "short".
+pub fn format_datetime(dt: &NaiveDateTime, style: &str, lang: &str) -> String {
+    let fr = is_french(lang);
+    let (day, month, year) = (dt.day(), dt.month(), dt.year());
+
+    let short = if fr {
+        format!("{:02}/{:02}/{}", day, month, year)
+    } else {
+        format!("{:02}/{:02}/{}", month, day, year)
+    };
+    let time = format!("{:02}:{:02}", dt.hour(), dt.minute());
+
+    match style {
+        "long" => {
+            let idx = (month - 1) as usize;
+            if fr {
+                format!("{} {} {}", day, MONTHS_FR[idx], year)
+            } else {
+                format!("{} {}, {}", MONTHS_EN[idx], day, year)
+            }
+        }
+        "time" => time,
+        "datetime" => format!("{} {}", short, time),
+        _ => short,
+    }
+}
+
+/// Format a Unix timestamp (seconds) in local time, see `format_datetime` for styles
+pub fn format_date(timestamp: i64, style: &str, lang: &str) -> String {
+    format_date_in(timestamp, style, lang, &chrono::Local)
+}
+
+/// Same as `format_date`, in the given timezone
+pub fn format_date_in<Tz: TimeZone>(timestamp: i64, style: &str, lang: &str, tz: &Tz) -> String {
+    match tz.timestamp_opt(timestamp, 0).single() {
+        Some(dt) => format_datetime(&dt.naive_local(), style, lang),
+        None => "-".to_string(),
+    }
+}
+
+/// Format an amount of money: `€0.84` / `0,84 €`
+pub fn format_cost(value: f64, currency_symbol: &str, lang: &str) -> String {
+    format_cost_with(value, 2, currency_symbol, lang)
+}
+
+/// Same as `format_cost` with `decimals` decimals: `€0.0042`
+pub fn format_cost_with(value: f64, decimals: usize, currency_symbol: &str, lang: &str) -> String {
+    let number = format_number(value, decimals, lang);
+    if is_french(lang) {
+        format!("{}{NBSP}{}", number, currency_symbol)
+    } else {
+        format!("{}{}", currency_symbol, number)
+    }
+}
+
+/// Energy converted to the unit it is displayed in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayedEnergy {
+    pub value: f64,
+    /// I18n key of the unit, `unit.watt_hours` or `unit.kilowatt_hours`
+    pub unit_key: &'static str,
+}
+
+impl DisplayedEnergy {
+    /// `wh` in `unit`, auto (and unknown units) switching to kWh from 1000 Wh
+    pub fn new(wh: f64, unit: &EnergyUnit) -> Self {
+        let kwh = match unit {
+            EnergyUnit::Wh => false,
+            EnergyUnit::Kwh => true,
+            _ => wh.abs() >= 1000.0,
+        };
+        if kwh {
+            Self { value: wh / 1000.0, unit_key: "unit.kilowatt_hours" }
+        } else {
+            Self { value: wh, unit_key: "unit.watt_hours" }
+        }
+    }
+
+    /// `850.5 Wh` / `1,250 kWh` given the translated unit, one decimal for Wh and three for kWh
+    pub fn format(&self, unit_label: &str, lang: &str) -> String {
+        let decimals = if self.unit_key == "unit.kilowatt_hours" { 3 } else { 1 };
+        let number = format_number(self.value, decimals, lang);
+        if is_french(lang) {
+            format!("{}{NBSP}{}", number, unit_label)
+        } else {
+            format!("{} {}", number, unit_label)
+        }
+    }
+}
+
+/// Name of the day of the week: `Tuesday` / `mardi`
+pub fn format_weekday(date: NaiveDate, lang: &str) -> String {
+    let idx = date.weekday().num_days_from_monday() as usize;
+    if is_french(lang) { WEEKDAYS_FR[idx] } else { WEEKDAYS_EN[idx] }.to_string()
+}
+
+/// Format a duration in seconds: `1h 05m`, `2m 30s`, `45s` (French: `1 h 05 min`...)
+pub fn format_duration(seconds: i64, lang: &str) -> String {
+    let seconds = seconds.max(0);
+    let (h, m, s) = (seconds / 3600, (seconds % 3600) / 60, seconds % 60);
+
+    if is_french(lang) {
+        if h > 0 {
+            format!("{}{NBSP}h{NBSP}{:02}{NBSP}min", h, m)
+        } else if m > 0 {
+            format!("{}{NBSP}min{NBSP}{:02}{NBSP}s", m, s)
+        } else {
+            format!("{}{NBSP}s", s)
+        }
+    } else if h > 0 {
+        format!("{}h {:02}m", h, m)
+    } else if m > 0 {
+        format!("{}m {:02}s", m, s)
+    } else {
+        format!("{}s", s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(1234.567, 2, "en"), "1,234.57");
+        assert_eq!(format_number(1234.567, 2, "fr"), "1\u{00A0}234,57");
+        assert_eq!(format_number(1234567.0, 0, "en"), "1,234,567");
+        assert_eq!(format_number(0.5, 2, "fr"), "0,50");
+        assert_eq!(format_number(-1500.0, 1, "en"), "-1,500.0");
+        assert_eq!(format_number(-0.001, 2, "en"), "0.00");
+        assert_eq!(format_number(f64::NAN, 2, "en"), "-");
+        // Unknown languages use English rules
+        assert_eq!(format_number(1000.0, 2, "de"), "1,000.00");
+    }
+
+    #[test]
+    fn test_format_datetime() {
+        let dt = NaiveDate::from_ymd_opt(2024, 8, 5).unwrap().and_hms_opt(14, 7, 0).unwrap();
+
+        assert_eq!(format_datetime(&dt, "short", "en"), "08/05/2024");
+        assert_eq!(format_datetime(&dt, "short", "fr"), "05/08/2024");
+        assert_eq!(format_datetime(&dt, "long", "en"), "August 5, 2024");
+        assert_eq!(format_datetime(&dt, "long", "fr"), "5 ao\u{00FB}t 2024");
+        assert_eq!(format_datetime(&dt, "time", "fr"), "14:07");
+        assert_eq!(format_datetime(&dt, "datetime", "fr"), "05/08/2024 14:07");
+        assert_eq!(format_datetime(&dt, "unknown", "en"), "08/05/2024");
+    }
+
+    #[test]
+    fn test_displayed_energy() {
+        let auto = DisplayedEnergy::new(999.0, &EnergyUnit::Auto);
+        assert_eq!(auto, DisplayedEnergy { value: 999.0, unit_key: "unit.watt_hours" });
+        let auto = DisplayedEnergy::new(1250.0, &EnergyUnit::Auto);
+        assert_eq!(auto.format("kWh", "en"), "1.250 kWh");
+        assert_eq!(auto.format("kWh", "fr"), "1,250\u{00A0}kWh");
+        assert_eq!(DisplayedEnergy::new(1250.0, &EnergyUnit::Wh).format("Wh", "en"), "1,250.0 Wh");
+        assert_eq!(DisplayedEnergy::new(42.0, &EnergyUnit::Kwh).format("kWh", "en"), "0.042 kWh");
+        // Unknown units display like auto
+        assert_eq!(DisplayedEnergy::new(1000.0, &EnergyUnit::Other("mwh".into())).unit_key, "unit.kilowatt_hours");
+
+        assert_eq!(format_cost_with(0.00421, 4, "\u{20AC}", "en"), "\u{20AC}0.0042");
+        assert_eq!(format_cost_with(0.00421, 3, "\u{20AC}", "fr"), "0,004\u{00A0}\u{20AC}");
+    }
+
+    #[test]
+    fn test_format_date_in() {
+        use crate::core::timezone::HistoryZone;
+        // 2024-03-10 12:00 UTC
+        let ts = 1_710_072_000;
+        assert_eq!(format_date_in(ts, "datetime", "en", &HistoryZone::Utc), "03/10/2024 12:00");
+        assert_eq!(format_date_in(ts, "datetime", "en", &HistoryZone::parse("UTC+13").unwrap()), "03/11/2024 01:00");
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(45, "en"), "45s");
+        assert_eq!(format_duration(150, "en"), "2m 30s");
+        assert_eq!(format_duration(3900, "en"), "1h 05m");
+        assert_eq!(format_duration(3900, "fr"), "1\u{00A0}h\u{00A0}05\u{00A0}min");
+        assert_eq!(format_duration(-5, "en"), "0s");
+    }
+
+    #[test]
+    fn test_format_cost_and_weekday() {
+        assert_eq!(format_cost(0.844, "\u{20AC}", "en"), "\u{20AC}0.84");
+        assert_eq!(format_cost(1234.5, "\u{20AC}", "fr"), "1\u{00A0}234,50\u{00A0}\u{20AC}");
+        let tuesday = NaiveDate::from_ymd_opt(2024, 3, 12).unwrap();
+        assert_eq!(format_weekday(tuesday, "en"), "Tuesday");
+        assert_eq!(format_weekday(tuesday, "fr"), "mardi");
+    }
+}
@@ -0,0 +1,215 @@
+//! Time since the last keyboard or mouse input
+//!
+//! Lets session idle detection tell an unattended machine from a busy one.
+//! - Windows: GetLastInputInfo
+//! - Linux X11: the XScreenSaver extension (libXss, loaded at runtime)
+//! - Linux Wayland: GNOME's Mutter IdleMonitor over D-Bus, via `gdbus`. The
+//!   ext-idle-notify protocol only sends idle/resumed events to a client that
+//!   stays connected, which needs a Wayland client library the tree doesn't
+//!   have, so other compositors report no input idle.
+//!
+//! Where input can't be read, sessions go by power alone.
+
+use std::time::{Duration, Instant};
+
+/// A reading is reused this long, the Wayland query spawns a process
+const QUERY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Longest wait for the Wayland query, a hung session bus must not stall the critical loop
+#[cfg(target_os = "linux")]
+const GDBUS_TIMEOUT_MS: u64 = 1000;
+
+enum Backend {
+    Unavailable,
+    #[cfg(target_os = "windows")]
+    Windows,
+    #[cfg(target_os = "linux")]
+    X11(Box<X11Idle>),
+    #[cfg(target_os = "linux")]
+    Mutter,
+}
+
+impl Backend {
+    #[cfg(target_os = "windows")]
+    fn probe() -> Self {
+        match windows_idle_secs() {
+            Some(_) => Self::Windows,
+            None => Self::Unavailable,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn probe() -> Self {
+        let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some()
+            || std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t == "wayland");
+        if wayland {
+            // XWayland only sees input going to X clients, its idle time would be wrong
+            return match mutter_idle_secs() {
+                Some(_) => Self::Mutter,
+                None => Self::Unavailable,
+            };
+        }
+        match X11Idle::open() {
+            Some(x11) => Self::X11(Box::new(x11)),
+            None => Self::Unavailable,
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    fn probe() -> Self {
+        Self::Unavailable
+    }
+
+    fn query(&self) -> Option<u64> {
+        match self {
+            Self::Unavailable => None,
+            #[cfg(target_os = "windows")]
+            Self::Windows => windows_idle_secs(),
+            #[cfg(target_os = "linux")]
+            Self::X11(x11) => x11.idle_secs(),
+            #[cfg(target_os = "linux")]
+            Self::Mutter => mutter_idle_secs(),
+        }
+    }
+}
+
+/// Source of input idle time, probed on first use
+#[derive(Default)]
+pub struct InputIdle {
+    backend: Option<Backend>,
+    last: Option<(Instant, Option<u64>)>,
+}
+
+impl InputIdle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn backend(&mut self) -> &Backend {
+        self.backend.get_or_insert_with(Backend::probe)
+    }
+
+    /// Whether input idle time can be read on this system
+    pub fn available(&mut self) -> bool {
+        !matches!(self.backend(), Backend::Unavailable)
+    }
+
+    /// Seconds since the last keyboard or mouse input, None when unknown
+    pub fn idle_secs(&mut self) -> Option<u64> {
+        if let Some((at, secs)) = self.last {
+            if at.elapsed() < QUERY_INTERVAL {
+                return secs.map(|s| s + at.elapsed().as_secs());
+            }
+        }
+        let secs = self.backend().query();
+        self.last = Some((Instant::now(), secs));
+        secs
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn windows_idle_secs() -> Option<u64> {
+    use windows_sys::Win32::System::SystemInformation::GetTickCount;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    unsafe {
+        let mut info = LASTINPUTINFO { cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32, dwTime: 0 };
+        if GetLastInputInfo(&mut info) == 0 {
+            return None;
+        }
+        // Both are milliseconds since boot, wrapping every 49.7 days
+        Some(GetTickCount().wrapping_sub(info.dwTime) as u64 / 1000)
+    }
+}
+
+/// libX11 and libXss, the display is opened for each query
+#[cfg(target_os = "linux")]
+struct X11Idle {
+    xlib: x11_dl::xlib::Xlib,
+    xss: x11_dl::xss::Xss,
+}
+
+#[cfg(target_os = "linux")]
+impl X11Idle {
+    /// None without the libraries, a display or the extension
+    fn open() -> Option<Self> {
+        let x11 = Self { xlib: x11_dl::xlib::Xlib::open().ok()?, xss: x11_dl::xss::Xss::open().ok()? };
+        x11.idle_secs()?;
+        Some(x11)
+    }
+
+    fn idle_secs(&self) -> Option<u64> {
+        unsafe {
+            let display = (self.xlib.XOpenDisplay)(std::ptr::null());
+            if display.is_null() {
+                return None;
+            }
+            let (mut event_base, mut error_base) = (0, 0);
+            let mut idle_ms = None;
+            if (self.xss.XScreenSaverQueryExtension)(display, &mut event_base, &mut error_base) != 0 {
+                let info = (self.xss.XScreenSaverAllocInfo)();
+                if !info.is_null() {
+                    let root = (self.xlib.XDefaultRootWindow)(display);
+                    if (self.xss.XScreenSaverQueryInfo)(display, root, info) != 0 {
+                        // c_ulong is 32 bits on some targets
+                        #[allow(clippy::unnecessary_cast)]
+                        let ms = (*info).idle as u64;
+                        idle_ms = Some(ms);
+                    }
+                    (self.xlib.XFree)(info.cast());
+                }
+            }
+            (self.xlib.XCloseDisplay)(display);
+            idle_ms.map(|ms| ms / 1000)
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn mutter_idle_secs() -> Option<u64> {
+    let output = crate::hardware::command::run_command_with_timeout(
+        "gdbus",
+        &[
+            "call",
+            "--session",
+            "--dest",
+            "org.gnome.Mutter.IdleMonitor",
+            "--object-path",
+            "/org/gnome/Mutter/IdleMonitor/Core",
+            "--method",
+            "org.gnome.Mutter.IdleMonitor.GetIdletime",
+        ],
+        GDBUS_TIMEOUT_MS,
+    )?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_gdbus_idletime(&String::from_utf8_lossy(&output.stdout)).map(|ms| ms / 1000)
+}
+
+/// Milliseconds of a `GetIdletime` reply printed by gdbus ("(uint64 12345,)")
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_gdbus_idletime(output: &str) -> Option<u64> {
+    let value = output.trim().strip_prefix('(')?.strip_suffix(')')?.trim_end_matches(',');
+    value.strip_prefix("uint64 ").unwrap_or(value).trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gdbus_idletime() {
+        assert_eq!(parse_gdbus_idletime("(uint64 12345,)\n"), Some(12345));
+        assert_eq!(parse_gdbus_idletime("(0,)"), Some(0));
+        assert_eq!(parse_gdbus_idletime("Error: GDBus.Error:org.freedesktop.DBus.Error.ServiceUnknown"), None);
+        assert_eq!(parse_gdbus_idletime("(uint64 -1,)"), None);
+    }
+
+    #[test]
+    fn test_unavailable_input_idle() {
+        let mut idle = InputIdle { backend: Some(Backend::Unavailable), last: None };
+        assert!(!idle.available());
+        assert_eq!(idle.idle_secs(), None);
+    }
+}
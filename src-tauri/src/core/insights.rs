@@ -0,0 +1,343 @@
+//! Short observations about recent consumption for the dashboard
+//!
+//! Each generator looks at one kind of data (daily stats, sessions and their
+//! component split, hourly costs) and returns at most one insight: a
+//! translation key and the values of its placeholders. Generators without
+//! enough data to say something meaningful return nothing. `localize` turns
+//! an insight into a sentence of the current language.
+
+use crate::core::{format, PricingConfig, PricingMode, Result};
+use crate::db::{DailyStats, Database};
+use crate::i18n::I18n;
+use crate::pricing::{self, PricingEngine};
+use chrono::{DateTime, Duration, NaiveDate, TimeZone};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::analytics::{self, ComparisonPeriod};
+
+/// Weekly changes smaller than this are not worth mentioning
+const MIN_CHANGE_PERCENT: f64 = 5.0;
+
+/// Priced days needed among the last week to name the most expensive one
+const MIN_PRICED_DAYS: usize = 3;
+
+/// Sessions considered for the component split of each category
+const CATEGORY_LOOKBACK_DAYS: i64 = 30;
+
+/// Smallest GPU share worth mentioning
+const MIN_GPU_SHARE_PERCENT: f64 = 10.0;
+
+/// Savings below this round to nothing
+const MIN_SAVINGS: f64 = 0.01;
+
+/// Value of an insight placeholder
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum InsightArg {
+    Percent(f64),
+    /// Amount in the main currency
+    Cost(f64),
+    /// Shown as the day of the week
+    Weekday(NaiveDate),
+    Text(String),
+}
+
+/// One observation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Insight {
+    /// Translation key, its `{name}` placeholders filled from `args`
+    pub key: &'static str,
+    pub args: BTreeMap<&'static str, InsightArg>,
+}
+
+impl Insight {
+    fn new(key: &'static str, args: impl IntoIterator<Item = (&'static str, InsightArg)>) -> Self {
+        Self { key, args: args.into_iter().collect() }
+    }
+}
+
+/// Insight with its sentence in the current language
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalizedInsight {
+    #[serde(flatten)]
+    pub insight: Insight,
+    pub text: String,
+}
+
+/// Fill the sentence of an insight
+pub fn localize(insight: Insight, i18n: &I18n, currency_symbol: &str) -> LocalizedInsight {
+    let lang = i18n.current_language();
+    let args: Vec<(&str, String)> = insight
+        .args
+        .iter()
+        .map(|(&name, value)| {
+            let value = match value {
+                InsightArg::Percent(p) => format::format_number(p.abs(), 0, lang),
+                InsightArg::Cost(c) => format::format_cost(*c, currency_symbol, lang),
+                InsightArg::Weekday(d) => format::format_weekday(*d, lang),
+                InsightArg::Text(t) => t.clone(),
+            };
+            (name, value)
+        })
+        .collect();
+    let text = i18n.format(insight.key, &args);
+    LocalizedInsight { insight, text }
+}
+
+/// Run every generator on the data available at `now`
+pub fn generate<Tz: TimeZone>(db: &Database, pricing: &PricingConfig, now: &DateTime<Tz>) -> Result<Vec<Insight>> {
+    let today = now.date_naive();
+    let fmt = |d: NaiveDate| d.format("%Y-%m-%d").to_string();
+
+    let ((week_start, week_end), (previous_start, previous_end)) = ComparisonPeriod::Week.ranges(today);
+    let week = db.get_daily_stats(&fmt(week_start), &fmt(week_end))?;
+    let previous_week = db.get_daily_stats(&fmt(previous_start), &fmt(previous_end))?;
+    let last_days = db.get_daily_stats(&fmt(today - Duration::days(7)), &fmt(today - Duration::days(1)))?;
+
+    let insights = [
+        weekly_energy_change(now.naive_local(), &week, &previous_week),
+        most_expensive_day(&last_days),
+        category_gpu_share(db, now.timestamp())?,
+        offpeak_savings(db, pricing, today, &now.timezone())?,
+    ];
+    Ok(insights.into_iter().flatten().collect())
+}
+
+/// This week so far against the same days of last week
+fn weekly_energy_change(now: chrono::NaiveDateTime, week: &[DailyStats], previous_week: &[DailyStats]) -> Option<Insight> {
+    let comparison = analytics::compare_days(ComparisonPeriod::Week, now, week, previous_week);
+    let change = comparison.kwh_change_percent.filter(|c| c.abs() >= MIN_CHANGE_PERCENT)?;
+    let key = if change > 0.0 { "insight.week_energy_up" } else { "insight.week_energy_down" };
+    Some(Insight::new(key, [("percent", InsightArg::Percent(change))]))
+}
+
+/// Costliest of the last complete days
+fn most_expensive_day(days: &[DailyStats]) -> Option<Insight> {
+    let priced: Vec<(NaiveDate, f64)> = days
+        .iter()
+        .filter_map(|d| Some((NaiveDate::parse_from_str(&d.date, "%Y-%m-%d").ok()?, d.total_cost.filter(|c| *c > 0.0)?)))
+        .collect();
+    if priced.len() < MIN_PRICED_DAYS {
+        return None;
+    }
+    let (date, cost) = priced.into_iter().max_by(|a, b| a.1.total_cmp(&b.1))?;
+    Some(Insight::new("insight.most_expensive_day", [("day", InsightArg::Weekday(date)), ("cost", InsightArg::Cost(cost))]))
+}
+
+/// Category of recent sessions where the GPU took the largest share of the energy
+fn category_gpu_share(db: &Database, now: i64) -> Result<Option<Insight>> {
+    let sessions = db.get_sessions_started_in_range(now - CATEGORY_LOOKBACK_DAYS * 86_400, now)?;
+
+    // Category -> (GPU kWh, attributed kWh, total kWh)
+    let mut categories: BTreeMap<String, (f64, f64, f64)> = BTreeMap::new();
+    for session in &sessions {
+        let Some(category) = session.category.clone() else {
+            continue;
+        };
+        let end = session.end_time.unwrap_or(now);
+        let readings = db.get_component_readings(session.start_time, end)?;
+        let breakdown = pricing::compute_cost_breakdown(&readings, session.start_time, end, |_| 0.0);
+        let kwh = |name: &str| breakdown.components.iter().find(|c| c.component == name).map_or(0.0, |c| c.kwh);
+        let totals = categories.entry(category).or_default();
+        totals.0 += kwh("gpu");
+        totals.1 += breakdown.total_kwh - kwh("unattributed");
+        totals.2 += breakdown.total_kwh;
+    }
+
+    // Only categories whose energy is mostly split into components, or the share means nothing
+    let best = categories
+        .into_iter()
+        .filter(|(_, (gpu, attributed, total))| *gpu > 0.0 && *attributed >= total / 2.0)
+        .map(|(category, (gpu, _, total))| (category, gpu / total * 100.0))
+        .filter(|(_, share)| *share >= MIN_GPU_SHARE_PERCENT)
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+
+    Ok(best.map(|(category, share)| {
+        Insight::new("insight.category_gpu_share", [("percent", InsightArg::Percent(share)), ("category", InsightArg::Text(category))])
+    }))
+}
+
+/// What this month's off-peak hours saved against paying the peak rate for everything
+fn offpeak_savings<Tz: TimeZone>(db: &Database, pricing: &PricingConfig, today: NaiveDate, tz: &Tz) -> Result<Option<Insight>> {
+    if pricing.mode != PricingMode::PeakOffpeak {
+        return Ok(None);
+    }
+    let engine = PricingEngine::new(pricing);
+    let rate_at = |ts: i64| {
+        tz.timestamp_opt(ts, 0)
+            .single()
+            .map_or(pricing.peak_offpeak.peak_rate, |dt| engine.rate_at(dt.naive_local()))
+    };
+
+    let ((month_start, _), _) = pricing::month_ranges(today);
+    let mut savings = 0.0;
+    for date in month_start.iter_days().take_while(|d| *d <= today) {
+        for hour in db.get_hourly_stats_in(date, &rate_at, tz)? {
+            savings += hour.total_wh / 1000.0 * pricing.peak_offpeak.peak_rate - hour.total_cost;
+        }
+    }
+
+    Ok((savings >= MIN_SAVINGS).then(|| Insight::new("insight.offpeak_savings", [("cost", InsightArg::Cost(savings))])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::PowerReading;
+    use chrono::{FixedOffset, NaiveDateTime};
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    fn fixture_db() -> Database {
+        Database::open(Path::new(":memory:")).unwrap()
+    }
+
+    fn tz() -> FixedOffset {
+        FixedOffset::east_opt(3600).unwrap()
+    }
+
+    /// Wednesday 2024-03-13 at 15:00, UTC+1
+    fn now() -> DateTime<FixedOffset> {
+        tz().from_local_datetime(&at("2024-03-13 15:00")).unwrap()
+    }
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn ts(s: &str) -> i64 {
+        tz().from_local_datetime(&at(s)).unwrap().timestamp()
+    }
+
+    fn add_day(db: &Database, date: &str, wh: f64, cost: Option<f64>) {
+        db.upsert_daily_stats(&DailyStats {
+            date: date.to_string(),
+            total_wh: wh,
+            total_cost: cost,
+            avg_watts: 0.0,
+            max_watts: 0.0,
+            pricing_mode: None,
+            usage_seconds: 0,
+            source: None,
+            note: None,
+        })
+        .unwrap();
+    }
+
+    /// Readings every 10 s over `minutes`, with an optional CPU/GPU split
+    fn add_readings(db: &Database, start: i64, minutes: i64, watts: f64, split: Option<(f64, f64)>) {
+        for i in 0..minutes * 6 {
+            let mut reading = PowerReading::new(watts, "test", false);
+            reading.timestamp = start + i * 10;
+            reading.components = split.map(|(cpu, gpu)| HashMap::from([("cpu".to_string(), cpu), ("gpu".to_string(), gpu)]));
+            db.insert_reading(&reading).unwrap();
+        }
+    }
+
+    fn add_session(db: &Database, start: i64, minutes: i64, category: &str) {
+        let id = db.start_session(50.0, None).unwrap();
+        db.reanchor_session_start(id, start).unwrap();
+        db.update_session_category(id, Some(category)).unwrap();
+        db.auto_end_session(id, start + minutes * 60, 0.0, 0.0, 0.0).unwrap();
+    }
+
+    #[test]
+    fn test_empty_database_has_no_insights() {
+        let insights = generate(&fixture_db(), &PricingConfig::default(), &now()).unwrap();
+        assert!(insights.is_empty());
+    }
+
+    #[test]
+    fn test_weekly_change_and_most_expensive_day() {
+        let db = fixture_db();
+        // Monday to Wednesday of last week, then this week
+        add_day(&db, "2024-03-04", 1000.0, Some(0.20));
+        add_day(&db, "2024-03-05", 1000.0, Some(0.84));
+        add_day(&db, "2024-03-06", 1000.0, Some(0.30));
+        add_day(&db, "2024-03-11", 1500.0, Some(0.30));
+        add_day(&db, "2024-03-12", 1500.0, Some(0.40));
+        add_day(&db, "2024-03-13", 1000.0, Some(0.25));
+
+        let insights = generate(&db, &PricingConfig::default(), &now()).unwrap();
+        assert_eq!(insights.len(), 2);
+        // 4 kWh this week so far against 3 kWh over the same days
+        assert_eq!(insights[0].key, "insight.week_energy_up");
+        let InsightArg::Percent(change) = insights[0].args["percent"] else { panic!() };
+        assert!((change - 33.33).abs() < 0.01);
+        // Last seven complete days: 2024-03-06 to 2024-03-12
+        assert_eq!(
+            insights[1],
+            Insight::new(
+                "insight.most_expensive_day",
+                [("day", InsightArg::Weekday(NaiveDate::from_ymd_opt(2024, 3, 12).unwrap())), ("cost", InsightArg::Cost(0.40))]
+            )
+        );
+    }
+
+    #[test]
+    fn test_small_change_and_few_priced_days_are_skipped() {
+        let db = fixture_db();
+        // Unpriced, and after the compared stretch of last week
+        add_day(&db, "2024-03-09", 1000.0, None);
+        add_day(&db, "2024-03-11", 1020.0, Some(0.3));
+        add_day(&db, "2024-03-12", 1000.0, Some(0.3));
+        add_day(&db, "2024-03-04", 1030.0, Some(0.3));
+        add_day(&db, "2024-03-05", 1000.0, Some(0.3));
+        assert!(generate(&db, &PricingConfig::default(), &now()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_category_gpu_share() {
+        let db = fixture_db();
+        let gaming = ts("2024-03-10 20:00");
+        add_session(&db, gaming, 30, "Gaming");
+        add_readings(&db, gaming, 30, 300.0, Some((100.0, 180.0)));
+        let work = ts("2024-03-11 09:00");
+        add_session(&db, work, 30, "Work");
+        add_readings(&db, work, 30, 100.0, Some((80.0, 5.0)));
+        // Category with readings that cannot be split
+        let render = ts("2024-03-12 09:00");
+        add_session(&db, render, 30, "Render");
+        add_readings(&db, render, 30, 400.0, None);
+
+        let insights = generate(&db, &PricingConfig::default(), &now()).unwrap();
+        assert_eq!(insights.len(), 1);
+        assert_eq!(insights[0].key, "insight.category_gpu_share");
+        assert_eq!(insights[0].args["category"], InsightArg::Text("Gaming".to_string()));
+        let InsightArg::Percent(share) = insights[0].args["percent"] else { panic!() };
+        assert!((share - 60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_offpeak_savings() {
+        let db = fixture_db();
+        // One hour off-peak (23:00) and one at peak (12:00), 1 kWh each
+        add_readings(&db, ts("2024-03-02 23:00"), 60, 1000.0, None);
+        add_readings(&db, ts("2024-03-02 12:00"), 60, 1000.0, None);
+
+        let mut pricing = PricingConfig { mode: PricingMode::PeakOffpeak, ..Default::default() };
+        pricing.peak_offpeak.peak_rate = 0.30;
+        pricing.peak_offpeak.offpeak_rate = 0.20;
+        let insights = generate(&db, &pricing, &now()).unwrap();
+        assert_eq!(insights.len(), 1);
+        assert_eq!(insights[0].key, "insight.offpeak_savings");
+        let InsightArg::Cost(savings) = insights[0].args["cost"] else { panic!() };
+        assert!((savings - 0.10).abs() < 1e-9);
+
+        // Nothing to report under a flat rate
+        assert!(generate(&db, &PricingConfig::default(), &now()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_localize() {
+        let insight = Insight::new(
+            "insight.most_expensive_day",
+            [("day", InsightArg::Weekday(NaiveDate::from_ymd_opt(2024, 3, 12).unwrap())), ("cost", InsightArg::Cost(0.84))],
+        );
+        let en = localize(insight.clone(), &I18n::new("en"), "\u{20AC}");
+        assert_eq!(en.text, "Tuesday was your most expensive day (\u{20AC}0.84)");
+        let fr = localize(insight, &I18n::new("fr"), "\u{20AC}");
+        assert_eq!(fr.text, "Votre jour le plus cher\u{00A0}: mardi (0,84\u{00A0}\u{20AC})");
+    }
+}
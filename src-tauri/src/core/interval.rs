@@ -0,0 +1,473 @@
+//! Monitoring loop intervals whose rate can change at runtime
+//!
+//! Each loop owns a `DynamicInterval` watching an atomic rate in milliseconds.
+//! `RefreshRates` is the only writer: it combines the configured rates with
+//! eco mode (slower while no window is shown) and low-power mode (critical
+//! loop at 5 s, detailed loop paused), so the loops never read the config
+//! just to learn their period.
+//!
+//! `CollectionTuner` stretches the detailed rate on machines where one
+//! collection takes most of the period, the configured rate staying the floor.
+//!
+//! `Cadence` counts periods of elapsed time across ticks, for work that must
+//! follow the wall clock (stored readings, daily stats) rather than the tick count.
+
+use crate::core::Config;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::time::{Duration, Instant, Interval};
+
+/// Fastest rate a loop can run at, whatever the config says
+pub const MIN_INTERVAL_MS: u64 = 100;
+
+/// How much slower the loops run in eco mode while no window is shown
+pub const ECO_RATE_FACTOR: u64 = 4;
+
+/// Slowest critical rate in low-power mode (slower configured rates are kept)
+pub const LOW_POWER_CRITICAL_MS: u64 = 5000;
+
+/// Consecutive collections over half the period before the detailed rate is stretched
+pub const SLOW_TICKS_BEFORE_STRETCH: u32 = 3;
+
+/// Consecutive collections under a fifth of the period before a stretch is undone by half
+pub const FAST_TICKS_BEFORE_SHRINK: u32 = 5;
+
+/// Largest stretch of the detailed rate, as a multiple of its configured value
+pub const MAX_STRETCH_FACTOR: u64 = 4;
+
+/// Interval following a rate shared through an atomic
+pub struct DynamicInterval {
+    rate_ms: Arc<AtomicU64>,
+    period_ms: u64,
+    interval: Interval,
+}
+
+impl DynamicInterval {
+    /// The first tick completes immediately, like `tokio::time::interval`
+    pub fn new(rate_ms: Arc<AtomicU64>) -> Self {
+        let period_ms = Self::floored(&rate_ms);
+        Self {
+            rate_ms,
+            period_ms,
+            interval: tokio::time::interval(Duration::from_millis(period_ms)),
+        }
+    }
+
+    fn floored(rate_ms: &AtomicU64) -> u64 {
+        rate_ms.load(Ordering::Relaxed).max(MIN_INTERVAL_MS)
+    }
+
+    /// Current period in milliseconds
+    pub fn period_ms(&self) -> u64 {
+        self.period_ms
+    }
+
+    /// Wait for the next tick, returns whether the rate changed since the previous one
+    ///
+    /// A new rate applies from this tick on: the next one comes a full new period later.
+    pub async fn tick(&mut self) -> bool {
+        self.interval.tick().await;
+        let rate_ms = Self::floored(&self.rate_ms);
+        if rate_ms == self.period_ms {
+            return false;
+        }
+        self.period_ms = rate_ms;
+        let period = Duration::from_millis(rate_ms);
+        self.interval = tokio::time::interval_at(Instant::now() + period, period);
+        true
+    }
+}
+
+/// Change of the detailed rate decided by `CollectionTuner`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RateAdjustment {
+    /// Rate the loop would run at without tuning
+    pub configured_ms: u64,
+    /// Rate it runs at from now on
+    pub effective_ms: u64,
+    /// Duration of the collection that triggered the change
+    pub collection_ms: u64,
+    /// First stretch since startup, the only one worth telling the user about
+    pub first_stretch: bool,
+}
+
+/// Detailed rate following how long the collections take
+///
+/// Doubles the rate after `SLOW_TICKS_BEFORE_STRETCH` collections over half of it
+/// (up to `MAX_STRETCH_FACTOR` times the configured rate) and halves it again
+/// after `FAST_TICKS_BEFORE_SHRINK` collections under a fifth of it.
+#[derive(Debug, Default)]
+pub struct CollectionTuner {
+    /// Stretched rate, 0 while the configured one applies
+    stretched_ms: u64,
+    slow_ticks: u32,
+    fast_ticks: u32,
+    stretched_once: bool,
+}
+
+impl CollectionTuner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rate to run at for `configured_ms`
+    pub fn effective_ms(&self, configured_ms: u64) -> u64 {
+        self.stretched_ms.max(configured_ms)
+    }
+
+    /// Account for one collection, returns the new rate when it changes
+    pub fn record(&mut self, configured_ms: u64, collection_ms: u64) -> Option<RateAdjustment> {
+        let current_ms = self.effective_ms(configured_ms);
+        if collection_ms.saturating_mul(2) > current_ms {
+            self.slow_ticks += 1;
+            self.fast_ticks = 0;
+        } else if collection_ms.saturating_mul(5) < current_ms {
+            self.fast_ticks += 1;
+            self.slow_ticks = 0;
+        } else {
+            self.slow_ticks = 0;
+            self.fast_ticks = 0;
+        }
+
+        let effective_ms = if self.slow_ticks >= SLOW_TICKS_BEFORE_STRETCH {
+            current_ms.saturating_mul(2).min(configured_ms.saturating_mul(MAX_STRETCH_FACTOR))
+        } else if self.fast_ticks >= FAST_TICKS_BEFORE_SHRINK {
+            (current_ms / 2).max(configured_ms)
+        } else {
+            return None;
+        };
+        self.slow_ticks = 0;
+        self.fast_ticks = 0;
+        if effective_ms == current_ms {
+            return None;
+        }
+
+        let first_stretch = effective_ms > current_ms && !self.stretched_once;
+        self.stretched_once |= first_stretch;
+        self.stretched_ms = if effective_ms > configured_ms { effective_ms } else { 0 };
+        Some(RateAdjustment { configured_ms, effective_ms, collection_ms, first_stretch })
+    }
+}
+
+/// Whole periods of elapsed time, whatever the length of the ticks reporting it
+///
+/// Due on the first call. The remainder of a period carries over to the next
+/// call, so ticks that don't divide the period still average one per period.
+#[derive(Debug, Clone)]
+pub struct Cadence {
+    period_secs: f64,
+    pending_secs: f64,
+}
+
+impl Cadence {
+    pub fn new(period_secs: f64) -> Self {
+        Self { period_secs, pending_secs: period_secs }
+    }
+
+    /// Account for `elapsed_secs`, returns the periods now due, at most `max_due`
+    ///
+    /// Periods over `max_due` are dropped rather than caught up later: a tick
+    /// far longer than its period means the loop was stalled or the machine asleep.
+    pub fn advance(&mut self, elapsed_secs: f64, max_due: u32) -> u32 {
+        self.pending_secs += elapsed_secs.max(0.0);
+        let due = (self.pending_secs / self.period_secs).floor();
+        self.pending_secs -= due * self.period_secs;
+        (due as u32).min(max_due)
+    }
+
+    /// Most periods a tick of `tick_secs` can be worth, with one spare for the carried remainder
+    pub fn max_due_per_tick(&self, tick_secs: f64) -> u32 {
+        (tick_secs / self.period_secs).ceil() as u32 + 1
+    }
+}
+
+/// What the published rates are derived from
+#[derive(Debug, Clone, Copy)]
+struct RateInputs {
+    critical_ms: u64,
+    detailed_ms: u64,
+    /// Detailed rate set by `CollectionTuner`, 0 when not stretched
+    detailed_stretch_ms: u64,
+    eco_mode: bool,
+    hidden: bool,
+    low_power: bool,
+}
+
+/// Rates of the critical and detailed monitoring loops
+pub struct RefreshRates {
+    critical_ms: Arc<AtomicU64>,
+    detailed_ms: Arc<AtomicU64>,
+    low_power: AtomicBool,
+    inputs: Mutex<RateInputs>,
+}
+
+impl RefreshRates {
+    pub fn new(config: &Config) -> Self {
+        let rates = Self {
+            critical_ms: Arc::new(AtomicU64::new(config.general.refresh_rate_ms)),
+            detailed_ms: Arc::new(AtomicU64::new(config.general.slow_refresh_rate_ms)),
+            low_power: AtomicBool::new(false),
+            inputs: Mutex::new(RateInputs {
+                critical_ms: config.general.refresh_rate_ms,
+                detailed_ms: config.general.slow_refresh_rate_ms,
+                detailed_stretch_ms: 0,
+                eco_mode: config.general.eco_mode,
+                hidden: false,
+                low_power: false,
+            }),
+        };
+        rates.update(|_| {});
+        rates
+    }
+
+    /// Interval of the critical (fast) loop
+    pub fn critical_interval(&self) -> DynamicInterval {
+        DynamicInterval::new(Arc::clone(&self.critical_ms))
+    }
+
+    /// Interval of the detailed (slow) loop
+    pub fn detailed_interval(&self) -> DynamicInterval {
+        DynamicInterval::new(Arc::clone(&self.detailed_ms))
+    }
+
+    /// Take the configured rates and eco mode setting
+    pub fn apply_config(&self, config: &Config) {
+        self.update(|inputs| {
+            inputs.critical_ms = config.general.refresh_rate_ms;
+            inputs.detailed_ms = config.general.slow_refresh_rate_ms;
+            inputs.eco_mode = config.general.eco_mode;
+        });
+    }
+
+    /// Whether no window is shown (eco mode slows the loops down meanwhile)
+    pub fn set_hidden(&self, hidden: bool) {
+        self.update(|inputs| inputs.hidden = hidden);
+    }
+
+    /// Enter or leave low-power mode, leaving restores the rates it overrode
+    pub fn set_low_power(&self, enabled: bool) {
+        self.update(|inputs| inputs.low_power = enabled);
+    }
+
+    /// Current period of the critical loop, eco and low-power modes included
+    pub fn critical_ms(&self) -> u64 {
+        self.critical_ms.load(Ordering::Relaxed).max(MIN_INTERVAL_MS)
+    }
+
+    /// Current period of the detailed loop, eco mode included
+    pub fn detailed_ms(&self) -> u64 {
+        self.detailed_ms.load(Ordering::Relaxed).max(MIN_INTERVAL_MS)
+    }
+
+    /// Period of the detailed loop before any stretch, eco mode included
+    pub fn configured_detailed_ms(&self) -> u64 {
+        let inputs = self.inputs.lock().unwrap_or_else(|e| e.into_inner());
+        Self::eco_factor(&inputs).saturating_mul(inputs.detailed_ms).max(MIN_INTERVAL_MS)
+    }
+
+    /// Run the detailed loop at least every `stretch_ms` (0 to follow the configured rate)
+    pub fn set_detailed_stretch(&self, stretch_ms: u64) {
+        self.update(|inputs| inputs.detailed_stretch_ms = stretch_ms);
+    }
+
+    /// Whether low-power mode is on (detailed loop paused, no events)
+    pub fn low_power(&self) -> bool {
+        self.low_power.load(Ordering::Relaxed)
+    }
+
+    fn eco_factor(inputs: &RateInputs) -> u64 {
+        if inputs.eco_mode && inputs.hidden { ECO_RATE_FACTOR } else { 1 }
+    }
+
+    fn update(&self, change: impl FnOnce(&mut RateInputs)) {
+        let mut inputs = self.inputs.lock().unwrap_or_else(|e| e.into_inner());
+        change(&mut inputs);
+        let factor = Self::eco_factor(&inputs);
+        let mut critical_ms = inputs.critical_ms.saturating_mul(factor);
+        if inputs.low_power {
+            critical_ms = critical_ms.max(LOW_POWER_CRITICAL_MS);
+        }
+        self.critical_ms.store(critical_ms, Ordering::Relaxed);
+        let detailed_ms = inputs.detailed_ms.saturating_mul(factor).max(inputs.detailed_stretch_ms);
+        self.detailed_ms.store(detailed_ms, Ordering::Relaxed);
+        self.low_power.store(inputs.low_power, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rate_change_mid_stream() {
+        let rate = Arc::new(AtomicU64::new(20));
+        let mut interval = DynamicInterval::new(Arc::clone(&rate));
+        assert!(!interval.tick().await); // immediate first tick
+
+        let started = std::time::Instant::now();
+        assert!(!interval.tick().await);
+        assert!(started.elapsed() >= std::time::Duration::from_millis(15));
+
+        // Picked up on the next tick, then applied in full
+        rate.store(120, Ordering::Relaxed);
+        assert!(interval.tick().await);
+        assert_eq!(interval.period_ms(), 120);
+        let started = std::time::Instant::now();
+        assert!(!interval.tick().await);
+        assert!(started.elapsed() >= std::time::Duration::from_millis(100));
+
+        // Zero (or anything too fast) is floored
+        rate.store(0, Ordering::Relaxed);
+        assert!(interval.tick().await);
+        assert_eq!(interval.period_ms(), MIN_INTERVAL_MS);
+        assert_eq!(DynamicInterval::new(Arc::new(AtomicU64::new(5))).period_ms(), MIN_INTERVAL_MS);
+    }
+
+    #[test]
+    fn test_eco_mode_slows_hidden_loops() {
+        let mut config = Config::default();
+        config.general.refresh_rate_ms = 1000;
+        config.general.slow_refresh_rate_ms = 5000;
+        let rates = RefreshRates::new(&config);
+
+        rates.set_hidden(true);
+        assert_eq!(rates.critical_ms.load(Ordering::Relaxed), 1000);
+
+        config.general.eco_mode = true;
+        rates.apply_config(&config);
+        assert_eq!(rates.critical_ms.load(Ordering::Relaxed), 1000 * ECO_RATE_FACTOR);
+        assert_eq!(rates.detailed_ms.load(Ordering::Relaxed), 5000 * ECO_RATE_FACTOR);
+
+        rates.set_hidden(false);
+        assert_eq!(rates.critical_ms.load(Ordering::Relaxed), 1000);
+        assert_eq!(rates.detailed_ms.load(Ordering::Relaxed), 5000);
+    }
+
+    #[test]
+    fn test_low_power_restores_rates() {
+        let mut config = Config::default();
+        config.general.refresh_rate_ms = 1000;
+        config.general.slow_refresh_rate_ms = 5000;
+        config.general.eco_mode = true;
+        let rates = RefreshRates::new(&config);
+
+        rates.set_low_power(true);
+        assert!(rates.low_power());
+        assert_eq!(rates.critical_ms.load(Ordering::Relaxed), LOW_POWER_CRITICAL_MS);
+
+        // Eco mode changes meanwhile are honored once low-power mode ends
+        rates.set_hidden(true);
+        assert_eq!(rates.critical_ms.load(Ordering::Relaxed), LOW_POWER_CRITICAL_MS);
+        rates.set_low_power(false);
+        assert!(!rates.low_power());
+        assert_eq!(rates.critical_ms.load(Ordering::Relaxed), 1000 * ECO_RATE_FACTOR);
+        assert_eq!(rates.detailed_ms.load(Ordering::Relaxed), 5000 * ECO_RATE_FACTOR);
+
+        // A configured rate slower than the low-power one is kept
+        config.general.refresh_rate_ms = 8000;
+        config.general.eco_mode = false;
+        rates.apply_config(&config);
+        rates.set_low_power(true);
+        assert_eq!(rates.critical_ms.load(Ordering::Relaxed), 8000);
+    }
+
+    #[test]
+    fn test_collection_tuner() {
+        let mut tuner = CollectionTuner::new();
+
+        // Slow collections must be consecutive
+        assert_eq!(tuner.record(5000, 4000), None);
+        assert_eq!(tuner.record(5000, 4000), None);
+        assert_eq!(tuner.record(5000, 2000), None);
+        assert_eq!(tuner.record(5000, 4000), None);
+        assert_eq!(tuner.record(5000, 4000), None);
+        let stretch = tuner.record(5000, 4000).unwrap();
+        assert_eq!(stretch, RateAdjustment { configured_ms: 5000, effective_ms: 10_000, collection_ms: 4000, first_stretch: true });
+
+        // Still over half the stretched period: stretched again, up to the cap
+        for _ in 0..SLOW_TICKS_BEFORE_STRETCH - 1 {
+            assert_eq!(tuner.record(5000, 6000), None);
+        }
+        let again = tuner.record(5000, 6000).unwrap();
+        assert_eq!(again.effective_ms, 20_000);
+        assert!(!again.first_stretch);
+        for _ in 0..SLOW_TICKS_BEFORE_STRETCH {
+            assert_eq!(tuner.record(5000, 15_000), None);
+        }
+        assert_eq!(tuner.effective_ms(5000), 5000 * MAX_STRETCH_FACTOR);
+
+        // Between a fifth and half of the period nothing changes
+        for _ in 0..10 {
+            assert_eq!(tuner.record(5000, 6000), None);
+        }
+
+        // Fast collections halve the stretch, never below the configured rate
+        for _ in 0..FAST_TICKS_BEFORE_SHRINK - 1 {
+            assert_eq!(tuner.record(5000, 500), None);
+        }
+        assert_eq!(tuner.record(5000, 500).unwrap().effective_ms, 10_000);
+        for _ in 0..FAST_TICKS_BEFORE_SHRINK - 1 {
+            assert_eq!(tuner.record(5000, 500), None);
+        }
+        assert_eq!(tuner.record(5000, 500).unwrap().effective_ms, 5000);
+        for _ in 0..FAST_TICKS_BEFORE_SHRINK {
+            assert_eq!(tuner.record(5000, 500), None);
+        }
+
+        // A slower configured rate overrides the stretch
+        let mut tuner = CollectionTuner::new();
+        for _ in 0..SLOW_TICKS_BEFORE_STRETCH {
+            tuner.record(1000, 900);
+        }
+        assert_eq!(tuner.effective_ms(1000), 2000);
+        assert_eq!(tuner.effective_ms(5000), 5000);
+
+        let rates = RefreshRates::new(&Config::default());
+        let configured = rates.configured_detailed_ms();
+        rates.set_detailed_stretch(configured * 2);
+        assert_eq!(rates.detailed_ms(), configured * 2);
+        assert_eq!(rates.configured_detailed_ms(), configured);
+        rates.set_detailed_stretch(0);
+        assert_eq!(rates.detailed_ms(), configured);
+    }
+
+    #[test]
+    fn test_cadence_follows_elapsed_time() {
+        // 1 s ticks: due at once, then every 10th
+        let mut cadence = Cadence::new(10.0);
+        let due: Vec<u32> = (0..21).map(|i| cadence.advance(if i == 0 { 0.0 } else { 1.0 }, 2)).collect();
+        assert_eq!(due.iter().sum::<u32>(), 3);
+        assert_eq!(due[..11], [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+
+        // Ticks longer than the period are worth several
+        let mut cadence = Cadence::new(10.0);
+        cadence.advance(0.0, 2);
+        assert_eq!(cadence.max_due_per_tick(30.0), 4);
+        assert_eq!(cadence.advance(30.0, 4), 3);
+
+        // A stall over the limit isn't caught up
+        assert_eq!(cadence.advance(3600.0, 4), 4);
+        assert_eq!(cadence.advance(0.0, 4), 0);
+    }
+
+    #[test]
+    fn test_slowed_loops_keep_one_reading_per_period() {
+        let mut config = Config::default();
+        config.general.refresh_rate_ms = 1000;
+        config.general.eco_mode = true;
+        let rates = RefreshRates::new(&config);
+
+        // Low-power and hidden eco ticks, a minute of each
+        for (hidden, low_power) in [(false, true), (true, false)] {
+            rates.set_hidden(hidden);
+            rates.set_low_power(low_power);
+            let tick_secs = rates.critical_ms() as f64 / 1000.0;
+            let mut cadence = Cadence::new(10.0);
+            cadence.advance(0.0, 2);
+            let ticks = (60.0 / tick_secs) as usize;
+            let due: u32 = (0..ticks).map(|_| cadence.advance(tick_secs, cadence.max_due_per_tick(tick_secs))).sum();
+            assert_eq!(due, 6, "{} s ticks", tick_secs);
+        }
+    }
+}
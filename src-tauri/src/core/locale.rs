@@ -0,0 +1,121 @@
+//! System locale detection
+//!
+//! Reads the user's language and region, used for the UI language and the
+//! pricing defaults of a new install.
+//! - Linux/macOS: `LC_ALL`, `LC_MONETARY` then `LANG` (POSIX order), and on
+//!   macOS `AppleLocale` since apps started from Finder get no `LANG`
+//! - Windows: GetUserDefaultLocaleName, the environment is usually unset there
+
+use crate::pricing::{find_preset, PricingPreset};
+
+/// Language and region of a locale tag such as `en_US.UTF-8` or `fr-FR`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemLocale {
+    /// Lowercase ISO 639-1 language code
+    pub language: String,
+    /// Uppercase ISO 3166-1 region code, when the tag has one
+    pub region: Option<String>,
+}
+
+impl SystemLocale {
+    /// Parse a POSIX (`en_US.UTF-8@euro`) or BCP 47 (`en-US`, `zh-Hant-TW`) tag
+    pub fn parse(tag: &str) -> Option<Self> {
+        let tag = tag.split(['.', '@']).next().unwrap_or_default().trim();
+        let mut parts = tag.split(['_', '-']);
+        let language = parts.next().unwrap_or_default().to_ascii_lowercase();
+        // "C" and "POSIX" say nothing about the user
+        if language.len() < 2 || language.len() > 3 || !language.chars().all(|c| c.is_ascii_alphabetic()) || language == "posix" {
+            return None;
+        }
+        // Skip a script subtag ("Hant"), the region is two letters
+        let region = parts
+            .find(|p| p.len() == 2 && p.chars().all(|c| c.is_ascii_alphabetic()))
+            .map(|p| p.to_ascii_uppercase());
+        Some(Self { language, region })
+    }
+
+    /// Pricing preset of the region, None for regions without one
+    pub fn pricing_preset(&self) -> Option<&'static PricingPreset> {
+        // "UK" is not ISO but shows up in hand-written locales
+        let region = match self.region.as_deref()? {
+            "UK" => "GB",
+            region => region,
+        };
+        find_preset(region)
+    }
+}
+
+/// Locale of the current user, None when it cannot be told
+pub fn system_locale() -> Option<SystemLocale> {
+    system_locale_tag().as_deref().and_then(SystemLocale::parse)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn system_locale_tag() -> Option<String> {
+    let from_env = ["LC_ALL", "LC_MONETARY", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| SystemLocale::parse(value).is_some());
+    #[cfg(target_os = "macos")]
+    let from_env = from_env.or_else(apple_locale);
+    from_env
+}
+
+#[cfg(target_os = "macos")]
+fn apple_locale() -> Option<String> {
+    let output = std::process::Command::new("defaults").args(["read", "-g", "AppleLocale"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn system_locale_tag() -> Option<String> {
+    use windows_sys::Win32::Globalization::GetUserDefaultLocaleName;
+
+    // LOCALE_NAME_MAX_LENGTH
+    let mut buffer = [0u16; 85];
+    // Length written including the terminating null, 0 on failure
+    let len = unsafe { GetUserDefaultLocaleName(buffer.as_mut_ptr(), buffer.len() as i32) };
+    if len <= 1 {
+        return None;
+    }
+    Some(String::from_utf16_lossy(&buffer[..len as usize - 1]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(tag: &str) -> SystemLocale {
+        SystemLocale::parse(tag).unwrap()
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(locale("en_US.UTF-8"), SystemLocale { language: "en".into(), region: Some("US".into()) });
+        assert_eq!(locale("fr-FR").region.as_deref(), Some("FR"));
+        assert_eq!(locale("de_DE@euro").region.as_deref(), Some("DE"));
+        assert_eq!(locale("zh-Hant-TW").region.as_deref(), Some("TW"));
+        assert_eq!(locale("fr").region, None);
+        assert!(SystemLocale::parse("C").is_none());
+        assert!(SystemLocale::parse("POSIX").is_none());
+        assert!(SystemLocale::parse("").is_none());
+    }
+
+    #[test]
+    fn test_region_presets() {
+        let currency = |tag: &str| locale(tag).pricing_preset().map(|p| (p.currency, p.currency_symbol));
+        assert_eq!(currency("en_US.UTF-8"), Some(("USD", "$")));
+        assert_eq!(currency("en_GB.UTF-8"), Some(("GBP", "\u{00A3}")));
+        assert_eq!(currency("en-UK"), Some(("GBP", "\u{00A3}")));
+        assert_eq!(currency("fr_FR.UTF-8"), Some(("EUR", "\u{20AC}")));
+        assert_eq!(currency("de-DE"), Some(("EUR", "\u{20AC}")));
+        // Same language, other regions
+        assert_eq!(currency("fr_CA"), Some(("CAD", "$")));
+        assert_eq!(currency("fr_CH"), Some(("CHF", "CHF")));
+        assert_eq!(currency("en_IN"), None);
+        assert_eq!(currency("en"), None);
+    }
+}
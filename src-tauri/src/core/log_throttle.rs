@@ -0,0 +1,117 @@
+//! Rate-limited logging of repeated warnings
+//!
+//! A failure that repeats on every monitoring tick (missing nvidia-smi, a
+//! locked database) is logged the first time, then at most once per interval
+//! with the number of repeats since the last time it was written.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Shortest delay between two identical warnings
+pub const LOG_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Distinct messages tracked before stale ones are forgotten
+const MAX_ENTRIES: usize = 256;
+
+struct Entry {
+    last_logged: Instant,
+    repeats: u64,
+}
+
+/// Suppresses identical messages seen within an interval
+pub struct LogThrottle {
+    interval: Duration,
+    entries: Mutex<HashMap<String, Entry>>,
+    suppressed: AtomicU64,
+}
+
+impl LogThrottle {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            entries: Mutex::new(HashMap::new()),
+            suppressed: AtomicU64::new(0),
+        }
+    }
+
+    /// Text to log for `message` at `now`, None when it has to be suppressed
+    ///
+    /// After suppressed repeats, the text ends with "(repeated N times)".
+    pub fn filter(&self, message: &str, now: Instant) -> Option<String> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(entry) = entries.get_mut(message) {
+            if now.saturating_duration_since(entry.last_logged) < self.interval {
+                entry.repeats += 1;
+                self.suppressed.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+            let repeats = std::mem::take(&mut entry.repeats);
+            entry.last_logged = now;
+            return Some(if repeats > 0 {
+                format!("{} (repeated {} times)", message, repeats)
+            } else {
+                message.to_string()
+            });
+        }
+
+        if entries.len() >= MAX_ENTRIES {
+            let interval = self.interval;
+            entries.retain(|_, e| now.saturating_duration_since(e.last_logged) < interval);
+        }
+        entries.insert(message.to_string(), Entry { last_logged: now, repeats: 0 });
+        Some(message.to_string())
+    }
+
+    /// Messages suppressed since creation
+    pub fn suppressed(&self) -> u64 {
+        self.suppressed.load(Ordering::Relaxed)
+    }
+}
+
+fn global() -> &'static LogThrottle {
+    static THROTTLE: OnceLock<LogThrottle> = OnceLock::new();
+    THROTTLE.get_or_init(|| LogThrottle::new(LOG_INTERVAL))
+}
+
+/// Log a warning, identical messages at most once per `LOG_INTERVAL`
+pub fn warn(message: &str) {
+    if let Some(text) = global().filter(message, Instant::now()) {
+        log::warn!("{}", text);
+    }
+}
+
+/// Warnings suppressed since startup
+pub fn suppressed_count() -> u64 {
+    global().suppressed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeats_suppressed_then_counted() {
+        let throttle = LogThrottle::new(Duration::from_secs(60));
+        let t0 = Instant::now();
+        let at = |secs: u64| t0 + Duration::from_secs(secs);
+
+        assert_eq!(throttle.filter("nvidia-smi command timed out", at(0)).as_deref(), Some("nvidia-smi command timed out"));
+        assert_eq!(throttle.filter("nvidia-smi command timed out", at(5)), None);
+        assert_eq!(throttle.filter("nvidia-smi command timed out", at(10)), None);
+        // Other messages are independent
+        assert!(throttle.filter("Failed to store power reading", at(10)).is_some());
+        assert_eq!(throttle.suppressed(), 2);
+
+        assert_eq!(
+            throttle.filter("nvidia-smi command timed out", at(61)).as_deref(),
+            Some("nvidia-smi command timed out (repeated 2 times)")
+        );
+        // Counter restarts after each write
+        assert_eq!(throttle.filter("nvidia-smi command timed out", at(62)), None);
+        assert_eq!(throttle.filter("nvidia-smi command timed out", at(200)).as_deref(), Some("nvidia-smi command timed out (repeated 1 times)"));
+        assert_eq!(throttle.filter("nvidia-smi command timed out", at(300)).as_deref(), Some("nvidia-smi command timed out"));
+    }
+}
@@ -1,12 +1,32 @@
 //! Core module - Application state, configuration, and common types
 
+pub mod accuracy;
+pub mod analytics;
+pub mod app_usage;
+pub mod badge;
+pub mod clock;
 mod config;
+pub mod config_watch;
+pub mod dashboard;
 mod error;
+pub mod format;
+pub mod idle;
+pub mod insights;
+pub mod interval;
+pub mod locale;
+pub mod log_throttle;
+pub mod notifier;
+pub mod receipt;
+pub mod session_idle;
+pub mod theme;
+pub mod timezone;
 mod types;
+pub mod vram_alert;
 
-// SimplePricing is used by bin/demo.rs
+// Re-exports used by bin/cli.rs and the integration tests
 #[allow(unused_imports)]
-pub use config::{Config, PricingConfig, DashboardConfig, LayoutProfile, SimplePricing, PeakOffpeakPricing, SeasonalPricing, TempoPricing};
-pub use error::{Error, Result};
+pub use config::{AdvancedConfig, BaselineBucket, ConfigRecovery, BaselineSchedule, Config, PricingConfig, PricingMode, ProcessGrouping, SecondaryCurrency, DashboardConfig, EnergyUnit, SessionIdleInput, MIN_COST_DECIMALS, MAX_COST_DECIMALS, DashboardWidget, DisplayMode, GlobalDisplay, LayoutProfile, SessionTemplate, SimplePricing, PeakOffpeakPricing, SeasonalPricing, TempoPricing, WebhookConfig, WidgetConfig, WidgetPosition, WidgetSessionMode, MIN_PROCESS_LIST_LIMIT, MAX_PROCESS_LIST_LIMIT, MAX_DUPLICATE_READING_WINDOW_SECS, validate_category_baseline};
 #[allow(unused_imports)]
-pub use types::{PowerReading, DashboardData, AppState, SystemMetrics, CpuMetrics, GpuMetrics, MemoryMetrics, ProcessMetrics, Session, SessionCategory, BaselineDetection, CriticalMetrics, DetailedMetrics, FanMetrics, FanReading, VoltageReading};
+pub use error::{CommandError, Error, ErrorCode, Result};
+#[allow(unused_imports)]
+pub use types::{PowerReading, DashboardData, AppState, SystemMetrics, CpuMetrics, GpuMetrics, MemoryMetrics, ProcessMetrics, ProcessTotals, OTHER_PROCESSES_NAME, Session, SessionCategory, BaselineDetection, BaselineReport, BillingSummary, BucketBaseline, MonitoringHealth, SourceCapabilities, CriticalMetrics, MetricsDisplay, MetricsFormatter, DetailedMetrics, DetailedPayload, MAX_PAYLOAD_CORES, Freshness, FanMetrics, FanReading, VoltageReading, HardwareGpu, HardwareInventory, ExchangeRate, RateSource};
@@ -0,0 +1,253 @@
+//! User-facing notifications behind one policy
+//!
+//! Alerting code calls `NotificationCenter::notify` with a kind and already
+//! localized texts; the center drops muted kinds and anything during quiet
+//! hours, then hands the rest to a `Notifier` backend. The app uses a Tauri
+//! backend, tests a `RecordingNotifier`.
+
+use crate::core::clock::parse_hhmm;
+use chrono::{NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// What a notification is about, each kind can be muted on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// A session used 80% or all of its cost/energy target
+    SessionTarget,
+    /// A session was ended after inactivity or its planned duration
+    SessionAutoEnded,
+    /// GPU memory stayed above the configured usage
+    VramAlert,
+}
+
+impl NotificationKind {
+    /// Translation key of the kind's name in settings
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Self::SessionTarget => "notification.kind.session_target",
+            Self::SessionAutoEnded => "notification.kind.session_auto_ended",
+            Self::VramAlert => "notification.kind.vram_alert",
+        }
+    }
+}
+
+impl std::str::FromStr for NotificationKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(s.to_string())).map_err(|_| format!("Unknown notification kind '{}'", s))
+    }
+}
+
+/// Notification settings, part of `GeneralConfig`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NotificationConfig {
+    /// Kinds turned on or off, missing kinds are on
+    #[serde(default)]
+    pub enabled: BTreeMap<NotificationKind, bool>,
+    /// Local "HH:MM" time quiet hours start at, None = no quiet hours
+    #[serde(default)]
+    pub quiet_hours_start: Option<String>,
+    /// Local "HH:MM" time quiet hours end at (may be on the next day)
+    #[serde(default)]
+    pub quiet_hours_end: Option<String>,
+}
+
+impl NotificationConfig {
+    pub fn is_enabled(&self, kind: NotificationKind) -> bool {
+        self.enabled.get(&kind).copied().unwrap_or(true)
+    }
+
+    /// Check the quiet hours, both set or both unset
+    pub fn validate(&self) -> Result<(), String> {
+        match (&self.quiet_hours_start, &self.quiet_hours_end) {
+            (None, None) => Ok(()),
+            (Some(start), Some(end)) => {
+                for time in [start, end] {
+                    parse_hhmm(time).ok_or_else(|| format!("Invalid quiet hours time '{}', expected HH:MM", time))?;
+                }
+                Ok(())
+            }
+            _ => Err("Quiet hours need both a start and an end".to_string()),
+        }
+    }
+
+    /// Whether `now` falls in the quiet hours, which may span midnight
+    pub fn is_quiet_at(&self, now: NaiveTime) -> bool {
+        let (Some(start), Some(end)) = (
+            self.quiet_hours_start.as_deref().and_then(parse_hhmm),
+            self.quiet_hours_end.as_deref().and_then(parse_hhmm),
+        ) else {
+            return false;
+        };
+        let now = now.hour() * 60 + now.minute();
+        if start <= end {
+            now >= start && now < end
+        } else {
+            now >= start || now < end
+        }
+    }
+}
+
+/// A notification ready to show
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+}
+
+/// Something that can show a notification to the user
+pub trait Notifier: Send + Sync {
+    fn show(&self, notification: &Notification);
+}
+
+/// What `notify` did with a notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Delivery {
+    Shown,
+    /// The kind is turned off
+    Muted,
+    QuietHours,
+}
+
+/// Applies the notification settings before handing notifications to the backend
+pub struct NotificationCenter {
+    backend: Box<dyn Notifier>,
+    config: NotificationConfig,
+}
+
+impl NotificationCenter {
+    pub fn new(backend: Box<dyn Notifier>, config: NotificationConfig) -> Self {
+        Self { backend, config }
+    }
+
+    pub fn set_config(&mut self, config: NotificationConfig) {
+        self.config = config;
+    }
+
+    /// Show a notification unless its kind is muted or quiet hours are on
+    pub fn notify(&self, kind: NotificationKind, title: &str, body: &str) -> Delivery {
+        self.notify_at(kind, title, body, chrono::Local::now().time())
+    }
+
+    /// Same as `notify`, with the local time given
+    pub fn notify_at(&self, kind: NotificationKind, title: &str, body: &str, now: NaiveTime) -> Delivery {
+        let delivery = if !self.config.is_enabled(kind) {
+            Delivery::Muted
+        } else if self.config.is_quiet_at(now) {
+            Delivery::QuietHours
+        } else {
+            self.show(kind, title, body);
+            Delivery::Shown
+        };
+        if delivery != Delivery::Shown {
+            log::debug!("Notification {:?} not shown: {:?}", kind, delivery);
+        }
+        delivery
+    }
+
+    /// Show a notification whatever the settings (test notifications)
+    pub fn show(&self, kind: NotificationKind, title: &str, body: &str) {
+        self.backend.show(&Notification { kind, title: title.to_string(), body: body.to_string() });
+    }
+}
+
+/// Backend keeping what it was asked to show, for tests
+#[cfg(test)]
+#[derive(Default)]
+pub struct RecordingNotifier {
+    shown: std::sync::Arc<std::sync::Mutex<Vec<Notification>>>,
+}
+
+#[cfg(test)]
+impl RecordingNotifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle on the shown notifications, still readable once the notifier is boxed
+    pub fn shown(&self) -> std::sync::Arc<std::sync::Mutex<Vec<Notification>>> {
+        self.shown.clone()
+    }
+}
+
+#[cfg(test)]
+impl Notifier for RecordingNotifier {
+    fn show(&self, notification: &Notification) {
+        self.shown.lock().unwrap().push(notification.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn at(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    fn center(config: NotificationConfig) -> (NotificationCenter, Arc<Mutex<Vec<Notification>>>) {
+        let backend = RecordingNotifier::new();
+        let shown = backend.shown();
+        (NotificationCenter::new(Box::new(backend), config), shown)
+    }
+
+    #[test]
+    fn test_muted_kinds() {
+        let config = NotificationConfig {
+            enabled: BTreeMap::from([(NotificationKind::VramAlert, false), (NotificationKind::SessionTarget, true)]),
+            ..Default::default()
+        };
+        let (center, shown) = center(config);
+        assert_eq!(center.notify_at(NotificationKind::VramAlert, "VRAM", "95%", at(12, 0)), Delivery::Muted);
+        assert_eq!(center.notify_at(NotificationKind::SessionTarget, "Target", "80%", at(12, 0)), Delivery::Shown);
+        assert_eq!(center.notify_at(NotificationKind::SessionAutoEnded, "Ended", "", at(12, 0)), Delivery::Shown);
+
+        let shown = shown.lock().unwrap();
+        assert_eq!(shown.len(), 2);
+        assert_eq!((shown[0].kind, shown[0].body.as_str()), (NotificationKind::SessionTarget, "80%"));
+    }
+
+    #[test]
+    fn test_quiet_hours_over_midnight() {
+        let config = NotificationConfig {
+            quiet_hours_start: Some("22:30".into()),
+            quiet_hours_end: Some("07:00".into()),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+        assert!(config.is_quiet_at(at(23, 0)));
+        assert!(config.is_quiet_at(at(6, 59)));
+        assert!(!config.is_quiet_at(at(7, 0)));
+        assert!(!config.is_quiet_at(at(22, 29)));
+
+        let (mut center, shown) = center(config);
+        assert_eq!(center.notify_at(NotificationKind::VramAlert, "VRAM", "", at(2, 0)), Delivery::QuietHours);
+        // Test notifications ignore the settings
+        center.show(NotificationKind::VramAlert, "Test", "");
+        center.set_config(NotificationConfig::default());
+        assert_eq!(center.notify_at(NotificationKind::VramAlert, "VRAM", "", at(2, 0)), Delivery::Shown);
+        assert_eq!(shown.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_validate_and_parse() {
+        let same_day = NotificationConfig { quiet_hours_start: Some("12:00".into()), quiet_hours_end: Some("14:00".into()), ..Default::default() };
+        assert!(same_day.is_quiet_at(at(13, 0)) && !same_day.is_quiet_at(at(14, 0)));
+
+        let half = NotificationConfig { quiet_hours_start: Some("22:00".into()), ..Default::default() };
+        assert!(half.validate().is_err());
+        let invalid = NotificationConfig { quiet_hours_start: Some("24:00".into()), quiet_hours_end: Some("07:00".into()), ..Default::default() };
+        assert!(invalid.validate().is_err());
+
+        assert_eq!("vram_alert".parse::<NotificationKind>(), Ok(NotificationKind::VramAlert));
+        assert!("power_spike".parse::<NotificationKind>().is_err());
+        let json = serde_json::to_string(&NotificationConfig { enabled: BTreeMap::from([(NotificationKind::SessionTarget, false)]), ..Default::default() }).unwrap();
+        assert!(json.contains("\"session_target\":false"));
+    }
+}
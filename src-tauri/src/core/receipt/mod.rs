@@ -0,0 +1,397 @@
+//! Shareable PNG summary card of a session
+//!
+//! Drawn without a graphics stack: filled rectangles and the embedded bitmap
+//! font of `font`, scaled up by whole pixels, then encoded with the png crate.
+//! The font covers printable ASCII, the euro and degree signs and accented
+//! Latin letters; other characters (the category emoji among them) are skipped.
+//! A currency symbol the font lacks (£, ¥, ₹...) is replaced by the ISO code.
+//!
+//! `layout` places every text without drawing anything, so the card can be
+//! checked through its dimensions and text positions rather than pixels.
+
+pub mod font;
+
+use crate::core::format::{format_cost, format_date, format_duration, format_number, is_french};
+use crate::core::{Error, Result, Session};
+use crate::i18n::I18n;
+use std::path::Path;
+
+/// Outer margin of the card
+const PADDING: u32 = 32;
+/// Height of the accent band at the top
+const ACCENT_HEIGHT: u32 = 8;
+const MIN_WIDTH: u32 = 480;
+/// Longer labels are cut with an ellipsis
+const MAX_TITLE_CHARS: usize = 32;
+
+const TITLE_SCALE: u32 = 4;
+const SMALL_SCALE: u32 = 2;
+const ROW_SCALE: u32 = 3;
+const ROW_GAP: u32 = 10;
+/// Space kept between a row's label and its value
+const COLUMN_GAP: u32 = 48;
+
+/// Color variant of the card
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+impl Theme {
+    /// Variant matching the `general.theme` setting
+    ///
+    /// "system" is resolved by the webview, the backend falls back to dark (the default theme).
+    pub fn from_setting(theme: &str) -> Self {
+        if theme == "light" { Self::Light } else { Self::Dark }
+    }
+
+    fn palette(self) -> Palette {
+        match self {
+            Self::Light => Palette {
+                background: [0xF5, 0xF6, 0xF8],
+                text: [0x1A, 0x1D, 0x23],
+                muted: [0x6B, 0x72, 0x80],
+                accent: [0x22, 0xC5, 0x5E],
+                divider: [0xD9, 0xDC, 0xE1],
+            },
+            Self::Dark => Palette {
+                background: [0x16, 0x18, 0x1D],
+                text: [0xF1, 0xF3, 0xF5],
+                muted: [0x9A, 0xA1, 0xAD],
+                accent: [0x22, 0xC5, 0x5E],
+                divider: [0x2E, 0x32, 0x3A],
+            },
+        }
+    }
+}
+
+struct Palette {
+    background: [u8; 3],
+    text: [u8; 3],
+    muted: [u8; 3],
+    accent: [u8; 3],
+    divider: [u8; 3],
+}
+
+/// Texts of the card, already localized
+#[derive(Debug, Clone, PartialEq)]
+pub struct Receipt {
+    pub title: String,
+    /// Category and start date
+    pub subtitle: String,
+    /// Label and value of each figure
+    pub rows: Vec<(String, String)>,
+    pub footer: String,
+}
+
+impl Receipt {
+    /// Summary of `session`, `category_emoji` being the emoji of its category if it has one
+    ///
+    /// Costs show `currency_symbol`, or the `currency` code when the font cannot draw it.
+    pub fn for_session(session: &Session, category_emoji: Option<&str>, i18n: &I18n, currency_symbol: &str, currency: &str) -> Self {
+        let lang = i18n.current_language();
+        let currency_symbol = drawable_currency(currency_symbol, currency, lang);
+        let kwh = |wh: f64| format!("{} kWh", format_number(wh / 1000.0, 3, lang));
+
+        let title = session.label.as_deref().filter(|l| !l.trim().is_empty()).map(str::to_string).unwrap_or_else(|| i18n.get("receipt.untitled"));
+        let category = match (category_emoji, session.category.as_deref()) {
+            (Some(emoji), Some(name)) => format!("{} {}", emoji, name),
+            (None, Some(name)) => name.to_string(),
+            _ => i18n.get("session.no_category"),
+        };
+        let avg_watts = session.avg_watts.map(|w| format!("{} W", format_number(w, 0, lang))).unwrap_or_else(|| "-".to_string());
+
+        Self {
+            title,
+            subtitle: format!("{} - {}", category, format_date(session.start_time, "datetime", lang)),
+            rows: vec![
+                (i18n.get("receipt.duration"), format_duration(session.duration_secs, lang)),
+                (i18n.get("receipt.total_energy"), kwh(session.total_wh)),
+                (i18n.get("receipt.surplus_energy"), kwh(session.surplus_wh)),
+                (i18n.get("receipt.surplus_cost"), format_cost(session.surplus_cost, &currency_symbol, lang)),
+                (i18n.get("session.avg_watts"), avg_watts),
+            ],
+            footer: "PowerCost Tracker".to_string(),
+        }
+    }
+}
+
+/// `symbol` when the font draws all of it, else the ISO code, spaced from the amount
+fn drawable_currency(symbol: &str, currency: &str, lang: &str) -> String {
+    if !symbol.trim().is_empty() && symbol.chars().all(|c| font::glyph(c).is_some()) {
+        symbol.to_string()
+    } else if is_french(lang) {
+        currency.to_string()
+    } else {
+        format!("{} ", currency)
+    }
+}
+
+/// What a text is, deciding its color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Title,
+    Subtitle,
+    Label,
+    Value,
+    Footer,
+}
+
+/// A text placed on the card, `(x, y)` being its top left corner
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextItem {
+    pub text: String,
+    pub role: Role,
+    pub x: u32,
+    pub y: u32,
+    pub scale: u32,
+}
+
+/// Card dimensions and text positions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    pub width: u32,
+    pub height: u32,
+    /// Top of the line separating the header from the figures
+    pub divider_y: u32,
+    pub texts: Vec<TextItem>,
+}
+
+/// Place the texts of `receipt`, the width growing with the longest line
+///
+/// Characters the font cannot draw are dropped here, so positions match what is drawn.
+pub fn layout(receipt: &Receipt) -> Layout {
+    let title = truncate(&font::drawable(&receipt.title), MAX_TITLE_CHARS);
+    let subtitle = font::drawable(&receipt.subtitle);
+    let footer = font::drawable(&receipt.footer);
+    let rows: Vec<(String, String)> = receipt.rows.iter().map(|(label, value)| (font::drawable(label), font::drawable(value))).collect();
+    let row_width = rows
+        .iter()
+        .map(|(label, value)| font::text_width(label, ROW_SCALE) + COLUMN_GAP + font::text_width(value, ROW_SCALE))
+        .max()
+        .unwrap_or(0);
+    let content_width = [
+        font::text_width(&title, TITLE_SCALE),
+        font::text_width(&subtitle, SMALL_SCALE),
+        font::text_width(&footer, SMALL_SCALE),
+        row_width,
+    ]
+    .into_iter()
+    .max()
+    .unwrap_or(0);
+    let width = (content_width + 2 * PADDING).max(MIN_WIDTH);
+    let right = width - PADDING;
+
+    let mut texts = Vec::new();
+    let mut y = ACCENT_HEIGHT + PADDING;
+    texts.push(TextItem { text: title, role: Role::Title, x: PADDING, y, scale: TITLE_SCALE });
+    y += font::GLYPH_HEIGHT * TITLE_SCALE + 8;
+    texts.push(TextItem { text: subtitle, role: Role::Subtitle, x: PADDING, y, scale: SMALL_SCALE });
+    y += font::GLYPH_HEIGHT * SMALL_SCALE + 20;
+    let divider_y = y;
+    y += 2 + 20;
+
+    for (label, value) in rows {
+        let value_x = right - font::text_width(&value, ROW_SCALE);
+        texts.push(TextItem { text: label, role: Role::Label, x: PADDING, y, scale: ROW_SCALE });
+        texts.push(TextItem { text: value, role: Role::Value, x: value_x, y, scale: ROW_SCALE });
+        y += font::GLYPH_HEIGHT * ROW_SCALE + ROW_GAP;
+    }
+
+    y += 10;
+    texts.push(TextItem { text: footer, role: Role::Footer, x: PADDING, y, scale: SMALL_SCALE });
+    y += font::GLYPH_HEIGHT * SMALL_SCALE + PADDING;
+
+    Layout { width, height: y, divider_y, texts }
+}
+
+/// RGB pixels, row by row
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub(crate) fn new(width: u32, height: u32, color: [u8; 3]) -> Self {
+        Self { width, height, pixels: color.repeat((width * height) as usize) }
+    }
+
+    /// Fill a rectangle, clipped to the image
+    pub(crate) fn fill(&mut self, x: u32, y: u32, w: u32, h: u32, color: [u8; 3]) {
+        for row in y..(y + h).min(self.height) {
+            for col in x..(x + w).min(self.width) {
+                let i = ((row * self.width + col) * 3) as usize;
+                self.pixels[i..i + 3].copy_from_slice(&color);
+            }
+        }
+    }
+
+    pub(crate) fn draw_text(&mut self, item: &TextItem, color: [u8; 3]) {
+        let mut x = item.x;
+        for rows in item.text.chars().filter_map(font::glyph) {
+            for (dy, bits) in rows.iter().enumerate() {
+                for dx in 0..font::GLYPH_WIDTH {
+                    if bits & (1 << (font::GLYPH_WIDTH - 1 - dx)) != 0 {
+                        self.fill(x + dx * item.scale, item.y + dy as u32 * item.scale, item.scale, item.scale, color);
+                    }
+                }
+            }
+            x += font::ADVANCE * item.scale;
+        }
+    }
+
+    /// Pixels with an opaque alpha channel, as icons take them
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.pixels.chunks(3).flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 0xFF]).collect()
+    }
+}
+
+/// Draw the card
+pub fn render(receipt: &Receipt, theme: Theme) -> Image {
+    let layout = layout(receipt);
+    let palette = theme.palette();
+    let mut image = Image::new(layout.width, layout.height, palette.background);
+
+    image.fill(0, 0, layout.width, ACCENT_HEIGHT, palette.accent);
+    image.fill(PADDING, layout.divider_y, layout.width - 2 * PADDING, 2, palette.divider);
+    for item in &layout.texts {
+        let color = match item.role {
+            Role::Title | Role::Value => palette.text,
+            Role::Subtitle | Role::Label => palette.muted,
+            Role::Footer => palette.accent,
+        };
+        image.draw_text(item, color);
+    }
+    image
+}
+
+/// Encode an image as PNG
+pub fn encode_png(image: &Image) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, image.width, image.height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| Error::Serialization(e.to_string()))?;
+    writer.write_image_data(&image.pixels).map_err(|e| Error::Serialization(e.to_string()))?;
+    writer.finish().map_err(|e| Error::Serialization(e.to_string()))?;
+    Ok(out)
+}
+
+/// Draw the card and write it to `path` as PNG
+pub fn write_png(receipt: &Receipt, theme: Theme, path: &Path) -> Result<()> {
+    let png = encode_png(&render(receipt, theme))?;
+    std::fs::write(path, png)?;
+    Ok(())
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max_chars - 3).collect();
+    format!("{}...", cut.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn width(item: &TextItem) -> u32 {
+        font::text_width(&item.text, item.scale)
+    }
+
+    fn session() -> Session {
+        let mut session = Session::new(80.0, Some("Blender render".to_string()));
+        session.start_time = 1_700_000_000;
+        session.end_time = Some(1_700_000_000 + 2 * 3600 + 300);
+        session.total_wh = 640.0;
+        session.surplus_wh = 480.0;
+        session.surplus_cost = 0.12;
+        session.category = Some("Work".to_string());
+        session.compute_rates(1_700_010_000);
+        session
+    }
+
+    #[test]
+    fn test_receipt_texts() {
+        let receipt = Receipt::for_session(&session(), Some("\u{1F4BC}"), &I18n::new("en"), "\u{20AC}", "EUR");
+        assert_eq!(receipt.title, "Blender render");
+        assert!(receipt.subtitle.starts_with("\u{1F4BC} Work - "));
+        let values: Vec<&str> = receipt.rows.iter().map(|(_, v)| v.as_str()).collect();
+        assert_eq!(values, ["2h 05m", "0.640 kWh", "0.480 kWh", "\u{20AC}0.12", "307 W"]);
+
+        // No glyph for the pound sign
+        let pounds = Receipt::for_session(&session(), None, &I18n::new("en"), "\u{00A3}", "GBP");
+        assert_eq!(pounds.rows[3].1, "GBP 0.12");
+    }
+
+    #[test]
+    fn test_layout_metrics() {
+        let receipt = Receipt::for_session(&session(), Some("\u{1F4BC}"), &I18n::new("en"), "\u{20AC}", "EUR");
+        let layout = layout(&receipt);
+        assert_eq!(layout.texts.len(), 2 + 2 * receipt.rows.len() + 1);
+        assert!(layout.width >= MIN_WIDTH);
+        // The emoji is not in the font
+        assert!(layout.texts[1].text.starts_with("Work - "));
+
+        // Labels on the left margin, values right-aligned on the right one
+        for pair in layout.texts[2..12].chunks(2) {
+            assert_eq!((pair[0].role, pair[0].x), (Role::Label, PADDING));
+            assert_eq!(pair[1].x + width(&pair[1]), layout.width - PADDING);
+            assert_eq!(pair[0].y, pair[1].y);
+        }
+        // Nothing overflows the card, lines never overlap
+        for pair in layout.texts.windows(2) {
+            assert!(pair[1].y >= pair[0].y);
+        }
+        let last = layout.texts.last().unwrap();
+        assert_eq!(last.y + font::GLYPH_HEIGHT * last.scale + PADDING, layout.height);
+        assert!(layout.texts.iter().all(|t| t.x + width(t) <= layout.width - PADDING));
+
+        let image = render(&receipt, Theme::Dark);
+        assert_eq!((image.width, image.height), (layout.width, layout.height));
+        assert_eq!(image.pixels.len(), (layout.width * layout.height * 3) as usize);
+    }
+
+    #[test]
+    fn test_long_title_widens_then_truncates() {
+        let mut long = session();
+        long.label = Some("Final render of the whole city scene at 4K with denoising".to_string());
+        let receipt = Receipt::for_session(&long, None, &I18n::new("en"), "$", "USD");
+        let layout = layout(&receipt);
+        assert_eq!(layout.texts[0].text.chars().count(), MAX_TITLE_CHARS);
+        assert!(layout.texts[0].text.ends_with("..."));
+        assert_eq!(layout.width, width(&layout.texts[0]) + 2 * PADDING);
+    }
+
+    #[test]
+    fn test_png_and_themes() {
+        let receipt = Receipt::for_session(&session(), None, &I18n::new("fr"), "\u{20AC}", "EUR");
+        let (light, dark) = (render(&receipt, Theme::Light), render(&receipt, Theme::Dark));
+        let below_accent = (light.width * ACCENT_HEIGHT * 3) as usize;
+        assert_eq!(light.pixels[below_accent..below_accent + 3], [0xF5, 0xF6, 0xF8]);
+        assert_eq!(dark.pixels[below_accent..below_accent + 3], [0x16, 0x18, 0x1D]);
+
+        let png = encode_png(&light).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()), light.width);
+        assert_eq!(u32::from_be_bytes(png[20..24].try_into().unwrap()), light.height);
+
+        assert_eq!(Theme::from_setting("light"), Theme::Light);
+        assert_eq!(Theme::from_setting("system"), Theme::Dark);
+    }
+
+    #[test]
+    fn test_font_coverage() {
+        assert_eq!(font::glyph('\u{00E9}'), font::glyph('e'));
+        assert!(font::glyph('\u{20AC}').is_some());
+        assert!(font::glyph('\u{1F3AE}').is_none());
+        assert_eq!(font::text_width("ab", 1), 11);
+        assert_eq!(font::text_width("\u{1F3AE} ab", 2), font::text_width(" ab", 2));
+        assert_eq!(font::drawable("\u{1F3AE} Gaming"), "Gaming");
+    }
+}
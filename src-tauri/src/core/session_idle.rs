@@ -0,0 +1,165 @@
+//! Inactivity detection for tracking sessions
+//!
+//! A session is idle while its surplus power stays within a few watts of the
+//! baseline. Once idle for long enough it gets ended, with the end time moved
+//! back to when the inactivity began. Where input idle time can be read, it
+//! is combined with the power test as `advanced.session_idle_input` says.
+
+use crate::core::SessionIdleInput;
+
+/// Surplus power (W) below which a session counts as idle
+pub const IDLE_TOLERANCE_WATTS: f64 = 5.0;
+
+/// Seconds without keyboard or mouse input after which the user counts as away
+pub const INPUT_IDLE_SECS: u64 = 60;
+
+/// Whether a sample is idle, `input_idle_secs` being None where input can't be read
+pub fn is_idle_sample(surplus_watts: f64, input_idle_secs: Option<u64>, combine: &SessionIdleInput) -> bool {
+    let power_idle = surplus_watts <= IDLE_TOLERANCE_WATTS;
+    let Some(input_idle_secs) = input_idle_secs else {
+        return power_idle;
+    };
+    let input_idle = input_idle_secs >= INPUT_IDLE_SECS;
+    match combine {
+        SessionIdleInput::And => power_idle && input_idle,
+        SessionIdleInput::Or => power_idle || input_idle,
+        SessionIdleInput::Off | SessionIdleInput::Other(_) => power_idle,
+    }
+}
+
+/// Outcome of feeding one sample to a [`SessionIdleTracker`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IdleDecision {
+    /// Surplus above tolerance, the session is in use
+    Active,
+    /// First idle sample after activity; totals should be snapshotted now
+    IdleStarted,
+    /// Still idle, not for long enough yet
+    Idle,
+    /// Idle for at least the configured duration since `idle_since`
+    Expired { idle_since: i64 },
+}
+
+/// Tracks how long a session has been idle
+#[derive(Debug, Clone, Default)]
+pub struct SessionIdleTracker {
+    idle_since: Option<i64>,
+}
+
+impl SessionIdleTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Timestamp of the first sample of the current idle stretch
+    pub fn idle_since(&self) -> Option<i64> {
+        self.idle_since
+    }
+
+    /// Move the idle start along with a wall-clock jump of `secs`
+    pub fn shift(&mut self, secs: i64) {
+        if let Some(since) = self.idle_since.as_mut() {
+            *since += secs;
+        }
+    }
+
+    /// Feed a surplus sample taken at `timestamp` (seconds)
+    pub fn update(&mut self, surplus_watts: f64, timestamp: i64, idle_after_secs: i64) -> IdleDecision {
+        self.update_sample(surplus_watts <= IDLE_TOLERANCE_WATTS, timestamp, idle_after_secs)
+    }
+
+    /// Feed a sample already found idle or not (see [`is_idle_sample`])
+    pub fn update_sample(&mut self, idle: bool, timestamp: i64, idle_after_secs: i64) -> IdleDecision {
+        if !idle {
+            self.idle_since = None;
+            return IdleDecision::Active;
+        }
+
+        match self.idle_since {
+            None => {
+                self.idle_since = Some(timestamp);
+                IdleDecision::IdleStarted
+            }
+            Some(since) if timestamp - since >= idle_after_secs => IdleDecision::Expired { idle_since: since },
+            Some(_) => IdleDecision::Idle,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed one sample per minute, return the first expiry
+    fn run(series: &[f64], idle_after_secs: i64) -> Option<(usize, i64)> {
+        let mut tracker = SessionIdleTracker::new();
+        series.iter().enumerate().find_map(|(i, &w)| {
+            match tracker.update(w, i as i64 * 60, idle_after_secs) {
+                IdleDecision::Expired { idle_since } => Some((i, idle_since)),
+                _ => None,
+            }
+        })
+    }
+
+    #[test]
+    fn test_expires_from_start_of_inactivity() {
+        // Busy for 3 minutes, then near baseline
+        let series = [120.0, 90.0, 100.0, 2.0, 1.0, 3.0, 0.0, 4.0, 1.0];
+        let (tick, idle_since) = run(&series, 5 * 60).unwrap();
+        assert_eq!(idle_since, 3 * 60);
+        assert_eq!(tick, 8);
+    }
+
+    #[test]
+    fn test_activity_resets_idle() {
+        let series = [1.0, 1.0, 1.0, 60.0, 1.0, 1.0, 1.0];
+        assert_eq!(run(&series, 4 * 60), None);
+
+        let mut tracker = SessionIdleTracker::new();
+        assert_eq!(tracker.update(1.0, 0, 60), IdleDecision::IdleStarted);
+        assert_eq!(tracker.update(30.0, 30, 60), IdleDecision::Active);
+        assert_eq!(tracker.idle_since(), None);
+        assert_eq!(tracker.update(1.0, 45, 60), IdleDecision::IdleStarted);
+        assert_eq!(tracker.update(1.0, 100, 60), IdleDecision::Idle);
+        assert_eq!(tracker.update(1.0, 105, 60), IdleDecision::Expired { idle_since: 45 });
+    }
+
+    #[test]
+    fn test_within_tolerance_counts_as_idle() {
+        let mut tracker = SessionIdleTracker::new();
+        assert_eq!(tracker.update(IDLE_TOLERANCE_WATTS, 0, 60), IdleDecision::IdleStarted);
+        assert_eq!(tracker.update(IDLE_TOLERANCE_WATTS + 0.5, 10, 60), IdleDecision::Active);
+    }
+
+    #[test]
+    fn test_input_idle_combination() {
+        let away = Some(INPUT_IDLE_SECS);
+        let present = Some(5);
+        // A background download keeps surplus above tolerance while nobody is there
+        assert!(!is_idle_sample(20.0, away, &SessionIdleInput::And));
+        assert!(is_idle_sample(20.0, away, &SessionIdleInput::Or));
+        assert!(!is_idle_sample(20.0, away, &SessionIdleInput::Off));
+        // Reading at low power with the user present
+        assert!(!is_idle_sample(1.0, present, &SessionIdleInput::And));
+        assert!(is_idle_sample(1.0, present, &SessionIdleInput::Or));
+        assert!(is_idle_sample(1.0, away, &SessionIdleInput::And));
+
+        // Input idle unknown: power alone whatever the setting
+        assert!(is_idle_sample(1.0, None, &SessionIdleInput::And));
+        assert!(!is_idle_sample(20.0, None, &SessionIdleInput::Or));
+
+        let mut tracker = SessionIdleTracker::new();
+        assert_eq!(tracker.update_sample(true, 0, 60), IdleDecision::IdleStarted);
+        assert_eq!(tracker.update_sample(true, 60, 60), IdleDecision::Expired { idle_since: 0 });
+    }
+
+    #[test]
+    fn test_shift_after_backward_clock_jump() {
+        let mut tracker = SessionIdleTracker::new();
+        assert_eq!(tracker.update(1.0, 10_000, 600), IdleDecision::IdleStarted);
+        // Clock set back an hour, 5 minutes into the idle stretch
+        tracker.shift(-3600);
+        assert_eq!(tracker.update(1.0, 10_300 - 3600, 600), IdleDecision::Idle);
+        assert_eq!(tracker.update(1.0, 10_600 - 3600, 600), IdleDecision::Expired { idle_since: 10_000 - 3600 });
+    }
+}
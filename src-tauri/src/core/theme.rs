@@ -0,0 +1,178 @@
+//! Light or dark appearance of `general.theme`
+//!
+//! "dark" and "light" are used as is, "system" follows the OS preference:
+//! - Windows: `AppsUseLightTheme` under the Personalize registry key
+//! - Linux: the freedesktop `color-scheme` setting through the desktop portal,
+//!   else GNOME's `color-scheme` and `gtk-theme` via `gsettings`
+//! - macOS: `AppleInterfaceStyle` via `defaults`
+//!
+//! An OS preference that can't be read resolves to dark, the default theme.
+
+use serde::{Deserialize, Serialize};
+
+/// Seconds between two reads of the OS preference while the theme is "system"
+pub const WATCH_INTERVAL_SECS: u64 = 10;
+
+/// Appearance the windows are styled with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Appearance {
+    Dark,
+    Light,
+}
+
+impl Appearance {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Dark => "dark",
+            Self::Light => "light",
+        }
+    }
+}
+
+/// Appearance of a theme setting, `system` being the OS preference if known
+pub fn resolve(setting: &str, system: Option<Appearance>) -> Appearance {
+    match setting {
+        "light" => Appearance::Light,
+        "dark" => Appearance::Dark,
+        _ => system.unwrap_or(Appearance::Dark),
+    }
+}
+
+/// Whether the setting follows the OS (unknown values too, they resolve like "system")
+pub fn follows_system(setting: &str) -> bool {
+    !matches!(setting, "light" | "dark")
+}
+
+/// Light or dark preference of the OS, None when it can't be read (runs commands on Linux and macOS)
+#[cfg(target_os = "windows")]
+pub fn system_appearance() -> Option<Appearance> {
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
+
+    let key: Vec<u16> = "Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize\0".encode_utf16().collect();
+    let value: Vec<u16> = "AppsUseLightTheme\0".encode_utf16().collect();
+    let mut data: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            key.as_ptr(),
+            value.as_ptr(),
+            RRF_RT_REG_DWORD,
+            std::ptr::null_mut(),
+            (&mut data as *mut u32).cast(),
+            &mut size,
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+    Some(if data == 0 { Appearance::Dark } else { Appearance::Light })
+}
+
+/// Light or dark preference of the OS, None when it can't be read (runs commands on Linux and macOS)
+#[cfg(target_os = "linux")]
+pub fn system_appearance() -> Option<Appearance> {
+    let portal = command_output(
+        "gdbus",
+        &[
+            "call",
+            "--session",
+            "--dest",
+            "org.freedesktop.portal.Desktop",
+            "--object-path",
+            "/org/freedesktop/portal/desktop",
+            "--method",
+            "org.freedesktop.portal.Settings.Read",
+            "org.freedesktop.appearance",
+            "color-scheme",
+        ],
+    );
+    if let Some(appearance) = portal.as_deref().and_then(parse_portal_color_scheme) {
+        return Some(appearance);
+    }
+
+    let scheme = command_output("gsettings", &["get", "org.gnome.desktop.interface", "color-scheme"]);
+    if let Some(appearance) = scheme.as_deref().and_then(parse_gsettings_color_scheme) {
+        return Some(appearance);
+    }
+    // Older desktops only switch the GTK theme ("Adwaita-dark")
+    let gtk_theme = command_output("gsettings", &["get", "org.gnome.desktop.interface", "gtk-theme"])?;
+    Some(if gtk_theme.to_ascii_lowercase().contains("dark") { Appearance::Dark } else { Appearance::Light })
+}
+
+/// Light or dark preference of the OS, None when it can't be read (runs commands on Linux and macOS)
+#[cfg(target_os = "macos")]
+pub fn system_appearance() -> Option<Appearance> {
+    // The key only exists in dark mode, `defaults` fails otherwise
+    let output = std::process::Command::new("defaults").args(["read", "-g", "AppleInterfaceStyle"]).output().ok()?;
+    let dark = output.status.success() && String::from_utf8_lossy(&output.stdout).trim().eq_ignore_ascii_case("dark");
+    Some(if dark { Appearance::Dark } else { Appearance::Light })
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+pub fn system_appearance() -> Option<Appearance> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Portal `color-scheme` reply ("(<<uint32 1>>,)"): 1 prefers dark, 2 light, 0 has no preference
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_portal_color_scheme(output: &str) -> Option<Appearance> {
+    let value = output.trim().trim_start_matches(['(', '<']).trim_end_matches([')', ',', '>']);
+    match value.strip_prefix("uint32 ").unwrap_or(value).trim() {
+        "1" => Some(Appearance::Dark),
+        "2" => Some(Appearance::Light),
+        _ => None,
+    }
+}
+
+/// `gsettings get org.gnome.desktop.interface color-scheme` ("'prefer-dark'"), None for 'default'
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_gsettings_color_scheme(output: &str) -> Option<Appearance> {
+    match output.trim().trim_matches('\'') {
+        "prefer-dark" => Some(Appearance::Dark),
+        "prefer-light" => Some(Appearance::Light),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explicit_theme_overrides_system() {
+        assert_eq!(resolve("dark", Some(Appearance::Light)), Appearance::Dark);
+        assert_eq!(resolve("light", Some(Appearance::Dark)), Appearance::Light);
+        assert_eq!(resolve("light", None), Appearance::Light);
+
+        assert_eq!(resolve("system", Some(Appearance::Light)), Appearance::Light);
+        assert_eq!(resolve("system", Some(Appearance::Dark)), Appearance::Dark);
+        // Unreadable preference, or a value this version doesn't know
+        assert_eq!(resolve("system", None), Appearance::Dark);
+        assert_eq!(resolve("sepia", Some(Appearance::Light)), Appearance::Light);
+
+        assert!(follows_system("system") && !follows_system("dark"));
+    }
+
+    #[test]
+    fn test_parse_linux_color_scheme() {
+        assert_eq!(parse_portal_color_scheme("(<<uint32 1>>,)\n"), Some(Appearance::Dark));
+        assert_eq!(parse_portal_color_scheme("(<uint32 2>,)\n"), Some(Appearance::Light));
+        assert_eq!(parse_portal_color_scheme("(<<uint32 0>>,)\n"), None);
+
+        assert_eq!(parse_gsettings_color_scheme("'prefer-dark'\n"), Some(Appearance::Dark));
+        assert_eq!(parse_gsettings_color_scheme("'prefer-light'\n"), Some(Appearance::Light));
+        assert_eq!(parse_gsettings_color_scheme("'default'\n"), None);
+    }
+}
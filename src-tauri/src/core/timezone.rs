@@ -0,0 +1,234 @@
+//! Time zone history is bucketed and shown in (`general.history_timezone`)
+//!
+//! Readings are stored as UTC timestamps and daily stats under system-local
+//! dates, so the preference only changes how queries group readings into
+//! days and hours: nothing stored is rewritten. Besides the system zone and
+//! UTC, a zone is a fixed offset (`UTC+13`, `UTC-03:30`, `Etc/GMT-13` whose
+//! sign is inverted) or an IANA name with its DST rules (`Europe/Paris`),
+//! looked up in the system timezone database (bundled on Windows).
+
+use chrono::{Datelike, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+
+/// Largest offset from UTC in use (Kiribati is UTC+14, Baker Island UTC-12)
+const MAX_OFFSET_SECS: i32 = 14 * 3600;
+
+/// Zone history queries use for day and hour boundaries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryZone {
+    /// The system zone, DST included (what daily stats are stored in)
+    Local,
+    Utc,
+    Fixed(FixedOffset),
+    Named(NamedZone),
+}
+
+/// IANA zone from the timezone database
+#[derive(Debug, Clone)]
+pub struct NamedZone {
+    name: String,
+    tz: jiff::tz::TimeZone,
+}
+
+impl PartialEq for NamedZone {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for NamedZone {}
+
+impl NamedZone {
+    fn lookup(name: &str) -> Option<Self> {
+        let tz = jiff::tz::TimeZone::get(name).ok()?;
+        let name = tz.iana_name().unwrap_or(name).to_string();
+        Some(Self { name, tz })
+    }
+
+    fn offset_at(&self, utc: &NaiveDateTime) -> FixedOffset {
+        let offset = jiff::Timestamp::from_second(utc.and_utc().timestamp())
+            .map(|ts| self.tz.to_offset(ts).seconds())
+            .unwrap_or(0);
+        fixed(offset)
+    }
+
+    fn offset_of_local(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+        let Ok(dt) = jiff::civil::DateTime::new(
+            local.year() as i16,
+            local.month() as i8,
+            local.day() as i8,
+            local.hour() as i8,
+            local.minute() as i8,
+            local.second() as i8,
+            0,
+        ) else {
+            return LocalResult::None;
+        };
+        match self.tz.to_ambiguous_timestamp(dt).offset() {
+            jiff::tz::AmbiguousOffset::Unambiguous { offset } => LocalResult::Single(fixed(offset.seconds())),
+            jiff::tz::AmbiguousOffset::Gap { .. } => LocalResult::None,
+            jiff::tz::AmbiguousOffset::Fold { before, after } => LocalResult::Ambiguous(fixed(before.seconds()), fixed(after.seconds())),
+        }
+    }
+}
+
+fn fixed(offset_secs: i32) -> FixedOffset {
+    FixedOffset::east_opt(offset_secs).unwrap_or_else(|| FixedOffset::east_opt(0).unwrap())
+}
+
+impl HistoryZone {
+    /// Zone of a `history_timezone` value, None when it is not one of the supported forms
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        match value.to_ascii_lowercase().as_str() {
+            "" | "local" => return Some(Self::Local),
+            "utc" | "gmt" | "etc/utc" | "etc/gmt" | "z" => return Some(Self::Utc),
+            _ => {}
+        }
+
+        let offset_secs = if let Some(rest) = strip_prefix_ignore_case(value, "Etc/GMT") {
+            // POSIX style: Etc/GMT-13 is 13 hours ahead of UTC, whole hours only
+            rest.parse::<i32>().ok().filter(|_| rest.starts_with(['+', '-'])).map(|hours| -hours * 3600)
+        } else {
+            let rest = strip_prefix_ignore_case(value, "UTC").or_else(|| strip_prefix_ignore_case(value, "GMT")).unwrap_or(value);
+            parse_offset(rest)
+        };
+        let Some(offset_secs) = offset_secs else {
+            // Offsets that don't parse are not looked up ("UTC+15" isn't a zone either)
+            let offset_like = value.starts_with(['+', '-']) || ["UTC", "GMT", "Etc/GMT"].iter().any(|p| strip_prefix_ignore_case(value, p).is_some());
+            return if offset_like { None } else { NamedZone::lookup(value).map(Self::Named) };
+        };
+        if offset_secs.abs() > MAX_OFFSET_SECS {
+            return None;
+        }
+        Some(if offset_secs == 0 { Self::Utc } else { Self::Fixed(FixedOffset::east_opt(offset_secs)?) })
+    }
+
+    /// Name echoed with query results: `local`, `UTC`, `UTC+13:00` or the IANA name
+    pub fn label(&self) -> String {
+        match self {
+            Self::Local => "local".to_string(),
+            Self::Utc => "UTC".to_string(),
+            Self::Fixed(offset) => format!("UTC{}", offset),
+            Self::Named(zone) => zone.name.clone(),
+        }
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(value: &'a str, prefix: &str) -> Option<&'a str> {
+    value
+        .get(..prefix.len())
+        .filter(|head| head.eq_ignore_ascii_case(prefix))
+        .map(|_| &value[prefix.len()..])
+}
+
+/// Seconds of `+13`, `-3:30` or `+0530`
+fn parse_offset(text: &str) -> Option<i32> {
+    let sign = match text.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits = &text[1..];
+    let (hours, minutes) = match digits.split_once(':') {
+        Some((h, m)) => (h, m),
+        None if digits.len() == 4 => digits.split_at(2),
+        None => (digits, "0"),
+    };
+    let (hours, minutes): (i32, i32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours <= 14 && minutes < 60 && !digits.is_empty()).then_some(sign * (hours * 3600 + minutes * 60))
+}
+
+impl TimeZone for HistoryZone {
+    type Offset = FixedOffset;
+
+    fn from_offset(offset: &FixedOffset) -> Self {
+        Self::Fixed(*offset)
+    }
+
+    fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+        match self {
+            Self::Local => chrono::Local.offset_from_local_date(local),
+            Self::Utc => LocalResult::Single(FixedOffset::east_opt(0).unwrap()),
+            Self::Fixed(offset) => LocalResult::Single(*offset),
+            Self::Named(zone) => zone.offset_of_local(&local.and_hms_opt(0, 0, 0).unwrap()),
+        }
+    }
+
+    fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+        match self {
+            Self::Local => chrono::Local.offset_from_local_datetime(local),
+            Self::Utc => LocalResult::Single(FixedOffset::east_opt(0).unwrap()),
+            Self::Fixed(offset) => LocalResult::Single(*offset),
+            Self::Named(zone) => zone.offset_of_local(local),
+        }
+    }
+
+    fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+        match self {
+            Self::Local => chrono::Local.offset_from_utc_date(utc),
+            Self::Utc => FixedOffset::east_opt(0).unwrap(),
+            Self::Fixed(offset) => *offset,
+            Self::Named(zone) => zone.offset_at(&utc.and_hms_opt(0, 0, 0).unwrap()),
+        }
+    }
+
+    fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+        match self {
+            Self::Local => chrono::Local.offset_from_utc_datetime(utc),
+            Self::Utc => FixedOffset::east_opt(0).unwrap(),
+            Self::Fixed(offset) => *offset,
+            Self::Named(zone) => zone.offset_at(utc),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_history_zone() {
+        let east = |secs| Some(HistoryZone::Fixed(FixedOffset::east_opt(secs).unwrap()));
+        assert_eq!(HistoryZone::parse("local"), Some(HistoryZone::Local));
+        assert_eq!(HistoryZone::parse(" UTC "), Some(HistoryZone::Utc));
+        assert_eq!(HistoryZone::parse("UTC+13"), east(13 * 3600));
+        assert_eq!(HistoryZone::parse("utc-03:30"), east(-(3 * 3600 + 1800)));
+        assert_eq!(HistoryZone::parse("+0545"), east(5 * 3600 + 2700));
+        // IANA fixed-offset names count the other way
+        assert_eq!(HistoryZone::parse("Etc/GMT-13"), east(13 * 3600));
+        assert_eq!(HistoryZone::parse("Etc/GMT+0"), Some(HistoryZone::Utc));
+
+        assert_eq!(HistoryZone::parse("Mars/Olympus_Mons"), None);
+        assert_eq!(HistoryZone::parse("UTC+15"), None);
+        assert_eq!(HistoryZone::parse("UTC+"), None);
+        assert_eq!(HistoryZone::parse("Etc/GMT13"), None);
+
+        assert_eq!(HistoryZone::parse("UTC+13").unwrap().label(), "UTC+13:00");
+        assert_eq!(HistoryZone::Local.label(), "local");
+    }
+
+    #[test]
+    fn test_history_zone_day_boundaries() {
+        // 2024-03-10 12:00 UTC is 01:00 on the 11th in UTC+13
+        let zone = HistoryZone::parse("UTC+13").unwrap();
+        let dt = zone.timestamp_opt(1_710_072_000, 0).unwrap();
+        assert_eq!(dt.date_naive(), NaiveDate::from_ymd_opt(2024, 3, 11).unwrap());
+        let day = NaiveDate::from_ymd_opt(2024, 3, 11).unwrap();
+        assert_eq!(crate::core::clock::local_day_start(day, &zone), 1_710_068_400);
+        assert_eq!(crate::core::clock::local_day_start(day, &HistoryZone::Utc), 1_710_115_200);
+    }
+
+    #[test]
+    fn test_named_zone_follows_dst() {
+        let zone = HistoryZone::parse("Europe/Paris").unwrap();
+        assert_eq!(zone.label(), "Europe/Paris");
+        // UTC+1 in winter, UTC+2 in summer
+        assert_eq!(crate::core::clock::local_day_start(NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(), &zone), 1_705_273_200);
+        assert_eq!(crate::core::clock::local_day_start(NaiveDate::from_ymd_opt(2024, 7, 15).unwrap(), &zone), 1_720_994_400);
+        // 02:30 on the spring-forward day does not exist, 02:30 on the fall-back day happens twice
+        let gap = NaiveDate::from_ymd_opt(2024, 3, 31).unwrap().and_hms_opt(2, 30, 0).unwrap();
+        assert_eq!(zone.from_local_datetime(&gap), LocalResult::None);
+        let fold = NaiveDate::from_ymd_opt(2024, 10, 27).unwrap().and_hms_opt(2, 30, 0).unwrap();
+        assert!(matches!(zone.from_local_datetime(&fold), LocalResult::Ambiguous(_, _)));
+    }
+}
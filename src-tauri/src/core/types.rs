@@ -1,7 +1,12 @@
 //! Common types used across the application
 
+use crate::core::config::ConfigRecovery;
+use crate::core::config_watch::DisplaySettings;
+use crate::core::format::{format_cost_with, DisplayedEnergy};
+use crate::i18n::I18n;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Instant;
 
 /// A single power reading from the hardware
@@ -165,6 +170,25 @@ pub struct GpuMetrics {
     pub fan_speed_percent: Option<u64>,
 }
 
+/// A GPU of the hardware inventory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HardwareGpu {
+    pub name: String,
+    pub driver_version: Option<String>,
+    pub vram_total_mb: Option<u64>,
+}
+
+/// Hardware and OS description, captured once at startup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HardwareInventory {
+    pub gpus: Vec<HardwareGpu>,
+    pub cpu_model: String,
+    pub physical_cores: Option<usize>,
+    pub logical_cores: usize,
+    pub total_ram_mb: u64,
+    pub os_version: Option<String>,
+}
+
 /// Memory (RAM) metrics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryMetrics {
@@ -203,6 +227,69 @@ pub struct ProcessMetrics {
     pub gpu_percent: Option<f64>,
     #[serde(default)]
     pub is_pinned: bool,
+    /// Matches `advanced.ignored_processes` (only set in the full process list)
+    #[serde(default)]
+    pub is_ignored: bool,
+    /// Synthetic row summing the processes left out of the top list
+    #[serde(default)]
+    pub is_aggregate: bool,
+    /// Container the process runs in (Linux), see `hardware::cgroup`
+    ///
+    /// A row grouping processes by name only has one when all of them share it.
+    #[serde(default)]
+    pub container: Option<String>,
+}
+
+/// Name of the synthetic row of processes left out of the top list
+pub const OTHER_PROCESSES_NAME: &str = "__other__";
+
+impl ProcessMetrics {
+    /// Fold the container of another process of this row, kept only while they all agree
+    pub fn merge_container(&mut self, container: Option<String>) {
+        if self.container != container {
+            self.container = None;
+        }
+    }
+
+    /// Row summing `excluded`, None when nothing was left out
+    pub fn other(excluded: &[ProcessMetrics]) -> Option<ProcessMetrics> {
+        if excluded.is_empty() {
+            return None;
+        }
+        let gpu: Vec<f64> = excluded.iter().filter_map(|p| p.gpu_percent).collect();
+        Some(ProcessMetrics {
+            pid: 0,
+            name: OTHER_PROCESSES_NAME.to_string(),
+            cpu_percent: excluded.iter().map(|p| p.cpu_percent).sum(),
+            memory_bytes: excluded.iter().map(|p| p.memory_bytes).sum(),
+            memory_percent: excluded.iter().map(|p| p.memory_percent).sum::<f64>().min(100.0),
+            gpu_percent: (!gpu.is_empty()).then(|| gpu.iter().sum::<f64>().min(100.0)),
+            is_pinned: false,
+            is_ignored: false,
+            is_aggregate: true,
+            container: None,
+        })
+    }
+}
+
+/// Sums over the top process list, "Other" row included unless stated
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProcessTotals {
+    /// CPU of all listed processes (100 = one logical core on Linux/macOS)
+    pub cpu_percent: f64,
+    pub memory_bytes: u64,
+    /// Memory of the named processes only
+    pub listed_memory_bytes: u64,
+}
+
+impl ProcessTotals {
+    pub fn of(processes: &[ProcessMetrics]) -> Self {
+        Self {
+            cpu_percent: processes.iter().map(|p| p.cpu_percent).sum(),
+            memory_bytes: processes.iter().map(|p| p.memory_bytes).sum(),
+            listed_memory_bytes: processes.iter().filter(|p| !p.is_aggregate).map(|p| p.memory_bytes).sum(),
+        }
+    }
 }
 
 /// Session category for organizing tracking sessions
@@ -210,6 +297,9 @@ pub struct ProcessMetrics {
 pub struct SessionCategory {
     pub emoji: String,
     pub name: String,
+    /// Baseline of sessions started in this category, instead of the global one
+    #[serde(default)]
+    pub baseline_watts: Option<f64>,
 }
 
 /// Tracking session for baseline/surplus calculation
@@ -225,6 +315,46 @@ pub struct Session {
     pub label: Option<String>,
     #[serde(default)]
     pub category: Option<String>,
+    /// Target surplus cost for the session
+    #[serde(default)]
+    pub target_cost: Option<f64>,
+    /// Target surplus energy for the session in Wh
+    #[serde(default)]
+    pub target_wh: Option<f64>,
+    /// Percent of the target consumed (highest of cost/energy when both are set)
+    #[serde(default)]
+    pub target_progress_percent: Option<f64>,
+    /// Whether the session stayed within its target (set when the session ends)
+    #[serde(default)]
+    pub target_met: Option<bool>,
+    /// Whether the session was ended automatically after inactivity
+    #[serde(default)]
+    pub auto_ended: bool,
+    /// Session this one continues after a split at midnight
+    #[serde(default)]
+    pub continued_from: Option<i64>,
+    /// Baseline schedule bucket in effect when the session started
+    #[serde(default)]
+    pub baseline_bucket: Option<String>,
+    /// Seconds from start to end, or to now while the session runs
+    #[serde(default)]
+    pub duration_secs: i64,
+    /// Average power over the session, None for sessions shorter than a minute
+    #[serde(default)]
+    pub avg_watts: Option<f64>,
+    #[serde(default)]
+    pub avg_surplus_watts: Option<f64>,
+    /// Surplus cost per hour of session
+    #[serde(default)]
+    pub cost_per_hour: Option<f64>,
+}
+
+/// Sessions shorter than this get no per-hour figures (too noisy to compare)
+pub const MIN_RATE_SECS: i64 = 60;
+
+/// `value` accumulated over `duration_secs`, per hour
+pub fn per_hour(value: f64, duration_secs: i64) -> Option<f64> {
+    (duration_secs >= MIN_RATE_SECS).then(|| value / (duration_secs as f64 / 3600.0))
 }
 
 impl Session {
@@ -239,6 +369,35 @@ impl Session {
             surplus_cost: 0.0,
             label,
             category: None,
+            target_cost: None,
+            target_wh: None,
+            target_progress_percent: None,
+            target_met: None,
+            auto_ended: false,
+            continued_from: None,
+            baseline_bucket: None,
+            duration_secs: 0,
+            avg_watts: None,
+            avg_surplus_watts: None,
+            cost_per_hour: None,
+        }
+    }
+
+    /// Fill the duration and per-hour averages, measuring open sessions up to `now`
+    pub fn compute_rates(&mut self, now: i64) {
+        self.duration_secs = (self.end_time.unwrap_or(now) - self.start_time).max(0);
+        self.avg_watts = per_hour(self.total_wh, self.duration_secs);
+        self.avg_surplus_watts = per_hour(self.surplus_wh, self.duration_secs);
+        self.cost_per_hour = per_hour(self.surplus_cost, self.duration_secs);
+    }
+
+    /// Percent of the target consumed, None if the session has no target
+    pub fn compute_target_progress(&self) -> Option<f64> {
+        let cost = self.target_cost.map(|t| self.surplus_cost / t * 100.0);
+        let energy = self.target_wh.map(|t| self.surplus_wh / t * 100.0);
+        match (cost, energy) {
+            (Some(c), Some(e)) => Some(c.max(e)),
+            (c, e) => c.or(e),
         }
     }
 }
@@ -251,6 +410,135 @@ pub struct BaselineDetection {
     pub confidence: f64,
 }
 
+/// Baseline of one time-of-day bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BucketBaseline {
+    pub name: String,
+    /// Local "HH:MM" start
+    pub start: String,
+    pub manual_watts: Option<f64>,
+    /// None when the bucket has too few samples
+    pub detection: Option<BaselineDetection>,
+    /// The bucket in effect now
+    pub active: bool,
+}
+
+/// Result of `detect_baseline`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineReport {
+    /// Detection of the bucket in effect now (the whole day without a schedule)
+    pub current: Option<BaselineDetection>,
+    /// Every bucket, empty without a schedule
+    pub buckets: Vec<BucketBaseline>,
+}
+
+/// What the active power source can report, probed once per monitor
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SourceCapabilities {
+    /// `std::env::consts::OS`, selects the hints shown for what is missing
+    pub platform: String,
+    /// Power is measured, not estimated
+    pub real_power: bool,
+    pub gpu_metrics: bool,
+    /// GPU usage per process
+    pub gpu_per_process: bool,
+    pub cpu_temperature: bool,
+    pub per_core_temperature: bool,
+    pub fans: bool,
+    pub voltages: bool,
+    /// Memory speed or type
+    pub memory_info: bool,
+    pub battery: bool,
+    /// Time since the last keyboard or mouse input, for session idle detection
+    #[serde(default)]
+    pub input_idle: bool,
+}
+
+/// Monitoring health counters (reading filter, event emission, webhook delivery, NVML recovery, log suppression, single instance)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MonitoringHealth {
+    /// Readings that passed the sanity filter
+    pub accepted_samples: u64,
+    /// Readings rejected as implausible (out of range or spikes)
+    pub rejected_samples: u64,
+    pub last_rejected_watts: Option<f64>,
+    /// Unix timestamp of the last rejected reading
+    pub last_rejected_at: Option<i64>,
+    /// Update events are currently skipped because no window is visible
+    #[serde(default)]
+    pub emit_suppressed: bool,
+    /// Update events skipped since startup
+    #[serde(default)]
+    pub suppressed_emits: u64,
+    /// Webhook deliveries that failed after retries since startup
+    #[serde(default)]
+    pub webhook_failures: u64,
+    #[serde(default)]
+    pub webhook_last_error: Option<String>,
+    /// Unix timestamp of the last successful webhook delivery
+    #[serde(default)]
+    pub webhook_last_success_at: Option<i64>,
+    /// NVML reinitialization attempts after GPU driver resets since startup
+    #[serde(default)]
+    pub nvml_reinit_attempts: u64,
+    /// Repeated warnings kept out of the log since startup
+    #[serde(default)]
+    pub suppressed_log_messages: u64,
+    /// Low-power mode is on (temporary, reset at restart)
+    #[serde(default)]
+    pub low_power_mode: bool,
+    /// Loopback port of the single-instance lock, None when duplicate launches are not prevented
+    #[serde(default)]
+    pub instance_lock_port: Option<u16>,
+    /// Later launches handed off to this instance since startup
+    #[serde(default)]
+    pub instance_handoffs: u64,
+    /// Oldest critical metrics handed out since startup, in milliseconds
+    #[serde(default)]
+    pub max_critical_age_ms: u64,
+    /// Oldest detailed metrics handed out since startup, in milliseconds
+    #[serde(default)]
+    pub max_detailed_age_ms: u64,
+    /// History is only kept in memory, the data file could not be opened
+    #[serde(default)]
+    pub persistence_disabled: bool,
+    /// The config file could not be parsed at startup and was replaced (backup or defaults)
+    #[serde(default)]
+    pub config_recovery: Option<ConfigRecovery>,
+    /// Factor applied to estimated readings (1.0 = none)
+    #[serde(default)]
+    pub estimation_correction: f64,
+    /// Capabilities of the active power source, None until probed
+    #[serde(default)]
+    pub capabilities: Option<SourceCapabilities>,
+    /// Period of the detailed loop from the settings (eco mode included), in milliseconds
+    #[serde(default)]
+    pub configured_detailed_refresh_ms: u64,
+    /// Period it actually runs at, longer when collections are slow on this machine
+    #[serde(default)]
+    pub effective_detailed_refresh_ms: u64,
+}
+
+/// Cached metrics older than this many refresh periods are stale
+pub const STALE_PERIODS: u64 = 3;
+
+/// Age of cached metrics when they are read
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Freshness {
+    pub age_ms: u64,
+    /// Older than `STALE_PERIODS` refresh periods (a hung GPU query, a paused loop)
+    pub stale: bool,
+}
+
+impl Freshness {
+    /// Freshness at `now_ms` of metrics taken at `timestamp_ms`, refreshed every `period_ms`
+    pub fn at(timestamp_ms: i64, now_ms: i64, period_ms: u64) -> Self {
+        let age_ms = now_ms.saturating_sub(timestamp_ms).max(0) as u64;
+        Self { age_ms, stale: age_ms > period_ms.saturating_mul(STALE_PERIODS) }
+    }
+}
+
 /// Critical metrics that need fast updates (power, CPU%, GPU%, cost, timer)
 /// Updated at the user's fast refresh rate (e.g., 1s)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -285,6 +573,176 @@ pub struct CriticalMetrics {
     pub is_estimated: bool,
     /// Timestamp of this reading
     pub timestamp: i64,
+    /// Month-to-date billing summary (refreshed about once a minute)
+    #[serde(default)]
+    pub billing_summary: Option<BillingSummary>,
+    /// Session baseline, or the detected (or manual) baseline when no session is active
+    #[serde(default)]
+    pub baseline_watts: Option<f64>,
+    /// Instantaneous power above `baseline_watts`, clamped at 0
+    #[serde(default)]
+    pub surplus_watts: Option<f64>,
+    /// When these metrics were taken, in Unix milliseconds
+    #[serde(default)]
+    pub timestamp_ms: i64,
+    /// Energy and costs formatted with the display preferences
+    #[serde(default)]
+    pub display: Option<MetricsDisplay>,
+    /// Age when read from the cache
+    #[serde(flatten)]
+    pub freshness: Freshness,
+}
+
+/// Session energy and costs of `CriticalMetrics` in `general.energy_unit` and `general.cost_decimals`
+///
+/// Formatted once in Rust so the dashboard and the widget show the same text.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetricsDisplay {
+    /// Session energy in `energy_unit`
+    pub energy_value: f64,
+    /// Translated unit of `energy_value` (`Wh`, `kWh`)
+    pub energy_unit: String,
+    pub cost_decimals: usize,
+    pub energy: String,
+    pub current_cost: String,
+    pub hourly_cost: String,
+    pub daily_cost: String,
+    pub monthly_cost: String,
+    /// Surplus cost of the active session
+    #[serde(default)]
+    pub session_surplus_cost: Option<String>,
+}
+
+impl MetricsDisplay {
+    pub fn new(metrics: &CriticalMetrics, settings: &DisplaySettings, i18n: &I18n) -> Self {
+        MetricsFormatter::new(settings, i18n).format(metrics)
+    }
+}
+
+/// Builds `MetricsDisplay` without the i18n lock, units translated up front
+///
+/// The critical loop rebuilds it when the display settings (language included) change.
+#[derive(Debug, Clone)]
+pub struct MetricsFormatter {
+    settings: DisplaySettings,
+    language: String,
+    watt_hours: String,
+    kilowatt_hours: String,
+}
+
+impl MetricsFormatter {
+    pub fn new(settings: &DisplaySettings, i18n: &I18n) -> Self {
+        Self {
+            settings: settings.clone(),
+            language: i18n.current_language().to_string(),
+            watt_hours: i18n.get("unit.watt_hours"),
+            kilowatt_hours: i18n.get("unit.kilowatt_hours"),
+        }
+    }
+
+    /// Formatting settings of the last build
+    pub fn settings(&self) -> &DisplaySettings {
+        &self.settings
+    }
+
+    pub fn format(&self, metrics: &CriticalMetrics) -> MetricsDisplay {
+        let settings = &self.settings;
+        let lang = self.language.as_str();
+        let energy = DisplayedEnergy::new(metrics.cumulative_wh, &settings.energy_unit);
+        let energy_unit = if energy.unit_key == "unit.kilowatt_hours" { &self.kilowatt_hours } else { &self.watt_hours }.clone();
+        let decimals = settings.cost_decimals;
+        let cost = |value: f64| format_cost_with(value, decimals, &settings.currency_symbol, lang);
+        MetricsDisplay {
+            energy_value: energy.value,
+            energy: energy.format(&energy_unit, lang),
+            energy_unit,
+            cost_decimals: decimals,
+            current_cost: cost(metrics.current_cost),
+            hourly_cost: cost(metrics.hourly_cost_estimate),
+            daily_cost: cost(metrics.daily_cost_estimate),
+            monthly_cost: cost(metrics.monthly_cost_estimate),
+            session_surplus_cost: metrics.active_session.as_ref().map(|s| cost(s.surplus_cost)),
+        }
+    }
+}
+
+/// Billing summary for the current month
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BillingSummary {
+    /// Month as "YYYY-MM"
+    pub month: String,
+    pub month_to_date_kwh: f64,
+    pub month_to_date_cost: f64,
+    /// Days elapsed in the month, including today
+    pub days_elapsed: u32,
+    /// Days left after today
+    pub days_remaining: u32,
+    pub days_in_month: u32,
+    /// Days of the month that have recorded data
+    pub days_with_data: u32,
+    /// Blended daily cost used for the projection
+    pub projected_daily_cost: f64,
+    pub projected_month_cost: f64,
+    pub projected_month_kwh: f64,
+    /// Last month's totals, if any data was recorded
+    pub last_month_kwh: Option<f64>,
+    pub last_month_cost: Option<f64>,
+    /// Projected cost vs last month, in percent
+    pub change_vs_last_month_percent: Option<f64>,
+    /// True when too few days are available for a reliable projection
+    pub low_confidence: bool,
+    /// Rate used for the converted amounts, None without a secondary currency
+    #[serde(default)]
+    pub exchange_rate: Option<ExchangeRate>,
+    #[serde(default)]
+    pub converted_month_to_date_cost: Option<f64>,
+    #[serde(default)]
+    pub converted_projected_month_cost: Option<f64>,
+    #[serde(default)]
+    pub converted_last_month_cost: Option<f64>,
+}
+
+impl BillingSummary {
+    /// Fill in the amounts converted to the secondary currency
+    pub fn with_exchange_rate(mut self, rate: Option<ExchangeRate>) -> Self {
+        if let Some(ref rate) = rate {
+            self.converted_month_to_date_cost = Some(rate.convert(self.month_to_date_cost));
+            self.converted_projected_month_cost = Some(rate.convert(self.projected_month_cost));
+            self.converted_last_month_cost = self.last_month_cost.map(|c| rate.convert(c));
+        }
+        self.exchange_rate = rate;
+        self
+    }
+}
+
+/// Where an exchange rate comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateSource {
+    Manual,
+    Fetched,
+}
+
+/// Exchange rate to the secondary currency and how fresh it is
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExchangeRate {
+    /// Secondary currency code and symbol
+    pub currency: String,
+    pub currency_symbol: String,
+    /// Units of the secondary currency per unit of the primary one
+    pub rate: f64,
+    pub source: RateSource,
+    /// Unix timestamp of the fetch (None for a manual rate)
+    pub updated_at: Option<i64>,
+    /// Fetched too long ago: still used, but should be shown as outdated
+    pub stale: bool,
+}
+
+impl ExchangeRate {
+    /// Convert an amount of the primary currency
+    pub fn convert(&self, amount: f64) -> f64 {
+        amount * self.rate
+    }
 }
 
 /// Detailed metrics that can be updated less frequently (processes, temps, VRAM)
@@ -293,11 +751,258 @@ pub struct CriticalMetrics {
 pub struct DetailedMetrics {
     /// Full system metrics including CPU, GPU, and memory
     pub system_metrics: Option<SystemMetrics>,
-    /// Top processes by resource usage
+    /// Top processes by resource usage, ending with an "Other" row when some were left out
     pub top_processes: Vec<ProcessMetrics>,
+    /// Sums over `top_processes`
+    #[serde(default)]
+    pub process_totals: ProcessTotals,
     /// Timestamp of this reading
     pub timestamp: i64,
     /// Whether extended metrics (per-core freq, fans) were collected this cycle
     #[serde(default)]
     pub extended_collected: bool,
+    /// Energy measured by the hardware counter since the previous detailed update (RAPL only)
+    #[serde(default)]
+    pub measured_energy_joules: Option<f64>,
+    /// CPU usage of the tracker itself (100 = one logical core)
+    #[serde(default)]
+    pub app_self_cpu_percent: Option<f64>,
+    /// Estimated power drawn by the tracker itself
+    #[serde(default)]
+    pub app_self_power_watts: Option<f64>,
+    /// GPU metrics are the last cached values, GPU polling being paused for a fullscreen app
+    #[serde(default)]
+    pub gpu_stale: bool,
+    /// When these metrics were taken, in Unix milliseconds
+    #[serde(default)]
+    pub timestamp_ms: i64,
+}
+
+/// Per-core entries sent with detailed metrics, `get_cpu_metrics` has them all
+pub const MAX_PAYLOAD_CORES: usize = 64;
+
+/// Detailed metrics on their way out of the cache, with their age when read
+///
+/// Events and commands share the cached allocation, it is only copied when
+/// the per-core arrays have to be cut to `MAX_PAYLOAD_CORES`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DetailedPayload {
+    #[serde(flatten)]
+    pub metrics: Arc<DetailedMetrics>,
+    /// Length of the per-core arrays before they were cut
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub per_core_count: Option<usize>,
+    /// Age when read from the cache
+    #[serde(flatten)]
+    pub freshness: Freshness,
+}
+
+impl DetailedPayload {
+    /// Payload of `metrics`, per-core arrays cut when the CPU has more than `MAX_PAYLOAD_CORES` cores
+    pub fn new(metrics: &Arc<DetailedMetrics>, freshness: Freshness) -> Self {
+        let cores = metrics.system_metrics.as_ref().map_or(0, |sys| {
+            sys.cpu.per_core_usage.len().max(sys.cpu.per_core_frequency_mhz.as_ref().map_or(0, Vec::len))
+        });
+        if cores <= MAX_PAYLOAD_CORES {
+            return Self { metrics: Arc::clone(metrics), per_core_count: None, freshness };
+        }
+
+        let mut cut = DetailedMetrics::clone(metrics);
+        if let Some(sys) = cut.system_metrics.as_mut() {
+            sys.cpu.per_core_usage.truncate(MAX_PAYLOAD_CORES);
+            if let Some(frequencies) = sys.cpu.per_core_frequency_mhz.as_mut() {
+                frequencies.truncate(MAX_PAYLOAD_CORES);
+            }
+        }
+        Self { metrics: Arc::new(cut), per_core_count: Some(cores), freshness }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+
+    fn critical_metrics() -> CriticalMetrics {
+        CriticalMetrics {
+            power_watts: 180.0,
+            avg_power_watts: 150.0,
+            cpu_usage_percent: 40.0,
+            gpu_usage_percent: None,
+            gpu_power_watts: None,
+            cumulative_wh: 12.0,
+            current_cost: 0.003,
+            hourly_cost_estimate: 0.03,
+            daily_cost_estimate: 0.72,
+            monthly_cost_estimate: 21.6,
+            session_duration_secs: 300,
+            active_session: None,
+            source: "rapl".to_string(),
+            is_estimated: false,
+            timestamp: 1_700_000_000,
+            billing_summary: None,
+            baseline_watts: Some(60.0),
+            surplus_watts: Some(120.0),
+            timestamp_ms: 1_700_000_000_000,
+            display: None,
+            freshness: Freshness::default(),
+        }
+    }
+
+    #[test]
+    fn test_critical_metrics_payload() {
+        let json = serde_json::to_value(critical_metrics()).unwrap();
+        assert_eq!(json["baseline_watts"], 60.0);
+        assert_eq!(json["surplus_watts"], 120.0);
+
+        // Payloads from before the baseline fields still parse
+        let mut old = json.as_object().unwrap().clone();
+        old.remove("baseline_watts");
+        old.remove("surplus_watts");
+        let parsed: CriticalMetrics = serde_json::from_value(serde_json::Value::Object(old)).unwrap();
+        assert_eq!(parsed.baseline_watts, None);
+        assert_eq!(parsed.surplus_watts, None);
+    }
+
+    #[test]
+    fn test_metrics_display() {
+        let mut config = Config::default();
+        config.pricing.currency_symbol = "\u{20AC}".to_string();
+        config.general.cost_decimals = 4;
+        let mut metrics = critical_metrics();
+        let display = MetricsDisplay::new(&metrics, &DisplaySettings::from_config(&config), &I18n::new("en"));
+        assert_eq!(display.energy, "12.0 Wh");
+        assert_eq!(display.current_cost, "\u{20AC}0.0030");
+        assert_eq!(display.monthly_cost, "\u{20AC}21.6000");
+
+        // Auto switches to kWh from 1000 Wh, raw fields are left alone
+        metrics.cumulative_wh = 1500.0;
+        config.general.cost_decimals = 2;
+        metrics.display = Some(MetricsDisplay::new(&metrics, &DisplaySettings::from_config(&config), &I18n::new("fr")));
+        let json = serde_json::to_value(&metrics).unwrap();
+        assert_eq!(json["cumulative_wh"], 1500.0);
+        assert_eq!(json["display"]["energy_value"], 1.5);
+        assert_eq!(json["display"]["energy_unit"], "kWh");
+        assert_eq!(json["display"]["energy"], "1,500\u{00A0}kWh");
+        assert_eq!(json["display"]["current_cost"], "0,00\u{00A0}\u{20AC}");
+    }
+
+    fn detailed_metrics(cores: usize, processes: usize) -> DetailedMetrics {
+        let cpu = CpuMetrics {
+            name: "Threadripper".to_string(),
+            usage_percent: 37.5,
+            per_core_usage: vec![37.5; cores],
+            frequency_mhz: Some(4200),
+            temperature_celsius: Some(61.0),
+            core_count: cores / 2,
+            thread_count: cores,
+            per_core_frequency_mhz: Some(vec![4200; cores]),
+            per_core_temperature: None,
+        };
+        let memory = MemoryMetrics {
+            used_bytes: 1 << 34,
+            total_bytes: 1 << 36,
+            usage_percent: 25.0,
+            swap_used_bytes: None,
+            swap_total_bytes: None,
+            swap_usage_percent: None,
+            memory_speed_mhz: None,
+            memory_type: None,
+            power_watts: None,
+        };
+        let top_processes: Vec<ProcessMetrics> = (0..processes)
+            .map(|i| ProcessMetrics {
+                pid: 10_000 + i as u32,
+                name: format!("worker-process-{}", i),
+                cpu_percent: 12.345,
+                memory_bytes: 123_456_789,
+                memory_percent: 0.75,
+                gpu_percent: Some(3.5),
+                is_pinned: false,
+                is_ignored: false,
+                is_aggregate: false,
+                container: None,
+            })
+            .collect();
+        DetailedMetrics {
+            system_metrics: Some(SystemMetrics { cpu, gpu: None, memory, timestamp: 0, fans: None, voltages: None }),
+            process_totals: ProcessTotals::of(&top_processes),
+            top_processes,
+            timestamp: 0,
+            extended_collected: true,
+            measured_energy_joules: None,
+            app_self_cpu_percent: None,
+            app_self_power_watts: None,
+            gpu_stale: false,
+            timestamp_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_detailed_payload_size() {
+        // Usual machines share the cached allocation
+        let metrics = Arc::new(detailed_metrics(16, 10));
+        let payload = DetailedPayload::new(&metrics, Freshness::at(0, 7_000, 2_000));
+        assert!(Arc::ptr_eq(&payload.metrics, &metrics));
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!((json["age_ms"].as_u64(), json["stale"].as_bool()), (Some(7_000), Some(true)));
+        assert!(json.get("per_core_count").is_none());
+
+        // Biggest process list on a 256-thread CPU, cores cut down with their count
+        let metrics = Arc::new(detailed_metrics(256, crate::core::MAX_PROCESS_LIST_LIMIT));
+        let payload = DetailedPayload::new(&metrics, Freshness::default());
+        let json = serde_json::to_string(&payload).unwrap();
+        assert!(json.len() < 32 * 1024, "detailed payload grew to {} bytes", json.len());
+        let cpu = &payload.metrics.system_metrics.as_ref().unwrap().cpu;
+        assert_eq!(cpu.per_core_usage.len(), MAX_PAYLOAD_CORES);
+        assert_eq!(cpu.per_core_frequency_mhz.as_ref().map(Vec::len), Some(MAX_PAYLOAD_CORES));
+        assert_eq!(payload.per_core_count, Some(256));
+        // The cache keeps every core
+        assert_eq!(metrics.system_metrics.as_ref().unwrap().cpu.per_core_usage.len(), 256);
+    }
+
+    #[test]
+    fn test_freshness_boundary() {
+        // Stale strictly after three periods
+        assert_eq!(Freshness::at(1_000, 4_000, 1_000), Freshness { age_ms: 3_000, stale: false });
+        assert_eq!(Freshness::at(1_000, 4_001, 1_000), Freshness { age_ms: 3_001, stale: true });
+        assert!(!Freshness::at(0, 15_000, 5_000).stale);
+        assert!(Freshness::at(0, 15_001, 5_000).stale);
+
+        // Clock moved back: no negative age
+        assert_eq!(Freshness::at(5_000, 4_000, 1_000), Freshness { age_ms: 0, stale: false });
+        assert!(!Freshness::at(0, i64::MAX, u64::MAX).stale);
+
+        // Flattened into the payload next to the metrics
+        let mut metrics = critical_metrics();
+        metrics.freshness = Freshness::at(metrics.timestamp_ms, metrics.timestamp_ms + 7_000, 2_000);
+        let json = serde_json::to_value(&metrics).unwrap();
+        assert_eq!((json["age_ms"].as_u64(), json["stale"].as_bool()), (Some(7_000), Some(true)));
+    }
+
+    #[test]
+    fn test_session_rates() {
+        let mut session = Session::new(50.0, None);
+        session.start_time = 1_700_000_000;
+        session.end_time = Some(1_700_000_000 + 1800);
+        session.total_wh = 100.0;
+        session.surplus_wh = 40.0;
+        session.surplus_cost = 0.01;
+        session.compute_rates(0);
+        assert_eq!(session.duration_secs, 1800);
+        assert_eq!(session.avg_watts, Some(200.0));
+        assert_eq!(session.avg_surplus_watts, Some(80.0));
+        assert_eq!(session.cost_per_hour, Some(0.02));
+
+        // Open sessions are measured up to now, sub-minute ones get no averages
+        session.end_time = None;
+        session.compute_rates(1_700_000_000 + 30);
+        assert_eq!(session.duration_secs, 30);
+        assert_eq!(session.avg_watts, None);
+        assert_eq!(session.cost_per_hour, None);
+
+        // A start in the future (clock moved back) is not a negative duration
+        session.compute_rates(1_700_000_000 - 100);
+        assert_eq!(session.duration_secs, 0);
+    }
 }
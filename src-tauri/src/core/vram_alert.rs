@@ -0,0 +1,82 @@
+//! High GPU memory usage detection
+//!
+//! An alert fires once VRAM usage has stayed above the configured percentage
+//! for `SUSTAINED_SECS`, then stays quiet until usage drops back below it.
+
+use serde::{Deserialize, Serialize};
+
+/// How long usage must stay above the threshold before alerting
+pub const SUSTAINED_SECS: i64 = 60;
+
+/// Payload of the `vram-alert` event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VramAlert {
+    pub used_mb: u64,
+    pub total_mb: u64,
+    pub usage_percent: f64,
+    pub threshold_percent: u32,
+    /// First sample of the stretch above the threshold
+    pub above_since: i64,
+}
+
+/// Tracks how long VRAM usage has been above the threshold
+#[derive(Debug, Clone, Default)]
+pub struct VramAlertTracker {
+    above_since: Option<i64>,
+    alerted: bool,
+}
+
+impl VramAlertTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a sample taken at `timestamp` (seconds), returns the alert when it fires
+    pub fn update(&mut self, used_mb: u64, total_mb: u64, threshold_percent: u32, timestamp: i64) -> Option<VramAlert> {
+        let usage_percent = if total_mb > 0 { used_mb as f64 / total_mb as f64 * 100.0 } else { 0.0 };
+        if threshold_percent == 0 || usage_percent <= threshold_percent as f64 {
+            self.above_since = None;
+            self.alerted = false;
+            return None;
+        }
+
+        let since = *self.above_since.get_or_insert(timestamp);
+        if self.alerted || timestamp - since < SUSTAINED_SECS {
+            return None;
+        }
+        self.alerted = true;
+        Some(VramAlert { used_mb, total_mb, usage_percent, threshold_percent, above_since: since })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alerts_once_after_a_minute() {
+        let mut tracker = VramAlertTracker::new();
+        // 9 of 10 GB, one sample every 20 s
+        assert_eq!(tracker.update(9000, 10000, 85, 0), None);
+        assert_eq!(tracker.update(9000, 10000, 85, 40), None);
+        let alert = tracker.update(9500, 10000, 85, 60).unwrap();
+        assert_eq!(alert.above_since, 0);
+        assert!((alert.usage_percent - 95.0).abs() < 1e-9);
+        assert_eq!(tracker.update(9500, 10000, 85, 80), None);
+
+        // Dropping below resets, a new stretch needs another minute
+        assert_eq!(tracker.update(5000, 10000, 85, 100), None);
+        assert_eq!(tracker.update(9000, 10000, 85, 120), None);
+        assert_eq!(tracker.update(9000, 10000, 85, 179), None);
+        assert_eq!(tracker.update(9000, 10000, 85, 180).unwrap().above_since, 120);
+    }
+
+    #[test]
+    fn test_disabled_or_unknown_total() {
+        let mut tracker = VramAlertTracker::new();
+        assert_eq!(tracker.update(9000, 10000, 0, 0), None);
+        assert_eq!(tracker.update(9000, 10000, 0, 120), None);
+        assert_eq!(tracker.update(9000, 0, 85, 0), None);
+        assert_eq!(tracker.update(9000, 0, 85, 120), None);
+    }
+}
@@ -2,17 +2,160 @@
 //!
 //! Uses SQLite for efficient local storage of:
 //! - Power readings (sampled data)
+//! - CPU/GPU temperature readings
 //! - Daily aggregated statistics
 //! - Session tracking
 
-use crate::core::{Error, PowerReading, Result, Session};
-use rusqlite::{params, Connection};
+use crate::core::accuracy::AccuracySample;
+use crate::core::app_usage::AppUsage;
+use crate::core::clock::local_day_start;
+use crate::core::{Error, HardwareInventory, PowerReading, PricingMode, Result, Session};
+use chrono::{Datelike, NaiveDate, TimeZone, Timelike};
+use rusqlite::functions::FunctionFlags;
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Wall time represented by one stored reading, in seconds
+///
+/// The monitoring loop and `powercost-cli watch --record` store one reading
+/// per 10 seconds elapsed whatever their tick rate, so energy is estimated
+/// from the sum of the readings.
+pub const READING_INTERVAL_SECS: i64 = 10;
+
+/// Time represented by one stored reading, in hours
+pub const HOURS_PER_READING: f64 = READING_INTERVAL_SECS as f64 / 3600.0;
+
+/// `count` readings standing for the `count` intervals up to `reading`
+///
+/// A tick slower than `READING_INTERVAL_SECS` repeats its reading, one
+/// interval apart back from its timestamp, so the stored energy covers it.
+pub fn spread_reading(reading: &PowerReading, count: u32) -> Vec<PowerReading> {
+    (0..i64::from(count))
+        .rev()
+        .map(|i| PowerReading { timestamp: reading.timestamp - i * READING_INTERVAL_SECS, ..reading.clone() })
+        .collect()
+}
+
+/// How long opening the database waits for a lock held by another process
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Shortest interval between two aggregations of today's stats by `update_today_stats`
+pub const TODAY_STATS_MIN_INTERVAL_SECS: i64 = 30;
+
+/// Longest day note, in characters
+pub const MAX_DAY_NOTE_CHARS: usize = 500;
+
+/// Energy, cost and time tracked since the first day, as kept in `lifetime_totals`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LifetimeTotals {
+    pub total_wh: f64,
+    /// Sum of the priced days, days without a cost count as zero
+    pub total_cost: f64,
+    /// Seconds the tracker was running
+    pub usage_seconds: i64,
+    /// Ended sessions, a session split at midnight counts once
+    pub session_count: u64,
+    /// First day with stats (YYYY-MM-DD), None before any
+    pub first_date: Option<String>,
+    /// Unix timestamp of the last refresh
+    pub updated_at: i64,
+}
+
+/// Outcome of `dedup_readings`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DedupOutcome {
+    pub removed: u64,
+    /// Local dates (YYYY-MM-DD) that lost readings, their daily stats need a rebuild
+    pub dates: Vec<String>,
+    /// Ended sessions that lost readings, their surplus needs a recalculation
+    #[serde(default)]
+    pub sessions: Vec<i64>,
+}
+
+/// Rate used to price a day of readings
+pub enum DailyRate<'a> {
+    /// One rate for the whole day (None = no cost)
+    Flat(Option<f64>),
+    /// Rate in effect at each reading's timestamp
+    AtTime(&'a dyn Fn(i64) -> f64),
+}
+
+/// Parts `get_merged_session` follows back, a split happens at most once a day
+const MAX_SESSION_PARTS: usize = 100_000;
+
+/// Task in `pending_tasks` set by migration 7: daily stats are still keyed by UTC dates
+const LOCAL_DATES_REBUILD: &str = "local_dates_rebuild";
 
 /// Database manager
 pub struct Database {
     conn: Connection,
+    /// Last aggregation of today's stats, shared by every caller of `update_today_stats`
+    today_stats: RefCell<Option<TodayStats>>,
+    /// `insert_reading` skips a reading this close to one of the same source (0 = keeps all)
+    duplicate_window_secs: Cell<i64>,
+}
+
+/// Today's stats as last computed, and when
+struct TodayStats {
+    computed_at: i64,
+    date: String,
+    stats: Option<DailyStats>,
+}
+
+/// Merge sessions split at midnight back into one logical session each
+///
+/// A chain is reported under its first session's id, label and start, with the
+/// end of its last part and the summed totals. Parts whose first session is not
+/// in `sessions` are kept as they are. The order of `sessions` is kept.
+pub fn merge_continuations(sessions: Vec<Session>) -> Vec<Session> {
+    let index: std::collections::HashMap<i64, usize> =
+        sessions.iter().enumerate().filter_map(|(i, s)| Some((s.id?, i))).collect();
+    let root_of = |mut i: usize| {
+        // Bounded by the number of sessions, should the links ever form a cycle
+        for _ in 0..sessions.len() {
+            match sessions[i].continued_from.and_then(|id| index.get(&id)) {
+                Some(&parent) => i = parent,
+                None => break,
+            }
+        }
+        i
+    };
+    let roots: Vec<usize> = (0..sessions.len()).map(root_of).collect();
+
+    let mut slots: Vec<Option<Session>> = sessions.into_iter().map(Some).collect();
+    let mut parts: Vec<Vec<Session>> = vec![Vec::new(); slots.len()];
+    for (i, &root) in roots.iter().enumerate() {
+        if root != i {
+            parts[root].extend(slots[i].take());
+        }
+    }
+
+    slots
+        .into_iter()
+        .zip(parts)
+        .filter_map(|(session, parts)| {
+            let mut merged = session?;
+            if parts.is_empty() {
+                return Some(merged);
+            }
+            let open = parts.iter().any(|p| p.end_time.is_none());
+            let last = parts.iter().max_by_key(|p| p.start_time).unwrap();
+            merged.end_time = if open { None } else { last.end_time };
+            merged.auto_ended = last.auto_ended;
+            merged.total_wh += parts.iter().map(|p| p.total_wh).sum::<f64>();
+            merged.surplus_wh += parts.iter().map(|p| p.surplus_wh).sum::<f64>();
+            merged.surplus_cost += parts.iter().map(|p| p.surplus_cost).sum::<f64>();
+            merged.target_progress_percent = merged.compute_target_progress();
+            merged.target_met = merged.target_progress_percent.filter(|_| !open).map(|p| p <= 100.0);
+            merged.compute_rates(chrono::Utc::now().timestamp());
+            Some(merged)
+        })
+        .collect()
 }
 
 /// Daily statistics record
@@ -23,9 +166,32 @@ pub struct DailyStats {
     pub total_cost: Option<f64>,
     pub avg_watts: f64,
     pub max_watts: f64,
-    pub pricing_mode: Option<String>,
+    pub pricing_mode: Option<PricingMode>,
     #[serde(default)]
     pub usage_seconds: i64,
+    /// Source of most of the day's readings
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Note the user wrote about the day
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Note attached to a local day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DayNote {
+    pub date: String,
+    pub note: String,
+    pub created_at: i64,
+}
+
+/// Energy of one local hour, integrated from the readings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HourlyStats {
+    /// Local hour of the day (0-23); the repeated hour of a DST change is merged
+    pub hour: u32,
+    pub total_wh: f64,
+    pub total_cost: f64,
 }
 
 /// Power reading database record
@@ -38,19 +204,324 @@ pub struct PowerReadingRecord {
     pub components: Option<String>,
 }
 
+/// Power reading with its stored component split (None when not recorded)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentReading {
+    pub timestamp: i64,
+    pub power_watts: f64,
+    pub cpu_watts: Option<f64>,
+    pub gpu_watts: Option<f64>,
+    /// Rest of the system ("base" on Windows, "other" for the estimator)
+    pub base_watts: Option<f64>,
+}
+
+/// Temperature reading record (NULL when the sensor was unavailable)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemperatureReadingRecord {
+    pub timestamp: i64,
+    pub cpu_c: Option<f64>,
+    pub gpu_c: Option<f64>,
+}
+
+/// GPU memory reading record (averaged when downsampled)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VramReadingRecord {
+    pub timestamp: i64,
+    pub used_mb: f64,
+    pub total_mb: f64,
+}
+
+/// A logged alert (e.g. sustained high VRAM usage)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRecord {
+    pub timestamp: i64,
+    pub kind: String,
+    pub value: f64,
+    pub threshold: f64,
+}
+
+/// A change of power source (old_source is None for the first one recorded)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceChangeRecord {
+    pub timestamp: i64,
+    pub old_source: Option<String>,
+    pub new_source: String,
+    pub is_estimated: bool,
+}
+
+/// Surplus values of a session before a baseline recalculation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SurplusAudit {
+    /// Unix timestamp of the recalculation
+    pub recalculated_at: i64,
+    pub baseline_watts: f64,
+    pub surplus_wh: f64,
+    pub surplus_cost: f64,
+}
+
+/// Hardware inventory as of a point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HardwareSnapshot {
+    /// Unix timestamp of the first startup with this hardware
+    pub timestamp: i64,
+    pub inventory: HardwareInventory,
+}
+
+/// Sessions of one local calendar day with subtotals
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionDay {
+    /// Local date (YYYY-MM-DD) the sessions started on
+    pub date: String,
+    pub sessions: Vec<Session>,
+    pub total_surplus_wh: f64,
+    pub total_surplus_cost: f64,
+}
+
+/// Totals of one category over a local calendar month
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryTrendPoint {
+    /// Local month (YYYY-MM) the sessions started in
+    pub month: String,
+    pub surplus_kwh: f64,
+    pub surplus_cost: f64,
+    pub session_count: u32,
+    /// Surplus cost per hour of session, 0 when the month has no sessions
+    pub avg_cost_per_hour: f64,
+}
+
+/// Local time-of-day and weekday restriction for reading queries
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TimeFilter {
+    /// First hour included (0-23)
+    #[serde(default)]
+    pub hour_start: Option<u32>,
+    /// Hour the range stops at, exclusive (1-24); below `hour_start` for overnight ranges (22-06)
+    #[serde(default)]
+    pub hour_end: Option<u32>,
+    /// Days included, 0 = Sunday to 6 = Saturday
+    #[serde(default)]
+    pub weekdays: Option<Vec<u32>>,
+}
+
+impl TimeFilter {
+    /// Whether the filter keeps every reading
+    pub fn is_empty(&self) -> bool {
+        self.hour_start.is_none() && self.hour_end.is_none() && self.weekdays.is_none()
+    }
+
+    /// Check the hour and weekday bounds
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if let Some(h) = self.hour_start.filter(|&h| h > 23) {
+            return Err(format!("Invalid start hour: {}", h));
+        }
+        if let Some(h) = self.hour_end.filter(|&h| h > 24) {
+            return Err(format!("Invalid end hour: {}", h));
+        }
+        if let Some(days) = &self.weekdays {
+            if days.is_empty() {
+                return Err("At least one weekday is required".to_string());
+            }
+            if let Some(d) = days.iter().find(|&&d| d > 6) {
+                return Err(format!("Invalid weekday: {}", d));
+            }
+        }
+        Ok(())
+    }
+
+    /// SQL condition on `timestamp`, "1" when nothing is filtered
+    ///
+    /// Only validated integers are inlined. Like the off-peak window, a range
+    /// whose end is before its start wraps around midnight; equal bounds keep
+    /// the whole day. Hours and weekdays are those of `register_local_time`.
+    fn sql_condition(&self) -> String {
+        const HOUR: &str = "local_hour(timestamp)";
+        const WEEKDAY: &str = "local_weekday(timestamp)";
+
+        let mut conditions = Vec::new();
+        match (self.hour_start, self.hour_end) {
+            (Some(start), Some(end)) if start > end => {
+                conditions.push(format!("({h} >= {start} OR {h} < {end})", h = HOUR));
+            }
+            (Some(start), Some(end)) if start < end => {
+                conditions.push(format!("({h} >= {start} AND {h} < {end})", h = HOUR));
+            }
+            (Some(start), None) => conditions.push(format!("{} >= {}", HOUR, start)),
+            (None, Some(end)) => conditions.push(format!("{} < {}", HOUR, end)),
+            _ => {}
+        }
+        if let Some(days) = &self.weekdays {
+            let list: Vec<String> = days.iter().map(|d| d.to_string()).collect();
+            conditions.push(format!("{} IN ({})", WEEKDAY, list.join(", ")));
+        }
+
+        if conditions.is_empty() {
+            "1".to_string()
+        } else {
+            conditions.join(" AND ")
+        }
+    }
+}
+
+/// Category a session must currently have to match a `SessionCategoryFilter`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CurrentCategory {
+    #[default]
+    Any,
+    /// No category (NULL or empty)
+    Uncategorized,
+    Is(String),
+}
+
+/// Sessions changed by `bulk_update_session_category`, every set condition must hold
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionCategoryFilter {
+    /// Case-insensitive part of the label
+    pub label_contains: Option<String>,
+    /// Bounds of the session start, Unix timestamps (inclusive)
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub current_category: CurrentCategory,
+    /// Also match the running session (excluded by default)
+    pub include_active: bool,
+}
+
+impl SessionCategoryFilter {
+    /// SQL condition on the sessions table and its parameters, numbered from `first_param`
+    fn sql_condition(&self, first_param: usize) -> (String, Vec<Value>) {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        let mut param = |value: Value| {
+            values.push(value);
+            first_param + values.len() - 1
+        };
+
+        if let Some(text) = self.label_contains.as_deref().filter(|t| !t.is_empty()) {
+            let n = param(Value::Text(text.to_string()));
+            conditions.push(format!("instr(lower(COALESCE(label, '')), lower(?{})) > 0", n));
+        }
+        if let Some(start) = self.start {
+            conditions.push(format!("start_time >= ?{}", param(Value::Integer(start))));
+        }
+        if let Some(end) = self.end {
+            conditions.push(format!("start_time <= ?{}", param(Value::Integer(end))));
+        }
+        match &self.current_category {
+            CurrentCategory::Any => {}
+            CurrentCategory::Uncategorized => conditions.push("(category IS NULL OR category = '')".to_string()),
+            CurrentCategory::Is(name) => conditions.push(format!("category = ?{}", param(Value::Text(name.clone())))),
+        }
+        if !self.include_active {
+            conditions.push("end_time IS NOT NULL".to_string());
+        }
+
+        if conditions.is_empty() {
+            ("1".to_string(), values)
+        } else {
+            (conditions.join(" AND "), values)
+        }
+    }
+}
+
+/// Which statistics `Database::reset` deletes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResetScope {
+    Readings,
+    DailyStats,
+    Sessions,
+    All,
+}
+
+impl ResetScope {
+    fn includes_readings(self) -> bool {
+        matches!(self, Self::Readings | Self::All)
+    }
+
+    fn includes_daily_stats(self) -> bool {
+        matches!(self, Self::DailyStats | Self::All)
+    }
+
+    pub fn includes_sessions(self) -> bool {
+        matches!(self, Self::Sessions | Self::All)
+    }
+}
+
+/// Number of rows deleted per table by a reset
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResetCounts {
+    pub readings: u64,
+    #[serde(default)]
+    pub temperature_readings: u64,
+    #[serde(default)]
+    pub vram_readings: u64,
+    #[serde(default)]
+    pub alerts: u64,
+    #[serde(default)]
+    pub accuracy_samples: u64,
+    #[serde(default)]
+    pub source_changes: u64,
+    pub daily_stats: u64,
+    pub sessions: u64,
+    #[serde(default)]
+    pub day_notes: u64,
+}
+
+/// Tables `copy_into` copies as they are, ids renumbered by the target
+const COPIED_TABLES: &[&str] = &["power_readings", "temperature_readings", "vram_readings", "alerts", "source_changes", "hardware_snapshots", "accuracy_samples"];
+
+/// Result of `copy_into`
+#[derive(Debug, Clone, Default)]
+pub struct CopiedRows {
+    /// Rows copied over all tables (daily stats excluded, they are rebuilt)
+    pub rows: u64,
+    /// New id of each copied session, by its id in the source
+    pub session_ids: HashMap<i64, i64>,
+}
+
 impl Database {
     /// Create a new database connection
     pub fn new() -> Result<Self> {
-        let db_path = Self::db_path()?;
-        let conn = Connection::open(&db_path)?;
+        Self::open(&Self::db_path()?)
+    }
 
-        let db = Self { conn };
+    /// Database living in memory only, for when the data file cannot be opened
+    pub fn in_memory() -> Result<Self> {
+        let db = Self { conn: Connection::open_in_memory()?, today_stats: RefCell::new(None), duplicate_window_secs: Cell::new(0) };
         db.init_schema()?;
         db.run_migrations()?;
-
         Ok(db)
     }
 
+    /// Open (or create) the database at `path` instead of the app data directory
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::open_with_timeout(path, BUSY_TIMEOUT)
+    }
+
+    fn open_with_timeout(path: &Path, busy_timeout: Duration) -> Result<Self> {
+        let open = || -> Result<Self> {
+            let conn = Connection::open(path)?;
+            // Locks are released after each transaction so the CLI can read
+            // while the app runs; writers wait for each other up to the timeout
+            conn.busy_timeout(busy_timeout)?;
+            conn.query_row("PRAGMA locking_mode = NORMAL", [], |_| Ok(()))?;
+
+            let db = Self { conn, today_stats: RefCell::new(None), duplicate_window_secs: Cell::new(0) };
+            db.init_schema()?;
+            db.run_migrations()?;
+            Ok(db)
+        };
+        open().map_err(|e| match e {
+            Error::Database(rusqlite::Error::SqliteFailure(failure, _))
+                if matches!(failure.code, rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked) =>
+            {
+                Error::DatabaseBusy(path.display().to_string())
+            }
+            e => e,
+        })
+    }
+
     /// Get the database file path
     fn db_path() -> Result<PathBuf> {
         let data_dir = dirs::data_dir()
@@ -75,6 +546,40 @@ impl Database {
                 components TEXT
             );
 
+            -- Temperature history (NULL = sensor unavailable)
+            CREATE TABLE IF NOT EXISTS temperature_readings (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                cpu_c REAL,
+                gpu_c REAL
+            );
+
+            -- GPU memory history (only recorded when a GPU reports it)
+            CREATE TABLE IF NOT EXISTS vram_readings (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                used_mb REAL NOT NULL,
+                total_mb REAL NOT NULL
+            );
+
+            -- Alerts raised by the monitoring loop
+            CREATE TABLE IF NOT EXISTS alerts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                value REAL NOT NULL,
+                threshold REAL NOT NULL
+            );
+
+            -- Power source changes (e.g. fallback from RAPL to estimation)
+            CREATE TABLE IF NOT EXISTS source_changes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                old_source TEXT,
+                new_source TEXT NOT NULL,
+                is_estimated INTEGER NOT NULL
+            );
+
             -- Daily aggregates
             CREATE TABLE IF NOT EXISTS daily_stats (
                 date TEXT PRIMARY KEY,
@@ -106,6 +611,9 @@ impl Database {
 
             -- Indexes
             CREATE INDEX IF NOT EXISTS idx_readings_timestamp ON power_readings(timestamp);
+            CREATE INDEX IF NOT EXISTS idx_temperature_timestamp ON temperature_readings(timestamp);
+            CREATE INDEX IF NOT EXISTS idx_vram_timestamp ON vram_readings(timestamp);
+            CREATE INDEX IF NOT EXISTS idx_alerts_timestamp ON alerts(timestamp);
             "#,
         )?;
 
@@ -171,487 +679,3341 @@ impl Database {
             log::info!("Schema updated to version {}", version);
         }
 
-        // Future migrations go here:
-        // if version < 2 { ... version = 2; self.set_schema_version(version)?; }
-
-        Ok(())
-    }
+        if version < 2 {
+            // Migration 2: Add session targets
+            for (column, sql) in [
+                ("target_cost", "ALTER TABLE sessions ADD COLUMN target_cost REAL"),
+                ("target_wh", "ALTER TABLE sessions ADD COLUMN target_wh REAL"),
+                ("target_met", "ALTER TABLE sessions ADD COLUMN target_met INTEGER"),
+            ] {
+                match self.conn.execute(sql, []) {
+                    Ok(_) => log::info!("Migration 2: added {} to sessions", column),
+                    Err(e) if e.to_string().contains("duplicate column") => {
+                        log::debug!("Migration 2: {} column already exists", column);
+                    }
+                    Err(e) => return Err(Error::Database(e)),
+                }
+            }
 
-    /// Insert a power reading
-    pub fn insert_reading(&self, reading: &PowerReading) -> Result<()> {
-        let components_json = reading
-            .components
-            .as_ref()
-            .map(|c| serde_json::to_string(c).unwrap_or_default());
+            version = 2;
+            self.set_schema_version(version)?;
+            log::info!("Schema updated to version {}", version);
+        }
 
-        self.conn.execute(
-            "INSERT INTO power_readings (timestamp, power_watts, source, components) VALUES (?1, ?2, ?3, ?4)",
-            params![reading.timestamp, reading.power_watts, reading.source, components_json],
-        )?;
+        if version < 3 {
+            // Migration 3: Add session heartbeat (crash recovery)
+            match self.conn.execute("ALTER TABLE sessions ADD COLUMN last_heartbeat INTEGER", []) {
+                Ok(_) => log::info!("Migration 3: added last_heartbeat to sessions"),
+                Err(e) if e.to_string().contains("duplicate column") => {
+                    log::debug!("Migration 3: last_heartbeat column already exists");
+                }
+                Err(e) => return Err(Error::Database(e)),
+            }
 
-        Ok(())
-    }
+            version = 3;
+            self.set_schema_version(version)?;
+            log::info!("Schema updated to version {}", version);
+        }
 
-    /// Get power readings for a time range
-    pub fn get_readings(&self, start: i64, end: i64) -> Result<Vec<PowerReadingRecord>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, timestamp, power_watts, source, components
-             FROM power_readings
-             WHERE timestamp >= ?1 AND timestamp <= ?2
-             ORDER BY timestamp ASC",
-        )?;
+        if version < 4 {
+            // Migration 4: Flag sessions ended automatically after inactivity
+            match self.conn.execute("ALTER TABLE sessions ADD COLUMN auto_ended INTEGER NOT NULL DEFAULT 0", []) {
+                Ok(_) => log::info!("Migration 4: added auto_ended to sessions"),
+                Err(e) if e.to_string().contains("duplicate column") => {
+                    log::debug!("Migration 4: auto_ended column already exists");
+                }
+                Err(e) => return Err(Error::Database(e)),
+            }
 
-        let readings = stmt
-            .query_map(params![start, end], |row| {
-                Ok(PowerReadingRecord {
-                    id: row.get(0)?,
-                    timestamp: row.get(1)?,
-                    power_watts: row.get(2)?,
-                    source: row.get(3)?,
-                    components: row.get(4)?,
-                })
-            })?
-            .filter_map(|r| r.ok())
-            .collect();
+            version = 4;
+            self.set_schema_version(version)?;
+            log::info!("Schema updated to version {}", version);
+        }
 
-        Ok(readings)
-    }
+        if version < 5 {
+            // Migration 5: Dominant power source per day
+            match self.conn.execute("ALTER TABLE daily_stats ADD COLUMN source TEXT", []) {
+                Ok(_) => log::info!("Migration 5: added source to daily_stats"),
+                Err(e) if e.to_string().contains("duplicate column") => {
+                    log::debug!("Migration 5: source column already exists");
+                }
+                Err(e) => return Err(Error::Database(e)),
+            }
 
-    /// Update or insert daily statistics
-    pub fn upsert_daily_stats(&self, stats: &DailyStats) -> Result<()> {
-        self.conn.execute(
-            r#"INSERT INTO daily_stats (date, total_wh, total_cost, avg_watts, max_watts, pricing_mode)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6)
-               ON CONFLICT(date) DO UPDATE SET
-                   total_wh = ?2,
-                   total_cost = ?3,
-                   avg_watts = ?4,
-                   max_watts = ?5,
-                   pricing_mode = ?6"#,
-            params![
-                stats.date,
-                stats.total_wh,
-                stats.total_cost,
-                stats.avg_watts,
-                stats.max_watts,
-                stats.pricing_mode
-            ],
-        )?;
+            version = 5;
+            self.set_schema_version(version)?;
+            log::info!("Schema updated to version {}", version);
+        }
 
-        Ok(())
-    }
+        if version < 6 {
+            // Migration 6: Top foreground apps per session (JSON)
+            match self.conn.execute("ALTER TABLE sessions ADD COLUMN top_apps TEXT", []) {
+                Ok(_) => log::info!("Migration 6: added top_apps to sessions"),
+                Err(e) if e.to_string().contains("duplicate column") => {
+                    log::debug!("Migration 6: top_apps column already exists");
+                }
+                Err(e) => return Err(Error::Database(e)),
+            }
 
-    /// Get daily statistics for a date range
-    pub fn get_daily_stats(&self, start: &str, end: &str) -> Result<Vec<DailyStats>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT date, total_wh, total_cost, avg_watts, max_watts, pricing_mode, COALESCE(usage_seconds, 0)
-             FROM daily_stats
-             WHERE date >= ?1 AND date <= ?2
-             ORDER BY date ASC",
-        )?;
+            version = 6;
+            self.set_schema_version(version)?;
+            log::info!("Schema updated to version {}", version);
+        }
 
-        let stats = stmt
-            .query_map(params![start, end], |row| {
-                Ok(DailyStats {
-                    date: row.get(0)?,
-                    total_wh: row.get(1)?,
-                    total_cost: row.get(2)?,
-                    avg_watts: row.get(3)?,
-                    max_watts: row.get(4)?,
-                    pricing_mode: row.get(5)?,
-                    usage_seconds: row.get(6)?,
-                })
-            })?
-            .filter_map(|r| r.ok())
-            .collect();
+        if version < 7 {
+            // Migration 7: daily stats switch from UTC to local dates. Rebuilding
+            // them needs the pricing config, so it is left to the caller, and
+            // recorded as a pending task until the rebuild completes.
+            self.conn.execute_batch("CREATE TABLE IF NOT EXISTS pending_tasks (name TEXT PRIMARY KEY);")?;
+            let has_stats: bool = self.conn.query_row("SELECT EXISTS(SELECT 1 FROM daily_stats)", [], |row| row.get(0))?;
+            if has_stats {
+                self.conn.execute("INSERT OR IGNORE INTO pending_tasks (name) VALUES (?1)", params![LOCAL_DATES_REBUILD])?;
+                log::info!("Migration 7: daily stats will be rebuilt with local dates");
+            }
 
-        Ok(stats)
+            version = 7;
+            self.set_schema_version(version)?;
+            log::info!("Schema updated to version {}", version);
+        }
+
+        if version < 8 {
+            // Migration 8: Previous surplus values of recalculated sessions (JSON)
+            match self.conn.execute("ALTER TABLE sessions ADD COLUMN surplus_audit TEXT", []) {
+                Ok(_) => log::info!("Migration 8: added surplus_audit to sessions"),
+                Err(e) if e.to_string().contains("duplicate column") => {
+                    log::debug!("Migration 8: surplus_audit column already exists");
+                }
+                Err(e) => return Err(Error::Database(e)),
+            }
+
+            version = 8;
+            self.set_schema_version(version)?;
+            log::info!("Schema updated to version {}", version);
+        }
+
+        if version < 9 {
+            // Migration 9: Hardware inventory history (JSON, one row per change)
+            self.conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS hardware_snapshots (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    timestamp INTEGER NOT NULL,
+                    inventory TEXT NOT NULL
+                );",
+            )?;
+            log::info!("Migration 9: added hardware_snapshots table");
+
+            version = 9;
+            self.set_schema_version(version)?;
+            log::info!("Schema updated to version {}", version);
+        }
+
+        if version < 10 {
+            // Migration 10: Sessions continued after a split at midnight
+            match self.conn.execute("ALTER TABLE sessions ADD COLUMN continued_from INTEGER", []) {
+                Ok(_) => log::info!("Migration 10: added continued_from to sessions"),
+                Err(e) if e.to_string().contains("duplicate column") => {
+                    log::debug!("Migration 10: continued_from column already exists");
+                }
+                Err(e) => return Err(Error::Database(e)),
+            }
+
+            version = 10;
+            self.set_schema_version(version)?;
+            log::info!("Schema updated to version {}", version);
+        }
+
+        if version < 11 {
+            // Migration 11: Baseline schedule bucket a session started in
+            match self.conn.execute("ALTER TABLE sessions ADD COLUMN baseline_bucket TEXT", []) {
+                Ok(_) => log::info!("Migration 11: added baseline_bucket to sessions"),
+                Err(e) if e.to_string().contains("duplicate column") => {
+                    log::debug!("Migration 11: baseline_bucket column already exists");
+                }
+                Err(e) => return Err(Error::Database(e)),
+            }
+
+            version = 11;
+            self.set_schema_version(version)?;
+            log::info!("Schema updated to version {}", version);
+        }
+
+        if version < 12 {
+            // Migration 12: Measured and estimated power pairs of the accuracy tracking
+            self.conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS accuracy_samples (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    timestamp INTEGER NOT NULL,
+                    measured_watts REAL NOT NULL,
+                    estimated_watts REAL NOT NULL
+                );",
+            )?;
+            log::info!("Migration 12: added accuracy_samples table");
+
+            version = 12;
+            self.set_schema_version(version)?;
+            log::info!("Schema updated to version {}", version);
+        }
+
+        if version < 13 {
+            // Migration 13: Costs filled in afterwards rather than computed with the readings
+            match self.conn.execute("ALTER TABLE daily_stats ADD COLUMN cost_backfilled INTEGER NOT NULL DEFAULT 0", []) {
+                Ok(_) => log::info!("Migration 13: added cost_backfilled to daily_stats"),
+                Err(e) if e.to_string().contains("duplicate column") => {
+                    log::debug!("Migration 13: cost_backfilled column already exists");
+                }
+                Err(e) => return Err(Error::Database(e)),
+            }
+
+            version = 13;
+            self.set_schema_version(version)?;
+            log::info!("Schema updated to version {}", version);
+        }
+
+        if version < 14 {
+            // Migration 14: Notes on days
+            self.conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS day_notes (
+                    date TEXT PRIMARY KEY,
+                    note TEXT NOT NULL,
+                    created_at INTEGER NOT NULL
+                );",
+            )?;
+            log::info!("Migration 14: created day_notes table");
+
+            version = 14;
+            self.set_schema_version(version)?;
+            log::info!("Schema updated to version {}", version);
+        }
+
+        if version < 15 {
+            // Migration 15: Lifetime totals, derived from daily stats and sessions
+            self.conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS lifetime_totals (
+                    id INTEGER PRIMARY KEY CHECK (id = 1),
+                    total_wh REAL NOT NULL,
+                    total_cost REAL NOT NULL,
+                    usage_seconds INTEGER NOT NULL,
+                    session_count INTEGER NOT NULL,
+                    first_date TEXT,
+                    updated_at INTEGER NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS idx_sessions_continued_from ON sessions(continued_from);",
+            )?;
+            self.refresh_lifetime_totals()?;
+            log::info!("Migration 15: created lifetime_totals table");
+
+            version = 15;
+            self.set_schema_version(version)?;
+            log::info!("Schema updated to version {}", version);
+        }
+
+        if version < 16 {
+            // Migration 16: Maintenance left to the caller (databases past migration 7 before it recorded its task)
+            self.conn.execute_batch("CREATE TABLE IF NOT EXISTS pending_tasks (name TEXT PRIMARY KEY);")?;
+            log::info!("Migration 16: created pending_tasks table");
+
+            version = 16;
+            self.set_schema_version(version)?;
+            log::info!("Schema updated to version {}", version);
+        }
+
+        if version < 17 {
+            // Migration 17: Accuracy samples are pruned by timestamp with the readings
+            self.conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_accuracy_timestamp ON accuracy_samples(timestamp);")?;
+            log::info!("Migration 17: indexed accuracy_samples by timestamp");
+
+            version = 17;
+            self.set_schema_version(version)?;
+            log::info!("Schema updated to version {}", version);
+        }
+
+        // Future migrations go here:
+        // if version < 18 { ... version = 18; self.set_schema_version(version)?; }
+
+        Ok(())
     }
 
-    /// Clean up old readings (keep only last N days of detailed data)
-    pub fn cleanup_old_readings(&self, days_to_keep: u32) -> Result<u64> {
-        let cutoff = chrono::Utc::now().timestamp() - (days_to_keep as i64 * 24 * 60 * 60);
+    /// Readings of a source closer than `secs` to a stored one are skipped by `insert_reading` (0 = keeps all)
+    pub fn set_duplicate_window_secs(&self, secs: u32) {
+        self.duplicate_window_secs.set(i64::from(secs));
+    }
 
-        let deleted = self.conn.execute(
-            "DELETE FROM power_readings WHERE timestamp < ?1",
-            params![cutoff],
+    /// Insert a power reading, returns false when it was skipped as a duplicate
+    ///
+    /// A reading is a duplicate when one of the same source was stored less
+    /// than the duplicate window away from it, see `set_duplicate_window_secs`.
+    pub fn insert_reading(&self, reading: &PowerReading) -> Result<bool> {
+        let window = self.duplicate_window_secs.get();
+        if window > 0 {
+            let duplicate: bool = self.conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM power_readings
+                 WHERE source = ?1 AND timestamp > ?2 - ?3 AND timestamp < ?2 + ?3)",
+                params![reading.source, reading.timestamp, window],
+                |row| row.get(0),
+            )?;
+            if duplicate {
+                return Ok(false);
+            }
+        }
+
+        let components_json = reading
+            .components
+            .as_ref()
+            .map(|c| serde_json::to_string(c).unwrap_or_default());
+
+        self.conn.execute(
+            "INSERT INTO power_readings (timestamp, power_watts, source, components) VALUES (?1, ?2, ?3, ?4)",
+            params![reading.timestamp, reading.power_watts, reading.source, components_json],
         )?;
 
-        Ok(deleted as u64)
+        Ok(true)
     }
 
-    /// Get total readings count
-    pub fn get_readings_count(&self) -> Result<i64> {
-        let count: i64 = self
-            .conn
-            .query_row("SELECT COUNT(*) FROM power_readings", [], |row| row.get(0))?;
-        Ok(count)
+    /// Delete stored readings that `insert_reading` would have skipped with a `window_secs` window
+    ///
+    /// Readings are kept in order, one per source and window, the others are
+    /// removed. The energy of the removed readings is taken off the ended
+    /// sessions they fell in. Daily stats and session surplus are left as they
+    /// were: rebuild the returned dates and recalculate the returned sessions.
+    pub fn dedup_readings(&self, window_secs: u32) -> Result<DedupOutcome> {
+        if window_secs == 0 {
+            return Ok(DedupOutcome::default());
+        }
+        let window = i64::from(window_secs);
+        self.register_local_time(&chrono::Local)?;
+
+        let mut duplicates = Vec::new();
+        let mut removed_energy = Vec::new();
+        let mut dates = BTreeSet::new();
+        {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, source, timestamp, local_date(timestamp), power_watts
+                 FROM power_readings
+                 ORDER BY source, timestamp, id",
+            )?;
+            let mut rows = stmt.query([])?;
+            let mut last_kept: Option<(String, i64)> = None;
+            while let Some(row) = rows.next()? {
+                let (id, source, timestamp): (i64, String, i64) = (row.get(0)?, row.get(1)?, row.get(2)?);
+                let duplicate = last_kept.as_ref().is_some_and(|(s, t)| *s == source && timestamp - t < window);
+                if duplicate {
+                    duplicates.push(id);
+                    dates.insert(row.get::<_, String>(3)?);
+                    removed_energy.push((timestamp, row.get::<_, f64>(4)? * HOURS_PER_READING));
+                } else {
+                    last_kept = Some((source, timestamp));
+                }
+            }
+        }
+
+        let mut sessions = BTreeSet::new();
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut delete = tx.prepare("DELETE FROM power_readings WHERE id = ?1")?;
+            for id in &duplicates {
+                delete.execute(params![id])?;
+            }
+            let mut session_at = tx.prepare(
+                "SELECT id FROM sessions WHERE end_time IS NOT NULL AND start_time <= ?1 AND end_time >= ?1",
+            )?;
+            let mut subtract = tx.prepare("UPDATE sessions SET total_wh = MAX(total_wh - ?1, 0.0) WHERE id = ?2")?;
+            for (timestamp, wh) in &removed_energy {
+                let ids: Vec<i64> = session_at
+                    .query_map(params![timestamp], |row| row.get(0))?
+                    .collect::<std::result::Result<_, _>>()?;
+                for id in ids {
+                    subtract.execute(params![wh, id])?;
+                    sessions.insert(id);
+                }
+            }
+        }
+        tx.commit()?;
+        self.invalidate_today_stats();
+
+        Ok(DedupOutcome {
+            removed: duplicates.len() as u64,
+            dates: dates.into_iter().collect(),
+            sessions: sessions.into_iter().collect(),
+        })
     }
 
-    /// Compute and update daily stats from power readings for a specific date
-    /// This aggregates all readings for the given date and updates the daily_stats table
-    /// If `rate_per_kwh` is provided, cost will be calculated as total_kwh * rate
-    pub fn update_daily_stats_for_date(&self, date: &str, pricing_mode: Option<&str>, rate_per_kwh: Option<f64>) -> Result<Option<DailyStats>> {
-        // Get start and end timestamps for the date
-        let start_of_day = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
-            .map_err(|e| Error::Database(rusqlite::Error::InvalidParameterName(e.to_string())))?
-            .and_hms_opt(0, 0, 0)
-            .unwrap()
-            .and_utc()
-            .timestamp();
-        let end_of_day = start_of_day + 86400; // 24 hours in seconds
+    /// Get power readings for a time range
+    pub fn get_readings(&self, start: i64, end: i64) -> Result<Vec<PowerReadingRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, timestamp, power_watts, source, components
+             FROM power_readings
+             WHERE timestamp >= ?1 AND timestamp <= ?2
+             ORDER BY timestamp ASC",
+        )?;
 
-        // Aggregate readings for this date
-        let result: std::result::Result<(f64, f64, f64, i64), rusqlite::Error> = self.conn.query_row(
-            "SELECT
-                COALESCE(AVG(power_watts), 0.0) as avg_watts,
-                COALESCE(MAX(power_watts), 0.0) as max_watts,
-                COALESCE(SUM(power_watts), 0.0) as sum_watts,
-                COUNT(*) as count
+        let readings = stmt
+            .query_map(params![start, end], |row| {
+                Ok(PowerReadingRecord {
+                    id: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    power_watts: row.get(2)?,
+                    source: row.get(3)?,
+                    components: row.get(4)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(readings)
+    }
+
+    /// Get power readings for a time range with their CPU, GPU and base components
+    ///
+    /// Rows without components (or with unreadable JSON) come back with all
+    /// components set to None. A GPU estimate stands in for a GPU reading.
+    pub fn get_component_readings(&self, start: i64, end: i64) -> Result<Vec<ComponentReading>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT timestamp, power_watts,
+                    CASE WHEN json_valid(components) THEN json_extract(components, '$.cpu') END,
+                    CASE WHEN json_valid(components)
+                         THEN COALESCE(json_extract(components, '$.gpu'), json_extract(components, '$.gpu_estimated')) END,
+                    CASE WHEN json_valid(components)
+                         THEN COALESCE(json_extract(components, '$.base'), json_extract(components, '$.other')) END
              FROM power_readings
-             WHERE timestamp >= ?1 AND timestamp < ?2",
-            params![start_of_day, end_of_day],
-            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+             WHERE timestamp >= ?1 AND timestamp <= ?2
+             ORDER BY timestamp ASC",
+        )?;
+
+        let readings = stmt
+            .query_map(params![start, end], |row| {
+                Ok(ComponentReading {
+                    timestamp: row.get(0)?,
+                    power_watts: row.get(1)?,
+                    cpu_watts: row.get(2)?,
+                    gpu_watts: row.get(3)?,
+                    base_watts: row.get(4)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(readings)
+    }
+
+    /// Get power readings matching `filter`, averaged into at most `max_points` buckets
+    ///
+    /// Each record is the mean of a bucket: `id` is its last reading, `source`
+    /// its most frequent one and `components` is not kept.
+    pub fn get_readings_filtered(&self, start: i64, end: i64, filter: &TimeFilter, max_points: u32) -> Result<Vec<PowerReadingRecord>> {
+        self.get_readings_filtered_in(start, end, filter, max_points, &chrono::Local)
+    }
+
+    /// Same as `get_readings_filtered`, with the filter hours in the given timezone
+    pub fn get_readings_filtered_in<Tz>(&self, start: i64, end: i64, filter: &TimeFilter, max_points: u32, tz: &Tz) -> Result<Vec<PowerReadingRecord>>
+    where
+        Tz: TimeZone + Send + std::panic::UnwindSafe + 'static,
+        Tz::Offset: Send,
+    {
+        self.register_local_time(tz)?;
+        let span = (end - start).max(0) + 1;
+        let max_points = max_points.max(1) as i64;
+        let bucket_secs = ((span + max_points - 1) / max_points).max(1);
+
+        let sql = format!(
+            "WITH filtered AS (
+                 SELECT id, timestamp, power_watts, source, (timestamp - ?1) / ?3 AS bucket
+                 FROM power_readings
+                 WHERE timestamp >= ?1 AND timestamp <= ?2 AND {}
+             )
+             SELECT MAX(id), CAST(AVG(timestamp) AS INTEGER), AVG(power_watts),
+                    (SELECT f2.source FROM filtered f2 WHERE f2.bucket = f.bucket
+                     GROUP BY f2.source ORDER BY COUNT(*) DESC LIMIT 1)
+             FROM filtered f
+             GROUP BY bucket
+             ORDER BY 2 ASC",
+            filter.sql_condition()
         );
+        let mut stmt = self.conn.prepare(&sql)?;
 
-        match result {
-            Ok((avg_watts, max_watts, sum_watts, count)) => {
-                if count == 0 {
-                    return Ok(None);
-                }
+        let readings = stmt
+            .query_map(params![start, end, bucket_secs], |row| {
+                Ok(PowerReadingRecord {
+                    id: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    power_watts: row.get(2)?,
+                    source: row.get(3)?,
+                    components: None,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(readings)
+    }
+
+    /// Insert a temperature reading, skipped when no sensor reported anything
+    pub fn insert_temperature_reading(&self, timestamp: i64, cpu_c: Option<f64>, gpu_c: Option<f64>) -> Result<()> {
+        if cpu_c.is_none() && gpu_c.is_none() {
+            return Ok(());
+        }
+
+        self.conn.execute(
+            "INSERT INTO temperature_readings (timestamp, cpu_c, gpu_c) VALUES (?1, ?2, ?3)",
+            params![timestamp, cpu_c, gpu_c],
+        )?;
+
+        Ok(())
+    }
+
+    /// Get temperature history for a time range, averaged into at most `max_points` buckets
+    ///
+    /// Missing sensor values stay NULL in the averages (SQLite's AVG ignores NULLs),
+    /// so a bucket only gets a value if the sensor reported at least once in it.
+    pub fn get_temperature_history(&self, start: i64, end: i64, max_points: u32) -> Result<Vec<TemperatureReadingRecord>> {
+        let span = (end - start).max(0) + 1;
+        let max_points = max_points.max(1) as i64;
+        let bucket_secs = ((span + max_points - 1) / max_points).max(1);
+
+        let mut stmt = self.conn.prepare(
+            "SELECT CAST(AVG(timestamp) AS INTEGER), AVG(cpu_c), AVG(gpu_c)
+             FROM temperature_readings
+             WHERE timestamp >= ?1 AND timestamp <= ?2
+             GROUP BY (timestamp - ?1) / ?3
+             ORDER BY 1 ASC",
+        )?;
+
+        let readings = stmt
+            .query_map(params![start, end, bucket_secs], |row| {
+                Ok(TemperatureReadingRecord {
+                    timestamp: row.get(0)?,
+                    cpu_c: row.get(1)?,
+                    gpu_c: row.get(2)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(readings)
+    }
+
+    /// Insert a GPU memory reading
+    pub fn insert_vram_reading(&self, timestamp: i64, used_mb: u64, total_mb: u64) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO vram_readings (timestamp, used_mb, total_mb) VALUES (?1, ?2, ?3)",
+            params![timestamp, used_mb as f64, total_mb as f64],
+        )?;
+
+        Ok(())
+    }
+
+    /// Get GPU memory history for a time range, averaged into at most `max_points` buckets
+    pub fn get_vram_history(&self, start: i64, end: i64, max_points: u32) -> Result<Vec<VramReadingRecord>> {
+        let span = (end - start).max(0) + 1;
+        let max_points = max_points.max(1) as i64;
+        let bucket_secs = ((span + max_points - 1) / max_points).max(1);
+
+        let mut stmt = self.conn.prepare(
+            "SELECT CAST(AVG(timestamp) AS INTEGER), AVG(used_mb), AVG(total_mb)
+             FROM vram_readings
+             WHERE timestamp >= ?1 AND timestamp <= ?2
+             GROUP BY (timestamp - ?1) / ?3
+             ORDER BY 1 ASC",
+        )?;
+
+        let readings = stmt
+            .query_map(params![start, end, bucket_secs], |row| {
+                Ok(VramReadingRecord {
+                    timestamp: row.get(0)?,
+                    used_mb: row.get(1)?,
+                    total_mb: row.get(2)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(readings)
+    }
+
+    /// Log an alert
+    pub fn insert_alert(&self, alert: &AlertRecord) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO alerts (timestamp, kind, value, threshold) VALUES (?1, ?2, ?3, ?4)",
+            params![alert.timestamp, alert.kind, alert.value, alert.threshold],
+        )?;
+
+        Ok(())
+    }
+
+    /// Get alerts logged in a time range, oldest first
+    pub fn get_alerts(&self, start: i64, end: i64) -> Result<Vec<AlertRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT timestamp, kind, value, threshold FROM alerts
+             WHERE timestamp >= ?1 AND timestamp <= ?2
+             ORDER BY timestamp ASC, id ASC",
+        )?;
+
+        let alerts = stmt
+            .query_map(params![start, end], |row| {
+                Ok(AlertRecord {
+                    timestamp: row.get(0)?,
+                    kind: row.get(1)?,
+                    value: row.get(2)?,
+                    threshold: row.get(3)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(alerts)
+    }
+
+    /// Record the current power source if it differs from the last one recorded
+    ///
+    /// Returns the inserted change, or None when the source is unchanged.
+    pub fn record_source(&self, source: &str, is_estimated: bool) -> Result<Option<SourceChangeRecord>> {
+        let last: Option<(String, bool)> = match self.conn.query_row(
+            "SELECT new_source, is_estimated FROM source_changes ORDER BY timestamp DESC, id DESC LIMIT 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ) {
+            Ok(last) => Some(last),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(Error::Database(e)),
+        };
+
+        if last.as_ref().is_some_and(|(s, e)| s == source && *e == is_estimated) {
+            return Ok(None);
+        }
+
+        let change = SourceChangeRecord {
+            timestamp: chrono::Utc::now().timestamp(),
+            old_source: last.map(|(s, _)| s),
+            new_source: source.to_string(),
+            is_estimated,
+        };
+        self.conn.execute(
+            "INSERT INTO source_changes (timestamp, old_source, new_source, is_estimated) VALUES (?1, ?2, ?3, ?4)",
+            params![change.timestamp, change.old_source, change.new_source, change.is_estimated],
+        )?;
+
+        Ok(Some(change))
+    }
+
+    /// Get source changes in a time range, preceded by the one in effect at `start`
+    pub fn get_source_history(&self, start: i64, end: i64) -> Result<Vec<SourceChangeRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT timestamp, old_source, new_source, is_estimated FROM source_changes
+             WHERE timestamp <= ?2 AND (timestamp >= ?1 OR id = (
+                 SELECT id FROM source_changes WHERE timestamp < ?1 ORDER BY timestamp DESC, id DESC LIMIT 1
+             ))
+             ORDER BY timestamp ASC, id ASC",
+        )?;
+
+        let changes = stmt
+            .query_map(params![start, end], |row| {
+                Ok(SourceChangeRecord {
+                    timestamp: row.get(0)?,
+                    old_source: row.get(1)?,
+                    new_source: row.get(2)?,
+                    is_estimated: row.get(3)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(changes)
+    }
+
+    /// Get total temperature readings count
+    pub fn get_temperature_readings_count(&self) -> Result<i64> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM temperature_readings", [], |row| row.get(0))?;
+        Ok(count)
+    }
+
+    /// Update or insert daily statistics
+    pub fn upsert_daily_stats(&self, stats: &DailyStats) -> Result<()> {
+        self.write_daily_stats(stats)?;
+        self.try_refresh_lifetime_totals();
+        Ok(())
+    }
+
+    /// `upsert_daily_stats` without refreshing lifetime totals
+    fn write_daily_stats(&self, stats: &DailyStats) -> Result<()> {
+        self.conn.execute(
+            r#"INSERT INTO daily_stats (date, total_wh, total_cost, avg_watts, max_watts, pricing_mode, source, cost_backfilled)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0)
+               ON CONFLICT(date) DO UPDATE SET
+                   total_wh = ?2,
+                   total_cost = ?3,
+                   avg_watts = ?4,
+                   max_watts = ?5,
+                   pricing_mode = ?6,
+                   source = ?7,
+                   cost_backfilled = 0"#,
+            params![
+                stats.date,
+                stats.total_wh,
+                stats.total_cost,
+                stats.avg_watts,
+                stats.max_watts,
+                stats.pricing_mode.as_ref().map(PricingMode::to_string),
+                stats.source
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Fill in the cost of days stored without one, at the rate `rate_for_date` gives for their date
+    ///
+    /// Such rows are flagged `cost_backfilled`, until a rebuild computes their
+    /// cost from the readings. Days without energy keep a NULL cost. Returns
+    /// the number of rows updated.
+    pub fn backfill_missing_costs(&self, rate_for_date: &dyn Fn(&str) -> f64) -> Result<usize> {
+        let days: Vec<(String, f64)> = self
+            .conn
+            .prepare("SELECT date, total_wh FROM daily_stats WHERE total_cost IS NULL AND total_wh > 0")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<_, _>>()?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        for (date, total_wh) in &days {
+            tx.execute(
+                "UPDATE daily_stats SET total_cost = ?2, cost_backfilled = 1 WHERE date = ?1",
+                params![date, total_wh / 1000.0 * rate_for_date(date)],
+            )?;
+        }
+        tx.commit()?;
+        if !days.is_empty() {
+            self.try_refresh_lifetime_totals();
+        }
+        Ok(days.len())
+    }
+
+    /// Recompute `lifetime_totals` from the daily stats and sessions
+    ///
+    /// Called after each write to either, so the row stays current however
+    /// many times a day is rolled up (bulk rebuilds call it once done).
+    /// Returns the new totals.
+    pub fn refresh_lifetime_totals(&self) -> Result<LifetimeTotals> {
+        let now = chrono::Utc::now().timestamp();
+        self.conn.execute(
+            "INSERT INTO lifetime_totals (id, total_wh, total_cost, usage_seconds, session_count, first_date, updated_at)
+             SELECT 1, d.total_wh, d.total_cost, d.usage_seconds,
+                    (SELECT COUNT(*) FROM sessions s
+                     WHERE s.end_time IS NOT NULL
+                       AND NOT EXISTS (SELECT 1 FROM sessions c WHERE c.continued_from = s.id)),
+                    d.first_date, ?1
+             FROM (SELECT COALESCE(SUM(total_wh), 0.0) AS total_wh,
+                          COALESCE(SUM(total_cost), 0.0) AS total_cost,
+                          COALESCE(SUM(usage_seconds), 0) AS usage_seconds,
+                          MIN(CASE WHEN total_wh > 0 OR usage_seconds > 0 THEN date END) AS first_date
+                   FROM daily_stats) d
+             WHERE true
+             ON CONFLICT(id) DO UPDATE SET
+                 total_wh = excluded.total_wh,
+                 total_cost = excluded.total_cost,
+                 usage_seconds = excluded.usage_seconds,
+                 session_count = excluded.session_count,
+                 first_date = excluded.first_date,
+                 updated_at = excluded.updated_at",
+            params![now],
+        )?;
+        self.get_lifetime_totals()
+    }
+
+    /// `refresh_lifetime_totals` after a write that already happened, logging a failure instead of returning it
+    pub fn try_refresh_lifetime_totals(&self) {
+        if let Err(e) = self.refresh_lifetime_totals() {
+            log::warn!("Failed to refresh lifetime totals: {}", e);
+        }
+    }
+
+    /// Lifetime totals as of their last refresh
+    pub fn get_lifetime_totals(&self) -> Result<LifetimeTotals> {
+        let totals = self
+            .conn
+            .query_row(
+                "SELECT total_wh, total_cost, usage_seconds, session_count, first_date, updated_at FROM lifetime_totals WHERE id = 1",
+                [],
+                |row| {
+                    Ok(LifetimeTotals {
+                        total_wh: row.get(0)?,
+                        total_cost: row.get(1)?,
+                        usage_seconds: row.get(2)?,
+                        session_count: row.get::<_, i64>(3)? as u64,
+                        first_date: row.get(4)?,
+                        updated_at: row.get(5)?,
+                    })
+                },
+            )
+            .optional()?;
+        match totals {
+            Some(totals) => Ok(totals),
+            None => self.refresh_lifetime_totals(),
+        }
+    }
+
+    /// Get daily statistics for a date range
+    pub fn get_daily_stats(&self, start: &str, end: &str) -> Result<Vec<DailyStats>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT d.date, d.total_wh, d.total_cost, d.avg_watts, d.max_watts, d.pricing_mode, COALESCE(d.usage_seconds, 0), d.source, n.note
+             FROM daily_stats d
+             LEFT JOIN day_notes n ON n.date = d.date
+             WHERE d.date >= ?1 AND d.date <= ?2
+             ORDER BY d.date ASC",
+        )?;
+
+        let stats = stmt
+            .query_map(params![start, end], |row| {
+                Ok(DailyStats {
+                    date: row.get(0)?,
+                    total_wh: row.get(1)?,
+                    total_cost: row.get(2)?,
+                    avg_watts: row.get(3)?,
+                    max_watts: row.get(4)?,
+                    pricing_mode: row.get::<_, Option<String>>(5)?.map(|m| m.parse().unwrap_or_else(|e| match e {})),
+                    usage_seconds: row.get(6)?,
+                    source: row.get(7)?,
+                    note: row.get(8)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(stats)
+    }
+
+    /// Compute daily statistics from the readings matching `filter`
+    ///
+    /// Days are local calendar days (the filter hours are local too). Nothing
+    /// is stored, and `source` is left empty.
+    pub fn get_daily_stats_filtered(&self, start: &str, end: &str, filter: &TimeFilter, rate: DailyRate<'_>) -> Result<Vec<DailyStats>> {
+        self.get_daily_stats_filtered_in(start, end, filter, rate, &chrono::Local)
+    }
+
+    /// Same as `get_daily_stats_filtered`, with days and filter hours in the given timezone
+    pub fn get_daily_stats_filtered_in<Tz>(&self, start: &str, end: &str, filter: &TimeFilter, rate: DailyRate<'_>, tz: &Tz) -> Result<Vec<DailyStats>>
+    where
+        Tz: TimeZone + Send + std::panic::UnwindSafe + 'static,
+        Tz::Offset: Send,
+    {
+        self.register_local_time(tz)?;
+        let parse = |date: &str| {
+            chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|e| Error::Database(rusqlite::Error::InvalidParameterName(e.to_string())))
+        };
+        // One day of margin on both sides covers any UTC offset (up to UTC+14 / UTC-12)
+        let start_ts = parse(start)?.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp() - 86400;
+        let end_ts = parse(end)?.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp() + 2 * 86400;
+
+        let sql = format!(
+            "SELECT local_date(timestamp) AS day,
+                    AVG(power_watts), MAX(power_watts), SUM(power_watts)
+             FROM power_readings
+             WHERE timestamp >= ?1 AND timestamp < ?2 AND {}
+             GROUP BY day
+             HAVING day >= ?3 AND day <= ?4
+             ORDER BY day ASC",
+            filter.sql_condition()
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let flat_rate = match rate {
+            DailyRate::Flat(rate_per_kwh) => rate_per_kwh,
+            DailyRate::AtTime(_) => None,
+        };
+
+        let mut stats = stmt
+            .query_map(params![start_ts, end_ts, start, end], |row| {
+                let sum_watts: f64 = row.get(3)?;
+                let total_wh = sum_watts * HOURS_PER_READING;
+                Ok(DailyStats {
+                    date: row.get(0)?,
+                    total_wh,
+                    total_cost: flat_rate.map(|rate| (total_wh / 1000.0) * rate),
+                    avg_watts: row.get(1)?,
+                    max_watts: row.get(2)?,
+                    pricing_mode: None,
+                    usage_seconds: 0,
+                    source: None,
+                    note: None,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect::<Vec<DailyStats>>();
+
+        if let DailyRate::AtTime(rate_at) = rate {
+            let sql = format!(
+                "SELECT local_date(timestamp), timestamp, power_watts
+                 FROM power_readings
+                 WHERE timestamp >= ?1 AND timestamp < ?2 AND {}",
+                filter.sql_condition()
+            );
+            let mut stmt = self.conn.prepare(&sql)?;
+            let readings = stmt.query_map(params![start_ts, end_ts], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, f64>(2)?))
+            })?;
+            let mut costs: HashMap<String, f64> = HashMap::new();
+            for reading in readings {
+                let (day, timestamp, watts) = reading?;
+                *costs.entry(day).or_default() += watts * HOURS_PER_READING / 1000.0 * rate_at(timestamp);
+            }
+            for day in &mut stats {
+                day.total_cost = Some(costs.get(&day.date).copied().unwrap_or(0.0));
+            }
+        }
+
+        let mut notes: HashMap<String, String> = self.get_day_notes(start, end)?.into_iter().map(|n| (n.date, n.note)).collect();
+        let stats = stats
+            .into_iter()
+            .map(|day| DailyStats { note: notes.remove(&day.date), ..day })
+            .collect();
+        Ok(stats)
+    }
+
+    /// Add or replace the note of a local day (YYYY-MM-DD)
+    pub fn set_day_note(&self, date: &str, note: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO day_notes (date, note, created_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(date) DO UPDATE SET note = excluded.note",
+            params![date, note, chrono::Utc::now().timestamp()],
+        )?;
+        self.invalidate_today_stats();
+        Ok(())
+    }
+
+    /// Note of a local day, if any
+    pub fn get_day_note(&self, date: &str) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row("SELECT note FROM day_notes WHERE date = ?1", params![date], |row| row.get(0))
+            .optional()?)
+    }
+
+    /// Notes of the days from `start` to `end` included, oldest first
+    pub fn get_day_notes(&self, start: &str, end: &str) -> Result<Vec<DayNote>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT date, note, created_at FROM day_notes WHERE date >= ?1 AND date <= ?2 ORDER BY date ASC",
+        )?;
+        let notes = stmt
+            .query_map(params![start, end], |row| Ok(DayNote { date: row.get(0)?, note: row.get(1)?, created_at: row.get(2)? }))?
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(notes)
+    }
+
+    /// Delete the note of a local day, false if it had none
+    pub fn delete_day_note(&self, date: &str) -> Result<bool> {
+        let deleted = self.conn.execute("DELETE FROM day_notes WHERE date = ?1", params![date])?;
+        self.invalidate_today_stats();
+        Ok(deleted > 0)
+    }
+
+    /// Clean up old readings (keep only last N days of detailed data)
+    ///
+    /// Applies to power, temperature and VRAM readings, logged alerts and
+    /// accuracy samples, returns the total deleted.
+    pub fn cleanup_old_readings(&self, days_to_keep: u32) -> Result<u64> {
+        let cutoff = chrono::Utc::now().timestamp() - (days_to_keep as i64 * 24 * 60 * 60);
+
+        let deleted = self.conn.execute(
+            "DELETE FROM power_readings WHERE timestamp < ?1",
+            params![cutoff],
+        )?;
+        let deleted_temps = self.conn.execute(
+            "DELETE FROM temperature_readings WHERE timestamp < ?1",
+            params![cutoff],
+        )?;
+
+        let deleted_vram = self.conn.execute(
+            "DELETE FROM vram_readings WHERE timestamp < ?1",
+            params![cutoff],
+        )?;
+        let deleted_alerts = self.conn.execute(
+            "DELETE FROM alerts WHERE timestamp < ?1",
+            params![cutoff],
+        )?;
+        let deleted_samples = self.conn.execute(
+            "DELETE FROM accuracy_samples WHERE timestamp < ?1",
+            params![cutoff],
+        )?;
+
+        Ok((deleted + deleted_temps + deleted_vram + deleted_alerts + deleted_samples) as u64)
+    }
+
+    /// Get total readings count
+    pub fn get_readings_count(&self) -> Result<i64> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM power_readings", [], |row| row.get(0))?;
+        Ok(count)
+    }
+
+    /// Whether daily stats still need rebuilding with local dates (migration 7)
+    pub fn daily_rebuild_pending(&self) -> Result<bool> {
+        let pending = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM pending_tasks WHERE name = ?1)",
+            params![LOCAL_DATES_REBUILD],
+            |row| row.get(0),
+        )?;
+        Ok(pending)
+    }
+
+    /// Record that the local date rebuild of daily stats completed
+    pub fn clear_daily_rebuild_pending(&self) -> Result<()> {
+        self.conn.execute("DELETE FROM pending_tasks WHERE name = ?1", params![LOCAL_DATES_REBUILD])?;
+        Ok(())
+    }
+
+    /// Delete daily stats left without readings between the first and last reading date
+    ///
+    /// Rows keyed by UTC dates whose readings all fall on other local dates.
+    /// Days before the oldest reading (readings pruned) are kept, they cannot be rebuilt.
+    pub fn delete_stale_daily_stats(&self) -> Result<u64> {
+        self.register_local_time(&chrono::Local)?;
+        let deleted = self.conn.execute(
+            "WITH reading_days AS (SELECT DISTINCT local_date(timestamp) AS date FROM power_readings)
+             DELETE FROM daily_stats
+             WHERE date >= (SELECT MIN(date) FROM reading_days)
+               AND date <= (SELECT MAX(date) FROM reading_days)
+               AND date NOT IN (SELECT date FROM reading_days)",
+            [],
+        )?;
+        if deleted > 0 {
+            self.try_refresh_lifetime_totals();
+        }
+        Ok(deleted as u64)
+    }
+
+    /// Compute and update daily stats from power readings for a specific date
+    /// This aggregates all readings for the given local date and updates the daily_stats table
+    /// If `rate_per_kwh` is provided, cost will be calculated as total_kwh * rate
+    pub fn update_daily_stats_for_date(&self, date: &str, pricing_mode: Option<&PricingMode>, rate_per_kwh: Option<f64>) -> Result<Option<DailyStats>> {
+        self.update_daily_stats_for_date_in(date, pricing_mode, DailyRate::Flat(rate_per_kwh), &chrono::Local)
+    }
+
+    /// Same as `update_daily_stats_for_date`, pricing each reading at `rate_at(timestamp)`
+    pub fn update_daily_stats_for_date_at(&self, date: &str, pricing_mode: Option<&PricingMode>, rate_at: &dyn Fn(i64) -> f64) -> Result<Option<DailyStats>> {
+        self.update_daily_stats_for_date_in(date, pricing_mode, DailyRate::AtTime(rate_at), &chrono::Local)
+    }
+
+    /// Same as `update_daily_stats_for_date_at`, without refreshing lifetime totals
+    ///
+    /// For bulk rebuilds, which call `refresh_lifetime_totals` once every date is done.
+    pub fn rebuild_daily_stats_for_date_at(&self, date: &str, pricing_mode: Option<&PricingMode>, rate_at: &dyn Fn(i64) -> f64) -> Result<Option<DailyStats>> {
+        self.compute_daily_stats(date, pricing_mode, DailyRate::AtTime(rate_at), &chrono::Local, false)
+    }
+
+    /// Same as `update_daily_stats_for_date`, with day boundaries in the given timezone
+    pub fn update_daily_stats_for_date_in<Tz: TimeZone>(&self, date: &str, pricing_mode: Option<&PricingMode>, rate: DailyRate<'_>, tz: &Tz) -> Result<Option<DailyStats>> {
+        self.compute_daily_stats(date, pricing_mode, rate, tz, true)
+    }
+
+    fn compute_daily_stats<Tz: TimeZone>(
+        &self,
+        date: &str,
+        pricing_mode: Option<&PricingMode>,
+        rate: DailyRate<'_>,
+        tz: &Tz,
+        refresh_totals: bool,
+    ) -> Result<Option<DailyStats>> {
+        // Local midnight to next local midnight (23 or 25 hours on DST days)
+        let day = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| Error::Database(rusqlite::Error::InvalidParameterName(e.to_string())))?;
+        let next_day = day
+            .succ_opt()
+            .ok_or_else(|| Error::Database(rusqlite::Error::InvalidParameterName(date.to_string())))?;
+        let start_of_day = local_day_start(day, tz);
+        let end_of_day = local_day_start(next_day, tz);
+
+        // Aggregate readings for this date
+        let result: std::result::Result<(f64, f64, f64, i64), rusqlite::Error> = self.conn.query_row(
+            "SELECT
+                COALESCE(AVG(power_watts), 0.0) as avg_watts,
+                COALESCE(MAX(power_watts), 0.0) as max_watts,
+                COALESCE(SUM(power_watts), 0.0) as sum_watts,
+                COUNT(*) as count
+             FROM power_readings
+             WHERE timestamp >= ?1 AND timestamp < ?2",
+            params![start_of_day, end_of_day],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        );
+
+        match result {
+            Ok((avg_watts, max_watts, sum_watts, count)) => {
+                if count == 0 {
+                    return Ok(None);
+                }
+
+                // Estimate total Wh from the sum of power readings
+                let total_wh = sum_watts * HOURS_PER_READING;
+
+                let total_cost = match rate {
+                    DailyRate::Flat(rate_per_kwh) => rate_per_kwh.map(|rate| (total_wh / 1000.0) * rate),
+                    DailyRate::AtTime(rate_at) => {
+                        let mut stmt = self.conn.prepare(
+                            "SELECT timestamp, power_watts FROM power_readings WHERE timestamp >= ?1 AND timestamp < ?2",
+                        )?;
+                        let readings = stmt.query_map(params![start_of_day, end_of_day], |row| {
+                            Ok((row.get::<_, i64>(0)?, row.get::<_, f64>(1)?))
+                        })?;
+                        let mut cost = 0.0;
+                        for reading in readings {
+                            let (timestamp, watts) = reading?;
+                            cost += watts * HOURS_PER_READING / 1000.0 * rate_at(timestamp);
+                        }
+                        Some(cost)
+                    }
+                };
+
+                // Most frequent source of the day (mode)
+                let source: Option<String> = self.conn.query_row(
+                    "SELECT source FROM power_readings
+                     WHERE timestamp >= ?1 AND timestamp < ?2
+                     GROUP BY source
+                     ORDER BY COUNT(*) DESC, MAX(timestamp) DESC
+                     LIMIT 1",
+                    params![start_of_day, end_of_day],
+                    |row| row.get(0),
+                ).ok();
+
+                let stats = DailyStats {
+                    date: date.to_string(),
+                    total_wh,
+                    total_cost,
+                    avg_watts,
+                    max_watts,
+                    pricing_mode: pricing_mode.cloned(),
+                    usage_seconds: 0,
+                    source,
+                    note: self.get_day_note(date)?,
+                };
+
+                self.write_daily_stats(&stats)?;
+                if refresh_totals {
+                    self.try_refresh_lifetime_totals();
+                }
+                Ok(Some(stats))
+            }
+            Err(e) => Err(Error::Database(e)),
+        }
+    }
+
+    /// Update daily stats for today based on current readings
+    ///
+    /// Runs at most once every `TODAY_STATS_MIN_INTERVAL_SECS` for the same
+    /// day, whoever calls it: in between, the stats last computed are returned.
+    pub fn update_today_stats(&self, pricing_mode: Option<&PricingMode>, rate_per_kwh: Option<f64>) -> Result<Option<DailyStats>> {
+        self.update_today_stats_at(chrono::Local::now(), pricing_mode, rate_per_kwh)
+    }
+
+    /// Same as `update_today_stats` with `now` as the current time
+    pub fn update_today_stats_at(
+        &self,
+        now: chrono::DateTime<chrono::Local>,
+        pricing_mode: Option<&PricingMode>,
+        rate_per_kwh: Option<f64>,
+    ) -> Result<Option<DailyStats>> {
+        let today = now.format("%Y-%m-%d").to_string();
+        let now = now.timestamp();
+        if let Some(last) = self.today_stats.borrow().as_ref() {
+            let age = now - last.computed_at;
+            if last.date == today && (0..TODAY_STATS_MIN_INTERVAL_SECS).contains(&age) {
+                return Ok(last.stats.clone());
+            }
+        }
+
+        let stats = self.update_daily_stats_for_date(&today, pricing_mode, rate_per_kwh)?;
+        *self.today_stats.borrow_mut() = Some(TodayStats { computed_at: now, date: today, stats: stats.clone() });
+        Ok(stats)
+    }
+
+    /// Make the next `update_today_stats` aggregate again, after daily stats were rewritten
+    pub fn invalidate_today_stats(&self) {
+        self.today_stats.borrow_mut().take();
+    }
+
+    /// Local dates (YYYY-MM-DD) that have readings, optionally limited to `start..=end`
+    pub fn reading_dates(&self, start: Option<&str>, end: Option<&str>) -> Result<Vec<String>> {
+        self.reading_dates_in(start, end, &chrono::Local)
+    }
+
+    /// Same as `reading_dates`, deriving dates in the given timezone
+    pub fn reading_dates_in<Tz>(&self, start: Option<&str>, end: Option<&str>, tz: &Tz) -> Result<Vec<String>>
+    where
+        Tz: TimeZone + Send + std::panic::UnwindSafe + 'static,
+        Tz::Offset: Send,
+    {
+        self.register_local_time(tz)?;
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT local_date(timestamp) as reading_date
+             FROM power_readings
+             ORDER BY reading_date ASC"
+        )?;
+
+        let dates = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .filter_map(|r| r.ok())
+            .filter(|d| start.map_or(true, |s| d.as_str() >= s) && end.map_or(true, |e| d.as_str() <= e))
+            .collect();
+
+        Ok(dates)
+    }
+
+    /// Per-hour energy and cost of a local date, hours without readings are left out
+    pub fn get_hourly_stats(&self, date: NaiveDate, rate_at: &dyn Fn(i64) -> f64) -> Result<Vec<HourlyStats>> {
+        self.get_hourly_stats_in(date, rate_at, &chrono::Local)
+    }
+
+    /// Same as `get_hourly_stats`, in the given timezone
+    pub fn get_hourly_stats_in<Tz: TimeZone>(&self, date: NaiveDate, rate_at: &dyn Fn(i64) -> f64, tz: &Tz) -> Result<Vec<HourlyStats>> {
+        let Some(next_day) = date.succ_opt() else {
+            return Ok(Vec::new());
+        };
+        let mut stmt = self.conn.prepare(
+            "SELECT timestamp, power_watts FROM power_readings
+             WHERE timestamp >= ?1 AND timestamp < ?2
+             ORDER BY timestamp ASC",
+        )?;
+        let readings = stmt.query_map(params![local_day_start(date, tz), local_day_start(next_day, tz)], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, f64>(1)?))
+        })?;
+
+        let mut hours: Vec<HourlyStats> = Vec::new();
+        for reading in readings {
+            let (timestamp, watts) = reading?;
+            let Some(hour) = tz.timestamp_opt(timestamp, 0).single().map(|dt| dt.hour()) else {
+                continue;
+            };
+            let wh = watts * HOURS_PER_READING;
+            match hours.iter_mut().find(|h| h.hour == hour) {
+                Some(stats) => {
+                    stats.total_wh += wh;
+                    stats.total_cost += wh / 1000.0 * rate_at(timestamp);
+                }
+                None => hours.push(HourlyStats { hour, total_wh: wh, total_cost: wh / 1000.0 * rate_at(timestamp) }),
+            }
+        }
+        hours.sort_by_key(|h| h.hour);
+        Ok(hours)
+    }
+
+    /// Rebuild daily stats for all dates that have readings
+    pub fn rebuild_all_daily_stats(&self, pricing_mode: Option<&PricingMode>, rate_per_kwh: Option<f64>) -> Result<u32> {
+        self.invalidate_today_stats();
+        let dates = self.reading_dates(None, None)?;
+
+        let mut count = 0;
+        for date in dates {
+            if self.update_daily_stats_for_date(&date, pricing_mode, rate_per_kwh)?.is_some() {
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Add usage seconds for a given date (accumulates)
+    pub fn add_usage_seconds(&self, date: &str, seconds: i64) -> Result<()> {
+        // First ensure the row exists
+        self.conn.execute(
+            "INSERT INTO daily_stats (date, total_wh, total_cost, avg_watts, max_watts, pricing_mode, usage_seconds)
+             VALUES (?1, 0.0, NULL, 0.0, 0.0, NULL, ?2)
+             ON CONFLICT(date) DO UPDATE SET usage_seconds = COALESCE(usage_seconds, 0) + ?2",
+            params![date, seconds],
+        )?;
+        Ok(())
+    }
+
+    /// Register `local_date`, `local_hour` and `local_weekday(timestamp)` (0 = Sunday) in `tz`
+    ///
+    /// SQLite only knows UTC and the system zone, so local times come from
+    /// functions backed by chrono. Each call replaces the previous zone.
+    fn register_local_time<Tz>(&self, tz: &Tz) -> Result<()>
+    where
+        Tz: TimeZone + Send + std::panic::UnwindSafe + 'static,
+        Tz::Offset: Send,
+    {
+        let flags = FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC;
+        let zone = tz.clone();
+        self.conn.create_scalar_function("local_date", 1, flags, move |ctx| {
+            let ts: i64 = ctx.get(0)?;
+            let dt = zone.timestamp_opt(ts, 0).single();
+            Ok(dt.map(|dt| dt.date_naive().format("%Y-%m-%d").to_string()))
+        })?;
+        let zone = tz.clone();
+        self.conn.create_scalar_function("local_hour", 1, flags, move |ctx| {
+            let ts: i64 = ctx.get(0)?;
+            Ok(zone.timestamp_opt(ts, 0).single().map(|dt| dt.hour()))
+        })?;
+        let zone = tz.clone();
+        self.conn.create_scalar_function("local_weekday", 1, flags, move |ctx| {
+            let ts: i64 = ctx.get(0)?;
+            Ok(zone.timestamp_opt(ts, 0).single().map(|dt| dt.weekday().num_days_from_sunday()))
+        })?;
+        Ok(())
+    }
+
+    // ===== Hardware Inventory =====
+
+    /// Store the inventory if it differs from the latest snapshot, returns whether a row was added
+    pub fn record_hardware_snapshot(&self, inventory: &HardwareInventory) -> Result<bool> {
+        if self.get_latest_hardware_snapshot()?.is_some_and(|s| s.inventory == *inventory) {
+            return Ok(false);
+        }
+        let json = serde_json::to_string(inventory).map_err(|e| Error::Serialization(e.to_string()))?;
+        self.conn.execute(
+            "INSERT INTO hardware_snapshots (timestamp, inventory) VALUES (?1, ?2)",
+            params![chrono::Utc::now().timestamp(), json],
+        )?;
+        Ok(true)
+    }
+
+    /// Most recent hardware inventory
+    pub fn get_latest_hardware_snapshot(&self) -> Result<Option<HardwareSnapshot>> {
+        let result = self.conn.query_row(
+            "SELECT timestamp, inventory FROM hardware_snapshots ORDER BY id DESC LIMIT 1",
+            [],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+        );
+
+        match result {
+            Ok((timestamp, json)) => {
+                let inventory = serde_json::from_str(&json).map_err(|e| Error::Serialization(e.to_string()))?;
+                Ok(Some(HardwareSnapshot { timestamp, inventory }))
+            }
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(Error::Database(e)),
+        }
+    }
+
+    // ===== Estimation Accuracy =====
+
+    /// Store a measured and estimated power pair
+    pub fn insert_accuracy_sample(&self, sample: &AccuracySample) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO accuracy_samples (timestamp, measured_watts, estimated_watts) VALUES (?1, ?2, ?3)",
+            params![sample.timestamp, sample.measured_watts, sample.estimated_watts],
+        )?;
+        Ok(())
+    }
+
+    /// The `limit` most recent accuracy samples, newest first
+    pub fn get_accuracy_samples(&self, limit: u32) -> Result<Vec<AccuracySample>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT timestamp, measured_watts, estimated_watts FROM accuracy_samples ORDER BY id DESC LIMIT ?1",
+        )?;
+        let samples = stmt
+            .query_map(params![limit], |row| {
+                Ok(AccuracySample {
+                    timestamp: row.get(0)?,
+                    measured_watts: row.get(1)?,
+                    estimated_watts: row.get(2)?,
+                })
+            })?
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(samples)
+    }
+
+    // ===== Session Management =====
+
+    /// Map a row selected with the standard session columns
+    fn session_from_row(row: &rusqlite::Row) -> rusqlite::Result<Session> {
+        let mut session = Session {
+            id: Some(row.get(0)?),
+            start_time: row.get(1)?,
+            end_time: row.get(2)?,
+            baseline_watts: row.get(3)?,
+            total_wh: row.get(4)?,
+            surplus_wh: row.get(5)?,
+            surplus_cost: row.get(6)?,
+            label: row.get(7)?,
+            category: row.get(8)?,
+            target_cost: row.get(9)?,
+            target_wh: row.get(10)?,
+            target_progress_percent: None,
+            target_met: row.get(11)?,
+            auto_ended: row.get(12)?,
+            continued_from: row.get(13)?,
+            baseline_bucket: row.get(14)?,
+            duration_secs: 0,
+            avg_watts: None,
+            avg_surplus_watts: None,
+            cost_per_hour: None,
+        };
+        session.target_progress_percent = session.compute_target_progress();
+        session.compute_rates(chrono::Utc::now().timestamp());
+        Ok(session)
+    }
+
+    /// Start a new tracking session
+    pub fn start_session(&self, baseline_watts: f64, label: Option<&str>) -> Result<i64> {
+        self.start_session_with_target(baseline_watts, label, None, None)
+    }
+
+    /// Start a new tracking session with optional cost/energy targets
+    ///
+    /// Fails with `Error::SessionActive` while a session has not ended. The
+    /// check and the insert share a write transaction, so two concurrent starts
+    /// (from this process or another one) cannot both succeed.
+    pub fn start_session_with_target(
+        &self,
+        baseline_watts: f64,
+        label: Option<&str>,
+        target_cost: Option<f64>,
+        target_wh: Option<f64>,
+    ) -> Result<i64> {
+        let now = chrono::Utc::now().timestamp();
+
+        let tx = Transaction::new_unchecked(&self.conn, TransactionBehavior::Immediate)?;
+        let open: Option<i64> = tx
+            .query_row("SELECT id FROM sessions WHERE end_time IS NULL LIMIT 1", [], |row| row.get(0))
+            .optional()?;
+        if let Some(id) = open {
+            return Err(Error::SessionActive(id));
+        }
+        tx.execute(
+            "INSERT INTO sessions (start_time, baseline_watts, total_wh, surplus_wh, surplus_cost, label, target_cost, target_wh)
+             VALUES (?1, ?2, 0.0, 0.0, 0.0, ?3, ?4, ?5)",
+            params![now, baseline_watts, label, target_cost, target_wh],
+        )?;
+        let id = tx.last_insert_rowid();
+        tx.commit()?;
+
+        Ok(id)
+    }
+
+    /// Move the start of a running session, after the wall clock jumped backwards
+    pub fn reanchor_session_start(&self, session_id: i64, start_time: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sessions SET start_time = ?1 WHERE id = ?2 AND end_time IS NULL",
+            params![start_time, session_id],
+        )?;
+        Ok(())
+    }
+
+    /// End a tracking session, recording whether its target (if any) was met
+    ///
+    /// The end time is never before the start, should the wall clock have gone back.
+    pub fn end_session(&self, session_id: i64, total_wh: f64, surplus_wh: f64, surplus_cost: f64) -> Result<Option<Session>> {
+        let now = chrono::Utc::now().timestamp();
+
+        self.conn.execute(
+            "UPDATE sessions SET end_time = MAX(?1, start_time), total_wh = ?2, surplus_wh = ?3, surplus_cost = ?4
+             WHERE id = ?5",
+            params![now, total_wh, surplus_wh, surplus_cost, session_id],
+        )?;
+        self.try_refresh_lifetime_totals();
+
+        self.record_target_result(session_id)
+    }
+
+    /// End a session that went idle, backdating it to when the inactivity began
+    ///
+    /// The totals are those snapshotted at `end_time`, so the idle stretch is not billed.
+    pub fn auto_end_session(
+        &self,
+        session_id: i64,
+        end_time: i64,
+        total_wh: f64,
+        surplus_wh: f64,
+        surplus_cost: f64,
+    ) -> Result<Option<Session>> {
+        self.conn.execute(
+            "UPDATE sessions SET end_time = MAX(?1, start_time), total_wh = ?2, surplus_wh = ?3, surplus_cost = ?4, auto_ended = 1
+             WHERE id = ?5",
+            params![end_time, total_wh, surplus_wh, surplus_cost, session_id],
+        )?;
+        self.try_refresh_lifetime_totals();
+
+        self.record_target_result(session_id)
+    }
+
+    /// End a running session at `at` and start its continuation there, in one transaction
+    ///
+    /// The continuation keeps the baseline, label, category and targets, and
+    /// references the session it continues. Returns its id, None when the
+    /// session does not exist or has already ended.
+    pub fn split_session(
+        &self,
+        session_id: i64,
+        at: i64,
+        total_wh: f64,
+        surplus_wh: f64,
+        surplus_cost: f64,
+    ) -> Result<Option<i64>> {
+        let tx = self.conn.unchecked_transaction()?;
+        let ended = tx.execute(
+            "UPDATE sessions SET end_time = MAX(?1, start_time), total_wh = ?2, surplus_wh = ?3, surplus_cost = ?4
+             WHERE id = ?5 AND end_time IS NULL",
+            params![at, total_wh, surplus_wh, surplus_cost, session_id],
+        )?;
+        if ended == 0 {
+            return Ok(None);
+        }
+        tx.execute(
+            "INSERT INTO sessions (start_time, baseline_watts, total_wh, surplus_wh, surplus_cost, label, category,
+                                   target_cost, target_wh, last_heartbeat, continued_from, baseline_bucket)
+             SELECT MAX(?1, end_time), baseline_watts, 0.0, 0.0, 0.0, label, category, target_cost, target_wh, MAX(?1, end_time), id,
+                    baseline_bucket
+             FROM sessions WHERE id = ?2",
+            params![at, session_id],
+        )?;
+        let continuation = tx.last_insert_rowid();
+        tx.commit()?;
+        Ok(Some(continuation))
+    }
+
+    /// Store whether an ended session met its target, returns the updated session
+    fn record_target_result(&self, session_id: i64) -> Result<Option<Session>> {
+        let session = self.get_session(session_id)?;
+        if let Some(mut session) = session {
+            if let Some(progress) = session.target_progress_percent {
+                let met = progress <= 100.0;
+                self.conn.execute(
+                    "UPDATE sessions SET target_met = ?1 WHERE id = ?2",
+                    params![met, session_id],
+                )?;
+                session.target_met = Some(met);
+            }
+            return Ok(Some(session));
+        }
+        Ok(None)
+    }
+
+    /// Persist the running totals of an active session and mark it alive
+    pub fn heartbeat_session(&self, session_id: i64, total_wh: f64, surplus_wh: f64, surplus_cost: f64) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        self.conn.execute(
+            "UPDATE sessions SET total_wh = ?1, surplus_wh = ?2, surplus_cost = ?3, last_heartbeat = ?4 WHERE id = ?5",
+            params![total_wh, surplus_wh, surplus_cost, now, session_id],
+        )?;
+        Ok(())
+    }
+
+    /// Store the top foreground apps of a session
+    pub fn set_session_top_apps(&self, session_id: i64, apps: &[AppUsage]) -> Result<()> {
+        let json = serde_json::to_string(apps).map_err(|e| Error::Serialization(e.to_string()))?;
+        self.conn.execute(
+            "UPDATE sessions SET top_apps = ?1 WHERE id = ?2",
+            params![json, session_id],
+        )?;
+        Ok(())
+    }
+
+    /// Top foreground apps of a session (empty when not tracked)
+    pub fn get_session_top_apps(&self, session_id: i64) -> Result<Vec<AppUsage>> {
+        let json: Option<String> = match self.conn.query_row(
+            "SELECT top_apps FROM sessions WHERE id = ?1",
+            params![session_id],
+            |row| row.get(0),
+        ) {
+            Ok(json) => json,
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(Error::Database(e)),
+        };
+
+        match json {
+            Some(json) => serde_json::from_str(&json).map_err(|e| Error::Serialization(e.to_string())),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Re-derive the surplus of an ended session from its readings with another baseline
+    ///
+    /// Each reading is priced at `rate_at(timestamp)`. The previous baseline and
+    /// surplus are appended to the session's audit trail. The app's own draw is
+    /// not stored with readings, so it is never excluded here. Returns None when
+    /// the session does not exist or has not ended. A session whose readings
+    /// were all pruned has nothing to re-derive from and is returned unchanged.
+    pub fn recalculate_session_surplus(
+        &self,
+        session_id: i64,
+        baseline_watts: f64,
+        rate_at: &dyn Fn(i64) -> f64,
+    ) -> Result<Option<Session>> {
+        let Some(session) = self.get_session(session_id)? else {
+            return Ok(None);
+        };
+        let Some(end_time) = session.end_time else {
+            return Ok(None);
+        };
+
+        let mut surplus_wh = 0.0;
+        let mut surplus_cost = 0.0;
+        let mut reading_count = 0usize;
+        {
+            let mut stmt = self.conn.prepare(
+                "SELECT timestamp, power_watts FROM power_readings WHERE timestamp >= ?1 AND timestamp <= ?2",
+            )?;
+            let readings = stmt.query_map(params![session.start_time, end_time], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, f64>(1)?))
+            })?;
+            for reading in readings {
+                let (timestamp, watts) = reading?;
+                let wh = (watts - baseline_watts).max(0.0) * HOURS_PER_READING;
+                surplus_wh += wh;
+                surplus_cost += wh / 1000.0 * rate_at(timestamp);
+                reading_count += 1;
+            }
+        }
+        if reading_count == 0 {
+            return Ok(Some(session));
+        }
+
+        let mut audit = self.get_session_surplus_audit(session_id)?;
+        audit.push(SurplusAudit {
+            recalculated_at: chrono::Utc::now().timestamp(),
+            baseline_watts: session.baseline_watts,
+            surplus_wh: session.surplus_wh,
+            surplus_cost: session.surplus_cost,
+        });
+        let audit = serde_json::to_string(&audit).map_err(|e| Error::Serialization(e.to_string()))?;
+
+        self.conn.execute(
+            "UPDATE sessions SET baseline_watts = ?1, surplus_wh = ?2, surplus_cost = ?3, surplus_audit = ?4 WHERE id = ?5",
+            params![baseline_watts, surplus_wh, surplus_cost, audit, session_id],
+        )?;
+
+        self.record_target_result(session_id)
+    }
+
+    /// `recalculate_session_surplus` of each session in `session_ids` with its own baseline
+    ///
+    /// For sessions that lost readings, see `dedup_readings`.
+    pub fn recalculate_sessions_surplus_in_place(&self, session_ids: &[i64], rate_at: &dyn Fn(i64) -> f64) -> Result<()> {
+        for &id in session_ids {
+            if let Some(session) = self.get_session(id)? {
+                self.recalculate_session_surplus(id, session.baseline_watts, rate_at)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Previous surplus values of a session, oldest recalculation first
+    pub fn get_session_surplus_audit(&self, session_id: i64) -> Result<Vec<SurplusAudit>> {
+        let json: Option<String> = match self.conn.query_row(
+            "SELECT surplus_audit FROM sessions WHERE id = ?1",
+            params![session_id],
+            |row| row.get(0),
+        ) {
+            Ok(json) => json,
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(Error::Database(e)),
+        };
+
+        match json {
+            Some(json) => serde_json::from_str(&json).map_err(|e| Error::Serialization(e.to_string())),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Remove the recorded foreground apps from every session
+    pub fn clear_session_top_apps(&self) -> Result<usize> {
+        let cleared = self.conn.execute("UPDATE sessions SET top_apps = NULL WHERE top_apps IS NOT NULL", [])?;
+        Ok(cleared)
+    }
+
+    /// Close sessions left open by a crash or power loss
+    ///
+    /// The end time is capped at the last heartbeat (or the start time when the
+    /// session never got one, or when the clock went back since it started), and
+    /// the totals are those of the last heartbeat.
+    pub fn recover_orphaned_sessions(&self) -> Result<Vec<Session>> {
+        self.force_close_stale_sessions(i64::MAX)
+    }
+
+    /// Close the sessions left open whose last sign of life (heartbeat, or start
+    /// without one) is before `before_ts`, the same way as `recover_orphaned_sessions`
+    pub fn force_close_stale_sessions(&self, before_ts: i64) -> Result<Vec<Session>> {
+        let ids: Vec<i64> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT id FROM sessions WHERE end_time IS NULL AND COALESCE(last_heartbeat, start_time) < ?1",
+            )?;
+            let ids = stmt.query_map(params![before_ts], |row| row.get(0))?
+                .filter_map(|r| r.ok())
+                .collect();
+            ids
+        };
+
+        let mut recovered = Vec::with_capacity(ids.len());
+        for id in ids {
+            self.conn.execute(
+                "UPDATE sessions SET end_time = MAX(COALESCE(last_heartbeat, start_time), start_time) WHERE id = ?1",
+                params![id],
+            )?;
+            if let Some(session) = self.record_target_result(id)? {
+                recovered.push(session);
+            }
+        }
+        if !recovered.is_empty() {
+            self.try_refresh_lifetime_totals();
+        }
+
+        Ok(recovered)
+    }
+
+    /// Get a specific session by ID
+    pub fn get_session(&self, session_id: i64) -> Result<Option<Session>> {
+        let result = self.conn.query_row(
+            "SELECT id, start_time, end_time, baseline_watts, total_wh, surplus_wh, surplus_cost, label, category,
+                    target_cost, target_wh, target_met, auto_ended, continued_from, baseline_bucket
+             FROM sessions WHERE id = ?1",
+            params![session_id],
+            Self::session_from_row,
+        );
+
+        match result {
+            Ok(session) => Ok(Some(session)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(Error::Database(e)),
+        }
+    }
+
+    /// Get all sessions, optionally limited
+    pub fn get_sessions(&self, limit: Option<u32>) -> Result<Vec<Session>> {
+        let query = match limit {
+            Some(n) => format!(
+                "SELECT id, start_time, end_time, baseline_watts, total_wh, surplus_wh, surplus_cost, label, category,
+                    target_cost, target_wh, target_met, auto_ended, continued_from, baseline_bucket
+                 FROM sessions ORDER BY start_time DESC LIMIT {}", n
+            ),
+            None => "SELECT id, start_time, end_time, baseline_watts, total_wh, surplus_wh, surplus_cost, label, category,
+                    target_cost, target_wh, target_met, auto_ended, continued_from, baseline_bucket
+                     FROM sessions ORDER BY start_time DESC".to_string(),
+        };
+
+        let mut stmt = self.conn.prepare(&query)?;
+
+        let sessions = stmt
+            .query_map([], Self::session_from_row)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(sessions)
+    }
+
+    /// Get all sessions with continuation chains merged into their first session, optionally limited
+    pub fn get_merged_sessions(&self, limit: Option<u32>) -> Result<Vec<Session>> {
+        let mut sessions = merge_continuations(self.get_sessions(None)?);
+        if let Some(n) = limit {
+            sessions.truncate(n as usize);
+        }
+        Ok(sessions)
+    }
+
+    /// The session `session_id` belongs to, merged with the parts it continues
+    ///
+    /// For the last part of a session split at midnight: reported like in
+    /// `get_merged_sessions`, under the first part's id with the summed totals.
+    pub fn get_merged_session(&self, session_id: i64) -> Result<Option<Session>> {
+        let mut parts = Vec::new();
+        let mut next = Some(session_id);
+        while let Some(id) = next {
+            // Bounded, should the links ever form a cycle
+            if parts.len() > MAX_SESSION_PARTS {
+                break;
+            }
+            let Some(part) = self.get_session(id)? else {
+                break;
+            };
+            next = part.continued_from;
+            parts.push(part);
+        }
+        Ok(merge_continuations(parts).into_iter().last())
+    }
+
+    /// Update session statistics (called during active session)
+    pub fn update_session_stats(&self, session_id: i64, total_wh: f64, surplus_wh: f64, surplus_cost: f64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sessions SET total_wh = ?1, surplus_wh = ?2, surplus_cost = ?3 WHERE id = ?4",
+            params![total_wh, surplus_wh, surplus_cost, session_id],
+        )?;
+        Ok(())
+    }
+
+    /// Get the most recent active (unended) session
+    pub fn get_active_session(&self) -> Result<Option<Session>> {
+        let result = self.conn.query_row(
+            "SELECT id, start_time, end_time, baseline_watts, total_wh, surplus_wh, surplus_cost, label, category,
+                    target_cost, target_wh, target_met, auto_ended, continued_from, baseline_bucket
+             FROM sessions WHERE end_time IS NULL ORDER BY start_time DESC LIMIT 1",
+            [],
+            Self::session_from_row,
+        );
+
+        match result {
+            Ok(session) => Ok(Some(session)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(Error::Database(e)),
+        }
+    }
+
+    /// Update session label
+    pub fn update_session_label(&self, session_id: i64, label: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sessions SET label = ?1 WHERE id = ?2",
+            params![label, session_id],
+        )?;
+        Ok(())
+    }
+
+    /// Update session category
+    pub fn update_session_category(&self, session_id: i64, category: Option<&str>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sessions SET category = ?1 WHERE id = ?2",
+            params![category, session_id],
+        )?;
+        Ok(())
+    }
+
+    /// Record the baseline schedule bucket a session started in
+    pub fn set_session_baseline_bucket(&self, session_id: i64, bucket: Option<&str>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sessions SET baseline_bucket = ?1 WHERE id = ?2",
+            params![bucket, session_id],
+        )?;
+        Ok(())
+    }
+
+    /// Delete a session by ID
+    pub fn delete_session(&self, session_id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM sessions WHERE id = ?1", params![session_id])?;
+        self.try_refresh_lifetime_totals();
+        Ok(())
+    }
+
+    /// Move sessions from one category name to another, returns the number of sessions updated
+    pub fn rename_session_category(&self, old_name: &str, new_name: &str) -> Result<u64> {
+        let updated = self.conn.execute(
+            "UPDATE sessions SET category = ?2 WHERE category = ?1",
+            params![old_name, new_name],
+        )?;
+        Ok(updated as u64)
+    }
+
+    /// Set the category of every session matching `filter` in one UPDATE, returns the number matched
+    ///
+    /// With `dry_run`, sessions are only counted.
+    pub fn bulk_update_session_category(&self, filter: &SessionCategoryFilter, category: Option<&str>, dry_run: bool) -> Result<u64> {
+        if dry_run {
+            let (condition, values) = filter.sql_condition(1);
+            let count: i64 = self.conn.query_row(
+                &format!("SELECT COUNT(*) FROM sessions WHERE {}", condition),
+                rusqlite::params_from_iter(values),
+                |row| row.get(0),
+            )?;
+            return Ok(count as u64);
+        }
+
+        let (condition, values) = filter.sql_condition(2);
+        let category = category.map_or(Value::Null, |name| Value::Text(name.to_string()));
+        let updated = self.conn.execute(
+            &format!("UPDATE sessions SET category = ?1 WHERE {}", condition),
+            rusqlite::params_from_iter(std::iter::once(category).chain(values)),
+        )?;
+        Ok(updated as u64)
+    }
+
+    /// Delete statistics in the given scope, in a single transaction
+    pub fn reset(&self, scope: ResetScope) -> Result<ResetCounts> {
+        self.invalidate_today_stats();
+        let tx = self.conn.unchecked_transaction()?;
+        let mut counts = ResetCounts::default();
+
+        if scope.includes_readings() {
+            counts.readings = tx.execute("DELETE FROM power_readings", [])? as u64;
+            counts.temperature_readings = tx.execute("DELETE FROM temperature_readings", [])? as u64;
+            counts.vram_readings = tx.execute("DELETE FROM vram_readings", [])? as u64;
+            counts.alerts = tx.execute("DELETE FROM alerts", [])? as u64;
+            counts.accuracy_samples = tx.execute("DELETE FROM accuracy_samples", [])? as u64;
+            counts.source_changes = tx.execute("DELETE FROM source_changes", [])? as u64;
+        }
+        if scope.includes_daily_stats() {
+            counts.daily_stats = tx.execute("DELETE FROM daily_stats", [])? as u64;
+        }
+        if scope.includes_sessions() {
+            counts.sessions = tx.execute("DELETE FROM sessions", [])? as u64;
+        }
+        if scope == ResetScope::All {
+            counts.day_notes = tx.execute("DELETE FROM day_notes", [])? as u64;
+        }
+
+        tx.commit()?;
+        self.try_refresh_lifetime_totals();
+        Ok(counts)
+    }
+
+    /// Copy every row of this database into `target`, in a single transaction
+    ///
+    /// Used to keep what an in-memory database gathered once the data file opens.
+    /// Daily stats of the copied days are rebuilt from the merged readings.
+    pub fn copy_into(&self, target: &Database, pricing_mode: Option<&PricingMode>, rate_per_kwh: Option<f64>) -> Result<CopiedRows> {
+        let tx = target.conn.unchecked_transaction()?;
+        let mut copied = CopiedRows::default();
+        for table in COPIED_TABLES {
+            copied.rows += self.copy_table(target, table, None)?.len() as u64;
+        }
+        copied.session_ids = self.copy_table(target, "sessions", Some("continued_from"))?;
+        copied.rows += copied.session_ids.len() as u64;
+        // Keyed by date, a note already in the target is kept
+        for note in self.get_day_notes("0000-00-00", "9999-99-99")? {
+            copied.rows += target.conn.execute(
+                "INSERT OR IGNORE INTO day_notes (date, note, created_at) VALUES (?1, ?2, ?3)",
+                params![note.date, note.note, note.created_at],
+            )? as u64;
+        }
+
+        // Usage time is only kept in daily_stats
+        let days: Vec<(String, i64)> = self
+            .conn
+            .prepare("SELECT date, COALESCE(usage_seconds, 0) FROM daily_stats")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<_, _>>()?;
+        for (date, usage_seconds) in days {
+            target.compute_daily_stats(&date, pricing_mode, DailyRate::Flat(rate_per_kwh), &chrono::Local, false)?;
+            if usage_seconds > 0 {
+                target.add_usage_seconds(&date, usage_seconds)?;
+            }
+        }
+
+        tx.commit()?;
+        target.try_refresh_lifetime_totals();
+        Ok(copied)
+    }
+
+    /// Copy the rows of `table` without their ids, returns the new id of each row
+    ///
+    /// `parent_column` holds ids of the same table, rewritten to the new ids.
+    fn copy_table(&self, target: &Database, table: &str, parent_column: Option<&str>) -> Result<HashMap<i64, i64>> {
+        let columns: Vec<String> = self
+            .conn
+            .prepare(&format!("PRAGMA table_info({})", table))?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<std::result::Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|c| c != "id")
+            .collect();
+        let list = columns.join(", ");
+        let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("?{}", i)).collect();
+        let parent = parent_column.and_then(|p| columns.iter().position(|c| c == p));
+
+        let mut insert = target.conn.prepare(&format!("INSERT INTO {} ({}) VALUES ({})", table, list, placeholders.join(", ")))?;
+        let mut select = self.conn.prepare(&format!("SELECT id, {} FROM {} ORDER BY id", list, table))?;
+        let mut rows = select.query([])?;
+        let mut ids = HashMap::new();
+        while let Some(row) = rows.next()? {
+            let old_id: i64 = row.get(0)?;
+            let mut values: Vec<Value> = (1..=columns.len()).map(|i| row.get(i)).collect::<std::result::Result<_, _>>()?;
+            if let Some(i) = parent {
+                if let Value::Integer(parent_id) = values[i] {
+                    if let Some(&new_id) = ids.get(&parent_id) {
+                        values[i] = Value::Integer(new_id);
+                    }
+                }
+            }
+            insert.execute(rusqlite::params_from_iter(values))?;
+            ids.insert(old_id, target.conn.last_insert_rowid());
+        }
+        Ok(ids)
+    }
+
+    /// Get sessions in a range grouped by local start date, most recent day first
+    pub fn get_sessions_grouped_by_day(&self, start_timestamp: i64, end_timestamp: i64) -> Result<Vec<SessionDay>> {
+        self.get_sessions_grouped_by_day_in(start_timestamp, end_timestamp, &chrono::Local)
+    }
+
+    /// Same as `get_sessions_grouped_by_day`, deriving dates in the given timezone
+    ///
+    /// Sessions crossing midnight belong to the day they started on.
+    pub fn get_sessions_grouped_by_day_in<Tz>(&self, start_timestamp: i64, end_timestamp: i64, tz: &Tz) -> Result<Vec<SessionDay>>
+    where
+        Tz: TimeZone + Send + std::panic::UnwindSafe + 'static,
+        Tz::Offset: Send,
+    {
+        self.register_local_time(tz)?;
+
+        let mut days: Vec<SessionDay> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT local_date(start_time) AS day, SUM(COALESCE(surplus_wh, 0)), SUM(COALESCE(surplus_cost, 0))
+                 FROM sessions
+                 WHERE start_time >= ?1 AND start_time <= ?2
+                 GROUP BY day
+                 ORDER BY day DESC",
+            )?;
+            let days = stmt
+                .query_map(params![start_timestamp, end_timestamp], |row| {
+                    Ok(SessionDay {
+                        date: row.get(0)?,
+                        sessions: Vec::new(),
+                        total_surplus_wh: row.get(1)?,
+                        total_surplus_cost: row.get(2)?,
+                    })
+                })?
+                .filter_map(|r| r.ok())
+                .collect();
+            days
+        };
+
+        let mut stmt = self.conn.prepare(
+            "SELECT id, start_time, end_time, baseline_watts, total_wh, surplus_wh, surplus_cost, label, category,
+                    target_cost, target_wh, target_met, auto_ended, continued_from, baseline_bucket, local_date(start_time)
+             FROM sessions
+             WHERE start_time >= ?1 AND start_time <= ?2
+             ORDER BY start_time DESC",
+        )?;
+        let rows = stmt.query_map(params![start_timestamp, end_timestamp], |row| {
+            Ok((row.get::<_, String>(15)?, Self::session_from_row(row)?))
+        })?;
+
+        for (date, session) in rows.filter_map(|r| r.ok()) {
+            if let Some(day) = days.iter_mut().find(|d| d.date == date) {
+                day.sessions.push(session);
+            }
+        }
+
+        Ok(days)
+    }
+
+    /// Monthly totals of the ended sessions of a category, over the last `months` local months
+    pub fn get_category_trend(&self, category: &str, months: u32) -> Result<Vec<CategoryTrendPoint>> {
+        self.get_category_trend_in(category, months, chrono::Utc::now().timestamp(), &chrono::Local)
+    }
+
+    /// Same as `get_category_trend`, ending at the month of `now` in the given timezone
+    ///
+    /// Oldest month first. Months without sessions are included with zeros;
+    /// sessions crossing a month boundary belong to the month they started in.
+    pub fn get_category_trend_in<Tz>(&self, category: &str, months: u32, now: i64, tz: &Tz) -> Result<Vec<CategoryTrendPoint>>
+    where
+        Tz: TimeZone + Send + std::panic::UnwindSafe + 'static,
+        Tz::Offset: Send,
+    {
+        if months == 0 {
+            return Ok(Vec::new());
+        }
+        let first_month = tz
+            .timestamp_opt(now, 0)
+            .single()
+            .and_then(|dt| dt.date_naive().with_day(1))
+            .and_then(|month| month.checked_sub_months(chrono::Months::new(months - 1)));
+        let Some(first_month) = first_month else {
+            return Ok(Vec::new());
+        };
+
+        self.register_local_time(tz)?;
+        let mut stmt = self.conn.prepare(
+            "WITH RECURSIVE buckets(month, n) AS (
+                 SELECT ?2, 1
+                 UNION ALL
+                 SELECT date(month, '+1 month'), n + 1 FROM buckets WHERE n < ?3
+             )
+             SELECT substr(b.month, 1, 7),
+                    COALESCE(SUM(s.surplus_wh), 0) / 1000.0,
+                    COALESCE(SUM(s.surplus_cost), 0),
+                    COUNT(s.id),
+                    CASE WHEN SUM(s.end_time - s.start_time) > 0
+                         THEN SUM(COALESCE(s.surplus_cost, 0)) * 3600.0 / SUM(s.end_time - s.start_time)
+                         ELSE 0 END
+             FROM buckets b
+             LEFT JOIN sessions s
+                    ON s.category = ?1
+                   AND s.end_time IS NOT NULL
+                   AND substr(local_date(s.start_time), 1, 7) = substr(b.month, 1, 7)
+             GROUP BY b.month
+             ORDER BY b.month ASC",
+        )?;
+
+        let points = stmt
+            .query_map(params![category, first_month.format("%Y-%m-%d").to_string(), months], |row| {
+                Ok(CategoryTrendPoint {
+                    month: row.get(0)?,
+                    surplus_kwh: row.get(1)?,
+                    surplus_cost: row.get(2)?,
+                    session_count: row.get(3)?,
+                    avg_cost_per_hour: row.get(4)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(points)
+    }
+
+    /// Get sessions running at some point of `[start, end)`, the active one included, latest first
+    ///
+    /// A session started before the range counts when it ends after the
+    /// range start, so the one running since the evening before is part of
+    /// today's sessions. One starting at the range end belongs to the next range.
+    pub fn get_sessions_in_range(&self, start_timestamp: i64, end_timestamp: i64) -> Result<Vec<Session>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, start_time, end_time, baseline_watts, total_wh, surplus_wh, surplus_cost, label, category,
+                    target_cost, target_wh, target_met, auto_ended, continued_from, baseline_bucket
+             FROM sessions
+             WHERE start_time < ?2 AND (end_time IS NULL OR end_time > ?1 OR start_time >= ?1)
+             ORDER BY start_time DESC",
+        )?;
+
+        let sessions = stmt
+            .query_map(params![start_timestamp, end_timestamp], Self::session_from_row)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(sessions)
+    }
+
+    /// Get sessions started in a time range (both ends included), latest first
+    pub fn get_sessions_started_in_range(&self, start_timestamp: i64, end_timestamp: i64) -> Result<Vec<Session>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, start_time, end_time, baseline_watts, total_wh, surplus_wh, surplus_cost, label, category,
+                    target_cost, target_wh, target_met, auto_ended, continued_from, baseline_bucket
+             FROM sessions
+             WHERE start_time >= ?1 AND start_time <= ?2
+             ORDER BY start_time DESC",
+        )?;
+
+        let sessions = stmt
+            .query_map(params![start_timestamp, end_timestamp], Self::session_from_row)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(sessions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::timezone::HistoryZone;
+    use rusqlite::Connection;
+
+    fn create_test_db() -> Database {
+        let conn = Connection::open_in_memory().unwrap();
+        let db = Database { conn, today_stats: RefCell::new(None), duplicate_window_secs: Cell::new(0) };
+        db.init_schema().unwrap();
+        db.run_migrations().unwrap();
+        db
+    }
+
+    /// Session row left open by an earlier run (start_session refuses a second open one)
+    fn insert_open_session(db: &Database) -> i64 {
+        db.conn
+            .execute(
+                "INSERT INTO sessions (start_time, baseline_watts, total_wh, surplus_wh, surplus_cost) VALUES (?1, 50.0, 0.0, 0.0, 0.0)",
+                params![chrono::Utc::now().timestamp()],
+            )
+            .unwrap();
+        db.conn.last_insert_rowid()
+    }
+
+    #[test]
+    fn test_insert_and_get_reading() {
+        let db = create_test_db();
+
+        let reading = PowerReading::new(100.0, "test", false);
+        db.insert_reading(&reading).unwrap();
+
+        let readings = db.get_readings(0, i64::MAX).unwrap();
+        assert_eq!(readings.len(), 1);
+        assert!((readings[0].power_watts - 100.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_daily_stats() {
+        let db = create_test_db();
+
+        let stats = DailyStats {
+            date: "2024-01-15".into(),
+            total_wh: 1500.0,
+            total_cost: Some(0.35),
+            avg_watts: 62.5,
+            max_watts: 150.0,
+            pricing_mode: Some(PricingMode::Simple),
+            usage_seconds: 0,
+            source: None,
+            note: None,
+        };
+
+        db.upsert_daily_stats(&stats).unwrap();
+
+        let retrieved = db.get_daily_stats("2024-01-01", "2024-01-31").unwrap();
+        assert_eq!(retrieved.len(), 1);
+        assert_eq!(retrieved[0].date, "2024-01-15");
+    }
+
+    #[test]
+    fn test_backfill_missing_costs() {
+        let db = create_test_db();
+        let day = |date: &str, total_wh: f64, total_cost: Option<f64>| DailyStats {
+            date: date.into(),
+            total_wh,
+            total_cost,
+            avg_watts: 0.0,
+            max_watts: 0.0,
+            pricing_mode: None,
+            usage_seconds: 0,
+            source: None,
+            note: None,
+        };
+        db.upsert_daily_stats(&day("2024-01-14", 2000.0, None)).unwrap();
+        db.upsert_daily_stats(&day("2024-01-15", 0.0, None)).unwrap();
+        db.upsert_daily_stats(&day("2024-01-16", 1000.0, Some(0.10))).unwrap();
+
+        let rate = |date: &str| if date == "2024-01-14" { 0.25 } else { 1.0 };
+        assert_eq!(db.backfill_missing_costs(&rate).unwrap(), 1);
+        let backfilled = |date: &str| -> bool {
+            db.conn.query_row("SELECT cost_backfilled FROM daily_stats WHERE date = ?1", [date], |row| row.get(0)).unwrap()
+        };
+
+        let stats = db.get_daily_stats("2024-01-01", "2024-01-31").unwrap();
+        assert_eq!(stats[0].total_cost, Some(0.5));
+        assert!(backfilled("2024-01-14"));
+        // No energy, no cost; an existing cost is left alone
+        assert_eq!(stats[1].total_cost, None);
+        assert!(!backfilled("2024-01-15"));
+        assert_eq!(stats[2].total_cost, Some(0.10));
+        assert!(!backfilled("2024-01-16"));
+
+        // Nothing left to do, and a later computed cost clears the flag
+        assert_eq!(db.backfill_missing_costs(&rate).unwrap(), 0);
+        db.upsert_daily_stats(&day("2024-01-14", 2000.0, Some(0.4))).unwrap();
+        assert!(!backfilled("2024-01-14"));
+    }
+
+    #[test]
+    fn test_update_daily_stats_from_readings() {
+        let db = create_test_db();
+
+        // Insert readings with a specific timestamp (2024-01-15 12:00:00 UTC)
+        let base_timestamp = 1705320000i64; // 2024-01-15 12:00:00 UTC
+
+        // Insert multiple readings
+        for i in 0..6 {
+            db.conn.execute(
+                "INSERT INTO power_readings (timestamp, power_watts, source, components) VALUES (?1, ?2, ?3, NULL)",
+                params![base_timestamp + i * 10, 100.0 + (i as f64 * 10.0), "test"],
+            ).unwrap();
+        }
+
+        // Update daily stats for that date
+        let result = db.update_daily_stats_for_date("2024-01-15", Some(&PricingMode::Simple), Some(0.20)).unwrap();
+        assert!(result.is_some());
+
+        let stats = result.unwrap();
+        assert_eq!(stats.date, "2024-01-15");
+        assert!(stats.avg_watts > 0.0);
+        assert!(stats.max_watts >= stats.avg_watts);
+        assert!(stats.total_wh > 0.0);
+        assert_eq!(stats.pricing_mode, Some(PricingMode::Simple));
+
+        assert_eq!(stats.source.as_deref(), Some("test"));
+
+        // Verify it was saved to the database
+        let retrieved = db.get_daily_stats("2024-01-15", "2024-01-15").unwrap();
+        assert_eq!(retrieved.len(), 1);
+        assert_eq!(retrieved[0].date, "2024-01-15");
+        assert_eq!(retrieved[0].source.as_deref(), Some("test"));
+    }
+
+    #[test]
+    fn test_source_history() {
+        let db = create_test_db();
+
+        let first = db.record_source("RAPL", false).unwrap().unwrap();
+        assert!(first.old_source.is_none());
+        // Unchanged source is not recorded again
+        assert!(db.record_source("RAPL", false).unwrap().is_none());
+
+        let change = db.record_source("Estimation", true).unwrap().unwrap();
+        assert_eq!(change.old_source.as_deref(), Some("RAPL"));
+
+        let now = chrono::Utc::now().timestamp();
+        let history = db.get_source_history(now - 60, now + 60).unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(history[1].is_estimated);
+
+        // The source in effect before the range is included
+        let later = db.get_source_history(now + 10, now + 60).unwrap();
+        assert_eq!(later.len(), 1);
+        assert_eq!(later[0].new_source, "Estimation");
+    }
+
+    #[test]
+    fn test_daily_source_is_dominant() {
+        let db = create_test_db();
+        let base_timestamp = 1705320000i64; // 2024-01-15 12:00:00 UTC
+        for (i, source) in ["RAPL", "Estimation", "Estimation", "RAPL", "Estimation"].iter().enumerate() {
+            db.conn.execute(
+                "INSERT INTO power_readings (timestamp, power_watts, source, components) VALUES (?1, 100.0, ?2, NULL)",
+                params![base_timestamp + i as i64 * 10, source],
+            ).unwrap();
+        }
+
+        let stats = db.update_daily_stats_for_date("2024-01-15", None, None).unwrap().unwrap();
+        assert_eq!(stats.source.as_deref(), Some("Estimation"));
+    }
+
+    #[test]
+    fn test_update_daily_stats_no_readings() {
+        let db = create_test_db();
+
+        // Try to update stats for a date with no readings
+        let result = db.update_daily_stats_for_date("2024-01-15", Some(&PricingMode::Simple), None).unwrap();
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_today_stats_rate_limited() {
+        let db = create_test_db();
+        let noon = chrono::Local.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).single().unwrap();
+        let insert = |offset: i64, watts: f64| {
+            db.conn.execute(
+                "INSERT INTO power_readings (timestamp, power_watts, source, components) VALUES (?1, ?2, 'test', NULL)",
+                params![noon.timestamp() + offset, watts],
+            ).unwrap();
+        };
+        let update = |offset: i64| {
+            db.update_today_stats_at(noon + chrono::Duration::seconds(offset), None, None).unwrap().unwrap()
+        };
+
+        insert(0, 100.0);
+        assert_eq!(update(0).max_watts, 100.0);
+
+        // Within the interval the stats are reused, even if readings came in
+        insert(10, 300.0);
+        assert_eq!(update(10).max_watts, 100.0);
+        assert_eq!(update(TODAY_STATS_MIN_INTERVAL_SECS - 1).max_watts, 100.0);
+        assert_eq!(update(TODAY_STATS_MIN_INTERVAL_SECS).max_watts, 300.0);
+
+        insert(40, 500.0);
+        db.invalidate_today_stats();
+        assert_eq!(update(TODAY_STATS_MIN_INTERVAL_SECS + 1).max_watts, 500.0);
+
+        // A new day is aggregated right away
+        let next_day = db.update_today_stats_at(noon + chrono::Duration::hours(12) + chrono::Duration::seconds(50), None, None).unwrap();
+        assert!(next_day.is_none());
+    }
+
+    #[test]
+    fn test_day_notes() {
+        let db = create_test_db();
+        let day = |date: &str| DailyStats {
+            date: date.into(),
+            total_wh: 100.0,
+            total_cost: None,
+            avg_watts: 50.0,
+            max_watts: 80.0,
+            pricing_mode: None,
+            usage_seconds: 0,
+            source: None,
+            note: None,
+        };
+        db.upsert_daily_stats(&day("2024-01-15")).unwrap();
+        db.upsert_daily_stats(&day("2024-01-16")).unwrap();
+
+        db.set_day_note("2024-01-15", "New GPU").unwrap();
+        db.set_day_note("2024-01-20", "Away").unwrap();
+        // Upsert keeps the creation time
+        let created_at = db.get_day_notes("2024-01-15", "2024-01-15").unwrap()[0].created_at;
+        db.set_day_note("2024-01-15", "New GPU installed").unwrap();
+        let notes = db.get_day_notes("2024-01-01", "2024-01-31").unwrap();
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0], DayNote { date: "2024-01-15".into(), note: "New GPU installed".into(), created_at });
+        assert_eq!(db.get_day_notes("2024-01-16", "2024-01-31").unwrap()[0].date, "2024-01-20");
+
+        let stats = db.get_daily_stats("2024-01-01", "2024-01-31").unwrap();
+        assert_eq!(stats[0].note.as_deref(), Some("New GPU installed"));
+        assert_eq!(stats[1].note, None);
+
+        assert!(db.delete_day_note("2024-01-15").unwrap());
+        assert!(!db.delete_day_note("2024-01-15").unwrap());
+        assert_eq!(db.get_daily_stats("2024-01-15", "2024-01-15").unwrap()[0].note, None);
+    }
+
+    #[test]
+    fn test_duplicate_readings_skipped_per_source() {
+        let db = create_test_db();
+        db.set_duplicate_window_secs(2);
+        let reading = |source: &str, ts: i64| {
+            let mut reading = PowerReading::new(100.0, source, false);
+            reading.timestamp = ts;
+            reading
+        };
+        let base = 1705320000i64;
+
+        assert!(db.insert_reading(&reading("rapl", base)).unwrap());
+        // Detailed and critical paths sampling in the same second
+        assert!(!db.insert_reading(&reading("rapl", base)).unwrap());
+        assert!(!db.insert_reading(&reading("rapl", base + 1)).unwrap());
+        // Another source in the window is its own series
+        assert!(db.insert_reading(&reading("nvml", base)).unwrap());
+        assert!(db.insert_reading(&reading("rapl", base + 2)).unwrap());
+        assert_eq!(db.get_readings(base - 10, base + 10).unwrap().len(), 3);
+
+        db.set_duplicate_window_secs(0);
+        assert!(db.insert_reading(&reading("rapl", base + 2)).unwrap());
+    }
+
+    #[test]
+    fn test_dedup_historical_readings() {
+        let db = create_test_db();
+        let base = 1705320000i64;
+        let session = db.start_session(40.0, None).unwrap();
+        db.conn.execute(
+            "UPDATE sessions SET start_time = ?1, end_time = ?2, total_wh = 3.0, surplus_wh = 3.0 WHERE id = ?3",
+            params![base, base + 15, session],
+        ).unwrap();
+        // Pairs one second apart every 10 s, plus a second source at the same times
+        for i in 0..5 {
+            for (source, ts) in [("rapl", base + i * 10), ("rapl", base + i * 10 + 1), ("nvml", base + i * 10)] {
+                let mut reading = PowerReading::new(100.0, source, false);
+                reading.timestamp = ts;
+                db.insert_reading(&reading).unwrap();
+            }
+        }
+
+        let outcome = db.dedup_readings(2).unwrap();
+        assert_eq!(outcome.removed, 5);
+        assert_eq!(outcome.dates.len(), 1);
+        // Two of the duplicates fell in the session
+        assert_eq!(outcome.sessions, vec![session]);
+        let total_wh = db.get_session(session).unwrap().unwrap().total_wh;
+        assert!((total_wh - (3.0 - 2.0 * 100.0 * HOURS_PER_READING)).abs() < 1e-9);
+        let recalculated = db.recalculate_session_surplus(session, 40.0, &|_| 0.2).unwrap().unwrap();
+        assert!((recalculated.surplus_wh - 4.0 * 60.0 * HOURS_PER_READING).abs() < 1e-9);
+        let readings = db.get_readings(base, base + 60).unwrap();
+        assert_eq!(readings.iter().filter(|r| r.source == "rapl").count(), 5);
+        assert_eq!(readings.iter().filter(|r| r.source == "nvml").count(), 5);
+        assert!(readings.iter().all(|r| (r.timestamp - base) % 10 == 0));
+
+        // Nothing left to collapse
+        assert_eq!(db.dedup_readings(2).unwrap(), DedupOutcome::default());
+    }
+
+    #[test]
+    fn test_lifetime_totals() {
+        let db = create_test_db();
+        let empty = db.get_lifetime_totals().unwrap();
+        assert_eq!((empty.total_wh, empty.session_count, empty.first_date), (0.0, 0, None));
+
+        let base = 1705320000i64; // 2024-01-15 12:00:00 UTC
+        for i in 0..6 {
+            let mut reading = PowerReading::new(360.0, "test", false);
+            reading.timestamp = base + i * 10;
+            db.insert_reading(&reading).unwrap();
+        }
+        let date = chrono::Local.timestamp_opt(base, 0).unwrap().format("%Y-%m-%d").to_string();
+        let stats = db.update_daily_stats_for_date(&date, None, Some(0.2)).unwrap().unwrap();
+        let once = db.get_lifetime_totals().unwrap();
+        assert_eq!(once.total_wh, stats.total_wh);
+        assert_eq!(Some(once.total_cost), stats.total_cost);
+        assert_eq!(once.first_date.as_deref(), Some(date.as_str()));
+
+        // Rolling the same day up again doesn't count it twice
+        db.update_daily_stats_for_date(&date, None, Some(0.2)).unwrap();
+        db.update_daily_stats_for_date(&date, None, Some(0.2)).unwrap();
+        let again = db.get_lifetime_totals().unwrap();
+        assert_eq!((again.total_wh, again.total_cost), (once.total_wh, once.total_cost));
+
+        // A session split at midnight counts once, when its last part ends
+        let id = db.start_session(40.0, None).unwrap();
+        let continuation = db.split_session(id, base + 60, 10.0, 5.0, 0.01).unwrap().unwrap();
+        assert_eq!(db.refresh_lifetime_totals().unwrap().session_count, 0);
+        db.end_session(continuation, 10.0, 5.0, 0.01).unwrap();
+        assert_eq!(db.get_lifetime_totals().unwrap().session_count, 1);
+
+        // Sessions closed on recovery count as well
+        db.start_session(40.0, None).unwrap();
+        assert_eq!(db.recover_orphaned_sessions().unwrap().len(), 1);
+        assert_eq!(db.get_lifetime_totals().unwrap().session_count, 2);
+
+        // Bulk rebuilds leave the refresh to their end
+        db.rebuild_daily_stats_for_date_at(&date, None, &|_| 0.4).unwrap();
+        assert_eq!(db.get_lifetime_totals().unwrap().total_cost, once.total_cost);
+        assert!(db.refresh_lifetime_totals().unwrap().total_cost > once.total_cost);
+
+        // Deleting readings keeps what the days recorded
+        db.reset(ResetScope::Readings).unwrap();
+        assert_eq!(db.get_lifetime_totals().unwrap().total_wh, once.total_wh);
+        db.reset(ResetScope::All).unwrap();
+        assert_eq!(db.get_lifetime_totals().unwrap().total_wh, 0.0);
+    }
+
+    #[test]
+    fn test_reset_scopes() {
+        let db = create_test_db();
+
+        db.insert_reading(&PowerReading::new(100.0, "test", false)).unwrap();
+        db.insert_reading(&PowerReading::new(120.0, "test", false)).unwrap();
+        db.upsert_daily_stats(&DailyStats {
+            date: "2024-01-15".into(),
+            total_wh: 1000.0,
+            total_cost: Some(0.25),
+            avg_watts: 100.0,
+            max_watts: 200.0,
+            pricing_mode: Some(PricingMode::Simple),
+            usage_seconds: 0,
+            source: None,
+            note: None,
+        }).unwrap();
+        db.start_session(50.0, None).unwrap();
+        db.insert_accuracy_sample(&AccuracySample { timestamp: 1_700_000_000, measured_watts: 50.0, estimated_watts: 60.0 }).unwrap();
+        db.record_source("rapl", false).unwrap();
+
+        let counts = db.reset(ResetScope::Readings).unwrap();
+        assert_eq!(counts.readings, 2);
+        assert_eq!((counts.accuracy_samples, counts.source_changes), (1, 1));
+        assert_eq!(counts.daily_stats, 0);
+        assert!(db.get_accuracy_samples(10).unwrap().is_empty());
+        assert!(db.get_source_history(0, i64::MAX).unwrap().is_empty());
+        assert_eq!(db.get_readings_count().unwrap(), 0);
+        assert_eq!(db.get_daily_stats("2024-01-01", "2024-12-31").unwrap().len(), 1);
+
+        let counts = db.reset(ResetScope::All).unwrap();
+        assert_eq!(counts.readings, 0);
+        assert_eq!(counts.daily_stats, 1);
+        assert_eq!(counts.sessions, 1);
+        assert!(db.get_sessions(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_session_target_met() {
+        let db = create_test_db();
+
+        let hit = db.start_session_with_target(50.0, None, Some(0.50), None).unwrap();
+        let ended = db.end_session(hit, 100.0, 40.0, 0.30).unwrap().unwrap();
+        assert_eq!(ended.target_met, Some(true));
+        assert!((ended.target_progress_percent.unwrap() - 60.0).abs() < 0.001);
+
+        // Both targets set: the most consumed one decides
+        let miss = db.start_session_with_target(50.0, None, Some(1.0), Some(100.0)).unwrap();
+        let ended = db.end_session(miss, 200.0, 150.0, 0.40).unwrap().unwrap();
+        assert_eq!(ended.target_met, Some(false));
+
+        let plain = db.start_session(50.0, None).unwrap();
+        let ended = db.end_session(plain, 10.0, 5.0, 0.01).unwrap().unwrap();
+        assert_eq!(ended.target_met, None);
+        assert_eq!(db.get_session(hit).unwrap().unwrap().target_met, Some(true));
+    }
+
+    #[test]
+    fn test_recover_orphaned_sessions() {
+        let db = create_test_db();
+
+        let ended = db.start_session(50.0, None).unwrap();
+        db.end_session(ended, 10.0, 5.0, 0.001).unwrap();
+        let crashed = db.start_session(50.0, None).unwrap();
+        db.heartbeat_session(crashed, 500.0, 300.0, 0.06).unwrap();
+        let never_beat = insert_open_session(&db);
+
+        let recovered = db.recover_orphaned_sessions().unwrap();
+        assert_eq!(recovered.len(), 2);
+        assert!(db.get_active_session().unwrap().is_none());
+
+        let crashed = db.get_session(crashed).unwrap().unwrap();
+        assert!((crashed.surplus_wh - 300.0).abs() < 0.001);
+        assert!(crashed.end_time.is_some());
+
+        let never_beat = db.get_session(never_beat).unwrap().unwrap();
+        assert_eq!(never_beat.end_time, Some(never_beat.start_time));
+    }
+
+    #[test]
+    fn test_start_refuses_overlapping_session() {
+        let db = create_test_db();
+        let first = db.start_session(50.0, None).unwrap();
+        assert!(matches!(db.start_session(50.0, Some("Second")), Err(Error::SessionActive(id)) if id == first));
+        assert_eq!(db.get_sessions(None).unwrap().len(), 1);
+
+        db.end_session(first, 10.0, 5.0, 0.001).unwrap();
+        assert!(db.start_session(50.0, None).is_ok());
+    }
+
+    #[test]
+    fn test_concurrent_session_starts() {
+        let dir = std::env::temp_dir().join(format!("powercost-db-race-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("data.db");
+        drop(Database::open(&path).unwrap());
+
+        let barrier = std::sync::Arc::new(std::sync::Barrier::new(2));
+        let starts: Vec<_> = (0..2)
+            .map(|_| {
+                let (path, barrier) = (path.clone(), barrier.clone());
+                std::thread::spawn(move || {
+                    let db = Database::open(&path).unwrap();
+                    barrier.wait();
+                    db.start_session(50.0, None)
+                })
+            })
+            .collect();
+        let results: Vec<_> = starts.into_iter().map(|t| t.join().unwrap()).collect();
+
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+        assert!(results.iter().any(|r| matches!(r, Err(Error::SessionActive(_)))));
+        assert_eq!(Database::open(&path).unwrap().get_sessions(None).unwrap().len(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_copy_in_memory_rows() {
+        let memory = Database::in_memory().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        memory.insert_reading(&PowerReading::new(100.0, "rapl", false)).unwrap();
+        memory.insert_reading(&PowerReading::new(120.0, "rapl", false)).unwrap();
+        let first = memory.start_session(50.0, Some("render")).unwrap();
+        let second = memory.split_session(first, now, 10.0, 5.0, 0.01).unwrap().unwrap();
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        memory.add_usage_seconds(&today, 120).unwrap();
+
+        // The file already has a session, copied ids must move past it
+        let file = create_test_db();
+        let existing = file.start_session(40.0, None).unwrap();
+        file.end_session(existing, 1.0, 0.5, 0.001).unwrap();
+        file.add_usage_seconds(&today, 60).unwrap();
+
+        let copied = memory.copy_into(&file, None, Some(0.2)).unwrap();
+        assert_eq!(copied.rows, 4);
+        let (new_first, new_second) = (copied.session_ids[&first], copied.session_ids[&second]);
+        assert!(new_first > existing && new_second > new_first);
+
+        let continued = file.get_session(new_second).unwrap().unwrap();
+        assert_eq!(continued.continued_from, Some(new_first));
+        assert_eq!(file.get_session(new_first).unwrap().unwrap().label.as_deref(), Some("render"));
+        assert_eq!(file.get_readings_count().unwrap(), 2);
+        let stats = file.get_daily_stats(&today, &today).unwrap();
+        assert_eq!(stats[0].usage_seconds, 180);
+    }
+
+    #[test]
+    fn test_force_close_stale_sessions() {
+        let db = create_test_db();
+        let now = chrono::Utc::now().timestamp();
+        let stale = insert_open_session(&db);
+        db.conn.execute("UPDATE sessions SET start_time = ?1, last_heartbeat = ?2 WHERE id = ?3", params![now - 3600, now - 600, stale]).unwrap();
+        let alive = insert_open_session(&db);
+        db.conn.execute("UPDATE sessions SET last_heartbeat = ?1 WHERE id = ?2", params![now, alive]).unwrap();
+
+        let closed = db.force_close_stale_sessions(now - 120).unwrap();
+        assert_eq!(closed.iter().map(|s| s.id).collect::<Vec<_>>(), [Some(stale)]);
+        assert_eq!(db.get_session(stale).unwrap().unwrap().end_time, Some(now - 600));
+        assert_eq!(db.get_session(alive).unwrap().unwrap().end_time, None);
+    }
+
+    #[test]
+    fn test_session_end_after_backward_clock_jump() {
+        let db = create_test_db();
+        // Started "one hour from now": the clock has since been set back
+        let future = chrono::Utc::now().timestamp() + 3600;
+        let ended = db.start_session(50.0, None).unwrap();
+        let crashed = insert_open_session(&db);
+        db.heartbeat_session(crashed, 10.0, 5.0, 0.001).unwrap();
+        for id in [ended, crashed] {
+            db.conn.execute("UPDATE sessions SET start_time = ?1 WHERE id = ?2", params![future, id]).unwrap();
+        }
+
+        let session = db.end_session(ended, 10.0, 5.0, 0.001).unwrap().unwrap();
+        assert_eq!(session.end_time, Some(future));
+        db.recover_orphaned_sessions().unwrap();
+        assert_eq!(db.get_session(crashed).unwrap().unwrap().end_time, Some(future));
+
+        // Re-anchoring only touches running sessions
+        let running = db.start_session(50.0, None).unwrap();
+        db.reanchor_session_start(running, 1_700_000_000).unwrap();
+        db.reanchor_session_start(ended, 1_700_000_000).unwrap();
+        assert_eq!(db.get_session(running).unwrap().unwrap().start_time, 1_700_000_000);
+        assert_eq!(db.get_session(ended).unwrap().unwrap().start_time, future);
+    }
+
+    #[test]
+    fn test_auto_end_session() {
+        let db = create_test_db();
+
+        let id = db.start_session(50.0, None).unwrap();
+        let start = db.get_session(id).unwrap().unwrap().start_time;
+        let ended = db.auto_end_session(id, start + 600, 80.0, 30.0, 0.006).unwrap().unwrap();
+        assert!(ended.auto_ended);
+        assert_eq!(ended.end_time, Some(start + 600));
+        assert!((ended.surplus_wh - 30.0).abs() < 0.001);
+
+        let manual = db.start_session(50.0, None).unwrap();
+        db.end_session(manual, 10.0, 5.0, 0.001).unwrap();
+        assert!(!db.get_session(manual).unwrap().unwrap().auto_ended);
+    }
+
+    #[test]
+    fn test_session_top_apps() {
+        let db = create_test_db();
 
-                // Estimate total Wh based on average power and assumed runtime
-                // Since readings are taken every ~10 seconds (every 10 monitoring cycles at 1s each),
-                // we can estimate energy from the sum of power readings
-                // Each reading represents approximately 10 seconds of monitoring
-                let hours_per_reading = 10.0 / 3600.0; // 10 seconds in hours
-                let total_wh = sum_watts * hours_per_reading;
+        let id = db.start_session(50.0, None).unwrap();
+        assert!(db.get_session_top_apps(id).unwrap().is_empty());
 
-                let total_cost = rate_per_kwh.map(|rate| (total_wh / 1000.0) * rate);
+        let apps = vec![
+            AppUsage { name: "firefox".to_string(), seconds: 120.0 },
+            AppUsage { name: "code".to_string(), seconds: 60.0 },
+        ];
+        db.set_session_top_apps(id, &apps).unwrap();
+        assert_eq!(db.get_session_top_apps(id).unwrap(), apps);
 
-                let stats = DailyStats {
-                    date: date.to_string(),
-                    total_wh,
-                    total_cost,
-                    avg_watts,
-                    max_watts,
-                    pricing_mode: pricing_mode.map(String::from),
-                    usage_seconds: 0,
-                };
+        assert_eq!(db.clear_session_top_apps().unwrap(), 1);
+        assert!(db.get_session_top_apps(id).unwrap().is_empty());
+        assert!(db.get_session_top_apps(id + 1).unwrap().is_empty());
+    }
 
-                self.upsert_daily_stats(&stats)?;
-                Ok(Some(stats))
-            }
-            Err(e) => Err(Error::Database(e)),
+    #[test]
+    fn test_rebuild_priced_at_reading_time() {
+        let db = create_test_db();
+        // 2024-03-11 00:00 UTC and 12:00 UTC, then the next day
+        for (ts, watts) in [(1_710_115_200, 360.0), (1_710_158_400, 360.0), (1_710_201_600, 720.0)] {
+            let mut reading = PowerReading::new(watts, "rapl", false);
+            reading.timestamp = ts;
+            db.insert_reading(&reading).unwrap();
         }
+
+        let utc = &chrono::Utc;
+        assert_eq!(db.reading_dates_in(None, None, utc).unwrap(), vec!["2024-03-11", "2024-03-12"]);
+        assert_eq!(db.reading_dates_in(Some("2024-03-12"), None, utc).unwrap(), vec!["2024-03-12"]);
+        assert!(db.reading_dates_in(None, Some("2024-03-10"), utc).unwrap().is_empty());
+
+        // Double rate in the afternoon
+        let rate_at = |ts: i64| if ts >= 1_710_158_400 { 0.4 } else { 0.2 };
+        let stats = db.update_daily_stats_for_date_in("2024-03-11", None, DailyRate::AtTime(&rate_at), utc).unwrap().unwrap();
+        assert!((stats.total_wh - 2.0).abs() < 1e-9);
+        assert!((stats.total_cost.unwrap() - 0.0006).abs() < 1e-12);
+
+        let flat = db.update_daily_stats_for_date_in("2024-03-11", None, DailyRate::Flat(Some(0.2)), utc).unwrap().unwrap();
+        assert!((flat.total_cost.unwrap() - 0.0004).abs() < 1e-12);
     }
 
-    /// Update daily stats for today based on current readings
-    pub fn update_today_stats(&self, pricing_mode: Option<&str>, rate_per_kwh: Option<f64>) -> Result<Option<DailyStats>> {
-        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
-        self.update_daily_stats_for_date(&today, pricing_mode, rate_per_kwh)
+    /// Europe/Paris in 2024: UTC+2 from March 31 01:00 UTC to October 27 01:00 UTC, UTC+1 otherwise
+    #[derive(Clone, Copy)]
+    struct Paris2024;
+
+    impl Paris2024 {
+        const SUMMER_START: i64 = 1_711_846_800;
+        const SUMMER_END: i64 = 1_729_990_800;
+
+        fn offset_at(ts: i64) -> chrono::FixedOffset {
+            let hours = if (Self::SUMMER_START..Self::SUMMER_END).contains(&ts) { 2 } else { 1 };
+            chrono::FixedOffset::east_opt(hours * 3600).unwrap()
+        }
     }
 
-    /// Rebuild daily stats for all dates that have readings
-    pub fn rebuild_all_daily_stats(&self, pricing_mode: Option<&str>, rate_per_kwh: Option<f64>) -> Result<u32> {
-        // Get all distinct dates from power_readings
-        let mut stmt = self.conn.prepare(
-            "SELECT DISTINCT date(timestamp, 'unixepoch') as reading_date
-             FROM power_readings
-             ORDER BY reading_date ASC"
-        )?;
+    impl TimeZone for Paris2024 {
+        type Offset = chrono::FixedOffset;
 
-        let dates: Vec<String> = stmt
-            .query_map([], |row| row.get(0))?
-            .filter_map(|r| r.ok())
-            .collect();
+        fn from_offset(_: &chrono::FixedOffset) -> Self {
+            Paris2024
+        }
 
-        let mut count = 0;
-        for date in dates {
-            if self.update_daily_stats_for_date(&date, pricing_mode, rate_per_kwh)?.is_some() {
-                count += 1;
+        fn offset_from_local_date(&self, local: &NaiveDate) -> chrono::LocalResult<chrono::FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(&self, local: &chrono::NaiveDateTime) -> chrono::LocalResult<chrono::FixedOffset> {
+            // Offsets that map this local time back onto itself, earliest instant first
+            let valid: Vec<_> = [2, 1]
+                .into_iter()
+                .map(|h| chrono::FixedOffset::east_opt(h * 3600).unwrap())
+                .filter(|o| Self::offset_at(local.and_utc().timestamp() - o.local_minus_utc() as i64) == *o)
+                .collect();
+            match valid[..] {
+                [a, b] => chrono::LocalResult::Ambiguous(a, b),
+                [a] => chrono::LocalResult::Single(a),
+                _ => chrono::LocalResult::None,
             }
         }
 
-        Ok(count)
-    }
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> chrono::FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+        }
 
-    /// Add usage seconds for a given date (accumulates)
-    pub fn add_usage_seconds(&self, date: &str, seconds: i64) -> Result<()> {
-        // First ensure the row exists
-        self.conn.execute(
-            "INSERT INTO daily_stats (date, total_wh, total_cost, avg_watts, max_watts, pricing_mode, usage_seconds)
-             VALUES (?1, 0.0, NULL, 0.0, 0.0, NULL, ?2)
-             ON CONFLICT(date) DO UPDATE SET usage_seconds = COALESCE(usage_seconds, 0) + ?2",
-            params![date, seconds],
-        )?;
-        Ok(())
+        fn offset_from_utc_datetime(&self, utc: &chrono::NaiveDateTime) -> chrono::FixedOffset {
+            Self::offset_at(utc.and_utc().timestamp())
+        }
     }
 
-    // ===== Session Management =====
+    #[test]
+    fn test_daily_stats_local_days_across_dst() {
+        let db = create_test_db();
+        let tz = Paris2024;
+        // One 360 W reading (1 Wh) per hour around both transitions
+        for (from, to) in [(1_711_839_600, 1_711_929_600), (1_729_980_000, 1_730_073_600)] {
+            for ts in (from..to).step_by(3600) {
+                let mut reading = PowerReading::new(360.0, "rapl", false);
+                reading.timestamp = ts;
+                db.insert_reading(&reading).unwrap();
+            }
+        }
 
-    /// Start a new tracking session
-    pub fn start_session(&self, baseline_watts: f64, label: Option<&str>) -> Result<i64> {
-        let now = chrono::Utc::now().timestamp();
+        let dates = db.reading_dates_in(None, None, &tz).unwrap();
+        assert_eq!(dates, vec!["2024-03-31", "2024-04-01", "2024-10-27", "2024-10-28"]);
 
-        self.conn.execute(
-            "INSERT INTO sessions (start_time, baseline_watts, total_wh, surplus_wh, surplus_cost, label)
-             VALUES (?1, ?2, 0.0, 0.0, 0.0, ?3)",
-            params![now, baseline_watts, label],
-        )?;
+        // Spring forward: 23 hours, fall back: 25 hours
+        let day = |date: &str| db.update_daily_stats_for_date_in(date, None, DailyRate::Flat(None), &tz).unwrap().unwrap();
+        assert!((day("2024-03-31").total_wh - 23.0).abs() < 1e-9);
+        assert!((day("2024-10-27").total_wh - 25.0).abs() < 1e-9);
 
-        Ok(self.conn.last_insert_rowid())
+        // A local day starts at local midnight, not at 00:00 UTC
+        assert_eq!(local_day_start(NaiveDate::from_ymd_opt(2024, 3, 31).unwrap(), &tz), 1_711_839_600);
+        assert_eq!(local_day_start(NaiveDate::from_ymd_opt(2024, 4, 1).unwrap(), &tz), 1_711_922_400);
     }
 
-    /// End a tracking session
-    pub fn end_session(&self, session_id: i64, total_wh: f64, surplus_wh: f64, surplus_cost: f64) -> Result<Option<Session>> {
-        let now = chrono::Utc::now().timestamp();
+    #[test]
+    fn test_local_dates_rebuild_task() {
+        let db = create_test_db();
+        assert!(!db.daily_rebuild_pending().unwrap());
+
+        // A database from before migration 7, with UTC-keyed stats
+        let stats = |date: &str| DailyStats {
+            date: date.to_string(),
+            total_wh: 100.0,
+            total_cost: None,
+            avg_watts: 0.0,
+            max_watts: 0.0,
+            pricing_mode: None,
+            usage_seconds: 0,
+            source: None,
+            note: None,
+        };
+        let first = chrono::Local::now().date_naive() - chrono::Duration::days(5);
+        let day = |offset: i64| (first + chrono::Duration::days(offset)).format("%Y-%m-%d").to_string();
+        for offset in [-10, 0, 1, 2] {
+            db.upsert_daily_stats(&stats(&day(offset))).unwrap();
+        }
+        db.set_schema_version(6).unwrap();
+        db.run_migrations().unwrap();
+        assert!(db.daily_rebuild_pending().unwrap());
 
-        self.conn.execute(
-            "UPDATE sessions SET end_time = ?1, total_wh = ?2, surplus_wh = ?3, surplus_cost = ?4
-             WHERE id = ?5",
-            params![now, total_wh, surplus_wh, surplus_cost, session_id],
-        )?;
+        // Readings on the first and third day only: the second day's row is stale
+        for offset in [0, 2] {
+            let mut reading = PowerReading::new(100.0, "rapl", false);
+            reading.timestamp = local_day_start(first + chrono::Duration::days(offset), &chrono::Local) + 3600;
+            db.insert_reading(&reading).unwrap();
+        }
+        assert_eq!(db.delete_stale_daily_stats().unwrap(), 1);
+        let dates: Vec<String> = db.get_daily_stats(&day(-10), &day(2)).unwrap().into_iter().map(|s| s.date).collect();
+        assert_eq!(dates, vec![day(-10), day(0), day(2)]);
 
-        self.get_session(session_id)
+        db.clear_daily_rebuild_pending().unwrap();
+        assert!(!db.daily_rebuild_pending().unwrap());
     }
 
-    /// Get a specific session by ID
-    pub fn get_session(&self, session_id: i64) -> Result<Option<Session>> {
-        let result = self.conn.query_row(
-            "SELECT id, start_time, end_time, baseline_watts, total_wh, surplus_wh, surplus_cost, label, category
-             FROM sessions WHERE id = ?1",
-            params![session_id],
-            |row| {
-                Ok(Session {
-                    id: Some(row.get(0)?),
-                    start_time: row.get(1)?,
-                    end_time: row.get(2)?,
-                    baseline_watts: row.get(3)?,
-                    total_wh: row.get(4)?,
-                    surplus_wh: row.get(5)?,
-                    surplus_cost: row.get(6)?,
-                    label: row.get(7)?,
-                    category: row.get(8)?,
-                })
-            },
-        );
-
-        match result {
-            Ok(session) => Ok(Some(session)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(Error::Database(e)),
+    #[test]
+    fn test_hourly_stats_local_hours() {
+        let db = create_test_db();
+        let tz = Paris2024;
+        // Local midnight of 2024-03-31, clocks jump from 02:00 to 03:00
+        let midnight = 1_711_839_600;
+        for ts in [midnight - 10, midnight, midnight + 1800, midnight + 3600, midnight + 7200] {
+            let mut reading = PowerReading::new(360.0, "rapl", false);
+            reading.timestamp = ts;
+            db.insert_reading(&reading).unwrap();
         }
+
+        let rate_at = |_: i64| 0.2;
+        let hours = db.get_hourly_stats_in(NaiveDate::from_ymd_opt(2024, 3, 31).unwrap(), &rate_at, &tz).unwrap();
+        let summary: Vec<(u32, f64)> = hours.iter().map(|h| (h.hour, (h.total_wh * 1e6).round() / 1e6)).collect();
+        assert_eq!(summary, vec![(0, 2.0), (1, 1.0), (3, 1.0)]);
+        assert!((hours[0].total_cost - 0.0004).abs() < 1e-12);
     }
 
-    /// Get all sessions, optionally limited
-    pub fn get_sessions(&self, limit: Option<u32>) -> Result<Vec<Session>> {
-        let query = match limit {
-            Some(n) => format!(
-                "SELECT id, start_time, end_time, baseline_watts, total_wh, surplus_wh, surplus_cost, label, category
-                 FROM sessions ORDER BY start_time DESC LIMIT {}", n
-            ),
-            None => "SELECT id, start_time, end_time, baseline_watts, total_wh, surplus_wh, surplus_cost, label, category
-                     FROM sessions ORDER BY start_time DESC".to_string(),
+    #[test]
+    fn test_daily_stats_filtered_in_zone() {
+        let db = create_test_db();
+        let insert = |ts: i64| {
+            let mut reading = PowerReading::new(360.0, "rapl", false);
+            reading.timestamp = ts;
+            db.insert_reading(&reading).unwrap();
         };
-
-        let mut stmt = self.conn.prepare(&query)?;
-
-        let sessions = stmt
-            .query_map([], |row| {
-                Ok(Session {
-                    id: Some(row.get(0)?),
-                    start_time: row.get(1)?,
-                    end_time: row.get(2)?,
-                    baseline_watts: row.get(3)?,
-                    total_wh: row.get(4)?,
-                    surplus_wh: row.get(5)?,
-                    surplus_cost: row.get(6)?,
-                    label: row.get(7)?,
-                    category: row.get(8)?,
-                })
-            })?
-            .filter_map(|r| r.ok())
+        // 2024-03-10 10:30 and 11:30 UTC, either side of midnight in UTC+13
+        insert(1_710_066_600);
+        insert(1_710_070_200);
+
+        let zone = HistoryZone::parse("UTC+13").unwrap();
+        let days = |filter: &TimeFilter, tz: &HistoryZone| -> Vec<(String, f64)> {
+            db.get_daily_stats_filtered_in("2024-03-10", "2024-03-11", filter, DailyRate::Flat(None), tz)
+                .unwrap()
+                .into_iter()
+                .map(|d| (d.date, (d.total_wh * 1e6).round() / 1e6))
+                .collect()
+        };
+        assert_eq!(days(&TimeFilter::default(), &zone), vec![("2024-03-10".to_string(), 1.0), ("2024-03-11".to_string(), 1.0)]);
+        assert_eq!(days(&TimeFilter::default(), &HistoryZone::Utc), vec![("2024-03-10".to_string(), 2.0)]);
+        // Filter hours are in the zone too (00:30 there, a Monday)
+        let first_hour = TimeFilter { hour_start: Some(0), hour_end: Some(1), weekdays: Some(vec![1]) };
+        assert_eq!(days(&first_hour, &zone), vec![("2024-03-11".to_string(), 1.0)]);
+        assert!(days(&first_hour, &HistoryZone::Utc).is_empty());
+
+        // Each reading priced at the rate of its own time
+        let rate_at = |ts: i64| if ts < 1_710_068_400 { 0.1 } else { 0.3 };
+        let costs: Vec<f64> = db
+            .get_daily_stats_filtered_in("2024-03-10", "2024-03-11", &TimeFilter::default(), DailyRate::AtTime(&rate_at), &zone)
+            .unwrap()
+            .iter()
+            .map(|d| d.total_cost.unwrap())
             .collect();
+        assert!((costs[0] - 0.0001).abs() < 1e-12 && (costs[1] - 0.0003).abs() < 1e-12);
 
-        Ok(sessions)
+        // A zone with DST: local midnight of 2024-03-31, then 03:00 right after the jump
+        let midnight = 1_711_839_600;
+        for ts in [midnight - 10, midnight, midnight + 7200] {
+            insert(ts);
+        }
+        let stats = db.get_daily_stats_filtered_in("2024-03-30", "2024-03-31", &TimeFilter::default(), DailyRate::Flat(None), &Paris2024).unwrap();
+        let dates: Vec<&str> = stats.iter().map(|d| d.date.as_str()).collect();
+        assert_eq!(dates, vec!["2024-03-30", "2024-03-31"]);
+        assert!((stats[1].total_wh - 2.0).abs() < 1e-9);
+        let after_jump = TimeFilter { hour_start: Some(3), hour_end: Some(4), weekdays: None };
+        let stats = db.get_daily_stats_filtered_in("2024-03-30", "2024-03-31", &after_jump, DailyRate::Flat(None), &Paris2024).unwrap();
+        assert_eq!(stats.len(), 1);
+        assert!((stats[0].total_wh - 1.0).abs() < 1e-9);
     }
 
-    /// Update session statistics (called during active session)
-    pub fn update_session_stats(&self, session_id: i64, total_wh: f64, surplus_wh: f64, surplus_cost: f64) -> Result<()> {
-        self.conn.execute(
-            "UPDATE sessions SET total_wh = ?1, surplus_wh = ?2, surplus_cost = ?3 WHERE id = ?4",
-            params![total_wh, surplus_wh, surplus_cost, session_id],
-        )?;
-        Ok(())
+    #[test]
+    fn test_recalculate_session_surplus() {
+        let db = create_test_db();
+        let id = db.start_session(150.0, Some("Gaming")).unwrap();
+        let start = db.get_session(id).unwrap().unwrap().start_time;
+        // 360 W and 180 W for one reading each
+        for (offset, watts) in [(10, 360.0), (20, 180.0)] {
+            let mut reading = PowerReading::new(watts, "rapl", false);
+            reading.timestamp = start + offset;
+            db.insert_reading(&reading).unwrap();
+        }
+
+        // Not ended yet
+        assert!(db.recalculate_session_surplus(id, 0.0, &|_| 0.2).unwrap().is_none());
+
+        db.conn.execute("UPDATE sessions SET end_time = ?1, surplus_wh = 0.1, surplus_cost = 0.0 WHERE id = ?2", params![start + 30, id]).unwrap();
+        let session = db.recalculate_session_surplus(id, 0.0, &|ts| if ts == start + 10 { 0.2 } else { 0.4 }).unwrap().unwrap();
+        assert_eq!(session.baseline_watts, 0.0);
+        assert!((session.surplus_wh - 1.5).abs() < 1e-9);
+        assert!((session.surplus_cost - (0.001 * 0.2 + 0.0005 * 0.4)).abs() < 1e-12);
+
+        // Readings under the baseline add nothing
+        let session = db.recalculate_session_surplus(id, 270.0, &|_| 0.2).unwrap().unwrap();
+        assert!((session.surplus_wh - 0.25).abs() < 1e-9);
+
+        let audit = db.get_session_surplus_audit(id).unwrap();
+        assert_eq!(audit.len(), 2);
+        assert_eq!((audit[0].baseline_watts, audit[0].surplus_wh), (150.0, 0.1));
+        assert_eq!(audit[1].baseline_watts, 0.0);
+        assert!(db.recalculate_session_surplus(id + 1, 0.0, &|_| 0.2).unwrap().is_none());
+
+        // Readings pruned: the stored surplus is all there is, it is kept
+        db.conn.execute("DELETE FROM power_readings", []).unwrap();
+        let session = db.recalculate_session_surplus(id, 0.0, &|_| 0.2).unwrap().unwrap();
+        assert_eq!(session.baseline_watts, 270.0);
+        assert!((session.surplus_wh - 0.25).abs() < 1e-9);
+        assert_eq!(db.get_session_surplus_audit(id).unwrap().len(), 2);
     }
 
-    /// Get the most recent active (unended) session
-    pub fn get_active_session(&self) -> Result<Option<Session>> {
-        let result = self.conn.query_row(
-            "SELECT id, start_time, end_time, baseline_watts, total_wh, surplus_wh, surplus_cost, label, category
-             FROM sessions WHERE end_time IS NULL ORDER BY start_time DESC LIMIT 1",
-            [],
-            |row| {
-                Ok(Session {
-                    id: Some(row.get(0)?),
-                    start_time: row.get(1)?,
-                    end_time: row.get(2)?,
-                    baseline_watts: row.get(3)?,
-                    total_wh: row.get(4)?,
-                    surplus_wh: row.get(5)?,
-                    surplus_cost: row.get(6)?,
-                    label: row.get(7)?,
-                    category: row.get(8)?,
-                })
-            },
-        );
+    #[test]
+    fn test_hardware_snapshot_only_on_change() {
+        let db = create_test_db();
+        assert!(db.get_latest_hardware_snapshot().unwrap().is_none());
+
+        let mut inventory = HardwareInventory {
+            gpus: vec![crate::core::HardwareGpu {
+                name: "NVIDIA GeForce RTX 3080".to_string(),
+                driver_version: Some("551.86".to_string()),
+                vram_total_mb: Some(10240),
+            }],
+            cpu_model: "AMD Ryzen 7 5800X".to_string(),
+            physical_cores: Some(8),
+            logical_cores: 16,
+            total_ram_mb: 32768,
+            os_version: Some("Linux 6.8".to_string()),
+        };
+        assert!(db.record_hardware_snapshot(&inventory).unwrap());
+        assert!(!db.record_hardware_snapshot(&inventory).unwrap());
+
+        // Driver update
+        inventory.gpus[0].driver_version = Some("555.42".to_string());
+        assert!(db.record_hardware_snapshot(&inventory).unwrap());
+        let latest = db.get_latest_hardware_snapshot().unwrap().unwrap();
+        assert_eq!(latest.inventory, inventory);
+        let rows: i64 = db.conn.query_row("SELECT COUNT(*) FROM hardware_snapshots", [], |row| row.get(0)).unwrap();
+        assert_eq!(rows, 2);
+    }
 
-        match result {
-            Ok(session) => Ok(Some(session)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(Error::Database(e)),
+    #[test]
+    fn test_accuracy_samples() {
+        let db = create_test_db();
+        assert!(db.get_accuracy_samples(10).unwrap().is_empty());
+        for i in 0..3 {
+            let sample = AccuracySample { timestamp: 1_700_000_000 + i * 60, measured_watts: 50.0 + i as f64, estimated_watts: 60.0 };
+            db.insert_accuracy_sample(&sample).unwrap();
         }
+        let samples = db.get_accuracy_samples(2).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].measured_watts, 52.0);
+        assert_eq!(samples[1].timestamp, 1_700_000_060);
+
+        // Pruned with the readings
+        assert_eq!(db.cleanup_old_readings(30).unwrap(), 3);
+        assert!(db.get_accuracy_samples(10).unwrap().is_empty());
     }
 
-    /// Update session label
-    pub fn update_session_label(&self, session_id: i64, label: &str) -> Result<()> {
-        self.conn.execute(
-            "UPDATE sessions SET label = ?1 WHERE id = ?2",
-            params![label, session_id],
-        )?;
-        Ok(())
+    #[test]
+    fn test_component_readings() {
+        let db = create_test_db();
+        let insert = |ts: i64, source: &str, components: Option<&[(&str, f64)]>| {
+            let mut reading = PowerReading::new(100.0, source, false);
+            reading.timestamp = ts;
+            if let Some(parts) = components {
+                reading = reading.with_components(parts.iter().map(|(k, v)| (k.to_string(), *v)).collect());
+            }
+            db.insert_reading(&reading).unwrap();
+        };
+        insert(1000, "sysinfo+nvidia", Some(&[("cpu", 40.0), ("gpu", 50.0), ("gpu_rtx_3080", 50.0), ("base", 10.0)]));
+        insert(1010, "estimated", Some(&[("cpu", 70.0), ("other", 30.0)]));
+        insert(1015, "estimated", Some(&[("cpu", 60.0), ("gpu_estimated", 25.0), ("other", 15.0)]));
+        insert(1020, "rapl", None);
+        db.conn.execute("UPDATE power_readings SET components = 'garbage' WHERE timestamp = 1020", []).unwrap();
+        insert(1030, "rapl", None);
+
+        let readings = db.get_component_readings(1000, 1030).unwrap();
+        assert_eq!(readings.len(), 5);
+        assert_eq!((readings[0].cpu_watts, readings[0].gpu_watts, readings[0].base_watts), (Some(40.0), Some(50.0), Some(10.0)));
+        // The estimator's "other" counts as base
+        assert_eq!((readings[1].cpu_watts, readings[1].gpu_watts, readings[1].base_watts), (Some(70.0), None, Some(30.0)));
+        // An estimated GPU counts as the GPU
+        assert_eq!(readings[2].gpu_watts, Some(25.0));
+        for r in &readings[3..] {
+            assert_eq!((r.cpu_watts, r.gpu_watts, r.base_watts), (None, None, None));
+            assert_eq!(r.power_watts, 100.0);
+        }
     }
 
-    /// Update session category
-    pub fn update_session_category(&self, session_id: i64, category: Option<&str>) -> Result<()> {
-        self.conn.execute(
-            "UPDATE sessions SET category = ?1 WHERE id = ?2",
-            params![category, session_id],
-        )?;
-        Ok(())
-    }
+    #[test]
+    fn test_temperature_history() {
+        let db = create_test_db();
 
-    /// Delete a session by ID
-    pub fn delete_session(&self, session_id: i64) -> Result<()> {
-        self.conn.execute("DELETE FROM sessions WHERE id = ?1", params![session_id])?;
-        Ok(())
+        db.insert_temperature_reading(1000, Some(50.0), None).unwrap();
+        db.insert_temperature_reading(1005, Some(60.0), Some(70.0)).unwrap();
+        db.insert_temperature_reading(1010, None, None).unwrap();
+        db.insert_temperature_reading(1020, Some(40.0), None).unwrap();
+        // Readings with no sensor data at all are not stored
+        assert_eq!(db.get_temperature_readings_count().unwrap(), 3);
+
+        let all = db.get_temperature_history(1000, 1029, 1000).unwrap();
+        assert_eq!(all.len(), 3);
+        assert!(all[0].gpu_c.is_none());
+
+        // Two 15s buckets: [1000, 1015) and [1015, 1030)
+        let buckets = db.get_temperature_history(1000, 1029, 2).unwrap();
+        assert_eq!(buckets.len(), 2);
+        assert!((buckets[0].cpu_c.unwrap() - 55.0).abs() < 0.001);
+        assert!((buckets[0].gpu_c.unwrap() - 70.0).abs() < 0.001);
+        assert!(buckets[1].gpu_c.is_none());
+
+        let counts = db.reset(ResetScope::Readings).unwrap();
+        assert_eq!(counts.temperature_readings, 3);
     }
 
-    /// Get sessions in a date range (by start_time)
-    pub fn get_sessions_in_range(&self, start_timestamp: i64, end_timestamp: i64) -> Result<Vec<Session>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, start_time, end_time, baseline_watts, total_wh, surplus_wh, surplus_cost, label, category
-             FROM sessions
-             WHERE start_time >= ?1 AND start_time <= ?2
-             ORDER BY start_time DESC",
-        )?;
+    #[test]
+    fn test_vram_history_and_alerts() {
+        let db = create_test_db();
+        assert!(db.get_vram_history(0, 2000, 100).unwrap().is_empty());
 
-        let sessions = stmt
-            .query_map(params![start_timestamp, end_timestamp], |row| {
-                Ok(Session {
-                    id: Some(row.get(0)?),
-                    start_time: row.get(1)?,
-                    end_time: row.get(2)?,
-                    baseline_watts: row.get(3)?,
-                    total_wh: row.get(4)?,
-                    surplus_wh: row.get(5)?,
-                    surplus_cost: row.get(6)?,
-                    label: row.get(7)?,
-                    category: row.get(8)?,
-                })
-            })?
-            .filter_map(|r| r.ok())
-            .collect();
+        db.insert_vram_reading(1000, 4000, 8000).unwrap();
+        db.insert_vram_reading(1005, 6000, 8000).unwrap();
+        db.insert_vram_reading(1020, 7000, 8000).unwrap();
 
-        Ok(sessions)
-    }
-}
+        let buckets = db.get_vram_history(1000, 1029, 2).unwrap();
+        assert_eq!(buckets.len(), 2);
+        assert!((buckets[0].used_mb - 5000.0).abs() < 0.001);
+        assert_eq!(buckets[1].total_mb, 8000.0);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rusqlite::Connection;
+        db.insert_alert(&AlertRecord { timestamp: 1080, kind: "vram".into(), value: 87.5, threshold: 85.0 }).unwrap();
+        let alerts = db.get_alerts(1000, 2000).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, "vram");
 
-    fn create_test_db() -> Database {
-        let conn = Connection::open_in_memory().unwrap();
-        let db = Database { conn };
-        db.init_schema().unwrap();
-        db.run_migrations().unwrap();
-        db
+        // Retention covers both tables, recent rows are kept
+        let now = chrono::Utc::now().timestamp();
+        db.insert_vram_reading(now, 1000, 8000).unwrap();
+        assert_eq!(db.cleanup_old_readings(30).unwrap(), 4);
+        assert_eq!(db.get_vram_history(now, now, 10).unwrap().len(), 1);
+        assert!(db.get_alerts(0, now).unwrap().is_empty());
+
+        db.insert_alert(&AlertRecord { timestamp: now, kind: "vram".into(), value: 90.0, threshold: 85.0 }).unwrap();
+        let counts = db.reset(ResetScope::Readings).unwrap();
+        assert_eq!((counts.vram_readings, counts.alerts), (1, 1));
     }
 
     #[test]
-    fn test_insert_and_get_reading() {
+    fn test_time_filter_readings() {
         let db = create_test_db();
-
-        let reading = PowerReading::new(100.0, "test", false);
-        db.insert_reading(&reading).unwrap();
-
-        let readings = db.get_readings(0, i64::MAX).unwrap();
+        // Local time, since the filter works on local hours: Mon 2024-03-11 and Sat 2024-03-16
+        let local = |d: u32, h: u32| chrono::Local.with_ymd_and_hms(2024, 3, d, h, 0, 0).earliest().unwrap().timestamp();
+        let insert = |ts: i64, watts: f64| {
+            let mut reading = PowerReading::new(watts, "rapl", false);
+            reading.timestamp = ts;
+            db.insert_reading(&reading).unwrap();
+        };
+        insert(local(11, 8), 50.0);
+        insert(local(11, 9), 100.0);
+        insert(local(11, 17), 200.0);
+        insert(local(11, 18), 300.0);
+        insert(local(11, 23), 400.0);
+        insert(local(16, 10), 500.0);
+
+        let (start, end) = (local(11, 0), local(17, 0));
+        let working_hours = TimeFilter { hour_start: Some(9), hour_end: Some(18), weekdays: None };
+        let readings = db.get_readings_filtered(start, end, &working_hours, 1000).unwrap();
+        let watts: Vec<f64> = readings.iter().map(|r| r.power_watts).collect();
+        assert_eq!(watts, vec![100.0, 200.0, 500.0]);
+
+        // Weekdays only, and everything averaged into a single bucket
+        let workdays = TimeFilter { weekdays: Some(vec![1, 2, 3, 4, 5]), ..working_hours.clone() };
+        let buckets = db.get_readings_filtered(start, end, &workdays, 1).unwrap();
+        assert_eq!(buckets.len(), 1);
+        assert!((buckets[0].power_watts - 150.0).abs() < 0.001);
+        assert_eq!(buckets[0].source, "rapl");
+
+        // Overnight range wraps around midnight
+        let overnight = TimeFilter { hour_start: Some(22), hour_end: Some(6), weekdays: None };
+        let readings = db.get_readings_filtered(start, end, &overnight, 1000).unwrap();
         assert_eq!(readings.len(), 1);
-        assert!((readings[0].power_watts - 100.0).abs() < 0.001);
+        assert!((readings[0].power_watts - 400.0).abs() < 0.001);
+
+        assert!(TimeFilter::default().is_empty());
+        assert!(TimeFilter { hour_start: Some(24), ..Default::default() }.validate().is_err());
+        assert!(TimeFilter { weekdays: Some(vec![7]), ..Default::default() }.validate().is_err());
+        assert!(overnight.validate().is_ok());
     }
 
     #[test]
-    fn test_daily_stats() {
+    fn test_daily_stats_filtered() {
         let db = create_test_db();
+        let local = |d: u32, h: u32| chrono::Local.with_ymd_and_hms(2024, 3, d, h, 30, 0).earliest().unwrap().timestamp();
+        for (d, h, watts) in [(11, 7, 1000.0), (11, 10, 360.0), (11, 12, 720.0), (12, 11, 36.0), (16, 11, 99.0)] {
+            let mut reading = PowerReading::new(watts, "rapl", false);
+            reading.timestamp = local(d, h);
+            db.insert_reading(&reading).unwrap();
+        }
 
-        let stats = DailyStats {
-            date: "2024-01-15".into(),
-            total_wh: 1500.0,
-            total_cost: Some(0.35),
-            avg_watts: 62.5,
-            max_watts: 150.0,
-            pricing_mode: Some("simple".into()),
-            usage_seconds: 0,
-        };
-
-        db.upsert_daily_stats(&stats).unwrap();
-
-        let retrieved = db.get_daily_stats("2024-01-01", "2024-01-31").unwrap();
-        assert_eq!(retrieved.len(), 1);
-        assert_eq!(retrieved[0].date, "2024-01-15");
+        let filter = TimeFilter { hour_start: Some(9), hour_end: Some(18), weekdays: Some(vec![1, 2, 3, 4, 5]) };
+        let stats = db.get_daily_stats_filtered("2024-03-11", "2024-03-16", &filter, DailyRate::Flat(Some(0.5))).unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].date, "2024-03-11");
+        // Two 10 s readings of 360 W and 720 W = 3 Wh
+        assert!((stats[0].total_wh - 3.0).abs() < 0.001);
+        assert!((stats[0].avg_watts - 540.0).abs() < 0.001);
+        assert!((stats[0].max_watts - 720.0).abs() < 0.001);
+        assert!((stats[0].total_cost.unwrap() - 0.0015).abs() < 1e-9);
+        assert_eq!(stats[1].date, "2024-03-12");
+        // Nothing is stored
+        assert!(db.get_daily_stats("2024-03-01", "2024-03-31").unwrap().is_empty());
     }
 
     #[test]
-    fn test_update_daily_stats_from_readings() {
+    fn test_sessions_grouped_by_day() {
         let db = create_test_db();
+        let insert = |start: i64, surplus_wh: f64, surplus_cost: f64| {
+            db.conn.execute(
+                "INSERT INTO sessions (start_time, end_time, baseline_watts, total_wh, surplus_wh, surplus_cost)
+                 VALUES (?1, ?2, 50.0, 0.0, ?3, ?4)",
+                params![start, start + 7200, surplus_wh, surplus_cost],
+            ).unwrap();
+        };
 
-        // Insert readings with a specific timestamp (2024-01-15 12:00:00 UTC)
-        let base_timestamp = 1705320000i64; // 2024-01-15 12:00:00 UTC
+        // 2024-03-10 21:30 UTC and 23:30 UTC, 2024-03-11 10:00 UTC
+        insert(1710106200, 100.0, 0.02);
+        insert(1710113400, 200.0, 0.05);
+        insert(1710151200, 50.0, 0.01);
+
+        // In UTC+2 the 23:30 session starts at 01:30 on the 11th
+        let tz = chrono::FixedOffset::east_opt(2 * 3600).unwrap();
+        let days = db.get_sessions_grouped_by_day_in(0, i64::MAX, &tz).unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].date, "2024-03-11");
+        assert_eq!(days[0].sessions.len(), 2);
+        assert!((days[0].total_surplus_wh - 250.0).abs() < 0.001);
+        assert!((days[0].total_surplus_cost - 0.06).abs() < 0.0001);
+        assert_eq!(days[1].date, "2024-03-10");
+        assert_eq!(days[1].sessions.len(), 1);
+
+        // In UTC the late session belongs to its start date even though it crosses midnight
+        let days = db.get_sessions_grouped_by_day_in(0, i64::MAX, &chrono::Utc).unwrap();
+        assert_eq!(days[1].date, "2024-03-10");
+        assert_eq!(days[1].sessions.len(), 2);
+    }
 
-        // Insert multiple readings
-        for i in 0..6 {
+    #[test]
+    fn test_sessions_in_range_overlap() {
+        let db = create_test_db();
+        let insert = |start: i64, end: Option<i64>| {
             db.conn.execute(
-                "INSERT INTO power_readings (timestamp, power_watts, source, components) VALUES (?1, ?2, ?3, NULL)",
-                params![base_timestamp + i * 10, 100.0 + (i as f64 * 10.0), "test"],
+                "INSERT INTO sessions (start_time, end_time, baseline_watts, total_wh, surplus_wh, surplus_cost)
+                 VALUES (?1, ?2, 50.0, 100.0, 0.0, 0.0)",
+                params![start, end],
             ).unwrap();
-        }
+        };
+        const DAY: i64 = 86_400;
+
+        // Across the start, inside, across the end, still running, ended at the start, after
+        insert(DAY - 3600, Some(DAY + 3600));
+        insert(DAY + 7200, Some(DAY + 10_800));
+        insert(2 * DAY - 3600, Some(2 * DAY + 3600));
+        insert(2 * DAY - 600, None);
+        insert(DAY - 7200, Some(DAY));
+        insert(2 * DAY + 60, Some(2 * DAY + 600));
+        insert(2 * DAY, Some(2 * DAY + 30));
+
+        // Started at the range end: part of the next range
+        let starts: Vec<i64> = db.get_sessions_in_range(DAY, 2 * DAY).unwrap().iter().map(|s| s.start_time).collect();
+        assert_eq!(starts, [2 * DAY - 600, 2 * DAY - 3600, DAY + 7200, DAY - 3600]);
+
+        // The start-based query misses the session from the evening before, and includes its end
+        let starts: Vec<i64> = db.get_sessions_started_in_range(DAY, 2 * DAY).unwrap().iter().map(|s| s.start_time).collect();
+        assert_eq!(starts, [2 * DAY, 2 * DAY - 600, 2 * DAY - 3600, DAY + 7200]);
+    }
 
-        // Update daily stats for that date
-        let result = db.update_daily_stats_for_date("2024-01-15", Some("simple"), Some(0.20)).unwrap();
-        assert!(result.is_some());
+    #[test]
+    fn test_rename_session_category() {
+        let db = create_test_db();
+        let a = db.start_session(50.0, None).unwrap();
+        db.end_session(a, 10.0, 5.0, 0.001).unwrap();
+        let b = db.start_session(50.0, None).unwrap();
+        db.update_session_category(a, Some("Gaming")).unwrap();
+        db.update_session_category(b, Some("Work")).unwrap();
+
+        assert_eq!(db.rename_session_category("Gaming", "Jeux").unwrap(), 1);
+        assert_eq!(db.get_session(a).unwrap().unwrap().category.as_deref(), Some("Jeux"));
+        assert_eq!(db.get_session(b).unwrap().unwrap().category.as_deref(), Some("Work"));
+    }
 
-        let stats = result.unwrap();
-        assert_eq!(stats.date, "2024-01-15");
-        assert!(stats.avg_watts > 0.0);
-        assert!(stats.max_watts >= stats.avg_watts);
-        assert!(stats.total_wh > 0.0);
-        assert_eq!(stats.pricing_mode, Some("simple".to_string()));
+    #[test]
+    fn test_bulk_update_session_category() {
+        let db = create_test_db();
+        let insert = |start: i64, end: Option<i64>, label: &str, category: Option<&str>| {
+            db.conn
+                .execute(
+                    "INSERT INTO sessions (start_time, end_time, baseline_watts, total_wh, surplus_wh, surplus_cost, label, category)
+                     VALUES (?1, ?2, 50.0, 0.0, 0.0, 0.0, ?3, ?4)",
+                    params![start, end, label, category],
+                )
+                .unwrap();
+            db.conn.last_insert_rowid()
+        };
+        let old_game = insert(1_000, Some(2_000), "Elden Ring", None);
+        let game = insert(10_000, Some(11_000), "elden ring night", Some(""));
+        let work = insert(20_000, Some(21_000), "Report", Some("Work"));
+        let active = insert(30_000, None, "Elden Ring again", None);
+        let category = |id: i64| db.get_session(id).unwrap().unwrap().category;
+
+        // Uncategorized sessions, NULL or empty, the running one left out
+        let uncategorized = SessionCategoryFilter { current_category: CurrentCategory::Uncategorized, ..Default::default() };
+        assert_eq!(db.bulk_update_session_category(&uncategorized, Some("Gaming"), true).unwrap(), 2);
+        assert_eq!(category(old_game), None);
+
+        let filter = SessionCategoryFilter { label_contains: Some("ELDEN".to_string()), start: Some(5_000), ..uncategorized.clone() };
+        assert_eq!(db.bulk_update_session_category(&filter, Some("Gaming"), false).unwrap(), 1);
+        assert_eq!(category(game).as_deref(), Some("Gaming"));
+        assert_eq!(category(old_game), None);
+
+        let with_active = SessionCategoryFilter { include_active: true, ..uncategorized };
+        assert_eq!(db.bulk_update_session_category(&with_active, Some("Gaming"), false).unwrap(), 2);
+        assert_eq!(category(active).as_deref(), Some("Gaming"));
+
+        // Clearing a named category
+        let gaming = SessionCategoryFilter { current_category: CurrentCategory::Is("Gaming".to_string()), end: Some(15_000), ..Default::default() };
+        assert_eq!(db.bulk_update_session_category(&gaming, None, false).unwrap(), 2);
+        assert_eq!(category(old_game), None);
+        assert_eq!(category(work).as_deref(), Some("Work"));
+        assert_eq!(category(active).as_deref(), Some("Gaming"));
+    }
 
-        // Verify it was saved to the database
-        let retrieved = db.get_daily_stats("2024-01-15", "2024-01-15").unwrap();
-        assert_eq!(retrieved.len(), 1);
-        assert_eq!(retrieved[0].date, "2024-01-15");
+    #[test]
+    fn test_category_trend_months() {
+        let db = create_test_db();
+        let insert = |start: i64, end: Option<i64>, wh: f64, cost: f64, category: &str| {
+            db.conn
+                .execute(
+                    "INSERT INTO sessions (start_time, end_time, baseline_watts, surplus_wh, surplus_cost, category)
+                     VALUES (?1, ?2, 50.0, ?3, ?4, ?5)",
+                    params![start, end, wh, cost, category],
+                )
+                .unwrap();
+        };
+        // 2024-01-31 23:00 UTC, two hours across the month boundary
+        insert(1_706_742_000, Some(1_706_749_200), 1000.0, 0.5, "AI");
+        // 2024-03-10 10:00 UTC, one hour
+        insert(1_710_064_800, Some(1_710_068_400), 500.0, 0.2, "AI");
+        // Still running, and another category
+        insert(1_710_100_000, None, 0.0, 0.0, "AI");
+        insert(1_707_523_200, Some(1_707_526_800), 800.0, 0.4, "Gaming");
+
+        // 2024-03-15
+        let trend = db.get_category_trend_in("AI", 4, 1_710_460_800, &chrono::Utc).unwrap();
+        let months: Vec<&str> = trend.iter().map(|p| p.month.as_str()).collect();
+        assert_eq!(months, ["2023-12", "2024-01", "2024-02", "2024-03"]);
+
+        for empty in [&trend[0], &trend[2]] {
+            assert_eq!(empty.session_count, 0);
+            assert_eq!(empty.surplus_kwh, 0.0);
+            assert_eq!(empty.avg_cost_per_hour, 0.0);
+        }
+        assert_eq!(trend[1].session_count, 1);
+        assert!((trend[1].surplus_kwh - 1.0).abs() < 1e-9);
+        assert!((trend[1].surplus_cost - 0.5).abs() < 1e-9);
+        assert!((trend[1].avg_cost_per_hour - 0.25).abs() < 1e-9);
+        assert_eq!(trend[3].session_count, 1);
+        assert!((trend[3].avg_cost_per_hour - 0.2).abs() < 1e-9);
+
+        assert!(db.get_category_trend_in("AI", 0, 1_710_460_800, &chrono::Utc).unwrap().is_empty());
     }
 
     #[test]
-    fn test_update_daily_stats_no_readings() {
+    fn test_split_session_and_merge() {
         let db = create_test_db();
+        let first = db.start_session_with_target(40.0, Some("Render"), None, Some(300.0)).unwrap();
+        db.update_session_category(first, Some("Work")).unwrap();
+        let start = db.get_session(first).unwrap().unwrap().start_time;
+
+        let second = db.split_session(first, start + 100, 150.0, 100.0, 0.02).unwrap().unwrap();
+        let third = db.split_session(second, start + 200, 120.0, 80.0, 0.016).unwrap().unwrap();
+        assert_eq!(db.split_session(first, start + 300, 0.0, 0.0, 0.0).unwrap(), None);
+        db.end_session(third, 60.0, 40.0, 0.008).unwrap();
+
+        let continuation = db.get_session(second).unwrap().unwrap();
+        assert_eq!((continuation.start_time, continuation.end_time), (start + 100, Some(start + 200)));
+        assert_eq!(continuation.continued_from, Some(first));
+        assert_eq!(continuation.label.as_deref(), Some("Render"));
+        assert_eq!(continuation.category.as_deref(), Some("Work"));
+        assert_eq!(continuation.target_wh, Some(300.0));
+
+        let whole = db.get_merged_session(third).unwrap().unwrap();
+        assert_eq!((whole.id, whole.start_time, whole.end_time), (Some(first), start, db.get_session(third).unwrap().unwrap().end_time));
+        assert!((whole.surplus_wh - 220.0).abs() < 1e-9);
+
+        let other = db.start_session(30.0, None).unwrap();
+        let merged = db.get_merged_sessions(None).unwrap();
+        assert_eq!(merged.len(), 2);
+        let chain = merged.iter().find(|s| s.id == Some(first)).unwrap();
+        assert_eq!(chain.end_time, Some(start + 200));
+        assert!((chain.total_wh - 330.0).abs() < 1e-9);
+        assert!((chain.surplus_wh - 220.0).abs() < 1e-9);
+        assert_eq!(chain.target_met, Some(true));
+        assert!(merged.iter().any(|s| s.id == Some(other)));
+        assert_eq!(db.get_sessions(None).unwrap().len(), 4);
+    }
 
-        // Try to update stats for a date with no readings
-        let result = db.update_daily_stats_for_date("2024-01-15", Some("simple"), None).unwrap();
-        assert!(result.is_none());
+    #[test]
+    fn test_open_reports_database_in_use() {
+        let dir = std::env::temp_dir().join(format!("powercost-db-lock-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("data.db");
+        drop(Database::open(&path).unwrap());
+
+        // Another process holding an exclusive lock
+        let other = Connection::open(&path).unwrap();
+        other.execute_batch("PRAGMA locking_mode = EXCLUSIVE; BEGIN EXCLUSIVE;").unwrap();
+        let err = Database::open_with_timeout(&path, Duration::from_millis(50)).err().unwrap();
+        assert!(matches!(err, Error::DatabaseBusy(_)), "{}", err);
+
+        other.execute_batch("COMMIT; PRAGMA locking_mode = NORMAL; SELECT 1 FROM sessions LIMIT 1;").unwrap();
+        assert!(Database::open(&path).is_ok());
+        drop(other);
+        let _ = std::fs::remove_dir_all(&dir);
     }
 }
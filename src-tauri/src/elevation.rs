@@ -1,5 +1,52 @@
 //! Elevation detection and relaunch helpers (Windows-only with no-op stubs for other platforms)
 
+use serde::Serialize;
+
+/// How long a denied elevation prompt is not shown again at startup (24 hours)
+pub const DENIAL_COOLDOWN_SECS: i64 = 24 * 3600;
+
+/// Time given to the elevated process to prove it started
+#[cfg(target_os = "windows")]
+const SPAWN_CONFIRM_MS: u32 = 1500;
+
+/// Elevation state reported to the frontend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ElevationStatus {
+    Elevated,
+    NotElevated,
+    /// Elevation was denied recently, the startup prompt is suspended
+    Denied,
+    /// Platform without UAC-style elevation
+    Unsupported,
+}
+
+/// Whether elevation is available on this platform
+pub fn is_supported() -> bool {
+    cfg!(target_os = "windows")
+}
+
+/// Whether the startup prompt should be shown, given the last denial timestamp
+pub fn should_prompt(denied_at: Option<i64>, now: i64) -> bool {
+    match denied_at {
+        Some(t) => now - t >= DENIAL_COOLDOWN_SECS || now < t,
+        None => true,
+    }
+}
+
+/// Current elevation status
+pub fn status(denied_at: Option<i64>, now: i64) -> ElevationStatus {
+    if !is_supported() {
+        ElevationStatus::Unsupported
+    } else if is_elevated() {
+        ElevationStatus::Elevated
+    } else if !should_prompt(denied_at, now) {
+        ElevationStatus::Denied
+    } else {
+        ElevationStatus::NotElevated
+    }
+}
+
 #[cfg(target_os = "windows")]
 pub fn is_elevated() -> bool {
     use windows_sys::Win32::Foundation::CloseHandle;
@@ -28,9 +75,14 @@ pub fn is_elevated() -> bool {
     }
 }
 
+/// Launch an elevated copy of the app, returns true once the new process is confirmed running
+///
+/// `extra_args` are added to the current launch arguments.
 #[cfg(target_os = "windows")]
-pub fn relaunch_elevated() -> bool {
-    use windows_sys::Win32::UI::Shell::ShellExecuteW;
+pub fn relaunch_elevated(extra_args: &[&str]) -> bool {
+    use windows_sys::Win32::Foundation::{CloseHandle, WAIT_TIMEOUT};
+    use windows_sys::Win32::System::Threading::WaitForSingleObject;
+    use windows_sys::Win32::UI::Shell::{ShellExecuteExW, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW};
 
     let exe_path = match std::env::current_exe() {
         Ok(p) => p,
@@ -44,18 +96,40 @@ pub fn relaunch_elevated() -> bool {
         .collect();
 
     let verb: Vec<u16> = "runas\0".encode_utf16().collect();
+    // Keep the launch arguments (a boot launch stays minimized once elevated)
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    for extra in extra_args {
+        if !args.iter().any(|arg| arg == extra) {
+            args.push(extra.to_string());
+        }
+    }
+    let params: Vec<u16> = args
+        .iter()
+        .map(|arg| format!("\"{}\"", arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
 
     unsafe {
-        let result = ShellExecuteW(
-            std::ptr::null_mut(),
-            verb.as_ptr(),
-            exe_wide.as_ptr(),
-            std::ptr::null(),
-            std::ptr::null(),
-            1, // SW_SHOWNORMAL
-        );
-        // ShellExecuteW returns > 32 on success
-        result as usize > 32
+        let mut info: SHELLEXECUTEINFOW = std::mem::zeroed();
+        info.cbSize = std::mem::size_of::<SHELLEXECUTEINFOW>() as u32;
+        info.fMask = SEE_MASK_NOCLOSEPROCESS;
+        info.lpVerb = verb.as_ptr();
+        info.lpFile = exe_wide.as_ptr();
+        info.lpParameters = params.as_ptr();
+        info.nShow = 1; // SW_SHOWNORMAL
+
+        // Fails when the UAC prompt is denied
+        if ShellExecuteExW(&mut info) == 0 || info.hProcess.is_null() {
+            return false;
+        }
+
+        // Make sure the new instance didn't die right away
+        let still_running = WaitForSingleObject(info.hProcess, SPAWN_CONFIRM_MS) == WAIT_TIMEOUT;
+        CloseHandle(info.hProcess);
+        still_running
     }
 }
 
@@ -65,6 +139,6 @@ pub fn is_elevated() -> bool {
 }
 
 #[cfg(not(target_os = "windows"))]
-pub fn relaunch_elevated() -> bool {
+pub fn relaunch_elevated(_extra_args: &[&str]) -> bool {
     false
 }
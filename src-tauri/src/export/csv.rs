@@ -0,0 +1,43 @@
+//! Minimal CSV writing (RFC 4180 quoting)
+
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+/// Quote a field when it holds a separator, a quote or a line break
+pub fn field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Write one row, CRLF-terminated
+pub fn write_row<W: Write, S: AsRef<str>>(out: &mut W, fields: &[S]) -> io::Result<()> {
+    for (i, value) in fields.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        out.write_all(field(value.as_ref()).as_bytes())?;
+    }
+    out.write_all(b"\r\n")
+}
+
+/// Empty for None, the value otherwise
+pub fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quoting() {
+        let mut out = Vec::new();
+        write_row(&mut out, &["plain", "a,b", "say \"hi\"", "two\nlines", ""]).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "plain,\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\",\r\n");
+        assert_eq!(optional(Some(1.5)), "1.5");
+        assert_eq!(optional::<f64>(None), "");
+    }
+}
@@ -0,0 +1,321 @@
+//! Session export to CSV or JSON
+//!
+//! Timestamps are exported as Unix seconds next to a local time column for
+//! people reading the file. The CSV export is a single sessions file, or a
+//! zip holding `sessions.csv` and `samples.csv` when samples are included.
+//! JSON is one document either way, samples nested in their session.
+
+pub mod csv;
+mod zip;
+
+use crate::core::{Error, ExchangeRate, Result, Session};
+use crate::db::{Database, PowerReadingRecord};
+use chrono::TimeZone;
+use serde::Serialize;
+use std::path::Path;
+
+/// Output format of an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            other => Err(format!("Unknown export format '{}', expected csv or json", other)),
+        }
+    }
+}
+
+/// Power reading taken while a session ran
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSample {
+    pub timestamp: i64,
+    pub local_time: String,
+    pub power_watts: f64,
+    pub source: String,
+}
+
+/// A session as exported, with its local times and optionally its samples
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportedSession {
+    #[serde(flatten)]
+    pub session: Session,
+    pub local_start: String,
+    pub local_end: Option<String>,
+    pub duration_secs: Option<i64>,
+    /// Surplus cost in the secondary currency, when one is configured
+    pub converted_surplus_cost: Option<f64>,
+    pub converted_currency: Option<String>,
+    /// Whether the conversion rate was outdated, and when it was fetched (None for a manual rate)
+    pub rate_stale: Option<bool>,
+    pub rate_updated_at: Option<i64>,
+    /// Note of the local day the session started on
+    pub day_note: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub samples: Option<Vec<SessionSample>>,
+}
+
+const SESSION_COLUMNS: [&str; 22] = [
+    "id",
+    "label",
+    "category",
+    "start_time",
+    "local_start",
+    "end_time",
+    "local_end",
+    "duration_secs",
+    "baseline_watts",
+    "total_wh",
+    "surplus_wh",
+    "surplus_cost",
+    "converted_surplus_cost",
+    "converted_currency",
+    "rate_stale",
+    "rate_updated_at",
+    "target_cost",
+    "target_wh",
+    "target_met",
+    "auto_ended",
+    "sample_count",
+    "day_note",
+];
+
+const SAMPLE_COLUMNS: [&str; 5] = ["session_id", "timestamp", "local_time", "power_watts", "source"];
+
+fn local_time<Tz: TimeZone>(timestamp: i64, tz: &Tz) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    tz.timestamp_opt(timestamp, 0)
+        .earliest()
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+/// Exported form of a session, with its readings when samples are included
+pub fn export_session<Tz: TimeZone>(
+    session: Session,
+    readings: Option<Vec<PowerReadingRecord>>,
+    rate: Option<&ExchangeRate>,
+    tz: &Tz,
+) -> ExportedSession
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let samples = readings.map(|readings| {
+        readings
+            .into_iter()
+            .map(|r| SessionSample {
+                local_time: local_time(r.timestamp, tz),
+                timestamp: r.timestamp,
+                power_watts: r.power_watts,
+                source: r.source,
+            })
+            .collect()
+    });
+    ExportedSession {
+        local_start: local_time(session.start_time, tz),
+        local_end: session.end_time.map(|t| local_time(t, tz)),
+        duration_secs: session.end_time.map(|t| (t - session.start_time).max(0)),
+        converted_surplus_cost: rate.map(|r| r.convert(session.surplus_cost)),
+        converted_currency: rate.map(|r| r.currency.clone()),
+        rate_stale: rate.map(|r| r.stale),
+        rate_updated_at: rate.and_then(|r| r.updated_at),
+        day_note: None,
+        samples,
+        session,
+    }
+}
+
+/// Sessions started in `[start, end]`, oldest first, with their samples if asked
+pub fn collect<Tz: TimeZone>(
+    db: &Database,
+    start: i64,
+    end: i64,
+    include_samples: bool,
+    rate: Option<&ExchangeRate>,
+    tz: &Tz,
+) -> Result<Vec<ExportedSession>>
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let now = chrono::Utc::now().timestamp();
+    // By start, so back-to-back exports never repeat a session
+    let mut sessions = db.get_sessions_started_in_range(start, end)?;
+    sessions.reverse();
+
+    sessions
+        .into_iter()
+        .map(|session| {
+            let readings = if include_samples {
+                Some(db.get_readings(session.start_time, session.end_time.unwrap_or(now))?)
+            } else {
+                None
+            };
+            let day = tz.timestamp_opt(session.start_time, 0).earliest().map(|dt| dt.format("%Y-%m-%d").to_string());
+            let day_note = match day {
+                Some(day) => db.get_day_note(&day)?,
+                None => None,
+            };
+            Ok(ExportedSession { day_note, ..export_session(session, readings, rate, tz) })
+        })
+        .collect()
+}
+
+fn sessions_csv(sessions: &[ExportedSession]) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    csv::write_row(&mut out, &SESSION_COLUMNS)?;
+    for exported in sessions {
+        let s = &exported.session;
+        csv::write_row(
+            &mut out,
+            &[
+                csv::optional(s.id),
+                s.label.clone().unwrap_or_default(),
+                s.category.clone().unwrap_or_default(),
+                s.start_time.to_string(),
+                exported.local_start.clone(),
+                csv::optional(s.end_time),
+                exported.local_end.clone().unwrap_or_default(),
+                csv::optional(exported.duration_secs),
+                s.baseline_watts.to_string(),
+                s.total_wh.to_string(),
+                s.surplus_wh.to_string(),
+                s.surplus_cost.to_string(),
+                csv::optional(exported.converted_surplus_cost),
+                exported.converted_currency.clone().unwrap_or_default(),
+                csv::optional(exported.rate_stale),
+                csv::optional(exported.rate_updated_at),
+                csv::optional(s.target_cost),
+                csv::optional(s.target_wh),
+                csv::optional(s.target_met),
+                s.auto_ended.to_string(),
+                csv::optional(exported.samples.as_ref().map(Vec::len)),
+                exported.day_note.clone().unwrap_or_default(),
+            ],
+        )?;
+    }
+    Ok(out)
+}
+
+fn samples_csv(sessions: &[ExportedSession]) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    csv::write_row(&mut out, &SAMPLE_COLUMNS)?;
+    for exported in sessions {
+        let session_id = csv::optional(exported.session.id);
+        for sample in exported.samples.iter().flatten() {
+            csv::write_row(
+                &mut out,
+                &[
+                    session_id.clone(),
+                    sample.timestamp.to_string(),
+                    sample.local_time.clone(),
+                    sample.power_watts.to_string(),
+                    sample.source.clone(),
+                ],
+            )?;
+        }
+    }
+    Ok(out)
+}
+
+/// Encode the export, a zip for CSV with samples
+pub fn encode(sessions: &[ExportedSession], format: ExportFormat) -> Result<Vec<u8>> {
+    match format {
+        ExportFormat::Json => serde_json::to_vec_pretty(sessions).map_err(|e| Error::Serialization(e.to_string())),
+        ExportFormat::Csv if sessions.iter().any(|s| s.samples.is_some()) => {
+            let mut archive = zip::ZipWriter::new();
+            archive.add("sessions.csv", sessions_csv(sessions)?);
+            archive.add("samples.csv", samples_csv(sessions)?);
+            let mut out = Vec::new();
+            archive.finish(&mut out)?;
+            Ok(out)
+        }
+        ExportFormat::Csv => Ok(sessions_csv(sessions)?),
+    }
+}
+
+/// Write the export to `path`, returns the number of sessions exported
+pub fn write(sessions: &[ExportedSession], format: ExportFormat, path: &Path) -> Result<usize> {
+    std::fs::write(path, encode(sessions, format)?)?;
+    Ok(sessions.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Session {
+        let mut s = Session::new(40.0, Some("Render, final".to_string()));
+        s.id = Some(7);
+        s.category = Some("Work".to_string());
+        s.start_time = 1_700_000_000;
+        s.end_time = Some(1_700_000_060);
+        s.total_wh = 120.0;
+        s.surplus_wh = 80.0;
+        s.surplus_cost = 0.02;
+        s
+    }
+
+    fn reading(timestamp: i64) -> PowerReadingRecord {
+        PowerReadingRecord { id: 0, timestamp, power_watts: 100.0, source: "rapl".to_string(), components: None }
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let noted = ExportedSession { day_note: Some("Away".to_string()), ..export_session(session(), None, None, &chrono::Utc) };
+        let sessions = [export_session(session(), None, None, &chrono::Utc), noted];
+        assert_eq!(sessions[0].duration_secs, Some(60));
+
+        let csv = String::from_utf8(encode(&sessions, ExportFormat::Csv).unwrap()).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("id,label,category,start_time,local_start"));
+        assert!(lines[1].starts_with("7,\"Render, final\",Work,1700000000,2023-11-14 22:13:20,1700000060,2023-11-14 22:14:20,60,"));
+        assert!(lines[1].ends_with(",false,,"));
+        assert!(lines[2].ends_with(",false,,Away"));
+    }
+
+    #[test]
+    fn test_export_with_samples() {
+        let readings = vec![reading(1_700_000_000), reading(1_700_000_030), reading(1_700_000_060)];
+        let rate = ExchangeRate {
+            currency: "USD".to_string(),
+            currency_symbol: "$".to_string(),
+            rate: 1.5,
+            source: crate::core::RateSource::Manual,
+            updated_at: Some(1_699_990_000),
+            stale: true,
+        };
+        let sessions = [export_session(session(), Some(readings), Some(&rate), &chrono::Utc)];
+
+        let zip = encode(&sessions, ExportFormat::Csv).unwrap();
+        assert_eq!(&zip[..4], b"PK\x03\x04");
+        let zip = String::from_utf8_lossy(&zip);
+        assert!(zip.contains("sessions.csv"));
+        assert!(zip.contains(",USD,true,1699990000,"));
+        assert!(zip.contains("session_id,timestamp,local_time,power_watts,source\r\n7,1700000000,2023-11-14 22:13:20,100,rapl\r\n"));
+
+        let json: serde_json::Value = serde_json::from_slice(&encode(&sessions, ExportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json[0]["label"], "Render, final");
+        assert_eq!(json[0]["local_end"], "2023-11-14 22:14:20");
+        assert_eq!(json[0]["samples"][2]["timestamp"], 1_700_000_060);
+        assert!((json[0]["converted_surplus_cost"].as_f64().unwrap() - 0.03).abs() < 1e-12);
+        assert_eq!(json[0]["converted_currency"], "USD");
+        assert_eq!(json[0]["rate_stale"], true);
+        assert_eq!(json[0]["rate_updated_at"], 1_699_990_000);
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!("json".parse::<ExportFormat>(), Ok(ExportFormat::Json));
+        assert!("xlsx".parse::<ExportFormat>().is_err());
+    }
+}
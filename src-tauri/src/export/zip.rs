@@ -0,0 +1,106 @@
+//! Minimal zip archive writer (stored entries, no compression)
+//!
+//! Enough to bundle a few exported files together; entries are kept in
+//! memory and written in one go by `finish`.
+
+use std::io::{self, Write};
+
+struct Entry {
+    name: String,
+    data: Vec<u8>,
+}
+
+/// Zip archive built in memory
+#[derive(Default)]
+pub struct ZipWriter {
+    entries: Vec<Entry>,
+}
+
+impl ZipWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file to the archive
+    pub fn add(&mut self, name: &str, data: Vec<u8>) {
+        self.entries.push(Entry { name: name.to_string(), data });
+    }
+
+    /// Write the archive: local headers and data, then the central directory
+    pub fn finish<W: Write>(self, out: &mut W) -> io::Result<()> {
+        let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "Archive too large for zip without zip64");
+        let mut offset: u32 = 0;
+        let mut central = Vec::new();
+
+        for entry in &self.entries {
+            let size = u32::try_from(entry.data.len()).map_err(|_| too_large())?;
+            let crc = crc32fast::hash(&entry.data);
+            let name = entry.name.as_bytes();
+
+            let mut local = Vec::with_capacity(30 + name.len());
+            local.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+            local.extend_from_slice(&20u16.to_le_bytes()); // version needed
+            local.extend_from_slice(&0x0800u16.to_le_bytes()); // UTF-8 names
+            local.extend_from_slice(&0u16.to_le_bytes()); // stored
+            local.extend_from_slice(&[0; 4]); // DOS time and date
+            local.extend_from_slice(&crc.to_le_bytes());
+            local.extend_from_slice(&size.to_le_bytes());
+            local.extend_from_slice(&size.to_le_bytes());
+            local.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            local.extend_from_slice(&0u16.to_le_bytes());
+            local.extend_from_slice(name);
+            out.write_all(&local)?;
+            out.write_all(&entry.data)?;
+
+            central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+            central.extend_from_slice(&20u16.to_le_bytes()); // version made by
+            central.extend_from_slice(&local[4..30]);
+            central.extend_from_slice(&[0; 6]); // comment length, disk, internal attributes
+            central.extend_from_slice(&[0; 4]); // external attributes
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name);
+
+            offset = offset
+                .checked_add(local.len() as u32)
+                .and_then(|o| o.checked_add(size))
+                .ok_or_else(too_large)?;
+        }
+
+        let count = self.entries.len() as u16;
+        out.write_all(&central)?;
+        out.write_all(&0x0605_4b50u32.to_le_bytes())?;
+        out.write_all(&[0; 4])?; // disk numbers
+        out.write_all(&count.to_le_bytes())?;
+        out.write_all(&count.to_le_bytes())?;
+        out.write_all(&(central.len() as u32).to_le_bytes())?;
+        out.write_all(&offset.to_le_bytes())?;
+        out.write_all(&0u16.to_le_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stored_archive_layout() {
+        let mut zip = ZipWriter::new();
+        zip.add("a.csv", b"x,y\r\n".to_vec());
+        zip.add("b.csv", Vec::new());
+        let mut out = Vec::new();
+        zip.finish(&mut out).unwrap();
+
+        assert_eq!(&out[..4], b"PK\x03\x04");
+        assert_eq!(&out[30..35], b"a.csv");
+        assert_eq!(&out[35..40], b"x,y\r\n");
+        assert_eq!(u32::from_le_bytes(out[14..18].try_into().unwrap()), crc32fast::hash(b"x,y\r\n"));
+
+        // End of central directory: 2 entries, directory right after the data
+        let end = &out[out.len() - 22..];
+        assert_eq!(&end[..4], b"PK\x05\x06");
+        assert_eq!(u16::from_le_bytes([end[10], end[11]]), 2);
+        let directory_offset = u32::from_le_bytes(end[16..20].try_into().unwrap()) as usize;
+        assert_eq!(directory_offset, (30 + 5 + 5) + (30 + 5));
+        assert_eq!(&out[directory_offset..directory_offset + 4], b"PK\x01\x02");
+    }
+}
@@ -0,0 +1,174 @@
+//! Foreground window sampling
+//!
+//! Resolves the process owning the focused window to its process name, and
+//! tells whether that window is fullscreen. Window titles are never read.
+//! Both calls block, the monitoring loops make them on a blocking thread.
+//! - Linux: X11 `_NET_ACTIVE_WINDOW` and `_NET_WM_PID` via `xprop`. Fullscreen
+//!   is not checked: it only pauses the GPU command line tools, which Linux
+//!   doesn't run (NVML and sysfs are read directly)
+//! - Windows: GetForegroundWindow, the owning process image name, and the
+//!   window rect against its monitor's rect (exclusive and borderless fullscreen)
+
+/// Longest wait for one `xprop` call, an unresponsive X server must not stall monitoring
+#[cfg(target_os = "linux")]
+const XPROP_TIMEOUT_MS: u64 = 500;
+
+#[cfg(target_os = "linux")]
+fn xprop(args: &[&str]) -> Option<String> {
+    let output = crate::hardware::command::run_command_with_timeout("xprop", args, XPROP_TIMEOUT_MS)?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Process name of the focused window, None when unknown (no X11, lock screen...)
+#[cfg(target_os = "linux")]
+pub fn foreground_process_name() -> Option<String> {
+    let window = parse_active_window(&xprop(&["-root", "_NET_ACTIVE_WINDOW"])?)?;
+    let pid = parse_window_pid(&xprop(&["-id", &window, "_NET_WM_PID"])?)?;
+    let comm = std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+    let name = comm.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Process name of the focused window, None when unknown (no X11, lock screen...)
+#[cfg(target_os = "windows")]
+pub fn foreground_process_name() -> Option<String> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_null() {
+            return None;
+        }
+        let mut pid: u32 = 0;
+        GetWindowThreadProcessId(hwnd, &mut pid);
+        if pid == 0 {
+            return None;
+        }
+
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if process.is_null() {
+            return None;
+        }
+        let mut buffer = [0u16; 1024];
+        let mut len = buffer.len() as u32;
+        let ok = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, buffer.as_mut_ptr(), &mut len);
+        CloseHandle(process);
+        if ok == 0 {
+            return None;
+        }
+
+        let path = String::from_utf16_lossy(&buffer[..len as usize]);
+        path.rsplit(['\\', '/']).next().filter(|n| !n.is_empty()).map(str::to_string)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+pub fn foreground_process_name() -> Option<String> {
+    None
+}
+
+/// Whether the focused window is fullscreen, false when unknown
+#[cfg(target_os = "windows")]
+pub fn foreground_is_fullscreen() -> bool {
+    use windows_sys::Win32::Foundation::RECT;
+    use windows_sys::Win32::Graphics::Gdi::{GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTONULL};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetDesktopWindow, GetForegroundWindow, GetShellWindow, GetWindowRect};
+
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        // The desktop covers the monitor too
+        if hwnd.is_null() || hwnd == GetDesktopWindow() || hwnd == GetShellWindow() {
+            return false;
+        }
+        let mut window: RECT = std::mem::zeroed();
+        if GetWindowRect(hwnd, &mut window) == 0 {
+            return false;
+        }
+        let monitor = MonitorFromWindow(hwnd, MONITOR_DEFAULTTONULL);
+        if monitor.is_null() {
+            return false;
+        }
+        let mut info: MONITORINFO = std::mem::zeroed();
+        info.cbSize = std::mem::size_of::<MONITORINFO>() as u32;
+        if GetMonitorInfoW(monitor, &mut info) == 0 {
+            return false;
+        }
+        let m = info.rcMonitor;
+        covers_monitor((window.left, window.top, window.right, window.bottom), (m.left, m.top, m.right, m.bottom))
+    }
+}
+
+/// Whether the focused window is fullscreen, false when unknown or not checked (Linux)
+#[cfg(not(target_os = "windows"))]
+pub fn foreground_is_fullscreen() -> bool {
+    false
+}
+
+/// Window id from `xprop -root _NET_ACTIVE_WINDOW` ("... window id # 0x3a00007")
+///
+/// Some window managers append a second value ("# 0x3a00007, 0x0"), the first is the active window.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_active_window(output: &str) -> Option<String> {
+    let (_, ids) = output.split_once('#')?;
+    let id = ids.split(',').next()?.trim();
+    let hex = id.strip_prefix("0x")?;
+    match u64::from_str_radix(hex, 16) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(id.to_string()),
+    }
+}
+
+/// Pid from `xprop -id <window> _NET_WM_PID` ("_NET_WM_PID(CARDINAL) = 1234")
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_window_pid(output: &str) -> Option<u32> {
+    let (_, value) = output.split_once('=')?;
+    value.trim().parse().ok().filter(|&pid| pid > 0)
+}
+
+/// Whether a window rect covers the whole monitor rect (left, top, right, bottom)
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn covers_monitor(window: (i32, i32, i32, i32), monitor: (i32, i32, i32, i32)) -> bool {
+    let (left, top, right, bottom) = window;
+    let (m_left, m_top, m_right, m_bottom) = monitor;
+    m_right > m_left && left <= m_left && top <= m_top && right >= m_right && bottom >= m_bottom
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_xprop_output() {
+        assert_eq!(
+            parse_active_window("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007\n"),
+            Some("0x3a00007".to_string())
+        );
+        // No focused window
+        assert_eq!(
+            parse_active_window("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x2c00003, 0x0\n"),
+            Some("0x2c00003".to_string())
+        );
+        assert_eq!(parse_active_window("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x0\n"), None);
+        assert_eq!(parse_active_window("_NET_ACTIVE_WINDOW:  not found.\n"), None);
+
+        assert_eq!(parse_window_pid("_NET_WM_PID(CARDINAL) = 4242\n"), Some(4242));
+        assert_eq!(parse_window_pid("_NET_WM_PID:  not found.\n"), None);
+    }
+
+    #[test]
+    fn test_fullscreen_detection() {
+        let monitor = (1920, 0, 4480, 1440);
+        // Borderless fullscreen, and a window with its borders pushed off screen
+        assert!(covers_monitor((1920, 0, 4480, 1440), monitor));
+        assert!(covers_monitor((1912, -8, 4488, 1448), monitor));
+        // Maximized: the taskbar stays visible
+        assert!(!covers_monitor((1920, 0, 4480, 1392), monitor));
+    }
+}
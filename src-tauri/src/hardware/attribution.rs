@@ -0,0 +1,83 @@
+//! Per-process power attribution
+//!
+//! A process gets the share of CPU-side power matching its share of the total
+//! CPU load. It's an estimate (it ignores frequency and core type differences),
+//! good enough to show and subtract the tracker's own footprint.
+
+/// Aggregation key for a process in the by-name process list
+///
+/// Our own process is never merged with others of the same name (e.g. a second
+/// instance in dev builds), so its usage stays identifiable.
+pub(crate) fn process_group_key(pid: u32, name: &str) -> String {
+    if pid == std::process::id() {
+        format!("{}#{}", name, pid)
+    } else {
+        name.to_string()
+    }
+}
+
+/// CPU usage of our own process (sysinfo convention: 100 = one logical core)
+pub(crate) fn self_cpu_percent(sys: &sysinfo::System) -> Option<f64> {
+    sys.process(sysinfo::Pid::from_u32(std::process::id()))
+        .map(|p| p.cpu_usage() as f64)
+}
+
+/// Estimate the power drawn by a process
+///
+/// - `process_cpu_percent`: process usage, 100 = one logical core
+/// - `logical_cpus`: number of logical cores
+/// - `system_cpu_percent`: overall usage, 100 = all cores busy
+/// - `cpu_power_watts`: power attributable to the CPU (total minus GPU)
+pub fn process_power_watts(
+    process_cpu_percent: f64,
+    logical_cpus: usize,
+    system_cpu_percent: f64,
+    cpu_power_watts: f64,
+) -> f64 {
+    if logical_cpus == 0 || system_cpu_percent <= 0.0 || cpu_power_watts <= 0.0 {
+        return 0.0;
+    }
+    let process_fraction = process_cpu_percent.max(0.0) / (100.0 * logical_cpus as f64);
+    let share = (process_fraction / (system_cpu_percent / 100.0)).min(1.0);
+    share * cpu_power_watts
+}
+
+/// Power above baseline, optionally excluding the app's own consumption
+pub fn surplus_watts(power_watts: f64, baseline_watts: f64, excluded_watts: Option<f64>) -> f64 {
+    (power_watts - baseline_watts - excluded_watts.unwrap_or(0.0).max(0.0)).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_power_share() {
+        // Half a core out of 8, machine at 25% => 1/4 of the load
+        let watts = process_power_watts(50.0, 8, 25.0, 80.0);
+        assert!((watts - 20.0).abs() < 1e-9);
+
+        // A process can't get more than all of the CPU power
+        assert!((process_power_watts(800.0, 8, 50.0, 80.0) - 80.0).abs() < 1e-9);
+
+        // Idle machine or missing data
+        assert_eq!(process_power_watts(5.0, 8, 0.0, 80.0), 0.0);
+        assert_eq!(process_power_watts(5.0, 0, 10.0, 80.0), 0.0);
+    }
+
+    #[test]
+    fn test_surplus_exclusion() {
+        assert_eq!(surplus_watts(150.0, 50.0, None), 100.0);
+        assert_eq!(surplus_watts(150.0, 50.0, Some(2.5)), 97.5);
+        // Never negative, even if the estimate exceeds the surplus
+        assert_eq!(surplus_watts(52.0, 50.0, Some(5.0)), 0.0);
+        assert_eq!(surplus_watts(40.0, 50.0, None), 0.0);
+    }
+
+    #[test]
+    fn test_self_not_merged() {
+        let me = std::process::id();
+        assert_eq!(process_group_key(me.wrapping_add(1), "powercost-tracker"), "powercost-tracker");
+        assert_ne!(process_group_key(me, "powercost-tracker"), "powercost-tracker");
+    }
+}
@@ -1,57 +1,123 @@
 //! Baseline power detection for surplus tracking
 //!
 //! Detects idle power consumption to calculate power usage above baseline.
+//! With a baseline schedule, each time-of-day bucket (day/night by default)
+//! keeps its own samples and baseline. The `_at` methods take the local time,
+//! the others use the current one.
 
-use crate::core::BaselineDetection;
+use crate::core::clock::parse_hhmm;
+use crate::core::{BaselineDetection, BaselineReport, BaselineSchedule, BucketBaseline};
+use chrono::{NaiveTime, Timelike};
 use std::collections::VecDeque;
 
 /// Sample window size for baseline detection
 const DEFAULT_SAMPLE_WINDOW: usize = 60; // 60 samples at 1s = 1 minute
 const BASELINE_PERCENTILE: f64 = 0.05; // 5th percentile for baseline
 
+/// Samples and baseline of one time-of-day bucket
+struct Bucket {
+    name: String,
+    start: String,
+    /// Minutes since midnight of `start`
+    start_minutes: u32,
+    manual_watts: Option<f64>,
+    samples: VecDeque<f64>,
+    last_detected: Option<f64>,
+}
+
+impl Bucket {
+    fn new(name: &str, start: &str, manual_watts: Option<f64>, capacity: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            start: start.to_string(),
+            start_minutes: parse_hhmm(start).unwrap_or(0),
+            manual_watts,
+            samples: VecDeque::with_capacity(capacity),
+            last_detected: None,
+        }
+    }
+}
+
 /// Baseline detector for power consumption
 ///
 /// Uses a sliding window of power readings to detect the baseline (idle) power
 /// consumption. The baseline is calculated as the 5th percentile of readings
 /// to filter out occasional low spikes while capturing true idle power.
 pub struct BaselineDetector {
-    /// Power readings window for detection
-    samples: VecDeque<f64>,
-    /// Maximum samples to keep
+    /// Buckets sorted by start, a single all-day one without a schedule
+    buckets: Vec<Bucket>,
+    /// Whether `buckets` come from a baseline schedule
+    scheduled: bool,
+    /// Maximum samples to keep per bucket
     max_samples: usize,
     /// Manually set baseline (overrides auto-detection)
     manual_baseline: Option<f64>,
-    /// Last detected baseline
-    last_detected: Option<f64>,
 }
 
 impl BaselineDetector {
     /// Create a new baseline detector with default settings
     pub fn new() -> Self {
-        Self {
-            samples: VecDeque::with_capacity(DEFAULT_SAMPLE_WINDOW),
-            max_samples: DEFAULT_SAMPLE_WINDOW,
-            manual_baseline: None,
-            last_detected: None,
-        }
+        Self::with_window_size(DEFAULT_SAMPLE_WINDOW)
     }
 
     /// Create a baseline detector with custom sample window size
     pub fn with_window_size(size: usize) -> Self {
         Self {
-            samples: VecDeque::with_capacity(size),
+            buckets: vec![Bucket::new("all_day", "00:00", None, size)],
+            scheduled: false,
             max_samples: size,
             manual_baseline: None,
-            last_detected: None,
         }
     }
 
+    /// Use the buckets of `schedule`, one baseline all day when it is off
+    ///
+    /// Samples are kept when the bucket times do not change.
+    pub fn set_schedule(&mut self, schedule: &BaselineSchedule) {
+        let mut wanted: Vec<(&str, &str, Option<f64>)> = if schedule.enabled && !schedule.buckets.is_empty() {
+            schedule.buckets.iter().map(|b| (b.name.as_str(), b.start.as_str(), b.manual_watts)).collect()
+        } else {
+            vec![("all_day", "00:00", None)]
+        };
+        wanted.sort_by_key(|(_, start, _)| parse_hhmm(start).unwrap_or(0));
+
+        let unchanged = self.scheduled == schedule.enabled
+            && wanted.len() == self.buckets.len()
+            && wanted.iter().zip(&self.buckets).all(|((name, start, _), b)| b.name == *name && b.start == *start);
+        if unchanged {
+            for ((_, _, manual_watts), bucket) in wanted.iter().zip(self.buckets.iter_mut()) {
+                bucket.manual_watts = *manual_watts;
+            }
+        } else {
+            self.buckets = wanted.iter().map(|(name, start, manual)| Bucket::new(name, start, *manual, self.max_samples)).collect();
+        }
+        self.scheduled = schedule.enabled && !schedule.buckets.is_empty();
+    }
+
+    /// Index of the bucket `time` falls in, the last one runs past midnight
+    fn bucket_index(&self, time: NaiveTime) -> usize {
+        let minutes = time.hour() * 60 + time.minute();
+        self.buckets.iter().rposition(|b| b.start_minutes <= minutes).unwrap_or(self.buckets.len() - 1)
+    }
+
+    fn now() -> NaiveTime {
+        chrono::Local::now().time()
+    }
+
     /// Add a power reading sample
     pub fn add_sample(&mut self, power_watts: f64) {
-        if self.samples.len() >= self.max_samples {
-            self.samples.pop_front();
+        self.add_sample_at(power_watts, Self::now());
+    }
+
+    /// Add a power reading sample to the bucket of `time`
+    pub fn add_sample_at(&mut self, power_watts: f64, time: NaiveTime) {
+        let max_samples = self.max_samples;
+        let index = self.bucket_index(time);
+        let samples = &mut self.buckets[index].samples;
+        if samples.len() >= max_samples {
+            samples.pop_front();
         }
-        self.samples.push_back(power_watts);
+        samples.push_back(power_watts);
     }
 
     /// Set a manual baseline (disables auto-detection)
@@ -68,7 +134,29 @@ impl BaselineDetector {
     ///
     /// Returns manual baseline if set, otherwise auto-detected baseline.
     pub fn get_baseline(&self) -> Option<f64> {
-        self.manual_baseline.or(self.last_detected)
+        self.get_baseline_at(Self::now())
+    }
+
+    /// Baseline in effect at `time`: the bucket's manual value, the global
+    /// manual baseline, then the bucket's detected one
+    pub fn get_baseline_at(&self, time: NaiveTime) -> Option<f64> {
+        let bucket = &self.buckets[self.bucket_index(time)];
+        bucket.manual_watts.or(self.manual_baseline).or(bucket.last_detected)
+    }
+
+    /// Manual value of the bucket in effect now, if it has one
+    pub fn bucket_manual_baseline(&self) -> Option<f64> {
+        self.buckets[self.bucket_index(Self::now())].manual_watts
+    }
+
+    /// Name of the bucket in effect at `time`, None without a schedule
+    pub fn bucket_name_at(&self, time: NaiveTime) -> Option<&str> {
+        self.scheduled.then(|| self.buckets[self.bucket_index(time)].name.as_str())
+    }
+
+    /// Name of the bucket in effect now, None without a schedule
+    pub fn current_bucket_name(&self) -> Option<&str> {
+        self.bucket_name_at(Self::now())
     }
 
     /// Check if using manual baseline
@@ -78,7 +166,7 @@ impl BaselineDetector {
 
     /// Get the number of samples collected
     pub fn sample_count(&self) -> usize {
-        self.samples.len()
+        self.buckets.iter().map(|b| b.samples.len()).sum()
     }
 
     /// Detect baseline from current samples
@@ -86,13 +174,41 @@ impl BaselineDetector {
     /// Uses the 5th percentile of readings as the baseline.
     /// Returns None if not enough samples are available.
     pub fn detect_baseline(&mut self) -> Option<BaselineDetection> {
-        if self.samples.len() < 10 {
+        self.detect_baseline_at(Self::now()).current
+    }
+
+    /// Detect the baseline of every bucket, `time` telling which one is in effect
+    pub fn detect_baseline_at(&mut self, time: NaiveTime) -> BaselineReport {
+        let active = self.bucket_index(time);
+        let detections: Vec<Option<BaselineDetection>> = (0..self.buckets.len()).map(|i| self.detect_bucket(i)).collect();
+        let buckets = if self.scheduled {
+            self.buckets
+                .iter()
+                .zip(&detections)
+                .enumerate()
+                .map(|(i, (bucket, detection))| BucketBaseline {
+                    name: bucket.name.clone(),
+                    start: bucket.start.clone(),
+                    manual_watts: bucket.manual_watts,
+                    detection: detection.clone(),
+                    active: i == active,
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        BaselineReport { current: detections[active].clone(), buckets }
+    }
+
+    fn detect_bucket(&mut self, index: usize) -> Option<BaselineDetection> {
+        let bucket = &mut self.buckets[index];
+        if bucket.samples.len() < 10 {
             // Need at least 10 samples for meaningful detection
             return None;
         }
 
         // Sort samples to find percentile
-        let mut sorted: Vec<f64> = self.samples.iter().copied().collect();
+        let mut sorted: Vec<f64> = bucket.samples.iter().copied().collect();
         sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
 
         // Calculate 5th percentile index
@@ -100,22 +216,22 @@ impl BaselineDetector {
         let baseline = sorted.get(index).copied().unwrap_or(sorted[0]);
 
         // Calculate confidence based on sample count and variance
-        let sample_ratio = self.samples.len() as f64 / self.max_samples as f64;
-        let variance = self.calculate_variance(&sorted);
+        let sample_ratio = bucket.samples.len() as f64 / self.max_samples as f64;
+        let variance = Self::calculate_variance(&sorted);
         let variance_factor = 1.0 / (1.0 + variance / 100.0); // Normalize variance impact
         let confidence = (sample_ratio * variance_factor).clamp(0.0, 1.0);
 
-        self.last_detected = Some(baseline);
+        bucket.last_detected = Some(baseline);
 
         Some(BaselineDetection {
             detected_watts: baseline,
-            sample_count: self.samples.len(),
+            sample_count: bucket.samples.len(),
             confidence,
         })
     }
 
     /// Calculate variance of samples
-    fn calculate_variance(&self, sorted: &[f64]) -> f64 {
+    fn calculate_variance(sorted: &[f64]) -> f64 {
         if sorted.is_empty() {
             return 0.0;
         }
@@ -151,8 +267,10 @@ impl BaselineDetector {
 
     /// Clear all samples and reset detection
     pub fn reset(&mut self) {
-        self.samples.clear();
-        self.last_detected = None;
+        for bucket in &mut self.buckets {
+            bucket.samples.clear();
+            bucket.last_detected = None;
+        }
     }
 }
 
@@ -219,4 +337,66 @@ mod tests {
         let surplus_wh = detector.calculate_surplus_wh(40.0, 1.0);
         assert_eq!(surplus_wh, 0.0);
     }
+
+    fn at(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    fn day_night() -> BaselineSchedule {
+        BaselineSchedule { enabled: true, ..Default::default() }
+    }
+
+    #[test]
+    fn test_day_night_buckets() {
+        let mut detector = BaselineDetector::with_window_size(20);
+        detector.set_schedule(&day_night());
+        for _ in 0..15 {
+            detector.add_sample_at(80.0, at(14, 0));
+            detector.add_sample_at(35.0, at(3, 0));
+        }
+
+        let report = detector.detect_baseline_at(at(14, 0));
+        assert_eq!(report.current.unwrap().detected_watts, 80.0);
+        assert_eq!(report.buckets.len(), 2);
+        assert!(report.buckets[0].active && report.buckets[0].name == "day");
+        assert_eq!(report.buckets[1].detection.as_ref().unwrap().detected_watts, 35.0);
+
+        // Night runs from 23:00 past midnight to 07:00
+        assert_eq!(detector.get_baseline_at(at(23, 30)), Some(35.0));
+        assert_eq!(detector.get_baseline_at(at(6, 59)), Some(35.0));
+        assert_eq!(detector.get_baseline_at(at(7, 0)), Some(80.0));
+        assert_eq!(detector.bucket_name_at(at(0, 0)), Some("night"));
+    }
+
+    #[test]
+    fn test_bucket_overrides_and_schedule_changes() {
+        let mut detector = BaselineDetector::with_window_size(20);
+        let mut schedule = day_night();
+        schedule.buckets[1].manual_watts = Some(30.0);
+        detector.set_schedule(&schedule);
+        detector.set_manual_baseline(60.0);
+        for _ in 0..15 {
+            detector.add_sample_at(90.0, at(12, 0));
+        }
+        detector.detect_baseline_at(at(12, 0));
+
+        // The bucket's own value wins, then the global manual one
+        assert_eq!(detector.get_baseline_at(at(1, 0)), Some(30.0));
+        assert_eq!(detector.get_baseline_at(at(12, 0)), Some(60.0));
+        detector.clear_manual_baseline();
+        assert_eq!(detector.get_baseline_at(at(12, 0)), Some(90.0));
+
+        // Same bucket times keep the samples, other times start over
+        schedule.buckets[1].manual_watts = None;
+        detector.set_schedule(&schedule);
+        assert_eq!(detector.sample_count(), 15);
+        schedule.buckets[0].start = "08:00".into();
+        detector.set_schedule(&schedule);
+        assert_eq!(detector.sample_count(), 0);
+
+        // Turning the schedule off leaves one all-day baseline
+        detector.set_schedule(&BaselineSchedule::default());
+        assert_eq!(detector.bucket_name_at(at(12, 0)), None);
+        assert!(detector.detect_baseline_at(at(12, 0)).buckets.is_empty());
+    }
 }
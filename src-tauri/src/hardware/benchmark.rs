@@ -0,0 +1,260 @@
+//! Side-by-side comparison of the available power sources
+//!
+//! Each source is instantiated on its own thread, independently of the
+//! active monitor, and sampled for the requested duration. Sources that hang
+//! (a GPU command stuck past its timeout, a slow WMI query) are reported with
+//! the samples gathered so far once twice the duration has elapsed.
+
+use super::PowerSource;
+use crate::core::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Longest benchmark accepted
+pub const MAX_DURATION_SECS: u64 = 120;
+
+/// Interval between two samples of a source
+pub const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Builds a fresh instance of a power source
+type SourceFactory = fn() -> Result<Box<dyn PowerSource>>;
+
+/// Statistics of one source over the benchmark
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceBenchmark {
+    /// Source tag ("rapl", "hwmon", "battery", "wmi", "powermetrics", "estimator")
+    pub source: String,
+    /// Display name, None when the source could not be created
+    pub name: Option<String>,
+    pub is_estimated: bool,
+    pub samples: usize,
+    /// Reads that returned an error or a non-finite value
+    pub failures: usize,
+    pub mean_watts: Option<f64>,
+    pub stddev_watts: Option<f64>,
+    /// Creation error, or the last read error
+    pub error: Option<String>,
+    /// Still sampling when the benchmark gave up on it
+    pub timed_out: bool,
+}
+
+/// Result of a benchmark run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    /// Unix timestamp
+    pub started_at: i64,
+    pub duration_secs: u64,
+    pub results: Vec<SourceBenchmark>,
+}
+
+/// Samples gathered by a source thread
+#[derive(Default)]
+struct SourceSamples {
+    name: Option<String>,
+    is_estimated: bool,
+    values: Vec<f64>,
+    failures: usize,
+    error: Option<String>,
+    finished: bool,
+}
+
+/// Sources that can be tried on this platform
+fn candidates() -> Vec<(&'static str, SourceFactory)> {
+    let mut list: Vec<(&'static str, SourceFactory)> = Vec::new();
+
+    #[cfg(target_os = "linux")]
+    {
+        list.push(("rapl", || Ok(Box::new(super::linux::LinuxSystemMonitor::try_rapl()?))));
+        list.push(("hwmon", || Ok(Box::new(super::linux::LinuxSystemMonitor::try_hwmon()?))));
+        list.push(("battery", || Ok(Box::new(super::linux::LinuxSystemMonitor::try_battery()?))));
+    }
+
+    #[cfg(target_os = "windows")]
+    list.push(("wmi", || Ok(Box::new(super::windows::WmiMonitor::new(&super::MonitorOptions::default())?))));
+
+    #[cfg(target_os = "macos")]
+    list.push(("powermetrics", || Ok(Box::new(super::macos::MacMonitor::new()?))));
+
+    list.push(("estimator", || Ok(Box::new(super::estimator::EstimationMonitor::new()))));
+    list
+}
+
+/// Mean and population standard deviation
+fn mean_stddev(values: &[f64]) -> Option<(f64, f64)> {
+    if values.is_empty() {
+        return None;
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    Some((mean, variance.sqrt()))
+}
+
+/// Create a source and sample it until `duration` has elapsed
+fn sample_source(factory: SourceFactory, slot: &Mutex<SourceSamples>, duration: Duration, interval: Duration) {
+    let lock = || slot.lock().unwrap_or_else(|e| e.into_inner());
+
+    let source = match factory() {
+        Ok(source) => source,
+        Err(e) => {
+            let mut samples = lock();
+            samples.error = Some(e.to_string());
+            samples.finished = true;
+            return;
+        }
+    };
+    {
+        let mut samples = lock();
+        samples.name = Some(source.name().to_string());
+        samples.is_estimated = source.is_estimated();
+    }
+
+    // Warm-up read: delta-based sources (RAPL, CPU usage) need a first reference
+    let _ = source.get_power_watts();
+
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        std::thread::sleep(interval);
+        let result = source.get_power_watts();
+        let mut samples = lock();
+        match result {
+            Ok(watts) if watts.is_finite() => samples.values.push(watts),
+            Ok(_) => samples.failures += 1,
+            Err(e) => {
+                samples.failures += 1;
+                samples.error = Some(e.to_string());
+            }
+        }
+    }
+    lock().finished = true;
+}
+
+fn run_with(factories: Vec<(&'static str, SourceFactory)>, duration: Duration, interval: Duration) -> BenchmarkReport {
+    let started_at = chrono::Utc::now().timestamp();
+    let started = Instant::now();
+    let deadline = duration * 2;
+    let (done_tx, done_rx) = mpsc::channel();
+
+    let mut slots = Vec::with_capacity(factories.len());
+    for (tag, factory) in factories {
+        let slot = Arc::new(Mutex::new(SourceSamples::default()));
+        slots.push((tag, Arc::clone(&slot)));
+        let done_tx = done_tx.clone();
+        std::thread::spawn(move || {
+            sample_source(factory, &slot, duration, interval);
+            let _ = done_tx.send(());
+        });
+    }
+    drop(done_tx);
+
+    // Hung threads are left behind; their source is dropped when they return
+    for _ in 0..slots.len() {
+        let remaining = deadline.saturating_sub(started.elapsed());
+        if remaining.is_zero() || done_rx.recv_timeout(remaining).is_err() {
+            break;
+        }
+    }
+
+    let results = slots
+        .into_iter()
+        .map(|(tag, slot)| {
+            let samples = slot.lock().unwrap_or_else(|e| e.into_inner());
+            let stats = mean_stddev(&samples.values);
+            SourceBenchmark {
+                source: tag.to_string(),
+                name: samples.name.clone(),
+                is_estimated: samples.is_estimated,
+                samples: samples.values.len(),
+                failures: samples.failures,
+                mean_watts: stats.map(|(mean, _)| mean),
+                stddev_watts: stats.map(|(_, stddev)| stddev),
+                error: samples.error.clone(),
+                timed_out: !samples.finished,
+            }
+        })
+        .collect();
+
+    BenchmarkReport {
+        started_at,
+        duration_secs: duration.as_secs(),
+        results,
+    }
+}
+
+/// Sample every available source in parallel for `duration` (blocking, at most twice as long)
+pub fn run(duration: Duration) -> BenchmarkReport {
+    run_with(candidates(), duration, SAMPLE_INTERVAL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Error, PowerReading};
+    use std::any::Any;
+
+    struct FakeSource {
+        watts: f64,
+        delay: Duration,
+    }
+
+    impl PowerSource for FakeSource {
+        fn get_power_watts(&self) -> Result<f64> {
+            std::thread::sleep(self.delay);
+            Ok(self.watts)
+        }
+
+        fn get_reading(&self) -> Result<PowerReading> {
+            Ok(PowerReading::new(self.watts, "fake", false))
+        }
+
+        fn name(&self) -> &str {
+            "Fake"
+        }
+
+        fn is_estimated(&self) -> bool {
+            false
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[test]
+    fn test_mean_stddev() {
+        assert_eq!(mean_stddev(&[]), None);
+        let (mean, stddev) = mean_stddev(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]).unwrap();
+        assert!((mean - 5.0).abs() < 1e-9);
+        assert!((stddev - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_hung_source_does_not_block() {
+        let factories: Vec<(&'static str, SourceFactory)> = vec![
+            ("steady", || Ok(Box::new(FakeSource { watts: 42.0, delay: Duration::ZERO }))),
+            // Hangs on its first read
+            ("hung", || Ok(Box::new(FakeSource { watts: 0.0, delay: Duration::from_secs(30) }))),
+            ("missing", || Err(Error::HardwareNotSupported("not here".to_string()))),
+        ];
+
+        let started = Instant::now();
+        let report = run_with(factories, Duration::from_millis(200), Duration::from_millis(20));
+        assert!(started.elapsed() < Duration::from_millis(600));
+
+        let steady = &report.results[0];
+        assert!(!steady.timed_out);
+        assert!(steady.samples > 0);
+        assert_eq!(steady.mean_watts, Some(42.0));
+        assert_eq!(steady.stddev_watts, Some(0.0));
+
+        let hung = &report.results[1];
+        assert!(hung.timed_out);
+        assert_eq!(hung.samples, 0);
+
+        let missing = &report.results[2];
+        assert!(!missing.timed_out);
+        assert_eq!(missing.name, None);
+        assert!(missing.error.as_deref().unwrap().contains("not here"));
+    }
+}
@@ -0,0 +1,122 @@
+//! What the active power source can report
+//!
+//! Probed once per monitor by collecting every detailed metric and noting
+//! which ones came back, so the UI can hide panels that would stay empty.
+
+use crate::core::{DetailedMetrics, SourceCapabilities};
+use crate::hardware::{DetailedOptions, PowerMonitor};
+
+/// Processes enumerated by the probe, enough to find one with GPU usage
+const PROBE_PROCESS_LIMIT: usize = 50;
+
+/// Collect all metrics once and report which were available (may run GPU tools)
+pub fn probe(monitor: &PowerMonitor) -> SourceCapabilities {
+    let options = DetailedOptions {
+        extended: true,
+        processes: true,
+        gpu: true,
+        pause_gpu_cli: false,
+        group_by_container: false,
+    };
+    let metrics = monitor.collect_detailed_metrics(PROBE_PROCESS_LIMIT, &[], &[], options).ok();
+    from_metrics(!monitor.is_estimated(), monitor.has_battery(), metrics.as_ref())
+}
+
+/// Capabilities shown by one detailed collection (None when it failed)
+pub fn from_metrics(real_power: bool, battery: bool, metrics: Option<&DetailedMetrics>) -> SourceCapabilities {
+    let mut capabilities = SourceCapabilities {
+        platform: std::env::consts::OS.to_string(),
+        real_power,
+        battery,
+        ..SourceCapabilities::default()
+    };
+    let Some(metrics) = metrics else {
+        return capabilities;
+    };
+
+    // A GPU listed without any reading gives nothing to show
+    capabilities.gpu_metrics = metrics
+        .system_metrics
+        .as_ref()
+        .and_then(|sys| sys.gpu.as_ref())
+        .is_some_and(|gpu| gpu.usage_percent.is_some() || gpu.power_watts.is_some() || gpu.temperature_celsius.is_some());
+    capabilities.gpu_per_process = metrics.top_processes.iter().any(|p| p.gpu_percent.is_some());
+    if let Some(sys) = &metrics.system_metrics {
+        capabilities.cpu_temperature = sys.cpu.temperature_celsius.is_some();
+        capabilities.per_core_temperature = sys.cpu.per_core_temperature.as_ref().is_some_and(|temps| temps.len() > 1);
+        capabilities.fans = sys.fans.as_ref().is_some_and(|fans| !fans.fans.is_empty());
+        capabilities.voltages = sys.voltages.as_ref().is_some_and(|voltages| !voltages.is_empty());
+        capabilities.memory_info = sys.memory.memory_speed_mhz.is_some() || sys.memory.memory_type.is_some();
+    }
+    capabilities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{CpuMetrics, FanMetrics, GpuMetrics, MemoryMetrics, ProcessTotals, SystemMetrics};
+
+    #[test]
+    fn test_capabilities_from_metrics() {
+        let failed = from_metrics(false, true, None);
+        assert!(!failed.real_power && failed.battery && !failed.gpu_metrics && !failed.cpu_temperature);
+        assert_eq!(failed.platform, std::env::consts::OS);
+
+        let sys = SystemMetrics {
+            cpu: CpuMetrics {
+                name: "CPU".to_string(),
+                usage_percent: 10.0,
+                per_core_usage: vec![10.0, 10.0],
+                frequency_mhz: None,
+                temperature_celsius: Some(45.0),
+                core_count: 2,
+                thread_count: 2,
+                per_core_frequency_mhz: None,
+                per_core_temperature: Some(vec![44.0]),
+            },
+            gpu: Some(GpuMetrics {
+                name: "GPU".to_string(),
+                usage_percent: None,
+                power_watts: None,
+                temperature_celsius: None,
+                vram_used_mb: None,
+                vram_total_mb: None,
+                clock_mhz: None,
+                source: "none".to_string(),
+                memory_clock_mhz: None,
+                fan_speed_percent: None,
+            }),
+            memory: MemoryMetrics {
+                used_bytes: 1,
+                total_bytes: 2,
+                usage_percent: 50.0,
+                swap_used_bytes: None,
+                swap_total_bytes: None,
+                swap_usage_percent: None,
+                memory_speed_mhz: None,
+                memory_type: Some("DDR5".to_string()),
+                power_watts: None,
+            },
+            timestamp: 0,
+            fans: Some(FanMetrics { fans: Vec::new() }),
+            voltages: None,
+        };
+        let metrics = DetailedMetrics {
+            system_metrics: Some(sys),
+            top_processes: Vec::new(),
+            process_totals: ProcessTotals::default(),
+            timestamp: 0,
+            extended_collected: true,
+            measured_energy_joules: None,
+            app_self_cpu_percent: None,
+            app_self_power_watts: None,
+            gpu_stale: false,
+            timestamp_ms: 0,
+        };
+        let capabilities = from_metrics(true, false, Some(&metrics));
+        assert!(capabilities.real_power && capabilities.cpu_temperature && capabilities.memory_info);
+        // Empty GPU reading, a single core sensor and no fan are not capabilities
+        assert!(!capabilities.gpu_metrics && !capabilities.per_core_temperature && !capabilities.fans);
+        assert!(!capabilities.voltages && !capabilities.gpu_per_process);
+    }
+}
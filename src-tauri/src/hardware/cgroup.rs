@@ -0,0 +1,200 @@
+//! Container of a process, from /proc/<pid>/cgroup
+//!
+//! Processes started by Docker or Podman show up under generic names
+//! ("python3", "node"), their cgroup tells which container they run in:
+//! - Docker: `docker-<id>.scope` (systemd driver) or `/docker/<id>` (cgroupfs driver)
+//! - Podman: `libpod-<id>.scope` or `/libpod_parent/libpod-<id>`
+//! - LXC: `/lxc/<name>` or `/lxc.payload.<name>`
+//! - systemd services: the `.service` unit, outside the user managers
+//!
+//! Container IDs are shortened to 12 characters like `docker ps` shows them,
+//! names would need the runtime's API. Both cgroup v1 (one line per
+//! controller) and v2 (a single `0::` line) layouts are read.
+//!
+//! A process stays in the container it started in, `ContainerCache` reads
+//! each one's cgroup once.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+/// Characters of a container ID kept, as printed by `docker ps`
+const SHORT_ID_LEN: usize = 12;
+
+/// Container of a running process, None outside containers or where cgroups can't be read
+pub fn container_of(pid: u32) -> Option<String> {
+    let content = fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+    parse_container(&content)
+}
+
+/// Whether `name` has the form of a name `container_of` returns
+pub fn is_container_name(name: &str) -> bool {
+    ["docker:", "podman:", "lxc:"].iter().any(|prefix| name.starts_with(prefix)) || name.ends_with(".service")
+}
+
+/// Container of each process, keyed by pid and start time so a reused pid is read again
+#[derive(Debug, Default)]
+pub struct ContainerCache {
+    by_process: HashMap<(u32, u64), Option<String>>,
+}
+
+impl ContainerCache {
+    /// Container of the process `pid` started at `start_time`, read on first use
+    pub fn get(&mut self, pid: u32, start_time: u64) -> Option<String> {
+        self.by_process.entry((pid, start_time)).or_insert_with(|| container_of(pid)).clone()
+    }
+
+    /// Forget the processes not in `running`
+    pub fn retain(&mut self, running: &HashSet<(u32, u64)>) {
+        self.by_process.retain(|key, _| running.contains(key));
+    }
+}
+
+/// Container named by the content of a /proc/<pid>/cgroup file
+///
+/// The unified (v2) hierarchy is looked at first, then the v1 `name=systemd`
+/// one, then the controllers in order.
+pub fn parse_container(content: &str) -> Option<String> {
+    let mut paths: Vec<(u8, &str)> = content
+        .lines()
+        .filter_map(|line| {
+            // hierarchy-ID:controller-list:cgroup-path
+            let mut fields = line.splitn(3, ':');
+            let (id, controllers, path) = (fields.next()?, fields.next()?, fields.next()?);
+            let priority = match (id, controllers) {
+                ("0", "") => 0,
+                (_, "name=systemd") => 1,
+                _ => 2,
+            };
+            Some((priority, path))
+        })
+        .collect();
+    paths.sort_by_key(|(priority, _)| *priority);
+    paths.into_iter().find_map(|(_, path)| container_in_path(path))
+}
+
+/// Container of one cgroup path, the innermost component that names one
+fn container_in_path(path: &str) -> Option<String> {
+    let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+
+    for (i, component) in components.iter().enumerate().rev() {
+        if let Some(id) = scope_id(component, "docker-") {
+            return Some(format!("docker:{}", short_id(id)));
+        }
+        // The conmon monitor sits next to the container, not in it
+        if !component.starts_with("libpod-conmon-") {
+            if let Some(id) = scope_id(component, "libpod-") {
+                return Some(format!("podman:{}", short_id(id)));
+            }
+        }
+        if let Some(name) = component.strip_prefix("lxc.payload.") {
+            return Some(format!("lxc:{}", name));
+        }
+        if i > 0 && is_container_id(component) {
+            match components[i - 1] {
+                "docker" => return Some(format!("docker:{}", short_id(component))),
+                "libpod_parent" => return Some(format!("podman:{}", short_id(component))),
+                _ => {}
+            }
+        }
+        if i > 0 && components[i - 1] == "lxc" {
+            return Some(format!("lxc:{}", component));
+        }
+    }
+
+    // Plain services: the last unit that isn't a user's service manager
+    components
+        .iter()
+        .rev()
+        .find(|c| c.ends_with(".service") && !c.starts_with("user@"))
+        .map(|unit| unit.to_string())
+}
+
+/// ID of a `<prefix><id>.scope` unit (or the bare `<prefix><id>` of cgroupfs layouts)
+fn scope_id<'a>(component: &'a str, prefix: &str) -> Option<&'a str> {
+    let id = component.strip_prefix(prefix)?;
+    let id = id.strip_suffix(".scope").unwrap_or(id);
+    is_container_id(id).then_some(id)
+}
+
+/// Hex IDs given to containers by Docker and Podman (64 characters)
+fn is_container_id(id: &str) -> bool {
+    id.len() >= SHORT_ID_LEN && id.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn short_id(id: &str) -> &str {
+    &id[..SHORT_ID_LEN.min(id.len())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "3f1c2b9a8e7d6c5b4a39281706f5e4d3c2b1a09f8e7d6c5b4a3928170f6e5d4c";
+
+    #[test]
+    fn test_container_cache() {
+        let pid = std::process::id();
+        let mut cache = ContainerCache::default();
+        assert_eq!(cache.get(pid, 1), container_of(pid));
+        cache.get(pid, 2);
+        cache.retain(&HashSet::from([(pid, 2)]));
+        assert_eq!(cache.by_process.keys().collect::<Vec<_>>(), vec![&(pid, 2)]);
+
+        assert!(is_container_name("docker:3f1c2b9a8e7d"));
+        assert!(is_container_name("nginx.service"));
+        assert!(!is_container_name("python3"));
+    }
+
+    #[test]
+    fn test_cgroup_v2_layouts() {
+        let docker = format!("0::/system.slice/docker-{}.scope\n", ID);
+        assert_eq!(parse_container(&docker).as_deref(), Some("docker:3f1c2b9a8e7d"));
+
+        // Rootless Podman, inside the user's service manager
+        let podman = format!("0::/user.slice/user-1000.slice/user@1000.service/user.slice/libpod-{}.scope/container\n", ID);
+        assert_eq!(parse_container(&podman).as_deref(), Some("podman:3f1c2b9a8e7d"));
+        let conmon = format!("0::/user.slice/user-1000.slice/user@1000.service/user.slice/libpod-conmon-{}.scope\n", ID);
+        assert_eq!(parse_container(&conmon), None);
+
+        assert_eq!(parse_container("0::/lxc.payload.web01\n").as_deref(), Some("lxc:web01"));
+        assert_eq!(parse_container("0::/system.slice/nginx.service\n").as_deref(), Some("nginx.service"));
+
+        // Desktop apps, sessions and the init process aren't containers
+        assert_eq!(parse_container("0::/user.slice/user-1000.slice/session-2.scope\n"), None);
+        assert_eq!(
+            parse_container("0::/user.slice/user-1000.slice/user@1000.service/app.slice/app-firefox-1234.scope\n"),
+            None
+        );
+        assert_eq!(parse_container("0::/init.scope\n"), None);
+        // Inside a cgroup namespace every process sees the root
+        assert_eq!(parse_container("0::/\n"), None);
+    }
+
+    #[test]
+    fn test_cgroup_v1_layouts() {
+        // cgroupfs driver: every controller points at the container
+        let docker = format!(
+            "12:pids:/docker/{id}\n11:memory:/docker/{id}\n4:cpu,cpuacct:/docker/{id}\n1:name=systemd:/docker/{id}\n",
+            id = ID
+        );
+        assert_eq!(parse_container(&docker).as_deref(), Some("docker:3f1c2b9a8e7d"));
+
+        // systemd driver, the v1 named hierarchy read before the controllers
+        let systemd = format!(
+            "11:memory:/system.slice/docker-{id}.scope\n2:devices:/\n1:name=systemd:/system.slice/docker-{id}.scope\n",
+            id = ID
+        );
+        assert_eq!(parse_container(&systemd).as_deref(), Some("docker:3f1c2b9a8e7d"));
+
+        let podman = format!("5:cpu:/libpod_parent/{}\n1:name=systemd:/libpod_parent/libpod-{}\n", ID, ID);
+        assert_eq!(parse_container(&podman).as_deref(), Some("podman:3f1c2b9a8e7d"));
+        assert_eq!(parse_container("3:cpuset:/lxc/db\n1:name=systemd:/lxc/db\n").as_deref(), Some("lxc:db"));
+
+        // Hybrid layout: the unified line wins over the controllers
+        let hybrid = "0::/system.slice/sshd.service\n4:memory:/system.slice/sshd.service\n1:name=systemd:/system.slice/sshd.service\n";
+        assert_eq!(parse_container(hybrid).as_deref(), Some("sshd.service"));
+
+        assert_eq!(parse_container("4:memory:/user.slice\n1:name=systemd:/user.slice/user-1000.slice/session-c2.scope\n"), None);
+        assert_eq!(parse_container(""), None);
+    }
+}
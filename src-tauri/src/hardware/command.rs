@@ -0,0 +1,67 @@
+//! External commands run with a timeout
+//!
+//! GPU vendor tools, PowerShell, xprop and gdbus can hang (a stuck driver, an
+//! unresponsive X server or session bus), none of them is waited for longer
+//! than its timeout.
+
+use std::process::{Command, Output, Stdio};
+use std::time::Duration;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+/// Windows flag to hide console window when spawning processes
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// Run a command with a timeout. Returns None if timeout exceeded or command fails.
+/// On Windows, hides the console window to prevent flashing.
+pub(crate) fn run_command_with_timeout(program: &str, args: &[&str], timeout_ms: u64) -> Option<Output> {
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    // Hide console window on Windows
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+
+    let mut child = cmd.spawn().ok()?;
+
+    let timeout = Duration::from_millis(timeout_ms);
+    let start = std::time::Instant::now();
+
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                // Process finished
+                let stdout = child.stdout.take().map(|mut s| {
+                    let mut buf = Vec::new();
+                    std::io::Read::read_to_end(&mut s, &mut buf).ok();
+                    buf
+                }).unwrap_or_default();
+
+                let stderr = child.stderr.take().map(|mut s| {
+                    let mut buf = Vec::new();
+                    std::io::Read::read_to_end(&mut s, &mut buf).ok();
+                    buf
+                }).unwrap_or_default();
+
+                return Some(Output { status, stdout, stderr });
+            }
+            Ok(None) => {
+                // Still running
+                if start.elapsed() > timeout {
+                    // Timeout - kill the process
+                    let _ = child.kill();
+                    let _ = child.wait(); // Reap the zombie
+                    crate::core::log_throttle::warn(&format!("{} command timed out after {}ms", program, timeout_ms));
+                    return None;
+                }
+                // Sleep briefly before checking again
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Err(_) => return None,
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Short per-core CPU usage history for the CPU detail view
+//!
+//! One sample per detailed-loop update, the last `HISTORY_LEN` kept per core
+//! as f32, so memory stays at `HISTORY_LEN × cores × 4` bytes. The number of
+//! cores may change between samples (CPU hotplug on Linux VMs): the buffers
+//! follow it, new cores starting with an empty history.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// Samples kept per core
+pub const HISTORY_LEN: usize = 60;
+
+/// Per-core usage series, oldest sample first
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PerCoreHistory {
+    pub core_count: usize,
+    /// Current frequency of each core, None when it cannot be read
+    pub frequency_mhz: Option<Vec<u64>>,
+    /// Usage percentage series of each core
+    pub usage: Vec<Vec<f32>>,
+}
+
+/// Ring buffers of per-core usage
+#[derive(Debug, Default)]
+pub struct CoreHistory {
+    usage: Vec<VecDeque<f32>>,
+    frequency_mhz: Option<Vec<u64>>,
+}
+
+impl CoreHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a sample of every core's usage, with their frequencies if known
+    pub fn push(&mut self, per_core_usage: &[f64], frequency_mhz: Option<&[u64]>) {
+        if per_core_usage.len() != self.usage.len() {
+            log::debug!("CPU core count changed from {} to {}", self.usage.len(), per_core_usage.len());
+            self.usage.resize_with(per_core_usage.len(), || VecDeque::with_capacity(HISTORY_LEN));
+        }
+        for (series, &usage) in self.usage.iter_mut().zip(per_core_usage) {
+            if series.len() >= HISTORY_LEN {
+                series.pop_front();
+            }
+            series.push_back(usage as f32);
+        }
+        self.frequency_mhz = frequency_mhz.map(<[u64]>::to_vec);
+    }
+
+    pub fn snapshot(&self) -> PerCoreHistory {
+        PerCoreHistory {
+            core_count: self.usage.len(),
+            frequency_mhz: self.frequency_mhz.clone(),
+            usage: self.usage.iter().map(|series| series.iter().copied().collect()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut history = CoreHistory::new();
+        for i in 0..HISTORY_LEN + 15 {
+            history.push(&[i as f64, 100.0 - i as f64], Some(&[3000, 3100]));
+        }
+        let snapshot = history.snapshot();
+        assert_eq!(snapshot.core_count, 2);
+        assert!(snapshot.usage.iter().all(|series| series.len() == HISTORY_LEN));
+        assert_eq!(snapshot.usage[0][0], 15.0);
+        assert_eq!(*snapshot.usage[1].last().unwrap(), 100.0 - (HISTORY_LEN + 14) as f32);
+        assert_eq!(snapshot.frequency_mhz, Some(vec![3000, 3100]));
+    }
+
+    #[test]
+    fn test_core_hotplug() {
+        let mut history = CoreHistory::new();
+        history.push(&[10.0, 20.0], None);
+        history.push(&[11.0, 21.0, 31.0, 41.0], None);
+        let snapshot = history.snapshot();
+        assert_eq!(snapshot.core_count, 4);
+        assert_eq!(snapshot.usage, vec![vec![10.0, 11.0], vec![20.0, 21.0], vec![31.0], vec![41.0]]);
+
+        history.push(&[12.0], None);
+        let snapshot = history.snapshot();
+        assert_eq!(snapshot.usage, vec![vec![10.0, 11.0, 12.0]]);
+        assert_eq!(snapshot.frequency_mhz, None);
+
+        history.push(&[], None);
+        assert_eq!(history.snapshot(), PerCoreHistory::default());
+    }
+}
@@ -7,6 +7,7 @@
 //! realistic TDP values based on the detected processor model.
 
 use crate::core::{PowerReading, Result};
+use crate::hardware::gpu_estimate::{GpuEstimate, GPU_ESTIMATED_COMPONENT};
 use crate::hardware::PowerSource;
 use std::any::Any;
 use std::collections::HashMap;
@@ -334,6 +335,10 @@ pub struct EstimationMonitor {
     idle_power_override: Option<f64>,
     /// Override max power (if set via with_power_values)
     max_power_override: Option<f64>,
+    /// Discrete GPU the estimate would otherwise leave out
+    gpu_estimate: Option<GpuEstimate>,
+    /// Multiplier learned from a measuring source (1.0 = none)
+    correction: f64,
 }
 
 impl EstimationMonitor {
@@ -360,6 +365,8 @@ impl EstimationMonitor {
             cpu_specs,
             idle_power_override: None,
             max_power_override: None,
+            gpu_estimate: None,
+            correction: 1.0,
         }
     }
 
@@ -379,6 +386,8 @@ impl EstimationMonitor {
             cpu_specs,
             idle_power_override: Some(idle_power),
             max_power_override: Some(idle_power + tdp),
+            gpu_estimate: None,
+            correction: 1.0,
         }
     }
 
@@ -420,12 +429,18 @@ impl EstimationMonitor {
     /// - If only some cores are loaded, power consumption is lower
     /// - We use a weighted average that considers per-core load distribution
     fn calculate_estimated_power(&self) -> f64 {
+        let (cpu_power, avg_load) = self.calculate_cpu_power();
+        (cpu_power + self.gpu_estimate.map_or(0.0, |gpu| gpu.power_watts(avg_load * 100.0))) * self.correction
+    }
+
+    /// Estimated CPU and platform power, and the average CPU load (0-1)
+    fn calculate_cpu_power(&self) -> (f64, f64) {
         let mut sys = self.sys.lock().unwrap();
         sys.refresh_cpu_usage();
 
         let cpus = sys.cpus();
         if cpus.is_empty() {
-            return self.get_idle_power();
+            return (self.get_idle_power(), 0.0);
         }
 
         // Calculate per-core loads
@@ -475,12 +490,12 @@ impl EstimationMonitor {
         let power = idle_power + (load_factor * (max_power - idle_power));
 
         // Ensure we return at least idle power and at most max power
-        power.clamp(idle_power, max_power)
+        (power.clamp(idle_power, max_power), avg_load)
     }
 
     /// Get per-component power breakdown estimation
     fn get_component_breakdown(&self) -> HashMap<String, f64> {
-        let total_power = self.calculate_estimated_power();
+        let (total_power, avg_load) = self.calculate_cpu_power();
         let mut components = HashMap::new();
 
         // Estimate component breakdown (rough estimates)
@@ -493,6 +508,12 @@ impl EstimationMonitor {
 
         components.insert("cpu".to_string(), cpu_power);
         components.insert("other".to_string(), other_power);
+        if let Some(gpu) = self.gpu_estimate {
+            components.insert(GPU_ESTIMATED_COMPONENT.to_string(), gpu.power_watts(avg_load * 100.0));
+        }
+        for watts in components.values_mut() {
+            *watts *= self.correction;
+        }
 
         components
     }
@@ -510,12 +531,21 @@ impl PowerSource for EstimationMonitor {
     }
 
     fn get_reading(&self) -> Result<PowerReading> {
-        let power = self.calculate_estimated_power();
+        // One sample for the total and its parts
         let components = self.get_component_breakdown();
+        let power = components.values().sum();
 
         Ok(PowerReading::new(power, "estimated", true).with_components(components))
     }
 
+    fn set_gpu_estimate(&mut self, estimate: Option<GpuEstimate>) {
+        self.gpu_estimate = estimate;
+    }
+
+    fn set_estimation_correction(&mut self, factor: f64) {
+        self.correction = factor;
+    }
+
     fn name(&self) -> &str {
         "TDP Estimation (auto-detected)"
     }
@@ -610,4 +640,22 @@ mod tests {
         assert!(reading.is_estimated);
         assert!(reading.components.is_some());
     }
+
+    #[test]
+    fn test_gpu_estimate_in_reading() {
+        let mut monitor = EstimationMonitor::with_power_values(10.0, 100.0);
+        monitor.set_gpu_estimate(Some(GpuEstimate { idle_watts: 20.0, tdp_watts: None }));
+
+        let reading = monitor.get_reading().unwrap();
+        let components = reading.components.unwrap();
+        assert_eq!(components.get(GPU_ESTIMATED_COMPONENT), Some(&20.0));
+        assert!(reading.power_watts >= 30.0);
+        assert!(reading.is_estimated);
+        assert!(monitor.calculate_estimated_power() >= 30.0);
+
+        monitor.set_estimation_correction(0.5);
+        let corrected = monitor.get_reading().unwrap();
+        assert_eq!(corrected.components.unwrap().get(GPU_ESTIMATED_COMPONENT), Some(&10.0));
+        assert!(corrected.power_watts < reading.power_watts);
+    }
 }
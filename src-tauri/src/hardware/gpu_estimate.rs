@@ -0,0 +1,158 @@
+//! Estimated power of a discrete GPU that cannot be read
+//!
+//! Without nvidia-smi, rocm-smi or NVML the estimators leave the GPU out,
+//! which misses 10-30 W of idle board and VRAM power on desktops. The user
+//! sets an idle floor (and optionally a TDP, scaled by CPU load as a crude
+//! proxy for GPU load); a default is suggested from the model family of the
+//! GPU found by lspci (Linux) or Win32_VideoController (Windows).
+
+use crate::core::AdvancedConfig;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Component name of the estimate in power readings
+pub const GPU_ESTIMATED_COMPONENT: &str = "gpu_estimated";
+
+/// Configured GPU power estimate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpuEstimate {
+    pub idle_watts: f64,
+    pub tdp_watts: Option<f64>,
+}
+
+impl GpuEstimate {
+    /// Estimate set in the config, None without an idle floor
+    pub fn from_config(advanced: &AdvancedConfig) -> Option<Self> {
+        let idle_watts = advanced.gpu_idle_watts.filter(|w| *w > 0.0)?;
+        Some(Self { idle_watts, tdp_watts: advanced.gpu_tdp_watts.filter(|w| *w > idle_watts) })
+    }
+
+    /// Estimated GPU power at `cpu_load_percent`
+    pub fn power_watts(&self, cpu_load_percent: f64) -> f64 {
+        let load = (cpu_load_percent / 100.0).clamp(0.0, 1.0);
+        match self.tdp_watts {
+            Some(tdp) => self.idle_watts + (tdp - self.idle_watts) * load,
+            None => self.idle_watts,
+        }
+    }
+}
+
+/// Default estimate for a detected GPU, offered by the settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GpuSuggestion {
+    /// GPU name as reported by the system
+    pub name: String,
+    pub idle_watts: f64,
+    pub tdp_watts: Option<f64>,
+}
+
+/// Typical idle and load power of a GPU family, None for integrated GPUs
+pub fn suggest_for(name: &str) -> Option<GpuSuggestion> {
+    let lower = name.to_lowercase();
+    let has = |needle: &str| lower.contains(needle);
+    let suggestion = |idle_watts: f64, tdp_watts: Option<f64>| GpuSuggestion { name: name.to_string(), idle_watts, tdp_watts };
+
+    // Integrated graphics share the CPU package power, already estimated
+    if has("uhd graphics") || has("iris") || has("hd graphics") || has("basic display") || has("llvmpipe") {
+        return None;
+    }
+    if has("radeon") && !has("rx") && !has("pro w") {
+        return None;
+    }
+
+    if has("laptop") || has("mobile") || has("max-q") {
+        return Some(suggestion(5.0, Some(80.0)));
+    }
+    if has("nvidia") || has("geforce") || has("quadro") || has("rtx") || has("gtx") {
+        let high_end = ["4090", "4080", "3090", "3080", "5090", "5080"].iter().any(|m| lower.contains(m));
+        return Some(if high_end {
+            suggestion(25.0, Some(320.0))
+        } else if has("gtx") {
+            suggestion(10.0, Some(120.0))
+        } else {
+            suggestion(15.0, Some(170.0))
+        });
+    }
+    if has("radeon") || has("amd") {
+        let high_end = ["7900", "6950", "6900", "6800"].iter().any(|m| lower.contains(m));
+        return Some(if high_end { suggestion(25.0, Some(300.0)) } else { suggestion(15.0, Some(180.0)) });
+    }
+    // Arc cards idle high without ASPM
+    if has("intel") && (has("arc(") || has("[arc") || has(" arc ")) {
+        return Some(suggestion(30.0, Some(190.0)));
+    }
+    None
+}
+
+/// First discrete GPU in `lspci` output
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_lspci(output: &str) -> Option<String> {
+    output
+        .lines()
+        .filter(|line| ["VGA compatible controller", "3D controller", "Display controller"].iter().any(|class| line.contains(class)))
+        .filter_map(|line| line.split_once(": ").map(|(_, name)| name.trim().to_string()))
+        .find(|name| suggest_for(name).is_some())
+}
+
+/// Name of the first discrete GPU, detected once and cached for the process
+///
+/// `allow_powershell` is false when the PowerShell probes are disabled (Windows).
+#[cfg_attr(not(target_os = "windows"), allow(unused_variables))]
+pub fn detect_discrete_gpu(allow_powershell: bool) -> Option<String> {
+    static DETECTED: OnceLock<Option<String>> = OnceLock::new();
+    DETECTED
+        .get_or_init(|| {
+            #[cfg(target_os = "linux")]
+            let name = std::process::Command::new("lspci")
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .and_then(|output| parse_lspci(&String::from_utf8_lossy(&output.stdout)));
+            #[cfg(target_os = "windows")]
+            let name = allow_powershell
+                .then(super::windows::video_controller_names)
+                .flatten()
+                .and_then(|names| names.into_iter().find(|name| suggest_for(name).is_some()));
+            #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+            let name = None;
+            log::info!("Discrete GPU without metrics: {:?}", name);
+            name
+        })
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_scales_with_load() {
+        let estimate = GpuEstimate { idle_watts: 20.0, tdp_watts: Some(220.0) };
+        assert_eq!(estimate.power_watts(0.0), 20.0);
+        assert_eq!(estimate.power_watts(50.0), 120.0);
+        assert_eq!(estimate.power_watts(150.0), 220.0);
+        assert_eq!(GpuEstimate { idle_watts: 20.0, tdp_watts: None }.power_watts(100.0), 20.0);
+
+        let mut advanced = AdvancedConfig::default();
+        assert!(GpuEstimate::from_config(&advanced).is_none());
+        advanced.gpu_idle_watts = Some(15.0);
+        advanced.gpu_tdp_watts = Some(10.0);
+        assert_eq!(GpuEstimate::from_config(&advanced), Some(GpuEstimate { idle_watts: 15.0, tdp_watts: None }));
+    }
+
+    #[test]
+    fn test_suggestions_and_lspci() {
+        assert_eq!(suggest_for("NVIDIA GeForce RTX 4090").map(|s| s.idle_watts), Some(25.0));
+        assert_eq!(suggest_for("NVIDIA GeForce RTX 3060 Laptop GPU").map(|s| s.idle_watts), Some(5.0));
+        assert_eq!(suggest_for("AMD Radeon RX 6600").map(|s| s.tdp_watts), Some(Some(180.0)));
+        assert!(suggest_for("Intel(R) UHD Graphics 770").is_none());
+        assert!(suggest_for("AMD Radeon(TM) Graphics").is_none());
+        assert_eq!(suggest_for("Intel Corporation DG2 [Arc A770] (rev 08)").map(|s| s.idle_watts), Some(30.0));
+
+        let lspci = "00:02.0 VGA compatible controller: Intel Corporation Alder Lake-S GT1 [UHD Graphics 770] (rev 0c)\n\
+                     01:00.0 VGA compatible controller: NVIDIA Corporation GA102 [GeForce RTX 3080] (rev a1)\n\
+                     01:00.1 Audio device: NVIDIA Corporation GA102 High Definition Audio Controller (rev a1)\n";
+        assert_eq!(parse_lspci(lspci).as_deref(), Some("NVIDIA Corporation GA102 [GeForce RTX 3080] (rev a1)"));
+        assert!(parse_lspci("00:02.0 VGA compatible controller: Intel Corporation HD Graphics 620 (rev 02)\n").is_none());
+    }
+}
@@ -0,0 +1,85 @@
+//! One-time hardware inventory (GPUs, CPU, RAM, OS)
+//!
+//! Collected once at startup, off the monitoring loops: NVML gives the GPU
+//! list and driver version, nvidia-smi is the fallback when NVML is missing.
+
+use crate::core::{HardwareGpu, HardwareInventory};
+use super::nvml_gpu;
+
+/// Describe the machine (blocking, runs nvidia-smi when NVML is not available)
+pub fn collect() -> HardwareInventory {
+    let mut sys = sysinfo::System::new();
+    sys.refresh_cpu();
+    sys.refresh_memory();
+
+    let gpus = nvml_gpu::list_devices()
+        .filter(|gpus| !gpus.is_empty())
+        .or_else(nvidia_smi_devices)
+        .unwrap_or_default();
+
+    HardwareInventory {
+        gpus,
+        cpu_model: sys
+            .cpus()
+            .first()
+            .map(|c| c.brand().trim().to_string())
+            .unwrap_or_else(|| "Unknown CPU".to_string()),
+        physical_cores: sys.physical_core_count(),
+        logical_cores: sys.cpus().len(),
+        total_ram_mb: sys.total_memory() / (1024 * 1024),
+        os_version: sysinfo::System::long_os_version(),
+    }
+}
+
+/// GPUs reported by `nvidia-smi`, None when it is not installed
+fn nvidia_smi_devices() -> Option<Vec<HardwareGpu>> {
+    let mut cmd = std::process::Command::new("nvidia-smi");
+    cmd.args(["--query-gpu=name,driver_version,memory.total", "--format=csv,noheader,nounits"]);
+
+    // Hide console window on Windows
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000);
+    }
+
+    let output = cmd.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(parse_nvidia_smi(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parse `name, driver_version, memory.total` CSV lines
+fn parse_nvidia_smi(output: &str) -> Vec<HardwareGpu> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(',').map(str::trim);
+            let name = fields.next().filter(|n| !n.is_empty())?;
+            let driver_version = fields.next().filter(|v| !v.is_empty() && *v != "[N/A]");
+            let vram_total_mb = fields.next().and_then(|v| v.parse().ok());
+            Some(HardwareGpu {
+                name: name.to_string(),
+                driver_version: driver_version.map(str::to_string),
+                vram_total_mb,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nvidia_smi() {
+        let gpus = parse_nvidia_smi("NVIDIA GeForce RTX 3080, 551.86, 10240\nTesla T4, [N/A], [N/A]\n\n");
+        assert_eq!(gpus.len(), 2);
+        assert_eq!(gpus[0].name, "NVIDIA GeForce RTX 3080");
+        assert_eq!(gpus[0].driver_version.as_deref(), Some("551.86"));
+        assert_eq!(gpus[0].vram_total_mb, Some(10240));
+        assert_eq!(gpus[1].driver_version, None);
+        assert_eq!(gpus[1].vram_total_mb, None);
+    }
+}
@@ -7,25 +7,44 @@
 //! - System metrics: CPU temp/freq, fans, GPU (AMD sysfs), processes
 
 use crate::core::{CpuMetrics, DetailedMetrics, Error, FanMetrics, FanReading, GpuMetrics,
-                   MemoryMetrics, PowerReading, ProcessMetrics, Result, SystemMetrics, VoltageReading};
-use crate::hardware::PowerSource;
-use crate::hardware::nvml_gpu;
+                   MemoryMetrics, PowerReading, ProcessMetrics, ProcessTotals, Result, SystemMetrics, VoltageReading};
+use crate::hardware::{DetailedOptions, PowerSource};
+use crate::hardware::procfs::{self, ProcCpuTracker};
+use crate::hardware::{attribution, cgroup, nvml_gpu, process_filter};
 use std::any::Any;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use sysinfo::ProcessRefreshKind;
 
 // ===== Power Source Implementations (RAPL, hwmon, battery) =====
 
+/// Calls closer together than this reuse the last computed power, since a
+/// tiny time delta turns counter granularity into garbage watts
+const RAPL_MIN_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Reads the package energy counter in µJ
+type EnergyReader = Box<dyn Fn() -> Result<u64> + Send + Sync>;
+
+/// Package counter state, kept under one lock so concurrent callers can't interleave
+struct RaplState {
+    last_energy: u64,
+    last_time: Instant,
+    /// Power computed by the last sample
+    last_power: f64,
+    /// Energy measured since the last `take_energy_joules` call in µJ
+    unreported_uj: u64,
+    /// Count of overflow events (for diagnostics)
+    overflow_count: u64,
+}
+
 /// Intel RAPL power monitor
 pub struct RaplMonitor {
-    energy_path: PathBuf,
+    read_energy: EnergyReader,
     max_energy: u64,
-    last_energy: Mutex<u64>,
-    last_time: Mutex<Instant>,
+    state: Mutex<RaplState>,
     component_paths: HashMap<String, PathBuf>,
     /// Track last energy for each component (dram, core, etc.) for overflow handling
     component_last_energy: Mutex<HashMap<String, u64>>,
@@ -33,8 +52,6 @@ pub struct RaplMonitor {
     component_last_time: Mutex<HashMap<String, Instant>>,
     /// Max energy range for each component
     component_max_energy: HashMap<String, u64>,
-    /// Count of overflow events (for diagnostics)
-    overflow_count: Mutex<u64>,
 }
 
 impl RaplMonitor {
@@ -106,11 +123,13 @@ impl RaplMonitor {
             }
         }
 
-        let initial_energy: u64 = fs::read_to_string(&energy_path)
-            .map_err(|e| Error::Io(e))?
-            .trim()
-            .parse()
-            .map_err(|_| Error::PowerMonitor("Failed to parse energy value".to_string()))?;
+        let read_energy: EnergyReader = Box::new(move || {
+            fs::read_to_string(&energy_path)?
+                .trim()
+                .parse()
+                .map_err(|_| Error::PowerMonitor("Failed to parse energy value".to_string()))
+        });
+        let initial_energy = read_energy()?;
 
         log::info!("RAPL initialized: max_energy={} µJ, components={:?}",
                    max_energy, component_paths.keys().collect::<Vec<_>>());
@@ -121,58 +140,70 @@ impl RaplMonitor {
             .collect();
 
         Ok(Self {
-            energy_path,
-            max_energy,
-            last_energy: Mutex::new(initial_energy),
-            last_time: Mutex::new(Instant::now()),
             component_paths,
             component_last_energy: Mutex::new(component_last_energy),
             component_last_time: Mutex::new(component_last_time),
             component_max_energy,
-            overflow_count: Mutex::new(0),
+            ..Self::with_energy_reader(max_energy, initial_energy, Instant::now(), read_energy)
         })
     }
 
-    fn read_energy(&self) -> Result<u64> {
-        fs::read_to_string(&self.energy_path)?
-            .trim()
-            .parse()
-            .map_err(|_| Error::PowerMonitor("Failed to parse energy value".to_string()))
+    /// Create a package-only monitor reading energy from `read_energy`
+    fn with_energy_reader(max_energy: u64, initial_energy: u64, now: Instant, read_energy: EnergyReader) -> Self {
+        Self {
+            read_energy,
+            max_energy,
+            state: Mutex::new(RaplState {
+                last_energy: initial_energy,
+                last_time: now,
+                last_power: 0.0,
+                unreported_uj: 0,
+                overflow_count: 0,
+            }),
+            component_paths: HashMap::new(),
+            component_last_energy: Mutex::new(HashMap::new()),
+            component_last_time: Mutex::new(HashMap::new()),
+            component_max_energy: HashMap::new(),
+        }
     }
 
     fn get_power(&self) -> Result<f64> {
-        let current_energy = self.read_energy()?;
-        let current_time = Instant::now();
+        self.sample(Instant::now())
+    }
+
+    /// Compute package power at `now`
+    ///
+    /// The counter is read while holding the state lock, so two callers always
+    /// see increasing (energy, time) pairs.
+    fn sample(&self, now: Instant) -> Result<f64> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
 
-        let mut last_energy = self.last_energy.lock().unwrap();
-        let mut last_time = self.last_time.lock().unwrap();
+        let time_diff = now.saturating_duration_since(state.last_time);
+        if time_diff < RAPL_MIN_INTERVAL {
+            return Ok(state.last_power);
+        }
 
-        let (energy_diff, overflowed) = if current_energy >= *last_energy {
-            (current_energy - *last_energy, false)
+        let current_energy = (self.read_energy)()?;
+        let (energy_diff, overflowed) = if current_energy >= state.last_energy {
+            (current_energy - state.last_energy, false)
         } else {
             // Overflow detected: counter wrapped around
-            let diff = (self.max_energy - *last_energy) + current_energy;
+            let diff = self.max_energy.saturating_sub(state.last_energy) + current_energy;
             (diff, true)
         };
 
         if overflowed {
-            let mut count = self.overflow_count.lock().unwrap();
-            *count += 1;
+            state.overflow_count += 1;
             log::debug!(
                 "RAPL overflow #{}: counter wrapped (last={}, current={}, max={}, computed_diff={})",
-                *count, *last_energy, current_energy, self.max_energy, energy_diff
+                state.overflow_count, state.last_energy, current_energy, self.max_energy, energy_diff
             );
         }
 
-        let time_diff = current_time.duration_since(*last_time);
-        *last_energy = current_energy;
-        *last_time = current_time;
+        state.last_energy = current_energy;
+        state.last_time = now;
 
-        let power_watts = if time_diff.as_secs_f64() > 0.0 {
-            (energy_diff as f64) / time_diff.as_secs_f64() / 1_000_000.0
-        } else {
-            0.0
-        };
+        let power_watts = (energy_diff as f64) / time_diff.as_secs_f64() / 1_000_000.0;
 
         // Sanity check: reject obviously wrong values (> 10 kW for a desktop)
         if power_watts > 10_000.0 {
@@ -180,12 +211,21 @@ impl RaplMonitor {
                 "RAPL reading rejected: {} W is unrealistic (possible overflow miscalculation)",
                 power_watts
             );
+            state.last_power = 0.0;
             return Ok(0.0);
         }
 
+        state.last_power = power_watts;
+        state.unreported_uj = state.unreported_uj.saturating_add(energy_diff);
         Ok(power_watts)
     }
 
+    /// Energy measured since the previous call, in joules
+    pub fn take_energy_joules(&self) -> f64 {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::take(&mut state.unreported_uj) as f64 / 1_000_000.0
+    }
+
     /// Read power in watts from a specific RAPL component (e.g., "dram", "core", "uncore")
     /// Returns None if component not available or read fails
     fn get_component_power_watts(&self, component: &str) -> Option<f64> {
@@ -244,7 +284,7 @@ impl RaplMonitor {
 
     /// Get total overflow count (for diagnostics)
     pub fn get_overflow_count(&self) -> u64 {
-        *self.overflow_count.lock().unwrap()
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).overflow_count
     }
 }
 
@@ -363,6 +403,14 @@ impl InnerPowerSource {
             _ => None,
         }
     }
+
+    /// Energy measured since the previous call in joules (RAPL only)
+    fn take_energy_joules(&self) -> Option<f64> {
+        match self {
+            InnerPowerSource::Rapl(m) => Some(m.take_energy_joules()),
+            _ => None,
+        }
+    }
 }
 
 // ===== Hwmon Discovery =====
@@ -407,7 +455,10 @@ pub struct LinuxSystemMonitor {
     sys: Mutex<sysinfo::System>,
     hwmon: HwmonDiscovery,
     /// NVML state for NVIDIA GPU (if available)
-    nvml_state: Option<nvml_gpu::NvmlState>,
+    nvml_state: Option<nvml_gpu::NvmlHandle>,
+    /// Per-process CPU time deltas between detailed ticks
+    proc_cpu: Mutex<ProcCpuTracker>,
+    containers: Mutex<cgroup::ContainerCache>,
 }
 
 impl LinuxSystemMonitor {
@@ -421,7 +472,7 @@ impl LinuxSystemMonitor {
         sys.refresh_processes_specifics(ProcessRefreshKind::new().with_cpu().with_memory());
 
         let hwmon = HwmonDiscovery::scan();
-        let nvml_state = nvml_gpu::init_nvml();
+        let nvml_state = nvml_gpu::NvmlHandle::init();
 
         if nvml_state.is_some() {
             log::info!("NVML initialized for Linux GPU monitoring");
@@ -432,6 +483,8 @@ impl LinuxSystemMonitor {
             sys: Mutex::new(sys),
             hwmon,
             nvml_state,
+            proc_cpu: Mutex::new(ProcCpuTracker::new()),
+            containers: Mutex::new(cgroup::ContainerCache::default()),
         }
     }
 
@@ -748,7 +801,7 @@ impl LinuxSystemMonitor {
     fn get_gpu_metrics(&self) -> Option<GpuMetrics> {
         // Try NVML first (NVIDIA)
         if let Some(ref nvml) = self.nvml_state {
-            if let Some(metrics) = nvml_gpu::query_gpu_metrics(nvml) {
+            if let Some(metrics) = nvml.gpu_metrics() {
                 return Some(metrics);
             }
         }
@@ -758,7 +811,7 @@ impl LinuxSystemMonitor {
 
     fn get_gpu_power(&self) -> Option<f64> {
         if let Some(ref nvml) = self.nvml_state {
-            if let Some((power, _)) = nvml_gpu::query_gpu_power(nvml) {
+            if let Some((power, _)) = nvml.gpu_power() {
                 return Some(power);
             }
         }
@@ -768,7 +821,8 @@ impl LinuxSystemMonitor {
 
     // ----- System Metrics (full) -----
 
-    fn get_system_metrics_impl(&self, extended: bool) -> Result<SystemMetrics> {
+    fn get_system_metrics_impl(&self, options: DetailedOptions) -> Result<SystemMetrics> {
+        let extended = options.extended;
         let mut sys = self.sys.lock().unwrap();
         sys.refresh_cpu_usage();
         sys.refresh_memory();
@@ -806,6 +860,7 @@ impl LinuxSystemMonitor {
         };
 
         // GPU
+        // NVML and sysfs are cheap, read even without a GPU widget to keep GPU% and its history
         let gpu = self.get_gpu_metrics();
 
         // Fans (always try on Linux since reading sysfs is cheap)
@@ -848,32 +903,60 @@ impl LinuxSystemMonitor {
 
     // ----- Processes -----
 
-    fn get_top_processes_impl(&self, limit: usize, pinned_names: &[String]) -> Result<Vec<ProcessMetrics>> {
+    fn get_top_processes_impl(&self, limit: usize, pinned_names: &[String], ignored: &[String], by_container: bool) -> Result<Vec<ProcessMetrics>> {
         let mut sys = self.sys.lock().unwrap();
         sys.refresh_processes_specifics(ProcessRefreshKind::new().with_cpu().with_memory());
         sys.refresh_memory();
 
         let total_memory = sys.total_memory();
 
-        let process_data: Vec<_> = sys.processes().iter()
+        let mut process_data: Vec<_> = sys.processes().iter()
             .map(|(pid, process)| {
-                (pid.as_u32(), process.name().to_string(), process.cpu_usage() as f64, process.memory())
+                (pid.as_u32(), process.name().to_string(), process.cpu_usage() as f64, process.memory(), process.start_time())
             })
             .collect();
         drop(sys);
 
+        // Average CPU over the interval from /proc, sysinfo's instantaneous value for new processes
+        {
+            let mut tracker = self.proc_cpu.lock().unwrap();
+            tracker.update(
+                Instant::now(),
+                process_data.iter().filter_map(|(pid, ..)| procfs::read_proc_stat(*pid).map(|s| (*pid, s))),
+            );
+            for (pid, _, cpu_percent, ..) in process_data.iter_mut() {
+                if let Some(avg) = tracker.cpu_percent(*pid) {
+                    *cpu_percent = avg;
+                }
+            }
+        }
+
         // GPU process usage from NVML
         let gpu_usage: HashMap<u32, f64> = self.nvml_state.as_ref()
-            .map(nvml_gpu::query_gpu_processes)
+            .and_then(nvml_gpu::NvmlHandle::gpu_processes)
             .unwrap_or_default();
 
-        // Aggregate by name
+        let mut containers = self.containers.lock().unwrap();
+        containers.retain(&process_data.iter().map(|(pid, .., start_time)| (*pid, *start_time)).collect());
+
+        // Aggregate by name, or by container; pinning or ignoring a container applies to its processes
         let mut aggregated: HashMap<String, ProcessMetrics> = HashMap::new();
-        for (pid, name, cpu_percent, memory_bytes) in process_data {
-            let is_pinned = pinned_names.iter().any(|p| p.eq_ignore_ascii_case(&name));
+        for (pid, name, cpu_percent, memory_bytes, start_time) in process_data {
+            let container = containers.get(pid, start_time);
+            let is_pinned = pinned_names.iter().any(|p| {
+                p.eq_ignore_ascii_case(&name) || container.as_deref().is_some_and(|c| p.eq_ignore_ascii_case(c))
+            });
+            // Dropped before the limit so the next ones show up
+            if !is_pinned && process_filter::is_process_ignored(ignored, &name, container.as_deref()) {
+                continue;
+            }
             let gpu_percent = gpu_usage.get(&pid).copied();
 
-            let entry = aggregated.entry(name.clone()).or_insert(ProcessMetrics {
+            let (key, name) = match &container {
+                Some(container) if by_container => (container.clone(), container.clone()),
+                _ => (attribution::process_group_key(pid, &name), name),
+            };
+            let entry = aggregated.entry(key).or_insert_with(|| ProcessMetrics {
                 pid,
                 name,
                 cpu_percent: 0.0,
@@ -881,6 +964,9 @@ impl LinuxSystemMonitor {
                 memory_percent: 0.0,
                 gpu_percent: None,
                 is_pinned: false,
+                is_ignored: false,
+                is_aggregate: false,
+                container: container.clone(),
             });
             entry.cpu_percent += cpu_percent;
             entry.memory_bytes += memory_bytes;
@@ -891,9 +977,10 @@ impl LinuxSystemMonitor {
             if is_pinned {
                 entry.is_pinned = true;
             }
+            entry.merge_container(container);
         }
+        drop(containers);
 
-        // Clamp
         let processes: Vec<ProcessMetrics> = aggregated.into_values()
             .map(|mut p| {
                 p.cpu_percent = p.cpu_percent.min(100.0 * 128.0); // Linux reports per-core, can exceed 100%
@@ -919,35 +1006,51 @@ impl LinuxSystemMonitor {
         pinned.sort_by(|a, b| usage_score(b).partial_cmp(&usage_score(a)).unwrap_or(std::cmp::Ordering::Equal));
         others.sort_by(|a, b| usage_score(b).partial_cmp(&usage_score(a)).unwrap_or(std::cmp::Ordering::Equal));
 
-        let remaining_slots = limit.saturating_sub(pinned.len());
-        others.truncate(remaining_slots);
+        let len = limit.max(pinned.len());
         pinned.extend(others);
+        process_filter::truncate_with_other(&mut pinned, len);
 
         Ok(pinned)
     }
 
+    /// Keep our own CPU average current while the process list is not collected
+    fn update_self_cpu(&self) {
+        let pid = std::process::id();
+        let stat = procfs::read_proc_stat(pid).map(|s| (pid, s));
+        self.proc_cpu.lock().unwrap().update(Instant::now(), stat);
+    }
+
     pub fn get_all_processes_impl(&self) -> Result<Vec<ProcessMetrics>> {
         let mut sys = self.sys.lock().unwrap();
         sys.refresh_processes_specifics(ProcessRefreshKind::new().with_cpu().with_memory());
         sys.refresh_memory();
 
         let total_memory = sys.total_memory();
+        let tracker = self.proc_cpu.lock().unwrap();
         let process_data: Vec<_> = sys.processes().iter()
             .filter(|(_, process)| process.cpu_usage() > 0.0 || process.memory() > 0)
             .map(|(pid, process)| {
-                (pid.as_u32(), process.name().to_string(), process.cpu_usage() as f64, process.memory())
+                // Last detailed interval average when known
+                let cpu_percent = tracker.cpu_percent(pid.as_u32()).unwrap_or(process.cpu_usage() as f64);
+                (pid.as_u32(), process.name().to_string(), cpu_percent, process.memory(), process.start_time())
             })
             .collect();
+        let running = sys.processes().iter().map(|(pid, process)| (pid.as_u32(), process.start_time())).collect();
+        drop(tracker);
         drop(sys);
 
         let gpu_usage: HashMap<u32, f64> = self.nvml_state.as_ref()
-            .map(nvml_gpu::query_gpu_processes)
+            .and_then(nvml_gpu::NvmlHandle::gpu_processes)
             .unwrap_or_default();
 
+        let mut containers = self.containers.lock().unwrap();
+        containers.retain(&running);
+
         let mut aggregated: HashMap<String, ProcessMetrics> = HashMap::new();
-        for (pid, name, cpu_percent, memory_bytes) in process_data {
+        for (pid, name, cpu_percent, memory_bytes, start_time) in process_data {
             let gpu_percent = gpu_usage.get(&pid).copied();
-            let entry = aggregated.entry(name.clone()).or_insert(ProcessMetrics {
+            let container = containers.get(pid, start_time);
+            let entry = aggregated.entry(attribution::process_group_key(pid, &name)).or_insert_with(|| ProcessMetrics {
                 pid,
                 name,
                 cpu_percent: 0.0,
@@ -955,6 +1058,9 @@ impl LinuxSystemMonitor {
                 memory_percent: 0.0,
                 gpu_percent: None,
                 is_pinned: false,
+                is_ignored: false,
+                is_aggregate: false,
+                container: container.clone(),
             });
             entry.cpu_percent += cpu_percent;
             entry.memory_bytes += memory_bytes;
@@ -962,7 +1068,9 @@ impl LinuxSystemMonitor {
             if let Some(gpu) = gpu_percent {
                 entry.gpu_percent = Some(entry.gpu_percent.unwrap_or(0.0) + gpu);
             }
+            entry.merge_container(container);
         }
+        drop(containers);
 
         let mut processes: Vec<ProcessMetrics> = aggregated.into_values().collect();
         processes.sort_by(|a, b| b.cpu_percent.partial_cmp(&a.cpu_percent).unwrap_or(std::cmp::Ordering::Equal));
@@ -988,21 +1096,37 @@ impl PowerSource for LinuxSystemMonitor {
 
         let gpu_power = self.get_gpu_power();
         let gpu_usage = self.nvml_state.as_ref()
-            .and_then(|nvml| nvml_gpu::query_gpu_metrics(nvml))
+            .and_then(nvml_gpu::NvmlHandle::gpu_metrics)
             .and_then(|m| m.usage_percent);
 
         Ok((power, cpu_usage, gpu_usage, gpu_power))
     }
 
-    fn collect_detailed_metrics(&self, limit: usize, pinned: &[String], extended: bool) -> Result<DetailedMetrics> {
-        let system_metrics = self.get_system_metrics_impl(extended).ok();
-        let top_processes = self.get_top_processes_impl(limit, pinned).unwrap_or_default();
+    fn collect_detailed_metrics(&self, limit: usize, pinned: &[String], ignored: &[String], options: DetailedOptions) -> Result<DetailedMetrics> {
+        let system_metrics = self.get_system_metrics_impl(options).ok();
+        let top_processes = if options.processes {
+            self.get_top_processes_impl(limit, pinned, ignored, options.group_by_container).unwrap_or_default()
+        } else {
+            self.update_self_cpu();
+            Vec::new()
+        };
+        // Interval average from get_top_processes_impl, instantaneous value until the second tick
+        let tracked = self.proc_cpu.lock().unwrap().cpu_percent(std::process::id());
+        let app_self_cpu_percent = tracked.or_else(|| attribution::self_cpu_percent(&self.sys.lock().unwrap()));
 
+        let now = chrono::Utc::now();
         Ok(DetailedMetrics {
             system_metrics,
+            process_totals: ProcessTotals::of(&top_processes),
             top_processes,
-            timestamp: chrono::Utc::now().timestamp(),
-            extended_collected: extended,
+            timestamp: now.timestamp(),
+            timestamp_ms: now.timestamp_millis(),
+            extended_collected: options.extended,
+            measured_energy_joules: self.inner_power.take_energy_joules(),
+            app_self_cpu_percent,
+            app_self_power_watts: None,
+            // NVML and sysfs spawn nothing, GPU polling is never paused here
+            gpu_stale: false,
         })
     }
 
@@ -1019,7 +1143,79 @@ impl PowerSource for LinuxSystemMonitor {
         false
     }
 
+    fn has_battery(&self) -> bool {
+        BatteryMonitor::new().is_ok()
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    /// RAPL monitor whose counter is driven by the test
+    fn fake_rapl(max_energy: u64, initial: u64) -> (RaplMonitor, Arc<AtomicU64>, Instant) {
+        let counter = Arc::new(AtomicU64::new(initial));
+        let reader = counter.clone();
+        let start = Instant::now();
+        let monitor = RaplMonitor::with_energy_reader(
+            max_energy,
+            initial,
+            start,
+            Box::new(move || Ok(reader.load(Ordering::SeqCst))),
+        );
+        (monitor, counter, start)
+    }
+
+    #[test]
+    fn test_rapl_power_and_energy_delta() {
+        let (rapl, counter, start) = fake_rapl(1_000_000_000, 0);
+
+        // 50 J over 1 s
+        counter.store(50_000_000, Ordering::SeqCst);
+        let watts = rapl.sample(start + Duration::from_secs(1)).unwrap();
+        assert!((watts - 50.0).abs() < 1e-9);
+        assert!((rapl.take_energy_joules() - 50.0).abs() < 1e-9);
+
+        counter.store(80_000_000, Ordering::SeqCst);
+        rapl.sample(start + Duration::from_secs(2)).unwrap();
+        assert!((rapl.take_energy_joules() - 30.0).abs() < 1e-9);
+        assert_eq!(rapl.take_energy_joules(), 0.0);
+    }
+
+    #[test]
+    fn test_rapl_wraparound() {
+        let max = 262_143_328_850;
+        let (rapl, counter, start) = fake_rapl(max, max - 10_000_000);
+
+        // Counter wraps: 10 J before max + 20 J after = 30 J over 1 s
+        counter.store(20_000_000, Ordering::SeqCst);
+        let watts = rapl.sample(start + Duration::from_secs(1)).unwrap();
+        assert!((watts - 30.0).abs() < 1e-9);
+        assert_eq!(rapl.get_overflow_count(), 1);
+    }
+
+    #[test]
+    fn test_rapl_back_to_back_calls() {
+        let (rapl, counter, start) = fake_rapl(1_000_000_000, 0);
+
+        counter.store(40_000_000, Ordering::SeqCst);
+        let first = rapl.sample(start + Duration::from_secs(1)).unwrap();
+
+        // A second caller 1 ms later gets the same value instead of a tiny-delta reading
+        counter.store(40_001_000, Ordering::SeqCst);
+        let second = rapl.sample(start + Duration::from_millis(1001)).unwrap();
+        assert_eq!(first, second);
+        assert!((rapl.take_energy_joules() - 40.0).abs() < 1e-9);
+
+        // The skipped energy is accounted for by the next real sample
+        counter.store(80_000_000, Ordering::SeqCst);
+        let third = rapl.sample(start + Duration::from_secs(2)).unwrap();
+        assert!((third - 40.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,299 @@
+//! macOS power monitoring via `powermetrics`
+//!
+//! A `powermetrics` child process streams one plist sample per second; a
+//! reader thread keeps the latest one so the fast path never waits on it.
+//! `powermetrics` needs root, so without privileges `new()` fails and the
+//! monitor falls back to the TDP estimator (which knows the Apple M-series).
+//! SMC keys would need IOKit bindings and are not read.
+//!
+//! The reported power covers the SoC rails (CPU, GPU, ANE) on Apple Silicon
+//! and the package on Intel Macs, not the display or the rest of the board.
+
+use crate::core::{CpuMetrics, DetailedMetrics, Error, GpuMetrics, MemoryMetrics, PowerReading, ProcessMetrics, ProcessTotals, Result, SystemMetrics};
+use crate::hardware::powermetrics::{self, PowermetricsSample};
+use crate::hardware::{attribution, process_filter, DetailedOptions, PowerSource};
+use std::any::Any;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use sysinfo::{ProcessRefreshKind, System};
+
+/// Interval between two powermetrics samples
+const SAMPLE_INTERVAL_MS: u64 = 1000;
+
+/// How long `new()` waits for the first sample
+const FIRST_SAMPLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Samples older than this are not reported (powermetrics died or stalled)
+const MAX_SAMPLE_AGE: Duration = Duration::from_secs(10);
+
+/// Latest sample and when it was received
+type SharedSample = Arc<Mutex<Option<(Instant, PowermetricsSample)>>>;
+
+/// Power source backed by a `powermetrics` child process
+pub struct MacMonitor {
+    sys: Mutex<System>,
+    latest: SharedSample,
+    child: Mutex<Child>,
+}
+
+impl MacMonitor {
+    /// Start powermetrics and wait for its first sample
+    pub fn new() -> Result<Self> {
+        let mut child = Command::new("powermetrics")
+            .args(["--samplers", "cpu_power,gpu_power", "-f", "plist", "-i"])
+            .arg(SAMPLE_INTERVAL_MS.to_string())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| Error::HardwareNotSupported(format!("powermetrics unavailable: {}", e)))?;
+
+        let stdout = child.stdout.take().ok_or_else(|| Error::PowerMonitor("powermetrics has no output".to_string()))?;
+        let latest: SharedSample = Arc::new(Mutex::new(None));
+        let (first_tx, first_rx) = mpsc::channel();
+
+        let shared = Arc::clone(&latest);
+        std::thread::spawn(move || {
+            // Samples are separated by a NUL byte in plist mode
+            let mut reader = BufReader::new(stdout);
+            let mut buffer = Vec::new();
+            let mut first_tx = Some(first_tx);
+            while matches!(reader.read_until(0, &mut buffer), Ok(n) if n > 0) {
+                if let Some(sample) = powermetrics::parse_sample(&String::from_utf8_lossy(&buffer)) {
+                    *shared.lock().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), sample));
+                    if let Some(tx) = first_tx.take() {
+                        let _ = tx.send(());
+                    }
+                }
+                buffer.clear();
+            }
+            log::debug!("powermetrics output closed");
+        });
+
+        if first_rx.recv_timeout(FIRST_SAMPLE_TIMEOUT).is_err() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(Error::PermissionDenied(
+                "powermetrics produced no sample (it must run as root)".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            sys: Mutex::new(System::new_all()),
+            latest,
+            child: Mutex::new(child),
+        })
+    }
+
+    /// Latest sample, None when it is too old
+    fn sample(&self) -> Option<PowermetricsSample> {
+        let latest = self.latest.lock().unwrap_or_else(|e| e.into_inner());
+        (*latest).filter(|(at, _)| at.elapsed() <= MAX_SAMPLE_AGE).map(|(_, sample)| sample)
+    }
+
+    fn sample_watts(&self) -> Result<(PowermetricsSample, f64)> {
+        let sample = self.sample().ok_or_else(|| Error::PowerMonitor("No recent powermetrics sample".to_string()))?;
+        let watts = sample.total_watts().ok_or_else(|| Error::PowerMonitor("powermetrics sample without power".to_string()))?;
+        Ok((sample, watts))
+    }
+
+    fn cpu_usage(sys: &System) -> f64 {
+        sys.cpus().iter().map(|c| c.cpu_usage() as f64).sum::<f64>() / sys.cpus().len().max(1) as f64
+    }
+
+    fn get_system_metrics_impl(&self, options: DetailedOptions) -> SystemMetrics {
+        let mut sys = self.sys.lock().unwrap();
+        sys.refresh_cpu_usage();
+        sys.refresh_memory();
+
+        let cpu = CpuMetrics {
+            name: sys.cpus().first().map(|c| c.brand().to_string()).unwrap_or_else(|| "Unknown CPU".to_string()),
+            usage_percent: Self::cpu_usage(&sys),
+            per_core_usage: sys.cpus().iter().map(|c| c.cpu_usage() as f64).collect(),
+            frequency_mhz: sys.cpus().first().map(|c| c.frequency()),
+            temperature_celsius: None,
+            core_count: sys.physical_core_count().unwrap_or(0),
+            thread_count: sys.cpus().len(),
+            per_core_frequency_mhz: None,
+            per_core_temperature: None,
+        };
+
+        let (used_swap, total_swap) = (sys.used_swap(), sys.total_swap());
+        let memory = MemoryMetrics {
+            used_bytes: sys.used_memory(),
+            total_bytes: sys.total_memory(),
+            usage_percent: (sys.used_memory() as f64 / sys.total_memory().max(1) as f64) * 100.0,
+            swap_used_bytes: (total_swap > 0).then_some(used_swap),
+            swap_total_bytes: (total_swap > 0).then_some(total_swap),
+            swap_usage_percent: (total_swap > 0).then(|| used_swap as f64 / total_swap as f64 * 100.0),
+            memory_speed_mhz: None,
+            memory_type: None,
+            power_watts: None,
+        };
+        drop(sys);
+
+        // Integrated GPU, only reported when powermetrics has GPU figures
+        let gpu = self.sample().filter(|s| options.gpu && (s.gpu_watts.is_some() || s.gpu_active_percent.is_some())).map(|s| GpuMetrics {
+            name: "Integrated GPU".to_string(),
+            usage_percent: s.gpu_active_percent,
+            power_watts: s.gpu_watts,
+            temperature_celsius: None,
+            vram_used_mb: None,
+            vram_total_mb: None,
+            clock_mhz: None,
+            source: "powermetrics".to_string(),
+            memory_clock_mhz: None,
+            fan_speed_percent: None,
+        });
+
+        SystemMetrics {
+            cpu,
+            gpu,
+            memory,
+            timestamp: chrono::Utc::now().timestamp(),
+            fans: None,
+            voltages: None,
+        }
+    }
+
+    /// Top processes aggregated by name, pinned first, ignored ones left out
+    fn get_top_processes_impl(&self, limit: usize, pinned_names: &[String], ignored: &[String]) -> Vec<ProcessMetrics> {
+        let mut processes: Vec<ProcessMetrics> = self
+            .aggregate_processes()
+            .into_iter()
+            .map(|mut p| {
+                p.is_pinned = pinned_names.iter().any(|n| n.eq_ignore_ascii_case(&p.name));
+                p
+            })
+            .filter(|p| p.is_pinned || !process_filter::is_ignored(ignored, &p.name))
+            .collect();
+
+        let usage_score = |p: &ProcessMetrics| p.cpu_percent * 0.4 + p.gpu_percent.unwrap_or(0.0) * 0.4 + p.memory_percent * 0.2;
+        processes.sort_by(|a, b| {
+            b.is_pinned
+                .cmp(&a.is_pinned)
+                .then(usage_score(b).partial_cmp(&usage_score(a)).unwrap_or(std::cmp::Ordering::Equal))
+        });
+
+        let pinned_count = processes.iter().filter(|p| p.is_pinned).count();
+        process_filter::truncate_with_other(&mut processes, pinned_count.max(limit));
+        processes
+    }
+
+    /// Get all processes (for discovery mode)
+    pub fn get_all_processes_impl(&self) -> Vec<ProcessMetrics> {
+        let mut processes = self.aggregate_processes();
+        processes.sort_by(|a, b| b.cpu_percent.partial_cmp(&a.cpu_percent).unwrap_or(std::cmp::Ordering::Equal));
+        processes
+    }
+
+    fn aggregate_processes(&self) -> Vec<ProcessMetrics> {
+        let mut sys = self.sys.lock().unwrap();
+        sys.refresh_processes_specifics(ProcessRefreshKind::new().with_cpu().with_memory());
+        sys.refresh_memory();
+        let total_memory = sys.total_memory().max(1);
+
+        let mut aggregated: HashMap<String, ProcessMetrics> = HashMap::new();
+        for (pid, process) in sys.processes() {
+            let name = process.name().to_string();
+            let entry = aggregated.entry(attribution::process_group_key(pid.as_u32(), &name)).or_insert(ProcessMetrics {
+                pid: pid.as_u32(),
+                name,
+                cpu_percent: 0.0,
+                memory_bytes: 0,
+                memory_percent: 0.0,
+                gpu_percent: None,
+                is_pinned: false,
+                is_ignored: false,
+                is_aggregate: false,
+                container: None,
+            });
+            entry.cpu_percent += process.cpu_usage() as f64;
+            entry.memory_bytes += process.memory();
+            entry.memory_percent += process.memory() as f64 / total_memory as f64 * 100.0;
+        }
+
+        aggregated
+            .into_values()
+            .map(|mut p| {
+                p.memory_percent = p.memory_percent.min(100.0);
+                p
+            })
+            .collect()
+    }
+}
+
+impl Drop for MacMonitor {
+    fn drop(&mut self) {
+        let child = self.child.get_mut().unwrap_or_else(|e| e.into_inner());
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+impl PowerSource for MacMonitor {
+    fn get_power_watts(&self) -> Result<f64> {
+        self.sample_watts().map(|(_, watts)| watts)
+    }
+
+    fn get_power_watts_fast(&self) -> Result<(f64, f64, Option<f64>, Option<f64>)> {
+        let (sample, watts) = self.sample_watts()?;
+        let mut sys = self.sys.lock().unwrap();
+        sys.refresh_cpu_usage();
+        let cpu_usage = Self::cpu_usage(&sys);
+        Ok((watts, cpu_usage, sample.gpu_active_percent, sample.gpu_watts))
+    }
+
+    fn collect_detailed_metrics(&self, limit: usize, pinned: &[String], ignored: &[String], options: DetailedOptions) -> Result<DetailedMetrics> {
+        let system_metrics = Some(self.get_system_metrics_impl(options));
+        let top_processes = if options.processes {
+            self.get_top_processes_impl(limit, pinned, ignored)
+        } else {
+            let pid = sysinfo::Pid::from_u32(std::process::id());
+            self.sys.lock().unwrap().refresh_process_specifics(pid, ProcessRefreshKind::new().with_cpu());
+            Vec::new()
+        };
+        let app_self_cpu_percent = attribution::self_cpu_percent(&self.sys.lock().unwrap());
+
+        let now = chrono::Utc::now();
+        Ok(DetailedMetrics {
+            system_metrics,
+            process_totals: ProcessTotals::of(&top_processes),
+            top_processes,
+            timestamp: now.timestamp(),
+            timestamp_ms: now.timestamp_millis(),
+            extended_collected: options.extended,
+            measured_energy_joules: None,
+            app_self_cpu_percent,
+            app_self_power_watts: None,
+            gpu_stale: false,
+        })
+    }
+
+    fn get_reading(&self) -> Result<PowerReading> {
+        let (sample, watts) = self.sample_watts()?;
+        let mut components = HashMap::new();
+        if let Some(cpu) = sample.cpu_watts.or(sample.package_watts) {
+            components.insert("cpu".to_string(), cpu);
+        }
+        if let Some(gpu) = sample.gpu_watts {
+            components.insert("gpu".to_string(), gpu);
+        }
+        Ok(PowerReading::new(watts, "powermetrics", false).with_components(components))
+    }
+
+    fn name(&self) -> &str {
+        "macOS powermetrics"
+    }
+
+    fn is_estimated(&self) -> bool {
+        false
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
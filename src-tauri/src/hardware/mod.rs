@@ -3,21 +3,129 @@
 //! Provides abstractions for reading power consumption from various sources:
 //! - Linux: RAPL (Intel Running Average Power Limit) via sysfs
 //! - Windows: WMI queries and hardware monitoring
+//! - macOS: `powermetrics` (requires root)
 //! - Fallback: TDP-based estimation
 
 #[cfg(target_os = "linux")]
 mod linux;
+#[cfg(target_os = "linux")]
+mod procfs;
 #[cfg(target_os = "windows")]
 mod windows;
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+mod powermetrics;
 mod estimator;
+pub mod active_window;
+pub mod attribution;
+pub mod benchmark;
+pub mod capabilities;
+pub mod cgroup;
+pub(crate) mod command;
+pub mod core_history;
+pub mod gpu_estimate;
+pub mod inventory;
+pub mod process_filter;
+pub mod process_history;
 pub mod baseline;
+pub mod sanitizer;
+pub mod simulated;
 pub(crate) mod nvml_gpu;
 
 pub use baseline::BaselineDetector;
+pub use core_history::CoreHistory;
+pub use gpu_estimate::GpuEstimate;
+pub use process_history::ProcessHistory;
+pub use nvml_gpu::reinit_attempts as nvml_reinit_attempts;
+pub use sanitizer::PowerSanitizer;
 
-use crate::core::{DetailedMetrics, Error, PowerReading, ProcessMetrics, Result, SystemMetrics};
+use crate::core::accuracy;
+use crate::core::{Config, DetailedMetrics, ProcessGrouping, Error, PowerReading, ProcessMetrics, Result, SystemMetrics};
 use std::any::Any;
 
+/// What `collect_detailed_metrics` gathers besides CPU and memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DetailedOptions {
+    /// Per-core frequencies, fan speeds, GPU memory clock
+    pub extended: bool,
+    /// Enumerate processes for the top-process list
+    pub processes: bool,
+    /// Run the GPU command line tools (nvidia-smi), otherwise GPU metrics come from NVML or the last values
+    pub gpu: bool,
+    /// Never run the GPU command line tools, keep the last values they gave (fullscreen app focused)
+    pub pause_gpu_cli: bool,
+    /// One process row per container instead of per name (Linux)
+    pub group_by_container: bool,
+}
+
+impl Default for DetailedOptions {
+    fn default() -> Self {
+        Self {
+            extended: false,
+            processes: true,
+            gpu: true,
+            pause_gpu_cli: false,
+            group_by_container: false,
+        }
+    }
+}
+
+impl DetailedOptions {
+    /// Collectors needed by the visible dashboard widgets and, when it is open, the widget window
+    pub fn for_layout(config: &Config, widget_open: bool, extended: bool) -> Self {
+        let dashboard_shows = |id: &str| config.dashboard.widgets.iter().any(|w| w.visible && w.id == id);
+        let widget_shows = |item: &str| widget_open && config.widget.display_items.iter().any(|i| i == item);
+
+        Self {
+            extended,
+            processes: dashboard_shows("processes") || dashboard_shows("pinned"),
+            // The widget's temperature item falls back to the GPU sensor,
+            // and the VRAM alert needs GPU memory even with nothing on screen
+            gpu: dashboard_shows("gpu") || widget_shows("gpu") || widget_shows("temp") || config.advanced.vram_alert_percent > 0,
+            pause_gpu_cli: false,
+            group_by_container: config.advanced.process_grouping == ProcessGrouping::Container,
+        }
+    }
+}
+
+/// Default timeout for GPU and PowerShell commands (nvidia-smi, rocm-smi, WMI probes)
+pub const DEFAULT_GPU_COMMAND_TIMEOUT_MS: u64 = 1500;
+
+/// Subprocess settings of the Windows monitor (no effect on Linux)
+///
+/// NVML is a library call and ignores these: with `disable_gpu_cli`, an NVIDIA
+/// GPU is still read through NVML, only the nvidia-smi fallback is gone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonitorOptions {
+    /// Timeout of nvidia-smi, rocm-smi, amd-smi and PowerShell probes
+    pub gpu_command_timeout_ms: u64,
+    /// Never run PowerShell (CPU temperature, RAM details, fans, battery detection)
+    pub disable_powershell_probes: bool,
+    /// Never run nvidia-smi, rocm-smi or amd-smi
+    pub disable_gpu_cli: bool,
+}
+
+impl Default for MonitorOptions {
+    fn default() -> Self {
+        Self {
+            gpu_command_timeout_ms: DEFAULT_GPU_COMMAND_TIMEOUT_MS,
+            disable_powershell_probes: false,
+            disable_gpu_cli: false,
+        }
+    }
+}
+
+impl MonitorOptions {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            gpu_command_timeout_ms: config.advanced.gpu_command_timeout_ms,
+            disable_powershell_probes: config.advanced.disable_powershell_probes,
+            disable_gpu_cli: config.advanced.disable_gpu_cli,
+        }
+    }
+}
+
 /// Power monitor that abstracts over different hardware sources
 pub struct PowerMonitor {
     source: Box<dyn PowerSource + Send + Sync>,
@@ -25,7 +133,8 @@ pub struct PowerMonitor {
 
 impl PowerMonitor {
     /// Create a new power monitor, automatically detecting the best source
-    pub fn new() -> Result<Self> {
+    #[cfg_attr(not(target_os = "windows"), allow(unused_variables))]
+    pub fn new(options: &MonitorOptions) -> Result<Self> {
         #[cfg(target_os = "linux")]
         {
             // Try RAPL first (most accurate) — wrapped in LinuxSystemMonitor for full metrics
@@ -56,7 +165,7 @@ impl PowerMonitor {
         #[cfg(target_os = "windows")]
         {
             // Try WMI
-            if let Ok(wmi) = windows::WmiMonitor::new() {
+            if let Ok(wmi) = windows::WmiMonitor::new(options) {
                 log::info!("Using WMI for power monitoring");
                 return Ok(Self {
                     source: Box::new(wmi),
@@ -64,6 +173,19 @@ impl PowerMonitor {
             }
         }
 
+        #[cfg(target_os = "macos")]
+        {
+            match macos::MacMonitor::new() {
+                Ok(monitor) => {
+                    log::info!("Using powermetrics for power monitoring");
+                    return Ok(Self {
+                        source: Box::new(monitor),
+                    });
+                }
+                Err(e) => log::warn!("powermetrics unavailable: {}", e),
+            }
+        }
+
         // Fallback to estimation
         log::warn!("No direct power source available, using estimation");
         Err(Error::HardwareNotSupported(
@@ -78,6 +200,13 @@ impl PowerMonitor {
         }
     }
 
+    /// Create a power monitor reading a constant `watts`, without any hardware access
+    pub fn simulated(watts: f64) -> Self {
+        Self {
+            source: Box::new(simulated::SimulatedSource::new(watts)),
+        }
+    }
+
     /// Get current power consumption in watts
     pub fn get_power_watts(&self) -> Result<f64> {
         self.source.get_power_watts()
@@ -98,16 +227,32 @@ impl PowerMonitor {
         self.source.is_estimated()
     }
 
+    /// Whether the machine has a battery the source can see
+    pub fn has_battery(&self) -> bool {
+        self.source.has_battery()
+    }
+
     /// Get power reading using fast path (CPU-only + cached GPU, no blocking commands)
     /// Returns (power_watts, cpu_usage_percent, cached_gpu_usage_percent, cached_gpu_power_watts)
     pub fn get_power_watts_fast(&self) -> Result<(f64, f64, Option<f64>, Option<f64>)> {
         self.source.get_power_watts_fast()
     }
 
+    /// Add `estimate` for a GPU the source cannot read (estimating sources only)
+    pub fn set_gpu_estimate(&mut self, estimate: Option<GpuEstimate>) {
+        self.source.set_gpu_estimate(estimate);
+    }
+
+    /// Multiply estimated readings by `factor`, kept within the correction bounds (the TDP estimator only)
+    pub fn set_estimation_correction(&mut self, factor: f64) {
+        self.source.set_estimation_correction(accuracy::clamp_correction(factor));
+    }
+
     /// Collect detailed metrics (processes, temps, VRAM) - may block for GPU commands
-    /// When `extended` is true, also collects per-core frequencies, fan speeds, GPU mem clock
-    pub fn collect_detailed_metrics(&self, limit: usize, pinned: &[String], extended: bool) -> Result<DetailedMetrics> {
-        self.source.collect_detailed_metrics(limit, pinned, extended)
+    ///
+    /// Processes matching `ignored` are left out of the top list (pinned ones are kept).
+    pub fn collect_detailed_metrics(&self, limit: usize, pinned: &[String], ignored: &[String], options: DetailedOptions) -> Result<DetailedMetrics> {
+        self.source.collect_detailed_metrics(limit, pinned, ignored, options)
     }
 
     /// Get system metrics (CPU, GPU, RAM) - uses stored source for cache sharing
@@ -126,7 +271,18 @@ impl PowerMonitor {
     pub fn get_system_metrics(&self) -> Result<SystemMetrics> {
         if let Some(monitor) = self.source.as_any().downcast_ref::<linux::LinuxSystemMonitor>() {
             // Use collect_detailed_metrics which populates system_metrics
-            let detailed = monitor.collect_detailed_metrics(0, &[], false)?;
+            let detailed = monitor.collect_detailed_metrics(0, &[], &[], DetailedOptions::default())?;
+            detailed.system_metrics.ok_or_else(|| Error::HardwareNotSupported("System metrics unavailable".to_string()))
+        } else {
+            Err(Error::HardwareNotSupported("System metrics not available for this source".to_string()))
+        }
+    }
+
+    /// Get system metrics (macOS)
+    #[cfg(target_os = "macos")]
+    pub fn get_system_metrics(&self) -> Result<SystemMetrics> {
+        if let Some(monitor) = self.source.as_any().downcast_ref::<macos::MacMonitor>() {
+            let detailed = monitor.collect_detailed_metrics(0, &[], &[], DetailedOptions::default())?;
             detailed.system_metrics.ok_or_else(|| Error::HardwareNotSupported("System metrics unavailable".to_string()))
         } else {
             Err(Error::HardwareNotSupported("System metrics not available for this source".to_string()))
@@ -143,27 +299,27 @@ impl PowerMonitor {
         }
     }
 
-    /// Get top processes (Linux)
-    #[cfg(target_os = "linux")]
+    /// Get top processes (Linux, macOS)
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
     pub fn get_top_processes(&self, limit: usize) -> Result<Vec<ProcessMetrics>> {
-        self.get_top_processes_with_pinned(limit, &[])
+        self.get_top_processes_with_pinned(limit, &[], &[])
     }
 
     /// Get top processes with pinned processes prioritized - uses stored source
     #[cfg(target_os = "windows")]
-    pub fn get_top_processes_with_pinned(&self, limit: usize, pinned: &[String]) -> Result<Vec<ProcessMetrics>> {
+    pub fn get_top_processes_with_pinned(&self, limit: usize, pinned: &[String], ignored: &[String]) -> Result<Vec<ProcessMetrics>> {
         if let Some(wmi) = self.source.as_any().downcast_ref::<windows::WmiMonitor>() {
-            wmi.get_top_processes_with_pinned(limit, pinned)
+            wmi.get_top_processes_with_pinned(limit, pinned, ignored)
         } else {
             Err(Error::HardwareNotSupported("Process metrics not available for this source".to_string()))
         }
     }
 
-    /// Get top processes with pinned (Linux)
-    #[cfg(target_os = "linux")]
-    pub fn get_top_processes_with_pinned(&self, limit: usize, pinned: &[String]) -> Result<Vec<ProcessMetrics>> {
+    /// Get top processes with pinned (Linux, macOS)
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn get_top_processes_with_pinned(&self, limit: usize, pinned: &[String], ignored: &[String]) -> Result<Vec<ProcessMetrics>> {
         // Use the collect_detailed_metrics pathway which handles processes
-        let detailed = self.source.collect_detailed_metrics(limit, pinned, false)?;
+        let detailed = self.source.collect_detailed_metrics(limit, pinned, ignored, DetailedOptions::default())?;
         Ok(detailed.top_processes)
     }
 
@@ -186,6 +342,16 @@ impl PowerMonitor {
             Err(Error::HardwareNotSupported("Process metrics not available for this source".to_string()))
         }
     }
+
+    /// Get all processes (macOS)
+    #[cfg(target_os = "macos")]
+    pub fn get_all_processes(&self) -> Result<Vec<ProcessMetrics>> {
+        if let Some(monitor) = self.source.as_any().downcast_ref::<macos::MacMonitor>() {
+            Ok(monitor.get_all_processes_impl())
+        } else {
+            Err(Error::HardwareNotSupported("Process metrics not available for this source".to_string()))
+        }
+    }
 }
 
 /// Trait for power monitoring sources
@@ -202,20 +368,60 @@ pub trait PowerSource: Send + Sync {
     }
 
     /// Collect detailed metrics (processes, temps, VRAM) - may block for GPU commands
-    /// When `extended` is true, also collects per-core frequencies, fan speeds, GPU mem clock
-    fn collect_detailed_metrics(&self, _limit: usize, _pinned: &[String], _extended: bool) -> Result<DetailedMetrics> {
+    /// `options` selects the optional collectors (extended metrics, processes, GPU)
+    fn collect_detailed_metrics(&self, _limit: usize, _pinned: &[String], _ignored: &[String], _options: DetailedOptions) -> Result<DetailedMetrics> {
         Err(Error::HardwareNotSupported("Detailed metrics not implemented".to_string()))
     }
 
     /// Get a full reading with metadata
     fn get_reading(&self) -> Result<PowerReading>;
 
+    /// Estimate of a GPU without metrics, ignored by sources that measure
+    fn set_gpu_estimate(&mut self, _estimate: Option<GpuEstimate>) {}
+
+    /// Correction factor of estimated readings, ignored by sources that measure
+    fn set_estimation_correction(&mut self, _factor: f64) {}
+
     /// Name of this power source
     fn name(&self) -> &str;
 
     /// Whether readings are estimated
     fn is_estimated(&self) -> bool;
 
+    /// Whether a battery was found
+    fn has_battery(&self) -> bool {
+        false
+    }
+
     /// Downcast support for type-specific operations
     fn as_any(&self) -> &dyn Any;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options_follow_visible_widgets() {
+        let mut config = Config::default();
+        let options = DetailedOptions::for_layout(&config, false, true);
+        assert!(options.processes && options.gpu && options.extended);
+
+        for widget in config.dashboard.widgets.iter_mut() {
+            if widget.id == "processes" || widget.id == "gpu" {
+                widget.visible = false;
+            }
+        }
+        let options = DetailedOptions::for_layout(&config, false, false);
+        assert!(!options.processes && !options.gpu);
+
+        // The widget window still needs the GPU while it shows it
+        config.widget.display_items = vec!["power".to_string(), "gpu".to_string()];
+        assert!(DetailedOptions::for_layout(&config, true, false).gpu);
+        assert!(!DetailedOptions::for_layout(&config, false, false).gpu);
+
+        // The pinned widget samples the process list on its own
+        config.dashboard.widgets.iter_mut().filter(|w| w.id == "pinned").for_each(|w| w.visible = true);
+        assert!(DetailedOptions::for_layout(&config, false, false).processes);
+    }
+}
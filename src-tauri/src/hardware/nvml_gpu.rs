@@ -5,12 +5,39 @@
 //! Latency improvement: ~500-1500ms (CLI) → ~1-5ms (NVML).
 //!
 //! Used by both Windows and Linux backends.
+//!
+//! A driver reset (TDR on Windows, driver update on Linux) invalidates the
+//! NVML handle. `NvmlHandle` drops it after a few consecutive failures and
+//! reinitializes NVML with exponential backoff; queries return None in the
+//! meantime so callers use their fallback source. A sensor the GPU doesn't
+//! have (power on many laptop and older GPUs) is a missing value, not a failure.
 
-use crate::core::GpuMetrics;
+use crate::core::{GpuMetrics, HardwareGpu};
 use nvml_wrapper::Nvml;
 use nvml_wrapper::enum_wrappers::device::{Clock, TemperatureSensor};
 use nvml_wrapper::enums::device::UsedGpuMemory;
+use nvml_wrapper::error::NvmlError;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Consecutive failed queries before the NVML handle is considered lost
+const FAILURE_THRESHOLD: u32 = 3;
+
+/// Delay before the first reinit attempt, doubled after each failed attempt
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Longest delay between two reinit attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// NVML reinit attempts since startup (all monitors)
+static REINIT_ATTEMPTS: AtomicU64 = AtomicU64::new(0);
+
+/// Number of NVML reinit attempts since startup
+pub fn reinit_attempts() -> u64 {
+    REINIT_ATTEMPTS.load(Ordering::Relaxed)
+}
 
 /// Holds the NVML library instance and the primary GPU device index.
 pub struct NvmlState {
@@ -48,10 +75,30 @@ pub fn init_nvml() -> Option<NvmlState> {
     })
 }
 
+/// Every NVIDIA GPU with the driver version, None if NVML is not available
+pub fn list_devices() -> Option<Vec<HardwareGpu>> {
+    let nvml = Nvml::init().ok()?;
+    let driver_version = nvml.sys_driver_version().ok();
+    let count = nvml.device_count().ok()?;
+
+    let devices = (0..count)
+        .filter_map(|i| nvml.device_by_index(i).ok())
+        .map(|device| HardwareGpu {
+            name: device.name().unwrap_or_else(|_| "NVIDIA GPU".to_string()),
+            driver_version: driver_version.clone(),
+            vram_total_mb: device.memory_info().ok().map(|m| m.total / (1024 * 1024)),
+        })
+        .collect();
+    Some(devices)
+}
+
+/// Answer of a device query: Ok(None) when the GPU has no such sensor, Err when NVML failed
+pub type QueryResult<T> = std::result::Result<Option<T>, NvmlError>;
+
 /// Query full GPU metrics via NVML.
-/// Returns None if any critical query fails.
-pub fn query_gpu_metrics(state: &NvmlState) -> Option<GpuMetrics> {
-    let device = state.nvml.device_by_index(state.device_index).ok()?;
+/// Fails only when the device can't be reached, the metrics it doesn't support are None.
+pub fn query_gpu_metrics(state: &NvmlState) -> QueryResult<GpuMetrics> {
+    let device = state.nvml.device_by_index(state.device_index)?;
 
     // Utilization rates (GPU & memory engine usage %)
     let utilization = device.utilization_rates().ok();
@@ -80,7 +127,7 @@ pub fn query_gpu_metrics(state: &NvmlState) -> Option<GpuMetrics> {
     // Fan speed (percentage) — may fail on laptops without fans
     let fan_speed_percent = device.fan_speed(0).ok().map(|f| f as u64);
 
-    Some(GpuMetrics {
+    Ok(Some(GpuMetrics {
         name: state.device_name.clone(),
         usage_percent,
         power_watts,
@@ -91,15 +138,141 @@ pub fn query_gpu_metrics(state: &NvmlState) -> Option<GpuMetrics> {
         source: "nvml".to_string(),
         memory_clock_mhz,
         fan_speed_percent,
-    })
+    }))
+}
+
+/// NVML queries used by the monitors (seam for tests)
+pub trait GpuDevice: Send {
+    fn metrics(&self) -> QueryResult<GpuMetrics>;
+    fn power(&self) -> QueryResult<(f64, String)>;
+    fn processes(&self) -> HashMap<u32, f64>;
+}
+
+impl GpuDevice for NvmlState {
+    fn metrics(&self) -> QueryResult<GpuMetrics> {
+        query_gpu_metrics(self)
+    }
+
+    fn power(&self) -> QueryResult<(f64, String)> {
+        query_gpu_power(self)
+    }
+
+    fn processes(&self) -> HashMap<u32, f64> {
+        query_gpu_processes(self)
+    }
+}
+
+/// Device state and reinit schedule of an `NvmlHandle`
+struct Recovery<D> {
+    /// None while NVML is lost
+    device: Option<D>,
+    consecutive_failures: u32,
+    backoff: Duration,
+    retry_at: Option<Instant>,
+}
+
+/// NVML state that recovers from driver resets
+pub struct NvmlHandle<D: GpuDevice = NvmlState> {
+    state: Mutex<Recovery<D>>,
+    init: fn() -> Option<D>,
+}
+
+impl NvmlHandle<NvmlState> {
+    /// Initialize NVML, None if it is not available at all
+    pub fn init() -> Option<Self> {
+        init_nvml().map(|state| Self::new(state, init_nvml))
+    }
+}
+
+impl<D: GpuDevice> NvmlHandle<D> {
+    fn new(device: D, init: fn() -> Option<D>) -> Self {
+        Self {
+            state: Mutex::new(Recovery {
+                device: Some(device),
+                consecutive_failures: 0,
+                backoff: INITIAL_BACKOFF,
+                retry_at: None,
+            }),
+            init,
+        }
+    }
+
+    /// Full GPU metrics, None on failure or while NVML is being reinitialized
+    pub fn gpu_metrics(&self) -> Option<GpuMetrics> {
+        self.query(Instant::now(), D::metrics)
+    }
+
+    /// GPU power and name, None without a power sensor, on failure or while NVML is being reinitialized
+    pub fn gpu_power(&self) -> Option<(f64, String)> {
+        self.query(Instant::now(), D::power)
+    }
+
+    /// Per-process GPU usage, None while NVML is being reinitialized
+    pub fn gpu_processes(&self) -> Option<HashMap<u32, f64>> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.device.as_ref().map(D::processes)
+    }
+
+    /// Run a query, tracking failures and reinitializing NVML when it is due
+    ///
+    /// Only errors count as failures, an unsupported sensor answers None like a healthy device.
+    fn query<T>(&self, now: Instant, f: impl Fn(&D) -> QueryResult<T>) -> Option<T> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        if state.device.is_none() {
+            if state.retry_at.is_some_and(|at| now < at) {
+                return None;
+            }
+            let attempt = REINIT_ATTEMPTS.fetch_add(1, Ordering::Relaxed) + 1;
+            match (self.init)() {
+                Some(device) => {
+                    log::info!("NVML reinitialized (attempt {} since startup)", attempt);
+                    state.device = Some(device);
+                    state.consecutive_failures = 0;
+                    state.backoff = INITIAL_BACKOFF;
+                    state.retry_at = None;
+                }
+                None => {
+                    log::warn!("NVML reinit failed, next attempt in {:?}", state.backoff);
+                    state.retry_at = Some(now + state.backoff);
+                    state.backoff = (state.backoff * 2).min(MAX_BACKOFF);
+                    return None;
+                }
+            }
+        }
+
+        match f(state.device.as_ref()?) {
+            Ok(value) => {
+                state.consecutive_failures = 0;
+                value
+            }
+            Err(e) => {
+                log::debug!("NVML query failed: {}", e);
+                state.consecutive_failures += 1;
+                if state.consecutive_failures >= FAILURE_THRESHOLD {
+                    log::warn!(
+                        "NVML failed {} times in a row (driver reset?), reinitializing in {:?}",
+                        state.consecutive_failures, state.backoff
+                    );
+                    state.device = None;
+                    state.consecutive_failures = 0;
+                    state.retry_at = Some(now + state.backoff);
+                }
+                None
+            }
+        }
+    }
 }
 
 /// Query GPU power only (for the fast path).
-/// Returns (power_watts, gpu_name).
-pub fn query_gpu_power(state: &NvmlState) -> Option<(f64, String)> {
-    let device = state.nvml.device_by_index(state.device_index).ok()?;
-    let power_mw = device.power_usage().ok()?;
-    Some((power_mw as f64 / 1000.0, state.device_name.clone()))
+/// Returns (power_watts, gpu_name), None on GPUs without power telemetry.
+pub fn query_gpu_power(state: &NvmlState) -> QueryResult<(f64, String)> {
+    let device = state.nvml.device_by_index(state.device_index)?;
+    match device.power_usage() {
+        Ok(power_mw) => Ok(Some((power_mw as f64 / 1000.0, state.device_name.clone()))),
+        Err(NvmlError::NotSupported) => Ok(None),
+        Err(e) => Err(e),
+    }
 }
 
 /// Query per-process GPU usage via NVML.
@@ -157,3 +330,78 @@ pub fn query_gpu_processes(state: &NvmlState) -> HashMap<u32, f64> {
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+
+    /// Simulated driver: queries fail while DRIVER_DOWN, init fails while INIT_FAILS
+    static DRIVER_DOWN: AtomicBool = AtomicBool::new(false);
+    static INIT_FAILS: AtomicBool = AtomicBool::new(false);
+
+    struct FakeDevice;
+
+    impl GpuDevice for FakeDevice {
+        /// A GPU without any sensor, like the power of many laptop GPUs
+        fn metrics(&self) -> QueryResult<GpuMetrics> {
+            Ok(None)
+        }
+
+        fn power(&self) -> QueryResult<(f64, String)> {
+            if DRIVER_DOWN.load(Ordering::SeqCst) {
+                return Err(NvmlError::Unknown);
+            }
+            Ok(Some((120.0, "Fake GPU".to_string())))
+        }
+
+        fn processes(&self) -> HashMap<u32, f64> {
+            HashMap::new()
+        }
+    }
+
+    fn fake_init() -> Option<FakeDevice> {
+        (!INIT_FAILS.load(Ordering::SeqCst)).then_some(FakeDevice)
+    }
+
+    #[test]
+    fn test_reinit_after_driver_reset() {
+        let handle = NvmlHandle::new(FakeDevice, fake_init);
+        let t0 = Instant::now();
+        let power = |at: Duration| handle.query(t0 + at, FakeDevice::power).map(|(w, _)| w);
+        assert_eq!(power(Duration::ZERO), Some(120.0));
+
+        // Unsupported sensors are no failures: the device stays
+        for _ in 0..FAILURE_THRESHOLD * 2 {
+            assert!(handle.query(t0, FakeDevice::metrics).is_none());
+        }
+        assert!(handle.gpu_processes().is_some());
+
+        // Driver reset: the handle is dropped after FAILURE_THRESHOLD failures
+        DRIVER_DOWN.store(true, Ordering::SeqCst);
+        INIT_FAILS.store(true, Ordering::SeqCst);
+        for _ in 0..FAILURE_THRESHOLD {
+            assert_eq!(power(Duration::ZERO), None);
+        }
+        assert!(handle.gpu_processes().is_none());
+
+        // No attempt before the backoff, then the delay doubles after each failed attempt
+        let attempts = reinit_attempts();
+        assert_eq!(power(Duration::from_secs(1)), None);
+        assert_eq!(reinit_attempts(), attempts);
+        assert_eq!(power(Duration::from_secs(2)), None);
+        assert_eq!(power(Duration::from_secs(5)), None);
+        assert_eq!(power(Duration::from_secs(6)), None);
+        assert_eq!(reinit_attempts(), attempts + 2);
+        assert_eq!(power(Duration::from_secs(13)), None);
+        assert_eq!(reinit_attempts(), attempts + 3);
+
+        // Driver back: the next attempt (due at 21s) succeeds
+        DRIVER_DOWN.store(false, Ordering::SeqCst);
+        INIT_FAILS.store(false, Ordering::SeqCst);
+        assert_eq!(power(Duration::from_secs(20)), None);
+        assert_eq!(power(Duration::from_secs(29)), Some(120.0));
+        assert_eq!(reinit_attempts(), attempts + 4);
+        assert!(handle.gpu_processes().is_some());
+    }
+}
@@ -0,0 +1,211 @@
+//! Parsing of macOS `powermetrics` samples
+//!
+//! `powermetrics --samplers cpu_power,gpu_power` reports the SoC power rails
+//! on Apple Silicon (CPU, GPU, ANE in mW) and the package power on Intel
+//! Macs. Both the plist format (`-f plist`, one NUL-separated document per
+//! sample) and the default text format are understood.
+
+/// One powermetrics sample, in watts
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PowermetricsSample {
+    pub cpu_watts: Option<f64>,
+    pub gpu_watts: Option<f64>,
+    /// Apple Neural Engine
+    pub ane_watts: Option<f64>,
+    /// CPU + GPU + ANE as reported by powermetrics
+    pub combined_watts: Option<f64>,
+    /// Intel Macs: package power (CPUs + GT + SA)
+    pub package_watts: Option<f64>,
+    /// GPU busy time over the sample (0-100)
+    pub gpu_active_percent: Option<f64>,
+}
+
+impl PowermetricsSample {
+    /// Total power of the sample, None when it carried no power figure
+    pub fn total_watts(&self) -> Option<f64> {
+        self.combined_watts.or(self.package_watts).or_else(|| {
+            let parts = [self.cpu_watts, self.gpu_watts, self.ane_watts];
+            parts.iter().any(Option::is_some).then(|| parts.iter().flatten().sum())
+        })
+    }
+}
+
+/// Parse one sample, plist or text
+pub fn parse_sample(output: &str) -> Option<PowermetricsSample> {
+    let output = output.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    let sample = if output.starts_with("<?xml") || output.starts_with("<plist") {
+        parse_plist(output)
+    } else {
+        parse_text(output)
+    };
+    sample.total_watts().is_some().then_some(sample)
+}
+
+/// Numeric value following `<key>name</key>` (first occurrence)
+fn plist_number(xml: &str, key: &str) -> Option<f64> {
+    let tag = format!("<key>{}</key>", key);
+    let rest = xml[xml.find(&tag)? + tag.len()..].trim_start();
+    let rest = rest.strip_prefix("<real>").or_else(|| rest.strip_prefix("<integer>"))?;
+    rest[..rest.find('<')?].trim().parse().ok()
+}
+
+fn parse_plist(xml: &str) -> PowermetricsSample {
+    let mw = |key: &str| plist_number(xml, key).map(|v| v / 1000.0);
+    // The per-CPU dicts have an idle_ratio too, only the one of the gpu dict counts
+    let gpu_dict = xml.find("<key>gpu</key>").map(|i| &xml[i..]);
+    PowermetricsSample {
+        cpu_watts: mw("cpu_power"),
+        gpu_watts: mw("gpu_power"),
+        ane_watts: mw("ane_power"),
+        combined_watts: mw("combined_power"),
+        package_watts: plist_number(xml, "package_watts"),
+        gpu_active_percent: gpu_dict
+            .and_then(|gpu| plist_number(gpu, "idle_ratio"))
+            .map(|idle| ((1.0 - idle) * 100.0).clamp(0.0, 100.0)),
+    }
+}
+
+/// Value in watts of "12.3 mW" / "4.56W"
+fn text_watts(value: &str) -> Option<f64> {
+    let value = value.trim();
+    if let Some(mw) = value.strip_suffix("mW") {
+        mw.trim().parse::<f64>().ok().map(|v| v / 1000.0)
+    } else {
+        value.strip_suffix('W')?.trim().parse().ok()
+    }
+}
+
+fn parse_text(output: &str) -> PowermetricsSample {
+    let mut sample = PowermetricsSample::default();
+    for line in output.lines() {
+        let Some((label, value)) = line.split_once(':') else {
+            continue;
+        };
+        let label = label.trim();
+        match label {
+            "CPU Power" => sample.cpu_watts = text_watts(value),
+            "GPU Power" => sample.gpu_watts = text_watts(value),
+            "ANE Power" => sample.ane_watts = text_watts(value),
+            _ if label.starts_with("Combined Power") => sample.combined_watts = text_watts(value),
+            _ if label.starts_with("Intel energy model derived package power") => {
+                sample.package_watts = text_watts(value)
+            }
+            "GPU HW active residency" | "GPU active residency" => {
+                // "  12.34% (389 MHz: 10% ...)"
+                sample.gpu_active_percent = value
+                    .split('%')
+                    .next()
+                    .and_then(|v| v.trim().parse().ok());
+            }
+            _ => {}
+        }
+    }
+    sample
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const APPLE_TEXT: &str = "\
+Machine model: Mac14,2
+OS version: 23A344
+
+*** Sampled system activity (Tue Oct 10 10:00:00 2023 +0200) (1004.12ms elapsed) ***
+
+**** Processor usage ****
+
+E-Cluster HW active frequency: 1120 MHz
+E-Cluster HW active residency:  38.42% (600 MHz: 20% 912 MHz: 80%)
+CPU 0 frequency: 1140 MHz
+CPU 0 idle residency:  61.58%
+
+CPU Power: 1234 mW
+GPU Power: 56 mW
+ANE Power: 0 mW
+Combined Power (CPU + GPU + ANE): 1290 mW
+
+**** GPU usage ****
+
+GPU HW active frequency: 389 MHz
+GPU HW active residency:  12.34% (389 MHz: 100% 486 MHz:   0%)
+GPU SW requested state: (P1 : 100% P2 :   0%)
+GPU idle residency:  87.66%
+GPU Power: 56 mW
+";
+
+    const INTEL_TEXT: &str = "\
+**** Processor usage ****
+
+Intel energy model derived package power (CPUs+GT+SA): 5.23W
+
+**** GPU usage ****
+
+GPU active residency:   3.50%
+";
+
+    const APPLE_PLIST: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">
+<plist version=\"1.0\">
+<dict>
+<key>processor</key>
+<dict>
+  <key>clusters</key>
+  <array>
+    <dict>
+      <key>name</key><string>E-Cluster</string>
+      <key>cpus</key>
+      <array>
+        <dict><key>cpu</key><integer>0</integer><key>idle_ratio</key><real>0.61</real></dict>
+      </array>
+    </dict>
+  </array>
+  <key>cpu_power</key><real>2345.5</real>
+  <key>gpu_power</key><real>120</real>
+  <key>ane_power</key><integer>0</integer>
+  <key>combined_power</key><real>2465.5</real>
+</dict>
+<key>gpu</key>
+<dict>
+  <key>freq_hz</key><real>389000000</real>
+  <key>idle_ratio</key><real>0.75</real>
+  <key>gpu_energy</key><integer>120</integer>
+</dict>
+</dict>
+</plist>
+\0";
+
+    #[test]
+    fn test_parse_text_samples() {
+        let apple = parse_sample(APPLE_TEXT).unwrap();
+        assert_eq!(apple.cpu_watts, Some(1.234));
+        assert_eq!(apple.gpu_watts, Some(0.056));
+        assert_eq!(apple.combined_watts, Some(1.29));
+        assert_eq!(apple.gpu_active_percent, Some(12.34));
+        assert_eq!(apple.total_watts(), Some(1.29));
+
+        let intel = parse_sample(INTEL_TEXT).unwrap();
+        assert_eq!(intel.package_watts, Some(5.23));
+        assert_eq!(intel.gpu_active_percent, Some(3.5));
+        assert_eq!(intel.total_watts(), Some(5.23));
+
+        // Error output when not run as root
+        assert_eq!(parse_sample("powermetrics must be invoked as the superuser\n"), None);
+    }
+
+    #[test]
+    fn test_parse_plist_sample() {
+        let sample = parse_sample(APPLE_PLIST).unwrap();
+        assert_eq!(sample.cpu_watts, Some(2.3455));
+        assert_eq!(sample.gpu_watts, Some(0.12));
+        assert_eq!(sample.ane_watts, Some(0.0));
+        assert_eq!(sample.total_watts(), Some(2.4655));
+        // From the gpu dict, not the per-CPU idle ratio
+        assert_eq!(sample.gpu_active_percent, Some(25.0));
+
+        // No combined figure: sum of the rails
+        let partial = PowermetricsSample { cpu_watts: Some(2.0), gpu_watts: Some(0.5), ..Default::default() };
+        assert_eq!(partial.total_watts(), Some(2.5));
+        assert_eq!(PowermetricsSample::default().total_watts(), None);
+    }
+}
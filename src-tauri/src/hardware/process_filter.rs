@@ -0,0 +1,120 @@
+//! Process ignore list matching and top list truncation
+//!
+//! Patterns are compared to process names case-insensitively. `*` matches any
+//! run of characters (so `kworker*` hides every kernel worker), everything
+//! else is literal.
+
+use crate::core::ProcessMetrics;
+
+/// Whether `name` matches one of `patterns`
+pub fn is_ignored(patterns: &[String], name: &str) -> bool {
+    let name = name.to_lowercase();
+    patterns.iter().any(|p| glob_match(&p.to_lowercase(), &name))
+}
+
+/// Whether a process is ignored by its name or, like pinning, by its container
+pub fn is_process_ignored(patterns: &[String], name: &str, container: Option<&str>) -> bool {
+    is_ignored(patterns, name) || container.is_some_and(|c| is_ignored(patterns, c))
+}
+
+/// Keep the first `len` processes and sum the rest into an "Other" row
+pub fn truncate_with_other(processes: &mut Vec<ProcessMetrics>, len: usize) {
+    if processes.len() <= len {
+        return;
+    }
+    let excluded = processes.split_off(len);
+    processes.extend(ProcessMetrics::other(&excluded));
+}
+
+/// Match `text` against a pattern where `*` is the only wildcard
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // split always yields at least one part
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let middle: Vec<&str> = parts.collect();
+    let Some((last, middle)) = middle.split_last() else {
+        // No wildcard: exact match
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignore_patterns() {
+        let patterns = vec!["svchost.exe".to_string(), "kworker*".to_string(), "*helper*".to_string()];
+        assert!(is_ignored(&patterns, "SvcHost.EXE"));
+        assert!(is_ignored(&patterns, "kworker/0:1-events"));
+        assert!(is_ignored(&patterns, "GoogleCrashHelper.exe"));
+        assert!(!is_ignored(&patterns, "svchost"));
+        assert!(!is_ignored(&patterns, "dwm.exe"));
+        assert!(!is_ignored(&[], "dwm.exe"));
+
+        let containers = vec!["docker:*".to_string()];
+        assert!(is_process_ignored(&containers, "python3", Some("docker:3f1c2b9a8e7d")));
+        assert!(!is_process_ignored(&containers, "python3", None));
+
+        assert!(glob_match("a*b*c", "axxbyyc"));
+        assert!(!glob_match("a*b*c", "axxcyyb"));
+        // The suffix may not reuse characters consumed by the prefix
+        assert!(!glob_match("ab*ba", "aba"));
+        assert!(glob_match("*", ""));
+    }
+
+    fn process(name: &str, cpu_percent: f64, memory_bytes: u64, gpu_percent: Option<f64>) -> ProcessMetrics {
+        ProcessMetrics {
+            pid: 1,
+            name: name.to_string(),
+            cpu_percent,
+            memory_bytes,
+            memory_percent: memory_bytes as f64 / 10.0,
+            gpu_percent,
+            is_pinned: false,
+            is_ignored: false,
+            is_aggregate: false,
+            container: None,
+        }
+    }
+
+    #[test]
+    fn test_truncate_with_other() {
+        let mut processes = vec![
+            process("game", 50.0, 400, Some(90.0)),
+            process("browser", 20.0, 300, None),
+            process("editor", 5.0, 100, None),
+            process("discord", 2.5, 150, Some(3.0)),
+        ];
+        let full = crate::core::ProcessTotals::of(&processes);
+        truncate_with_other(&mut processes, 2);
+
+        assert_eq!(processes.len(), 3);
+        let other = &processes[2];
+        assert!(other.is_aggregate && !other.is_pinned);
+        assert_eq!(other.name, crate::core::OTHER_PROCESSES_NAME);
+        assert_eq!((other.cpu_percent, other.memory_bytes, other.gpu_percent), (7.5, 250, Some(3.0)));
+        assert_eq!(other.memory_percent, 25.0);
+
+        // The Other row keeps the totals whole, the listed memory leaves it out
+        let totals = crate::core::ProcessTotals::of(&processes);
+        assert_eq!((totals.cpu_percent, totals.memory_bytes), (full.cpu_percent, full.memory_bytes));
+        assert_eq!(totals.listed_memory_bytes, 700);
+
+        // Nothing left out, no row
+        truncate_with_other(&mut processes, 5);
+        assert_eq!(processes.len(), 3);
+    }
+}
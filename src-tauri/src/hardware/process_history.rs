@@ -0,0 +1,178 @@
+//! Short history of the pinned processes for the pinned dashboard widget
+//!
+//! One sample per detailed-loop update, the last `HISTORY_LEN` kept per
+//! pinned name, independently of whether the process made the top list. A
+//! pinned process that is not running is sampled as zero so the series stay
+//! aligned; unpinning drops its buffers.
+
+use crate::core::ProcessMetrics;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// Samples kept per pinned process
+pub const HISTORY_LEN: usize = 60;
+
+/// Current values and recent series of a pinned process, oldest sample first
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PinnedProcessHistory {
+    pub name: String,
+    /// Whether the process was found in the latest sample
+    pub running: bool,
+    pub cpu_percent: f64,
+    pub gpu_percent: Option<f64>,
+    pub memory_percent: f64,
+    pub memory_bytes: u64,
+    pub cpu: Vec<f32>,
+    /// None where the GPU usage of the process was not known
+    pub gpu: Vec<Option<f32>>,
+    pub memory: Vec<f32>,
+}
+
+#[derive(Debug)]
+struct Series {
+    name: String,
+    latest: Option<ProcessMetrics>,
+    cpu: VecDeque<f32>,
+    gpu: VecDeque<Option<f32>>,
+    memory: VecDeque<f32>,
+}
+
+impl Series {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            latest: None,
+            cpu: VecDeque::with_capacity(HISTORY_LEN),
+            gpu: VecDeque::with_capacity(HISTORY_LEN),
+            memory: VecDeque::with_capacity(HISTORY_LEN),
+        }
+    }
+
+    fn push(&mut self, process: Option<&ProcessMetrics>) {
+        if self.cpu.len() >= HISTORY_LEN {
+            self.cpu.pop_front();
+            self.gpu.pop_front();
+            self.memory.pop_front();
+        }
+        self.cpu.push_back(process.map_or(0.0, |p| p.cpu_percent as f32));
+        self.gpu.push_back(process.map_or(Some(0.0), |p| p.gpu_percent.map(|g| g as f32)));
+        self.memory.push_back(process.map_or(0.0, |p| p.memory_percent as f32));
+        self.latest = process.cloned();
+    }
+}
+
+/// Ring buffers of the pinned processes, matched by name (case-insensitive)
+#[derive(Debug, Default)]
+pub struct ProcessHistory {
+    series: Vec<Series>,
+}
+
+impl ProcessHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a sample of every pinned process from the latest process list
+    pub fn push(&mut self, pinned: &[String], processes: &[ProcessMetrics]) {
+        self.series.retain(|s| pinned.iter().any(|name| name.eq_ignore_ascii_case(&s.name)));
+        for name in pinned {
+            let process = processes.iter().find(|p| !p.is_aggregate && p.name.eq_ignore_ascii_case(name));
+            match self.series.iter_mut().find(|s| s.name.eq_ignore_ascii_case(name)) {
+                Some(series) => series.push(process),
+                None => {
+                    let mut series = Series::new(name);
+                    series.push(process);
+                    self.series.push(series);
+                }
+            }
+        }
+    }
+
+    /// Drop the history of an unpinned process
+    pub fn forget(&mut self, name: &str) {
+        self.series.retain(|s| !s.name.eq_ignore_ascii_case(name));
+    }
+
+    /// History of each pinned process in `pinned` order, empty for names not sampled yet
+    pub fn snapshot(&self, pinned: &[String]) -> Vec<PinnedProcessHistory> {
+        pinned
+            .iter()
+            .map(|name| {
+                let series = self.series.iter().find(|s| s.name.eq_ignore_ascii_case(name));
+                let latest = series.and_then(|s| s.latest.as_ref());
+                PinnedProcessHistory {
+                    name: name.clone(),
+                    running: latest.is_some(),
+                    cpu_percent: latest.map_or(0.0, |p| p.cpu_percent),
+                    gpu_percent: latest.and_then(|p| p.gpu_percent),
+                    memory_percent: latest.map_or(0.0, |p| p.memory_percent),
+                    memory_bytes: latest.map_or(0, |p| p.memory_bytes),
+                    cpu: series.map_or_else(Vec::new, |s| s.cpu.iter().copied().collect()),
+                    gpu: series.map_or_else(Vec::new, |s| s.gpu.iter().copied().collect()),
+                    memory: series.map_or_else(Vec::new, |s| s.memory.iter().copied().collect()),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(name: &str, cpu: f64) -> ProcessMetrics {
+        ProcessMetrics {
+            pid: 1,
+            name: name.to_string(),
+            cpu_percent: cpu,
+            memory_bytes: 1024,
+            memory_percent: 2.0,
+            gpu_percent: None,
+            is_pinned: true,
+            is_ignored: false,
+            is_aggregate: false,
+            container: None,
+        }
+    }
+
+    #[test]
+    fn test_pinned_history_is_bounded() {
+        let mut history = ProcessHistory::new();
+        let pinned = vec!["firefox".to_string(), "steam".to_string()];
+        for i in 0..HISTORY_LEN + 5 {
+            history.push(&pinned, &[process("Firefox", i as f64)]);
+        }
+
+        let snapshot = history.snapshot(&pinned);
+        assert_eq!(snapshot.len(), 2);
+        assert!(snapshot[0].running);
+        assert_eq!(snapshot[0].name, "firefox");
+        assert_eq!(snapshot[0].cpu.len(), HISTORY_LEN);
+        assert_eq!(snapshot[0].cpu[0], 5.0);
+        assert_eq!(snapshot[0].cpu_percent, (HISTORY_LEN + 4) as f64);
+        assert_eq!(snapshot[0].gpu.last(), Some(&None));
+
+        // Not running: sampled as zero and flagged
+        assert!(!snapshot[1].running);
+        assert_eq!(snapshot[1].cpu.len(), HISTORY_LEN);
+        assert!(snapshot[1].cpu.iter().all(|&c| c == 0.0));
+    }
+
+    #[test]
+    fn test_unpinned_history_is_dropped() {
+        let mut history = ProcessHistory::new();
+        let both = vec!["firefox".to_string(), "steam".to_string()];
+        history.push(&both, &[process("firefox", 10.0), process("steam", 20.0)]);
+
+        history.forget("STEAM");
+        let again = history.snapshot(&both);
+        assert!(again[1].cpu.is_empty() && !again[1].running);
+
+        // Names no longer pinned are dropped on the next sample too
+        history.push(&both[..1], &[process("firefox", 10.0)]);
+        history.push(&both, &[process("steam", 5.0)]);
+        let snapshot = history.snapshot(&both);
+        assert_eq!(snapshot[0].cpu, vec![10.0, 10.0, 0.0]);
+        assert_eq!(snapshot[1].cpu, vec![5.0]);
+    }
+}
@@ -0,0 +1,164 @@
+//! Per-process CPU time from /proc/<pid>/stat
+//!
+//! sysinfo's `cpu_usage()` is an instantaneous value, noisy at the detailed
+//! cadence and blind to spikes between two refreshes. Cumulative CPU time
+//! deltas give the true average over the interval instead.
+
+use std::collections::HashMap;
+use std::fs;
+use std::time::Instant;
+
+/// Clock ticks per second used by /proc (USER_HZ, fixed at 100 by the kernel ABI)
+const USER_HZ: f64 = 100.0;
+
+/// Fields of /proc/<pid>/stat we use
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcStat {
+    pub comm: String,
+    /// User + system time, in clock ticks
+    pub cpu_ticks: u64,
+    /// Start time after boot, in clock ticks (identifies the process across PID reuse)
+    pub start_time: u64,
+}
+
+/// Parse a /proc/<pid>/stat line
+///
+/// `comm` is wrapped in parentheses and may itself contain spaces and
+/// parentheses, so it ends at the *last* `)` of the line.
+pub fn parse_proc_stat(line: &str) -> Option<ProcStat> {
+    let open = line.find('(')?;
+    let close = line.rfind(')')?;
+    if close < open {
+        return None;
+    }
+    let comm = line[open + 1..close].to_string();
+
+    // Fields after comm, starting at field 3 (state)
+    let fields: Vec<&str> = line[close + 1..].split_whitespace().collect();
+    let field = |n: usize| -> Option<u64> { fields.get(n - 3)?.parse().ok() };
+
+    let utime = field(14)?;
+    let stime = field(15)?;
+    let start_time = field(22)?;
+
+    Some(ProcStat {
+        comm,
+        cpu_ticks: utime + stime,
+        start_time,
+    })
+}
+
+/// Read and parse /proc/<pid>/stat (None once the process has exited)
+pub fn read_proc_stat(pid: u32) -> Option<ProcStat> {
+    let line = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    parse_proc_stat(&line)
+}
+
+/// Average CPU usage per PID between successive updates
+#[derive(Debug, Default)]
+pub struct ProcCpuTracker {
+    /// (start_time, cpu_ticks) at the previous update
+    previous: HashMap<u32, (u64, u64)>,
+    /// Usage over the last interval (100 = one logical core)
+    usage: HashMap<u32, f64>,
+    last_update: Option<Instant>,
+}
+
+impl ProcCpuTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the current stats of all live processes
+    ///
+    /// PIDs missing from `stats` have exited and are dropped. A PID whose
+    /// start time changed was reused by a new process and starts over.
+    pub fn update<I>(&mut self, now: Instant, stats: I)
+    where
+        I: IntoIterator<Item = (u32, ProcStat)>,
+    {
+        let elapsed = self
+            .last_update
+            .map(|t| now.saturating_duration_since(t).as_secs_f64())
+            .filter(|&s| s > 0.0);
+
+        let mut current = HashMap::new();
+        self.usage.clear();
+        for (pid, stat) in stats {
+            if let (Some(secs), Some(&(start_time, ticks))) = (elapsed, self.previous.get(&pid)) {
+                if start_time == stat.start_time {
+                    let delta = stat.cpu_ticks.saturating_sub(ticks) as f64 / USER_HZ;
+                    self.usage.insert(pid, delta / secs * 100.0);
+                }
+            }
+            current.insert(pid, (stat.start_time, stat.cpu_ticks));
+        }
+
+        self.previous = current;
+        self.last_update = Some(now);
+    }
+
+    /// Average usage over the last interval, None for processes first seen in it
+    pub fn cpu_percent(&self, pid: u32) -> Option<f64> {
+        self.usage.get(&pid).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const PLAIN: &str = "1234 (bash) S 1 1234 1234 34816 1234 4194304 2000 0 0 0 150 50 0 0 20 0 1 0 98765 12345678 900 18446744073709551615 0 0 0 0 0 0 0 0 0 0 0 0 17 2 0 0 0 0 0";
+    const TRICKY: &str = "42 (Web Content (1) x) R 1 42 42 0 -1 4194560 100 0 0 0 7 3 0 0 20 0 30 0 5555 1000 10 18446744073709551615";
+
+    fn stat(cpu_ticks: u64, start_time: u64) -> ProcStat {
+        ProcStat { comm: "p".to_string(), cpu_ticks, start_time }
+    }
+
+    #[test]
+    fn test_parse_proc_stat() {
+        let s = parse_proc_stat(PLAIN).unwrap();
+        assert_eq!(s.comm, "bash");
+        assert_eq!(s.cpu_ticks, 200);
+        assert_eq!(s.start_time, 98765);
+
+        // Spaces and parentheses in comm don't shift the fields
+        let s = parse_proc_stat(TRICKY).unwrap();
+        assert_eq!(s.comm, "Web Content (1) x");
+        assert_eq!(s.cpu_ticks, 10);
+        assert_eq!(s.start_time, 5555);
+
+        assert!(parse_proc_stat("garbage").is_none());
+        assert!(parse_proc_stat("1 (short) S 1 2").is_none());
+    }
+
+    #[test]
+    fn test_average_usage_over_interval() {
+        let mut tracker = ProcCpuTracker::new();
+        let t0 = Instant::now();
+        tracker.update(t0, vec![(1, stat(1000, 10)), (2, stat(50, 20))]);
+        assert_eq!(tracker.cpu_percent(1), None);
+
+        // 5 s later: pid 1 used 250 ticks = 2.5 s of CPU => 50%, pid 2 used two cores
+        tracker.update(t0 + Duration::from_secs(5), vec![(1, stat(1250, 10)), (2, stat(1050, 20))]);
+        assert!((tracker.cpu_percent(1).unwrap() - 50.0).abs() < 1e-9);
+        assert!((tracker.cpu_percent(2).unwrap() - 200.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pid_reuse_and_exit() {
+        let mut tracker = ProcCpuTracker::new();
+        let t0 = Instant::now();
+        tracker.update(t0, vec![(1, stat(5000, 10)), (2, stat(100, 20))]);
+
+        // pid 1 was reused by a new process, pid 2 exited
+        tracker.update(t0 + Duration::from_secs(5), vec![(1, stat(30, 900))]);
+        assert_eq!(tracker.cpu_percent(1), None);
+        assert_eq!(tracker.cpu_percent(2), None);
+        assert!(!tracker.previous.contains_key(&2));
+
+        tracker.update(t0 + Duration::from_secs(10), vec![(1, stat(80, 900))]);
+        assert!((tracker.cpu_percent(1).unwrap() - 10.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,234 @@
+//! Sanity filter for power readings
+//!
+//! Drops physically implausible samples (RAPL wraparound mishandling, nvidia-smi
+//! glitches) before they reach cumulative energy, the daily max or the database.
+//! A reading is rejected when it is above an absolute limit, or when it is more
+//! than `spike_factor` times the trailing median. A high reading that persists
+//! for `SUSTAIN_SAMPLES` consecutive samples is treated as a genuine load step
+//! and passes through.
+
+use crate::core::MonitoringHealth;
+use std::collections::VecDeque;
+
+/// Default absolute maximum plausible power in watts
+pub const DEFAULT_MAX_WATTS: f64 = 2000.0;
+
+/// Default spike factor relative to the trailing median
+pub const DEFAULT_SPIKE_FACTOR: f64 = 4.0;
+
+/// Trailing window used for the median
+const MEDIAN_WINDOW: usize = 30;
+
+/// Samples needed before the spike filter kicks in
+const MIN_HISTORY: usize = 5;
+
+/// Consecutive high samples after which a jump is accepted as real
+const SUSTAIN_SAMPLES: u32 = 3;
+
+/// Spike threshold floor, so small absolute jumps at very low idle aren't rejected
+const MIN_SPIKE_WATTS: f64 = 50.0;
+
+/// Outcome of filtering one reading
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterResult {
+    /// Reading is plausible and should be used as is
+    Accepted(f64),
+    /// Reading was rejected, `substitute` is the previous accepted value
+    Rejected { watts: f64, substitute: f64 },
+}
+
+impl FilterResult {
+    /// Value to display and integrate (the substitute for rejected readings)
+    pub fn watts(self) -> f64 {
+        match self {
+            Self::Accepted(w) => w,
+            Self::Rejected { substitute, .. } => substitute,
+        }
+    }
+
+    pub fn is_rejected(self) -> bool {
+        matches!(self, Self::Rejected { .. })
+    }
+}
+
+/// Stateful power reading filter
+pub struct PowerSanitizer {
+    /// Absolute maximum plausible power
+    max_watts: f64,
+    /// Rejection factor relative to the trailing median (0 = disabled)
+    spike_factor: f64,
+    /// Trailing accepted samples
+    history: VecDeque<f64>,
+    /// Consecutive samples above the spike threshold
+    high_streak: u32,
+    /// Last accepted value
+    last_accepted: Option<f64>,
+    health: MonitoringHealth,
+}
+
+impl PowerSanitizer {
+    /// Create a filter with the given limits
+    pub fn new(max_watts: f64, spike_factor: f64) -> Self {
+        Self {
+            max_watts,
+            spike_factor,
+            history: VecDeque::with_capacity(MEDIAN_WINDOW),
+            high_streak: 0,
+            last_accepted: None,
+            health: MonitoringHealth::default(),
+        }
+    }
+
+    /// Update the limits (e.g. after a config change)
+    pub fn set_limits(&mut self, max_watts: f64, spike_factor: f64) {
+        self.max_watts = max_watts;
+        self.spike_factor = spike_factor;
+    }
+
+    /// Counters of accepted and rejected samples
+    pub fn health(&self) -> &MonitoringHealth {
+        &self.health
+    }
+
+    /// Median of the trailing window, if there is enough history
+    fn trailing_median(&self) -> Option<f64> {
+        if self.history.len() < MIN_HISTORY {
+            return None;
+        }
+        let mut sorted: Vec<f64> = self.history.iter().copied().collect();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let mid = sorted.len() / 2;
+        Some(if sorted.len() % 2 == 0 {
+            (sorted[mid - 1] + sorted[mid]) / 2.0
+        } else {
+            sorted[mid]
+        })
+    }
+
+    /// Whether a reading is outside the absolute limits
+    fn is_out_of_range(&self, watts: f64) -> bool {
+        !watts.is_finite() || watts < 0.0 || (self.max_watts > 0.0 && watts > self.max_watts)
+    }
+
+    /// Whether a reading is above the spike threshold
+    fn is_above_spike_threshold(&self, watts: f64) -> bool {
+        if self.spike_factor <= 0.0 {
+            return false;
+        }
+        self.trailing_median()
+            .is_some_and(|median| watts > (median * self.spike_factor).max(MIN_SPIKE_WATTS))
+    }
+
+    /// Check a reading against the filter without updating any state
+    pub fn is_plausible(&self, watts: f64) -> bool {
+        !self.is_out_of_range(watts) && !self.is_above_spike_threshold(watts)
+    }
+
+    fn accept(&mut self, watts: f64) -> FilterResult {
+        if self.history.len() >= MEDIAN_WINDOW {
+            self.history.pop_front();
+        }
+        self.history.push_back(watts);
+        self.last_accepted = Some(watts);
+        self.health.accepted_samples += 1;
+        FilterResult::Accepted(watts)
+    }
+
+    fn reject(&mut self, watts: f64) -> FilterResult {
+        self.health.rejected_samples += 1;
+        self.health.last_rejected_watts = Some(watts);
+        self.health.last_rejected_at = Some(chrono::Utc::now().timestamp());
+        FilterResult::Rejected {
+            watts,
+            substitute: self.last_accepted.unwrap_or(0.0),
+        }
+    }
+
+    /// Filter one reading
+    pub fn filter(&mut self, watts: f64) -> FilterResult {
+        if self.is_out_of_range(watts) {
+            return self.reject(watts);
+        }
+
+        if !self.is_above_spike_threshold(watts) {
+            self.high_streak = 0;
+            return self.accept(watts);
+        }
+
+        // Sustained high readings are a real load step: restart the window at the new level
+        self.high_streak += 1;
+        if self.high_streak >= SUSTAIN_SAMPLES {
+            self.high_streak = 0;
+            self.history.clear();
+            return self.accept(watts);
+        }
+
+        self.reject(watts)
+    }
+}
+
+impl Default for PowerSanitizer {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_WATTS, DEFAULT_SPIKE_FACTOR)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_isolated_spikes_rejected() {
+        let mut filter = PowerSanitizer::default();
+        let series = [60.0, 62.0, 58.0, 61.0, 60.0, 900.0, 59.0, 61.0, 1200.0, 60.0];
+        let out: Vec<FilterResult> = series.iter().map(|&w| filter.filter(w)).collect();
+
+        assert!(out[5].is_rejected());
+        assert_eq!(out[5].watts(), 60.0);
+        assert!(out[8].is_rejected());
+        assert_eq!(out[8].watts(), 61.0);
+        assert_eq!(out.iter().filter(|r| r.is_rejected()).count(), 2);
+        assert_eq!(filter.health().rejected_samples, 2);
+        assert_eq!(filter.health().last_rejected_watts, Some(1200.0));
+    }
+
+    #[test]
+    fn test_sustained_load_step_passes() {
+        let mut filter = PowerSanitizer::default();
+        for _ in 0..10 {
+            filter.filter(40.0);
+        }
+
+        // Idle to gaming: the first two samples look like spikes, the third confirms the step
+        let step: Vec<FilterResult> = (0..6).map(|_| filter.filter(350.0)).collect();
+        assert!(step[0].is_rejected() && step[1].is_rejected());
+        assert!(step[2..].iter().all(|r| *r == FilterResult::Accepted(350.0)));
+
+        // Back down is never a spike
+        assert_eq!(filter.filter(45.0), FilterResult::Accepted(45.0));
+    }
+
+    #[test]
+    fn test_absolute_limit() {
+        let mut filter = PowerSanitizer::new(2000.0, 0.0);
+        assert_eq!(filter.filter(150.0), FilterResult::Accepted(150.0));
+        // Out of range readings are rejected even when sustained
+        for _ in 0..5 {
+            assert!(filter.filter(4000.0).is_rejected());
+        }
+        assert!(filter.filter(f64::NAN).is_rejected());
+        assert!(filter.filter(-5.0).is_rejected());
+        assert!(!filter.is_plausible(2500.0));
+        assert!(filter.is_plausible(1500.0));
+    }
+
+    #[test]
+    fn test_low_idle_floor() {
+        let mut filter = PowerSanitizer::default();
+        for _ in 0..10 {
+            filter.filter(3.0);
+        }
+        // 10x the median but below the absolute floor
+        assert_eq!(filter.filter(30.0), FilterResult::Accepted(30.0));
+    }
+}
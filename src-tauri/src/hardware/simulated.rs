@@ -0,0 +1,45 @@
+//! Constant power source
+//!
+//! Reports a fixed draw without touching the hardware, for the command line
+//! tool's `--simulate` option and tests of the monitoring stack.
+
+use crate::core::{PowerReading, Result};
+use crate::hardware::PowerSource;
+use std::any::Any;
+
+/// Power source always reading the same wattage
+pub struct SimulatedSource {
+    watts: f64,
+}
+
+impl SimulatedSource {
+    pub fn new(watts: f64) -> Self {
+        Self { watts: watts.max(0.0) }
+    }
+}
+
+impl PowerSource for SimulatedSource {
+    fn get_power_watts(&self) -> Result<f64> {
+        Ok(self.watts)
+    }
+
+    fn get_power_watts_fast(&self) -> Result<(f64, f64, Option<f64>, Option<f64>)> {
+        Ok((self.watts, 0.0, None, None))
+    }
+
+    fn get_reading(&self) -> Result<PowerReading> {
+        Ok(PowerReading::new(self.watts, "simulated", true))
+    }
+
+    fn name(&self) -> &str {
+        "Simulated"
+    }
+
+    fn is_estimated(&self) -> bool {
+        true
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -3,14 +3,16 @@
 //! Uses sysinfo for CPU monitoring and nvidia-smi/rocm-smi for GPU power.
 //! WMI is complex and has version-specific API changes, so we avoid it for simplicity.
 
-use crate::core::{CpuMetrics, DetailedMetrics, FanMetrics, FanReading, GpuMetrics, MemoryMetrics, PowerReading, ProcessMetrics, Result, SystemMetrics};
-use crate::hardware::PowerSource;
-use crate::hardware::nvml_gpu;
+use crate::core::{CpuMetrics, DetailedMetrics, FanMetrics, FanReading, GpuMetrics, MemoryMetrics, PowerReading, ProcessMetrics, ProcessTotals, Result, SystemMetrics};
+use crate::hardware::gpu_estimate::{GpuEstimate, GPU_ESTIMATED_COMPONENT};
+use crate::hardware::{DetailedOptions, MonitorOptions, PowerSource};
+use crate::hardware::command::run_command_with_timeout;
+use crate::hardware::{attribution, nvml_gpu, process_filter};
 use std::any::Any;
 use std::collections::HashMap;
-use std::process::{Command, Output, Stdio};
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
-use std::time::Duration;
 use sysinfo::ProcessRefreshKind;
 
 #[cfg(target_os = "windows")]
@@ -43,59 +45,24 @@ unsafe impl Sync for PdhThermalQuery {}
 #[cfg(target_os = "windows")]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
-/// Default timeout for GPU commands (nvidia-smi, rocm-smi, etc.)
-const GPU_COMMAND_TIMEOUT_MS: u64 = 1500;
-
-/// Run a command with a timeout. Returns None if timeout exceeded or command fails.
-/// On Windows, hides the console window to prevent flashing.
-fn run_command_with_timeout(program: &str, args: &[&str], timeout_ms: u64) -> Option<Output> {
-    let mut cmd = Command::new(program);
-    cmd.args(args)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
-
-    // Hide console window on Windows
-    #[cfg(target_os = "windows")]
-    cmd.creation_flags(CREATE_NO_WINDOW);
-
-    let mut child = cmd.spawn().ok()?;
-
-    let timeout = Duration::from_millis(timeout_ms);
-    let start = std::time::Instant::now();
-
-    loop {
-        match child.try_wait() {
-            Ok(Some(status)) => {
-                // Process finished
-                let stdout = child.stdout.take().map(|mut s| {
-                    let mut buf = Vec::new();
-                    std::io::Read::read_to_end(&mut s, &mut buf).ok();
-                    buf
-                }).unwrap_or_default();
-
-                let stderr = child.stderr.take().map(|mut s| {
-                    let mut buf = Vec::new();
-                    std::io::Read::read_to_end(&mut s, &mut buf).ok();
-                    buf
-                }).unwrap_or_default();
-
-                return Some(Output { status, stdout, stderr });
-            }
-            Ok(None) => {
-                // Still running
-                if start.elapsed() > timeout {
-                    // Timeout - kill the process
-                    let _ = child.kill();
-                    let _ = child.wait(); // Reap the zombie
-                    log::warn!("{} command timed out after {}ms", program, timeout_ms);
-                    return None;
-                }
-                // Sleep briefly before checking again
-                std::thread::sleep(Duration::from_millis(10));
-            }
-            Err(_) => return None,
-        }
+/// Names of the display adapters from Win32_VideoController (PowerShell)
+pub(super) fn video_controller_names() -> Option<Vec<String>> {
+    let output = run_command_with_timeout(
+        "powershell",
+        &["-NoProfile", "-Command", "(Get-CimInstance Win32_VideoController).Name"],
+        5000,
+    )?;
+    if !output.status.success() {
+        return None;
     }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect(),
+    )
 }
 
 /// Available GPU monitoring sources
@@ -156,17 +123,26 @@ impl<T: Clone> CachedValue<T> {
 /// - sysinfo for CPU load
 /// - nvidia-smi for NVIDIA GPU power
 /// - rocm-smi for AMD GPU power
+///
+/// NVML does not spawn anything, so it keeps working when the GPU command line
+/// tools are disabled; they are then only missing as a fallback. While a
+/// fullscreen app is focused the tools are paused the same way, and the last
+/// values they returned are kept (flagged stale) instead of being dropped.
 pub struct WmiMonitor {
+    /// Subprocess timeout and disabled probes
+    options: MonitorOptions,
     /// Detected GPU monitoring source
     gpu_source: GpuSource,
     /// NVML state for direct NVIDIA GPU access (if available)
-    nvml_state: Option<nvml_gpu::NvmlState>,
+    nvml_state: Option<nvml_gpu::NvmlHandle>,
     /// Sysinfo for CPU data
     sys: Mutex<sysinfo::System>,
     /// Cached TDP estimate for CPU (watts)
     cpu_tdp_estimate: f64,
     /// Whether this is a laptop (has battery)
     is_laptop: bool,
+    /// Discrete GPU added to the estimate when no GPU tool is available
+    gpu_estimate: Option<GpuEstimate>,
     /// Cached GPU power reading (used for CLI fallback; NVML is fast enough to skip cache)
     gpu_cache: Mutex<Option<CachedValue<Option<GpuInfo>>>>,
     /// Cached GPU metrics (full metrics)
@@ -179,6 +155,10 @@ pub struct WmiMonitor {
     fan_cache: Mutex<Option<CachedValue<Option<FanMetrics>>>>,
     /// Cached memory info: (speed_mhz, type_string) - permanent cache, RAM never changes at runtime
     memory_info_cache: Mutex<Option<(Option<u64>, Option<String>)>>,
+    /// GPU command line tools paused by the last detailed collection (fullscreen app focused, or no GPU widget shown)
+    gpu_cli_paused: AtomicBool,
+    /// The last GPU metrics came from the cache while paused
+    gpu_stale: AtomicBool,
     /// PDH query handle for thermal zone temperature (lazily initialized, reused)
     #[cfg(target_os = "windows")]
     pdh_thermal_query: Mutex<Option<PdhThermalQuery>>,
@@ -186,7 +166,7 @@ pub struct WmiMonitor {
 
 impl WmiMonitor {
     /// Create a new power monitor
-    pub fn new() -> Result<Self> {
+    pub fn new(options: &MonitorOptions) -> Result<Self> {
         // Initialize sysinfo
         let mut sys = sysinfo::System::new();
 
@@ -207,13 +187,13 @@ impl WmiMonitor {
         );
 
         // Try NVML first for NVIDIA GPU (fast, direct API)
-        let nvml_state = nvml_gpu::init_nvml();
+        let nvml_state = nvml_gpu::NvmlHandle::init();
         let gpu_source = if nvml_state.is_some() {
             log::info!("Using NVML for NVIDIA GPU monitoring (direct API)");
             GpuSource::NvmlNvidia
         } else {
             // Fallback to CLI-based detection
-            let source = Self::detect_gpu_source();
+            let source = Self::detect_gpu_source(options);
             log::info!("GPU monitoring source: {:?}", source);
             source
         };
@@ -223,27 +203,36 @@ impl WmiMonitor {
         let cpu_tdp_estimate = Self::estimate_cpu_tdp(cpu_count);
 
         // Check if this is a laptop
-        let is_laptop = Self::check_is_laptop();
+        let is_laptop = Self::check_is_laptop(options);
 
         Ok(Self {
+            options: options.clone(),
             gpu_source,
             nvml_state,
             sys: Mutex::new(sys),
             cpu_tdp_estimate,
             is_laptop,
+            gpu_estimate: None,
             gpu_cache: Mutex::new(None),
             gpu_metrics_cache: Mutex::new(None),
             cpu_temp_cache: Mutex::new(None),
             gpu_process_cache: Mutex::new(None),
             fan_cache: Mutex::new(None),
             memory_info_cache: Mutex::new(None),
+            gpu_cli_paused: AtomicBool::new(false),
+            gpu_stale: AtomicBool::new(false),
             #[cfg(target_os = "windows")]
             pdh_thermal_query: Mutex::new(None),
         })
     }
 
     /// Detect available GPU monitoring tool
-    fn detect_gpu_source() -> GpuSource {
+    fn detect_gpu_source(options: &MonitorOptions) -> GpuSource {
+        if options.disable_gpu_cli {
+            log::info!("GPU command line tools disabled in settings");
+            return GpuSource::None;
+        }
+
         // Helper to create a command with hidden console window on Windows
         fn create_hidden_command(program: &str) -> Command {
             let mut cmd = Command::new(program);
@@ -297,7 +286,11 @@ impl WmiMonitor {
     }
 
     /// Check if running on a laptop (check for battery via PowerShell)
-    fn check_is_laptop() -> bool {
+    fn check_is_laptop(options: &MonitorOptions) -> bool {
+        if options.disable_powershell_probes {
+            return false;
+        }
+
         // Use PowerShell to check for battery (with hidden console window)
         let mut cmd = Command::new("powershell");
         cmd.args(["-Command", "(Get-WmiObject Win32_Battery).EstimatedChargeRemaining"]);
@@ -315,6 +308,36 @@ impl WmiMonitor {
         false
     }
 
+    /// Whether the GPU command line tools are paused for a fullscreen app
+    fn gpu_cli_paused(&self) -> bool {
+        self.gpu_cli_paused.load(Ordering::Relaxed)
+    }
+
+    /// Cache lifetime of GPU command line results, unlimited while paused
+    fn gpu_cache_ttl(&self, ttl_ms: u64) -> u64 {
+        if self.gpu_cli_paused() {
+            u64::MAX
+        } else {
+            ttl_ms
+        }
+    }
+
+    /// Run a GPU vendor tool (nvidia-smi, rocm-smi, amd-smi), None without spawning when they are disabled or paused
+    fn run_gpu_cli(&self, program: &str, args: &[&str]) -> Option<Output> {
+        if self.options.disable_gpu_cli || self.gpu_cli_paused() {
+            return None;
+        }
+        run_command_with_timeout(program, args, self.options.gpu_command_timeout_ms)
+    }
+
+    /// Run a PowerShell probe, None without spawning when they are disabled
+    fn run_powershell(&self, args: &[&str], timeout_ms: u64) -> Option<Output> {
+        if self.options.disable_powershell_probes {
+            return None;
+        }
+        run_command_with_timeout("powershell", args, timeout_ms)
+    }
+
     /// Get CPU info from sysinfo
     fn get_cpu_info(&self) -> CpuInfo {
         let mut sys = self.sys.lock().unwrap();
@@ -332,10 +355,9 @@ impl WmiMonitor {
 
     /// Get GPU power via nvidia-smi (with timeout)
     fn get_nvidia_gpu_power(&self) -> Option<GpuInfo> {
-        let output = run_command_with_timeout(
+        let output = self.run_gpu_cli(
             "nvidia-smi",
             &["--query-gpu=power.draw,name", "--format=csv,noheader,nounits"],
-            GPU_COMMAND_TIMEOUT_MS,
         )?;
 
         if !output.status.success() {
@@ -362,7 +384,7 @@ impl WmiMonitor {
     /// Get GPU power via rocm-smi (AMD) - with timeout
     fn get_amd_gpu_power(&self) -> Option<GpuInfo> {
         // Try rocm-smi first
-        if let Some(output) = run_command_with_timeout("rocm-smi", &["--showpower", "--json"], GPU_COMMAND_TIMEOUT_MS) {
+        if let Some(output) = self.run_gpu_cli("rocm-smi", &["--showpower", "--json"]) {
             if output.status.success() {
                 let stdout = String::from_utf8_lossy(&output.stdout);
                 if let Some(power) = Self::parse_rocm_smi_power(&stdout) {
@@ -375,7 +397,7 @@ impl WmiMonitor {
         }
 
         // Try amd-smi as fallback
-        if let Some(output) = run_command_with_timeout("amd-smi", &["metric", "-p", "--json"], GPU_COMMAND_TIMEOUT_MS) {
+        if let Some(output) = self.run_gpu_cli("amd-smi", &["metric", "-p", "--json"]) {
             if output.status.success() {
                 let stdout = String::from_utf8_lossy(&output.stdout);
                 if let Some(power) = Self::parse_amd_smi_power(&stdout) {
@@ -388,7 +410,7 @@ impl WmiMonitor {
         }
 
         // Try simple text output
-        if let Some(output) = run_command_with_timeout("rocm-smi", &["--showpower"], GPU_COMMAND_TIMEOUT_MS) {
+        if let Some(output) = self.run_gpu_cli("rocm-smi", &["--showpower"]) {
             if output.status.success() {
                 let stdout = String::from_utf8_lossy(&output.stdout);
                 for line in stdout.lines() {
@@ -458,22 +480,25 @@ impl WmiMonitor {
         // NVML fast path — no cache needed
         if self.gpu_source == GpuSource::NvmlNvidia {
             if let Some(ref nvml) = self.nvml_state {
-                if let Some((power, name)) = nvml_gpu::query_gpu_power(nvml) {
+                if let Some((power, name)) = nvml.gpu_power() {
                     return Some(GpuInfo { power_watts: power, name });
                 }
             }
-            // NVML query failed, fall through to CLI
+            // NVML query failed or NVML is being reinitialized, fall through to CLI
         }
 
         // Check cache first (2000ms TTL - GPU commands are slow)
         {
             let cache = self.gpu_cache.lock().unwrap();
             if let Some(ref cached) = *cache {
-                if let Some(value) = cached.get(2000) {
+                if let Some(value) = cached.get(self.gpu_cache_ttl(2000)) {
                     return value;
                 }
             }
         }
+        if self.gpu_cli_paused() {
+            return None;
+        }
 
         // Cache miss - fetch fresh data via CLI
         let result = match self.gpu_source {
@@ -502,6 +527,14 @@ impl WmiMonitor {
         idle_power + (load_factor * active_power)
     }
 
+    /// Configured GPU estimate at `cpu_load_percent`, only without a GPU tool
+    fn estimated_gpu_power(&self, cpu_load_percent: f64) -> Option<f64> {
+        if self.gpu_source != GpuSource::None {
+            return None;
+        }
+        self.gpu_estimate.map(|gpu| gpu.power_watts(cpu_load_percent))
+    }
+
     /// Estimate system base power (motherboard, RAM, storage, etc.)
     fn estimate_base_power(&self) -> f64 {
         if self.is_laptop {
@@ -524,6 +557,7 @@ impl WmiMonitor {
         if let Some(gpu_info) = self.get_gpu_power() {
             total_power += gpu_info.power_watts;
         }
+        total_power += self.estimated_gpu_power(cpu_info.average_load).unwrap_or(0.0);
 
         // Add base system power
         total_power += self.estimate_base_power();
@@ -549,6 +583,7 @@ impl WmiMonitor {
         if let Some(power) = gpu_power_watts {
             total_power += power;
         }
+        total_power += self.estimated_gpu_power(cpu_info.average_load).unwrap_or(0.0);
 
         // Add base system power
         total_power += self.estimate_base_power();
@@ -559,14 +594,15 @@ impl WmiMonitor {
     /// Get cached GPU data with extended staleness tolerance for fast path (10s)
     /// This NEVER triggers a GPU command - it only reads from cache
     fn get_cached_gpu_data_for_fast_path(&self) -> (Option<f64>, Option<f64>) {
-        // Extended staleness tolerance for fast path: 10 seconds
+        // Extended staleness tolerance for fast path: 10 seconds (the last values while paused)
         const FAST_PATH_CACHE_TTL_MS: u64 = 10000;
+        let ttl = self.gpu_cache_ttl(FAST_PATH_CACHE_TTL_MS);
 
         // Check GPU metrics cache for usage
         let gpu_usage = {
             let cache = self.gpu_metrics_cache.lock().unwrap();
             if let Some(ref cached) = *cache {
-                if let Some(metrics) = cached.get(FAST_PATH_CACHE_TTL_MS) {
+                if let Some(metrics) = cached.get(ttl) {
                     metrics.and_then(|m| m.usage_percent)
                 } else {
                     None
@@ -580,7 +616,7 @@ impl WmiMonitor {
         let gpu_power = {
             let cache = self.gpu_cache.lock().unwrap();
             if let Some(ref cached) = *cache {
-                if let Some(info) = cached.get(FAST_PATH_CACHE_TTL_MS) {
+                if let Some(info) = cached.get(ttl) {
                     info.map(|i| i.power_watts)
                 } else {
                     None
@@ -596,19 +632,39 @@ impl WmiMonitor {
     /// Collect all detailed metrics in one blocking call
     /// This consolidates all slow operations: GPU commands, temps, processes
     /// Should be called from a background task, not the main monitoring loop
-    /// When `extended` is true, also collects per-core frequencies and fan speeds
-    pub fn collect_detailed_metrics_impl(&self, limit: usize, pinned: &[String], extended: bool) -> Result<DetailedMetrics> {
+    /// `options` selects the optional collectors (extended metrics, processes, GPU)
+    pub fn collect_detailed_metrics_impl(&self, limit: usize, pinned: &[String], ignored: &[String], options: DetailedOptions) -> Result<DetailedMetrics> {
+        // Applies to every GPU query until the next collection. Without a
+        // visible GPU widget only the tools are skipped, NVML and the last values stay
+        self.gpu_cli_paused.store(options.pause_gpu_cli || !options.gpu, Ordering::Relaxed);
+
         // Get full system metrics (this will refresh GPU cache via nvidia-smi)
-        let system_metrics = self.get_system_metrics_impl(extended).ok();
+        let system_metrics = self.get_system_metrics_impl(options).ok();
 
         // Get top processes (uses sysinfo which is relatively fast)
-        let top_processes = self.get_top_processes_with_pinned(limit, pinned).unwrap_or_default();
+        let top_processes = if options.processes {
+            self.get_top_processes_with_pinned(limit, pinned, ignored).unwrap_or_default()
+        } else {
+            // Only our own process, for the self power estimate
+            let pid = sysinfo::Pid::from_u32(std::process::id());
+            self.sys.lock().unwrap().refresh_process_specifics(pid, ProcessRefreshKind::new().with_cpu());
+            Vec::new()
+        };
+        // Processes were just refreshed above
+        let app_self_cpu_percent = attribution::self_cpu_percent(&self.sys.lock().unwrap());
 
+        let now = chrono::Utc::now();
         Ok(DetailedMetrics {
             system_metrics,
+            process_totals: ProcessTotals::of(&top_processes),
             top_processes,
-            timestamp: chrono::Utc::now().timestamp(),
-            extended_collected: extended,
+            timestamp: now.timestamp(),
+            timestamp_ms: now.timestamp_millis(),
+            extended_collected: options.extended,
+            measured_energy_joules: None,
+            app_self_cpu_percent,
+            app_self_power_watts: None,
+            gpu_stale: options.gpu && self.gpu_stale.load(Ordering::Relaxed),
         })
     }
 
@@ -634,6 +690,10 @@ impl WmiMonitor {
             total_power += gpu_info.power_watts;
             has_real_reading = true;
         }
+        if let Some(gpu_power) = self.estimated_gpu_power(cpu_info.average_load) {
+            components.insert(GPU_ESTIMATED_COMPONENT.to_string(), gpu_power);
+            total_power += gpu_power;
+        }
 
         // Base system power
         let base_power = self.estimate_base_power();
@@ -663,14 +723,18 @@ impl PowerSource for WmiMonitor {
         self.get_power_watts_fast_impl()
     }
 
-    fn collect_detailed_metrics(&self, limit: usize, pinned: &[String], extended: bool) -> Result<DetailedMetrics> {
-        self.collect_detailed_metrics_impl(limit, pinned, extended)
+    fn collect_detailed_metrics(&self, limit: usize, pinned: &[String], ignored: &[String], options: DetailedOptions) -> Result<DetailedMetrics> {
+        self.collect_detailed_metrics_impl(limit, pinned, ignored, options)
     }
 
     fn get_reading(&self) -> Result<PowerReading> {
         self.get_reading()
     }
 
+    fn set_gpu_estimate(&mut self, estimate: Option<GpuEstimate>) {
+        self.gpu_estimate = estimate;
+    }
+
     fn name(&self) -> &str {
         match self.gpu_source {
             GpuSource::NvmlNvidia => "Windows Monitor + NVIDIA (NVML)",
@@ -684,6 +748,10 @@ impl PowerSource for WmiMonitor {
         self.gpu_source == GpuSource::None
     }
 
+    fn has_battery(&self) -> bool {
+        self.is_laptop
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -782,11 +850,12 @@ impl WmiMonitor {
 impl WmiMonitor {
     /// Get comprehensive system metrics including CPU, GPU, and memory
     pub fn get_system_metrics(&self) -> Result<SystemMetrics> {
-        self.get_system_metrics_impl(false)
+        self.get_system_metrics_impl(DetailedOptions::default())
     }
 
-    /// Get system metrics with optional extended collection (per-core freq, fans)
-    fn get_system_metrics_impl(&self, extended: bool) -> Result<SystemMetrics> {
+    /// Get system metrics with optional extended collection (per-core freq, fans) and GPU
+    fn get_system_metrics_impl(&self, options: DetailedOptions) -> Result<SystemMetrics> {
+        let extended = options.extended;
         let mut sys = self.sys.lock().unwrap();
         // NOTE: Do NOT refresh CPU here - it interferes with critical loop baseline.
         // CPU values are already refreshed by get_cpu_info() in the critical loop.
@@ -837,7 +906,8 @@ impl WmiMonitor {
             per_core_temperature: None, // Per-core temps not available on Windows without OHM/LHM
         };
 
-        // GPU metrics (fan speed and mem clock come free from nvidia-smi query)
+        // GPU metrics (fan speed and mem clock come free from nvidia-smi query),
+        // the cached ones while the tools are skipped
         let gpu = self.get_gpu_metrics();
 
         // System fan speeds - only when extended (WMI call is slow)
@@ -878,11 +948,11 @@ impl WmiMonitor {
 
     /// Get top N processes by CPU usage with optional pinned processes
     pub fn get_top_processes(&self, limit: usize) -> Result<Vec<ProcessMetrics>> {
-        self.get_top_processes_with_pinned(limit, &[])
+        self.get_top_processes_with_pinned(limit, &[], &[])
     }
 
-    /// Get top N processes by CPU usage, including pinned processes
-    pub fn get_top_processes_with_pinned(&self, limit: usize, pinned_names: &[String]) -> Result<Vec<ProcessMetrics>> {
+    /// Get top N processes by CPU usage, including pinned processes and leaving out ignored ones
+    pub fn get_top_processes_with_pinned(&self, limit: usize, pinned_names: &[String], ignored: &[String]) -> Result<Vec<ProcessMetrics>> {
         let mut sys = self.sys.lock().unwrap();
         // Must use refresh_processes_specifics with cpu AND memory flags
         // CPU flag is required for per-process CPU usage calculation
@@ -918,6 +988,9 @@ impl WmiMonitor {
                     memory_percent: (memory_bytes as f64 / total_memory as f64) * 100.0,
                     gpu_percent,
                     is_pinned,
+                    is_ignored: false,
+                    is_aggregate: false,
+                    container: None,
                 }
             })
             .collect();
@@ -925,7 +998,7 @@ impl WmiMonitor {
         // Second pass: aggregate processes by name to avoid duplicates
         let mut aggregated: HashMap<String, ProcessMetrics> = HashMap::new();
         for proc in raw_processes {
-            let entry = aggregated.entry(proc.name.clone()).or_insert(ProcessMetrics {
+            let entry = aggregated.entry(attribution::process_group_key(proc.pid, &proc.name)).or_insert(ProcessMetrics {
                 pid: proc.pid, // Keep first PID encountered
                 name: proc.name.clone(),
                 cpu_percent: 0.0,
@@ -933,6 +1006,9 @@ impl WmiMonitor {
                 memory_percent: 0.0,
                 gpu_percent: None,
                 is_pinned: proc.is_pinned,
+                is_ignored: false,
+                is_aggregate: false,
+                container: None,
             });
             entry.cpu_percent += proc.cpu_percent;
             entry.memory_bytes += proc.memory_bytes;
@@ -947,8 +1023,10 @@ impl WmiMonitor {
             }
         }
 
-        // Clamp aggregated percentages to 100% max
+        // Clamp aggregated percentages to 100% max, and drop ignored processes
+        // before the limit so the next ones show up (pinned ones are kept)
         let processes: Vec<ProcessMetrics> = aggregated.into_values()
+            .filter(|p| p.is_pinned || !process_filter::is_ignored(ignored, &p.name))
             .map(|mut p| {
                 p.cpu_percent = p.cpu_percent.min(100.0);
                 p.memory_percent = p.memory_percent.min(100.0);
@@ -976,11 +1054,11 @@ impl WmiMonitor {
         others.sort_by(|a, b| usage_score(b).partial_cmp(&usage_score(a)).unwrap_or(std::cmp::Ordering::Equal));
 
         // Take top N from others, then prepend pinned
-        let remaining_slots = limit.saturating_sub(pinned.len());
-        others.truncate(remaining_slots);
+        let len = limit.max(pinned.len());
 
         // Combine: pinned first, then top others
         pinned.extend(others);
+        process_filter::truncate_with_other(&mut pinned, len);
 
         Ok(pinned)
     }
@@ -1022,6 +1100,9 @@ impl WmiMonitor {
                     memory_percent: (memory_bytes as f64 / total_memory as f64) * 100.0,
                     gpu_percent,
                     is_pinned: false,
+                    is_ignored: false,
+                    is_aggregate: false,
+                    container: None,
                 }
             })
             .collect();
@@ -1029,7 +1110,7 @@ impl WmiMonitor {
         // Second pass: aggregate processes by name to avoid duplicates
         let mut aggregated: HashMap<String, ProcessMetrics> = HashMap::new();
         for proc in raw_processes {
-            let entry = aggregated.entry(proc.name.clone()).or_insert(ProcessMetrics {
+            let entry = aggregated.entry(attribution::process_group_key(proc.pid, &proc.name)).or_insert(ProcessMetrics {
                 pid: proc.pid,
                 name: proc.name.clone(),
                 cpu_percent: 0.0,
@@ -1037,6 +1118,9 @@ impl WmiMonitor {
                 memory_percent: 0.0,
                 gpu_percent: None,
                 is_pinned: false,
+                is_ignored: false,
+                is_aggregate: false,
+                container: None,
             });
             entry.cpu_percent += proc.cpu_percent;
             entry.memory_bytes += proc.memory_bytes;
@@ -1116,6 +1200,7 @@ impl WmiMonitor {
             return Some(temp);
         }
 
+        crate::core::log_throttle::warn("No CPU temperature source responded (PDH, OHM, LHM, MSAcpi)");
         None
     }
 
@@ -1204,10 +1289,9 @@ impl WmiMonitor {
 
     /// Fetch CPU temperature via Open Hardware Monitor WMI namespace (with timeout)
     fn fetch_cpu_temperature_ohm(&self) -> Option<f64> {
-        let output = run_command_with_timeout(
-            "powershell",
+        let output = self.run_powershell(
             &["-Command", "Get-WmiObject Sensor -Namespace root/OpenHardwareMonitor 2>$null | Where-Object { $_.SensorType -eq 'Temperature' -and $_.Name -like '*CPU*' } | Select-Object -First 1 -ExpandProperty Value"],
-            GPU_COMMAND_TIMEOUT_MS,
+            self.options.gpu_command_timeout_ms,
         )?;
 
         if output.status.success() {
@@ -1224,10 +1308,9 @@ impl WmiMonitor {
 
     /// Fetch CPU temperature via LibreHardwareMonitor WMI namespace (with timeout)
     fn fetch_cpu_temperature_lhm(&self) -> Option<f64> {
-        let output = run_command_with_timeout(
-            "powershell",
+        let output = self.run_powershell(
             &["-Command", "Get-WmiObject Sensor -Namespace root/LibreHardwareMonitor 2>$null | Where-Object { $_.SensorType -eq 'Temperature' -and $_.Name -like '*CPU*' } | Select-Object -First 1 -ExpandProperty Value"],
-            GPU_COMMAND_TIMEOUT_MS,
+            self.options.gpu_command_timeout_ms,
         )?;
 
         if output.status.success() {
@@ -1244,10 +1327,9 @@ impl WmiMonitor {
 
     /// Fetch CPU temperature via MSAcpi_ThermalZoneTemperature WMI (fallback, requires admin)
     fn fetch_cpu_temperature_msacpi(&self) -> Option<f64> {
-        let output = run_command_with_timeout(
-            "powershell",
+        let output = self.run_powershell(
             &["-Command", "Get-WmiObject MSAcpi_ThermalZoneTemperature -Namespace root/wmi 2>$null | Select-Object -First 1 -ExpandProperty CurrentTemperature"],
-            GPU_COMMAND_TIMEOUT_MS,
+            self.options.gpu_command_timeout_ms,
         )?;
 
         if output.status.success() {
@@ -1268,6 +1350,23 @@ impl WmiMonitor {
     /// NVML: cached for 500ms (fast API). CLI: cached for 2000ms (slow subprocess).
     fn get_gpu_metrics(&self) -> Option<GpuMetrics> {
         let cache_ttl = if self.gpu_source == GpuSource::NvmlNvidia { 500 } else { 2000 };
+        self.gpu_stale.store(false, Ordering::Relaxed);
+
+        // Paused: NVML still answers, otherwise keep the last values
+        if self.gpu_cli_paused() {
+            let fresh = match self.gpu_source {
+                GpuSource::NvmlNvidia => self.nvml_state.as_ref().and_then(nvml_gpu::NvmlHandle::gpu_metrics),
+                _ => None,
+            };
+            let mut cache = self.gpu_metrics_cache.lock().unwrap();
+            if fresh.is_some() {
+                *cache = Some(CachedValue::new(fresh.clone()));
+                return fresh;
+            }
+            let last = cache.as_ref().and_then(|cached| cached.get(u64::MAX)).flatten();
+            self.gpu_stale.store(last.is_some(), Ordering::Relaxed);
+            return last;
+        }
 
         // Check cache first
         {
@@ -1284,7 +1383,7 @@ impl WmiMonitor {
             GpuSource::NvmlNvidia => {
                 // Try NVML first
                 self.nvml_state.as_ref()
-                    .and_then(nvml_gpu::query_gpu_metrics)
+                    .and_then(nvml_gpu::NvmlHandle::gpu_metrics)
                     .or_else(|| self.get_nvidia_gpu_metrics()) // CLI fallback
             }
             GpuSource::Nvidia => self.get_nvidia_gpu_metrics(),
@@ -1304,10 +1403,9 @@ impl WmiMonitor {
     /// Get NVIDIA GPU metrics via nvidia-smi (with timeout)
     /// Queries clocks.mem and fan.speed in the same call (zero extra process spawns)
     fn get_nvidia_gpu_metrics(&self) -> Option<GpuMetrics> {
-        let output = run_command_with_timeout(
+        let output = self.run_gpu_cli(
             "nvidia-smi",
             &["--query-gpu=name,utilization.gpu,power.draw,temperature.gpu,memory.used,memory.total,clocks.gr,clocks.mem,fan.speed", "--format=csv,noheader,nounits"],
-            GPU_COMMAND_TIMEOUT_MS,
         )?;
 
         if !output.status.success() {
@@ -1344,10 +1442,9 @@ impl WmiMonitor {
     /// Get AMD GPU metrics via rocm-smi or amd-smi (with timeout)
     fn get_amd_gpu_metrics(&self) -> Option<GpuMetrics> {
         // Try rocm-smi first
-        if let Some(output) = run_command_with_timeout(
+        if let Some(output) = self.run_gpu_cli(
             "rocm-smi",
             &["--showuse", "--showpower", "--showtemp", "--showmemuse", "--json"],
-            GPU_COMMAND_TIMEOUT_MS,
         ) {
             if output.status.success() {
                 let stdout = String::from_utf8_lossy(&output.stdout);
@@ -1358,7 +1455,7 @@ impl WmiMonitor {
         }
 
         // Try amd-smi as fallback
-        if let Some(output) = run_command_with_timeout("amd-smi", &["metric", "--json"], GPU_COMMAND_TIMEOUT_MS) {
+        if let Some(output) = self.run_gpu_cli("amd-smi", &["metric", "--json"]) {
             if output.status.success() {
                 let stdout = String::from_utf8_lossy(&output.stdout);
                 if let Some(metrics) = self.parse_amd_smi_metrics(&stdout) {
@@ -1460,10 +1557,9 @@ impl WmiMonitor {
 
     /// Fetch memory speed and type from WMI Win32_PhysicalMemory (slow - calls PowerShell, one-time only)
     fn fetch_memory_info(&self) -> (Option<u64>, Option<String>) {
-        let output = run_command_with_timeout(
-            "powershell",
+        let output = self.run_powershell(
             &["-Command", "Get-WmiObject Win32_PhysicalMemory | Select-Object -First 1 Speed, SMBIOSMemoryType | ForEach-Object { \"$($_.Speed)|$($_.SMBIOSMemoryType)\" }"],
-            GPU_COMMAND_TIMEOUT_MS,
+            self.options.gpu_command_timeout_ms,
         );
 
         if let Some(output) = output {
@@ -1520,8 +1616,7 @@ impl WmiMonitor {
 
     /// Fetch system fan speeds via WMI Win32_Fan (slow - calls PowerShell)
     fn fetch_system_fans(&self) -> Option<FanMetrics> {
-        let output = run_command_with_timeout(
-            "powershell",
+        let output = self.run_powershell(
             &["-Command", "Get-WmiObject Win32_Fan 2>$null | Select-Object Name,DesiredSpeed,ActiveCooling | ConvertTo-Json -Compress"],
             2000,
         )?;
@@ -1574,6 +1669,20 @@ impl WmiMonitor {
     fn get_gpu_process_usage(&self) -> HashMap<u32, f64> {
         let cache_ttl = if self.gpu_source == GpuSource::NvmlNvidia { 500 } else { 2000 };
 
+        // Paused: NVML still answers, otherwise keep the last values
+        if self.gpu_cli_paused() {
+            let fresh = match self.gpu_source {
+                GpuSource::NvmlNvidia => self.nvml_state.as_ref().and_then(nvml_gpu::NvmlHandle::gpu_processes),
+                _ => None,
+            };
+            let mut cache = self.gpu_process_cache.lock().unwrap();
+            if let Some(fresh) = fresh {
+                *cache = Some(CachedValue::new(fresh.clone()));
+                return fresh;
+            }
+            return cache.as_ref().and_then(|cached| cached.get(u64::MAX)).unwrap_or_default();
+        }
+
         // Check cache first
         {
             let cache = self.gpu_process_cache.lock().unwrap();
@@ -1589,7 +1698,7 @@ impl WmiMonitor {
             GpuSource::NvmlNvidia => {
                 // Try NVML first, fall back to CLI pmon
                 self.nvml_state.as_ref()
-                    .map(nvml_gpu::query_gpu_processes)
+                    .and_then(nvml_gpu::NvmlHandle::gpu_processes)
                     .unwrap_or_else(|| self.fetch_nvidia_gpu_processes())
             }
             GpuSource::Nvidia => self.fetch_nvidia_gpu_processes(),
@@ -1618,7 +1727,7 @@ impl WmiMonitor {
 
         // Use nvidia-smi pmon for per-process GPU utilization (with timeout)
         // -c 1 means capture one sample
-        let output = match run_command_with_timeout("nvidia-smi", &["pmon", "-c", "1", "-s", "u"], GPU_COMMAND_TIMEOUT_MS) {
+        let output = match self.run_gpu_cli("nvidia-smi", &["pmon", "-c", "1", "-s", "u"]) {
             Some(o) => o,
             None => return result,
         };
@@ -1662,7 +1771,7 @@ impl WmiMonitor {
         let mut result = HashMap::new();
 
         // Try amd-smi process --json
-        if let Some(output) = run_command_with_timeout("amd-smi", &["process", "--json"], GPU_COMMAND_TIMEOUT_MS) {
+        if let Some(output) = self.run_gpu_cli("amd-smi", &["process", "--json"]) {
             if output.status.success() {
                 let stdout = String::from_utf8_lossy(&output.stdout);
                 if let Ok(value) = serde_json::from_str::<serde_json::Value>(&stdout) {
@@ -1688,7 +1797,7 @@ impl WmiMonitor {
 
         // Fallback: try rocm-smi --showpidgpus
         if result.is_empty() {
-            if let Some(output) = run_command_with_timeout("rocm-smi", &["--showpidgpus"], GPU_COMMAND_TIMEOUT_MS) {
+            if let Some(output) = self.run_gpu_cli("rocm-smi", &["--showpidgpus"]) {
                 if output.status.success() {
                     let stdout = String::from_utf8_lossy(&output.stdout);
                     // Parse text output - format varies by rocm version
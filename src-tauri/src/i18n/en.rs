@@ -25,6 +25,7 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("dashboard.session_duration".into(), "Session Duration".into());
     t.insert("dashboard.power_source".into(), "Power Source".into());
     t.insert("dashboard.estimated".into(), "Estimated".into());
+    t.insert("dashboard.stale".into(), "Stale data".into());
     t.insert("dashboard.measured".into(), "Measured".into());
 
     // Units
@@ -47,13 +48,18 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("settings.refresh_rate".into(), "Refresh Rate".into());
     t.insert("settings.eco_mode".into(), "Eco Mode".into());
     t.insert("settings.eco_mode.description".into(), "Reduce refresh rate when minimized".into());
+    t.insert("settings.show_taskbar_badge".into(), "Cost on taskbar icon".into());
+    t.insert("settings.show_taskbar_badge_desc".into(), "Show today's cost as a badge, updated every minute".into());
     t.insert("settings.start_minimized".into(), "Start Minimized".into());
     t.insert("settings.start_with_system".into(), "Start with System".into());
     t.insert("settings.remember_window_position".into(), "Remember window position and size".into());
+    t.insert("settings.vram_alert_percent".into(), "VRAM usage alert (%)".into());
+    t.insert("settings.vram_alert_percent_desc".into(), "Notify when GPU memory stays above this level for a minute (0 = off)".into());
     t.insert("settings.run_as_admin".into(), "Run as Administrator".into());
     t.insert("settings.run_as_admin.tooltip".into(), "Enables killing protected/system processes. Requires UAC confirmation on startup.".into());
     t.insert("settings.run_as_admin.elevated".into(), "Elevated".into());
     t.insert("settings.run_as_admin.not_elevated".into(), "Not elevated".into());
+    t.insert("settings.run_as_admin.denied".into(), "Elevation denied, will ask again in 24h".into());
 
     // Settings - Pricing
     t.insert("settings.pricing".into(), "Pricing".into());
@@ -63,6 +69,15 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("settings.pricing.mode.seasonal".into(), "Seasonal".into());
     t.insert("settings.pricing.mode.tempo".into(), "Tempo (EDF-style)".into());
     t.insert("settings.pricing.currency".into(), "Currency".into());
+    t.insert("settings.pricing.preset".into(), "Country preset".into());
+    t.insert("settings.pricing.secondary_currency".into(), "Secondary currency".into());
+    t.insert("settings.pricing.secondary_currency_desc".into(), "Also show costs converted to this currency".into());
+    t.insert("settings.pricing.secondary_currency.none".into(), "None".into());
+    t.insert("settings.pricing.manual_rate".into(), "Exchange rate".into());
+    t.insert("settings.pricing.auto_fetch_rate".into(), "Fetch rate daily".into());
+    t.insert("settings.pricing.rate_url".into(), "Rate source URL".into());
+    t.insert("settings.pricing.preset.apply".into(), "Apply".into());
+    t.insert("settings.pricing.preset.applied".into(), "Preset applied".into());
     t.insert("settings.pricing.rate".into(), "Rate per kWh".into());
     t.insert("settings.pricing.peak_rate".into(), "Peak Rate".into());
     t.insert("settings.pricing.offpeak_rate".into(), "Off-peak Rate".into());
@@ -77,15 +92,21 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("settings.widget".into(), "Widget".into());
     t.insert("settings.widget.enabled".into(), "Enable Widget".into());
     t.insert("settings.widget.show_cost".into(), "Show Cost".into());
+    t.insert("settings.widget.remember_open_state".into(), "Remember if widget is open".into());
     t.insert("settings.widget.show_power".into(), "Show Power Only".into());
     t.insert("settings.widget.position".into(), "Position".into());
     t.insert("settings.widget.position.top_left".into(), "Top Left".into());
     t.insert("settings.widget.position.top_right".into(), "Top Right".into());
     t.insert("settings.widget.position.bottom_left".into(), "Bottom Left".into());
     t.insert("settings.widget.position.bottom_right".into(), "Bottom Right".into());
+    t.insert("settings.widget.session_mode".into(), "Show the active session".into());
+    t.insert("settings.widget.session_mode.auto".into(), "While a session runs".into());
+    t.insert("settings.widget.session_mode.always".into(), "Always".into());
+    t.insert("settings.widget.session_mode.never".into(), "Never".into());
     t.insert("settings.widget.opacity".into(), "Opacity".into());
     t.insert("settings.widget.open".into(), "Open Widget".into());
     t.insert("settings.widget.close".into(), "Close Widget".into());
+    t.insert("settings.widget.limited".into(), "Your desktop does not allow pinning or positioning the widget; it opens as a regular window".into());
 
     // Settings - Pricing Tempo
     t.insert("settings.pricing.tempo.blue".into(), "Blue Days".into());
@@ -128,7 +149,21 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("error.using_estimation".into(), "Using power estimation mode".into());
     t.insert("error.permission_denied".into(), "Permission denied".into());
     t.insert("error.save_failed".into(), "Failed to save settings".into());
+    t.insert("error.code.hardware_not_supported".into(), "This hardware is not supported".into());
+    t.insert("error.code.permission_denied".into(), "Permission denied, try running as administrator".into());
+    t.insert("error.code.db_error".into(), "Database error".into());
+    t.insert("error.code.config_invalid".into(), "Invalid settings".into());
+    t.insert("error.code.session_active".into(), "A session is already active".into());
+    t.insert("error.code.not_found".into(), "Not found".into());
+    t.insert("error.code.invalid_input".into(), "Invalid value".into());
+    t.insert("error.code.internal".into(), "Unexpected error".into());
     t.insert("warning.estimated_values".into(), "Power values are estimated (no direct sensor detected)".into());
+    t.insert("warning.persistence_disabled".into(), "History cannot be saved, the data folder is read-only. Live monitoring still works.".into());
+    t.insert("warning.persistence_retry".into(), "Retry".into());
+    t.insert("warning.persistence_restored".into(), "History saving is back on".into());
+    t.insert("onboarding.region_defaults".into(), "Pricing was set for your region ({region}): {rate}/kWh. Check it matches your contract.".into());
+    t.insert("onboarding.region_keep".into(), "Keep".into());
+    t.insert("onboarding.region_change".into(), "Change".into());
 
     // Actions
     t.insert("action.save".into(), "Save".into());
@@ -184,6 +219,31 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("session.ended".into(), "Session ended".into());
     t.insert("session.end_failed".into(), "Failed to end session".into());
     t.insert("session.surplus".into(), "surplus".into());
+    t.insert("session.avg_watts".into(), "Avg power".into());
+    t.insert("session.avg_surplus_watts".into(), "Avg surplus".into());
+    t.insert("session.cost_per_hour".into(), "Cost/h".into());
+    t.insert("receipt.untitled".into(), "Session".into());
+    t.insert("receipt.duration".into(), "Duration".into());
+    t.insert("receipt.total_energy".into(), "Total energy".into());
+    t.insert("receipt.surplus_energy".into(), "Surplus energy".into());
+    t.insert("receipt.surplus_cost".into(), "Surplus cost".into());
+
+    // Dashboard insights
+    t.insert("widget.insights".into(), "Insights".into());
+    t.insert("widget.no_insights".into(), "Not enough data yet".into());
+    t.insert("widget.pinned".into(), "Pinned processes".into());
+    t.insert("widget.no_pinned".into(), "Pin processes from the process list to follow them here".into());
+    t.insert("widget.not_running".into(), "Not running".into());
+    t.insert("insight.week_energy_up".into(), "Your PC used {percent}% more energy this week".into());
+    t.insert("insight.week_energy_down".into(), "Your PC used {percent}% less energy this week".into());
+    t.insert("insight.most_expensive_day".into(), "{day} was your most expensive day ({cost})".into());
+    t.insert("insight.category_gpu_share".into(), "GPU accounted for {percent}% of power during {category} sessions".into());
+    t.insert("insight.offpeak_savings".into(), "Off-peak usage saved you {cost} this month".into());
+
+    // Per-core CPU detail
+    t.insert("cpu_cores.title".into(), "CPU Cores".into());
+    t.insert("cpu_cores.core".into(), "Core".into());
+    t.insert("cpu_cores.empty".into(), "No per-core data yet".into());
 
     // Process list
     t.insert("processes.all".into(), "All Processes".into());
@@ -195,11 +255,20 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("processes.pinned".into(), "Pinned".into());
     t.insert("processes.unpinned".into(), "Unpinned".into());
     t.insert("processes.pin_failed".into(), "Failed to update pin".into());
+    t.insert("processes.ignore".into(), "Hide from the top list".into());
+    t.insert("processes.unignore".into(), "Show in the top list".into());
+    t.insert("processes.ignored".into(), "Hidden".into());
+    t.insert("processes.unignored".into(), "Shown again".into());
+    t.insert("processes.ignore_failed".into(), "Failed to update the ignore list".into());
     t.insert("processes.killed".into(), "Process killed".into());
     t.insert("processes.kill_failed".into(), "Failed to kill process".into());
-    t.insert("processes.kill_access_denied".into(), "Access denied — process requires elevated privileges".into());
-    t.insert("processes.kill_not_found".into(), "Process not found — it may have already exited".into());
+    t.insert("processes.kill_access_denied".into(), "Not allowed to kill {name}".into());
+    t.insert("processes.kill_not_found".into(), "{name} is not running, it may have already exited".into());
+    t.insert("processes.elevation_hint".into(), "run PowerCost Tracker as administrator to kill it".into());
+    t.insert("processes.pin_not_found".into(), "{name} is not running, only running processes can be pinned".into());
+    t.insert("processes.other_row".into(), "The Other row groups several processes, it cannot be pinned or killed".into());
     t.insert("processes.kill_confirm".into(), "Kill process".into());
+    t.insert("processes.other".into(), "Other".into());
 
     // Settings - Baseline detection
     t.insert("settings.baseline".into(), "Baseline Detection".into());
@@ -212,7 +281,66 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("settings.baseline.detect_failed".into(), "Failed to detect baseline".into());
     t.insert("settings.baseline.set_success".into(), "Baseline set to".into());
     t.insert("settings.baseline.set_failed".into(), "Failed to set baseline".into());
+    t.insert("settings.baseline.schedule".into(), "Day/night baselines".into());
+    t.insert("settings.baseline.schedule_desc".into(), "Keep a separate idle power for each part of the day".into());
+    t.insert("settings.baseline.bucket_day".into(), "Day from".into());
+    t.insert("settings.baseline.bucket_night".into(), "Night from".into());
+    t.insert("settings.baseline.bucket_auto".into(), "Auto".into());
     t.insert("settings.process_limit".into(), "Process List Limit".into());
+    t.insert("settings.process_grouping".into(), "Group processes by".into());
+    t.insert("settings.process_grouping_desc".into(), "One row per Docker, Podman or LXC container or systemd service (Linux), pinning a container pins its processes".into());
+    t.insert("settings.process_grouping.name".into(), "Process name".into());
+    t.insert("settings.process_grouping.container".into(), "Container".into());
+    t.insert("settings.gpu_command_timeout".into(), "GPU command timeout (ms)".into());
+    t.insert("settings.gpu_command_timeout_desc".into(), "Raise it if nvidia-smi is slow to answer when the GPU is asleep".into());
+    t.insert("settings.disable_gpu_cli".into(), "Disable GPU command line tools".into());
+    t.insert("settings.disable_gpu_cli_desc".into(), "Never run nvidia-smi, rocm-smi or amd-smi (NVIDIA GPUs are still read through NVML)".into());
+    t.insert("settings.pause_gpu_polling_in_game".into(), "Pause GPU tools in fullscreen games".into());
+    t.insert("settings.pause_gpu_polling_in_game_desc".into(), "Stop running nvidia-smi, rocm-smi or amd-smi while a fullscreen app is focused, keeping the last GPU values".into());
+    t.insert("settings.gpu_estimate".into(), "Estimated GPU power (W)".into());
+    t.insert("settings.gpu_estimate_desc".into(), "Idle and full-load power of a graphics card that cannot be read, added to estimated readings".into());
+    t.insert("settings.gpu_estimate_idle".into(), "Idle".into());
+    t.insert("settings.gpu_estimate_tdp".into(), "Max".into());
+    t.insert("settings.gpu_estimate_suggestion".into(), "{name} found: about {idle} W idle, {tdp} W at full load".into());
+    t.insert("settings.gpu_estimate_apply".into(), "Use".into());
+    t.insert("settings.accuracy_tracking".into(), "Track estimation accuracy".into());
+    t.insert("settings.accuracy_tracking_desc".into(), "While power is measured, compare it with the estimate once a minute".into());
+    t.insert("settings.accuracy_summary".into(), "{count} samples: average error {error} W, bias {bias} W, suggested factor {factor} (applied {applied})".into());
+    t.insert("settings.auto_apply_correction".into(), "Correct estimates automatically".into());
+    t.insert("settings.auto_apply_correction_desc".into(), "Scale estimated readings by the factor learned from the measurements (0.5 to 2)".into());
+    t.insert("monitoring.adjusted".into(), "Collecting detailed metrics takes {collection} s on this machine, they now refresh every {effective} s".into());
+    t.insert("settings.capabilities".into(), "Hardware support".into());
+    t.insert("settings.capabilities_desc".into(), "What this system reports, panels that would stay empty are hidden".into());
+    t.insert("settings.capabilities_all".into(), "Everything is available".into());
+    t.insert("settings.capabilities_refresh".into(), "Detect again".into());
+    t.insert("capabilities.real_power".into(), "Measured power".into());
+    t.insert("capabilities.gpu_metrics".into(), "GPU metrics".into());
+    t.insert("capabilities.gpu_per_process".into(), "GPU usage per process".into());
+    t.insert("capabilities.cpu_temperature".into(), "CPU temperature".into());
+    t.insert("capabilities.per_core_temperature".into(), "Per-core temperatures".into());
+    t.insert("capabilities.fans".into(), "Fans".into());
+    t.insert("capabilities.voltages".into(), "Voltages".into());
+    t.insert("capabilities.memory_info".into(), "Memory details".into());
+    t.insert("capabilities.hint.real_power.linux".into(), "estimated, RAPL needs read access to /sys/class/powercap (root or a udev rule)".into());
+    t.insert("capabilities.hint.real_power.windows".into(), "estimated from CPU load, a supported NVIDIA or AMD GPU adds its measured power".into());
+    t.insert("capabilities.hint.real_power.macos".into(), "estimated, powermetrics needs administrator rights".into());
+    t.insert("capabilities.hint.real_power".into(), "estimated from CPU load".into());
+    t.insert("capabilities.hint.cpu_temperature.windows".into(), "run LibreHardwareMonitor to read CPU sensors".into());
+    t.insert("capabilities.hint.cpu_temperature.linux".into(), "load the coretemp or k10temp module".into());
+    t.insert("capabilities.hint.cpu_temperature".into(), "no CPU sensor reported".into());
+    t.insert("capabilities.hint.gpu_metrics".into(), "no nvidia-smi, rocm-smi or NVML found".into());
+    t.insert("capabilities.hint.gpu_per_process".into(), "not reported by the GPU driver".into());
+    t.insert("capabilities.hint.per_core_temperature".into(), "the CPU only has one sensor".into());
+    t.insert("capabilities.hint.fans.linux".into(), "no hwmon fan sensor, install lm-sensors".into());
+    t.insert("capabilities.hint.fans".into(), "no fan sensor reported".into());
+    t.insert("capabilities.hint.voltages.windows".into(), "not available without LibreHardwareMonitor".into());
+    t.insert("capabilities.hint.voltages".into(), "no voltage sensor reported".into());
+    t.insert("capabilities.hint.memory_info".into(), "memory speed and type are not reported".into());
+    t.insert("capabilities.input_idle".into(), "Keyboard and mouse idle time".into());
+    t.insert("capabilities.hint.input_idle.linux".into(), "needs X11 with libXss, or GNOME on Wayland; idle sessions go by power only".into());
+    t.insert("capabilities.hint.input_idle".into(), "not readable, idle sessions go by power only".into());
+    t.insert("settings.disable_powershell_probes".into(), "Disable PowerShell probes".into());
+    t.insert("settings.disable_powershell_probes_desc".into(), "Never run PowerShell for temperatures, fans and memory details".into());
     t.insert("settings.refresh_rate_detailed".into(), "Refresh Rate (Detailed)".into());
     t.insert("settings.refresh_rate_critical".into(), "Refresh Rate (Critical)".into());
 
@@ -220,6 +348,9 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("history.daily_breakdown".into(), "Daily Breakdown".into());
     t.insert("history.date".into(), "Date".into());
     t.insert("history.energy".into(), "Energy".into());
+    t.insert("history.temperature".into(), "Temperature".into());
+    t.insert("history.estimated".into(), "estimated".into());
+    t.insert("history.estimated_desc".into(), "Most readings this day were estimated, not measured".into());
     t.insert("history.cost".into(), "Cost".into());
     t.insert("history.rate".into(), "Rate".into());
     t.insert("history.avg".into(), "Avg".into());
@@ -233,7 +364,9 @@ pub fn get_translations() -> HashMap<String, String> {
     // Tray menu
     t.insert("tray.show".into(), "Show".into());
     t.insert("tray.restart".into(), "Restart".into());
+    t.insert("tray.low_power".into(), "Low-power mode".into());
     t.insert("tray.exit".into(), "Exit".into());
+    t.insert("widget.window_title".into(), "PowerCost Widget".into());
 
     // Widget titles and labels
     t.insert("widget.cpu".into(), "CPU".into());
@@ -249,6 +382,8 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("widget.power".into(), "Power".into());
     t.insert("widget.usage".into(), "Usage".into());
     t.insert("widget.cost".into(), "Cost".into());
+    t.insert("widget.session_cost".into(), "Session cost".into());
+    t.insert("widget.session_time".into(), "Session time".into());
     t.insert("widget.baseline".into(), "Baseline".into());
     t.insert("widget.current".into(), "Current".into());
     t.insert("widget.set_baseline".into(), "Set Baseline".into());
@@ -282,6 +417,8 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("widget.processes_short".into(), "Procs".into());
     t.insert("widget.session_controls_short".into(), "Session".into());
     t.insert("widget.surplus_short".into(), "Surplus".into());
+    t.insert("widget.above_baseline".into(), "Above baseline".into());
+    t.insert("widget.above_idle".into(), "Above idle".into());
 
     // Estimation widget toggle labels
     t.insert("widget.show_cost".into(), "Cost".into());
@@ -303,12 +440,59 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("session.name_placeholder".into(), "Session name...".into());
     t.insert("session.no_category".into(), "No category".into());
     t.insert("session.category".into(), "Category".into());
+    t.insert("session.target".into(), "Target".into());
+    t.insert("session.target_met".into(), "Met".into());
+    t.insert("session.target_missed".into(), "Missed".into());
+    t.insert("session.target_warning".into(), "Session is nearing its target".into());
+    t.insert("session.target_reached".into(), "Session target reached".into());
+    t.insert("alert.vram_high".into(), "GPU memory usage is high".into());
+    t.insert("notification.session_target_body".into(), "{percent}% of the target used".into());
+    t.insert("notification.session_idle_body".into(), "{minutes} idle minutes left out".into());
+    t.insert("notification.session_duration_body".into(), "Planned duration reached".into());
+    t.insert("notification.vram_body".into(), "{percent}% used ({used} / {total} MB)".into());
+    t.insert("notification.test_title".into(), "Test notification".into());
+    t.insert("notification.kind.session_target".into(), "Session targets".into());
+    t.insert("notification.kind.session_auto_ended".into(), "Sessions ended automatically".into());
+    t.insert("notification.kind.vram_alert".into(), "GPU memory alerts".into());
+    t.insert("settings.notifications".into(), "Notifications".into());
+    t.insert("settings.quiet_hours".into(), "Quiet hours".into());
+    t.insert("settings.quiet_hours_desc".into(), "No alerts between these times (leave empty to disable)".into());
+    t.insert("settings.notifications.test".into(), "Test".into());
+    t.insert("session.auto_ended".into(), "Session ended after inactivity".into());
 
     // Settings - Categories
+    t.insert("session.category.gaming".into(), "Gaming".into());
+    t.insert("session.category.work".into(), "Work".into());
+    t.insert("session.category.ai".into(), "AI".into());
+    t.insert("session.category.browsing".into(), "Browsing".into());
+    t.insert("settings.use_emoji".into(), "Show emoji".into());
+    t.insert("settings.use_emoji_desc".into(), "Show emoji next to session categories".into());
+    t.insert("settings.track_active_window".into(), "Track active app".into());
+    t.insert("settings.track_active_window_desc".into(), "Record which apps are in the foreground during sessions (process names only)".into());
+    t.insert("settings.split_sessions_at_midnight".into(), "Split sessions at midnight".into());
+    t.insert("settings.split_sessions_at_midnight_desc".into(), "End sessions running past midnight and continue them in a new session, so each day gets its own cost".into());
+    t.insert("settings.session_end_hook".into(), "Session end script".into());
+    t.insert("settings.session_end_hook_desc".into(), "Absolute path of a program run when a session ends, with the session as JSON on its input".into());
+    t.insert("settings.session_end_hook_test".into(), "Test".into());
+    t.insert("settings.session_end_hook_ok".into(), "The script ran successfully".into());
+    t.insert("settings.session_end_hook_timeout".into(), "The script was stopped after 30 seconds".into());
+    t.insert("settings.session_end_hook_failed".into(), "The script failed".into());
+    t.insert("settings.session_idle_input".into(), "Input idle for auto-end".into());
+    t.insert("settings.session_idle_input_desc".into(), "How a minute without keyboard or mouse input combines with power near baseline when ending idle sessions".into());
+    t.insert("settings.session_idle_input.and".into(), "Power and input idle".into());
+    t.insert("settings.session_idle_input.or".into(), "Power or input idle".into());
+    t.insert("settings.session_idle_input.off".into(), "Power only".into());
     t.insert("settings.categories".into(), "Session Categories".into());
     t.insert("settings.categories.add".into(), "Add".into());
     t.insert("settings.categories.delete".into(), "Delete".into());
     t.insert("settings.categories.name_placeholder".into(), "Category name".into());
+    t.insert("settings.categories.bulk_label_placeholder".into(), "Label contains".into());
+    t.insert("settings.categories.bulk_assign".into(), "Assign to uncategorized".into());
+    t.insert("settings.categories.bulk_confirm".into(), "Put {count} past uncategorized sessions in {category}?".into());
+    t.insert("settings.categories.bulk_done".into(), "{count} sessions updated".into());
+    t.insert("settings.categories.bulk_none".into(), "No uncategorized session matches".into());
+    t.insert("settings.categories.baseline_placeholder".into(), "Baseline W".into());
+    t.insert("settings.categories.baseline_hint".into(), "Baseline of sessions in this category, empty to use the global one".into());
 
     // Settings - Updates
     t.insert("settings.updates".into(), "Updates".into());
@@ -320,6 +504,23 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("settings.updates.download".into(), "Download".into());
     t.insert("settings.updates.error".into(), "Failed to check for updates".into());
     t.insert("settings.updates.current_version".into(), "Current version".into());
+    t.insert("settings.lifetime".into(), "Tracked so far".into());
+    t.insert("settings.lifetime_summary".into(), "You've tracked {energy} since {since}".into());
+    t.insert("settings.lifetime_detail".into(), "{cost} over {hours} h, {sessions} sessions".into());
+    t.insert("settings.lifetime_none".into(), "Nothing tracked yet".into());
+    t.insert("settings.updates.stale".into(), "offline, showing last known result".into());
+
+    // Settings - Data
+    t.insert("settings.data".into(), "Data".into());
+    t.insert("settings.data.reset_scope".into(), "Statistics to reset".into());
+    t.insert("settings.data.scope.readings".into(), "Power readings".into());
+    t.insert("settings.data.scope.daily_stats".into(), "Daily statistics".into());
+    t.insert("settings.data.scope.sessions".into(), "Sessions".into());
+    t.insert("settings.data.scope.all".into(), "Everything".into());
+    t.insert("settings.data.reset".into(), "Reset Statistics".into());
+    t.insert("settings.data.reset_confirm".into(), "Delete these statistics? This cannot be undone.".into());
+    t.insert("settings.data.reset_session_active".into(), "A session is active. Reset anyway?".into());
+    t.insert("settings.data.reset_done".into(), "Statistics reset".into());
 
     // DateTime widget
     t.insert("widget.datetime".into(), "Date & Time".into());
@@ -336,6 +537,11 @@ pub fn get_translations() -> HashMap<String, String> {
 
     // History - Session histogram
     t.insert("history.usage".into(), "Usage".into());
+    t.insert("history.note".into(), "Note".into());
+    t.insert("history.note_add".into(), "Add a note".into());
+    t.insert("history.note_edit".into(), "Edit the note".into());
+    t.insert("history.note_prompt".into(), "Note for {date} (leave empty to delete)".into());
+    t.insert("history.note_failed".into(), "Could not save the note".into());
     t.insert("history.7_days".into(), "7 Days".into());
     t.insert("history.30_days".into(), "30 Days".into());
     t.insert("history.12_months".into(), "12 Months".into());
@@ -343,5 +549,40 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("history.apply".into(), "Apply".into());
     t.insert("history.hours".into(), "hours".into());
 
+    // Widget tooltip
+    t.insert("widget.tooltip.today".into(), "Today: {kwh} kWh, {cost}".into());
+    t.insert("widget.tooltip.today_energy".into(), "Today: {kwh} kWh".into());
+    t.insert("widget.tooltip.session".into(), "Session: {duration}, {cost}".into());
+    t.insert("widget.tooltip.source_measured".into(), "Source: {source} (measured)".into());
+    t.insert("widget.tooltip.source_estimated".into(), "Source: {source} (estimated)".into());
+    t.insert("widget.tooltip.rate".into(), "{name}: {rate}/kWh".into());
+    t.insert("widget.tooltip.updated".into(), "Updated {age} ago".into());
+    t.insert("widget.tooltip.stale".into(), "No reading for {age}, values may be outdated".into());
+
+    // Rate names
+    t.insert("rate.flat".into(), "Flat rate".into());
+    t.insert("rate.peak".into(), "Peak hours".into());
+    t.insert("rate.offpeak".into(), "Off-peak hours".into());
+    t.insert("rate.summer".into(), "Summer rate".into());
+    t.insert("rate.winter".into(), "Winter rate".into());
+    t.insert("rate.tempo.blue_peak".into(), "Blue day, peak hours".into());
+    t.insert("rate.tempo.blue_offpeak".into(), "Blue day, off-peak hours".into());
+    t.insert("rate.tempo.white_peak".into(), "White day, peak hours".into());
+    t.insert("rate.tempo.white_offpeak".into(), "White day, off-peak hours".into());
+    t.insert("rate.tempo.red_peak".into(), "Red day, peak hours".into());
+    t.insert("rate.tempo.red_offpeak".into(), "Red day, off-peak hours".into());
+
+    // Energy unit and cost precision
+    t.insert("settings.energy_unit".into(), "Energy unit".into());
+    t.insert("settings.energy_unit.auto".into(), "Auto (Wh, then kWh)".into());
+    t.insert("settings.cost_decimals".into(), "Cost decimals".into());
+
+    // Config recovery
+    t.insert("config.recovered".into(), "Settings could not be read and were restored from their backup. The damaged file was kept as {path}".into());
+
+    // History time zone
+    t.insert("settings.history_timezone".into(), "History time zone".into());
+    t.insert("settings.history_timezone_desc".into(), "Days and hours of the history: local, UTC, a fixed offset such as UTC+13 or a zone name such as Europe/Paris".into());
+
     t
 }
@@ -25,6 +25,7 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("dashboard.session_duration".into(), "Dur\u{00E9}e de session".into());
     t.insert("dashboard.power_source".into(), "Source de mesure".into());
     t.insert("dashboard.estimated".into(), "Estim\u{00E9}".into());
+    t.insert("dashboard.stale".into(), "Donn\u{00E9}es p\u{00E9}rim\u{00E9}es".into());
     t.insert("dashboard.measured".into(), "Mesur\u{00E9}".into());
 
     // Units
@@ -47,13 +48,18 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("settings.refresh_rate".into(), "Fr\u{00E9}quence de rafra\u{00EE}chissement".into());
     t.insert("settings.eco_mode".into(), "Mode \u{00E9}co".into());
     t.insert("settings.eco_mode.description".into(), "R\u{00E9}duire la fr\u{00E9}quence quand minimis\u{00E9}".into());
+    t.insert("settings.show_taskbar_badge".into(), "Co\u{00FB}t sur l'ic\u{00F4}ne de la barre des t\u{00E2}ches".into());
+    t.insert("settings.show_taskbar_badge_desc".into(), "Afficher le co\u{00FB}t du jour en pastille, mis \u{00E0} jour chaque minute".into());
     t.insert("settings.start_minimized".into(), "D\u{00E9}marrer minimis\u{00E9}".into());
     t.insert("settings.start_with_system".into(), "D\u{00E9}marrer avec le syst\u{00E8}me".into());
     t.insert("settings.remember_window_position".into(), "M\u{00E9}moriser la position et la taille de la fen\u{00EA}tre".into());
+    t.insert("settings.vram_alert_percent".into(), "Alerte d'utilisation VRAM (%)".into());
+    t.insert("settings.vram_alert_percent_desc".into(), "Pr\u{00E9}venir quand la m\u{00E9}moire GPU reste au-dessus de ce niveau pendant une minute (0 = d\u{00E9}sactiv\u{00E9})".into());
     t.insert("settings.run_as_admin".into(), "Ex\u{00E9}cuter en tant qu'administrateur".into());
     t.insert("settings.run_as_admin.tooltip".into(), "Permet d'arr\u{00EA}ter les processus prot\u{00E9}g\u{00E9}s/syst\u{00E8}me. N\u{00E9}cessite une confirmation UAC au d\u{00E9}marrage.".into());
     t.insert("settings.run_as_admin.elevated".into(), "\u{00C9}lev\u{00E9}".into());
     t.insert("settings.run_as_admin.not_elevated".into(), "Non \u{00E9}lev\u{00E9}".into());
+    t.insert("settings.run_as_admin.denied".into(), "\u{00C9}l\u{00E9}vation refus\u{00E9}e, nouvelle demande dans 24 h".into());
 
     // Settings - Pricing
     t.insert("settings.pricing".into(), "Tarification".into());
@@ -63,6 +69,15 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("settings.pricing.mode.seasonal".into(), "Saisonnier".into());
     t.insert("settings.pricing.mode.tempo".into(), "Tempo (style EDF)".into());
     t.insert("settings.pricing.currency".into(), "Devise".into());
+    t.insert("settings.pricing.preset".into(), "Pr\u{00E9}r\u{00E9}glage par pays".into());
+    t.insert("settings.pricing.secondary_currency".into(), "Devise secondaire".into());
+    t.insert("settings.pricing.secondary_currency_desc".into(), "Afficher aussi les co\u{00FB}ts convertis dans cette devise".into());
+    t.insert("settings.pricing.secondary_currency.none".into(), "Aucune".into());
+    t.insert("settings.pricing.manual_rate".into(), "Taux de change".into());
+    t.insert("settings.pricing.auto_fetch_rate".into(), "R\u{00E9}cup\u{00E9}rer le taux chaque jour".into());
+    t.insert("settings.pricing.rate_url".into(), "URL de la source du taux".into());
+    t.insert("settings.pricing.preset.apply".into(), "Appliquer".into());
+    t.insert("settings.pricing.preset.applied".into(), "Pr\u{00E9}r\u{00E9}glage appliqu\u{00E9}".into());
     t.insert("settings.pricing.rate".into(), "Tarif au kWh".into());
     t.insert("settings.pricing.peak_rate".into(), "Tarif heures pleines".into());
     t.insert("settings.pricing.offpeak_rate".into(), "Tarif heures creuses".into());
@@ -77,15 +92,21 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("settings.widget".into(), "Widget".into());
     t.insert("settings.widget.enabled".into(), "Activer le widget".into());
     t.insert("settings.widget.show_cost".into(), "Afficher le co\u{00FB}t".into());
+    t.insert("settings.widget.remember_open_state".into(), "M\u{00E9}moriser l'ouverture du widget".into());
     t.insert("settings.widget.show_power".into(), "Afficher la consommation uniquement".into());
     t.insert("settings.widget.position".into(), "Position".into());
     t.insert("settings.widget.position.top_left".into(), "Haut gauche".into());
     t.insert("settings.widget.position.top_right".into(), "Haut droite".into());
     t.insert("settings.widget.position.bottom_left".into(), "Bas gauche".into());
     t.insert("settings.widget.position.bottom_right".into(), "Bas droite".into());
+    t.insert("settings.widget.session_mode".into(), "Afficher la session en cours".into());
+    t.insert("settings.widget.session_mode.auto".into(), "Pendant une session".into());
+    t.insert("settings.widget.session_mode.always".into(), "Toujours".into());
+    t.insert("settings.widget.session_mode.never".into(), "Jamais".into());
     t.insert("settings.widget.opacity".into(), "Opacit\u{00E9}".into());
     t.insert("settings.widget.open".into(), "Ouvrir le widget".into());
     t.insert("settings.widget.close".into(), "Fermer le widget".into());
+    t.insert("settings.widget.limited".into(), "Votre bureau ne permet pas d'\u{00E9}pingler ou de positionner le widget ; il s'ouvre comme une fen\u{00EA}tre normale".into());
 
     // Settings - Pricing Tempo
     t.insert("settings.pricing.tempo.blue".into(), "Jours bleus".into());
@@ -128,7 +149,21 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("error.using_estimation".into(), "Utilisation du mode estimation".into());
     t.insert("error.permission_denied".into(), "Permission refus\u{00E9}e".into());
     t.insert("error.save_failed".into(), "\u{00C9}chec de l'enregistrement".into());
+    t.insert("error.code.hardware_not_supported".into(), "Ce mat\u{00E9}riel n'est pas pris en charge".into());
+    t.insert("error.code.permission_denied".into(), "Permission refus\u{00E9}e, essayez en tant qu'administrateur".into());
+    t.insert("error.code.db_error".into(), "Erreur de base de donn\u{00E9}es".into());
+    t.insert("error.code.config_invalid".into(), "Param\u{00E8}tres invalides".into());
+    t.insert("error.code.session_active".into(), "Une session est d\u{00E9}j\u{00E0} active".into());
+    t.insert("error.code.not_found".into(), "Introuvable".into());
+    t.insert("error.code.invalid_input".into(), "Valeur invalide".into());
+    t.insert("error.code.internal".into(), "Erreur inattendue".into());
     t.insert("warning.estimated_values".into(), "Les valeurs de puissance sont estim\u{00E9}es (aucun capteur direct d\u{00E9}tect\u{00E9})".into());
+    t.insert("warning.persistence_disabled".into(), "L'historique ne peut pas \u{00EA}tre enregistr\u{00E9}, le dossier de donn\u{00E9}es est en lecture seule. Le suivi en direct fonctionne toujours.".into());
+    t.insert("warning.persistence_retry".into(), "R\u{00E9}essayer".into());
+    t.insert("warning.persistence_restored".into(), "L'enregistrement de l'historique a repris".into());
+    t.insert("onboarding.region_defaults".into(), "Le tarif a \u{00E9}t\u{00E9} choisi pour votre r\u{00E9}gion ({region}) : {rate}/kWh. V\u{00E9}rifiez qu'il correspond \u{00E0} votre contrat.".into());
+    t.insert("onboarding.region_keep".into(), "Conserver".into());
+    t.insert("onboarding.region_change".into(), "Modifier".into());
 
     // Actions
     t.insert("action.save".into(), "Enregistrer".into());
@@ -184,6 +219,31 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("session.ended".into(), "Session terminée".into());
     t.insert("session.end_failed".into(), "Échec de la fin de session".into());
     t.insert("session.surplus".into(), "surplus".into());
+    t.insert("session.avg_watts".into(), "Puissance moy.".into());
+    t.insert("session.avg_surplus_watts".into(), "Surplus moy.".into());
+    t.insert("session.cost_per_hour".into(), "Co\u{00FB}t/h".into());
+    t.insert("receipt.untitled".into(), "Session".into());
+    t.insert("receipt.duration".into(), "Dur\u{00E9}e".into());
+    t.insert("receipt.total_energy".into(), "\u{00C9}nergie totale".into());
+    t.insert("receipt.surplus_energy".into(), "\u{00C9}nergie en surplus".into());
+    t.insert("receipt.surplus_cost".into(), "Co\u{00FB}t du surplus".into());
+
+    // Dashboard insights
+    t.insert("widget.insights".into(), "Observations".into());
+    t.insert("widget.no_insights".into(), "Pas encore assez de donn\u{00E9}es".into());
+    t.insert("widget.pinned".into(), "Processus \u{00E9}pingl\u{00E9}s".into());
+    t.insert("widget.no_pinned".into(), "\u{00C9}pinglez des processus depuis la liste pour les suivre ici".into());
+    t.insert("widget.not_running".into(), "Non lanc\u{00E9}".into());
+    t.insert("insight.week_energy_up".into(), "Votre PC a consomm\u{00E9} {percent}\u{00A0}% d'\u{00E9}nergie en plus cette semaine".into());
+    t.insert("insight.week_energy_down".into(), "Votre PC a consomm\u{00E9} {percent}\u{00A0}% d'\u{00E9}nergie en moins cette semaine".into());
+    t.insert("insight.most_expensive_day".into(), "Votre jour le plus cher\u{00A0}: {day} ({cost})".into());
+    t.insert("insight.category_gpu_share".into(), "Le GPU a repr\u{00E9}sent\u{00E9} {percent}\u{00A0}% de la puissance pendant les sessions {category}".into());
+    t.insert("insight.offpeak_savings".into(), "Les heures creuses vous ont fait \u{00E9}conomiser {cost} ce mois-ci".into());
+
+    // Per-core CPU detail
+    t.insert("cpu_cores.title".into(), "C\u{0153}urs du CPU".into());
+    t.insert("cpu_cores.core".into(), "C\u{0153}ur".into());
+    t.insert("cpu_cores.empty".into(), "Pas encore de donn\u{00E9}es par c\u{0153}ur".into());
 
     // Process list
     t.insert("processes.all".into(), "Tous les processus".into());
@@ -194,12 +254,21 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("processes.header.ram".into(), "RAM %".into());
     t.insert("processes.pinned".into(), "Épinglé".into());
     t.insert("processes.unpinned".into(), "Désépinglé".into());
+    t.insert("processes.ignore".into(), "Masquer de la liste".into());
+    t.insert("processes.unignore".into(), "Afficher dans la liste".into());
+    t.insert("processes.ignored".into(), "Masqu\u{00E9}".into());
+    t.insert("processes.unignored".into(), "Affich\u{00E9} \u{00E0} nouveau".into());
+    t.insert("processes.ignore_failed".into(), "\u{00C9}chec de la mise \u{00E0} jour de la liste ignor\u{00E9}e".into());
     t.insert("processes.pin_failed".into(), "Échec de la mise à jour de l'épingle".into());
     t.insert("processes.killed".into(), "Processus arrêté".into());
     t.insert("processes.kill_failed".into(), "Échec de l'arrêt du processus".into());
-    t.insert("processes.kill_access_denied".into(), "Accès refusé — le processus nécessite des privilèges élevés".into());
-    t.insert("processes.kill_not_found".into(), "Processus introuvable — il a peut-être déjà été fermé".into());
+    t.insert("processes.kill_access_denied".into(), "Arr\u{00EA}t de {name} non autoris\u{00E9}".into());
+    t.insert("processes.kill_not_found".into(), "{name} n'est pas lanc\u{00E9}, il s'est peut-\u{00EA}tre d\u{00E9}j\u{00E0} arr\u{00EA}t\u{00E9}".into());
+    t.insert("processes.elevation_hint".into(), "lancez PowerCost Tracker en administrateur pour l'arr\u{00EA}ter".into());
+    t.insert("processes.pin_not_found".into(), "{name} n'est pas lanc\u{00E9}, seuls les processus lanc\u{00E9}s peuvent \u{00EA}tre \u{00E9}pingl\u{00E9}s".into());
+    t.insert("processes.other_row".into(), "La ligne Autres regroupe plusieurs processus, elle ne peut pas \u{00EA}tre \u{00E9}pingl\u{00E9}e ni arr\u{00EA}t\u{00E9}e".into());
     t.insert("processes.kill_confirm".into(), "Arrêter le processus".into());
+    t.insert("processes.other".into(), "Autres".into());
 
     // Settings - Baseline detection
     t.insert("settings.baseline".into(), "Détection de base".into());
@@ -212,7 +281,66 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("settings.baseline.detect_failed".into(), "Échec de la détection".into());
     t.insert("settings.baseline.set_success".into(), "Base définie à".into());
     t.insert("settings.baseline.set_failed".into(), "Échec de la définition de la base".into());
+    t.insert("settings.baseline.schedule".into(), "Base jour/nuit".into());
+    t.insert("settings.baseline.schedule_desc".into(), "Garder une consommation au repos pour chaque moment de la journ\u{00E9}e".into());
+    t.insert("settings.baseline.bucket_day".into(), "Jour \u{00E0} partir de".into());
+    t.insert("settings.baseline.bucket_night".into(), "Nuit \u{00E0} partir de".into());
+    t.insert("settings.baseline.bucket_auto".into(), "Auto".into());
     t.insert("settings.process_limit".into(), "Limite de processus".into());
+    t.insert("settings.process_grouping".into(), "Regrouper les processus par".into());
+    t.insert("settings.process_grouping_desc".into(), "Une ligne par conteneur Docker, Podman ou LXC ou service systemd (Linux), \u{00E9}pingler un conteneur \u{00E9}pingle ses processus".into());
+    t.insert("settings.process_grouping.name".into(), "Nom du processus".into());
+    t.insert("settings.process_grouping.container".into(), "Conteneur".into());
+    t.insert("settings.gpu_command_timeout".into(), "D\u{00E9}lai des commandes GPU (ms)".into());
+    t.insert("settings.gpu_command_timeout_desc".into(), "\u{00C0} augmenter si nvidia-smi r\u{00E9}pond lentement quand le GPU est en veille".into());
+    t.insert("settings.disable_gpu_cli".into(), "D\u{00E9}sactiver les outils GPU en ligne de commande".into());
+    t.insert("settings.disable_gpu_cli_desc".into(), "Ne jamais lancer nvidia-smi, rocm-smi ou amd-smi (les GPU NVIDIA restent lus via NVML)".into());
+    t.insert("settings.pause_gpu_polling_in_game".into(), "Suspendre les outils GPU dans les jeux plein \u{00E9}cran".into());
+    t.insert("settings.pause_gpu_polling_in_game_desc".into(), "Ne plus lancer nvidia-smi, rocm-smi ou amd-smi quand une application plein \u{00E9}cran est au premier plan, en gardant les derni\u{00E8}res valeurs GPU".into());
+    t.insert("settings.gpu_estimate".into(), "Puissance GPU estim\u{00E9}e (W)".into());
+    t.insert("settings.gpu_estimate_desc".into(), "Puissance au repos et en charge d'une carte graphique illisible, ajout\u{00E9}e aux mesures estim\u{00E9}es".into());
+    t.insert("settings.gpu_estimate_idle".into(), "Repos".into());
+    t.insert("settings.gpu_estimate_tdp".into(), "Max".into());
+    t.insert("settings.gpu_estimate_suggestion".into(), "{name} d\u{00E9}tect\u{00E9}e : environ {idle} W au repos, {tdp} W en pleine charge".into());
+    t.insert("settings.gpu_estimate_apply".into(), "Utiliser".into());
+    t.insert("settings.accuracy_tracking".into(), "Suivre la pr\u{00E9}cision de l'estimation".into());
+    t.insert("settings.accuracy_tracking_desc".into(), "Quand la puissance est mesur\u{00E9}e, la comparer \u{00E0} l'estimation chaque minute".into());
+    t.insert("settings.accuracy_summary".into(), "{count} \u{00E9}chantillons : erreur moyenne {error} W, biais {bias} W, facteur sugg\u{00E9}r\u{00E9} {factor} (appliqu\u{00E9} {applied})".into());
+    t.insert("settings.auto_apply_correction".into(), "Corriger les estimations automatiquement".into());
+    t.insert("settings.auto_apply_correction_desc".into(), "Multiplier les mesures estim\u{00E9}es par le facteur appris des mesures r\u{00E9}elles (0,5 \u{00E0} 2)".into());
+    t.insert("monitoring.adjusted".into(), "La collecte des m\u{00E9}triques d\u{00E9}taill\u{00E9}es prend {collection} s sur cette machine, elles sont d\u{00E9}sormais rafra\u{00EE}chies toutes les {effective} s".into());
+    t.insert("settings.capabilities".into(), "Mat\u{00E9}riel pris en charge".into());
+    t.insert("settings.capabilities_desc".into(), "Ce que ce syst\u{00E8}me remonte, les panneaux qui resteraient vides sont masqu\u{00E9}s".into());
+    t.insert("settings.capabilities_all".into(), "Tout est disponible".into());
+    t.insert("settings.capabilities_refresh".into(), "D\u{00E9}tecter \u{00E0} nouveau".into());
+    t.insert("capabilities.real_power".into(), "Puissance mesur\u{00E9}e".into());
+    t.insert("capabilities.gpu_metrics".into(), "M\u{00E9}triques GPU".into());
+    t.insert("capabilities.gpu_per_process".into(), "Utilisation GPU par processus".into());
+    t.insert("capabilities.cpu_temperature".into(), "Temp\u{00E9}rature CPU".into());
+    t.insert("capabilities.per_core_temperature".into(), "Temp\u{00E9}ratures par c\u{0153}ur".into());
+    t.insert("capabilities.fans".into(), "Ventilateurs".into());
+    t.insert("capabilities.voltages".into(), "Tensions".into());
+    t.insert("capabilities.memory_info".into(), "D\u{00E9}tails m\u{00E9}moire".into());
+    t.insert("capabilities.hint.real_power.linux".into(), "estim\u{00E9}e, RAPL n\u{00E9}cessite la lecture de /sys/class/powercap (root ou une r\u{00E8}gle udev)".into());
+    t.insert("capabilities.hint.real_power.windows".into(), "estim\u{00E9}e \u{00E0} partir de la charge CPU, un GPU NVIDIA ou AMD compatible ajoute sa puissance mesur\u{00E9}e".into());
+    t.insert("capabilities.hint.real_power.macos".into(), "estim\u{00E9}e, powermetrics n\u{00E9}cessite les droits administrateur".into());
+    t.insert("capabilities.hint.real_power".into(), "estim\u{00E9}e \u{00E0} partir de la charge CPU".into());
+    t.insert("capabilities.hint.cpu_temperature.windows".into(), "lancez LibreHardwareMonitor pour lire les capteurs du CPU".into());
+    t.insert("capabilities.hint.cpu_temperature.linux".into(), "chargez le module coretemp ou k10temp".into());
+    t.insert("capabilities.hint.cpu_temperature".into(), "aucun capteur CPU remont\u{00E9}".into());
+    t.insert("capabilities.hint.gpu_metrics".into(), "nvidia-smi, rocm-smi ou NVML introuvable".into());
+    t.insert("capabilities.hint.gpu_per_process".into(), "non remont\u{00E9}e par le pilote GPU".into());
+    t.insert("capabilities.hint.per_core_temperature".into(), "le CPU n'a qu'un seul capteur".into());
+    t.insert("capabilities.hint.fans.linux".into(), "aucun capteur de ventilateur hwmon, installez lm-sensors".into());
+    t.insert("capabilities.hint.fans".into(), "aucun capteur de ventilateur remont\u{00E9}".into());
+    t.insert("capabilities.hint.voltages.windows".into(), "indisponible sans LibreHardwareMonitor".into());
+    t.insert("capabilities.hint.voltages".into(), "aucun capteur de tension remont\u{00E9}".into());
+    t.insert("capabilities.hint.memory_info".into(), "la fr\u{00E9}quence et le type de m\u{00E9}moire ne sont pas remont\u{00E9}s".into());
+    t.insert("capabilities.input_idle".into(), "Inactivit\u{00E9} clavier et souris".into());
+    t.insert("capabilities.hint.input_idle.linux".into(), "n\u{00E9}cessite X11 avec libXss, ou GNOME sous Wayland ; les sessions inactives se basent sur la puissance seule".into());
+    t.insert("capabilities.hint.input_idle".into(), "illisible, les sessions inactives se basent sur la puissance seule".into());
+    t.insert("settings.disable_powershell_probes".into(), "D\u{00E9}sactiver les sondes PowerShell".into());
+    t.insert("settings.disable_powershell_probes_desc".into(), "Ne jamais lancer PowerShell pour les temp\u{00E9}ratures, ventilateurs et d\u{00E9}tails m\u{00E9}moire".into());
     t.insert("settings.refresh_rate_detailed".into(), "Fréquence (Détaillée)".into());
     t.insert("settings.refresh_rate_critical".into(), "Fréquence (Critique)".into());
 
@@ -220,6 +348,9 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("history.daily_breakdown".into(), "D\u{00E9}tail journalier".into());
     t.insert("history.date".into(), "Date".into());
     t.insert("history.energy".into(), "\u{00C9}nergie".into());
+    t.insert("history.temperature".into(), "Temp\u{00E9}rature".into());
+    t.insert("history.estimated".into(), "estim\u{00E9}".into());
+    t.insert("history.estimated_desc".into(), "La plupart des mesures de ce jour ont \u{00E9}t\u{00E9} estim\u{00E9}es".into());
     t.insert("history.cost".into(), "Co\u{00FB}t".into());
     t.insert("history.rate".into(), "Tarif".into());
     t.insert("history.avg".into(), "Moy.".into());
@@ -233,7 +364,9 @@ pub fn get_translations() -> HashMap<String, String> {
     // Tray menu
     t.insert("tray.show".into(), "Afficher".into());
     t.insert("tray.restart".into(), "Redémarrer".into());
+    t.insert("tray.low_power".into(), "Mode basse consommation".into());
     t.insert("tray.exit".into(), "Quitter".into());
+    t.insert("widget.window_title".into(), "Widget PowerCost".into());
 
     // Widget titles and labels
     t.insert("widget.cpu".into(), "CPU".into());
@@ -249,6 +382,8 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("widget.power".into(), "Puissance".into());
     t.insert("widget.usage".into(), "Utilisation".into());
     t.insert("widget.cost".into(), "Co\u{00FB}t".into());
+    t.insert("widget.session_cost".into(), "Co\u{00FB}t de session".into());
+    t.insert("widget.session_time".into(), "Dur\u{00E9}e de session".into());
     t.insert("widget.baseline".into(), "Base".into());
     t.insert("widget.current".into(), "Actuel".into());
     t.insert("widget.set_baseline".into(), "D\u{00E9}finir la base".into());
@@ -281,6 +416,8 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("dashboard.current_power_short".into(), "Puissance".into());
     t.insert("widget.processes_short".into(), "Procs".into());
     t.insert("widget.session_controls_short".into(), "Session".into());
+    t.insert("widget.above_baseline".into(), "Au-dessus de la base".into());
+    t.insert("widget.above_idle".into(), "Au-dessus du repos".into());
     t.insert("widget.surplus_short".into(), "Surplus".into());
 
     // Estimation widget toggle labels
@@ -303,12 +440,59 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("session.name_placeholder".into(), "Nom de session...".into());
     t.insert("session.no_category".into(), "Aucune cat\u{00E9}gorie".into());
     t.insert("session.category".into(), "Cat\u{00E9}gorie".into());
+    t.insert("session.target".into(), "Objectif".into());
+    t.insert("session.target_met".into(), "Atteint".into());
+    t.insert("session.target_missed".into(), "D\u{00E9}pass\u{00E9}".into());
+    t.insert("session.target_warning".into(), "La session approche de son objectif".into());
+    t.insert("session.target_reached".into(), "Objectif de session atteint".into());
+    t.insert("alert.vram_high".into(), "Utilisation de la m\u{00E9}moire GPU \u{00E9}lev\u{00E9}e".into());
+    t.insert("notification.session_target_body".into(), "{percent} % de l'objectif utilis\u{00E9}".into());
+    t.insert("notification.session_idle_body".into(), "{minutes} min d'inactivit\u{00E9} retir\u{00E9}es".into());
+    t.insert("notification.session_duration_body".into(), "Dur\u{00E9}e pr\u{00E9}vue atteinte".into());
+    t.insert("notification.vram_body".into(), "{percent} % utilis\u{00E9}s ({used} / {total} Mo)".into());
+    t.insert("notification.test_title".into(), "Notification de test".into());
+    t.insert("notification.kind.session_target".into(), "Objectifs de session".into());
+    t.insert("notification.kind.session_auto_ended".into(), "Sessions termin\u{00E9}es automatiquement".into());
+    t.insert("notification.kind.vram_alert".into(), "Alertes de m\u{00E9}moire GPU".into());
+    t.insert("settings.notifications".into(), "Notifications".into());
+    t.insert("settings.quiet_hours".into(), "Heures calmes".into());
+    t.insert("settings.quiet_hours_desc".into(), "Aucune alerte entre ces heures (laisser vide pour d\u{00E9}sactiver)".into());
+    t.insert("settings.notifications.test".into(), "Tester".into());
+    t.insert("session.auto_ended".into(), "Session termin\u{00E9}e apr\u{00E8}s inactivit\u{00E9}".into());
 
     // Settings - Categories
+    t.insert("session.category.gaming".into(), "Jeux".into());
+    t.insert("session.category.work".into(), "Travail".into());
+    t.insert("session.category.ai".into(), "IA".into());
+    t.insert("session.category.browsing".into(), "Navigation".into());
+    t.insert("settings.use_emoji".into(), "Afficher les emoji".into());
+    t.insert("settings.use_emoji_desc".into(), "Afficher les emoji \u{00E0} c\u{00F4}t\u{00E9} des cat\u{00E9}gories de session".into());
+    t.insert("settings.track_active_window".into(), "Suivre l'application active".into());
+    t.insert("settings.track_active_window_desc".into(), "Enregistrer les applications au premier plan pendant les sessions (noms de processus uniquement)".into());
+    t.insert("settings.split_sessions_at_midnight".into(), "Couper les sessions \u{00E0} minuit".into());
+    t.insert("settings.split_sessions_at_midnight_desc".into(), "Terminer les sessions qui d\u{00E9}passent minuit et les poursuivre dans une nouvelle session, pour que chaque jour ait son propre co\u{00FB}t".into());
+    t.insert("settings.session_end_hook".into(), "Script de fin de session".into());
+    t.insert("settings.session_end_hook_desc".into(), "Chemin absolu d'un programme lanc\u{00E9} \u{00E0} la fin d'une session, avec la session en JSON sur son entr\u{00E9}e".into());
+    t.insert("settings.session_end_hook_test".into(), "Tester".into());
+    t.insert("settings.session_end_hook_ok".into(), "Le script s'est ex\u{00E9}cut\u{00E9} correctement".into());
+    t.insert("settings.session_end_hook_timeout".into(), "Le script a \u{00E9}t\u{00E9} arr\u{00EA}t\u{00E9} apr\u{00E8}s 30 secondes".into());
+    t.insert("settings.session_end_hook_failed".into(), "Le script a \u{00E9}chou\u{00E9}".into());
+    t.insert("settings.session_idle_input".into(), "Inactivit\u{00E9} clavier pour l'arr\u{00EA}t auto".into());
+    t.insert("settings.session_idle_input_desc".into(), "Comment une minute sans clavier ni souris se combine avec une puissance proche de la base pour terminer les sessions inactives".into());
+    t.insert("settings.session_idle_input.and".into(), "Puissance et clavier inactifs".into());
+    t.insert("settings.session_idle_input.or".into(), "Puissance ou clavier inactifs".into());
+    t.insert("settings.session_idle_input.off".into(), "Puissance seule".into());
     t.insert("settings.categories".into(), "Cat\u{00E9}gories de session".into());
     t.insert("settings.categories.add".into(), "Ajouter".into());
     t.insert("settings.categories.delete".into(), "Supprimer".into());
     t.insert("settings.categories.name_placeholder".into(), "Nom de cat\u{00E9}gorie".into());
+    t.insert("settings.categories.bulk_label_placeholder".into(), "Le libell\u{00E9} contient".into());
+    t.insert("settings.categories.bulk_assign".into(), "Attribuer aux sessions sans cat\u{00E9}gorie".into());
+    t.insert("settings.categories.bulk_confirm".into(), "Mettre {count} sessions pass\u{00E9}es sans cat\u{00E9}gorie dans {category} ?".into());
+    t.insert("settings.categories.bulk_done".into(), "{count} sessions mises \u{00E0} jour".into());
+    t.insert("settings.categories.bulk_none".into(), "Aucune session sans cat\u{00E9}gorie ne correspond".into());
+    t.insert("settings.categories.baseline_placeholder".into(), "Base W".into());
+    t.insert("settings.categories.baseline_hint".into(), "Base des sessions de cette cat\u{00E9}gorie, vide pour utiliser la base globale".into());
 
     // Settings - Updates
     t.insert("settings.updates".into(), "Mises \u{00E0} jour".into());
@@ -320,6 +504,23 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("settings.updates.download".into(), "T\u{00E9}l\u{00E9}charger".into());
     t.insert("settings.updates.error".into(), "\u{00C9}chec de la v\u{00E9}rification des mises \u{00E0} jour".into());
     t.insert("settings.updates.current_version".into(), "Version actuelle".into());
+    t.insert("settings.lifetime".into(), "Suivi jusqu'ici".into());
+    t.insert("settings.lifetime_summary".into(), "Vous avez suivi {energy} depuis {since}".into());
+    t.insert("settings.lifetime_detail".into(), "{cost} sur {hours} h, {sessions} sessions".into());
+    t.insert("settings.lifetime_none".into(), "Rien de suivi pour l'instant".into());
+    t.insert("settings.updates.stale".into(), "hors ligne, dernier r\u{00E9}sultat connu".into());
+
+    // Settings - Data
+    t.insert("settings.data".into(), "Donn\u{00E9}es".into());
+    t.insert("settings.data.reset_scope".into(), "Statistiques \u{00E0} r\u{00E9}initialiser".into());
+    t.insert("settings.data.scope.readings".into(), "Mesures de puissance".into());
+    t.insert("settings.data.scope.daily_stats".into(), "Statistiques journali\u{00E8}res".into());
+    t.insert("settings.data.scope.sessions".into(), "Sessions".into());
+    t.insert("settings.data.scope.all".into(), "Tout".into());
+    t.insert("settings.data.reset".into(), "R\u{00E9}initialiser les statistiques".into());
+    t.insert("settings.data.reset_confirm".into(), "Supprimer ces statistiques ? Cette action est irr\u{00E9}versible.".into());
+    t.insert("settings.data.reset_session_active".into(), "Une session est en cours. R\u{00E9}initialiser quand m\u{00EA}me ?".into());
+    t.insert("settings.data.reset_done".into(), "Statistiques r\u{00E9}initialis\u{00E9}es".into());
 
     // DateTime widget
     t.insert("widget.datetime".into(), "Date & Heure".into());
@@ -336,6 +537,11 @@ pub fn get_translations() -> HashMap<String, String> {
 
     // History - Session histogram
     t.insert("history.usage".into(), "Utilisation".into());
+    t.insert("history.note".into(), "Note".into());
+    t.insert("history.note_add".into(), "Ajouter une note".into());
+    t.insert("history.note_edit".into(), "Modifier la note".into());
+    t.insert("history.note_prompt".into(), "Note du {date} (laisser vide pour supprimer)".into());
+    t.insert("history.note_failed".into(), "Impossible d'enregistrer la note".into());
     t.insert("history.7_days".into(), "7 jours".into());
     t.insert("history.30_days".into(), "30 jours".into());
     t.insert("history.12_months".into(), "12 mois".into());
@@ -343,5 +549,40 @@ pub fn get_translations() -> HashMap<String, String> {
     t.insert("history.apply".into(), "Appliquer".into());
     t.insert("history.hours".into(), "heures".into());
 
+    // Widget tooltip
+    t.insert("widget.tooltip.today".into(), "Aujourd'hui : {kwh} kWh, {cost}".into());
+    t.insert("widget.tooltip.today_energy".into(), "Aujourd'hui : {kwh} kWh".into());
+    t.insert("widget.tooltip.session".into(), "Session : {duration}, {cost}".into());
+    t.insert("widget.tooltip.source_measured".into(), "Source : {source} (mesur\u{00E9})".into());
+    t.insert("widget.tooltip.source_estimated".into(), "Source : {source} (estim\u{00E9})".into());
+    t.insert("widget.tooltip.rate".into(), "{name} : {rate}/kWh".into());
+    t.insert("widget.tooltip.updated".into(), "Mis \u{00E0} jour il y a {age}".into());
+    t.insert("widget.tooltip.stale".into(), "Aucune mesure depuis {age}, valeurs peut-\u{00EA}tre d\u{00E9}pass\u{00E9}es".into());
+
+    // Rate names
+    t.insert("rate.flat".into(), "Tarif unique".into());
+    t.insert("rate.peak".into(), "Heures pleines".into());
+    t.insert("rate.offpeak".into(), "Heures creuses".into());
+    t.insert("rate.summer".into(), "Tarif \u{00E9}t\u{00E9}".into());
+    t.insert("rate.winter".into(), "Tarif hiver".into());
+    t.insert("rate.tempo.blue_peak".into(), "Jour bleu, heures pleines".into());
+    t.insert("rate.tempo.blue_offpeak".into(), "Jour bleu, heures creuses".into());
+    t.insert("rate.tempo.white_peak".into(), "Jour blanc, heures pleines".into());
+    t.insert("rate.tempo.white_offpeak".into(), "Jour blanc, heures creuses".into());
+    t.insert("rate.tempo.red_peak".into(), "Jour rouge, heures pleines".into());
+    t.insert("rate.tempo.red_offpeak".into(), "Jour rouge, heures creuses".into());
+
+    // Energy unit and cost precision
+    t.insert("settings.energy_unit".into(), "Unit\u{00E9} d'\u{00E9}nergie".into());
+    t.insert("settings.energy_unit.auto".into(), "Auto (Wh, puis kWh)".into());
+    t.insert("settings.cost_decimals".into(), "D\u{00E9}cimales des co\u{00FB}ts".into());
+
+    // Config recovery
+    t.insert("config.recovered".into(), "Les param\u{00E8}tres \u{00E9}taient illisibles et ont \u{00E9}t\u{00E9} restaur\u{00E9}s depuis leur sauvegarde. Le fichier endommag\u{00E9} a \u{00E9}t\u{00E9} conserv\u{00E9} sous {path}".into());
+
+    // History time zone
+    t.insert("settings.history_timezone".into(), "Fuseau horaire de l'historique".into());
+    t.insert("settings.history_timezone_desc".into(), "Jours et heures de l'historique : local, UTC, un d\u{00E9}calage fixe comme UTC+13 ou un nom de fuseau comme Europe/Paris".into());
+
     t
 }
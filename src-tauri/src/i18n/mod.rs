@@ -50,6 +50,12 @@ impl I18n {
             .unwrap_or_else(|| key.to_string())
     }
 
+    /// Get a translated string with its `{name}` placeholders replaced by `args`
+    pub fn format(&self, key: &str, args: &[(&str, String)]) -> String {
+        args.iter()
+            .fold(self.get(key), |text, (name, value)| text.replace(&format!("{{{}}}", name), value))
+    }
+
     /// Get all translations
     pub fn get_all(&self) -> HashMap<String, String> {
         self.translations.clone()
@@ -67,24 +73,9 @@ impl I18n {
 
     /// Detect system language
     fn detect_system_language(&self) -> String {
-        // Try to detect from environment variables
-        let lang_env = std::env::var("LANG")
-            .or_else(|_| std::env::var("LC_ALL"))
-            .or_else(|_| std::env::var("LC_MESSAGES"))
-            .unwrap_or_else(|_| "en".to_string());
-
-        // Extract language code (e.g., "fr_FR.UTF-8" -> "fr")
-        let lang_code = lang_env
-            .split('_')
-            .next()
-            .unwrap_or("en")
-            .split('.')
-            .next()
-            .unwrap_or("en");
-
         // Only return supported languages
-        match lang_code {
-            "fr" => "fr".to_string(),
+        match crate::core::locale::system_locale() {
+            Some(locale) if locale.language == "fr" => "fr".to_string(),
             _ => "en".to_string(),
         }
     }
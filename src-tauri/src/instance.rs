@@ -0,0 +1,222 @@
+//! Single-instance enforcement through a loopback socket
+//!
+//! The first instance listens on a port derived from the user's data
+//! directory. A later launch (autostart plus a manual launch, say) connects
+//! to it, asks it to show its main window and exits instead of starting a
+//! second monitoring loop writing into the same database.
+//!
+//! An instance relaunching itself (elevated) still holds the lock while its
+//! replacement starts, so the replacement is given `TAKEOVER_ARG` and waits
+//! for the port to be released instead of handing off.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Line sent by a second instance to the running one
+const SHOW_REQUEST: &str = "powercost-tracker show";
+
+/// Reply of the running instance
+const ACK: &str = "ok";
+
+/// Ports are picked in `FIRST_PORT..FIRST_PORT + PORT_RANGE`
+const FIRST_PORT: u16 = 40000;
+const PORT_RANGE: u32 = 10000;
+
+/// How long a second instance waits for the running one, which may still be starting
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Launch argument of an instance replacing the running one
+pub const TAKEOVER_ARG: &str = "--takeover";
+
+/// How long a replacement waits for the instance it replaces to exit
+const TAKEOVER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Port held by this process, 0 when it does not hold the lock
+static LOCK_PORT: AtomicU32 = AtomicU32::new(0);
+
+static HANDOFFS: AtomicU64 = AtomicU64::new(0);
+
+/// Outcome of trying to become the only instance
+pub enum Acquired {
+    /// This process is the first instance
+    Primary(InstanceLock),
+    /// The running instance was asked to show its window, this one should exit
+    HandedOff,
+    /// The lock could not be taken (port used by another program), duplicates are not prevented
+    Unguarded(String),
+}
+
+/// Listener held by the first instance for as long as it runs
+pub struct InstanceLock {
+    listener: TcpListener,
+}
+
+impl InstanceLock {
+    /// Port the lock listens on
+    pub fn port(&self) -> u16 {
+        self.listener.local_addr().map(|a| a.port()).unwrap_or(0)
+    }
+
+    /// Answer later launches on a background thread, calling `on_show` for each
+    pub fn serve(self, on_show: impl Fn() + Send + 'static) {
+        std::thread::spawn(move || {
+            for stream in self.listener.incoming() {
+                match stream.map(answer) {
+                    Ok(true) => {
+                        HANDOFFS.fetch_add(1, Ordering::Relaxed);
+                        log::info!("Another instance was launched, showing the main window");
+                        on_show();
+                    }
+                    Ok(false) => log::debug!("Ignored unexpected connection on the instance port"),
+                    Err(e) => log::debug!("Instance port accept failed: {}", e),
+                }
+            }
+        });
+    }
+}
+
+/// Lock port for a per-user key, the same for every launch of the same user
+pub fn port_for(key: &str) -> u16 {
+    FIRST_PORT + (crc32fast::hash(key.as_bytes()) % PORT_RANGE) as u16
+}
+
+/// Key of the current user (the app data directory)
+pub fn default_key() -> String {
+    dirs::data_dir()
+        .map(|dir| dir.join("powercost-tracker").display().to_string())
+        .unwrap_or_else(|| "powercost-tracker".to_string())
+}
+
+/// Become the only instance on `port`, or hand off to the one already running
+pub fn acquire(port: u16) -> Acquired {
+    match TcpListener::bind((Ipv4Addr::LOCALHOST, port)) {
+        Ok(listener) => {
+            let lock = InstanceLock { listener };
+            LOCK_PORT.store(lock.port() as u32, Ordering::Relaxed);
+            Acquired::Primary(lock)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => match request_show(port) {
+            Ok(()) => Acquired::HandedOff,
+            Err(e) => Acquired::Unguarded(format!("port {} is used by another program ({})", port, e)),
+        },
+        Err(e) => Acquired::Unguarded(e.to_string()),
+    }
+}
+
+/// Like `acquire`, but waits up to `TAKEOVER_TIMEOUT` for the running instance to exit
+///
+/// For a replacement launched by that instance, which exits once it sees the
+/// replacement running. Past the timeout the lock is acquired as usual.
+pub fn take_over(port: u16) -> Acquired {
+    take_over_within(port, TAKEOVER_TIMEOUT)
+}
+
+fn take_over_within(port: u16, timeout: Duration) -> Acquired {
+    let started = std::time::Instant::now();
+    while started.elapsed() < timeout {
+        match TcpListener::bind((Ipv4Addr::LOCALHOST, port)) {
+            Ok(listener) => {
+                let lock = InstanceLock { listener };
+                LOCK_PORT.store(lock.port() as u32, Ordering::Relaxed);
+                return Acquired::Primary(lock);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => std::thread::sleep(Duration::from_millis(100)),
+            Err(e) => return Acquired::Unguarded(e.to_string()),
+        }
+    }
+    acquire(port)
+}
+
+/// Port of the lock held by this process, None when duplicates are not prevented
+pub fn lock_port() -> Option<u16> {
+    match LOCK_PORT.load(Ordering::Relaxed) {
+        0 => None,
+        port => Some(port as u16),
+    }
+}
+
+/// Later launches handed off to this instance since startup
+pub fn handoffs() -> u64 {
+    HANDOFFS.load(Ordering::Relaxed)
+}
+
+fn read_line(stream: &TcpStream) -> std::io::Result<String> {
+    let mut line = String::new();
+    // A line is never longer than this
+    BufReader::new(Read::take(stream, 64)).read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
+
+/// Reply to a connection, true when it was a show request
+fn answer(mut stream: TcpStream) -> bool {
+    let _ = stream.set_read_timeout(Some(HANDOFF_TIMEOUT));
+    match read_line(&stream) {
+        Ok(line) if line == SHOW_REQUEST => stream.write_all(format!("{}\n", ACK).as_bytes()).is_ok(),
+        _ => false,
+    }
+}
+
+fn request_show(port: u16) -> std::io::Result<()> {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let mut stream = TcpStream::connect_timeout(&addr, HANDOFF_TIMEOUT)?;
+    stream.set_read_timeout(Some(HANDOFF_TIMEOUT))?;
+    stream.write_all(format!("{}\n", SHOW_REQUEST).as_bytes())?;
+    match read_line(&stream)? {
+        reply if reply == ACK => Ok(()),
+        reply => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("unexpected reply {:?}", reply))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_second_instance_hands_off() {
+        let Acquired::Primary(lock) = acquire(0) else {
+            panic!("no ephemeral port");
+        };
+        let port = lock.port();
+        let (tx, rx) = mpsc::channel();
+        lock.serve(move || tx.send(()).unwrap());
+
+        let before = handoffs();
+        assert!(matches!(acquire(port), Acquired::HandedOff));
+        rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(handoffs(), before + 1);
+    }
+
+    #[test]
+    fn test_replacement_waits_for_the_lock() {
+        let Acquired::Primary(lock) = acquire(0) else {
+            panic!("no ephemeral port");
+        };
+        let port = lock.port();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(300));
+            drop(lock);
+        });
+        assert!(matches!(take_over_within(port, Duration::from_secs(5)), Acquired::Primary(_)));
+    }
+
+    #[test]
+    fn test_foreign_port_is_unguarded() {
+        let foreign = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = foreign.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let (mut stream, _) = foreign.accept().unwrap();
+            stream.write_all(b"HTTP/1.1 400 Bad Request\r\n").unwrap();
+        });
+        assert!(matches!(acquire(port), Acquired::Unguarded(_)));
+    }
+
+    #[test]
+    fn test_port_for() {
+        let port = port_for("/home/alice/.local/share/powercost-tracker");
+        assert_eq!(port, port_for("/home/alice/.local/share/powercost-tracker"));
+        assert!((FIRST_PORT..FIRST_PORT + PORT_RANGE as u16).contains(&port));
+    }
+}
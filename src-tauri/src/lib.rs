@@ -5,6 +5,14 @@
 
 pub mod core;
 pub mod db;
+pub mod export;
 pub mod hardware;
 pub mod i18n;
+pub mod instance;
 pub mod pricing;
+pub mod report;
+pub mod secrets;
+pub mod session_hook;
+pub mod updater;
+pub mod webhook;
+pub mod widget;
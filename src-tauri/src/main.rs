@@ -8,19 +8,52 @@
 mod core;
 mod db;
 mod elevation;
+mod export;
 mod hardware;
 mod i18n;
+mod instance;
 mod pricing;
-
-use crate::core::{AppState, BaselineDetection, Config, CriticalMetrics, DetailedMetrics, LayoutProfile, ProcessMetrics, Session, SessionCategory, SystemMetrics};
-use crate::db::Database;
-use crate::hardware::{BaselineDetector, PowerMonitor};
+mod report;
+mod secrets;
+mod session_hook;
+mod updater;
+mod webhook;
+mod widget;
+
+use crate::core::analytics::{self, SessionInRange};
+use crate::core::insights::{self, LocalizedInsight};
+use crate::core::badge;
+use crate::core::app_usage::{AppUsage, AppUsageTracker, TOP_APPS};
+use crate::core::accuracy::{self, AccuracySample, EstimationAccuracy};
+use crate::core::clock::{self, ClockJumpDetector};
+use crate::core::config_watch::ConfigWatch;
+use crate::core::dashboard::{self, validate_dashboard, LayoutViolation, ViolationKind, WidgetPatch, WidgetSpec, WIDGET_CATALOG};
+use crate::core::log_throttle;
+use crate::core::notifier::{Notification, NotificationCenter, NotificationKind, Notifier};
+use crate::core::receipt;
+use crate::core::interval::{Cadence, CollectionTuner, RefreshRates, MIN_INTERVAL_MS};
+use crate::core::idle::InputIdle;
+use crate::core::session_idle::{self, IdleDecision, SessionIdleTracker};
+use crate::core::theme::{self, Appearance};
+use crate::core::timezone::HistoryZone;
+use crate::core::vram_alert::VramAlertTracker;
+use crate::core::{AppState, CommandError, ConfigRecovery, BaselineReport, ExchangeRate, BillingSummary, Config, CpuMetrics, CriticalMetrics, DetailedMetrics, DetailedPayload, Freshness, SessionIdleInput, LayoutProfile, MetricsFormatter, MonitoringHealth, PricingConfig, ProcessMetrics, SourceCapabilities, ProcessTotals, OTHER_PROCESSES_NAME, Session, SessionCategory, SessionTemplate, SystemMetrics, WebhookConfig, WidgetConfig, MAX_COST_DECIMALS, MAX_DUPLICATE_READING_WINDOW_SECS, MAX_PROCESS_LIST_LIMIT, MIN_COST_DECIMALS, MIN_PROCESS_LIST_LIMIT, validate_category_baseline};
+use crate::db::{DailyRate, DailyStats, Database, DayNote, DedupOutcome, LifetimeTotals, MAX_DAY_NOTE_CHARS, ResetCounts, ResetScope, SessionCategoryFilter, TimeFilter};
+use crate::hardware::benchmark::{self, BenchmarkReport};
+use crate::hardware::core_history::PerCoreHistory;
+use crate::hardware::process_history::PinnedProcessHistory;
+use crate::hardware::gpu_estimate::{self, GpuSuggestion};
+use crate::hardware::{active_window, attribution, capabilities, cgroup, process_filter, BaselineDetector, CoreHistory, GpuEstimate, MonitorOptions, PowerMonitor, PowerSanitizer, ProcessHistory};
 use crate::i18n::I18n;
-use crate::pricing::PricingEngine;
+use crate::pricing::{compute_cost_breakdown, compute_savings, CostBreakdown, PricingEngine, SavingsSummary};
+use crate::secrets::{SecretStore, SecretStoreInfo};
+use crate::updater::{UpdateCheckResult, UpdateChecker};
+use crate::webhook::WebhookNotifier;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use tauri::{Emitter, LogicalSize, Manager, PhysicalPosition, WebviewUrl, WebviewWindowBuilder};
 use tauri_plugin_opener::OpenerExt;
-use tauri::menu::{Menu, MenuItem};
+use tauri::menu::{CheckMenuItem, Menu, MenuItem};
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
 use tokio::sync::Mutex;
 
@@ -33,11 +66,126 @@ pub struct TauriState {
     pub i18n: Arc<Mutex<I18n>>,
     pub app_state: Arc<Mutex<AppState>>,
     pub baseline_detector: Arc<Mutex<BaselineDetector>>,
+    /// Sanity filter applied to every power reading
+    pub power_sanitizer: Arc<Mutex<PowerSanitizer>>,
     pub active_session: Arc<Mutex<Option<SessionState>>>,
     /// Cached critical metrics (updated at fast rate)
     pub critical_metrics_cache: Arc<Mutex<Option<CriticalMetrics>>>,
-    /// Cached detailed metrics (updated at slow rate)
-    pub detailed_metrics_cache: Arc<Mutex<Option<DetailedMetrics>>>,
+    /// Cached detailed metrics (updated at slow rate), shared with the payloads sent out
+    pub detailed_metrics_cache: Arc<Mutex<Option<Arc<DetailedMetrics>>>>,
+    /// Update check cache and retry backoff
+    pub update_checker: Arc<Mutex<UpdateChecker>>,
+    /// Confirmation token issued by `prepare_reset`
+    pub pending_reset: Arc<Mutex<Option<PendingReset>>>,
+    /// Last computed billing summary (refreshed about once a minute)
+    pub billing_cache: Arc<Mutex<Option<BillingSummary>>>,
+    /// Whether a window that shows live metrics is visible (main shown or widget open)
+    pub listeners_visible: Arc<AtomicBool>,
+    /// Update events skipped because nothing was visible
+    pub suppressed_emits: Arc<AtomicU64>,
+    /// Oldest critical and detailed metrics handed out, for the health report
+    pub max_critical_age_ms: Arc<AtomicU64>,
+    pub max_detailed_age_ms: Arc<AtomicU64>,
+    /// The data file could not be opened, `db` is in memory until `retry_database_init` succeeds
+    pub persistence_disabled: Arc<AtomicBool>,
+    /// How the config file was replaced at startup, read by the UI once it loads
+    pub config_recovery: Option<ConfigRecovery>,
+    /// Rates of the monitoring loops (config rates, slowed down by eco mode)
+    pub refresh_rates: Arc<RefreshRates>,
+    /// Hot config fields for the monitoring loops, published on every config change
+    pub config_watch: Arc<ConfigWatch>,
+    /// Integration credentials (keychain or obfuscated file fallback)
+    pub secrets: Arc<SecretStore>,
+    /// Scheduled report deliveries and their failures
+    pub webhook: Arc<Mutex<WebhookNotifier>>,
+    /// Tray menu entries, relabeled when the language changes (set in setup)
+    pub tray_menu: Arc<Mutex<Option<TrayMenu>>>,
+    /// Running and cancellation flags of `rebuild_history`
+    pub history_rebuild: Arc<HistoryRebuild>,
+    /// Recent per-core CPU usage (updated at slow rate)
+    pub core_history: Arc<Mutex<CoreHistory>>,
+    pub process_history: Arc<Mutex<ProcessHistory>>,
+    /// Time spent above the VRAM alert threshold
+    pub vram_alert: Arc<Mutex<VramAlertTracker>>,
+    /// Muting and quiet hours of alerts (set in setup, the backend needs the app handle)
+    pub notifications: Arc<Mutex<Option<NotificationCenter>>>,
+    /// What the power monitor can report, probed at startup and when it is recreated
+    pub capabilities: Arc<Mutex<Option<SourceCapabilities>>>,
+    /// Time since the last keyboard or mouse input, for session idle detection
+    pub input_idle: Arc<Mutex<InputIdle>>,
+    /// Today's daily stats as last read for the widget tooltip
+    pub today_stats_cache: Arc<Mutex<Option<TodayStatsCache>>>,
+    /// Bumped by every main window move or resize, a debounced geometry save only runs if it is still current
+    pub geometry_save_generation: Arc<AtomicU64>,
+}
+
+/// State of the daily stats rebuild started from the UI
+#[derive(Default)]
+pub struct HistoryRebuild {
+    running: AtomicBool,
+    cancel_requested: AtomicBool,
+}
+
+/// Clears the running flag when the rebuild ends, even on error
+struct HistoryRebuildGuard<'a>(&'a HistoryRebuild);
+
+impl Drop for HistoryRebuildGuard<'_> {
+    fn drop(&mut self) {
+        self.0.running.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Outcome of `get_history`
+#[derive(Clone, serde::Serialize)]
+struct HistoryDays {
+    days: Vec<DailyStats>,
+    /// Zone the days are in, see `HistoryZone::label`
+    timezone: String,
+}
+
+/// Zone history is bucketed in, the system zone if the saved value is not supported
+fn history_zone(config: &Config) -> HistoryZone {
+    HistoryZone::parse(&config.general.history_timezone).unwrap_or(HistoryZone::Local)
+}
+
+/// Payload of the `history-rebuild-progress` event
+#[derive(Clone, serde::Serialize)]
+struct HistoryRebuildProgress {
+    processed: usize,
+    total: usize,
+    date: String,
+}
+
+/// Outcome of `rebuild_history`
+#[derive(Clone, serde::Serialize)]
+struct HistoryRebuildResult {
+    processed: usize,
+    total: usize,
+    cancelled: bool,
+    /// Days without readings whose missing cost was filled in at their day's average rate
+    backfilled: usize,
+}
+
+/// Tray menu entries with translated labels
+pub struct TrayMenu {
+    show: MenuItem<tauri::Wry>,
+    restart: MenuItem<tauri::Wry>,
+    quit: MenuItem<tauri::Wry>,
+    low_power: CheckMenuItem<tauri::Wry>,
+}
+
+impl TrayMenu {
+    /// Relabel the entries in the current language
+    fn retranslate(&self, i18n: &I18n) {
+        for (item, key) in [(&self.show, "tray.show"), (&self.restart, "tray.restart"), (&self.quit, "tray.exit")] {
+            if let Err(e) = item.set_text(i18n.get(key)) {
+                log::warn!("Failed to relabel tray menu entry {}: {}", key, e);
+            }
+        }
+        if let Err(e) = self.low_power.set_text(i18n.get("tray.low_power")) {
+            log::warn!("Failed to relabel tray menu entry tray.low_power: {}", e);
+        }
+    }
 }
 
 /// State for an active tracking session
@@ -46,9 +194,153 @@ pub struct SessionState {
     pub baseline_watts: f64,
     pub total_wh: f64,
     pub surplus_wh: f64,
+    /// Surplus cost, each tick priced at the rate in effect when it was consumed
+    pub surplus_cost: f64,
     pub start_time: std::time::Instant,
+    /// Wall-clock start, as stored in the database (moved only after a backward clock jump)
+    pub started_at: i64,
     pub label: Option<String>,
     pub category: Option<String>,
+    pub target_cost: Option<f64>,
+    pub target_wh: Option<f64>,
+    /// Highest target threshold (80 or 100) already announced
+    pub target_notified: u8,
+    /// Last time the running totals were written to the database
+    pub last_heartbeat: std::time::Instant,
+    /// End the session automatically once it has run this long
+    pub auto_end_after: Option<std::time::Duration>,
+    /// End the session once it has been idle this many seconds
+    pub idle_after_secs: Option<i64>,
+    pub idle_tracker: SessionIdleTracker,
+    /// Totals (total Wh, surplus Wh, surplus cost) when the current idle stretch began
+    pub idle_snapshot: (f64, f64, f64),
+    /// Foreground time per app (only filled with advanced.track_active_window)
+    pub app_usage: AppUsageTracker,
+    /// Local day of the current part, for advanced.split_sessions_at_midnight
+    pub day: chrono::NaiveDate,
+    /// Session this part continues after a split at midnight
+    pub continued_from: Option<i64>,
+    /// Baseline schedule bucket in effect at the start
+    pub baseline_bucket: Option<String>,
+    /// Surplus Wh and cost of the earlier parts, so targets cover the whole chain
+    pub earlier_surplus: (f64, f64),
+}
+
+/// Interval between active session heartbeats written to the database
+const SESSION_HEARTBEAT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+impl SessionState {
+    /// Build the live session data sent to the frontend
+    fn to_session(&self) -> Session {
+        let mut session = Session {
+            id: Some(self.id),
+            start_time: self.started_at,
+            end_time: None,
+            baseline_watts: self.baseline_watts,
+            total_wh: self.total_wh,
+            surplus_wh: self.surplus_wh,
+            surplus_cost: self.surplus_cost,
+            label: self.label.clone(),
+            category: self.category.clone(),
+            target_cost: self.target_cost,
+            target_wh: self.target_wh,
+            target_progress_percent: None,
+            target_met: None,
+            auto_ended: false,
+            continued_from: self.continued_from,
+            baseline_bucket: self.baseline_bucket.clone(),
+            duration_secs: 0,
+            avg_watts: None,
+            avg_surplus_watts: None,
+            cost_per_hour: None,
+        };
+        let chain = Session {
+            surplus_wh: self.surplus_wh + self.earlier_surplus.0,
+            surplus_cost: self.surplus_cost + self.earlier_surplus.1,
+            ..session.clone()
+        };
+        session.target_progress_percent = chain.compute_target_progress();
+        session.compute_rates(chrono::Utc::now().timestamp());
+        session
+    }
+
+    /// Add a share of a tick's energy to the running totals
+    fn add_energy(&mut self, energy_wh: f64, surplus_wh: f64, surplus_cost: f64) {
+        self.total_wh += energy_wh;
+        self.surplus_wh += surplus_wh;
+        self.surplus_cost += surplus_cost;
+    }
+
+    /// Carry on as the continuation `id`, started at `at` on local day `day`
+    fn continue_as(&mut self, id: i64, at: i64, day: chrono::NaiveDate) {
+        self.continued_from = Some(self.id);
+        self.earlier_surplus.0 += self.surplus_wh;
+        self.earlier_surplus.1 += self.surplus_cost;
+        self.id = id;
+        self.started_at = at;
+        self.day = day;
+        self.total_wh = 0.0;
+        self.surplus_wh = 0.0;
+        self.surplus_cost = 0.0;
+        // An idle stretch restarts at the split, its snapshot belonged to the previous part
+        self.idle_tracker = SessionIdleTracker::new();
+        self.idle_snapshot = (0.0, 0.0, 0.0);
+        self.app_usage.clear();
+    }
+}
+
+/// Store the top foreground apps of a session, if any were recorded
+fn save_top_apps(db: &Database, session: &SessionState) {
+    if session.app_usage.is_empty() {
+        return;
+    }
+    if let Err(e) = db.set_session_top_apps(session.id, &session.app_usage.top(TOP_APPS)) {
+        log::warn!("Failed to store session apps: {}", e);
+    }
+}
+
+/// A session with its foreground app breakdown
+#[derive(Clone, serde::Serialize)]
+struct SessionDetail {
+    session: Session,
+    /// Apps with the most foreground time, longest first (empty when not tracked)
+    top_apps: Vec<AppUsage>,
+}
+
+/// Payload of the `session-auto-ended` event, sent when a session ends after inactivity or its planned duration
+#[derive(Clone, serde::Serialize)]
+struct SessionAutoEndedEvent {
+    session: Session,
+    /// Idle minutes trimmed from the end of the session, None for a planned duration
+    reclaimed_minutes: Option<f64>,
+}
+
+/// Payload of the `session-target-reached` event
+#[derive(Clone, serde::Serialize)]
+struct SessionTargetEvent {
+    session_id: i64,
+    threshold: u8,
+    progress_percent: f64,
+}
+
+/// Progress thresholds that trigger a `session-target-reached` event
+const SESSION_TARGET_THRESHOLDS: [u8; 2] = [80, 100];
+
+/// A statistics reset awaiting confirmation
+pub struct PendingReset {
+    pub token: String,
+    pub scope: ResetScope,
+    pub expires_at: std::time::Instant,
+}
+
+/// How long the widget tooltip reuses today's daily stats
+const TODAY_STATS_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Today's daily stats, read at most once per `TODAY_STATS_CACHE_TTL`
+pub struct TodayStatsCache {
+    pub date: String,
+    pub read_at: std::time::Instant,
+    pub stats: Option<DailyStats>,
 }
 
 // Tauri commands exposed to the frontend
@@ -95,174 +387,1270 @@ async fn get_dashboard_data(state: tauri::State<'_, TauriState>) -> Result<core:
 
     // Use session average power for estimates instead of instantaneous
     let session_duration_secs = app_state.session_start.elapsed().as_secs();
-    let avg_power_watts = if session_duration_secs > 0 {
-        app_state.cumulative_wh / (session_duration_secs as f64 / 3600.0)
-    } else {
-        power_watts
-    };
-
-    let hourly_cost = pricing.calculate_hourly_cost(avg_power_watts);
-    let daily_cost = pricing.calculate_daily_cost(avg_power_watts);
-    let monthly_cost = pricing.calculate_monthly_cost(avg_power_watts);
+    let avg_power_watts = pricing::average_power_watts(app_state.cumulative_wh, session_duration_secs, power_watts);
+    let estimates = pricing.cost_estimates(avg_power_watts);
 
     Ok(core::DashboardData {
         power_watts,
         avg_power_watts,
         cumulative_wh: app_state.cumulative_wh,
         current_cost: app_state.current_cost,
-        hourly_cost_estimate: hourly_cost,
-        daily_cost_estimate: daily_cost,
-        monthly_cost_estimate: monthly_cost,
+        hourly_cost_estimate: estimates.hourly,
+        daily_cost_estimate: estimates.daily,
+        monthly_cost_estimate: estimates.monthly,
         session_duration_secs,
         source: monitor.get_source_name().to_string(),
         is_estimated: monitor.is_estimated(),
     })
 }
 
-/// Get application configuration
+/// Get application configuration
+#[tauri::command]
+async fn get_config(state: tauri::State<'_, TauriState>) -> Result<Config, CommandError> {
+    let config = state.config.lock().await;
+    Ok(config.clone())
+}
+
+/// Check a config sent by the frontend before anything is applied
+fn validate_config(config: &Config) -> Result<(), CommandError> {
+    webhook::validate_config(&config.webhook).map_err(CommandError::invalid_input)?;
+    session_hook::validate_path(config.advanced.session_end_hook.as_deref()).map_err(CommandError::invalid_input)?;
+    if !(MIN_GPU_COMMAND_TIMEOUT_MS..=MAX_GPU_COMMAND_TIMEOUT_MS).contains(&config.advanced.gpu_command_timeout_ms) {
+        return Err(CommandError::invalid_input(format!(
+            "GPU command timeout must be between {} and {} ms",
+            MIN_GPU_COMMAND_TIMEOUT_MS, MAX_GPU_COMMAND_TIMEOUT_MS
+        )));
+    }
+    if config.pricing.secondary.manual_rate.is_some_and(|rate| !(rate.is_finite() && rate > 0.0)) {
+        return Err(CommandError::invalid_input("Exchange rate must be greater than zero"));
+    }
+    config.general.notifications.validate().map_err(CommandError::invalid_input)?;
+    if HistoryZone::parse(&config.general.history_timezone).is_none() {
+        return Err(CommandError::invalid_input(format!(
+            "Unsupported history time zone '{}': use local, UTC, a fixed offset such as UTC+13 or an IANA name such as Europe/Paris",
+            config.general.history_timezone
+        )));
+    }
+    config.advanced.baseline_schedule.validate().map_err(CommandError::invalid_input)?;
+    if config.advanced.gpu_idle_watts.is_some_and(|w| !(w.is_finite() && w >= 0.0))
+        || config.advanced.gpu_tdp_watts.is_some_and(|w| !(w.is_finite() && w >= 0.0))
+    {
+        return Err(CommandError::invalid_input("GPU power estimate must be zero or more watts"));
+    }
+    if !(accuracy::MIN_CORRECTION..=accuracy::MAX_CORRECTION).contains(&config.advanced.estimation_correction) {
+        return Err(CommandError::invalid_input(format!(
+            "Estimation correction must be between {} and {}",
+            accuracy::MIN_CORRECTION, accuracy::MAX_CORRECTION
+        )));
+    }
+    if !(MIN_PROCESS_LIST_LIMIT..=MAX_PROCESS_LIST_LIMIT).contains(&config.advanced.process_list_limit) {
+        return Err(CommandError::invalid_input(format!(
+            "Process list limit must be between {} and {}",
+            MIN_PROCESS_LIST_LIMIT, MAX_PROCESS_LIST_LIMIT
+        )));
+    }
+    if !(MIN_COST_DECIMALS..=MAX_COST_DECIMALS).contains(&config.general.cost_decimals) {
+        return Err(CommandError::invalid_input(format!(
+            "Cost decimals must be between {} and {}",
+            MIN_COST_DECIMALS, MAX_COST_DECIMALS
+        )));
+    }
+    if config.advanced.duplicate_reading_window_secs > MAX_DUPLICATE_READING_WINDOW_SECS {
+        return Err(CommandError::invalid_input(format!(
+            "Duplicate reading window must be between 0 and {} seconds",
+            MAX_DUPLICATE_READING_WINDOW_SECS
+        )));
+    }
+    if config.advanced.vram_alert_percent > 100 {
+        return Err(CommandError::invalid_input("VRAM alert threshold must be between 0 and 100%"));
+    }
+    if config.widget.refresh_rate_ms.is_some_and(|ms| ms < MIN_INTERVAL_MS) {
+        return Err(CommandError::invalid_input(format!(
+            "Widget refresh rate must be at least {} ms",
+            MIN_INTERVAL_MS
+        )));
+    }
+    Ok(())
+}
+
+/// Update application configuration, returns the config as stored
+///
+/// The new config is validated, staged and saved before it replaces the
+/// current one, so a failure leaves both the file and the engines untouched.
+/// The config lock is held until pricing, i18n and the monitoring settings
+/// follow, so concurrent updates cannot interleave. A `config-changed` event
+/// lists the top-level sections that changed.
+#[tauri::command]
+async fn set_config(app: tauri::AppHandle, state: tauri::State<'_, TauriState>, config: Config) -> Result<Config, CommandError> {
+    validate_config(&config)?;
+    let mut current_config = state.config.lock().await;
+
+    let mut staged = config;
+    // Categories are edited through their own commands, and the frontend may
+    // only hold the emoji-stripped copy
+    staged.advanced.session_categories = current_config.advanced.session_categories.clone();
+    staged.advanced.session_categories_seeded = current_config.advanced.session_categories_seeded;
+    staged.advanced.session_templates = current_config.advanced.session_templates.clone();
+    // The widget position is saved when the widget closes, picking another corner forgets it
+    if staged.widget.position == current_config.widget.position {
+        staged.widget.window_x = current_config.widget.window_x;
+        staged.widget.window_y = current_config.widget.window_y;
+    } else {
+        staged.widget.window_x = None;
+        staged.widget.window_y = None;
+    }
+    staged.normalize();
+    let stopped_tracking_apps = current_config.advanced.track_active_window && !staged.advanced.track_active_window;
+    let badge_toggled = current_config.general.show_taskbar_badge != staged.general.show_taskbar_badge;
+    let language_changed = current_config.general.language != staged.general.language;
+    let theme_changed = current_config.general.theme != staged.general.theme;
+    // The config mirrors the autostart entry, so a failed change keeps the old value
+    let path = Config::config_path()?;
+    let mut autostart_changed = false;
+    if current_config.general.start_with_system != staged.general.start_with_system {
+        match apply_autostart(&app, staged.general.start_with_system) {
+            Ok(()) => autostart_changed = true,
+            Err(e) => {
+                log::warn!("Failed to change autostart: {}", e);
+                staged.general.start_with_system = current_config.general.start_with_system;
+            }
+        }
+    }
+    let monitor_options = MonitorOptions::from_config(&staged);
+    let monitor_options_changed = MonitorOptions::from_config(&current_config) != monitor_options;
+
+    let changed = match current_config.replace_saved(staged, &path) {
+        Ok(changed) => changed,
+        Err(e) => {
+            // And the other way round: the entry goes back to what the saved config says
+            if autostart_changed {
+                if let Err(e) = apply_autostart(&app, current_config.general.start_with_system) {
+                    log::warn!("Failed to restore autostart: {}", e);
+                }
+            }
+            return Err(e.into());
+        }
+    };
+    let config = current_config.clone();
+
+    // Before publishing, the critical loop formats with the language of the display snapshot
+    state.i18n.lock().await.set_language(&config.general.language);
+    state.refresh_rates.apply_config(&config);
+    state.config_watch.publish(&config);
+    if let Some(center) = state.notifications.lock().await.as_mut() {
+        center.set_config(config.general.notifications.clone());
+    }
+    state.baseline_detector.lock().await.set_schedule(&config.advanced.baseline_schedule);
+    state.pricing.lock().await.update_config(&config.pricing);
+    let i18n = state.i18n.lock().await;
+    drop(current_config);
+
+    // Relabel what Rust built with the old language and let every window reload its strings
+    if language_changed {
+        if let Some(tray) = state.tray_menu.lock().await.as_ref() {
+            tray.retranslate(&i18n);
+        }
+        if let Some(widget) = app.get_webview_window("widget") {
+            let _ = widget.set_title(&i18n.get("widget.window_title"));
+        }
+        let _ = app.emit("language-changed", i18n.get_all());
+        log::info!("Language changed to {}", i18n.current_language());
+    }
+    drop(i18n);
+    let mut monitor = state.monitor.lock().await;
+    monitor.set_gpu_estimate(GpuEstimate::from_config(&config.advanced));
+    monitor.set_estimation_correction(config.advanced.estimation_correction);
+    drop(monitor);
+    state.db.lock().await.set_duplicate_window_secs(config.advanced.duplicate_reading_window_secs);
+
+    // Turning app tracking off wipes what it recorded
+    if stopped_tracking_apps {
+        if let Some(session) = state.active_session.lock().await.as_mut() {
+            session.app_usage.clear();
+        }
+        let cleared = state.db.lock().await.clear_session_top_apps()?;
+        log::info!("Active app tracking disabled, cleared {} sessions", cleared);
+    }
+
+    if badge_toggled {
+        refresh_taskbar_badge(&app, &state).await;
+    }
+
+    // The Windows monitor reads its subprocess settings when it is created
+    if monitor_options_changed && cfg!(target_os = "windows") {
+        reinitialize_monitor(&state, monitor_options).await;
+    }
+
+    if !changed.is_empty() {
+        let _ = app.emit("config-changed", &changed);
+    }
+    if theme_changed {
+        let _ = app.emit("theme-changed", resolve_theme(&config.general.theme).await);
+    }
+    Ok(config)
+}
+
+/// Appearance of a theme setting, the OS preference being read off the async runtime
+async fn resolve_theme(setting: &str) -> Appearance {
+    let system = if theme::follows_system(setting) {
+        tauri::async_runtime::spawn_blocking(theme::system_appearance).await.ok().flatten()
+    } else {
+        None
+    };
+    theme::resolve(setting, system)
+}
+
+/// Light or dark appearance of the theme setting, "system" resolved from the OS preference
+#[tauri::command]
+async fn get_resolved_theme(state: tauri::State<'_, TauriState>) -> Result<Appearance, CommandError> {
+    let setting = state.config.lock().await.general.theme.clone();
+    Ok(resolve_theme(&setting).await)
+}
+
+/// Accepted range of `advanced.gpu_command_timeout_ms`
+const MIN_GPU_COMMAND_TIMEOUT_MS: u64 = 250;
+const MAX_GPU_COMMAND_TIMEOUT_MS: u64 = 10_000;
+
+/// Recreate the power monitor with new options (source detection runs again), returns its capabilities
+async fn reinitialize_monitor(state: &TauriState, options: MonitorOptions) -> SourceCapabilities {
+    let monitor = tauri::async_runtime::spawn_blocking(move || PowerMonitor::new(&options))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result.map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to reinitialize power monitor: {}", e);
+            PowerMonitor::estimation_fallback()
+        });
+
+    let db = state.db.lock().await;
+    if let Err(e) = db.record_source(monitor.get_source_name(), monitor.is_estimated()) {
+        log::warn!("Failed to record power source: {}", e);
+    }
+    drop(db);
+
+    let mut monitor = monitor;
+    let advanced = state.config.lock().await.advanced.clone();
+    monitor.set_gpu_estimate(GpuEstimate::from_config(&advanced));
+    monitor.set_estimation_correction(advanced.estimation_correction);
+    log::info!("Power monitor reinitialized: {}", monitor.get_source_name());
+    *state.monitor.lock().await = monitor;
+    refresh_capabilities(state).await
+}
+
+/// Probe what the power monitor can report and cache it (may run GPU tools)
+async fn refresh_capabilities(state: &TauriState) -> SourceCapabilities {
+    let mut found = capabilities::probe(&*state.monitor.lock().await);
+    let input_idle = state.input_idle.clone();
+    found.input_idle = tauri::async_runtime::spawn_blocking(move || input_idle.blocking_lock().available())
+        .await
+        .unwrap_or(false);
+    log::info!("Power source capabilities: {:?}", found);
+    *state.capabilities.lock().await = Some(found.clone());
+    found
+}
+
+/// Get what the power monitor can report, probed once and cached
+#[tauri::command]
+async fn get_capabilities(state: tauri::State<'_, TauriState>) -> Result<SourceCapabilities, CommandError> {
+    let cached = state.capabilities.lock().await.clone();
+    match cached {
+        Some(found) => Ok(found),
+        None => Ok(refresh_capabilities(&state).await),
+    }
+}
+
+/// Recreate the power monitor (source detection runs again), returns its capabilities
+#[tauri::command]
+async fn reinitialize_power_monitor(state: tauri::State<'_, TauriState>) -> Result<SourceCapabilities, CommandError> {
+    let options = MonitorOptions::from_config(&*state.config.lock().await);
+    Ok(reinitialize_monitor(&state, options).await)
+}
+
+/// Get translated string
+#[tauri::command]
+async fn translate(state: tauri::State<'_, TauriState>, key: String) -> Result<String, String> {
+    let i18n = state.i18n.lock().await;
+    Ok(i18n.get(&key))
+}
+
+/// Get all translations for current language
+#[tauri::command]
+async fn get_translations(state: tauri::State<'_, TauriState>) -> Result<std::collections::HashMap<String, String>, String> {
+    let i18n = state.i18n.lock().await;
+    Ok(i18n.get_all())
+}
+
+/// Get historical data for a date range, in days of the history time zone
+///
+/// With an hour range or weekdays, or a zone other than the system one, the
+/// stats are computed from the matching readings (not stored), each reading
+/// costed at the rate in effect at its time.
+#[tauri::command]
+async fn get_history(
+    state: tauri::State<'_, TauriState>,
+    start_date: String,
+    end_date: String,
+    hour_start: Option<u32>,
+    hour_end: Option<u32>,
+    weekdays: Option<Vec<u32>>,
+) -> Result<HistoryDays, CommandError> {
+    let filter = time_filter(hour_start, hour_end, weekdays)?;
+    let db = state.db.lock().await;
+    let config = state.config.lock().await;
+    let pricing_mode = config.pricing.mode.clone();
+    let zone = history_zone(&config);
+    drop(config);
+
+    // Get current rate from pricing engine
+    let rate_per_kwh = {
+        let pricing = state.pricing.lock().await;
+        pricing.get_current_rate()
+    };
+
+    let timezone = zone.label();
+    if !filter.is_empty() || zone != HistoryZone::Local {
+        // Each reading at the rate of its time, like the stored daily stats
+        let pricing = state.pricing.lock().await;
+        let rate_at = |ts: i64| pricing.rate_at_timestamp(ts);
+        let days = db.get_daily_stats_filtered_in(&start_date, &end_date, &filter, DailyRate::AtTime(&rate_at), &zone)?;
+        return Ok(HistoryDays { days, timezone });
+    }
+
+    // Update today's stats before fetching to ensure fresh data
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    if start_date <= today && end_date >= today {
+        let _ = db.update_today_stats(Some(&pricing_mode), Some(rate_per_kwh));
+    }
+
+    let days = db.get_daily_stats(&start_date, &end_date)?;
+    Ok(HistoryDays { days, timezone })
+}
+
+/// Check a YYYY-MM-DD date given to a command
+fn validate_day(date: &str) -> Result<(), CommandError> {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|_| ())
+        .map_err(|_| CommandError::invalid_input(format!("Invalid date '{}', expected YYYY-MM-DD", date)))
+}
+
+/// Add or replace the note of a day (plain text, trimmed)
+#[tauri::command]
+async fn set_day_note(state: tauri::State<'_, TauriState>, date: String, note: String) -> Result<DayNote, CommandError> {
+    validate_day(&date)?;
+    let note = note.trim();
+    if note.is_empty() {
+        return Err(CommandError::invalid_input("Note must not be empty"));
+    }
+    if note.chars().count() > MAX_DAY_NOTE_CHARS {
+        return Err(CommandError::invalid_input(format!("Note must be at most {} characters", MAX_DAY_NOTE_CHARS)));
+    }
+    let db = state.db.lock().await;
+    db.set_day_note(&date, note)?;
+    db.get_day_notes(&date, &date)?
+        .pop()
+        .ok_or_else(|| CommandError::not_found(format!("No note for {}", date)))
+}
+
+/// Energy, cost, time and sessions tracked since the first day
+#[tauri::command]
+async fn get_lifetime_totals(state: tauri::State<'_, TauriState>) -> Result<LifetimeTotals, CommandError> {
+    Ok(state.db.lock().await.get_lifetime_totals()?)
+}
+
+/// Notes of the days from `start_date` to `end_date` included
+#[tauri::command]
+async fn get_day_notes(state: tauri::State<'_, TauriState>, start_date: String, end_date: String) -> Result<Vec<DayNote>, CommandError> {
+    validate_day(&start_date)?;
+    validate_day(&end_date)?;
+    Ok(state.db.lock().await.get_day_notes(&start_date, &end_date)?)
+}
+
+/// Delete the note of a day, false if it had none
+#[tauri::command]
+async fn delete_day_note(state: tauri::State<'_, TauriState>, date: String) -> Result<bool, CommandError> {
+    validate_day(&date)?;
+    Ok(state.db.lock().await.delete_day_note(&date)?)
+}
+
+/// Get power readings for a time range (for graphs)
+///
+/// With an hour range or weekdays, only matching readings are returned,
+/// averaged into at most `max_points` buckets.
+#[tauri::command]
+async fn get_readings(
+    state: tauri::State<'_, TauriState>,
+    start_timestamp: i64,
+    end_timestamp: i64,
+    hour_start: Option<u32>,
+    hour_end: Option<u32>,
+    weekdays: Option<Vec<u32>>,
+    max_points: Option<u32>,
+) -> Result<Vec<db::PowerReadingRecord>, CommandError> {
+    let filter = time_filter(hour_start, hour_end, weekdays)?;
+    let zone = history_zone(&*state.config.lock().await);
+    let db = state.db.lock().await;
+    if filter.is_empty() && max_points.is_none() {
+        return db.get_readings(start_timestamp, end_timestamp).map_err(CommandError::from);
+    }
+    db.get_readings_filtered_in(start_timestamp, end_timestamp, &filter, max_points.unwrap_or(DEFAULT_READING_POINTS), &zone)
+        .map_err(CommandError::from)
+}
+
+/// Default number of points returned by filtered `get_readings`
+const DEFAULT_READING_POINTS: u32 = 500;
+
+/// Build and validate a time-of-day filter from command parameters
+fn time_filter(hour_start: Option<u32>, hour_end: Option<u32>, weekdays: Option<Vec<u32>>) -> Result<TimeFilter, CommandError> {
+    let filter = TimeFilter { hour_start, hour_end, weekdays };
+    filter.validate().map_err(CommandError::invalid_input)?;
+    Ok(filter)
+}
+
+/// Default number of points returned by `get_temperature_history`
+const DEFAULT_TEMPERATURE_POINTS: u32 = 500;
+
+/// Get CPU/GPU temperature history for a time range (downsampled for graphs)
+#[tauri::command]
+async fn get_temperature_history(
+    state: tauri::State<'_, TauriState>,
+    start_timestamp: i64,
+    end_timestamp: i64,
+    max_points: Option<u32>,
+) -> Result<Vec<db::TemperatureReadingRecord>, CommandError> {
+    let db = state.db.lock().await;
+    db.get_temperature_history(start_timestamp, end_timestamp, max_points.unwrap_or(DEFAULT_TEMPERATURE_POINTS))
+        .map_err(CommandError::from)
+}
+
+/// Default number of points returned by `get_vram_history`
+const DEFAULT_VRAM_POINTS: u32 = 500;
+
+/// Get GPU memory usage history for a time range (downsampled for graphs)
+#[tauri::command]
+async fn get_vram_history(
+    state: tauri::State<'_, TauriState>,
+    start_timestamp: i64,
+    end_timestamp: i64,
+    max_points: Option<u32>,
+) -> Result<Vec<db::VramReadingRecord>, CommandError> {
+    let db = state.db.lock().await;
+    db.get_vram_history(start_timestamp, end_timestamp, max_points.unwrap_or(DEFAULT_VRAM_POINTS))
+        .map_err(CommandError::from)
+}
+
+/// Get power source changes for a time range (first entry is the source in effect at the start)
+#[tauri::command]
+async fn get_source_history(
+    state: tauri::State<'_, TauriState>,
+    start_timestamp: i64,
+    end_timestamp: i64,
+) -> Result<Vec<db::SourceChangeRecord>, CommandError> {
+    let db = state.db.lock().await;
+    db.get_source_history(start_timestamp, end_timestamp)
+        .map_err(CommandError::from)
+}
+
+/// Open the data file again after falling back to memory, keeping the rows recorded meanwhile
+///
+/// Returns the number of rows moved to the file.
+#[tauri::command]
+async fn retry_database_init(app: tauri::AppHandle, state: tauri::State<'_, TauriState>) -> Result<u64, CommandError> {
+    if !state.persistence_disabled.load(Ordering::Relaxed) {
+        return Ok(0);
+    }
+    let file = Database::new()?;
+    let (pricing_mode, duplicate_window) = {
+        let config = state.config.lock().await;
+        (config.pricing.mode.clone(), config.advanced.duplicate_reading_window_secs)
+    };
+    file.set_duplicate_window_secs(duplicate_window);
+    let rate = state.pricing.lock().await.get_current_rate();
+
+    // Same lock order as reset_statistics, the running session follows its row to the new id
+    let mut active = state.active_session.lock().await;
+    let mut db = state.db.lock().await;
+    let copied = db.copy_into(&file, Some(&pricing_mode), Some(rate))?;
+    if let Some(session) = active.as_mut() {
+        if let Some(&id) = copied.session_ids.get(&session.id) {
+            session.id = id;
+        }
+    }
+    *db = file;
+    state.persistence_disabled.store(false, Ordering::Relaxed);
+    drop(db);
+    drop(active);
+
+    log::info!("Database opened, {} rows kept from memory", copied.rows);
+    let _ = app.emit("persistence-restored", copied.rows);
+    Ok(copied.rows)
+}
+
+/// Default estimate for a discrete GPU the estimating power source leaves out
+///
+/// None when readings are measured or no discrete GPU is found. The GPU is
+/// looked up once (lspci, or Win32_VideoController through PowerShell).
+#[tauri::command]
+async fn get_gpu_estimate_suggestion(state: tauri::State<'_, TauriState>) -> Result<Option<GpuSuggestion>, CommandError> {
+    if !state.monitor.lock().await.is_estimated() {
+        return Ok(None);
+    }
+    let allow_powershell = !state.config.lock().await.advanced.disable_powershell_probes;
+    let name = tauri::async_runtime::spawn_blocking(move || gpu_estimate::detect_discrete_gpu(allow_powershell))
+        .await
+        .map_err(|e| CommandError::from(e.to_string()))?;
+    Ok(name.as_deref().and_then(gpu_estimate::suggest_for))
+}
+
+/// Get counters of the power reading sanity filter
+#[tauri::command]
+async fn get_monitoring_health(state: tauri::State<'_, TauriState>) -> Result<MonitoringHealth, String> {
+    let mut health = state.power_sanitizer.lock().await.health().clone();
+    health.emit_suppressed = !state.listeners_visible.load(Ordering::Relaxed);
+    health.suppressed_emits = state.suppressed_emits.load(Ordering::Relaxed);
+    let webhook = state.webhook.lock().await;
+    health.webhook_failures = webhook.failures();
+    health.webhook_last_error = webhook.last_error();
+    health.webhook_last_success_at = webhook.last_success_at();
+    health.nvml_reinit_attempts = hardware::nvml_reinit_attempts();
+    health.suppressed_log_messages = log_throttle::suppressed_count();
+    health.low_power_mode = state.refresh_rates.low_power();
+    health.instance_lock_port = instance::lock_port();
+    health.instance_handoffs = instance::handoffs();
+    health.max_critical_age_ms = state.max_critical_age_ms.load(Ordering::Relaxed);
+    health.max_detailed_age_ms = state.max_detailed_age_ms.load(Ordering::Relaxed);
+    health.persistence_disabled = state.persistence_disabled.load(Ordering::Relaxed);
+    health.config_recovery = state.config_recovery.clone();
+    health.estimation_correction = accuracy::clamp_correction(state.config.lock().await.advanced.estimation_correction);
+    health.capabilities = state.capabilities.lock().await.clone();
+    health.configured_detailed_refresh_ms = state.refresh_rates.configured_detailed_ms();
+    health.effective_detailed_refresh_ms = state.refresh_rates.detailed_ms();
+    Ok(health)
+}
+
+/// Error of the estimator against the measuring source, over the last week of samples
+#[tauri::command]
+async fn get_estimation_accuracy(state: tauri::State<'_, TauriState>) -> Result<EstimationAccuracy, CommandError> {
+    let applied = accuracy::clamp_correction(state.config.lock().await.advanced.estimation_correction);
+    let samples = state.db.lock().await.get_accuracy_samples(accuracy::SUMMARY_WINDOW)?;
+    Ok(EstimationAccuracy::from_samples(&samples, applied))
+}
+
+/// Show today's cost on the taskbar icon, or clear it when the badge is off
+///
+/// Called right after today's daily stats are refreshed from the readings,
+/// so they include the energy of the last minute.
+async fn refresh_taskbar_badge(app: &tauri::AppHandle, state: &TauriState) {
+    let cost = if state.config.lock().await.general.show_taskbar_badge {
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        match state.db.lock().await.get_daily_stats(&today, &today) {
+            Ok(days) => days.first().and_then(|d| d.total_cost).unwrap_or(0.0),
+            Err(e) => {
+                log::debug!("Failed to read today's cost for the badge: {}", e);
+                return;
+            }
+        }
+    } else {
+        0.0
+    };
+    set_taskbar_badge(app, cost);
+}
+
+fn set_taskbar_badge(app: &tauri::AppHandle, cost: f64) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    #[cfg(target_os = "windows")]
+    let result = window.set_overlay_icon(badge::label(cost).map(|label| {
+        let image = badge::render(&label);
+        tauri::image::Image::new_owned(image.to_rgba(), image.width, image.height)
+    }));
+    #[cfg(target_os = "macos")]
+    let result = window.set_badge_label(badge::label(cost));
+    // Unity launcher API, ignored by desktops without it
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let result = window.set_badge_count(badge::count(cost));
+    if let Err(e) = result {
+        log::debug!("Failed to update the taskbar badge: {}", e);
+    }
+}
+
+// ===== Event Emission =====
+
+/// Refresh `listeners_visible` from the window states and return it
+fn update_listener_visibility(app: &tauri::AppHandle, state: &TauriState) -> bool {
+    let main = app.get_webview_window("main");
+    let main_visible = main.as_ref().and_then(|w| w.is_visible().ok()).unwrap_or(false);
+    let widget_open = app.get_webview_window("widget").and_then(|w| w.is_visible().ok()).unwrap_or(false);
+    let visible = main_visible || widget_open;
+    state.listeners_visible.store(visible, Ordering::Relaxed);
+
+    // Eco mode also slows down while the main window is minimized
+    let main_minimized = main.as_ref().and_then(|w| w.is_minimized().ok()).unwrap_or(false);
+    state.refresh_rates.set_hidden(!widget_open && (!main_visible || main_minimized));
+    visible
+}
+
+/// Emit a metrics event, unless no window is visible to receive it or low-power mode is on
+fn emit_if_visible<S: serde::Serialize + Clone>(app: &tauri::AppHandle, state: &TauriState, event: &str, payload: S) {
+    if update_listener_visibility(app, state) && !state.refresh_rates.low_power() {
+        let _ = app.emit(event, payload);
+    } else {
+        state.suppressed_emits.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Emit a session notification event, unless low-power mode is on
+fn emit_notification<S: serde::Serialize + Clone>(app: &tauri::AppHandle, state: &TauriState, event: &str, payload: S) {
+    if !state.refresh_rates.low_power() {
+        let _ = app.emit(event, payload);
+    }
+}
+
+/// Notifications shown by the webview: a toast, or a system notification while the window is hidden
+///
+/// Nothing is shown in low-power mode, like the other session events.
+struct TauriNotifier {
+    app: tauri::AppHandle,
+    refresh_rates: Arc<RefreshRates>,
+}
+
+impl Notifier for TauriNotifier {
+    fn show(&self, notification: &Notification) {
+        if !self.refresh_rates.low_power() {
+            let _ = self.app.emit("notification", notification);
+        }
+    }
+}
+
+/// Localize an alert and show it, unless its kind is muted or quiet hours are on
+async fn notify(state: &TauriState, kind: NotificationKind, title_key: &str, body_key: &str, args: &[(&str, String)]) {
+    let (title, body) = {
+        let i18n = state.i18n.lock().await;
+        (i18n.get(title_key), i18n.format(body_key, args))
+    };
+    if let Some(center) = state.notifications.lock().await.as_ref() {
+        center.notify(kind, &title, &body);
+    }
+}
+
+/// Show a notification of `kind` whatever the settings, to check the OS displays them
+#[tauri::command]
+async fn test_notification(state: tauri::State<'_, TauriState>, kind: String) -> Result<(), CommandError> {
+    let kind: NotificationKind = kind.parse().map_err(CommandError::invalid_input)?;
+    let (title, body) = {
+        let i18n = state.i18n.lock().await;
+        (i18n.get("notification.test_title"), i18n.get(kind.label_key()))
+    };
+    match state.notifications.lock().await.as_ref() {
+        Some(center) => center.show(kind, &title, &body),
+        None => return Err(CommandError::from("Notifications are not ready yet")),
+    }
+    Ok(())
+}
+
+/// Push the widget its configured items
+async fn emit_widget_update(app: &tauri::AppHandle, state: &TauriState, config: &WidgetConfig, critical: &CriticalMetrics) {
+    let update = {
+        let detailed = state.detailed_metrics_cache.lock().await;
+        let system = detailed.as_ref().and_then(|m| m.system_metrics.as_ref());
+        widget::WidgetUpdate::build(config, critical, system)
+    };
+    let _ = app.emit_to("widget", "widget-update", update);
+}
+
+/// Push the widget its items right away, called by the widget once it listens
+#[tauri::command]
+async fn request_widget_update(app: tauri::AppHandle, state: tauri::State<'_, TauriState>) -> Result<(), CommandError> {
+    let critical = state.critical_metrics_cache.lock().await.clone();
+    if let Some(critical) = critical {
+        let config = state.config.lock().await.widget.clone();
+        emit_widget_update(&app, &state, &config, &critical).await;
+    }
+    Ok(())
+}
+
+/// Summary shown when hovering the widget, from the cached metrics (None before the first reading)
+#[tauri::command]
+async fn get_widget_tooltip(state: tauri::State<'_, TauriState>) -> Result<Option<widget::WidgetTooltip>, CommandError> {
+    let Some(critical) = state.critical_metrics_cache.lock().await.clone() else {
+        return Ok(None);
+    };
+    let critical = stamp_critical(&state, critical);
+    let currency_symbol = state.config.lock().await.pricing.currency_symbol.clone();
+    let (rate_name_key, rate) = {
+        let pricing = state.pricing.lock().await;
+        (pricing.rate_name_key_at(chrono::Local::now().naive_local()), pricing.get_current_rate())
+    };
+    let today = cached_today_stats(&state).await;
+    let i18n = state.i18n.lock().await;
+    Ok(Some(widget::WidgetTooltip::build(&i18n, &critical, today.as_ref(), rate_name_key, rate, &currency_symbol)))
+}
+
+/// Today's daily stats, read from the database when the cached copy is old or from another day
+async fn cached_today_stats(state: &TauriState) -> Option<DailyStats> {
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let mut cache = state.today_stats_cache.lock().await;
+    if let Some(cached) = cache.as_ref().filter(|c| c.date == today && c.read_at.elapsed() < TODAY_STATS_CACHE_TTL) {
+        return cached.stats.clone();
+    }
+    let stats = match state.db.lock().await.get_daily_stats(&today, &today) {
+        Ok(days) => days.into_iter().next(),
+        Err(e) => {
+            log::debug!("Failed to read today's stats for the widget tooltip: {}", e);
+            return cache.as_ref().filter(|c| c.date == today).and_then(|c| c.stats.clone());
+        }
+    };
+    *cache = Some(TodayStatsCache { date: today, read_at: std::time::Instant::now(), stats: stats.clone() });
+    stats
+}
+
+/// Per-core usage of the last detailed updates, for the CPU detail view
+#[tauri::command]
+async fn get_per_core_history(state: tauri::State<'_, TauriState>) -> Result<PerCoreHistory, CommandError> {
+    Ok(state.core_history.lock().await.snapshot())
+}
+
+/// Push the cached metrics right away, so a window that was just shown doesn't wait a tick
+async fn emit_cached_metrics(app: &tauri::AppHandle) {
+    let state: tauri::State<'_, TauriState> = app.state();
+    state.listeners_visible.store(true, Ordering::Relaxed);
+    state.refresh_rates.set_hidden(false);
+    if state.refresh_rates.low_power() {
+        return;
+    }
+    let critical = state.critical_metrics_cache.lock().await.clone();
+    if let Some(metrics) = critical {
+        let _ = app.emit("critical-update", stamp_critical(&state, metrics));
+    }
+    let detailed = state.detailed_metrics_cache.lock().await.clone();
+    if let Some(metrics) = detailed {
+        let _ = app.emit("detailed-update", stamp_detailed(&state, &metrics));
+    }
+}
+
+/// Show and focus the main window, then refresh it with the latest metrics
+fn show_main_window(app: &tauri::AppHandle) -> bool {
+    let Some(window) = app.get_webview_window("main") else {
+        return false;
+    };
+    let _ = window.show();
+    let _ = window.set_focus();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        emit_cached_metrics(&app).await;
+    });
+    true
+}
+
+/// Enter or leave low-power mode
+///
+/// The critical loop keeps integrating energy at a slower rate, the detailed
+/// loop pauses, no event is emitted and the widget is hidden until the mode ends.
+async fn apply_low_power_mode(app: &tauri::AppHandle, enabled: bool) {
+    let state: tauri::State<'_, TauriState> = app.state();
+    state.refresh_rates.set_low_power(enabled);
+    if let Some(widget) = app.get_webview_window("widget") {
+        let _ = if enabled { widget.hide() } else { widget.show() };
+    }
+    if let Some(tray) = state.tray_menu.lock().await.as_ref() {
+        if let Err(e) = tray.low_power.set_checked(enabled) {
+            log::warn!("Failed to update low-power tray entry: {}", e);
+        }
+    }
+    if !enabled && update_listener_visibility(app, &state) {
+        emit_cached_metrics(app).await;
+    }
+    log::info!("Low-power mode {}", if enabled { "enabled" } else { "disabled" });
+}
+
+/// Serializes widget creation so startup and frontend calls can't both build it
+static WIDGET_OPEN_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Create the widget window unless it already exists
+fn open_widget_window(app: &tauri::AppHandle, widget: &WidgetConfig, widget_title: &str) -> Result<(), String> {
+    let _guard = WIDGET_OPEN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    // Check if widget is already open
+    if app.get_webview_window("widget").is_some() {
+        return Ok(());
+    }
+
+    // Calculate position based on config
+    let (x, y) = match widget.position.as_str() {
+        "top_left" => (20.0, 20.0),
+        "top_right" => (1200.0, 20.0),  // Will be adjusted by screen size
+        "bottom_left" => (20.0, 700.0),
+        "bottom_right" => (1200.0, 700.0),
+        _ => (20.0, 20.0),
+    };
+
+    // Create widget window, only asking for what the platform supports
+    let caps = widget::capabilities();
+    let mut builder = WebviewWindowBuilder::new(app, "widget", WebviewUrl::App("widget.html".into()))
+        .title(widget_title)
+        .inner_size(180.0, 70.0)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(caps.always_on_top)
+        .transparent(true)
+        .skip_taskbar(caps.skip_taskbar);
+    if caps.positioning {
+        builder = builder.position(x, y);
+    }
+    if caps.session_type == widget::SessionType::Wayland {
+        // Don't steal focus from the app the user is working in
+        builder = builder.focused(false);
+    }
+    if let Some(ref warning) = caps.warning {
+        log::warn!("Widget: {}", warning);
+    }
+    let widget_window = builder.build().map_err(|e| e.to_string())?;
+
+    // Reopen where it was closed, unless that monitor is gone
+    if let (true, Some(x), Some(y)) = (caps.positioning, widget.window_x, widget.window_y) {
+        if on_any_monitor(&widget_window, x, y, 180.0, 70.0) {
+            let _ = widget_window.set_position(PhysicalPosition::new(x as i32, y as i32));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether part of a `width` x `height` window at (`x`, `y`) shows on a monitor (physical pixels)
+fn on_any_monitor(window: &tauri::WebviewWindow, x: f64, y: f64, width: f64, height: f64) -> bool {
+    window
+        .available_monitors()
+        .map(|monitors| {
+            monitors.iter().any(|m| {
+                let (pos, size) = (m.position(), m.size());
+                let (mx, my) = (pos.x as f64, pos.y as f64);
+                x < mx + size.width as f64 && x + width > mx && y < my + size.height as f64 && y + height > my
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// Persist the position of the widget being closed, and that it is closed if `remember_open_state` is set
+async fn remember_widget_closed(app: &tauri::AppHandle, position: Option<PhysicalPosition<i32>>) {
+    let state: tauri::State<'_, TauriState> = app.state();
+    let mut config = state.config.lock().await;
+
+    let mut changed = false;
+    if let Some(position) = position {
+        let saved = (Some(position.x as f64), Some(position.y as f64));
+        changed |= (config.widget.window_x, config.widget.window_y) != saved;
+        (config.widget.window_x, config.widget.window_y) = saved;
+    }
+    if config.widget.remember_open_state && config.widget.enabled {
+        config.widget.enabled = false;
+        changed = true;
+    }
+
+    if changed {
+        if let Err(e) = config.save() {
+            log::warn!("Failed to save widget state: {}", e);
+        }
+        state.config_watch.publish(&config);
+    }
+}
+
+/// Persist the widget open state if `remember_open_state` is set
+async fn remember_widget_open_state(app: &tauri::AppHandle, open: bool) {
+    let state: tauri::State<'_, TauriState> = app.state();
+    let mut config = state.config.lock().await;
+
+    if !config.widget.remember_open_state || config.widget.enabled == open {
+        return;
+    }
+
+    config.widget.enabled = open;
+    if let Err(e) = config.save() {
+        log::warn!("Failed to save widget state: {}", e);
+    }
+    state.config_watch.publish(&config);
+}
+
+/// Open the widget window
+#[tauri::command]
+async fn open_widget(app: tauri::AppHandle, state: tauri::State<'_, TauriState>) -> Result<(), String> {
+    let widget = state.config.lock().await.widget.clone();
+    let title = state.i18n.lock().await.get("widget.window_title");
+    open_widget_window(&app, &widget, &title)?;
+    remember_widget_open_state(&app, true).await;
+    Ok(())
+}
+
+/// Get what the widget window supports on this platform (always-on-top, positioning...)
+#[tauri::command]
+fn get_widget_capabilities() -> widget::WidgetCapabilities {
+    widget::capabilities()
+}
+
+/// Close the widget window
+#[tauri::command]
+async fn close_widget(app: tauri::AppHandle) -> Result<(), String> {
+    if let Some(widget) = app.get_webview_window("widget") {
+        widget.close().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Toggle widget visibility
+#[tauri::command]
+async fn toggle_widget(app: tauri::AppHandle, state: tauri::State<'_, TauriState>) -> Result<bool, String> {
+    if let Some(widget) = app.get_webview_window("widget") {
+        widget.close().map_err(|e| e.to_string())?;
+        Ok(false)
+    } else {
+        open_widget(app, state).await?;
+        Ok(true)
+    }
+}
+
+// ===== Pricing Presets =====
+
+/// Get the list of country pricing presets
+#[tauri::command]
+fn get_pricing_presets() -> Vec<pricing::PricingPreset> {
+    pricing::PRICING_PRESETS.to_vec()
+}
+
+/// Fill the pricing config from a country preset, returns the updated config
+#[tauri::command]
+async fn apply_pricing_preset(state: tauri::State<'_, TauriState>, country_code: String) -> Result<Config, String> {
+    let preset = pricing::find_preset(&country_code)
+        .ok_or_else(|| format!("Unknown pricing preset: {}", country_code))?;
+
+    let mut config = state.config.lock().await;
+    let mut updated = config.clone();
+    pricing::apply_preset(&mut updated.pricing, preset);
+    // Picking a preset settles the first-run guess
+    updated.pricing.auto_region = None;
+
+    // Only commit in memory once the file is written
+    updated.save().map_err(|e| e.to_string())?;
+    *config = updated;
+    state.config_watch.publish(&config);
+
+    let mut pricing = state.pricing.lock().await;
+    pricing.update_config(&config.pricing);
+
+    Ok(config.clone())
+}
+
+/// Keep the pricing picked from the system region on first run
+#[tauri::command]
+async fn confirm_region_defaults(state: tauri::State<'_, TauriState>) -> Result<Config, String> {
+    let mut config = state.config.lock().await;
+    if config.pricing.auto_region.is_some() {
+        let mut updated = config.clone();
+        updated.pricing.auto_region = None;
+        updated.save().map_err(|e| e.to_string())?;
+        *config = updated;
+    }
+    Ok(config.clone())
+}
+
+// ===== Billing Commands =====
+
+/// Fetch the secondary currency rate now, cache it and hand it to the pricing engine
+async fn fetch_exchange_rate(state: &TauriState) -> crate::core::Result<Option<ExchangeRate>> {
+    let pricing_config = state.config.lock().await.pricing.clone();
+    let target = pricing_config.secondary.currency.trim();
+    if target.is_empty() {
+        return Ok(None);
+    }
+    let now = chrono::Utc::now().timestamp();
+    let fetched = pricing::currency::fetch_rate(&pricing_config.secondary.rate_url, &pricing_config.currency, target, now).await?;
+    log::info!("Exchange rate {} -> {}: {}", fetched.base, fetched.target, fetched.rate);
+    pricing::currency::save_cached(&fetched);
+
+    let mut pricing = state.pricing.lock().await;
+    pricing.set_fetched_rate(Some(fetched));
+    Ok(pricing.exchange_rate())
+}
+
+/// Exchange rate to the secondary currency and its freshness, None when none is configured
 #[tauri::command]
-async fn get_config(state: tauri::State<'_, TauriState>) -> Result<Config, String> {
-    let config = state.config.lock().await;
-    Ok(config.clone())
+async fn get_exchange_rate(state: tauri::State<'_, TauriState>) -> Result<Option<ExchangeRate>, CommandError> {
+    Ok(state.pricing.lock().await.exchange_rate())
 }
 
-/// Update application configuration
+/// Fetch the exchange rate right away instead of waiting for the daily fetch
 #[tauri::command]
-async fn set_config(state: tauri::State<'_, TauriState>, config: Config) -> Result<(), String> {
-    let mut current_config = state.config.lock().await;
-    *current_config = config.clone();
-    current_config.save().map_err(|e| e.to_string())?;
+async fn refresh_exchange_rate(state: tauri::State<'_, TauriState>) -> Result<Option<ExchangeRate>, CommandError> {
+    let secondary = state.config.lock().await.pricing.secondary.clone();
+    if secondary.currency.trim().is_empty() || !secondary.auto_fetch {
+        return Err(CommandError::invalid_input("Automatic exchange rate fetch is not enabled"));
+    }
+    fetch_exchange_rate(&state).await.map_err(CommandError::from)
+}
 
-    // Update pricing engine with new config
-    let mut pricing = state.pricing.lock().await;
-    pricing.update_config(&config.pricing);
+/// Build the current month's billing summary from daily stats
+fn compute_billing_summary(db: &Database, rate_per_kwh: f64, exchange_rate: Option<ExchangeRate>) -> crate::core::Result<BillingSummary> {
+    let today = chrono::Local::now().date_naive();
+    let ((first, last), (prev_first, prev_last)) = pricing::month_ranges(today);
+    let fmt = |d: chrono::NaiveDate| d.format("%Y-%m-%d").to_string();
 
-    // Update i18n with new language
-    let mut i18n = state.i18n.lock().await;
-    i18n.set_language(&config.general.language);
+    let current = db.get_daily_stats(&fmt(first), &fmt(last))?;
+    let previous = db.get_daily_stats(&fmt(prev_first), &fmt(prev_last))?;
+    Ok(pricing::compute_billing_summary(&current, &previous, today, rate_per_kwh).with_exchange_rate(exchange_rate))
+}
 
-    Ok(())
+/// Get month-to-date consumption, cost and end-of-month projection
+#[tauri::command]
+async fn get_billing_summary(state: tauri::State<'_, TauriState>) -> Result<BillingSummary, String> {
+    let (rate, exchange_rate) = {
+        let pricing = state.pricing.lock().await;
+        (pricing.get_current_rate(), pricing.exchange_rate())
+    };
+    let summary = {
+        let db = state.db.lock().await;
+        compute_billing_summary(&db, rate, exchange_rate).map_err(|e| e.to_string())?
+    };
+    *state.billing_cache.lock().await = Some(summary.clone());
+    Ok(summary)
 }
 
-/// Get translated string
+/// Energy and cost split between CPU, GPU, base and unattributed power over a time range
 #[tauri::command]
-async fn translate(state: tauri::State<'_, TauriState>, key: String) -> Result<String, String> {
-    let i18n = state.i18n.lock().await;
-    Ok(i18n.get(&key))
+async fn get_cost_breakdown(state: tauri::State<'_, TauriState>, start: i64, end: i64) -> Result<CostBreakdown, CommandError> {
+    if start > end {
+        return Err(CommandError::invalid_input("Start must not be after end"));
+    }
+    let readings = state.db.lock().await.get_component_readings(start, end)?;
+    let pricing = state.pricing.lock().await;
+    Ok(compute_cost_breakdown(&readings, start, end, |ts| pricing.rate_at_timestamp(ts)))
 }
 
-/// Get all translations for current language
+/// Cost over a time range against everything billed at the peak rate or at the simple rate
 #[tauri::command]
-async fn get_translations(state: tauri::State<'_, TauriState>) -> Result<std::collections::HashMap<String, String>, String> {
-    let i18n = state.i18n.lock().await;
-    Ok(i18n.get_all())
+async fn get_savings_summary(state: tauri::State<'_, TauriState>, start: i64, end: i64) -> Result<SavingsSummary, CommandError> {
+    if start > end {
+        return Err(CommandError::invalid_input("Start must not be after end"));
+    }
+    let readings = state.db.lock().await.get_component_readings(start, end)?;
+    let pricing = state.pricing.lock().await;
+    Ok(compute_savings(
+        &readings,
+        start,
+        end,
+        |ts| pricing.rate_at_timestamp(ts),
+        |ts| pricing.peak_rate_at_timestamp(ts),
+        pricing.simple_rate(),
+    ))
 }
 
-/// Get historical data for a date range
+/// Recompute daily stats from the stored readings, optionally limited to `start_date..=end_date` (YYYY-MM-DD)
+///
+/// Each reading is priced at the rate in effect when it was taken. Progress is
+/// reported through `history-rebuild-progress` events; `cancel_history_rebuild`
+/// stops the rebuild after the current date.
 #[tauri::command]
-async fn get_history(
+async fn rebuild_history(
+    app: tauri::AppHandle,
     state: tauri::State<'_, TauriState>,
-    start_date: String,
-    end_date: String,
-) -> Result<Vec<db::DailyStats>, String> {
-    let db = state.db.lock().await;
-    let config = state.config.lock().await;
-    let pricing_mode = config.pricing.mode.clone();
-    drop(config);
-
-    // Get current rate from pricing engine
-    let rate_per_kwh = {
-        let pricing = state.pricing.lock().await;
-        pricing.get_current_rate()
-    };
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<HistoryRebuildResult, CommandError> {
+    for date in start_date.iter().chain(end_date.iter()) {
+        if chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+            return Err(CommandError::invalid_input(format!("Invalid date '{}', expected YYYY-MM-DD", date)));
+        }
+    }
+    if let (Some(start), Some(end)) = (&start_date, &end_date) {
+        if start > end {
+            return Err(CommandError::invalid_input("Start date must not be after end date"));
+        }
+    }
 
-    // Update today's stats before fetching to ensure fresh data
-    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
-    if start_date <= today && end_date >= today {
-        let _ = db.update_today_stats(Some(&pricing_mode), Some(rate_per_kwh));
+    let rebuild = Arc::clone(&state.history_rebuild);
+    if rebuild.running.swap(true, Ordering::SeqCst) {
+        return Err(CommandError::invalid_input("A history rebuild is already running"));
     }
+    let _guard = HistoryRebuildGuard(&rebuild);
+    rebuild.cancel_requested.store(false, Ordering::SeqCst);
 
-    let mut stats = db.get_daily_stats(&start_date, &end_date)
-        .map_err(|e| e.to_string())?;
+    let pricing_config = state.config.lock().await.pricing.clone();
+    let dates = state.db.lock().await.reading_dates(start_date.as_deref(), end_date.as_deref())?;
+    rebuild_daily_stats(app, Arc::clone(&state.db), Arc::clone(&rebuild), pricing_config, dates).await
+}
 
-    // Backfill cost for any days that have NULL total_cost
-    for stat in stats.iter_mut() {
-        if stat.total_cost.is_none() && stat.total_wh > 0.0 {
-            stat.total_cost = Some((stat.total_wh / 1000.0) * rate_per_kwh);
+/// Recompute the daily stats of `dates` on a blocking thread, for a caller holding `flags.running`
+async fn rebuild_daily_stats(
+    app: tauri::AppHandle,
+    db: Arc<Mutex<Database>>,
+    flags: Arc<HistoryRebuild>,
+    pricing_config: PricingConfig,
+    dates: Vec<String>,
+) -> Result<HistoryRebuildResult, CommandError> {
+    let result = tokio::task::spawn_blocking(move || -> crate::core::Result<HistoryRebuildResult> {
+        let pricing = PricingEngine::new(&pricing_config);
+        let rate_at = |ts: i64| pricing.rate_at_timestamp(ts);
+        let total = dates.len();
+        let mut processed = 0;
+
+        for date in dates {
+            if flags.cancel_requested.load(Ordering::SeqCst) {
+                log::info!("History rebuild cancelled after {}/{} dates", processed, total);
+                db.blocking_lock().try_refresh_lifetime_totals();
+                return Ok(HistoryRebuildResult { processed, total, cancelled: true, backfilled: 0 });
+            }
+            // Locked per date so live monitoring keeps writing in between
+            db.blocking_lock().rebuild_daily_stats_for_date_at(&date, Some(&pricing_config.mode), &rate_at)?;
+            processed += 1;
+            let _ = app.emit("history-rebuild-progress", HistoryRebuildProgress { processed, total, date });
         }
-    }
 
-    Ok(stats)
+        let backfilled = {
+            let db = db.blocking_lock();
+            db.try_refresh_lifetime_totals();
+            db.backfill_missing_costs(&|date| backfill_rate(&pricing, date))?
+        };
+        log::info!("History rebuild finished: {} dates, {} costs backfilled", processed, backfilled);
+        Ok(HistoryRebuildResult { processed, total, cancelled: false, backfilled })
+    })
+    .await
+    .map_err(|e| CommandError::from(format!("History rebuild failed: {}", e)))??;
+
+    Ok(result)
 }
 
-/// Get power readings for a time range (for graphs)
-#[tauri::command]
-async fn get_readings(
-    state: tauri::State<'_, TauriState>,
-    start_timestamp: i64,
-    end_timestamp: i64,
-) -> Result<Vec<db::PowerReadingRecord>, String> {
-    let db = state.db.lock().await;
-    db.get_readings(start_timestamp, end_timestamp)
-        .map_err(|e| e.to_string())
+/// Rate the cost of a stored day is filled in at: its average rate, today's rate if the date is malformed
+fn backfill_rate(pricing: &PricingEngine, date: &str) -> f64 {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|day| pricing.day_average_rate(day))
+        .unwrap_or_else(|_| pricing.get_current_rate())
 }
 
-/// Open the widget window
-#[tauri::command]
-async fn open_widget(app: tauri::AppHandle, state: tauri::State<'_, TauriState>) -> Result<(), String> {
-    // Check if widget is already open
-    if app.get_webview_window("widget").is_some() {
-        return Ok(());
+/// Rebuild daily stats keyed by UTC dates (before schema 7) with local dates, like `rebuild_history`
+///
+/// Stale UTC rows are deleted once every date is rebuilt. The task stays
+/// pending until then, a failed or cancelled rebuild runs again on the next launch.
+async fn local_dates_rebuild(app: tauri::AppHandle) {
+    let state = app.state::<TauriState>();
+    match state.db.lock().await.daily_rebuild_pending() {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            log::warn!("Failed to check for a pending daily stats rebuild: {}", e);
+            return;
+        }
     }
 
-    // Get widget position from config
-    let config = state.config.lock().await;
-    let position = &config.widget.position;
+    let rebuild = Arc::clone(&state.history_rebuild);
+    if rebuild.running.swap(true, Ordering::SeqCst) {
+        log::info!("A history rebuild is already running, the local date rebuild waits for the next launch");
+        return;
+    }
+    let _guard = HistoryRebuildGuard(&rebuild);
+    rebuild.cancel_requested.store(false, Ordering::SeqCst);
+
+    let pricing_config = state.config.lock().await.pricing.clone();
+    let dates = match state.db.lock().await.reading_dates(None, None) {
+        Ok(dates) => dates,
+        Err(e) => {
+            log::warn!("Failed to list the dates to rebuild with local dates: {}", e);
+            return;
+        }
+    };
+    match rebuild_daily_stats(app.clone(), Arc::clone(&state.db), Arc::clone(&rebuild), pricing_config, dates).await {
+        Ok(result) if result.cancelled => log::info!("Local date rebuild of daily stats cancelled, it resumes on the next launch"),
+        Ok(result) => {
+            let db = state.db.lock().await;
+            match db.delete_stale_daily_stats().and_then(|removed| db.clear_daily_rebuild_pending().map(|()| removed)) {
+                Ok(removed) => log::info!("Rebuilt daily stats for {} local date(s), removed {} stale UTC day(s)", result.processed, removed),
+                Err(e) => log::warn!("Failed to finish the local date rebuild of daily stats: {}", e),
+            }
+        }
+        Err(e) => log::warn!("Failed to rebuild daily stats with local dates, retrying on the next launch: {}", e),
+    }
+}
 
-    // Calculate position based on config
-    let (x, y) = match position.as_str() {
-        "top_left" => (20.0, 20.0),
-        "top_right" => (1200.0, 20.0),  // Will be adjusted by screen size
-        "bottom_left" => (20.0, 700.0),
-        "bottom_right" => (1200.0, 700.0),
-        _ => (20.0, 20.0),
+/// Delete stored readings closer than `window_secs` to the previous one of their source and rebuild their days
+///
+/// Cleans up the duplicates recorded before `insert_reading` skipped them.
+/// Ended sessions that lost readings have their energy and surplus updated.
+/// `window_secs` defaults to `advanced.duplicate_reading_window_secs`.
+#[tauri::command]
+async fn dedup_readings(state: tauri::State<'_, TauriState>, window_secs: Option<u32>) -> Result<DedupOutcome, CommandError> {
+    let (pricing_config, configured_window) = {
+        let config = state.config.lock().await;
+        (config.pricing.clone(), config.advanced.duplicate_reading_window_secs)
     };
+    let window_secs = window_secs.unwrap_or(configured_window);
+    if window_secs > MAX_DUPLICATE_READING_WINDOW_SECS {
+        return Err(CommandError::invalid_input(format!(
+            "Duplicate reading window must be between 0 and {} seconds",
+            MAX_DUPLICATE_READING_WINDOW_SECS
+        )));
+    }
 
-    // Create widget window
-    let _widget = WebviewWindowBuilder::new(&app, "widget", WebviewUrl::App("widget.html".into()))
-        .title("PowerCost Widget")
-        .inner_size(180.0, 70.0)
-        .position(x, y)
-        .resizable(false)
-        .decorations(false)
-        .always_on_top(true)
-        .transparent(true)
-        .skip_taskbar(true)
-        .build()
-        .map_err(|e| e.to_string())?;
+    // Shares the rebuild flag, both rewrite daily stats
+    let rebuild = Arc::clone(&state.history_rebuild);
+    if rebuild.running.swap(true, Ordering::SeqCst) {
+        return Err(CommandError::invalid_input("A history rebuild is already running"));
+    }
+    let _guard = HistoryRebuildGuard(&rebuild);
+    let db = Arc::clone(&state.db);
+
+    let outcome = tokio::task::spawn_blocking(move || -> crate::core::Result<DedupOutcome> {
+        let outcome = db.blocking_lock().dedup_readings(window_secs)?;
+        let pricing = PricingEngine::new(&pricing_config);
+        let rate_at = |ts: i64| pricing.rate_at_timestamp(ts);
+        for date in &outcome.dates {
+            db.blocking_lock().rebuild_daily_stats_for_date_at(date, Some(&pricing_config.mode), &rate_at)?;
+        }
+        let db = db.blocking_lock();
+        db.recalculate_sessions_surplus_in_place(&outcome.sessions, &rate_at)?;
+        db.try_refresh_lifetime_totals();
+        log::info!(
+            "Removed {} duplicate readings over {} days and {} sessions",
+            outcome.removed,
+            outcome.dates.len(),
+            outcome.sessions.len()
+        );
+        Ok(outcome)
+    })
+    .await
+    .map_err(|e| CommandError::from(format!("Duplicate reading cleanup failed: {}", e)))??;
 
-    Ok(())
+    Ok(outcome)
 }
 
-/// Close the widget window
+/// Ask a running `rebuild_history` to stop, returns false when none is running
 #[tauri::command]
-async fn close_widget(app: tauri::AppHandle) -> Result<(), String> {
-    if let Some(widget) = app.get_webview_window("widget") {
-        widget.close().map_err(|e| e.to_string())?;
+async fn cancel_history_rebuild(state: tauri::State<'_, TauriState>) -> Result<bool, CommandError> {
+    let rebuild = &state.history_rebuild;
+    if !rebuild.running.load(Ordering::SeqCst) {
+        return Ok(false);
     }
-    Ok(())
+    rebuild.cancel_requested.store(true, Ordering::SeqCst);
+    Ok(true)
 }
 
-/// Toggle widget visibility
+// ===== Locale Formatting Commands =====
+
+/// Format a number using the current language's separators
 #[tauri::command]
-async fn toggle_widget(app: tauri::AppHandle, state: tauri::State<'_, TauriState>) -> Result<bool, String> {
-    if let Some(widget) = app.get_webview_window("widget") {
-        widget.close().map_err(|e| e.to_string())?;
-        Ok(false)
-    } else {
-        open_widget(app, state).await?;
-        Ok(true)
-    }
+async fn format_number(state: tauri::State<'_, TauriState>, value: f64, decimals: Option<usize>) -> Result<String, String> {
+    let i18n = state.i18n.lock().await;
+    Ok(core::format::format_number(value, decimals.unwrap_or(2), i18n.current_language()))
+}
+
+/// Format a Unix timestamp ("short", "long", "time" or "datetime") in the history time zone
+#[tauri::command]
+async fn format_date(state: tauri::State<'_, TauriState>, timestamp: i64, style: Option<String>) -> Result<String, String> {
+    let zone = history_zone(&*state.config.lock().await);
+    let i18n = state.i18n.lock().await;
+    Ok(core::format::format_date_in(timestamp, style.as_deref().unwrap_or("short"), i18n.current_language(), &zone))
+}
+
+/// Format a duration in seconds
+#[tauri::command]
+async fn format_duration(state: tauri::State<'_, TauriState>, seconds: i64) -> Result<String, String> {
+    let i18n = state.i18n.lock().await;
+    Ok(core::format::format_duration(seconds, i18n.current_language()))
 }
 
 // ===== New System Metrics Commands =====
@@ -278,38 +1666,65 @@ async fn get_system_metrics(state: tauri::State<'_, TauriState>) -> Result<Syste
 #[tauri::command]
 async fn get_top_processes(state: tauri::State<'_, TauriState>, limit: Option<usize>) -> Result<Vec<ProcessMetrics>, String> {
     let config = state.config.lock().await;
-    let limit = limit.unwrap_or(config.advanced.process_list_limit);
+    let limit = limit.unwrap_or(config.advanced.process_list_limit).min(MAX_PROCESS_LIST_LIMIT);
     let pinned = config.advanced.pinned_processes.clone();
+    let ignored = config.advanced.ignored_processes.clone();
     drop(config);
 
     let monitor = state.monitor.lock().await;
-    monitor.get_top_processes_with_pinned(limit, &pinned).map_err(|e| e.to_string())
+    monitor.get_top_processes_with_pinned(limit, &pinned, &ignored).map_err(|e| e.to_string())
 }
 
-/// Get all processes (for discovery mode)
+/// Get all processes (for discovery mode), ignored ones included and flagged
 #[tauri::command]
 async fn get_all_processes(state: tauri::State<'_, TauriState>) -> Result<Vec<ProcessMetrics>, String> {
+    let ignored = state.config.lock().await.advanced.ignored_processes.clone();
     let monitor = state.monitor.lock().await;
-    monitor.get_all_processes().map_err(|e| e.to_string())
+    let mut processes = monitor.get_all_processes().map_err(|e| e.to_string())?;
+    for process in &mut processes {
+        process.is_ignored = process_filter::is_process_ignored(&ignored, &process.name, process.container.as_deref());
+    }
+    Ok(processes)
 }
 
 /// Pin a process for tracking
 #[tauri::command]
-async fn pin_process(state: tauri::State<'_, TauriState>, name: String) -> Result<Vec<String>, String> {
+async fn pin_process(state: tauri::State<'_, TauriState>, name: String) -> Result<Vec<String>, CommandError> {
+    if name == OTHER_PROCESSES_NAME {
+        return Err(CommandError::invalid_input("The Other row cannot be pinned").with_subject(name));
+    }
     let mut config = state.config.lock().await;
     if !config.advanced.pinned_processes.iter().any(|p| p.eq_ignore_ascii_case(&name)) {
+        if !process_running(&name) {
+            return Err(CommandError::not_found(format!("No running process named {}", name)).with_subject(name));
+        }
         config.advanced.pinned_processes.push(name);
-        config.save().map_err(|e| e.to_string())?;
+        config.save()?;
+        state.config_watch.publish(&config);
     }
     Ok(config.advanced.pinned_processes.clone())
 }
 
+/// Whether a process or a container with this name (case-insensitive) is running
+///
+/// Cgroups are only read for names that can be containers.
+fn process_running(name: &str) -> bool {
+    let mut sys = sysinfo::System::new();
+    sys.refresh_processes();
+    let processes = sys.processes();
+    processes.values().any(|p| p.name().eq_ignore_ascii_case(name))
+        || (cgroup::is_container_name(&name.to_lowercase())
+            && processes.keys().any(|pid| cgroup::container_of(pid.as_u32()).is_some_and(|c| c.eq_ignore_ascii_case(name))))
+}
+
 /// Unpin a process
 #[tauri::command]
 async fn unpin_process(state: tauri::State<'_, TauriState>, name: String) -> Result<Vec<String>, String> {
     let mut config = state.config.lock().await;
     config.advanced.pinned_processes.retain(|p| !p.eq_ignore_ascii_case(&name));
     config.save().map_err(|e| e.to_string())?;
+    state.config_watch.publish(&config);
+    state.process_history.lock().await.forget(&name);
     Ok(config.advanced.pinned_processes.clone())
 }
 
@@ -320,38 +1735,97 @@ async fn get_pinned_processes(state: tauri::State<'_, TauriState>) -> Result<Vec
     Ok(config.advanced.pinned_processes.clone())
 }
 
+/// Current values and recent cpu/gpu/memory series of each pinned process
+#[tauri::command]
+async fn get_pinned_process_history(state: tauri::State<'_, TauriState>) -> Result<Vec<PinnedProcessHistory>, CommandError> {
+    let pinned = state.config.lock().await.advanced.pinned_processes.clone();
+    Ok(state.process_history.lock().await.snapshot(&pinned))
+}
+
+/// Hide a process name (or `*` pattern) from the top process list
+#[tauri::command]
+async fn ignore_process(state: tauri::State<'_, TauriState>, name: String) -> Result<Vec<String>, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Process name cannot be empty".to_string());
+    }
+    let mut config = state.config.lock().await;
+    if !config.advanced.ignored_processes.iter().any(|p| p.eq_ignore_ascii_case(&name)) {
+        config.advanced.ignored_processes.push(name);
+        config.save().map_err(|e| e.to_string())?;
+        state.config_watch.publish(&config);
+    }
+    Ok(config.advanced.ignored_processes.clone())
+}
+
+/// Show an ignored process again
+#[tauri::command]
+async fn unignore_process(state: tauri::State<'_, TauriState>, name: String) -> Result<Vec<String>, String> {
+    let mut config = state.config.lock().await;
+    config.advanced.ignored_processes.retain(|p| !p.eq_ignore_ascii_case(name.trim()));
+    config.save().map_err(|e| e.to_string())?;
+    state.config_watch.publish(&config);
+    Ok(config.advanced.ignored_processes.clone())
+}
+
+/// Get ignored process patterns
+#[tauri::command]
+async fn get_ignored_processes(state: tauri::State<'_, TauriState>) -> Result<Vec<String>, String> {
+    let config = state.config.lock().await;
+    Ok(config.advanced.ignored_processes.clone())
+}
+
 /// Kill a process by name
 #[tauri::command]
-async fn kill_process(name: String) -> Result<(), String> {
+async fn kill_process(name: String) -> Result<KilledProcesses, CommandError> {
+    if name == OTHER_PROCESSES_NAME {
+        return Err(CommandError::invalid_input("The Other row is not a process").with_subject(name));
+    }
     let mut sys = sysinfo::System::new();
     sys.refresh_processes();
 
     let mut found = false;
-    let mut killed = false;
+    let mut killed = 0;
     for (_pid, process) in sys.processes() {
         if process.name().eq_ignore_ascii_case(&name) {
             found = true;
             if process.kill() {
-                killed = true;
+                killed += 1;
             }
         }
     }
 
-    if killed {
-        Ok(())
+    if killed > 0 {
+        Ok(KilledProcesses { name, killed })
     } else if found {
-        Err(format!("ACCESS_DENIED:{}", name))
+        let elevation_hint = elevation::is_supported() && !elevation::is_elevated();
+        Err(CommandError::permission_denied(format!("Not allowed to kill {}", name))
+            .with_subject(name)
+            .with_elevation_hint(elevation_hint))
     } else {
-        Err(format!("NOT_FOUND:{}", name))
+        Err(CommandError::not_found(format!("No running process named {}", name)).with_subject(name))
     }
 }
 
+/// Outcome of `kill_process`
+#[derive(Clone, serde::Serialize)]
+struct KilledProcesses {
+    name: String,
+    /// Processes with that name that were killed
+    killed: usize,
+}
+
 /// Set process list limit
 #[tauri::command]
 async fn set_process_limit(state: tauri::State<'_, TauriState>, limit: usize) -> Result<(), String> {
+    if !(MIN_PROCESS_LIST_LIMIT..=MAX_PROCESS_LIST_LIMIT).contains(&limit) {
+        return Err(format!("Process list limit must be between {} and {}", MIN_PROCESS_LIST_LIMIT, MAX_PROCESS_LIST_LIMIT));
+    }
     let mut config = state.config.lock().await;
     config.advanced.process_list_limit = limit;
-    config.save().map_err(|e| e.to_string())
+    config.save().map_err(|e| e.to_string())?;
+    state.config_watch.publish(&config);
+    Ok(())
 }
 
 // ===== Session Tracking Commands =====
@@ -361,31 +1835,86 @@ async fn set_process_limit(state: tauri::State<'_, TauriState>, limit: usize) ->
 async fn start_tracking_session(
     state: tauri::State<'_, TauriState>,
     label: Option<String>,
-) -> Result<i64, String> {
+    target_cost: Option<f64>,
+    target_wh: Option<f64>,
+    auto_end_after_idle_minutes: Option<u32>,
+    category: Option<String>,
+) -> Result<i64, CommandError> {
+    begin_session(&state, label, category, target_cost, target_wh, None, auto_end_after_idle_minutes).await
+}
+
+/// Start a tracking session from a saved template
+#[tauri::command]
+async fn start_session_from_template(state: tauri::State<'_, TauriState>, name: String) -> Result<i64, CommandError> {
+    let template = {
+        let config = state.config.lock().await;
+        config.advanced.session_templates.iter().find(|t| t.name == name).cloned()
+    };
+    let template = template.ok_or_else(|| CommandError::not_found(format!("Template '{}' not found", name)))?;
+    let auto_end_after = template
+        .auto_end_minutes
+        .map(|m| std::time::Duration::from_secs(m as u64 * 60));
+
+    begin_session(&state, template.label, template.category, template.target_cost, None, auto_end_after, None).await
+}
+
+/// Validate targets, create the session in the database and make it active
+async fn begin_session(
+    state: &TauriState,
+    label: Option<String>,
+    category: Option<String>,
+    target_cost: Option<f64>,
+    target_wh: Option<f64>,
+    auto_end_after: Option<std::time::Duration>,
+    idle_minutes: Option<u32>,
+) -> Result<i64, CommandError> {
+    // Targets must be strictly positive
+    if target_cost.is_some_and(|t| t <= 0.0 || !t.is_finite()) {
+        return Err(CommandError::invalid_input("Target cost must be greater than zero"));
+    }
+    if target_wh.is_some_and(|t| t <= 0.0 || !t.is_finite()) {
+        return Err(CommandError::invalid_input("Target energy must be greater than zero"));
+    }
+
     // Guard: don't start a new session if one is already active
     {
         let active = state.active_session.lock().await;
         if active.is_some() {
-            return Err("A session is already active".to_string());
+            return Err(CommandError::session_active("A session is already active"));
         }
     }
 
-    // Get baseline
-    let baseline_watts = {
+    // Get baseline and idle timeout (the session override wins, 0 disables)
+    let (baseline_watts, baseline_bucket, idle_after_secs) = {
         let config = state.config.lock().await;
-        if config.advanced.baseline_auto {
-            let detector = state.baseline_detector.lock().await;
-            detector.get_baseline().unwrap_or(0.0)
-        } else {
-            config.advanced.baseline_watts
-        }
+        let idle_minutes = idle_minutes.unwrap_or(config.advanced.auto_end_after_idle_minutes);
+        let idle_after_secs = (idle_minutes > 0).then_some(idle_minutes as i64 * 60);
+        let detector = state.baseline_detector.lock().await;
+        // A bucket's manual value applies in both modes, a category's override over both
+        let baseline_watts = config.advanced.session_baseline(
+            category.as_deref(),
+            detector.get_baseline(),
+            detector.bucket_manual_baseline(),
+        );
+        let baseline_bucket = match config.advanced.category_baseline(category.as_deref()) {
+            Some(_) => None,
+            None => detector.current_bucket_name().map(str::to_string),
+        };
+        (baseline_watts, baseline_bucket, idle_after_secs)
     };
 
     // Create session in database
-    let session_id = {
+    let (session_id, started_at) = {
         let db = state.db.lock().await;
-        db.start_session(baseline_watts, label.as_deref())
-            .map_err(|e| e.to_string())?
+        let id = db.start_session_with_target(baseline_watts, label.as_deref(), target_cost, target_wh)?;
+        if category.is_some() {
+            db.update_session_category(id, category.as_deref())?;
+        }
+        if baseline_bucket.is_some() {
+            db.set_session_baseline_bucket(id, baseline_bucket.as_deref())?;
+        }
+        let started_at = db.get_session(id)?.map_or_else(|| chrono::Utc::now().timestamp(), |s| s.start_time);
+        (id, started_at)
     };
 
     // Set active session
@@ -396,9 +1925,24 @@ async fn start_tracking_session(
             baseline_watts,
             total_wh: 0.0,
             surplus_wh: 0.0,
+            surplus_cost: 0.0,
             start_time: std::time::Instant::now(),
+            started_at,
             label: label.clone(),
-            category: None,
+            category,
+            target_cost,
+            target_wh,
+            target_notified: 0,
+            last_heartbeat: std::time::Instant::now(),
+            auto_end_after,
+            idle_after_secs,
+            idle_tracker: SessionIdleTracker::new(),
+            idle_snapshot: (0.0, 0.0, 0.0),
+            app_usage: AppUsageTracker::new(),
+            day: chrono::Local::now().date_naive(),
+            continued_from: None,
+            baseline_bucket,
+            earlier_surplus: (0.0, 0.0),
         });
     }
 
@@ -407,7 +1951,7 @@ async fn start_tracking_session(
 
 /// End the current tracking session
 #[tauri::command]
-async fn end_tracking_session(state: tauri::State<'_, TauriState>) -> Result<Option<Session>, String> {
+async fn end_tracking_session(state: tauri::State<'_, TauriState>) -> Result<Option<Session>, CommandError> {
     let session_state = {
         let mut active = state.active_session.lock().await;
         active.take()
@@ -415,61 +1959,228 @@ async fn end_tracking_session(state: tauri::State<'_, TauriState>) -> Result<Opt
 
     match session_state {
         Some(session) => {
-            // Calculate final surplus cost
-            let surplus_cost = {
-                let pricing = state.pricing.lock().await;
-                pricing.calculate_cost(session.surplus_wh / 1000.0)
-            };
-
             // End session in database
             let db = state.db.lock().await;
-            db.end_session(session.id, session.total_wh, session.surplus_wh, surplus_cost)
-                .map_err(|e| e.to_string())
+            save_top_apps(&db, &session);
+            let ended = db.end_session(session.id, session.total_wh, session.surplus_wh, session.surplus_cost)?;
+            drop(db);
+            if let Some(ended) = &ended {
+                run_session_end_hook(&state, ended).await;
+            }
+            Ok(ended)
         }
         None => Ok(None),
     }
 }
 
+/// Start `advanced.session_end_hook` for an ended session, without waiting for it
+///
+/// A session split at midnight is given whole, merged with its earlier parts.
+/// The hook runs on a blocking thread; how it ended is only logged.
+async fn run_session_end_hook(state: &TauriState, session: &Session) {
+    let (hook, currency) = {
+        let config = state.config.lock().await;
+        (config.advanced.session_end_hook.clone(), config.pricing.currency.clone())
+    };
+    let Some(hook) = hook else {
+        return;
+    };
+    let session = match (session.continued_from, session.id) {
+        (Some(_), Some(id)) => match state.db.lock().await.get_merged_session(id) {
+            Ok(Some(merged)) => merged,
+            Ok(None) => session.clone(),
+            Err(e) => {
+                log::warn!("Failed to merge the parts of session {} for its end hook: {}", id, e);
+                session.clone()
+            }
+        },
+        _ => session.clone(),
+    };
+    tokio::task::spawn_blocking(move || {
+        match session_hook::run(std::path::Path::new(&hook), &session, &currency, false, session_hook::TIMEOUT) {
+            Ok(outcome) => session_hook::log_outcome(session.id, &outcome),
+            Err(e) => log::warn!("Session end hook for session {:?} failed: {}", session.id, e),
+        }
+    });
+}
+
+/// Run the saved `advanced.session_end_hook` with a made-up session and return how it ended
+///
+/// Only the saved path is run, the UI saves a new one first. The script sees
+/// `POWERCOST_HOOK_TEST=1`.
+#[tauri::command]
+async fn test_session_hook(state: tauri::State<'_, TauriState>) -> Result<session_hook::HookOutcome, CommandError> {
+    let (path, currency) = {
+        let config = state.config.lock().await;
+        (config.advanced.session_end_hook.clone(), config.pricing.currency.clone())
+    };
+    let path = path.filter(|p| !p.trim().is_empty())
+        .ok_or_else(|| CommandError::invalid_input("No session end hook configured"))?;
+
+    let session = session_hook::synthetic_session(chrono::Utc::now().timestamp());
+    let outcome = tokio::task::spawn_blocking(move || {
+        session_hook::run(std::path::Path::new(path.trim()), &session, &currency, true, session_hook::TIMEOUT)
+    })
+    .await
+    .map_err(|e| CommandError::from(format!("Session end hook failed: {}", e)))??;
+    session_hook::log_outcome(None, &outcome);
+    Ok(outcome)
+}
+
 /// Get current session statistics
 #[tauri::command]
-async fn get_session_stats(state: tauri::State<'_, TauriState>) -> Result<Option<Session>, String> {
+async fn get_session_stats(state: tauri::State<'_, TauriState>) -> Result<Option<Session>, CommandError> {
     let active = state.active_session.lock().await;
 
     match active.as_ref() {
-        Some(session) => {
-            let pricing = state.pricing.lock().await;
-            let surplus_cost = pricing.calculate_cost(session.surplus_wh / 1000.0);
-
-            Ok(Some(Session {
-                id: Some(session.id),
-                start_time: chrono::Utc::now().timestamp() - session.start_time.elapsed().as_secs() as i64,
-                end_time: None,
-                baseline_watts: session.baseline_watts,
-                total_wh: session.total_wh,
-                surplus_wh: session.surplus_wh,
-                surplus_cost,
-                label: session.label.clone(),
-                category: session.category.clone(),
-            }))
-        }
+        Some(session) => Ok(Some(session.to_session())),
         None => Ok(None),
     }
 }
 
-/// Get session history
+/// Sample every available power source side by side, without changing the active one
+///
+/// With `persist`, the report is also written to the app data directory.
+#[tauri::command]
+async fn run_source_benchmark(duration_secs: u64, persist: Option<bool>) -> Result<BenchmarkReport, CommandError> {
+    if !(1..=benchmark::MAX_DURATION_SECS).contains(&duration_secs) {
+        return Err(CommandError::invalid_input(format!(
+            "Benchmark duration must be between 1 and {} seconds",
+            benchmark::MAX_DURATION_SECS
+        )));
+    }
+
+    let report = tokio::task::spawn_blocking(move || benchmark::run(std::time::Duration::from_secs(duration_secs)))
+        .await
+        .map_err(|e| CommandError::from(format!("Benchmark failed: {}", e)))?;
+
+    if persist.unwrap_or(false) {
+        if let Some(path) = updater::cache_file_path("source_benchmark.json") {
+            let content = serde_json::to_string_pretty(&report).map_err(|e| CommandError::from(e.to_string()))?;
+            std::fs::write(&path, content).map_err(|e| CommandError::from(crate::core::Error::Io(e)))?;
+            log::info!("Source benchmark saved to {:?}", path);
+        }
+    }
+
+    Ok(report)
+}
+
+/// Get the latest hardware inventory (GPUs and driver, CPU, RAM, OS) for the About page
+#[tauri::command]
+async fn get_hardware_info(state: tauri::State<'_, TauriState>) -> Result<Option<db::HardwareSnapshot>, CommandError> {
+    let db = state.db.lock().await;
+    db.get_latest_hardware_snapshot().map_err(CommandError::from)
+}
+
+/// Get a session with its foreground app breakdown
+#[tauri::command]
+async fn get_session_detail(state: tauri::State<'_, TauriState>, session_id: i64) -> Result<SessionDetail, CommandError> {
+    {
+        let active = state.active_session.lock().await;
+        if let Some(session) = active.as_ref().filter(|s| s.id == session_id) {
+            return Ok(SessionDetail {
+                session: session.to_session(),
+                top_apps: session.app_usage.top(TOP_APPS),
+            });
+        }
+    }
+
+    let db = state.db.lock().await;
+    let session = db
+        .get_session(session_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Session {} not found", session_id)))?;
+    let top_apps = db.get_session_top_apps(session_id)?;
+    Ok(SessionDetail { session, top_apps })
+}
+
+/// Get session history, optionally with sessions split at midnight merged back together
+#[tauri::command]
+async fn get_sessions(
+    state: tauri::State<'_, TauriState>,
+    limit: Option<u32>,
+    merge_continuations: Option<bool>,
+) -> Result<Vec<Session>, CommandError> {
+    let db = state.db.lock().await;
+    if merge_continuations.unwrap_or(false) {
+        db.get_merged_sessions(limit).map_err(CommandError::from)
+    } else {
+        db.get_sessions(limit).map_err(CommandError::from)
+    }
+}
+
+/// Validate a what-if baseline and get the active session id, if any
+async fn prepare_surplus_recalculation(state: &TauriState, baseline_watts: f64) -> Result<(Option<i64>, PricingEngine), CommandError> {
+    if !baseline_watts.is_finite() || baseline_watts < 0.0 {
+        return Err(CommandError::invalid_input("Baseline must be a positive number of watts"));
+    }
+    let active_id = state.active_session.lock().await.as_ref().map(|s| s.id);
+    let pricing = PricingEngine::new(&state.config.lock().await.pricing);
+    Ok((active_id, pricing))
+}
+
+/// Recompute the surplus of an ended session with another baseline
+///
+/// Readings are priced at the rate in effect when they were taken; the previous
+/// values are kept in the session's audit trail.
+#[tauri::command]
+async fn recalculate_session_surplus(
+    state: tauri::State<'_, TauriState>,
+    session_id: i64,
+    new_baseline_watts: f64,
+) -> Result<Session, CommandError> {
+    let (active_id, pricing) = prepare_surplus_recalculation(&state, new_baseline_watts).await?;
+    if active_id == Some(session_id) {
+        return Err(CommandError::session_active("Cannot recalculate the active session"));
+    }
+
+    let db = state.db.lock().await;
+    let session = db
+        .get_session(session_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Session {} not found", session_id)))?;
+    if session.end_time.is_none() {
+        return Err(CommandError::session_active(format!("Session {} has not ended", session_id)));
+    }
+
+    let rate_at = |ts: i64| pricing.rate_at_timestamp(ts);
+    db.recalculate_session_surplus(session_id, new_baseline_watts, &rate_at)?
+        .ok_or_else(|| CommandError::not_found(format!("Session {} not found", session_id)))
+}
+
+/// Recompute the surplus of every ended session started in a time range with another baseline
 #[tauri::command]
-async fn get_sessions(state: tauri::State<'_, TauriState>, limit: Option<u32>) -> Result<Vec<Session>, String> {
+async fn recalculate_sessions_surplus(
+    state: tauri::State<'_, TauriState>,
+    start: i64,
+    end: i64,
+    new_baseline_watts: f64,
+) -> Result<Vec<Session>, CommandError> {
+    if start > end {
+        return Err(CommandError::invalid_input("Start must not be after end"));
+    }
+    let (active_id, pricing) = prepare_surplus_recalculation(&state, new_baseline_watts).await?;
+    let rate_at = |ts: i64| pricing.rate_at_timestamp(ts);
+
     let db = state.db.lock().await;
-    db.get_sessions(limit).map_err(|e| e.to_string())
+    let mut updated = Vec::new();
+    for session in db.get_sessions_started_in_range(start, end)? {
+        let Some(id) = session.id.filter(|&id| Some(id) != active_id && session.end_time.is_some()) else {
+            continue;
+        };
+        if let Some(session) = db.recalculate_session_surplus(id, new_baseline_watts, &rate_at)? {
+            updated.push(session);
+        }
+    }
+    log::info!("Recalculated surplus of {} session(s) with a {:.1} W baseline", updated.len(), new_baseline_watts);
+    Ok(updated)
 }
 
 // ===== Baseline Detection Commands =====
 
-/// Detect baseline power consumption
+/// Detect baseline power consumption, per schedule bucket when there is a schedule
 #[tauri::command]
-async fn detect_baseline(state: tauri::State<'_, TauriState>) -> Result<Option<BaselineDetection>, String> {
+async fn detect_baseline(state: tauri::State<'_, TauriState>) -> Result<BaselineReport, String> {
     let mut detector = state.baseline_detector.lock().await;
-    Ok(detector.detect_baseline())
+    Ok(detector.detect_baseline_at(chrono::Local::now().time()))
 }
 
 /// Set manual baseline
@@ -518,20 +2229,70 @@ async fn get_dashboard_config(state: tauri::State<'_, TauriState>) -> Result<cra
     Ok(config.dashboard.clone())
 }
 
-/// Save dashboard config
+/// Save dashboard config, rejecting invalid layouts
+#[tauri::command]
+async fn save_dashboard_config(
+    state: tauri::State<'_, TauriState>,
+    dashboard: crate::core::DashboardConfig,
+) -> Result<(), CommandError> {
+    let violations = validate_dashboard(&dashboard);
+    if !violations.is_empty() {
+        let details: Vec<String> = violations.into_iter().map(|v| v.message).collect();
+        return Err(CommandError::invalid_input("Invalid dashboard layout").with_details(details.join("; ")));
+    }
+
+    let mut config = state.config.lock().await;
+    config.dashboard = dashboard;
+    config.save()?;
+    // The detailed loop collects for the visible widgets
+    state.config_watch.publish(&config);
+    Ok(())
+}
+
+/// Change some fields of one dashboard widget, returns the updated widget
+///
+/// The active layout profile, if any, gets the same change.
 #[tauri::command]
-async fn save_dashboard_config(
+async fn update_dashboard_widget(
     state: tauri::State<'_, TauriState>,
-    dashboard: crate::core::DashboardConfig,
-) -> Result<(), String> {
+    id: String,
+    patch: WidgetPatch,
+) -> Result<crate::core::DashboardWidget, CommandError> {
     let mut config = state.config.lock().await;
-    config.dashboard = dashboard;
-    config.save().map_err(|e| e.to_string())
+    let mut staged = config.clone();
+    let widget = match dashboard::patch_widget(&mut staged.dashboard, &id, &patch) {
+        Ok(widget) => widget,
+        Err(violations) if violations.iter().any(|v| v.kind == ViolationKind::UnknownWidget) => {
+            return Err(CommandError::not_found(format!("Widget '{}' not found", id)));
+        }
+        Err(violations) => {
+            let details: Vec<String> = violations.into_iter().map(|v| v.message).collect();
+            return Err(CommandError::invalid_input("Invalid dashboard layout").with_details(details.join("; ")));
+        }
+    };
+    staged.save()?;
+    *config = staged;
+    state.config_watch.publish(&config);
+    Ok(widget)
 }
 
-/// Set autostart (start with system) enabled/disabled
+/// Check a dashboard layout without saving it
+#[tauri::command]
+async fn validate_dashboard_config(dashboard: crate::core::DashboardConfig) -> Result<Vec<LayoutViolation>, CommandError> {
+    Ok(validate_dashboard(&dashboard))
+}
+
+/// List the widgets a dashboard layout may contain
 #[tauri::command]
-async fn set_autostart(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+async fn get_widget_catalog() -> Result<Vec<WidgetSpec>, CommandError> {
+    Ok(WIDGET_CATALOG.to_vec())
+}
+
+/// Argument of the autostart entry, boot launches start hidden whatever `start_minimized` says
+const AUTOSTART_ARG: &str = "--minimized";
+
+/// Register or remove the autostart entry
+fn apply_autostart(app: &tauri::AppHandle, enabled: bool) -> Result<(), String> {
     use tauri_plugin_autostart::ManagerExt;
 
     let autostart_manager = app.autolaunch();
@@ -547,11 +2308,60 @@ async fn set_autostart(app: tauri::AppHandle, enabled: bool) -> Result<(), Strin
     Ok(())
 }
 
+/// Set autostart (start with system) enabled/disabled
+#[tauri::command]
+async fn set_autostart(app: tauri::AppHandle, state: tauri::State<'_, TauriState>, enabled: bool) -> Result<(), String> {
+    apply_autostart(&app, enabled)?;
+    let mut config = state.config.lock().await;
+    if config.general.start_with_system != enabled {
+        config.general.start_with_system = enabled;
+        if let Err(e) = config.save() {
+            config.general.start_with_system = !enabled;
+            if let Err(e) = apply_autostart(&app, !enabled) {
+                log::warn!("Failed to restore autostart: {}", e);
+            }
+            return Err(e.to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Make `general.start_with_system` match the autostart entry, the OS state wins
+fn reconcile_autostart(app: &tauri::AppHandle, state: &TauriState) {
+    use tauri_plugin_autostart::ManagerExt;
+
+    let enabled = match app.autolaunch().is_enabled() {
+        Ok(enabled) => enabled,
+        Err(e) => {
+            log::warn!("Failed to read autostart state: {}", e);
+            return;
+        }
+    };
+    // Entries written by older versions lack the launch argument
+    if enabled {
+        if let Err(e) = app.autolaunch().enable() {
+            log::warn!("Failed to refresh autostart entry: {}", e);
+        }
+    }
+
+    let mut config = tauri::async_runtime::block_on(state.config.lock());
+    if config.general.start_with_system != enabled {
+        log::info!(
+            "Autostart is {} in the system, updating start_with_system",
+            if enabled { "enabled" } else { "disabled" }
+        );
+        config.general.start_with_system = enabled;
+        if let Err(e) = config.save() {
+            log::warn!("Failed to save autostart state: {}", e);
+        }
+    }
+}
+
 // ===== Session Category & Label Commands =====
 
 /// Update a session's label
 #[tauri::command]
-async fn update_session_label(state: tauri::State<'_, TauriState>, session_id: i64, label: String) -> Result<(), String> {
+async fn update_session_label(state: tauri::State<'_, TauriState>, session_id: i64, label: String) -> Result<(), CommandError> {
     // Update in-memory state if this is the active session
     {
         let mut active = state.active_session.lock().await;
@@ -562,12 +2372,12 @@ async fn update_session_label(state: tauri::State<'_, TauriState>, session_id: i
         }
     }
     let db = state.db.lock().await;
-    db.update_session_label(session_id, &label).map_err(|e| e.to_string())
+    db.update_session_label(session_id, &label).map_err(CommandError::from)
 }
 
 /// Update a session's category
 #[tauri::command]
-async fn update_session_category(state: tauri::State<'_, TauriState>, session_id: i64, category: Option<String>) -> Result<(), String> {
+async fn update_session_category(state: tauri::State<'_, TauriState>, session_id: i64, category: Option<String>) -> Result<(), CommandError> {
     // Update in-memory state if this is the active session
     {
         let mut active = state.active_session.lock().await;
@@ -578,66 +2388,352 @@ async fn update_session_category(state: tauri::State<'_, TauriState>, session_id
         }
     }
     let db = state.db.lock().await;
-    db.update_session_category(session_id, category.as_deref()).map_err(|e| e.to_string())
+    db.update_session_category(session_id, category.as_deref()).map_err(CommandError::from)
+}
+
+/// Set the category of every session matching `filter`, returns how many matched
+///
+/// With `dry_run` nothing changes. The running session is only included with
+/// `filter.include_active`, and then its in-memory category follows.
+#[tauri::command]
+async fn bulk_update_session_category(
+    state: tauri::State<'_, TauriState>,
+    filter: SessionCategoryFilter,
+    category: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<u64, CommandError> {
+    let dry_run = dry_run.unwrap_or(false);
+    let mut active = state.active_session.lock().await;
+    let db = state.db.lock().await;
+    let count = db.bulk_update_session_category(&filter, category.as_deref(), dry_run)?;
+    if filter.include_active && !dry_run {
+        if let Some(session) = active.as_mut() {
+            if let Some(stored) = db.get_session(session.id)? {
+                session.category = stored.category;
+            }
+        }
+    }
+    Ok(count)
+}
+
+/// Session categories as served to the frontend (emoji stripped when disabled)
+fn served_session_categories(config: &Config) -> Vec<SessionCategory> {
+    let mut categories = config.advanced.session_categories.clone();
+    if !config.general.use_emoji {
+        for category in categories.iter_mut() {
+            category.emoji.clear();
+        }
+    }
+    categories
 }
 
 /// Get session categories from config
 #[tauri::command]
-async fn get_session_categories(state: tauri::State<'_, TauriState>) -> Result<Vec<SessionCategory>, String> {
+async fn get_session_categories(state: tauri::State<'_, TauriState>) -> Result<Vec<SessionCategory>, CommandError> {
     let config = state.config.lock().await;
-    Ok(config.advanced.session_categories.clone())
+    Ok(served_session_categories(&config))
 }
 
 /// Add a new session category
 #[tauri::command]
-async fn add_session_category(state: tauri::State<'_, TauriState>, category: SessionCategory) -> Result<Vec<SessionCategory>, String> {
+async fn add_session_category(state: tauri::State<'_, TauriState>, category: SessionCategory) -> Result<Vec<SessionCategory>, CommandError> {
+    validate_category_baseline(category.baseline_watts)?;
     let mut config = state.config.lock().await;
     if !config.advanced.session_categories.iter().any(|c| c.name == category.name) {
         config.advanced.session_categories.push(category);
-        config.save().map_err(|e| e.to_string())?;
+        config.save()?;
     }
-    Ok(config.advanced.session_categories.clone())
+    Ok(served_session_categories(&config))
 }
 
 /// Remove a session category by name
 #[tauri::command]
-async fn remove_session_category(state: tauri::State<'_, TauriState>, name: String) -> Result<Vec<SessionCategory>, String> {
+async fn remove_session_category(state: tauri::State<'_, TauriState>, name: String) -> Result<Vec<SessionCategory>, CommandError> {
     let mut config = state.config.lock().await;
     config.advanced.session_categories.retain(|c| c.name != name);
-    config.save().map_err(|e| e.to_string())?;
-    Ok(config.advanced.session_categories.clone())
+    config.save()?;
+    Ok(served_session_categories(&config))
+}
+
+/// Set or clear (None) the baseline sessions of a category start with
+#[tauri::command]
+async fn set_session_category_baseline(
+    state: tauri::State<'_, TauriState>,
+    name: String,
+    baseline_watts: Option<f64>,
+) -> Result<Vec<SessionCategory>, CommandError> {
+    let mut config = state.config.lock().await;
+    config.advanced.set_session_category_baseline(&name, baseline_watts)?;
+    config.save()?;
+    Ok(served_session_categories(&config))
+}
+
+/// Rename a session category and move existing sessions to the new name
+#[tauri::command]
+async fn rename_session_category(
+    state: tauri::State<'_, TauriState>,
+    old_name: String,
+    new_name: String,
+) -> Result<Vec<SessionCategory>, CommandError> {
+    // Saved first, and the config lock released before the db one is taken
+    let path = Config::config_path()?;
+    let (previous, categories) = {
+        let mut config = state.config.lock().await;
+        let previous = config.clone();
+        let mut staged = config.clone();
+        staged.advanced.rename_session_category(&old_name, &new_name)?;
+        config.replace_saved(staged, &path)?;
+        (previous, served_session_categories(&config))
+    };
+    let new_name = new_name.trim().to_string();
+
+    let renamed = state.db.lock().await.rename_session_category(&old_name, &new_name);
+    if let Err(e) = renamed {
+        // Sessions keep the old name, so does the config
+        if let Err(e) = state.config.lock().await.replace_saved(previous, &path) {
+            log::warn!("Failed to restore the config after a failed category rename: {}", e);
+        }
+        return Err(e.into());
+    }
+    {
+        let mut active = state.active_session.lock().await;
+        if let Some(ref mut session) = *active {
+            if session.category.as_deref() == Some(old_name.as_str()) {
+                session.category = Some(new_name.clone());
+            }
+        }
+    }
+
+    Ok(categories)
 }
 
 /// Delete a session
 #[tauri::command]
-async fn delete_session(state: tauri::State<'_, TauriState>, session_id: i64) -> Result<(), String> {
+async fn delete_session(state: tauri::State<'_, TauriState>, session_id: i64) -> Result<(), CommandError> {
+    let db = state.db.lock().await;
+    db.delete_session(session_id).map_err(CommandError::from)
+}
+
+/// Draw a PNG summary card of a session at `path`, in the colors of the current theme
+#[tauri::command]
+async fn render_session_receipt(state: tauri::State<'_, TauriState>, session_id: i64, path: String) -> Result<(), CommandError> {
+    if path.trim().is_empty() {
+        return Err(CommandError::invalid_input("Receipt path is empty"));
+    }
+    let session = state.db.lock().await.get_session(session_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Session {} not found", session_id)))?;
+    let (theme_setting, emoji, symbol, currency) = {
+        let config = state.config.lock().await;
+        let emoji = config.advanced.session_categories.iter()
+            .find(|c| Some(&c.name) == session.category.as_ref())
+            .map(|c| c.emoji.clone());
+        (config.general.theme.clone(), emoji, config.pricing.currency_symbol.clone(), config.pricing.currency.clone())
+    };
+    let theme = receipt::Theme::from_setting(resolve_theme(&theme_setting).await.as_str());
+    let card = receipt::Receipt::for_session(&session, emoji.as_deref(), &*state.i18n.lock().await, &symbol, &currency);
+    tauri::async_runtime::spawn_blocking(move || receipt::write_png(&card, theme, std::path::Path::new(&path)))
+        .await
+        .map_err(|e| CommandError::from(e.to_string()))??;
+    log::info!("Rendered receipt of session {}", session_id);
+    Ok(())
+}
+
+/// Get sessions in a date range grouped by local start date, with per-day subtotals
+#[tauri::command]
+async fn get_sessions_grouped_by_day(state: tauri::State<'_, TauriState>, start: i64, end: i64) -> Result<Vec<db::SessionDay>, CommandError> {
+    let db = state.db.lock().await;
+    db.get_sessions_grouped_by_day(start, end).map_err(CommandError::from)
+}
+
+/// Longest category trend accepted, in months
+const MAX_TREND_MONTHS: u32 = 120;
+
+/// Get the monthly surplus totals of a session category, oldest month first
+#[tauri::command]
+async fn get_category_trend(state: tauri::State<'_, TauriState>, category: String, months: u32) -> Result<Vec<db::CategoryTrendPoint>, CommandError> {
+    if !(1..=MAX_TREND_MONTHS).contains(&months) {
+        return Err(CommandError::invalid_input(format!("months must be between 1 and {}", MAX_TREND_MONTHS)));
+    }
+    let db = state.db.lock().await;
+    db.get_category_trend(&category, months).map_err(CommandError::from)
+}
+
+/// Compare today, this week or this month with the previous one, in the history time zone
+#[tauri::command]
+async fn get_period_comparison(state: tauri::State<'_, TauriState>, period: String) -> Result<analytics::PeriodComparison, CommandError> {
+    let period: analytics::ComparisonPeriod = period.parse().map_err(CommandError::invalid_input)?;
+    let zone = history_zone(&*state.config.lock().await);
+    let now = chrono::Utc::now().with_timezone(&zone).naive_local();
+    let ((current_start, current_end), (previous_start, previous_end)) = period.ranges(now.date());
+
+    let db = state.db.lock().await;
+    let pricing = state.pricing.lock().await;
+    if period == analytics::ComparisonPeriod::Day {
+        let rate_at = |ts: i64| pricing.rate_at_timestamp(ts);
+        let current = db.get_hourly_stats_in(current_start, &rate_at, &zone)?;
+        let previous = db.get_hourly_stats_in(previous_start, &rate_at, &zone)?;
+        return Ok(analytics::compare_hours(now, &current, &previous));
+    }
+
+    let fmt = |d: chrono::NaiveDate| d.format("%Y-%m-%d").to_string();
+    let rate_at = |ts: i64| pricing.rate_at_timestamp(ts);
+    let days = |start: chrono::NaiveDate, end: chrono::NaiveDate| match zone {
+        HistoryZone::Local => db.get_daily_stats(&fmt(start), &fmt(end)),
+        _ => db.get_daily_stats_filtered_in(&fmt(start), &fmt(end), &TimeFilter::default(), DailyRate::AtTime(&rate_at), &zone),
+    };
+    let current = days(current_start, current_end)?;
+    let previous = days(previous_start, previous_end)?;
+    Ok(analytics::compare_days(period, now, &current, &previous))
+}
+
+/// Energy of a day's sessions against the day's total, to spot daily stats worth rebuilding
+#[tauri::command]
+async fn get_reconciliation(state: tauri::State<'_, TauriState>, date: String) -> Result<analytics::Reconciliation, CommandError> {
+    let day = chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|_| CommandError::invalid_input(format!("Invalid date '{}', expected YYYY-MM-DD", date)))?;
+    let day_start = clock::local_day_start(day, &chrono::Local);
+    let day_end = day.succ_opt().map_or(day_start + 86_400, |next| clock::local_day_start(next, &chrono::Local));
+
+    let active = state.active_session.lock().await;
     let db = state.db.lock().await;
-    db.delete_session(session_id).map_err(|e| e.to_string())
+    let daily = db.get_daily_stats(&date, &date)?.into_iter().next();
+    let mut sessions = db.get_sessions_in_range(day_start, day_end)?;
+    // The stored row of the running session lags behind its live totals
+    if let Some(live) = active.as_ref() {
+        sessions.retain(|s| s.id != Some(live.id));
+        if live.started_at < day_end {
+            sessions.push(live.to_session());
+        }
+    }
+    Ok(analytics::reconcile(&date, day_start, day_end, daily.as_ref(), &sessions, chrono::Utc::now().timestamp()))
+}
+
+/// Observations about recent consumption, in the current language
+#[tauri::command]
+async fn get_insights(state: tauri::State<'_, TauriState>) -> Result<Vec<LocalizedInsight>, CommandError> {
+    let pricing = state.config.lock().await.pricing.clone();
+    let found = {
+        let db = state.db.lock().await;
+        insights::generate(&db, &pricing, &chrono::Local::now())?
+    };
+    let i18n = state.i18n.lock().await;
+    Ok(found.into_iter().map(|insight| insights::localize(insight, &i18n, &pricing.currency_symbol)).collect())
+}
+
+/// Export the sessions started in a time range to a CSV or JSON file, returns how many were written
+///
+/// With samples, the power readings of each session are included (CSV then
+/// becomes a zip of two files).
+#[tauri::command]
+async fn export_sessions(
+    state: tauri::State<'_, TauriState>,
+    path: String,
+    format: String,
+    start_ts: i64,
+    end_ts: i64,
+    include_samples: Option<bool>,
+) -> Result<usize, CommandError> {
+    let format: export::ExportFormat = format.parse().map_err(CommandError::invalid_input)?;
+    if path.trim().is_empty() {
+        return Err(CommandError::invalid_input("Export path is empty"));
+    }
+    if start_ts > end_ts {
+        return Err(CommandError::invalid_input("Start must not be after end"));
+    }
+
+    let exchange_rate = state.pricing.lock().await.exchange_rate();
+    let sessions = {
+        let db = state.db.lock().await;
+        export::collect(&db, start_ts, end_ts, include_samples.unwrap_or(false), exchange_rate.as_ref(), &chrono::Local)?
+    };
+    let count = tauri::async_runtime::spawn_blocking(move || export::write(&sessions, format, std::path::Path::new(&path)))
+        .await
+        .map_err(|e| CommandError::from(e.to_string()))??;
+    log::info!("Exported {} sessions", count);
+    Ok(count)
 }
 
-/// Get sessions in a date range
+/// Get sessions running in a time range, or only those started in it with `overlap_only_start`
+///
+/// With `clip`, sessions crossing the range edges also get their energy within the range.
 #[tauri::command]
-async fn get_sessions_in_range(state: tauri::State<'_, TauriState>, start: i64, end: i64) -> Result<Vec<Session>, String> {
+async fn get_sessions_in_range(
+    state: tauri::State<'_, TauriState>,
+    start: i64,
+    end: i64,
+    overlap_only_start: Option<bool>,
+    clip: Option<bool>,
+) -> Result<Vec<SessionInRange>, CommandError> {
     let db = state.db.lock().await;
-    db.get_sessions_in_range(start, end).map_err(|e| e.to_string())
+    let sessions = if overlap_only_start.unwrap_or(false) {
+        db.get_sessions_started_in_range(start, end)?
+    } else {
+        db.get_sessions_in_range(start, end)?
+    };
+    let now = chrono::Utc::now().timestamp();
+
+    let mut in_range = Vec::with_capacity(sessions.len());
+    for session in sessions {
+        let range_wh = if clip.unwrap_or(false) {
+            let session_end = session.end_time.unwrap_or(now);
+            let samples: Vec<(i64, f64)> = if session.start_time < start || session_end > end {
+                db.get_readings(session.start_time, session_end)?.iter().map(|r| (r.timestamp, r.power_watts)).collect()
+            } else {
+                Vec::new()
+            };
+            Some(analytics::clipped_session_wh(&session, start, end, &samples, now))
+        } else {
+            None
+        };
+        in_range.push(SessionInRange { session, range_wh });
+    }
+    Ok(in_range)
 }
 
 // ===== Tiered Monitoring API (Fast/Slow refresh) =====
 
+/// Turn low-power mode on or off (not persisted), returns the new state
+#[tauri::command]
+async fn set_low_power_mode(app: tauri::AppHandle, enabled: bool) -> Result<bool, CommandError> {
+    apply_low_power_mode(&app, enabled).await;
+    Ok(enabled)
+}
+
 /// Get critical metrics (cached, updated at fast rate)
 /// Returns power, CPU%, GPU%, cost, session data - always responsive
 #[tauri::command]
 async fn get_critical_metrics(state: tauri::State<'_, TauriState>) -> Result<Option<CriticalMetrics>, String> {
-    let cache = state.critical_metrics_cache.lock().await;
-    Ok(cache.clone())
+    let cached = state.critical_metrics_cache.lock().await.clone();
+    Ok(cached.map(|metrics| stamp_critical(&state, metrics)))
 }
 
 /// Get detailed metrics (cached, updated at slow rate)
 /// Returns processes, temps, VRAM - may be slightly stale
 #[tauri::command]
-async fn get_detailed_metrics(state: tauri::State<'_, TauriState>) -> Result<Option<DetailedMetrics>, String> {
-    let cache = state.detailed_metrics_cache.lock().await;
-    Ok(cache.clone())
+async fn get_detailed_metrics(state: tauri::State<'_, TauriState>) -> Result<Option<DetailedPayload>, String> {
+    let cached = state.detailed_metrics_cache.lock().await.clone();
+    Ok(cached.map(|metrics| stamp_detailed(&state, &metrics)))
+}
+
+/// CPU metrics of the last detailed update with every core, detailed payloads stop at `MAX_PAYLOAD_CORES`
+#[tauri::command]
+async fn get_cpu_metrics(state: tauri::State<'_, TauriState>) -> Result<Option<CpuMetrics>, CommandError> {
+    let cached = state.detailed_metrics_cache.lock().await;
+    Ok(cached.as_ref().and_then(|m| m.system_metrics.as_ref()).map(|sys| sys.cpu.clone()))
+}
+
+/// Set the age of critical metrics on their way out, keeping the largest seen
+fn stamp_critical(state: &TauriState, mut metrics: CriticalMetrics) -> CriticalMetrics {
+    metrics.freshness = Freshness::at(metrics.timestamp_ms, chrono::Utc::now().timestamp_millis(), state.refresh_rates.critical_ms());
+    state.max_critical_age_ms.fetch_max(metrics.freshness.age_ms, Ordering::Relaxed);
+    metrics
+}
+
+/// Payload of detailed metrics on their way out with their age, keeping the largest seen
+fn stamp_detailed(state: &TauriState, metrics: &Arc<DetailedMetrics>) -> DetailedPayload {
+    let freshness = Freshness::at(metrics.timestamp_ms, chrono::Utc::now().timestamp_millis(), state.refresh_rates.detailed_ms());
+    state.max_detailed_age_ms.fetch_max(freshness.age_ms, Ordering::Relaxed);
+    DetailedPayload::new(metrics, freshness)
 }
 
 // ===== Elevation commands =====
@@ -648,27 +2744,163 @@ fn is_elevated() -> bool {
     elevation::is_elevated()
 }
 
-/// Relaunch the app with elevated privileges, then exit
+/// Get elevation status: "elevated", "not_elevated", "denied" or "unsupported"
+#[tauri::command]
+async fn get_elevation_status(state: tauri::State<'_, TauriState>) -> Result<elevation::ElevationStatus, String> {
+    let config = state.config.lock().await;
+    Ok(elevation::status(config.general.elevation_denied_at, chrono::Utc::now().timestamp()))
+}
+
+/// Relaunch the app with elevated privileges, then exit once the new process is running
+///
+/// This instance holds the single-instance lock until it exits, the new one
+/// is told to wait for it rather than hand off to it.
 #[tauri::command]
-fn relaunch_elevated() -> bool {
-    if elevation::relaunch_elevated() {
+fn relaunch_elevated() -> Result<bool, String> {
+    if !elevation::is_supported() {
+        return Err("Elevation is not supported on this platform".to_string());
+    }
+    if elevation::relaunch_elevated(&[instance::TAKEOVER_ARG]) {
         std::process::exit(0);
     }
-    false
+    Ok(false)
+}
+
+// ===== Secret Commands =====
+
+/// Run `f` on the secret store from a blocking thread, keychains are reached through subprocesses
+async fn with_secret_store<T: Send + 'static>(
+    secrets: &Arc<SecretStore>,
+    f: impl FnOnce(&SecretStore) -> crate::core::Result<T> + Send + 'static,
+) -> crate::core::Result<T> {
+    let secrets = Arc::clone(secrets);
+    tokio::task::spawn_blocking(move || f(&secrets))
+        .await
+        .map_err(|e| crate::core::Error::Config(format!("Secret store task failed: {}", e)))?
+}
+
+/// Store an integration credential, returns the reference to put in the config
+#[tauri::command]
+async fn set_secret(state: tauri::State<'_, TauriState>, name: String, value: String) -> Result<String, CommandError> {
+    Ok(with_secret_store(&state.secrets, move |secrets| secrets.set_secret(&name, &value)).await?)
+}
+
+/// Delete an integration credential
+#[tauri::command]
+async fn delete_secret(state: tauri::State<'_, TauriState>, name: String) -> Result<(), CommandError> {
+    Ok(with_secret_store(&state.secrets, move |secrets| secrets.delete_secret(&name)).await?)
+}
+
+/// Whether a credential is stored (values are never sent back to the frontend)
+#[tauri::command]
+async fn has_secret(state: tauri::State<'_, TauriState>, name: String) -> Result<bool, CommandError> {
+    Ok(with_secret_store(&state.secrets, move |secrets| secrets.get_secret(&name)).await?.is_some())
+}
+
+/// Which backend holds secrets, and whether it is actually secure
+#[tauri::command]
+fn get_secret_store_info(state: tauri::State<'_, TauriState>) -> SecretStoreInfo {
+    state.secrets.info()
+}
+
+// ===== Webhook Commands =====
+
+/// Build the report for `period` and resolve the webhook's bearer token
+async fn webhook_report(
+    state: &TauriState,
+    config: &WebhookConfig,
+    period: report::ReportPeriod,
+) -> crate::core::Result<(report::Report, Option<String>)> {
+    let currency = state.config.lock().await.pricing.currency.clone();
+    let today = chrono::Local::now().date_naive();
+    let exchange_rate = state.pricing.lock().await.exchange_rate();
+    let report = report::build_report(&*state.db.lock().await, period, today, &currency)?.with_exchange_rate(exchange_rate);
+
+    let token = match config.auth_secret.clone().filter(|name| !name.is_empty()) {
+        Some(name) => {
+            let lookup = name.clone();
+            Some(
+                with_secret_store(&state.secrets, move |secrets| secrets.get_secret(&lookup))
+                    .await?
+                    .ok_or_else(|| crate::core::Error::Config(format!("Webhook secret '{}' is not stored", name)))?,
+            )
+        }
+        None => None,
+    };
+    Ok((report, token))
+}
+
+/// Send the report for the configured schedule right away (single attempt)
+#[tauri::command]
+async fn test_webhook(state: tauri::State<'_, TauriState>) -> Result<report::Report, CommandError> {
+    let config = state.config.lock().await.webhook.clone();
+    if config.url.is_empty() {
+        return Err(CommandError::invalid_input("No webhook URL configured"));
+    }
+    let period = webhook::validate_config(&WebhookConfig { enabled: true, ..config.clone() })
+        .map_err(CommandError::invalid_input)?;
+
+    let (report, token) = webhook_report(&state, &config, period).await?;
+    webhook::post_report(&config.url, token.as_deref(), &report).await?;
+    log::info!("Test webhook delivered to {}", config.url);
+    Ok(report)
+}
+
+// ===== Session Template Commands =====
+
+/// Get all saved session templates
+#[tauri::command]
+async fn get_session_templates(state: tauri::State<'_, TauriState>) -> Result<Vec<SessionTemplate>, CommandError> {
+    let config = state.config.lock().await;
+    Ok(config.advanced.session_templates.clone())
+}
+
+/// Save a session template (upsert by name)
+#[tauri::command]
+async fn save_session_template(state: tauri::State<'_, TauriState>, template: SessionTemplate) -> Result<Vec<SessionTemplate>, CommandError> {
+    if template.name.trim().is_empty() {
+        return Err(CommandError::invalid_input("Template name cannot be empty"));
+    }
+    if template.target_cost.is_some_and(|t| t <= 0.0 || !t.is_finite()) {
+        return Err(CommandError::invalid_input("Target cost must be greater than zero"));
+    }
+    if template.auto_end_minutes == Some(0) {
+        return Err(CommandError::invalid_input("Auto-end duration must be at least one minute"));
+    }
+
+    let mut config = state.config.lock().await;
+    // Upsert: replace existing template with same name, or add new
+    if let Some(existing) = config.advanced.session_templates.iter_mut().find(|t| t.name == template.name) {
+        *existing = template;
+    } else {
+        config.advanced.session_templates.push(template);
+    }
+
+    config.save()?;
+    Ok(config.advanced.session_templates.clone())
+}
+
+/// Delete a session template by name
+#[tauri::command]
+async fn delete_session_template(state: tauri::State<'_, TauriState>, name: String) -> Result<Vec<SessionTemplate>, CommandError> {
+    let mut config = state.config.lock().await;
+    config.advanced.session_templates.retain(|t| t.name != name);
+    config.save()?;
+    Ok(config.advanced.session_templates.clone())
 }
 
 // ===== Layout Profile Commands =====
 
 /// Get all saved layout profiles
 #[tauri::command]
-async fn get_layout_profiles(state: tauri::State<'_, TauriState>) -> Result<Vec<LayoutProfile>, String> {
+async fn get_layout_profiles(state: tauri::State<'_, TauriState>) -> Result<Vec<LayoutProfile>, CommandError> {
     let config = state.config.lock().await;
     Ok(config.dashboard.profiles.clone())
 }
 
 /// Save current layout as a named profile (upsert)
 #[tauri::command]
-async fn save_layout_profile(state: tauri::State<'_, TauriState>, name: String) -> Result<Vec<LayoutProfile>, String> {
+async fn save_layout_profile(state: tauri::State<'_, TauriState>, name: String) -> Result<Vec<LayoutProfile>, CommandError> {
     let mut config = state.config.lock().await;
     let profile = LayoutProfile {
         name: name.clone(),
@@ -684,13 +2916,13 @@ async fn save_layout_profile(state: tauri::State<'_, TauriState>, name: String)
     }
 
     config.dashboard.active_profile = name;
-    config.save().map_err(|e| e.to_string())?;
+    config.save()?;
     Ok(config.dashboard.profiles.clone())
 }
 
 /// Load a named profile, applying its widgets to the active config
 #[tauri::command]
-async fn load_layout_profile(state: tauri::State<'_, TauriState>, name: String) -> Result<crate::core::DashboardConfig, String> {
+async fn load_layout_profile(state: tauri::State<'_, TauriState>, name: String) -> Result<crate::core::DashboardConfig, CommandError> {
     let mut config = state.config.lock().await;
     let profile = config.dashboard.profiles.iter().find(|p| p.name == name).cloned();
 
@@ -699,18 +2931,19 @@ async fn load_layout_profile(state: tauri::State<'_, TauriState>, name: String)
             config.dashboard.widgets = p.widgets;
             config.dashboard.global_display = p.global_display;
             config.dashboard.active_profile = name;
-            config.save().map_err(|e| e.to_string())?;
+            config.save()?;
+            state.config_watch.publish(&config);
             Ok(config.dashboard.clone())
         }
-        None => Err(format!("Profile '{}' not found", name)),
+        None => Err(CommandError::not_found(format!("Profile '{}' not found", name))),
     }
 }
 
 /// Delete a named profile
 #[tauri::command]
-async fn delete_layout_profile(state: tauri::State<'_, TauriState>, name: String) -> Result<Vec<LayoutProfile>, String> {
+async fn delete_layout_profile(state: tauri::State<'_, TauriState>, name: String) -> Result<Vec<LayoutProfile>, CommandError> {
     if name == "Default" {
-        return Err("Cannot delete the Default profile".to_string());
+        return Err(CommandError::invalid_input("Cannot delete the Default profile"));
     }
     let mut config = state.config.lock().await;
     config.dashboard.profiles.retain(|p| p.name != name);
@@ -720,35 +2953,85 @@ async fn delete_layout_profile(state: tauri::State<'_, TauriState>, name: String
         config.dashboard.active_profile = String::new();
     }
 
-    config.save().map_err(|e| e.to_string())?;
+    config.save()?;
     Ok(config.dashboard.profiles.clone())
 }
 
-// ===== Update Check =====
+// ===== Statistics Reset =====
+
+/// How long a reset confirmation token stays valid
+const RESET_TOKEN_TTL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Issue a one-time token that must be passed back to `reset_statistics`
+#[tauri::command]
+async fn prepare_reset(state: tauri::State<'_, TauriState>, scope: ResetScope) -> Result<String, CommandError> {
+    use std::hash::{BuildHasher, Hasher};
+
+    // RandomState is seeded randomly, which is enough for an anti-misclick token
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u128(std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0));
+    let token = format!("{:016x}", hasher.finish());
+
+    *state.pending_reset.lock().await = Some(PendingReset {
+        token: token.clone(),
+        scope,
+        expires_at: std::time::Instant::now() + RESET_TOKEN_TTL,
+    });
+
+    Ok(token)
+}
+
+/// Delete statistics in the given scope, returns deleted row counts per table
+#[tauri::command]
+async fn reset_statistics(
+    state: tauri::State<'_, TauriState>,
+    scope: ResetScope,
+    token: String,
+    force: Option<bool>,
+) -> Result<ResetCounts, CommandError> {
+    // The token is single-use, whatever the outcome
+    let pending = state.pending_reset.lock().await.take();
+    match pending {
+        Some(p) if p.token == token && p.scope == scope && p.expires_at > std::time::Instant::now() => {}
+        _ => return Err(CommandError::invalid_input("Reset confirmation is invalid or expired")),
+    }
+
+    let mut active_session = state.active_session.lock().await;
+    if active_session.is_some() && !force.unwrap_or(false) {
+        return Err(CommandError::session_active("Cannot reset statistics while a session is active"));
+    }
 
-#[derive(serde::Serialize, Clone)]
-struct UpdateCheckResult {
-    update_available: bool,
-    current_version: String,
-    latest_version: String,
-    release_url: String,
-    release_notes: String,
-}
-
-/// Compare two semver strings, returns true if `latest` is newer than `current`
-fn version_is_newer(current: &str, latest: &str) -> bool {
-    let parse = |v: &str| -> (u64, u64, u64) {
-        let v = v.trim_start_matches('v');
-        let parts: Vec<u64> = v.split('.').filter_map(|p| p.parse().ok()).collect();
-        (
-            parts.first().copied().unwrap_or(0),
-            parts.get(1).copied().unwrap_or(0),
-            parts.get(2).copied().unwrap_or(0),
-        )
+    let counts = {
+        let db = state.db.lock().await;
+        db.reset(scope)?
     };
-    parse(latest) > parse(current)
+
+    // The active session row is gone, stop tracking it
+    if scope.includes_sessions() {
+        *active_session = None;
+    }
+    drop(active_session);
+
+    if scope != ResetScope::Sessions {
+        *state.app_state.lock().await = AppState::new();
+        *state.critical_metrics_cache.lock().await = None;
+        // Both are built from the deleted daily stats
+        *state.today_stats_cache.lock().await = None;
+        *state.billing_cache.lock().await = None;
+    }
+
+    log::info!(
+        "Statistics reset ({:?}): {} readings, {} daily stats, {} sessions deleted",
+        scope, counts.readings, counts.daily_stats, counts.sessions
+    );
+    Ok(counts)
 }
 
+// ===== Update Check =====
+
 #[tauri::command]
 async fn open_url(app: tauri::AppHandle, url: String) -> Result<(), String> {
     app.opener().open_url(&url, None::<&str>).map_err(|e| e.to_string())
@@ -756,46 +3039,60 @@ async fn open_url(app: tauri::AppHandle, url: String) -> Result<(), String> {
 
 #[tauri::command]
 fn get_app_version() -> String {
-    env!("CARGO_PKG_VERSION").to_string()
+    updater::current_version()
 }
 
-#[tauri::command]
-async fn check_for_updates() -> Result<UpdateCheckResult, String> {
-    let current_version = env!("CARGO_PKG_VERSION").to_string();
-
-    let client = reqwest::Client::builder()
-        .user_agent(format!("PowerCost-Tracker/{}", current_version))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+/// Number of network attempts made by the startup update check
+const STARTUP_UPDATE_ATTEMPTS: u32 = 3;
 
-    let resp = client
-        .get("https://api.github.com/repos/HLE-C0DE/PowerCost-Tracker/releases/latest")
-        .send()
-        .await
-        .map_err(|e| format!("Failed to fetch releases: {}", e))?;
+/// Run an update check, serving the cache when fresh and falling back to it when offline
+async fn run_update_check(state: &TauriState, force: bool) -> Result<UpdateCheckResult, String> {
+    let now = chrono::Utc::now().timestamp();
 
-    if !resp.status().is_success() {
-        return Err(format!("GitHub API returned status {}", resp.status()));
+    {
+        let checker = state.update_checker.lock().await;
+        if !force {
+            if let Some(cached) = checker.fresh_cached(now) {
+                return Ok(cached);
+            }
+            if !checker.can_attempt(now) {
+                return checker
+                    .stale_cached()
+                    .ok_or_else(|| "Update check postponed after a recent failure".to_string());
+            }
+        }
     }
 
-    let json: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    // Don't hold the lock across the network request
+    match updater::fetch_latest_release().await {
+        Ok(result) => {
+            state.update_checker.lock().await.record_success(&result);
+            Ok(result)
+        }
+        Err(e) => {
+            let mut checker = state.update_checker.lock().await;
+            let delay = checker.record_failure(now);
+            log::warn!("Update check failed (next retry in {}s): {}", delay, e);
+            checker.stale_cached().ok_or_else(|| e.to_string())
+        }
+    }
+}
 
-    let tag = json["tag_name"].as_str().unwrap_or("").to_string();
-    let release_url = json["html_url"].as_str().unwrap_or("").to_string();
-    let release_notes = json["body"].as_str().unwrap_or("").to_string();
+#[tauri::command]
+async fn check_for_updates(state: tauri::State<'_, TauriState>) -> Result<UpdateCheckResult, String> {
+    run_update_check(&state, false).await
+}
 
-    let update_available = version_is_newer(&current_version, &tag);
+/// Release notes for every version newer than `since_version`
+#[tauri::command]
+async fn get_changelog(since_version: String) -> Result<updater::ChangelogResult, String> {
+    updater::get_changelog(&since_version).await.map_err(|e| e.to_string())
+}
 
-    Ok(UpdateCheckResult {
-        update_available,
-        current_version,
-        latest_version: tag.trim_start_matches('v').to_string(),
-        release_url,
-        release_notes,
-    })
+/// Manual "Check now": always queries GitHub
+#[tauri::command]
+async fn force_check_for_updates(state: tauri::State<'_, TauriState>) -> Result<UpdateCheckResult, String> {
+    run_update_check(&state, true).await
 }
 
 fn main() {
@@ -805,39 +3102,120 @@ fn main() {
     log::info!("Starting PowerCost Tracker v{}", env!("CARGO_PKG_VERSION"));
 
     // Load or create configuration
-    let config = Config::load().unwrap_or_else(|e| {
+    let (mut config, config_recovery) = Config::load().unwrap_or_else(|e| {
         log::warn!("Failed to load config, using defaults: {}", e);
-        Config::default()
+        (Config::default(), None)
     });
 
     // Auto-relaunch elevated if configured (Windows only)
-    if config.general.run_as_admin && !elevation::is_elevated() {
-        log::info!("Run as admin is enabled but not elevated, requesting elevation...");
-        if elevation::relaunch_elevated() {
-            log::info!("Elevated process launched, exiting current instance");
-            std::process::exit(0);
+    if config.general.run_as_admin && elevation::is_supported() {
+        let now = chrono::Utc::now().timestamp();
+        if elevation::is_elevated() {
+            // Elevation worked, forget any previous denial
+            if config.general.elevation_denied_at.take().is_some() {
+                let _ = config.save();
+            }
+        } else if elevation::should_prompt(config.general.elevation_denied_at, now) {
+            log::info!("Run as admin is enabled but not elevated, requesting elevation...");
+            if elevation::relaunch_elevated(&[]) {
+                log::info!("Elevated process launched, exiting current instance");
+                std::process::exit(0);
+            }
+            log::warn!("UAC was denied or elevation failed, not asking again for 24 hours");
+            config.general.elevation_denied_at = Some(now);
+            if let Err(e) = config.save() {
+                log::warn!("Failed to save elevation denial: {}", e);
+            }
+        } else {
+            log::info!("Elevation was denied recently, skipping prompt");
         }
-        log::warn!("UAC was denied or elevation failed, continuing without elevation");
     }
 
-    // Initialize database
-    let db = Database::new().unwrap_or_else(|e| {
-        log::error!("Failed to initialize database: {}", e);
-        std::process::exit(1);
-    });
+    // Hand off to an instance already running instead of monitoring twice,
+    // unless it relaunched this one to be replaced
+    let instance_port = instance::port_for(&instance::default_key());
+    let acquired = if std::env::args().any(|arg| arg == instance::TAKEOVER_ARG) {
+        instance::take_over(instance_port)
+    } else {
+        instance::acquire(instance_port)
+    };
+    let instance_lock = match acquired {
+        instance::Acquired::Primary(lock) => Some(lock),
+        instance::Acquired::HandedOff => {
+            log::info!("PowerCost Tracker is already running, showed its window instead");
+            return;
+        }
+        instance::Acquired::Unguarded(reason) => {
+            log::warn!("Single-instance lock unavailable, duplicate launches are not prevented: {}", reason);
+            None
+        }
+    };
+
+    // Initialize database, in memory when the data file cannot be opened (read-only data directory)
+    let (db, persistence_error) = match Database::new() {
+        Ok(db) => (db, None),
+        Err(e) => {
+            log::error!("Failed to open the database, history will not be saved: {}", e);
+            let db = Database::in_memory().unwrap_or_else(|e| {
+                log::error!("Failed to initialize database: {}", e);
+                std::process::exit(1);
+            });
+            (db, Some(e.to_string()))
+        }
+    };
+    db.set_duplicate_window_secs(config.advanced.duplicate_reading_window_secs);
+
+    // Close sessions left open by a crash (nothing is active at startup)
+    match db.recover_orphaned_sessions() {
+        Ok(recovered) if !recovered.is_empty() => {
+            log::info!("Recovered {} session(s) interrupted by a crash", recovered.len());
+        }
+        Ok(_) => {}
+        Err(e) => log::warn!("Failed to recover interrupted sessions: {}", e),
+    }
 
     // Initialize power monitor
-    let monitor = PowerMonitor::new().unwrap_or_else(|e| {
+    let mut monitor = PowerMonitor::new(&MonitorOptions::from_config(&config)).unwrap_or_else(|e| {
         log::warn!("Failed to initialize power monitor: {}", e);
         PowerMonitor::estimation_fallback()
     });
+    monitor.set_gpu_estimate(GpuEstimate::from_config(&config.advanced));
+    monitor.set_estimation_correction(config.advanced.estimation_correction);
+
+    // Keep track of which source produced the stored readings
+    match db.record_source(monitor.get_source_name(), monitor.is_estimated()) {
+        Ok(Some(change)) if change.is_estimated && change.old_source.is_some() => {
+            log::warn!(
+                "Power source changed from {} to {} (estimated), new readings are not measured",
+                change.old_source.as_deref().unwrap_or_default(), change.new_source
+            );
+        }
+        Ok(Some(change)) => log::info!("Power source recorded: {}", change.new_source),
+        Ok(None) => {}
+        Err(e) => log::warn!("Failed to record power source: {}", e),
+    }
 
-    // Initialize pricing engine
-    let pricing = PricingEngine::new(&config.pricing);
+    // Initialize pricing engine, with the last fetched exchange rate
+    let mut pricing = PricingEngine::new(&config.pricing);
+    pricing.set_fetched_rate(pricing::currency::load_cached());
+
+    // Days stored without a cost get one at their day's average rate
+    match db.backfill_missing_costs(&|date| backfill_rate(&pricing, date)) {
+        Ok(0) => {}
+        Ok(count) => log::info!("Backfilled the cost of {} day(s)", count),
+        Err(e) => log::warn!("Failed to backfill missing costs: {}", e),
+    }
 
     // Initialize i18n
     let i18n = I18n::new(&config.general.language);
 
+    // First run: default categories get names in the user's language
+    if config.advanced.seed_session_categories(|key| i18n.get(key)) {
+        if let Err(e) = config.save() {
+            log::warn!("Failed to save seeded session categories: {}", e);
+        }
+    }
+
     // Create application state
     let app_state = AppState::new();
 
@@ -846,6 +3224,18 @@ fn main() {
     if !config.advanced.baseline_auto && config.advanced.baseline_watts > 0.0 {
         baseline_detector.set_manual_baseline(config.advanced.baseline_watts);
     }
+    baseline_detector.set_schedule(&config.advanced.baseline_schedule);
+
+    let power_sanitizer = PowerSanitizer::new(config.advanced.max_power_watts, config.advanced.spike_filter_factor);
+
+    // Only the integrations needing a credential are affected
+    let secrets = SecretStore::new().unwrap_or_else(|e| {
+        log::error!("Failed to initialize secret store, secrets are disabled: {}", e);
+        SecretStore::unavailable()
+    });
+
+    let refresh_rates = RefreshRates::new(&config);
+    let config_watch = ConfigWatch::new(&config);
 
     // Wrap in Arc<Mutex> for thread-safe sharing
     let state = TauriState {
@@ -856,9 +3246,33 @@ fn main() {
         i18n: Arc::new(Mutex::new(i18n)),
         app_state: Arc::new(Mutex::new(app_state)),
         baseline_detector: Arc::new(Mutex::new(baseline_detector)),
+        power_sanitizer: Arc::new(Mutex::new(power_sanitizer)),
         active_session: Arc::new(Mutex::new(None)),
         critical_metrics_cache: Arc::new(Mutex::new(None)),
         detailed_metrics_cache: Arc::new(Mutex::new(None)),
+        update_checker: Arc::new(Mutex::new(UpdateChecker::new())),
+        pending_reset: Arc::new(Mutex::new(None)),
+        billing_cache: Arc::new(Mutex::new(None)),
+        listeners_visible: Arc::new(AtomicBool::new(true)),
+        suppressed_emits: Arc::new(AtomicU64::new(0)),
+        max_critical_age_ms: Arc::new(AtomicU64::new(0)),
+        max_detailed_age_ms: Arc::new(AtomicU64::new(0)),
+        persistence_disabled: Arc::new(AtomicBool::new(persistence_error.is_some())),
+        config_recovery,
+        refresh_rates: Arc::new(refresh_rates),
+        config_watch: Arc::new(config_watch),
+        secrets: Arc::new(secrets),
+        webhook: Arc::new(Mutex::new(WebhookNotifier::new())),
+        tray_menu: Arc::new(Mutex::new(None)),
+        history_rebuild: Arc::new(HistoryRebuild::default()),
+        core_history: Arc::new(Mutex::new(CoreHistory::new())),
+        process_history: Arc::new(Mutex::new(ProcessHistory::new())),
+        vram_alert: Arc::new(Mutex::new(VramAlertTracker::new())),
+        notifications: Arc::new(Mutex::new(None)),
+        capabilities: Arc::new(Mutex::new(None)),
+        input_idle: Arc::new(Mutex::new(InputIdle::new())),
+        today_stats_cache: Arc::new(Mutex::new(None)),
+        geometry_save_generation: Arc::new(AtomicU64::new(0)),
     };
 
     tauri::Builder::default()
@@ -866,7 +3280,7 @@ fn main() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_autostart::init(
             tauri_plugin_autostart::MacosLauncher::LaunchAgent,
-            None,
+            Some(vec![AUTOSTART_ARG]),
         ))
         .manage(state)
         .invoke_handler(tauri::generate_handler![
@@ -881,9 +3295,36 @@ fn main() {
             get_translations,
             get_history,
             get_readings,
+            get_temperature_history,
+            get_vram_history,
+            get_source_history,
+            get_monitoring_health,
+            get_gpu_estimate_suggestion,
+            get_estimation_accuracy,
+            get_capabilities,
+            reinitialize_power_monitor,
+            retry_database_init,
             open_widget,
             close_widget,
             toggle_widget,
+            get_widget_capabilities,
+            // Pricing presets
+            get_pricing_presets,
+            apply_pricing_preset,
+            confirm_region_defaults,
+            // Billing
+            get_billing_summary,
+            get_cost_breakdown,
+            get_savings_summary,
+            rebuild_history,
+            cancel_history_rebuild,
+            dedup_readings,
+            get_lifetime_totals,
+            test_session_hook,
+            // Locale formatting commands
+            format_number,
+            format_date,
+            format_duration,
             // New system metrics commands
             get_system_metrics,
             get_top_processes,
@@ -891,13 +3332,23 @@ fn main() {
             pin_process,
             unpin_process,
             get_pinned_processes,
+            get_pinned_process_history,
+            ignore_process,
+            unignore_process,
+            get_ignored_processes,
             kill_process,
             set_process_limit,
             // Session tracking commands
             start_tracking_session,
+            start_session_from_template,
             end_tracking_session,
             get_session_stats,
+            get_session_detail,
+            run_source_benchmark,
+            get_hardware_info,
             get_sessions,
+            recalculate_session_surplus,
+            recalculate_sessions_surplus,
             // Baseline detection commands
             detect_baseline,
             set_manual_baseline,
@@ -905,7 +3356,18 @@ fn main() {
             // Dashboard config commands
             get_dashboard_config,
             save_dashboard_config,
+            update_dashboard_widget,
+            validate_dashboard_config,
+            get_widget_catalog,
             // Layout profile commands
+            set_secret,
+            delete_secret,
+            has_secret,
+            get_secret_store_info,
+            test_webhook,
+            get_session_templates,
+            save_session_template,
+            delete_session_template,
             get_layout_profiles,
             save_layout_profile,
             load_layout_profile,
@@ -915,27 +3377,70 @@ fn main() {
             // Tiered monitoring API (fast/slow refresh)
             get_critical_metrics,
             get_detailed_metrics,
+            get_cpu_metrics,
             // Session category & label commands
             update_session_label,
             update_session_category,
+            bulk_update_session_category,
             get_session_categories,
             add_session_category,
             remove_session_category,
+            rename_session_category,
+            set_session_category_baseline,
+            get_resolved_theme,
             get_sessions_in_range,
+            get_sessions_grouped_by_day,
+            get_category_trend,
+            get_period_comparison,
+            get_reconciliation,
+            set_day_note,
+            get_day_notes,
+            delete_day_note,
+            get_insights,
+            get_exchange_rate,
+            refresh_exchange_rate,
+            request_widget_update,
+            get_widget_tooltip,
+            get_per_core_history,
+            set_low_power_mode,
+            export_sessions,
             delete_session,
+            render_session_receipt,
+            test_notification,
             // Elevation commands
             is_elevated,
+            get_elevation_status,
             relaunch_elevated,
+            // Statistics reset
+            prepare_reset,
+            reset_statistics,
             // Update check
             get_app_version,
             check_for_updates,
+            force_check_for_updates,
+            get_changelog,
             open_url,
         ])
-        .setup(|app| {
+        .setup(move |app| {
             let app_handle = app.handle().clone();
 
-            // Check if start_minimized is enabled and hide the main window
+            // Later launches bring this instance's window up
+            if let Some(lock) = instance_lock {
+                let handle = app_handle.clone();
+                lock.serve(move || {
+                    show_main_window(&handle);
+                });
+            }
+
             let state: tauri::State<'_, TauriState> = app.state();
+            reconcile_autostart(&app_handle, &state);
+
+            if let Some(error) = persistence_error.as_ref() {
+                let _ = app_handle.emit("persistence-disabled", error);
+            }
+
+            // Check if start_minimized is enabled (or this is a boot launch) and hide the main window
+            let launched_at_boot = std::env::args().any(|arg| arg == AUTOSTART_ARG);
             let (start_minimized, remember_pos, win_x, win_y, win_w, win_h) = {
                 // Use block_on since we're in sync context
                 let config = tauri::async_runtime::block_on(state.config.lock());
@@ -957,23 +3462,7 @@ fn main() {
                     }
                     if let (Some(x), Some(y)) = (win_x, win_y) {
                         // Verify position is within available screen area (physical coords)
-                        let is_visible = main_window.available_monitors().ok()
-                            .map(|monitors| {
-                                monitors.iter().any(|m| {
-                                    let pos = m.position();
-                                    let size = m.size();
-                                    let mx = pos.x as f64;
-                                    let my = pos.y as f64;
-                                    let mw = size.width as f64;
-                                    let mh = size.height as f64;
-                                    // Check if at least part of the window is visible on this monitor
-                                    x < mx + mw && x + win_w.unwrap_or(900.0) > mx &&
-                                    y < my + mh && y + win_h.unwrap_or(600.0) > my
-                                })
-                            })
-                            .unwrap_or(false);
-
-                        if is_visible {
+                        if on_any_monitor(&main_window, x, y, win_w.unwrap_or(900.0), win_h.unwrap_or(600.0)) {
                             let _ = main_window.set_position(PhysicalPosition::new(x as i32, y as i32));
                             log::info!("Restored window position: ({}, {})", x, y);
                         } else {
@@ -983,7 +3472,7 @@ fn main() {
                 }
             }
 
-            if !start_minimized {
+            if !start_minimized && !launched_at_boot {
                 if let Some(main_window) = app.get_webview_window("main") {
                     let _ = main_window.show();
                     log::info!("Main window shown on startup");
@@ -997,8 +3486,19 @@ fn main() {
             let quit_item = MenuItem::with_id(app, "quit", i18n.get("tray.exit"), true, None::<&str>)?;
             let show_item = MenuItem::with_id(app, "show", i18n.get("tray.show"), true, None::<&str>)?;
             let restart_item = MenuItem::with_id(app, "restart", i18n.get("tray.restart"), true, None::<&str>)?;
+            let low_power_item = CheckMenuItem::with_id(app, "low_power", i18n.get("tray.low_power"), true, false, None::<&str>)?;
             drop(i18n);
-            let menu = Menu::with_items(app, &[&show_item, &restart_item, &quit_item])?;
+            let menu = Menu::with_items(app, &[&show_item, &low_power_item, &restart_item, &quit_item])?;
+            let notification_config = tauri::async_runtime::block_on(state.config.lock()).general.notifications.clone();
+            let notifier = TauriNotifier { app: app.handle().clone(), refresh_rates: state.refresh_rates.clone() };
+            *tauri::async_runtime::block_on(state.notifications.lock()) = Some(NotificationCenter::new(Box::new(notifier), notification_config));
+
+            *tauri::async_runtime::block_on(state.tray_menu.lock()) = Some(TrayMenu {
+                show: show_item,
+                restart: restart_item,
+                quit: quit_item,
+                low_power: low_power_item,
+            });
 
             // Build tray icon with menu
             let _tray = TrayIconBuilder::new()
@@ -1011,12 +3511,15 @@ fn main() {
                             log::info!("Quit requested from tray menu");
                             std::process::exit(0);
                         }
-                        "show" => {
-                            if let Some(window) = app.get_webview_window("main") {
-                                let _ = window.show();
-                                let _ = window.set_focus();
-                                log::info!("Window shown from tray menu");
-                            }
+                        "show" if show_main_window(app) => {
+                            log::info!("Window shown from tray menu");
+                        }
+                        "low_power" => {
+                            let app = app.clone();
+                            tauri::async_runtime::spawn(async move {
+                                let enabled = !app.state::<TauriState>().refresh_rates.low_power();
+                                apply_low_power_mode(&app, enabled).await;
+                            });
                         }
                         "restart" => {
                             log::info!("Restart requested from tray menu");
@@ -1027,9 +3530,7 @@ fn main() {
                 })
                 .on_tray_icon_event(|tray, event| {
                     if let TrayIconEvent::Click { button: MouseButton::Left, button_state: MouseButtonState::Up, .. } = event {
-                        if let Some(window) = tray.app_handle().get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
+                        if show_main_window(tray.app_handle()) {
                             log::info!("Window shown from tray icon click");
                         }
                     }
@@ -1047,18 +3548,43 @@ fn main() {
                     tauri::async_runtime::spawn(async move {
                         // Delay to avoid slowing down startup
                         tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
-                        match check_for_updates().await {
-                            Ok(result) if result.update_available => {
-                                let _ = app_handle_updates.emit("update-available", result);
-                                log::info!("Update available, notified frontend");
+                        let state = app_handle_updates.state::<TauriState>();
+                        let mut notified = false;
+
+                        // Retry a few times while offline, following the checker's backoff
+                        for attempt in 1..=STARTUP_UPDATE_ATTEMPTS {
+                            match run_update_check(&state, false).await {
+                                Ok(result) => {
+                                    if result.update_available && !notified {
+                                        let _ = app_handle_updates.emit("update-available", result.clone());
+                                        log::info!("Update available, notified frontend");
+                                        notified = true;
+                                    } else if !result.update_available {
+                                        log::info!("App is up to date");
+                                    }
+                                    if !result.stale {
+                                        break;
+                                    }
+                                }
+                                Err(e) => log::warn!("Startup update check failed: {}", e),
+                            }
+
+                            if attempt == STARTUP_UPDATE_ATTEMPTS {
+                                break;
                             }
-                            Ok(_) => log::info!("App is up to date"),
-                            Err(e) => log::warn!("Startup update check failed: {}", e),
+                            let delay = state.update_checker.lock().await.retry_delay_secs().max(1);
+                            tokio::time::sleep(tokio::time::Duration::from_secs(delay as u64)).await;
                         }
                     });
                 }
             }
 
+            // Daily stats were keyed by UTC dates before schema 7: rebuild them with local dates
+            let app_handle_rebuild = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                local_dates_rebuild(app_handle_rebuild).await;
+            });
+
             // Start critical monitoring loop (fast rate: power, CPU%, GPU%, cost)
             let app_handle_critical = app_handle.clone();
             tauri::async_runtime::spawn(async move {
@@ -1071,16 +3597,89 @@ fn main() {
                 detailed_monitoring_loop(app_handle_detailed).await;
             });
 
+            // Capture the hardware inventory once, off the monitoring loops (nvidia-smi can be slow)
+            let app_handle_inventory = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                let inventory = match tauri::async_runtime::spawn_blocking(hardware::inventory::collect).await {
+                    Ok(inventory) => inventory,
+                    Err(e) => {
+                        log::warn!("Failed to collect hardware inventory: {}", e);
+                        return;
+                    }
+                };
+                let state = app_handle_inventory.state::<TauriState>();
+                let db = state.db.lock().await;
+                match db.record_hardware_snapshot(&inventory) {
+                    Ok(true) => log::info!("Hardware inventory recorded: {} GPU(s), {}", inventory.gpus.len(), inventory.cpu_model),
+                    Ok(false) => {}
+                    Err(e) => log::warn!("Failed to store hardware inventory: {}", e),
+                }
+            });
+
+            // Probe what the power source can report, off the monitoring loops (nvidia-smi can be slow)
+            let app_handle_capabilities = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                let state = app_handle_capabilities.state::<TauriState>();
+                refresh_capabilities(&state).await;
+            });
+
+            // Follow the OS light/dark preference (does nothing unless the theme is "system")
+            let app_handle_theme = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                theme_watch_loop(app_handle_theme).await;
+            });
+
+            // Fetch the secondary currency rate once a day (does nothing unless enabled)
+            let app_handle_rates = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                exchange_rate_loop(app_handle_rates).await;
+            });
+
+            // Start scheduled report push (does nothing until a webhook is enabled)
+            let app_handle_webhook = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                webhook_loop(app_handle_webhook).await;
+            });
+
+            // Compare the estimator with the measuring source (does nothing until enabled)
+            let app_handle_accuracy = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                accuracy_loop(app_handle_accuracy).await;
+            });
+
+            // Open the widget automatically (independent of start_minimized)
+            let widget = tauri::async_runtime::block_on(state.config.lock()).widget.clone();
+            if widget.enabled {
+                let title = tauri::async_runtime::block_on(state.i18n.lock()).get("widget.window_title");
+                if let Err(e) = open_widget_window(&app_handle, &widget, &title) {
+                    log::warn!("Failed to open widget on startup: {}", e);
+                }
+            }
+
             Ok(())
         })
         .on_window_event(|window, event| {
+            if window.label() == "widget" {
+                // Closed by the user (close button, toggle or the OS), not by app exit
+                if let tauri::WindowEvent::CloseRequested { .. } = event {
+                    let position = widget::capabilities().positioning.then(|| window.outer_position().ok()).flatten();
+                    let app = window.app_handle().clone();
+                    tauri::async_runtime::spawn(async move {
+                        remember_widget_closed(&app, position).await;
+                        let _ = app.emit("widget-closed", ());
+                    });
+                }
+                return;
+            }
             if window.label() != "main" {
                 return;
             }
 
             match event {
                 tauri::WindowEvent::CloseRequested { api, .. } => {
-                    // Save window geometry before hiding
+                    // Save window geometry before hiding, a pending debounced save is dropped
+                    let state: tauri::State<'_, TauriState> = window.state();
+                    state.geometry_save_generation.fetch_add(1, Ordering::Relaxed);
                     let app = window.app_handle().clone();
                     let win = window.clone();
                     tauri::async_runtime::spawn(async move {
@@ -1093,11 +3692,17 @@ fn main() {
                     log::info!("Main window hidden to tray");
                 }
                 tauri::WindowEvent::Moved(_) | tauri::WindowEvent::Resized(_) => {
-                    // Save geometry on move/resize (in case of crash before close)
+                    // Save geometry once the window stops moving (in case of crash before close)
+                    let state: tauri::State<'_, TauriState> = window.state();
+                    let generation = Arc::clone(&state.geometry_save_generation);
+                    let pending = generation.fetch_add(1, Ordering::Relaxed) + 1;
                     let app = window.app_handle().clone();
                     let win = window.clone();
                     tauri::async_runtime::spawn(async move {
-                        save_window_geometry(&app, &win).await;
+                        tokio::time::sleep(GEOMETRY_SAVE_DELAY).await;
+                        if generation.load(Ordering::Relaxed) == pending {
+                            save_window_geometry(&app, &win).await;
+                        }
                     });
                 }
                 _ => {}
@@ -1107,6 +3712,9 @@ fn main() {
         .expect("error while running tauri application");
 }
 
+/// Quiet time after the last move or resize of the main window before its geometry is saved
+const GEOMETRY_SAVE_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
 /// Save window position and size to config
 async fn save_window_geometry(app: &tauri::AppHandle, window: &tauri::Window) {
     let state: tauri::State<'_, TauriState> = app.state();
@@ -1135,6 +3743,27 @@ async fn save_window_geometry(app: &tauri::AppHandle, window: &tauri::Window) {
     }
 }
 
+/// Move the running session start into the new wall-clock frame after a backward jump
+///
+/// Otherwise the session would start in the future and end before it started.
+async fn reanchor_active_session(state: &TauriState, jump_secs: i64) {
+    let (id, started_at) = {
+        let mut active = state.active_session.lock().await;
+        let Some(session) = active.as_mut() else {
+            return;
+        };
+        session.started_at = clock::anchored_start(chrono::Utc::now().timestamp(), session.start_time.elapsed().as_secs());
+        session.idle_tracker.shift(jump_secs);
+        (session.id, session.started_at)
+    };
+    if let Err(e) = state.db.lock().await.reanchor_session_start(id, started_at) {
+        log::warn!("Failed to re-anchor session {} start: {}", id, e);
+    }
+}
+
+/// Elapsed time between two updates of today's stats and usage time by the critical loop
+const STATS_UPDATE_INTERVAL_SECS: i64 = 60;
+
 /// Critical monitoring loop - runs at fast rate (user's refresh_rate_ms)
 /// Updates: power, CPU%, GPU% (from cache), cost, session tracking
 /// NEVER blocks on GPU commands - uses cached values for GPU metrics
@@ -1143,43 +3772,63 @@ async fn critical_monitoring_loop(app: tauri::AppHandle) {
     let state: tauri::State<'_, TauriState> = app.state();
 
     let mut last_reading_time = std::time::Instant::now();
-
-    // Get initial refresh rate
-    let initial_refresh_ms = {
-        let config = state.config.lock().await;
-        config.general.refresh_rate_ms
-    };
-    let mut current_refresh_ms = initial_refresh_ms;
-    let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(current_refresh_ms));
-
-    log::info!("Critical monitoring loop initialized with {}ms refresh rate", current_refresh_ms);
+    let mut last_widget_update = None;
+    let mut clock = ClockJumpDetector::new();
+    // Each stored reading stands for READING_INTERVAL_SECS, whatever the tick rate
+    let mut reading_cadence = Cadence::new(db::READING_INTERVAL_SECS as f64);
+    let mut stats_cadence = Cadence::new(STATS_UPDATE_INTERVAL_SECS as f64);
+
+    let mut interval = state.refresh_rates.critical_interval();
+    log::info!("Critical monitoring loop initialized with {}ms refresh rate", interval.period_ms());
+    let mut sampling = state.config_watch.sampling();
+    let mut refresh = state.config_watch.refresh();
+    let mut pricing_snapshot = state.config_watch.pricing();
+    let mut display = state.config_watch.display();
+    // Rebuilt when the display settings change, so formatting a tick takes no i18n lock
+    let mut formatter: Option<MetricsFormatter> = None;
+    let mut widget_settings = state.config_watch.widget();
 
     loop {
-        interval.tick().await;
-
-        // Get current refresh rate from config
-        let refresh_ms = {
-            let config = state.config.lock().await;
-            config.general.refresh_rate_ms
-        };
+        if interval.tick().await {
+            log::info!("Critical monitoring loop rate changed to {}ms", interval.period_ms());
+        }
 
-        // Only recreate interval if refresh rate changed
-        if refresh_ms != current_refresh_ms {
-            current_refresh_ms = refresh_ms;
-            interval = tokio::time::interval(tokio::time::Duration::from_millis(refresh_ms));
-            log::info!("Critical monitoring loop rate changed to {}ms", refresh_ms);
+        // Energy is integrated over monotonic time, only the timestamps follow the wall clock
+        if let Some(jump) = clock.check(std::time::Instant::now(), chrono::Utc::now().timestamp()) {
+            log::warn!("Wall clock jumped {}s between two ticks", jump);
+            if jump < 0 {
+                reanchor_active_session(&state, jump).await;
+            }
         }
 
+        // Filter limits, session splitting and idle detection, without the config lock
+        let settings = sampling.borrow_and_update().clone();
+        let (max_power_watts, spike_filter_factor, split_at_midnight) =
+            (settings.max_power_watts, settings.spike_filter_factor, settings.split_sessions_at_midnight);
+
         // Read power using FAST path (CPU-only + cached GPU, no blocking commands)
-        let (power_watts, cpu_usage, gpu_usage, gpu_power) = {
+        let (raw_power_watts, cpu_usage, gpu_usage, gpu_power) = {
             let monitor = state.monitor.lock().await;
             monitor.get_power_watts_fast().unwrap_or((0.0, 0.0, None, None))
         };
 
+        // Discard implausible readings, integrating the previous value instead
+        let filtered = {
+            let mut sanitizer = state.power_sanitizer.lock().await;
+            sanitizer.set_limits(max_power_watts, spike_filter_factor);
+            sanitizer.filter(raw_power_watts)
+        };
+        if filtered.is_rejected() {
+            log::debug!("Rejected implausible power reading: {:.1}W", raw_power_watts);
+        }
+        let power_watts = filtered.watts();
+
         // Calculate energy consumed since last reading
-        let elapsed_hours = last_reading_time.elapsed().as_secs_f64() / 3600.0;
+        let elapsed_secs = last_reading_time.elapsed().as_secs_f64();
+        let elapsed_hours = elapsed_secs / 3600.0;
         let energy_wh = power_watts * elapsed_hours;
         last_reading_time = std::time::Instant::now();
+        let tick_end = chrono::Utc::now().timestamp_millis() as f64 / 1000.0;
 
         // Update app state and get values for critical metrics
         let (cumulative_wh, current_cost, session_duration_secs) = {
@@ -1187,9 +3836,9 @@ async fn critical_monitoring_loop(app: tauri::AppHandle) {
             app_state.cumulative_wh += energy_wh;
             app_state.last_power_watts = power_watts;
 
-            // Update cost
+            // Price this tick's energy at the current rate
             let pricing = state.pricing.lock().await;
-            app_state.current_cost = pricing.calculate_cost(app_state.cumulative_wh / 1000.0);
+            app_state.current_cost += pricing.calculate_cost(energy_wh / 1000.0);
 
             (
                 app_state.cumulative_wh,
@@ -1199,60 +3848,210 @@ async fn critical_monitoring_loop(app: tauri::AppHandle) {
         };
 
         // Use session average power for estimates instead of instantaneous
-        let avg_power_watts = if session_duration_secs > 0 {
-            cumulative_wh / (session_duration_secs as f64 / 3600.0)
-        } else {
-            power_watts // fallback to instantaneous at start
+        let avg_power_watts = pricing::average_power_watts(cumulative_wh, session_duration_secs, power_watts);
+        let estimates = state.pricing.lock().await.cost_estimates(avg_power_watts);
+
+        // Update baseline detector with new sample, reading the baseline under the same lock
+        let detected_baseline = {
+            let mut detector = state.baseline_detector.lock().await;
+            if !filtered.is_rejected() {
+                detector.add_sample(power_watts);
+            }
+            detector.get_baseline()
         };
 
-        // Calculate cost estimates
-        let (hourly_cost, daily_cost, monthly_cost) = {
-            let pricing = state.pricing.lock().await;
-            (
-                pricing.calculate_hourly_cost(avg_power_watts),
-                pricing.calculate_daily_cost(avg_power_watts),
-                pricing.calculate_monthly_cost(avg_power_watts),
-            )
+        // Our own estimated draw, if it should not count towards session surplus
+        let self_power_watts = if settings.exclude_self_from_surplus {
+            state.detailed_metrics_cache.lock().await.as_ref().and_then(|m| m.app_self_power_watts)
+        } else {
+            None
         };
 
-        // Update baseline detector with new sample
-        {
-            let mut detector = state.baseline_detector.lock().await;
-            detector.add_sample(power_watts);
-        }
+        // Input idleness, only read while the session can auto-end (the Wayland query spawns gdbus)
+        let idle_input = settings.session_idle_input.clone();
+        let wants_input_idle = idle_input != SessionIdleInput::Off
+            && state.active_session.lock().await.as_ref().is_some_and(|s| s.idle_after_secs.is_some());
+        let input_idle_secs = if wants_input_idle {
+            let input_idle = state.input_idle.clone();
+            tauri::async_runtime::spawn_blocking(move || input_idle.blocking_lock().idle_secs())
+                .await
+                .ok()
+                .flatten()
+        } else {
+            None
+        };
 
         // Update active session and get session data
-        let active_session = {
+        let mut heartbeat = None;
+        let mut auto_end = false;
+        let mut idle_expired = None;
+        let mut target_reached = None;
+        let mut session_surplus = None;
+        let mut active_session = {
             let mut active = state.active_session.lock().await;
 
             if let Some(ref mut session) = *active {
-                session.total_wh += energy_wh;
+                // Calculate surplus (power above baseline), optionally without our own draw
+                let surplus_watts = attribution::surplus_watts(power_watts, session.baseline_watts, self_power_watts);
+                session_surplus = Some((session.baseline_watts, surplus_watts));
+
+                // Check inactivity before adding this tick, so the snapshot excludes it
+                if let Some(idle_after_secs) = session.idle_after_secs {
+                    let idle = session_idle::is_idle_sample(surplus_watts, input_idle_secs, &idle_input);
+                    match session.idle_tracker.update_sample(idle, chrono::Utc::now().timestamp(), idle_after_secs) {
+                        IdleDecision::IdleStarted => {
+                            session.idle_snapshot = (session.total_wh, session.surplus_wh, session.surplus_cost);
+                        }
+                        IdleDecision::Expired { idle_since } => idle_expired = Some(idle_since),
+                        IdleDecision::Active | IdleDecision::Idle => {}
+                    }
+                }
 
-                // Calculate surplus (power above baseline)
-                let surplus_watts = (power_watts - session.baseline_watts).max(0.0);
                 let surplus_energy = surplus_watts * elapsed_hours;
-                session.surplus_wh += surplus_energy;
+                let surplus_cost = state.pricing.lock().await.calculate_cost(surplus_energy / 1000.0);
+
+                // Past local midnight, the share of the tick before it closes this part
+                // and the rest opens the continuation, both under the session lock so
+                // no tick lands in between. Sessions ending idle this tick are not split.
+                let crossing = if split_at_midnight && idle_expired.is_none() {
+                    clock::midnight_crossing(session.day, tick_end - elapsed_secs, tick_end, &chrono::Local)
+                } else {
+                    None
+                };
+                match crossing {
+                    Some(crossing) => {
+                        let before = crossing.fraction_before;
+                        session.add_energy(energy_wh * before, surplus_energy * before, surplus_cost * before);
+                        let db = state.db.lock().await;
+                        save_top_apps(&db, session);
+                        match db.split_session(session.id, crossing.at, session.total_wh, session.surplus_wh, session.surplus_cost) {
+                            Ok(Some(id)) => {
+                                log::info!("Session {} split at midnight, continued as session {}", session.id, id);
+                                session.continue_as(id, crossing.at, crossing.date);
+                            }
+                            Ok(None) => session.day = crossing.date,
+                            Err(e) => {
+                                log::warn!("Failed to split session {} at midnight: {}", session.id, e);
+                                session.day = crossing.date;
+                            }
+                        }
+                        let after = 1.0 - before;
+                        session.add_energy(energy_wh * after, surplus_energy * after, surplus_cost * after);
+                    }
+                    None => session.add_energy(energy_wh, surplus_energy, surplus_cost),
+                }
 
                 // Build session data for frontend
-                let pricing = state.pricing.lock().await;
-                let surplus_cost = pricing.calculate_cost(session.surplus_wh / 1000.0);
-
-                Some(Session {
-                    id: Some(session.id),
-                    start_time: chrono::Utc::now().timestamp() - session.start_time.elapsed().as_secs() as i64,
-                    end_time: None,
-                    baseline_watts: session.baseline_watts,
-                    total_wh: session.total_wh,
-                    surplus_wh: session.surplus_wh,
-                    surplus_cost,
-                    label: session.label.clone(),
-                    category: session.category.clone(),
-                })
+                let session_data = session.to_session();
+
+                // Announce each target threshold once
+                if let Some(progress) = session_data.target_progress_percent {
+                    let reached = SESSION_TARGET_THRESHOLDS
+                        .iter()
+                        .rev()
+                        .find(|&&t| progress >= t as f64)
+                        .copied();
+                    if let Some(threshold) = reached.filter(|&t| t > session.target_notified) {
+                        session.target_notified = threshold;
+                        target_reached = Some(threshold);
+                        emit_notification(&app, &state, "session-target-reached", SessionTargetEvent {
+                            session_id: session.id,
+                            threshold,
+                            progress_percent: progress,
+                        });
+                    }
+                }
+
+                // Persist totals periodically so a crash loses at most one interval
+                if session.last_heartbeat.elapsed() >= SESSION_HEARTBEAT_INTERVAL {
+                    session.last_heartbeat = std::time::Instant::now();
+                    heartbeat = Some((session.id, session.total_wh, session.surplus_wh, session.surplus_cost, session.app_usage.top(TOP_APPS)));
+                }
+
+                auto_end = session.auto_end_after.is_some_and(|d| session.start_time.elapsed() >= d);
+
+                Some(session_data)
             } else {
                 None
             }
         };
 
+        if let Some(threshold) = target_reached {
+            let title = if threshold >= 100 { "session.target_reached" } else { "session.target_warning" };
+            notify(&state, NotificationKind::SessionTarget, title, "notification.session_target_body", &[("percent", threshold.to_string())]).await;
+        }
+
+        if let Some((id, total_wh, surplus_wh, surplus_cost, top_apps)) = heartbeat {
+            let db = state.db.lock().await;
+            if let Err(e) = db.heartbeat_session(id, total_wh, surplus_wh, surplus_cost) {
+                log::warn!("Failed to write session heartbeat: {}", e);
+            }
+            if !top_apps.is_empty() {
+                if let Err(e) = db.set_session_top_apps(id, &top_apps) {
+                    log::warn!("Failed to store session apps: {}", e);
+                }
+            }
+        }
+
+        // End idle sessions, trimming the idle stretch
+        if let Some(idle_since) = idle_expired {
+            let ended = state.active_session.lock().await.take();
+            if let Some(session) = ended {
+                let (total_wh, surplus_wh, surplus_cost) = session.idle_snapshot;
+                let db = state.db.lock().await;
+                save_top_apps(&db, &session);
+                match db.auto_end_session(session.id, idle_since, total_wh, surplus_wh, surplus_cost) {
+                    Ok(Some(ended)) => {
+                        let reclaimed_minutes = clock::elapsed_secs(idle_since, chrono::Utc::now().timestamp()) as f64 / 60.0;
+                        log::info!("Session {} auto-ended after {:.0} idle minutes", session.id, reclaimed_minutes);
+                        drop(db);
+                        run_session_end_hook(&state, &ended).await;
+                        emit_notification(&app, &state, "session-auto-ended", SessionAutoEndedEvent {
+                            session: ended,
+                            reclaimed_minutes: Some(reclaimed_minutes),
+                        });
+                        let minutes = format!("{:.0}", reclaimed_minutes);
+                        notify(&state, NotificationKind::SessionAutoEnded, "session.auto_ended", "notification.session_idle_body", &[("minutes", minutes)]).await;
+                    }
+                    Ok(None) => {}
+                    Err(e) => log::warn!("Failed to auto-end idle session: {}", e),
+                }
+            }
+            active_session = None;
+        }
+
+        // End sessions started with an auto-end duration once it has elapsed
+        if auto_end {
+            let ended = state.active_session.lock().await.take();
+            if let Some(session) = ended {
+                let db = state.db.lock().await;
+                save_top_apps(&db, &session);
+                match db.end_session(session.id, session.total_wh, session.surplus_wh, session.surplus_cost) {
+                    Ok(Some(ended)) => {
+                        drop(db);
+                        run_session_end_hook(&state, &ended).await;
+                        emit_notification(&app, &state, "session-auto-ended", SessionAutoEndedEvent {
+                            session: ended,
+                            reclaimed_minutes: None,
+                        });
+                        notify(&state, NotificationKind::SessionAutoEnded, "session.ended", "notification.session_duration_body", &[]).await;
+                    }
+                    Ok(None) => {}
+                    Err(e) => log::warn!("Failed to auto-end session: {}", e),
+                }
+            }
+            active_session = None;
+        }
+
+        // Always-on "above idle" figure when no session is running
+        let (baseline_watts, surplus_watts) = match session_surplus.filter(|_| active_session.is_some()) {
+            Some((baseline, surplus)) => (Some(baseline), Some(surplus)),
+            None => (
+                detected_baseline,
+                detected_baseline.map(|b| attribution::surplus_watts(power_watts, b, self_power_watts)),
+            ),
+        };
+
         // Get source info
         let (source, is_estimated) = {
             let monitor = state.monitor.lock().await;
@@ -1260,7 +4059,8 @@ async fn critical_monitoring_loop(app: tauri::AppHandle) {
         };
 
         // Build and cache critical metrics
-        let critical_metrics = CriticalMetrics {
+        let now = chrono::Utc::now();
+        let mut critical_metrics = CriticalMetrics {
             power_watts,
             avg_power_watts,
             cpu_usage_percent: cpu_usage,
@@ -1268,15 +4068,27 @@ async fn critical_monitoring_loop(app: tauri::AppHandle) {
             gpu_power_watts: gpu_power,
             cumulative_wh,
             current_cost,
-            hourly_cost_estimate: hourly_cost,
-            daily_cost_estimate: daily_cost,
-            monthly_cost_estimate: monthly_cost,
+            hourly_cost_estimate: estimates.hourly,
+            daily_cost_estimate: estimates.daily,
+            monthly_cost_estimate: estimates.monthly,
             session_duration_secs,
             active_session,
             source,
             is_estimated,
-            timestamp: chrono::Utc::now().timestamp(),
+            timestamp: now.timestamp(),
+            billing_summary: state.billing_cache.lock().await.clone(),
+            baseline_watts,
+            surplus_watts,
+            timestamp_ms: now.timestamp_millis(),
+            display: None,
+            freshness: Freshness::default(),
         };
+        if formatter.is_none() || display.has_changed().unwrap_or(false) {
+            let settings = display.borrow_and_update().clone();
+            formatter = Some(MetricsFormatter::new(&settings, &*state.i18n.lock().await));
+        }
+        let display_format = formatter.as_ref().expect("built above");
+        critical_metrics.display = Some(display_format.format(&critical_metrics));
 
         // Update cache
         {
@@ -1284,39 +4096,273 @@ async fn critical_monitoring_loop(app: tauri::AppHandle) {
             *cache = Some(critical_metrics.clone());
         }
 
-        // Store reading in database (every 10 readings to reduce writes)
-        static READING_COUNT: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
-        let count = READING_COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        // Store one reading per READING_INTERVAL_SECS of elapsed time, a slow tick stands for several
+        let tick_secs = interval.period_ms() as f64 / 1000.0;
+        let readings_due = reading_cadence.advance(elapsed_secs, reading_cadence.max_due_per_tick(tick_secs));
+        let stats_due = stats_cadence.advance(elapsed_secs, stats_cadence.max_due_per_tick(tick_secs));
 
-        if count % 10 == 0 {
+        let mut badge_due = false;
+        if readings_due > 0 {
             let monitor = state.monitor.lock().await;
             if let Ok(reading) = monitor.get_reading() {
                 let db = state.db.lock().await;
-                let _ = db.insert_reading(&reading);
-
-                // Update daily stats every 60 readings (~every minute at 1s refresh)
-                if count % 60 == 0 {
-                    let config = state.config.lock().await;
-                    let pricing_mode = config.pricing.mode.clone();
-                    drop(config);
-                    let rate = {
-                        let pricing = state.pricing.lock().await;
-                        pricing.get_current_rate()
-                    };
-                    let _ = db.update_today_stats(Some(&pricing_mode), Some(rate));
+                // Never store a sample the sanity filter would reject
+                let plausible = !filtered.is_rejected()
+                    && state.power_sanitizer.lock().await.is_plausible(reading.power_watts);
+                if plausible {
+                    for reading in db::spread_reading(&reading, readings_due) {
+                        if let Err(e) = db.insert_reading(&reading) {
+                            log_throttle::warn(&format!("Failed to store power reading: {}", e));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Update daily stats about every minute of elapsed time
+        if stats_due > 0 {
+            let db = state.db.lock().await;
+            let pricing_mode = pricing_snapshot.borrow_and_update().mode.clone();
+            let (rate, exchange_rate) = {
+                let pricing = state.pricing.lock().await;
+                (pricing.get_current_rate(), pricing.exchange_rate())
+            };
+            let _ = db.update_today_stats(Some(&pricing_mode), Some(rate));
+            badge_due = true;
+
+            // Track app usage time, one interval's worth per interval elapsed
+            let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+            let _ = db.add_usage_seconds(&today, STATS_UPDATE_INTERVAL_SECS * i64::from(stats_due));
+
+            // Refresh the billing summary with the updated stats
+            match compute_billing_summary(&db, rate, exchange_rate) {
+                Ok(summary) => *state.billing_cache.lock().await = Some(summary),
+                Err(e) => log::debug!("Failed to compute billing summary: {}", e),
+            }
+        }
+        if badge_due && display_format.settings().show_taskbar_badge {
+            refresh_taskbar_badge(&app, &state).await;
+        }
+
+        // Push the widget its items at its own rate, only while it exists
+        if app.get_webview_window("widget").is_some() {
+            let widget_rate_ms = refresh.borrow_and_update().widget_refresh_rate_ms;
+            let rate_ms = widget_rate_ms.unwrap_or(interval.period_ms()).max(MIN_INTERVAL_MS);
+            let since_last_ms = last_widget_update.map(|t: std::time::Instant| t.elapsed().as_millis() as u64);
+            if widget::update_due(since_last_ms, rate_ms, interval.period_ms()) {
+                last_widget_update = Some(std::time::Instant::now());
+                let widget_config = widget_settings.borrow_and_update().clone();
+                emit_widget_update(&app, &state, &widget_config, &critical_metrics).await;
+            }
+        } else {
+            last_widget_update = None;
+        }
+
+        // Emit critical update event to frontend (skipped while no window is visible)
+        emit_if_visible(&app, &state, "critical-update", stamp_critical(&state, critical_metrics));
+    }
+}
+
+/// Refresh the secondary currency rate when it is due (daily, retried hourly on failure)
+async fn exchange_rate_loop(app: tauri::AppHandle) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(pricing::currency::CHECK_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+        let state = app.state::<TauriState>();
+
+        let pricing_config = state.config.lock().await.pricing.clone();
+        let due = {
+            let pricing = state.pricing.lock().await;
+            pricing::currency::fetch_due(&pricing_config, pricing.fetched_rate(), chrono::Utc::now().timestamp())
+        };
+        if due {
+            if let Err(e) = fetch_exchange_rate(&state).await {
+                log::warn!("Failed to fetch exchange rate: {}", e);
+            }
+        }
+    }
+}
+
+/// Emit `theme-changed` when the OS switches between light and dark while the theme is "system"
+async fn theme_watch_loop(app: tauri::AppHandle) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(theme::WATCH_INTERVAL_SECS));
+    let mut last = None;
+
+    loop {
+        interval.tick().await;
+        let state = app.state::<TauriState>();
+
+        let setting = state.config.lock().await.general.theme.clone();
+        if !theme::follows_system(&setting) {
+            // Switching to "system" emits from set_config, the next read starts over
+            last = None;
+            continue;
+        }
+        let appearance = resolve_theme(&setting).await;
+        if last.is_some_and(|previous| previous != appearance) {
+            log::info!("System theme changed to {}", appearance.as_str());
+            let _ = app.emit("theme-changed", appearance);
+        }
+        last = Some(appearance);
+    }
+}
+
+/// Push scheduled reports to the configured webhook
+///
+/// Failures are logged and counted in the monitoring health, and the period
+/// stays due so it is sent once the endpoint is reachable again.
+async fn webhook_loop(app: tauri::AppHandle) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(webhook::CHECK_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+        let state = app.state::<TauriState>();
+
+        let config = state.config.lock().await.webhook.clone();
+        if !config.enabled {
+            continue;
+        }
+        let period = match webhook::validate_config(&config) {
+            Ok(period) => period,
+            Err(e) => {
+                log::warn!("Webhook disabled by invalid settings: {}", e);
+                continue;
+            }
+        };
+
+        {
+            let notifier = state.webhook.lock().await;
+            let now = chrono::Utc::now().timestamp();
+            if !notifier.can_attempt(now) || !notifier.is_due(period, config.hour, chrono::Local::now().naive_local()) {
+                continue;
+            }
+        }
+
+        let result = match webhook_report(&state, &config, period).await {
+            Ok((report, token)) => webhook::send_with_retry(&config.url, token.as_deref(), &report)
+                .await
+                .map(|_| report),
+            Err(e) => Err(e),
+        };
+
+        let now = chrono::Utc::now().timestamp();
+        let mut notifier = state.webhook.lock().await;
+        match result {
+            Ok(report) => {
+                log::info!("Webhook report for {}..{} delivered", report.start_date, report.end_date);
+                notifier.record_success(&report, now);
+            }
+            Err(e) => {
+                log::warn!("Webhook report delivery failed: {}", e);
+                notifier.record_failure(&e, now);
+            }
+        }
+    }
+}
+
+/// Store a measured and estimated power pair once a minute while accuracy tracking is on
+///
+/// The estimator only runs while the monitor measures. The measured side is
+/// the average over the minute from the integrated energy, as the estimator's
+/// CPU load is averaged over the same minute. With auto-apply, the suggested
+/// correction replaces the configured one once an hour.
+async fn accuracy_loop(app: tauri::AppHandle) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(accuracy::SAMPLE_INTERVAL_SECS));
+    let mut estimator: Option<PowerMonitor> = None;
+    let mut last_energy: Option<(f64, std::time::Instant)> = None;
+    let mut samples_taken: u64 = 0;
+
+    loop {
+        interval.tick().await;
+        let state = app.state::<TauriState>();
 
-                    // Track app usage time (accumulate 60 seconds per minute)
-                    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
-                    let _ = db.add_usage_seconds(&today, 60);
+        let advanced = state.config.lock().await.advanced.clone();
+        if !advanced.accuracy_tracking || state.monitor.lock().await.is_estimated() {
+            estimator = None;
+            last_energy = None;
+            continue;
+        }
+        if estimator.is_none() {
+            match tauri::async_runtime::spawn_blocking(PowerMonitor::estimation_fallback).await {
+                Ok(monitor) => estimator = Some(monitor),
+                Err(e) => {
+                    log::warn!("Failed to start the estimator for accuracy tracking: {}", e);
+                    continue;
                 }
             }
         }
+        let Some(monitor) = estimator.as_mut() else {
+            continue;
+        };
+        monitor.set_gpu_estimate(GpuEstimate::from_config(&advanced));
+
+        let estimated_watts = match monitor.get_power_watts() {
+            Ok(watts) => watts,
+            Err(e) => {
+                log::debug!("Estimator reading failed: {}", e);
+                continue;
+            }
+        };
+        let cumulative_wh = state.app_state.lock().await.cumulative_wh;
+        let now = std::time::Instant::now();
+        let Some((previous_wh, previous_at)) = last_energy.replace((cumulative_wh, now)) else {
+            // The first estimate only starts the CPU load average
+            continue;
+        };
+        let hours = now.duration_since(previous_at).as_secs_f64() / 3600.0;
+        if hours <= 0.0 || cumulative_wh < previous_wh {
+            continue;
+        }
 
-        // Emit critical update event to frontend
-        let _ = app.emit("critical-update", critical_metrics);
+        let sample = AccuracySample {
+            timestamp: chrono::Utc::now().timestamp(),
+            measured_watts: (cumulative_wh - previous_wh) / hours,
+            estimated_watts,
+        };
+        let db = state.db.lock().await;
+        if let Err(e) = db.insert_accuracy_sample(&sample) {
+            log::warn!("Failed to store accuracy sample: {}", e);
+            continue;
+        }
+        samples_taken += 1;
+        if !advanced.auto_apply_correction || samples_taken % 60 != 0 {
+            continue;
+        }
+        let summary = match db.get_accuracy_samples(accuracy::SUMMARY_WINDOW) {
+            Ok(samples) => EstimationAccuracy::from_samples(&samples, accuracy::clamp_correction(advanced.estimation_correction)),
+            Err(e) => {
+                log::warn!("Failed to read accuracy samples: {}", e);
+                continue;
+            }
+        };
+        drop(db);
+        if let Some(factor) = summary.correction_to_apply() {
+            apply_estimation_correction(&app, &state, factor).await;
+        }
     }
 }
 
+/// Save `factor` as the estimation correction and apply it to the monitor
+async fn apply_estimation_correction(app: &tauri::AppHandle, state: &TauriState, factor: f64) {
+    let mut config = state.config.lock().await;
+    let mut staged = config.clone();
+    staged.advanced.estimation_correction = factor;
+    let changed = match Config::config_path().and_then(|path| config.replace_saved(staged, &path)) {
+        Ok(changed) => changed,
+        Err(e) => {
+            log::warn!("Failed to save the estimation correction: {}", e);
+            return;
+        }
+    };
+    state.monitor.lock().await.set_estimation_correction(factor);
+    state.config_watch.publish(&config);
+    drop(config);
+
+    log::info!("Estimation correction set to {:.2}", factor);
+    let _ = app.emit("config-changed", &changed);
+}
+
 /// Detailed monitoring loop - runs at slow rate (slow_refresh_rate_ms, default 5s)
 /// Updates: top processes, temperatures, VRAM details
 /// This loop uses spawn_blocking for GPU commands to avoid blocking the async runtime
@@ -1324,47 +4370,33 @@ async fn detailed_monitoring_loop(app: tauri::AppHandle) {
     log::info!("Starting detailed monitoring loop");
     let state: tauri::State<'_, TauriState> = app.state();
 
-    // Get initial slow refresh rate
-    let initial_slow_refresh_ms = {
-        let config = state.config.lock().await;
-        config.general.slow_refresh_rate_ms
-    };
-    let mut current_slow_refresh_ms = initial_slow_refresh_ms;
-    let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(current_slow_refresh_ms));
-
-    log::info!("Detailed monitoring loop initialized with {}ms refresh rate", current_slow_refresh_ms);
+    let mut interval = state.refresh_rates.detailed_interval();
+    log::info!("Detailed monitoring loop initialized with {}ms refresh rate", interval.period_ms());
+    let mut gpu_polling_paused = false;
+    let mut tuner = CollectionTuner::new();
+    let mut processes = state.config_watch.processes();
+    let mut detailed = state.config_watch.detailed();
 
     loop {
-        interval.tick().await;
-
-        // Get current slow refresh rate from config
-        let slow_refresh_ms = {
-            let config = state.config.lock().await;
-            config.general.slow_refresh_rate_ms
-        };
+        if interval.tick().await {
+            log::info!("Detailed monitoring loop rate changed to {}ms", interval.period_ms());
+        }
+        let slow_refresh_ms = interval.period_ms();
 
-        // Only recreate interval if refresh rate changed
-        if slow_refresh_ms != current_slow_refresh_ms {
-            current_slow_refresh_ms = slow_refresh_ms;
-            interval = tokio::time::interval(tokio::time::Duration::from_millis(slow_refresh_ms));
-            log::info!("Detailed monitoring loop rate changed to {}ms", slow_refresh_ms);
+        // Paused in low-power mode, the critical loop alone keeps tracking energy
+        if state.refresh_rates.low_power() {
+            continue;
         }
 
-        // Get config for process limit and pinned processes
-        let (limit, pinned) = {
-            let config = state.config.lock().await;
-            (
-                config.advanced.process_list_limit,
-                config.advanced.pinned_processes.clone(),
-            )
-        };
+        // Process limit, pinned and ignored processes, collectors and thresholds, without the config lock
+        let crate::core::config_watch::ProcessSettings { limit, pinned, ignored } = processes.borrow_and_update().clone();
+        let settings = detailed.borrow_and_update().clone();
 
         // Determine if we should collect extended metrics (per-core freq, fans)
         // based on whether CPU or GPU load exceeds the configured threshold
         let should_collect_extended = {
             let critical = state.critical_metrics_cache.lock().await;
-            let config = state.config.lock().await;
-            let threshold = config.advanced.extended_metrics_threshold;
+            let threshold = settings.extended_metrics_threshold;
             if let Some(ref cm) = *critical {
                 cm.cpu_usage_percent >= threshold
                     || cm.gpu_usage_percent.map_or(false, |g| g >= threshold)
@@ -1373,42 +4405,155 @@ async fn detailed_monitoring_loop(app: tauri::AppHandle) {
             }
         };
 
+        // Skip process enumeration and GPU queries nothing on screen uses.
+        // Published with each config change, so layout changes apply on the next tick.
+        let widget_open = app.get_webview_window("widget").is_some();
+        let mut options = settings.options(widget_open, should_collect_extended);
+
+        // GPU command line tools stutter some games: pause them while a fullscreen
+        // app is focused, checked every tick so polling resumes on the next one
+        options.pause_gpu_cli = settings.pause_gpu_polling_in_game
+            && options.gpu
+            && tokio::task::spawn_blocking(active_window::foreground_is_fullscreen)
+                .await
+                .unwrap_or(false);
+        if options.pause_gpu_cli != gpu_polling_paused {
+            gpu_polling_paused = options.pause_gpu_cli;
+            if gpu_polling_paused {
+                log::info!("Fullscreen app focused, GPU command line polling paused");
+            } else {
+                log::info!("GPU command line polling resumed");
+            }
+        }
+
         // Collect detailed metrics in a blocking task to avoid blocking async runtime
         // This is where slow GPU commands (nvidia-smi) and process enumeration happen
-        let detailed_metrics = {
+        let collection_started = std::time::Instant::now();
+        let mut detailed_metrics = {
             let monitor = state.monitor.lock().await;
             // Use spawn_blocking for the slow operations
             let limit_clone = limit;
             let pinned_clone = pinned.clone();
 
             // We need to clone what we need since spawn_blocking requires 'static
-            match monitor.collect_detailed_metrics(limit_clone, &pinned_clone, should_collect_extended) {
+            match monitor.collect_detailed_metrics(limit_clone, &pinned_clone, &ignored, options) {
                 Ok(metrics) => Some(metrics),
                 Err(e) => {
                     log::debug!("Failed to collect detailed metrics: {}", e);
                     // Fallback: try to get metrics individually
                     let system_metrics = monitor.get_system_metrics().ok();
-                    let top_processes = monitor.get_top_processes_with_pinned(limit_clone, &pinned_clone).unwrap_or_default();
+                    let top_processes = monitor.get_top_processes_with_pinned(limit_clone, &pinned_clone, &ignored).unwrap_or_default();
 
+                    let now = chrono::Utc::now();
                     Some(DetailedMetrics {
                         system_metrics,
+                        process_totals: ProcessTotals::of(&top_processes),
                         top_processes,
-                        timestamp: chrono::Utc::now().timestamp(),
+                        timestamp: now.timestamp(),
+                        timestamp_ms: now.timestamp_millis(),
                         extended_collected: false,
+                        measured_energy_joules: None,
+                        app_self_cpu_percent: None,
+                        app_self_power_watts: None,
+                        gpu_stale: false,
                     })
                 }
             }
         };
 
-        // Update cache
-        if let Some(metrics) = detailed_metrics.clone() {
-            let mut cache = state.detailed_metrics_cache.lock().await;
-            *cache = Some(metrics);
+        // Slow collections stretch the rate so the machine gets to idle between them
+        let collection_ms = collection_started.elapsed().as_millis() as u64;
+        if let Some(adjustment) = tuner.record(state.refresh_rates.configured_detailed_ms(), collection_ms) {
+            let stretch_ms = if adjustment.effective_ms > adjustment.configured_ms { adjustment.effective_ms } else { 0 };
+            state.refresh_rates.set_detailed_stretch(stretch_ms);
+            log::info!(
+                "Detailed collection took {}ms, refresh rate adjusted to {}ms (configured {}ms)",
+                collection_ms, adjustment.effective_ms, adjustment.configured_ms
+            );
+            if adjustment.first_stretch {
+                let _ = app.emit("monitoring-adjusted", adjustment);
+            }
+        }
+
+        // Estimate our own power draw from our share of the CPU load
+        if let Some(metrics) = detailed_metrics.as_mut() {
+            let critical = state.critical_metrics_cache.lock().await;
+            metrics.app_self_power_watts = match (metrics.app_self_cpu_percent, metrics.system_metrics.as_ref(), critical.as_ref()) {
+                (Some(self_cpu), Some(sys), Some(cm)) => Some(attribution::process_power_watts(
+                    self_cpu,
+                    sys.cpu.thread_count,
+                    cm.cpu_usage_percent,
+                    cm.power_watts - cm.gpu_power_watts.unwrap_or(0.0),
+                )),
+                _ => None,
+            };
+        }
+
+        // Credit this tick to the foreground app of the active session
+        if settings.track_active_window && state.active_session.lock().await.is_some() {
+            let foreground = tokio::task::spawn_blocking(active_window::foreground_process_name).await;
+            if let Ok(Some(name)) = foreground {
+                if let Some(session) = state.active_session.lock().await.as_mut() {
+                    session.app_usage.record(&name, slow_refresh_ms as f64 / 1000.0);
+                }
+            }
+        }
+
+        if let Some(sys) = detailed_metrics.as_ref().and_then(|m| m.system_metrics.as_ref()) {
+            let mut history = state.core_history.lock().await;
+            history.push(&sys.cpu.per_core_usage, sys.cpu.per_core_frequency_mhz.as_deref());
+        }
+
+        // Without process enumeration a pinned process would look stopped
+        if let Some(metrics) = detailed_metrics.as_ref().filter(|_| options.processes) {
+            state.process_history.lock().await.push(&pinned, &metrics.top_processes);
+        }
+
+        // Record temperatures at the slow cadence (None stays NULL, not zero)
+        if let Some(sys) = detailed_metrics.as_ref().and_then(|m| m.system_metrics.as_ref()) {
+            let cpu_c = sys.cpu.temperature_celsius;
+            let gpu_c = sys.gpu.as_ref().and_then(|g| g.temperature_celsius);
+            let db = state.db.lock().await;
+            if let Err(e) = db.insert_temperature_reading(sys.timestamp, cpu_c, gpu_c) {
+                log_throttle::warn(&format!("Failed to store temperature reading: {}", e));
+            }
+        }
+
+        // Record GPU memory, only when the GPU reports both values
+        if let Some(sys) = detailed_metrics.as_ref().and_then(|m| m.system_metrics.as_ref()) {
+            if let Some((used_mb, total_mb)) = sys.gpu.as_ref().and_then(|g| g.vram_used_mb.zip(g.vram_total_mb)) {
+                let alert = state.vram_alert.lock().await.update(used_mb, total_mb, settings.vram_alert_percent, sys.timestamp);
+                let db = state.db.lock().await;
+                if let Err(e) = db.insert_vram_reading(sys.timestamp, used_mb, total_mb) {
+                    log_throttle::warn(&format!("Failed to store VRAM reading: {}", e));
+                }
+                if let Some(alert) = alert {
+                    log::warn!("VRAM usage above {}% for a minute ({} / {} MB)", alert.threshold_percent, alert.used_mb, alert.total_mb);
+                    let record = db::AlertRecord {
+                        timestamp: sys.timestamp,
+                        kind: "vram".to_string(),
+                        value: alert.usage_percent,
+                        threshold: alert.threshold_percent as f64,
+                    };
+                    if let Err(e) = db.insert_alert(&record) {
+                        log::warn!("Failed to log VRAM alert: {}", e);
+                    }
+                    drop(db);
+                    let args = [
+                        ("percent", format!("{:.0}", alert.usage_percent)),
+                        ("used", alert.used_mb.to_string()),
+                        ("total", alert.total_mb.to_string()),
+                    ];
+                    emit_notification(&app, &state, "vram-alert", alert);
+                    notify(&state, NotificationKind::VramAlert, "alert.vram_high", "notification.vram_body", &args).await;
+                }
+            }
         }
 
-        // Emit detailed update event to frontend
-        if let Some(metrics) = detailed_metrics {
-            let _ = app.emit("detailed-update", metrics);
+        // Update cache, then emit detailed update event to frontend (skipped while no window is visible)
+        if let Some(metrics) = detailed_metrics.map(Arc::new) {
+            *state.detailed_metrics_cache.lock().await = Some(Arc::clone(&metrics));
+            emit_if_visible(&app, &state, "detailed-update", stamp_detailed(&state, &metrics));
         }
     }
 }
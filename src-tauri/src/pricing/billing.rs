@@ -0,0 +1,233 @@
+//! Month-to-date billing summary and end-of-month projection
+//!
+//! Works on `daily_stats` rows (UTC dates). The projection blends the
+//! month-to-date daily average with the last 7 days, and falls back to
+//! extrapolating from the days that have data when the month has gaps.
+
+use crate::core::BillingSummary;
+use crate::db::DailyStats;
+use chrono::{Datelike, Duration, NaiveDate};
+
+/// Number of recent days used for the trend part of the projection
+const TREND_DAYS: i64 = 7;
+
+/// Weight of the recent trend in the blended daily average
+const TREND_WEIGHT: f64 = 0.5;
+
+/// Below this many complete days with data, the projection is flagged as low confidence
+const MIN_CONFIDENT_DAYS: usize = 3;
+
+/// Number of days in the month containing `date`
+fn days_in_month(date: NaiveDate) -> u32 {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|next| next.pred_opt())
+        .map(|last| last.day())
+        .unwrap_or(30)
+}
+
+/// Cost of a day, falling back to `fallback_rate` when no cost was recorded
+fn day_cost(stats: &DailyStats, fallback_rate: f64) -> f64 {
+    stats
+        .total_cost
+        .unwrap_or(stats.total_wh / 1000.0 * fallback_rate)
+}
+
+/// Compute the billing summary from this month's and last month's daily stats
+pub fn compute_billing_summary(
+    current_month: &[DailyStats],
+    last_month: &[DailyStats],
+    today: NaiveDate,
+    fallback_rate: f64,
+) -> BillingSummary {
+    let days_in_month = days_in_month(today);
+    let days_elapsed = today.day();
+    let completed_days = days_elapsed - 1;
+    let trend_start = today - Duration::days(TREND_DAYS);
+
+    let parse = |s: &DailyStats| NaiveDate::parse_from_str(&s.date, "%Y-%m-%d").ok();
+    let in_month: Vec<(NaiveDate, &DailyStats)> = current_month
+        .iter()
+        .filter_map(|s| parse(s).map(|d| (d, s)))
+        .filter(|(d, _)| d.year() == today.year() && d.month() == today.month() && *d <= today)
+        .collect();
+
+    let month_to_date_kwh: f64 = in_month.iter().map(|(_, s)| s.total_wh / 1000.0).sum();
+    let month_to_date_cost: f64 = in_month.iter().map(|(_, s)| day_cost(s, fallback_rate)).sum();
+
+    // Today is partial, so averages are based on completed days when there are any
+    let completed: Vec<&(NaiveDate, &DailyStats)> = in_month.iter().filter(|(d, _)| *d < today).collect();
+    let recent: Vec<&&(NaiveDate, &DailyStats)> = completed.iter().filter(|(d, _)| *d >= trend_start).collect();
+
+    let average = |cost: f64, kwh: f64, days: usize| (cost / days as f64, kwh / days as f64);
+    let (mtd_avg_cost, mtd_avg_kwh) = if completed.is_empty() {
+        (month_to_date_cost, month_to_date_kwh)
+    } else {
+        average(
+            completed.iter().map(|(_, s)| day_cost(s, fallback_rate)).sum(),
+            completed.iter().map(|(_, s)| s.total_wh / 1000.0).sum(),
+            completed.len(),
+        )
+    };
+    let (daily_cost, daily_kwh) = if recent.is_empty() {
+        (mtd_avg_cost, mtd_avg_kwh)
+    } else {
+        let (trend_cost, trend_kwh) = average(
+            recent.iter().map(|(_, s)| day_cost(s, fallback_rate)).sum(),
+            recent.iter().map(|(_, s)| s.total_wh / 1000.0).sum(),
+            recent.len(),
+        );
+        (
+            mtd_avg_cost * (1.0 - TREND_WEIGHT) + trend_cost * TREND_WEIGHT,
+            mtd_avg_kwh * (1.0 - TREND_WEIGHT) + trend_kwh * TREND_WEIGHT,
+        )
+    };
+
+    // With full coverage, add the projected remainder to what was actually spent;
+    // with gaps (e.g. installed mid-month), extrapolate the whole month from the average
+    let full_coverage = completed.len() as u32 == completed_days;
+    let (projected_month_cost, projected_month_kwh) = if full_coverage {
+        let completed_cost: f64 = completed.iter().map(|(_, s)| day_cost(s, fallback_rate)).sum();
+        let completed_kwh: f64 = completed.iter().map(|(_, s)| s.total_wh / 1000.0).sum();
+        let remaining = (days_in_month - completed_days) as f64;
+        (completed_cost + daily_cost * remaining, completed_kwh + daily_kwh * remaining)
+    } else {
+        (daily_cost * days_in_month as f64, daily_kwh * days_in_month as f64)
+    };
+    // Never project less than what has already been spent
+    let projected_month_cost = projected_month_cost.max(month_to_date_cost);
+    let projected_month_kwh = projected_month_kwh.max(month_to_date_kwh);
+
+    let low_confidence = completed.len() < MIN_CONFIDENT_DAYS
+        || (completed.len() as f64) < completed_days as f64 * 0.5;
+
+    let (last_month_kwh, last_month_cost) = if last_month.is_empty() {
+        (None, None)
+    } else {
+        (
+            Some(last_month.iter().map(|s| s.total_wh / 1000.0).sum()),
+            Some(last_month.iter().map(|s| day_cost(s, fallback_rate)).sum::<f64>()),
+        )
+    };
+    let change_vs_last_month_percent = last_month_cost
+        .filter(|&c| c > 0.0)
+        .map(|c| (projected_month_cost - c) / c * 100.0);
+
+    BillingSummary {
+        month: today.format("%Y-%m").to_string(),
+        month_to_date_kwh,
+        month_to_date_cost,
+        days_elapsed,
+        days_remaining: days_in_month - days_elapsed,
+        days_in_month,
+        days_with_data: in_month.len() as u32,
+        projected_daily_cost: daily_cost,
+        projected_month_cost,
+        projected_month_kwh,
+        last_month_kwh,
+        last_month_cost,
+        change_vs_last_month_percent,
+        low_confidence,
+        exchange_rate: None,
+        converted_month_to_date_cost: None,
+        converted_projected_month_cost: None,
+        converted_last_month_cost: None,
+    }
+}
+
+/// First and last day of the month containing `date`, and of the month before
+pub fn month_ranges(date: NaiveDate) -> ((NaiveDate, NaiveDate), (NaiveDate, NaiveDate)) {
+    let first = date.with_day(1).unwrap_or(date);
+    let last = first + Duration::days(days_in_month(first) as i64 - 1);
+    let prev_last = first.pred_opt().unwrap_or(first);
+    let prev_first = prev_last.with_day(1).unwrap_or(prev_last);
+    ((first, last), (prev_first, prev_last))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(date: &str, kwh: f64, cost: f64) -> DailyStats {
+        DailyStats {
+            date: date.into(),
+            total_wh: kwh * 1000.0,
+            total_cost: Some(cost),
+            avg_watts: 0.0,
+            max_watts: 0.0,
+            pricing_mode: None,
+            usage_seconds: 0,
+            source: None,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn test_days_in_month() {
+        assert_eq!(days_in_month(NaiveDate::from_ymd_opt(2024, 2, 10).unwrap()), 29);
+        assert_eq!(days_in_month(NaiveDate::from_ymd_opt(2023, 2, 10).unwrap()), 28);
+        assert_eq!(days_in_month(NaiveDate::from_ymd_opt(2024, 12, 31).unwrap()), 31);
+    }
+
+    #[test]
+    fn test_full_month_projection() {
+        // 10 complete days at 1 EUR/day in a 30-day month, today is partial
+        let today = NaiveDate::from_ymd_opt(2024, 6, 11).unwrap();
+        let mut stats: Vec<DailyStats> = (1..=10)
+            .map(|d| day(&format!("2024-06-{:02}", d), 5.0, 1.0))
+            .collect();
+        stats.push(day("2024-06-11", 1.0, 0.2));
+
+        let last_month = vec![day("2024-05-15", 100.0, 20.0)];
+        let summary = compute_billing_summary(&stats, &last_month, today, 0.2);
+
+        assert!((summary.month_to_date_cost - 10.2).abs() < 1e-9);
+        assert_eq!(summary.days_elapsed, 11);
+        assert_eq!(summary.days_remaining, 19);
+        assert_eq!(summary.days_with_data, 11);
+        // 10 spent + 20 remaining days (incl. today) at 1/day
+        assert!((summary.projected_month_cost - 30.0).abs() < 1e-9);
+        assert!((summary.change_vs_last_month_percent.unwrap() - 50.0).abs() < 1e-9);
+        assert!(!summary.low_confidence);
+    }
+
+    #[test]
+    fn test_sparse_month_extrapolates() {
+        // Installed on the 20th: only 2 complete days of data
+        let today = NaiveDate::from_ymd_opt(2024, 6, 22).unwrap();
+        let stats = vec![day("2024-06-20", 5.0, 2.0), day("2024-06-21", 5.0, 2.0)];
+        let summary = compute_billing_summary(&stats, &[], today, 0.2);
+
+        assert!((summary.projected_month_cost - 60.0).abs() < 1e-9);
+        assert!(summary.low_confidence);
+        assert!(summary.last_month_cost.is_none());
+        assert!(summary.change_vs_last_month_percent.is_none());
+    }
+
+    #[test]
+    fn test_recent_trend_blend() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+        // Days 1-10 at 1/day, days 11-20 at 3/day
+        let stats: Vec<DailyStats> = (1..=20)
+            .map(|d| day(&format!("2024-06-{:02}", d), 1.0, if d <= 10 { 1.0 } else { 3.0 }))
+            .collect();
+        let summary = compute_billing_summary(&stats, &[], today, 0.2);
+
+        // MTD average 2.0, last 7 days 3.0 => blended 2.5
+        assert!((summary.projected_daily_cost - 2.5).abs() < 1e-9);
+        assert!((summary.projected_month_cost - (40.0 + 2.5 * 10.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_month_ranges() {
+        let ((first, last), (prev_first, prev_last)) = month_ranges(NaiveDate::from_ymd_opt(2024, 3, 15).unwrap());
+        assert_eq!(first.to_string(), "2024-03-01");
+        assert_eq!(last.to_string(), "2024-03-31");
+        assert_eq!(prev_first.to_string(), "2024-02-01");
+        assert_eq!(prev_last.to_string(), "2024-02-29");
+    }
+}
@@ -0,0 +1,148 @@
+//! Energy and cost split between CPU, GPU and the rest of the system
+//!
+//! Uses the components stored with each reading, each one priced at the rate
+//! in effect when it was recorded. Energy not covered by a component (older
+//! rows, sources that only report a total) goes to an "unattributed" bucket,
+//! so the parts always add up to the overall total.
+
+use crate::db::{ComponentReading, HOURS_PER_READING};
+use serde::{Deserialize, Serialize};
+
+/// Buckets of a breakdown, in display order
+const COMPONENTS: [&str; 4] = ["cpu", "gpu", "base", "unattributed"];
+
+/// Energy and cost of one component
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentCost {
+    /// "cpu", "gpu", "base" or "unattributed"
+    pub component: String,
+    pub kwh: f64,
+    pub cost: f64,
+    /// Share of the total cost (of the energy when nothing was billed)
+    pub percent: f64,
+}
+
+/// Cost breakdown over a time range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostBreakdown {
+    pub start: i64,
+    pub end: i64,
+    pub total_kwh: f64,
+    pub total_cost: f64,
+    pub components: Vec<ComponentCost>,
+}
+
+/// Integrate each component over `readings`, `rate_at` giving the rate at a timestamp
+pub fn compute_cost_breakdown(
+    readings: &[ComponentReading],
+    start: i64,
+    end: i64,
+    rate_at: impl Fn(i64) -> f64,
+) -> CostBreakdown {
+    let mut kwh = [0.0; 4];
+    let mut cost = [0.0; 4];
+
+    for reading in readings {
+        let total = reading.power_watts.max(0.0) * HOURS_PER_READING / 1000.0;
+        let mut parts = [reading.cpu_watts, reading.gpu_watts, reading.base_watts]
+            .map(|w| w.unwrap_or(0.0).max(0.0) * HOURS_PER_READING / 1000.0);
+
+        // Components can only explain the reading's total, never exceed it
+        let attributed: f64 = parts.iter().sum();
+        if attributed > total && attributed > 0.0 {
+            let scale = total / attributed;
+            parts.iter_mut().for_each(|p| *p *= scale);
+        }
+        let unattributed = (total - parts.iter().sum::<f64>()).max(0.0);
+
+        let rate = rate_at(reading.timestamp);
+        for (i, part) in parts.iter().chain(std::iter::once(&unattributed)).enumerate() {
+            kwh[i] += part;
+            cost[i] += part * rate;
+        }
+    }
+
+    let total_kwh: f64 = kwh.iter().sum();
+    let total_cost: f64 = cost.iter().sum();
+    let share = |i: usize| {
+        if total_cost > 0.0 {
+            cost[i] / total_cost * 100.0
+        } else if total_kwh > 0.0 {
+            kwh[i] / total_kwh * 100.0
+        } else {
+            0.0
+        }
+    };
+
+    CostBreakdown {
+        start,
+        end,
+        total_kwh,
+        total_cost,
+        components: COMPONENTS
+            .iter()
+            .enumerate()
+            .map(|(i, name)| ComponentCost {
+                component: name.to_string(),
+                kwh: kwh[i],
+                cost: cost[i],
+                percent: share(i),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(timestamp: i64, watts: f64, cpu: Option<f64>, gpu: Option<f64>, base: Option<f64>) -> ComponentReading {
+        ComponentReading { timestamp, power_watts: watts, cpu_watts: cpu, gpu_watts: gpu, base_watts: base }
+    }
+
+    fn part<'a>(breakdown: &'a CostBreakdown, name: &str) -> &'a ComponentCost {
+        breakdown.components.iter().find(|c| c.component == name).unwrap()
+    }
+
+    #[test]
+    fn test_mixed_sources_add_up() {
+        // 360 W for one reading = 1 Wh
+        let readings = [
+            // Windows reading with a full split
+            reading(0, 360.0, Some(180.0), Some(144.0), Some(36.0)),
+            // Estimator reading without GPU
+            reading(10, 360.0, Some(288.0), None, Some(72.0)),
+            // RAPL/hwmon reading, total only
+            reading(20, 720.0, None, None, None),
+        ];
+        // Second reading billed at double rate
+        let b = compute_cost_breakdown(&readings, 0, 30, |ts| if ts == 10 { 0.4 } else { 0.2 });
+
+        assert!((b.total_kwh - 0.004).abs() < 1e-12);
+        assert!((part(&b, "cpu").kwh - 0.0013).abs() < 1e-12);
+        assert!((part(&b, "gpu").kwh - 0.0004).abs() < 1e-12);
+        assert!((part(&b, "base").kwh - 0.0003).abs() < 1e-12);
+        assert!((part(&b, "unattributed").kwh - 0.002).abs() < 1e-12);
+
+        // 0.001 * 0.2 + 0.001 * 0.4 + 0.002 * 0.2
+        assert!((b.total_cost - 0.001).abs() < 1e-12);
+        let cost_sum: f64 = b.components.iter().map(|c| c.cost).sum();
+        assert!((cost_sum - b.total_cost).abs() < 1e-12);
+        let percent_sum: f64 = b.components.iter().map(|c| c.percent).sum();
+        assert!((percent_sum - 100.0).abs() < 1e-9);
+        assert!((part(&b, "unattributed").percent - 40.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_components_capped_at_total() {
+        let readings = [reading(0, 100.0, Some(150.0), Some(50.0), None)];
+        let b = compute_cost_breakdown(&readings, 0, 10, |_| 0.0);
+        assert!((part(&b, "cpu").percent - 75.0).abs() < 1e-9);
+        assert!((part(&b, "gpu").percent - 25.0).abs() < 1e-9);
+        assert_eq!(part(&b, "unattributed").kwh, 0.0);
+
+        let empty = compute_cost_breakdown(&[], 0, 10, |_| 0.2);
+        assert_eq!(empty.total_kwh, 0.0);
+        assert!(empty.components.iter().all(|c| c.percent == 0.0));
+    }
+}
@@ -0,0 +1,205 @@
+//! Conversion to a secondary currency
+//!
+//! The rate is either set by hand (fully offline) or fetched once a day from
+//! a configurable URL. The last fetched rate is cached in the app data
+//! directory, so it survives restarts and offline days; once older than a
+//! week it is still used but flagged as stale.
+
+use crate::core::{Error, ExchangeRate, PricingConfig, RateSource, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Minimum time between two rate fetches (one day)
+pub const FETCH_INTERVAL_SECS: i64 = 24 * 3600;
+
+/// Age after which a fetched rate is flagged as stale (7 days)
+pub const STALE_AFTER_SECS: i64 = 7 * 24 * 3600;
+
+/// How often the fetch loop checks whether a new rate is due
+pub const CHECK_INTERVAL_SECS: u64 = 3600;
+
+/// A rate fetched from the network
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FetchedRate {
+    /// Primary and secondary currency codes the rate converts between
+    pub base: String,
+    pub target: String,
+    pub rate: f64,
+    /// Unix timestamp of the fetch
+    pub fetched_at: i64,
+}
+
+fn valid_rate(rate: f64) -> bool {
+    rate.is_finite() && rate > 0.0
+}
+
+/// Rate to use at `now`, None when no secondary currency (or no usable rate) is configured
+///
+/// With auto fetch, a cached rate for the same currency pair wins; the manual
+/// rate covers the time before the first successful fetch.
+pub fn resolve(config: &PricingConfig, fetched: Option<&FetchedRate>, now: i64) -> Option<ExchangeRate> {
+    let secondary = &config.secondary;
+    let target = secondary.currency.trim();
+    if target.is_empty() {
+        return None;
+    }
+    let rate = |rate: f64, source: RateSource, updated_at: Option<i64>| ExchangeRate {
+        currency: target.to_string(),
+        currency_symbol: if secondary.currency_symbol.is_empty() { target.to_string() } else { secondary.currency_symbol.clone() },
+        rate,
+        source,
+        updated_at,
+        stale: updated_at.is_some_and(|at| now - at > STALE_AFTER_SECS),
+    };
+
+    let fetched = fetched.filter(|f| {
+        secondary.auto_fetch && f.base.eq_ignore_ascii_case(&config.currency) && f.target.eq_ignore_ascii_case(target) && valid_rate(f.rate)
+    });
+    match fetched {
+        Some(f) => Some(rate(f.rate, RateSource::Fetched, Some(f.fetched_at))),
+        None => secondary.manual_rate.filter(|&r| valid_rate(r)).map(|r| rate(r, RateSource::Manual, None)),
+    }
+}
+
+/// Whether a new rate should be fetched at `now`
+pub fn fetch_due(config: &PricingConfig, fetched: Option<&FetchedRate>, now: i64) -> bool {
+    let secondary = &config.secondary;
+    if !secondary.auto_fetch || secondary.currency.trim().is_empty() {
+        return false;
+    }
+    match fetched {
+        Some(f) if f.base.eq_ignore_ascii_case(&config.currency) && f.target.eq_ignore_ascii_case(secondary.currency.trim()) => {
+            now - f.fetched_at >= FETCH_INTERVAL_SECS
+        }
+        _ => true,
+    }
+}
+
+/// Request URL, `{base}` and `{target}` replaced by the currency codes
+pub fn rate_url(template: &str, base: &str, target: &str) -> String {
+    template.replace("{base}", base).replace("{target}", target)
+}
+
+/// Rate for `target` in a response: `rates.<target>` (Frankfurter, open.er-api.com, exchangerate.host) or a bare number
+pub fn parse_rate_response(json: &serde_json::Value, target: &str) -> Option<f64> {
+    let rates = json.get("rates").or_else(|| json.get("conversion_rates"));
+    rates
+        .and_then(|r| r.get(target).or_else(|| r.get(target.to_ascii_uppercase())))
+        .or(Some(json))
+        .and_then(serde_json::Value::as_f64)
+        .filter(|&r| valid_rate(r))
+}
+
+/// Fetch the rate from `base` to `target`
+pub async fn fetch_rate(url_template: &str, base: &str, target: &str, now: i64) -> Result<FetchedRate> {
+    let url = rate_url(url_template, base, target);
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(Error::Config("Exchange rate URL must be an http(s) URL".to_string()));
+    }
+    let client = crate::updater::http_client()?;
+    let resp = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| Error::Network(format!("Failed to fetch exchange rate: {}", e)))?;
+    if !resp.status().is_success() {
+        return Err(Error::Network(format!("Exchange rate source returned status {}", resp.status())));
+    }
+    let json: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| Error::Serialization(format!("Failed to parse exchange rate response: {}", e)))?;
+    let rate = parse_rate_response(&json, target)
+        .ok_or_else(|| Error::Serialization(format!("No {} rate in the exchange rate response", target)))?;
+
+    Ok(FetchedRate { base: base.to_string(), target: target.to_string(), rate, fetched_at: now })
+}
+
+fn cache_path() -> Option<PathBuf> {
+    crate::updater::cache_file_path("exchange_rate_cache.json")
+}
+
+/// Last fetched rate, if one was cached
+pub fn load_cached() -> Option<FetchedRate> {
+    let content = std::fs::read_to_string(cache_path()?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Persist the last fetched rate
+pub fn save_cached(rate: &FetchedRate) {
+    let Some(path) = cache_path() else {
+        return;
+    };
+    match serde_json::to_string(rate) {
+        Ok(content) => {
+            if let Err(e) = std::fs::write(path, content) {
+                log::warn!("Failed to write exchange rate cache: {}", e);
+            }
+        }
+        Err(e) => log::warn!("Failed to serialize exchange rate cache: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(manual_rate: Option<f64>, auto_fetch: bool) -> PricingConfig {
+        let mut config = PricingConfig::default();
+        config.secondary.currency = "USD".to_string();
+        config.secondary.currency_symbol = "$".to_string();
+        config.secondary.manual_rate = manual_rate;
+        config.secondary.auto_fetch = auto_fetch;
+        config
+    }
+
+    fn fetched(rate: f64, fetched_at: i64) -> FetchedRate {
+        FetchedRate { base: "EUR".to_string(), target: "USD".to_string(), rate, fetched_at }
+    }
+
+    #[test]
+    fn test_resolve_manual_and_fetched() {
+        let now = 1_700_000_000;
+        assert_eq!(resolve(&PricingConfig::default(), None, now), None);
+
+        // Manual only: offline, never stale
+        let manual = resolve(&config(Some(1.08), false), Some(&fetched(1.1, 0)), now).unwrap();
+        assert_eq!((manual.rate, manual.source, manual.stale), (1.08, RateSource::Manual, false));
+        assert!((manual.convert(10.0) - 10.8).abs() < 1e-9);
+
+        // Fetched wins, the manual rate covers the time before the first fetch
+        let auto = config(Some(1.08), true);
+        assert_eq!(resolve(&auto, None, now).unwrap().source, RateSource::Manual);
+        let recent = resolve(&auto, Some(&fetched(1.1, now - 3600)), now).unwrap();
+        assert_eq!((recent.rate, recent.source, recent.stale), (1.1, RateSource::Fetched, false));
+
+        // Older than a week: used, but flagged
+        let old = resolve(&auto, Some(&fetched(1.1, now - STALE_AFTER_SECS - 1)), now).unwrap();
+        assert!(old.stale);
+
+        // A rate cached for another pair is ignored
+        let mut other = fetched(150.0, now);
+        other.target = "JPY".to_string();
+        assert_eq!(resolve(&auto, Some(&other), now).unwrap().source, RateSource::Manual);
+        assert!(fetch_due(&auto, Some(&other), now));
+        assert!(!fetch_due(&auto, Some(&fetched(1.1, now - 3600)), now));
+        assert!(fetch_due(&auto, Some(&fetched(1.1, now - FETCH_INTERVAL_SECS)), now));
+        assert!(!fetch_due(&config(Some(1.08), false), None, now));
+    }
+
+    #[test]
+    fn test_parse_rate_response() {
+        let frankfurter = serde_json::json!({ "amount": 1.0, "base": "EUR", "rates": { "USD": 1.0823 } });
+        assert_eq!(parse_rate_response(&frankfurter, "USD"), Some(1.0823));
+        let er_api = serde_json::json!({ "result": "success", "conversion_rates": { "USD": 1.08 } });
+        assert_eq!(parse_rate_response(&er_api, "USD"), Some(1.08));
+        assert_eq!(parse_rate_response(&serde_json::json!(0.92), "EUR"), Some(0.92));
+        assert_eq!(parse_rate_response(&frankfurter, "GBP"), None);
+        assert_eq!(parse_rate_response(&serde_json::json!({ "rates": { "USD": -1 } }), "USD"), None);
+
+        assert_eq!(
+            rate_url("https://api.frankfurter.app/latest?from={base}&to={target}", "EUR", "USD"),
+            "https://api.frankfurter.app/latest?from=EUR&to=USD"
+        );
+    }
+}
@@ -6,12 +6,43 @@
 //! - Seasonal: different rates by season (summer/winter)
 //! - Tempo: EDF-style with day colors (blue/white/red) and peak/off-peak
 
-use crate::core::PricingConfig;
-use chrono::{Local, Timelike, Datelike};
+mod billing;
+mod breakdown;
+pub mod currency;
+mod presets;
+mod savings;
+
+pub use billing::{compute_billing_summary, month_ranges};
+pub use breakdown::{compute_cost_breakdown, CostBreakdown};
+pub use presets::{apply_preset, find_preset, PricingPreset, PRICING_PRESETS};
+pub use savings::{compute_savings, SavingsSummary};
+
+use crate::core::{ExchangeRate, PricingConfig, PricingMode};
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+
+/// Cost projections at a constant power
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostEstimates {
+    pub hourly: f64,
+    pub daily: f64,
+    /// 30 days
+    pub monthly: f64,
+}
+
+/// Average power over a run, `instant_watts` until a second has passed
+pub fn average_power_watts(cumulative_wh: f64, duration_secs: u64, instant_watts: f64) -> f64 {
+    if duration_secs > 0 {
+        cumulative_wh / (duration_secs as f64 / 3600.0)
+    } else {
+        instant_watts
+    }
+}
 
 /// Pricing engine that calculates electricity costs
 pub struct PricingEngine {
     config: PricingConfig,
+    /// Last exchange rate fetched for the secondary currency
+    fetched_rate: Option<currency::FetchedRate>,
 }
 
 impl PricingEngine {
@@ -19,6 +50,7 @@ impl PricingEngine {
     pub fn new(config: &PricingConfig) -> Self {
         Self {
             config: config.clone(),
+            fetched_rate: None,
         }
     }
 
@@ -29,20 +61,86 @@ impl PricingEngine {
 
     /// Get the current rate per kWh based on the pricing mode and current time
     pub fn get_current_rate(&self) -> f64 {
-        match self.config.mode.as_str() {
-            "simple" => self.config.simple.rate_per_kwh,
-            "peak_offpeak" => self.get_peak_offpeak_rate(),
-            "seasonal" => self.get_seasonal_rate(),
-            "tempo" => self.get_tempo_rate(),
-            _ => self.config.simple.rate_per_kwh, // Default to simple
+        self.rate_at(Local::now().naive_local())
+    }
+
+    /// Get the rate per kWh at a given local time
+    pub fn rate_at(&self, at: NaiveDateTime) -> f64 {
+        match self.config.mode {
+            PricingMode::Simple => self.config.simple.rate_per_kwh,
+            PricingMode::PeakOffpeak => self.get_peak_offpeak_rate(at),
+            PricingMode::Seasonal => self.get_seasonal_rate(at),
+            PricingMode::Tempo => self.get_tempo_rate(at),
+            PricingMode::Other(_) => self.config.simple.rate_per_kwh, // Default to simple
+        }
+    }
+
+    /// Get the rate per kWh in effect at a Unix timestamp (local time)
+    pub fn rate_at_timestamp(&self, timestamp: i64) -> f64 {
+        let at = Local
+            .timestamp_opt(timestamp, 0)
+            .single()
+            .map(|dt| dt.naive_local())
+            .unwrap_or_else(|| Local::now().naive_local());
+        self.rate_at(at)
+    }
+
+    /// Average rate over a local day, each hour weighted the same
+    ///
+    /// For days whose energy is known but not when it was used.
+    pub fn day_average_rate(&self, date: NaiveDate) -> f64 {
+        let half_past = |hour: u32| date.and_hms_opt(hour, 30, 0).unwrap_or_default();
+        (0..24).map(|hour| self.rate_at(half_past(hour))).sum::<f64>() / 24.0
+    }
+
+    /// Translation key naming the rate in effect at a given local time ("rate.offpeak"...)
+    pub fn rate_name_key_at(&self, at: NaiveDateTime) -> &'static str {
+        match self.config.mode {
+            PricingMode::PeakOffpeak if self.is_offpeak_time(at) => "rate.offpeak",
+            PricingMode::PeakOffpeak => "rate.peak",
+            PricingMode::Seasonal if self.config.seasonal.winter_months.contains(&at.month()) => "rate.winter",
+            PricingMode::Seasonal => "rate.summer",
+            PricingMode::Tempo => match (self.tempo_day_color(at), self.is_offpeak_time(at)) {
+                ("white", true) => "rate.tempo.white_offpeak",
+                ("white", false) => "rate.tempo.white_peak",
+                ("red", true) => "rate.tempo.red_offpeak",
+                ("red", false) => "rate.tempo.red_peak",
+                (_, true) => "rate.tempo.blue_offpeak",
+                (_, false) => "rate.tempo.blue_peak",
+            },
+            PricingMode::Simple | PricingMode::Other(_) => "rate.flat",
         }
     }
 
+    /// Peak rate of the mode in effect at a Unix timestamp (local time)
+    ///
+    /// The white-day peak rate in tempo mode, the rate itself in modes without off-peak hours.
+    pub fn peak_rate_at_timestamp(&self, timestamp: i64) -> f64 {
+        match self.config.mode {
+            PricingMode::PeakOffpeak => self.config.peak_offpeak.peak_rate,
+            PricingMode::Tempo => self.config.tempo.white_peak,
+            _ => self.rate_at_timestamp(timestamp),
+        }
+    }
+
+    /// Rate of the simple mode, whichever mode is in effect
+    pub fn simple_rate(&self) -> f64 {
+        self.config.simple.rate_per_kwh
+    }
+
     /// Calculate cost for a given energy consumption in kWh
     pub fn calculate_cost(&self, kwh: f64) -> f64 {
         kwh * self.get_current_rate()
     }
 
+    /// Calculate cost for energy consumed at a given local time
+    ///
+    /// Summing this per tick gives a time-weighted cost, unlike pricing a
+    /// running total at whatever rate applies when it is read.
+    pub fn calculate_cost_at(&self, kwh: f64, at: NaiveDateTime) -> f64 {
+        kwh * self.rate_at(at)
+    }
+
     /// Calculate estimated hourly cost at current power consumption
     pub fn calculate_hourly_cost(&self, watts: f64) -> f64 {
         let kwh_per_hour = watts / 1000.0;
@@ -59,11 +157,40 @@ impl PricingEngine {
         self.calculate_daily_cost(watts) * 30.0
     }
 
+    /// Hourly, daily and monthly cost estimates at an average power
+    pub fn cost_estimates(&self, avg_watts: f64) -> CostEstimates {
+        CostEstimates {
+            hourly: self.calculate_hourly_cost(avg_watts),
+            daily: self.calculate_daily_cost(avg_watts),
+            monthly: self.calculate_monthly_cost(avg_watts),
+        }
+    }
+
     /// Get the currency symbol
     pub fn get_currency_symbol(&self) -> &str {
         &self.config.currency_symbol
     }
 
+    /// Exchange rate to the secondary currency, None when none is configured
+    pub fn exchange_rate(&self) -> Option<ExchangeRate> {
+        currency::resolve(&self.config, self.fetched_rate.as_ref(), chrono::Utc::now().timestamp())
+    }
+
+    /// Convert an amount to the secondary currency
+    pub fn convert(&self, amount: f64) -> Option<f64> {
+        self.exchange_rate().map(|rate| rate.convert(amount))
+    }
+
+    /// Last fetched exchange rate (cached or from this run)
+    pub fn fetched_rate(&self) -> Option<&currency::FetchedRate> {
+        self.fetched_rate.as_ref()
+    }
+
+    /// Use a fetched exchange rate from now on
+    pub fn set_fetched_rate(&mut self, rate: Option<currency::FetchedRate>) {
+        self.fetched_rate = rate;
+    }
+
     /// Check if pricing is configured (not just using defaults)
     pub fn is_configured(&self) -> bool {
         // Check if user has set a rate different from 0
@@ -72,18 +199,17 @@ impl PricingEngine {
 
     // Private methods for each pricing mode
 
-    fn get_peak_offpeak_rate(&self) -> f64 {
-        if self.is_offpeak_time() {
+    fn get_peak_offpeak_rate(&self, at: NaiveDateTime) -> f64 {
+        if self.is_offpeak_time(at) {
             self.config.peak_offpeak.offpeak_rate
         } else {
             self.config.peak_offpeak.peak_rate
         }
     }
 
-    fn is_offpeak_time(&self) -> bool {
-        let now = Local::now();
-        let current_hour = now.hour();
-        let current_minute = now.minute();
+    fn is_offpeak_time(&self, at: NaiveDateTime) -> bool {
+        let current_hour = at.hour();
+        let current_minute = at.minute();
         let current_time = current_hour * 60 + current_minute;
 
         // Parse offpeak start and end times
@@ -111,9 +237,8 @@ impl PricingEngine {
         }
     }
 
-    fn get_seasonal_rate(&self) -> f64 {
-        let now = Local::now();
-        let current_month = now.month();
+    fn get_seasonal_rate(&self, at: NaiveDateTime) -> f64 {
+        let current_month = at.month();
 
         if self.config.seasonal.winter_months.contains(&current_month) {
             self.config.seasonal.winter_rate
@@ -122,7 +247,19 @@ impl PricingEngine {
         }
     }
 
-    fn get_tempo_rate(&self) -> f64 {
+    fn get_tempo_rate(&self, at: NaiveDateTime) -> f64 {
+        match (self.tempo_day_color(at), self.is_offpeak_time(at)) {
+            ("blue", true) => self.config.tempo.blue_offpeak,
+            ("blue", false) => self.config.tempo.blue_peak,
+            ("white", true) => self.config.tempo.white_offpeak,
+            ("white", false) => self.config.tempo.white_peak,
+            ("red", true) => self.config.tempo.red_offpeak,
+            ("red", false) => self.config.tempo.red_peak,
+            _ => self.config.tempo.blue_peak, // Default
+        }
+    }
+
+    fn tempo_day_color(&self, at: NaiveDateTime) -> &'static str {
         // Tempo uses day colors (blue, white, red) combined with peak/offpeak
         // For simplicity, we'll use a simple heuristic:
         // - Winter weekdays during peak months: red days
@@ -131,9 +268,8 @@ impl PricingEngine {
         //
         // Note: Real Tempo implementation would require fetching day colors from EDF API
 
-        let now = Local::now();
-        let month = now.month();
-        let weekday = now.weekday();
+        let month = at.month();
+        let weekday = at.weekday();
 
         // Determine day color (simplified)
         let is_winter = [12, 1, 2].contains(&month);
@@ -146,7 +282,7 @@ impl PricingEngine {
                 | chrono::Weekday::Fri
         );
 
-        let day_color = if is_winter && is_weekday {
+        if is_winter && is_weekday {
             // Cold winter weekdays: higher chance of red/white
             if month == 1 || month == 2 {
                 "white" // Could be red on very cold days
@@ -157,18 +293,6 @@ impl PricingEngine {
             "white"
         } else {
             "blue"
-        };
-
-        let is_offpeak = self.is_offpeak_time();
-
-        match (day_color, is_offpeak) {
-            ("blue", true) => self.config.tempo.blue_offpeak,
-            ("blue", false) => self.config.tempo.blue_peak,
-            ("white", true) => self.config.tempo.white_offpeak,
-            ("white", false) => self.config.tempo.white_peak,
-            ("red", true) => self.config.tempo.red_offpeak,
-            ("red", false) => self.config.tempo.red_peak,
-            _ => self.config.tempo.blue_peak, // Default
         }
     }
 }
@@ -180,13 +304,15 @@ mod tests {
 
     fn default_pricing_config() -> PricingConfig {
         PricingConfig {
-            mode: "simple".to_string(),
+            mode: PricingMode::Simple,
             currency: "EUR".to_string(),
             currency_symbol: "\u{20AC}".to_string(),
             simple: SimplePricing { rate_per_kwh: 0.20 },
             peak_offpeak: PeakOffpeakPricing::default(),
             seasonal: SeasonalPricing::default(),
             tempo: TempoPricing::default(),
+            secondary: Default::default(),
+            auto_region: None,
         }
     }
 
@@ -209,4 +335,70 @@ mod tests {
         let hourly = engine.calculate_hourly_cost(100.0);
         assert!((hourly - 0.02).abs() < 0.001);
     }
+
+    #[test]
+    fn test_cost_across_offpeak_boundary() {
+        let mut config = default_pricing_config();
+        config.mode = PricingMode::PeakOffpeak;
+        config.peak_offpeak = PeakOffpeakPricing {
+            peak_rate: 0.30,
+            offpeak_rate: 0.10,
+            offpeak_start: "22:00".to_string(),
+            offpeak_end: "06:00".to_string(),
+        };
+        let engine = PricingEngine::new(&config);
+
+        // 1 kW from 21:30 to 22:30, one tick per minute
+        let start = chrono::NaiveDate::from_ymd_opt(2024, 6, 3).unwrap().and_hms_opt(21, 30, 0).unwrap();
+        let tick_kwh = 1.0 / 60.0;
+        let cost: f64 = (0..60)
+            .map(|i| engine.calculate_cost_at(tick_kwh, start + chrono::Duration::minutes(i)))
+            .sum();
+
+        // Half an hour at each rate, not the whole hour at the rate read at the end
+        assert!((cost - (0.5 * 0.30 + 0.5 * 0.10)).abs() < 1e-9);
+        let end = start + chrono::Duration::minutes(60);
+        assert!((engine.calculate_cost_at(1.0, end) - 0.10).abs() < 1e-9);
+
+        // 8 off-peak hours and 16 peak hours
+        let day_rate = engine.day_average_rate(start.date());
+        assert!((day_rate - (8.0 * 0.10 + 16.0 * 0.30) / 24.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_peak_rate_by_mode() {
+        let mut config = default_pricing_config();
+        assert_eq!(PricingEngine::new(&config).peak_rate_at_timestamp(0), 0.20);
+
+        config.mode = PricingMode::PeakOffpeak;
+        assert_eq!(PricingEngine::new(&config).peak_rate_at_timestamp(0), config.peak_offpeak.peak_rate);
+
+        // Tempo compares with the white-day peak rate, whatever the day color
+        config.mode = PricingMode::Tempo;
+        let engine = PricingEngine::new(&config);
+        assert_eq!(engine.peak_rate_at_timestamp(0), config.tempo.white_peak);
+        assert_eq!(engine.simple_rate(), 0.20);
+    }
+
+    #[test]
+    fn test_rate_name_key() {
+        let mut config = default_pricing_config();
+        let night = NaiveDate::from_ymd_opt(2024, 7, 6).unwrap().and_hms_opt(23, 30, 0).unwrap();
+        let noon = NaiveDate::from_ymd_opt(2024, 1, 10).unwrap().and_hms_opt(12, 0, 0).unwrap();
+        assert_eq!(PricingEngine::new(&config).rate_name_key_at(noon), "rate.flat");
+
+        config.mode = PricingMode::PeakOffpeak;
+        let engine = PricingEngine::new(&config);
+        assert_eq!((engine.rate_name_key_at(night), engine.rate_name_key_at(noon)), ("rate.offpeak", "rate.peak"));
+
+        config.mode = PricingMode::Seasonal;
+        let engine = PricingEngine::new(&config);
+        assert_eq!((engine.rate_name_key_at(night), engine.rate_name_key_at(noon)), ("rate.summer", "rate.winter"));
+
+        // A summer Saturday is a blue day, a January weekday a white one
+        config.mode = PricingMode::Tempo;
+        let engine = PricingEngine::new(&config);
+        assert_eq!(engine.rate_name_key_at(night), "rate.tempo.blue_offpeak");
+        assert_eq!(engine.rate_name_key_at(noon), "rate.tempo.white_peak");
+    }
 }
@@ -0,0 +1,149 @@
+//! Country pricing presets
+//!
+//! Average residential rates, rounded, meant as a starting point the user can
+//! adjust. Countries where HP/HC contracts are common also carry a typical split.
+
+use crate::core::{PricingConfig, PricingMode};
+use serde::Serialize;
+
+/// Typical peak/off-peak split for a country
+#[derive(Debug, Clone, Serialize)]
+pub struct PeakOffpeakPreset {
+    pub peak_rate: f64,
+    pub offpeak_rate: f64,
+    pub offpeak_start: &'static str,
+    pub offpeak_end: &'static str,
+}
+
+/// Pricing preset for a country
+#[derive(Debug, Clone, Serialize)]
+pub struct PricingPreset {
+    /// ISO 3166-1 alpha-2 country code
+    pub country_code: &'static str,
+    pub country_name: &'static str,
+    /// ISO 4217 currency code
+    pub currency: &'static str,
+    pub currency_symbol: &'static str,
+    /// Average simple rate per kWh
+    pub rate_per_kwh: f64,
+    /// Pricing mode suggested for this country
+    pub mode: PricingMode,
+    pub peak_offpeak: Option<PeakOffpeakPreset>,
+}
+
+const fn preset(
+    country_code: &'static str,
+    country_name: &'static str,
+    currency: &'static str,
+    currency_symbol: &'static str,
+    rate_per_kwh: f64,
+) -> PricingPreset {
+    PricingPreset {
+        country_code,
+        country_name,
+        currency,
+        currency_symbol,
+        rate_per_kwh,
+        mode: PricingMode::Simple,
+        peak_offpeak: None,
+    }
+}
+
+impl PricingPreset {
+    /// Same preset with a typical peak/off-peak split
+    const fn with_peak_offpeak(mut self, split: PeakOffpeakPreset) -> Self {
+        self.peak_offpeak = Some(split);
+        self
+    }
+}
+
+/// Curated country presets
+pub static PRICING_PRESETS: &[PricingPreset] = &[
+    preset("FR", "France", "EUR", "\u{20AC}", 0.2516).with_peak_offpeak(PeakOffpeakPreset {
+        peak_rate: 0.2700,
+        offpeak_rate: 0.2068,
+        offpeak_start: "22:00",
+        offpeak_end: "06:00",
+    }),
+    preset("BE", "Belgique", "EUR", "\u{20AC}", 0.35),
+    preset("CH", "Schweiz / Suisse", "CHF", "CHF", 0.32),
+    preset("DE", "Deutschland", "EUR", "\u{20AC}", 0.40),
+    preset("ES", "Espa\u{00F1}a", "EUR", "\u{20AC}", 0.17),
+    preset("IT", "Italia", "EUR", "\u{20AC}", 0.30),
+    preset("LU", "Luxembourg", "EUR", "\u{20AC}", 0.25),
+    preset("NL", "Nederland", "EUR", "\u{20AC}", 0.30),
+    preset("PT", "Portugal", "EUR", "\u{20AC}", 0.22),
+    preset("GB", "United Kingdom", "GBP", "\u{00A3}", 0.245),
+    preset("IE", "Ireland", "EUR", "\u{20AC}", 0.38),
+    preset("US", "United States", "USD", "$", 0.16),
+    preset("CA", "Canada", "CAD", "$", 0.13),
+    preset("AU", "Australia", "AUD", "$", 0.30),
+    preset("JP", "Japan", "JPY", "\u{00A5}", 31.0),
+];
+
+/// Find a preset by country code (case-insensitive)
+pub fn find_preset(country_code: &str) -> Option<&'static PricingPreset> {
+    PRICING_PRESETS
+        .iter()
+        .find(|p| p.country_code.eq_ignore_ascii_case(country_code))
+}
+
+/// Fill a pricing config from a preset
+///
+/// The mode is only changed if the user hasn't customized it (still "simple").
+pub fn apply_preset(config: &mut PricingConfig, preset: &PricingPreset) {
+    config.currency = preset.currency.to_string();
+    config.currency_symbol = preset.currency_symbol.to_string();
+    config.simple.rate_per_kwh = preset.rate_per_kwh;
+
+    if let Some(ref hphc) = preset.peak_offpeak {
+        config.peak_offpeak.peak_rate = hphc.peak_rate;
+        config.peak_offpeak.offpeak_rate = hphc.offpeak_rate;
+        config.peak_offpeak.offpeak_start = hphc.offpeak_start.to_string();
+        config.peak_offpeak.offpeak_end = hphc.offpeak_end.to_string();
+    }
+
+    if config.mode == PricingMode::Simple {
+        config.mode = preset.mode.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_are_valid() {
+        for preset in PRICING_PRESETS {
+            assert!(
+                preset.currency.len() == 3 && preset.currency.chars().all(|c| c.is_ascii_uppercase()),
+                "{} has invalid currency code {}",
+                preset.country_code,
+                preset.currency
+            );
+            assert!(preset.rate_per_kwh > 0.0, "{} has non-positive rate", preset.country_code);
+            assert_eq!(preset.country_code.len(), 2);
+            assert!(!preset.currency_symbol.is_empty());
+            if let Some(ref hphc) = preset.peak_offpeak {
+                assert!(hphc.peak_rate > 0.0 && hphc.offpeak_rate > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_apply_preset_keeps_custom_mode() {
+        let mut config = PricingConfig {
+            mode: PricingMode::Tempo,
+            ..PricingConfig::default()
+        };
+
+        apply_preset(&mut config, find_preset("gb").unwrap());
+        assert_eq!(config.mode, PricingMode::Tempo);
+        assert_eq!(config.currency, "GBP");
+        assert_eq!(config.simple.rate_per_kwh, 0.245);
+
+        apply_preset(&mut config, find_preset("FR").unwrap());
+        assert_eq!(config.peak_offpeak.offpeak_rate, 0.2068);
+        assert!(find_preset("XX").is_none());
+    }
+}
@@ -0,0 +1,104 @@
+//! Savings from shifting consumption to cheaper hours
+//!
+//! The stored readings are priced three ways: at the rate in effect when they
+//! were taken (the actual cost), as if everything had been billed at the peak
+//! rate, and at the simple flat rate. Rates come from the current pricing
+//! settings; there is no history of past rate changes to replay.
+
+use crate::db::{ComponentReading, HOURS_PER_READING};
+use serde::{Deserialize, Serialize};
+
+/// Actual cost over a time range against two counterfactuals
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavingsSummary {
+    pub start: i64,
+    pub end: i64,
+    pub total_kwh: f64,
+    /// Energy billed below the peak rate
+    pub offpeak_kwh: f64,
+    pub actual_cost: f64,
+    /// Everything at the peak rate (the white-day peak rate in tempo mode)
+    pub peak_cost: f64,
+    /// Everything at the simple rate
+    pub simple_cost: f64,
+    /// Positive when the actual cost is lower
+    pub savings_vs_peak: f64,
+    /// Savings in percent of the counterfactual cost, None when it is zero
+    pub savings_vs_peak_percent: Option<f64>,
+    pub savings_vs_simple: f64,
+    pub savings_vs_simple_percent: Option<f64>,
+}
+
+/// Price `readings` at `rate_at` (rate at a timestamp) against `peak_rate_at` and `simple_rate`
+pub fn compute_savings(
+    readings: &[ComponentReading],
+    start: i64,
+    end: i64,
+    rate_at: impl Fn(i64) -> f64,
+    peak_rate_at: impl Fn(i64) -> f64,
+    simple_rate: f64,
+) -> SavingsSummary {
+    let mut total_kwh = 0.0;
+    let mut offpeak_kwh = 0.0;
+    let mut actual_cost = 0.0;
+    let mut peak_cost = 0.0;
+
+    for reading in readings {
+        let kwh = reading.power_watts.max(0.0) * HOURS_PER_READING / 1000.0;
+        let (rate, peak_rate) = (rate_at(reading.timestamp), peak_rate_at(reading.timestamp));
+        total_kwh += kwh;
+        actual_cost += kwh * rate;
+        peak_cost += kwh * peak_rate;
+        if rate < peak_rate {
+            offpeak_kwh += kwh;
+        }
+    }
+
+    let simple_cost = total_kwh * simple_rate;
+    let percent = |savings: f64, counterfactual: f64| (counterfactual > 0.0).then(|| savings / counterfactual * 100.0);
+    SavingsSummary {
+        start,
+        end,
+        total_kwh,
+        offpeak_kwh,
+        actual_cost,
+        peak_cost,
+        simple_cost,
+        savings_vs_peak: peak_cost - actual_cost,
+        savings_vs_peak_percent: percent(peak_cost - actual_cost, peak_cost),
+        savings_vs_simple: simple_cost - actual_cost,
+        savings_vs_simple_percent: percent(simple_cost - actual_cost, simple_cost),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(timestamp: i64, watts: f64) -> ComponentReading {
+        ComponentReading { timestamp, power_watts: watts, cpu_watts: None, gpu_watts: None, base_watts: None }
+    }
+
+    #[test]
+    fn test_savings_against_peak_and_simple() {
+        // 360 W for one reading = 1 Wh, the second one billed off-peak
+        let readings = [reading(0, 360.0), reading(10, 360.0), reading(20, 720.0)];
+        let s = compute_savings(&readings, 0, 30, |ts| if ts == 10 { 0.1 } else { 0.3 }, |_| 0.3, 0.25);
+
+        assert!((s.total_kwh - 0.004).abs() < 1e-12);
+        assert!((s.offpeak_kwh - 0.001).abs() < 1e-12);
+        // 0.003 * 0.3 + 0.001 * 0.1
+        assert!((s.actual_cost - 0.001).abs() < 1e-12);
+        assert!((s.peak_cost - 0.0012).abs() < 1e-12);
+        assert!((s.savings_vs_peak - 0.0002).abs() < 1e-12);
+        assert!((s.savings_vs_peak_percent.unwrap() - 100.0 / 6.0).abs() < 1e-9);
+        // The flat rate would have cost the same
+        assert!((s.simple_cost - 0.001).abs() < 1e-12);
+        assert!(s.savings_vs_simple.abs() < 1e-12);
+
+        let empty = compute_savings(&[], 0, 10, |_| 0.2, |_| 0.3, 0.25);
+        assert_eq!(empty.total_kwh, 0.0);
+        assert_eq!(empty.savings_vs_peak_percent, None);
+        assert_eq!(empty.savings_vs_simple_percent, None);
+    }
+}
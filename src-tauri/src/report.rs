@@ -0,0 +1,315 @@
+//! Consumption reports over a completed period
+//!
+//! A report covers the last complete day, ISO week (Monday to Sunday) or
+//! number of days, and compares it with the period just before. It is
+//! serialized as JSON for integrations such as the webhook notifier.
+
+use crate::core::clock::local_day_start;
+use crate::core::{ExchangeRate, Result, Session};
+use crate::db::{DailyStats, Database, DayNote, LifetimeTotals};
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// Number of sessions listed in a report
+pub const TOP_SESSIONS: usize = 5;
+
+/// Length of a reported period
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportPeriod {
+    Daily,
+    Weekly,
+    /// The last `n` complete days, up to yesterday
+    Days(u32),
+}
+
+impl ReportPeriod {
+    /// Parse a config value ("daily" or "weekly")
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "daily" => Some(Self::Daily),
+            "weekly" => Some(Self::Weekly),
+            _ => None,
+        }
+    }
+
+    /// First and last day of the last complete period before `today`
+    pub fn range(self, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            Self::Daily => {
+                let day = today - Duration::days(1);
+                (day, day)
+            }
+            Self::Weekly => {
+                let this_monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+                (this_monday - Duration::days(7), this_monday - Duration::days(1))
+            }
+            Self::Days(n) => (today - Duration::days(n.max(1) as i64), today - Duration::days(1)),
+        }
+    }
+
+    /// Range of the period just before `range(today)`
+    pub fn previous_range(self, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let (start, _) = self.range(today);
+        self.range(start)
+    }
+}
+
+/// Session summary included in a report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportSession {
+    pub id: Option<i64>,
+    pub label: Option<String>,
+    pub category: Option<String>,
+    pub start_time: i64,
+    pub end_time: Option<i64>,
+    pub surplus_wh: f64,
+    pub surplus_cost: f64,
+    /// Surplus cost in the secondary currency
+    #[serde(default)]
+    pub converted_surplus_cost: Option<f64>,
+}
+
+impl From<&Session> for ReportSession {
+    fn from(session: &Session) -> Self {
+        Self {
+            id: session.id,
+            label: session.label.clone(),
+            category: session.category.clone(),
+            start_time: session.start_time,
+            end_time: session.end_time,
+            surplus_wh: session.surplus_wh,
+            surplus_cost: session.surplus_cost,
+            converted_surplus_cost: None,
+        }
+    }
+}
+
+/// Totals and top sessions for one period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    pub period: ReportPeriod,
+    /// First day of the period (YYYY-MM-DD, inclusive)
+    pub start_date: String,
+    /// Last day of the period (YYYY-MM-DD, inclusive)
+    pub end_date: String,
+    pub total_kwh: f64,
+    /// None when no day of the period has a cost
+    pub total_cost: Option<f64>,
+    pub previous_total_kwh: f64,
+    pub previous_total_cost: Option<f64>,
+    /// Cost change versus the previous period, in percent (None without a previous cost)
+    pub cost_change_percent: Option<f64>,
+    pub currency: String,
+    pub session_count: usize,
+    /// Most expensive sessions of the period, by surplus cost
+    pub top_sessions: Vec<ReportSession>,
+    /// Unix timestamp
+    pub generated_at: i64,
+    /// Rate used for the converted amounts, None without a secondary currency
+    #[serde(default)]
+    pub exchange_rate: Option<ExchangeRate>,
+    #[serde(default)]
+    pub converted_total_cost: Option<f64>,
+    #[serde(default)]
+    pub converted_previous_total_cost: Option<f64>,
+    /// Notes written on days of the period, oldest first
+    #[serde(default)]
+    pub day_notes: Vec<DayNote>,
+    /// Everything tracked so far, for the footer
+    #[serde(default)]
+    pub lifetime: Option<LifetimeTotals>,
+}
+
+impl Report {
+    /// Fill in the amounts converted to the secondary currency
+    pub fn with_exchange_rate(mut self, rate: Option<ExchangeRate>) -> Self {
+        if let Some(ref rate) = rate {
+            self.converted_total_cost = self.total_cost.map(|c| rate.convert(c));
+            self.converted_previous_total_cost = self.previous_total_cost.map(|c| rate.convert(c));
+            for session in &mut self.top_sessions {
+                session.converted_surplus_cost = Some(rate.convert(session.surplus_cost));
+            }
+        }
+        self.exchange_rate = rate;
+        self
+    }
+}
+
+/// Sum energy (kWh) and cost over daily stats
+fn totals(stats: &[DailyStats]) -> (f64, Option<f64>) {
+    let kwh = stats.iter().map(|s| s.total_wh).sum::<f64>() / 1000.0;
+    let costs: Vec<f64> = stats.iter().filter_map(|s| s.total_cost).collect();
+    let cost = if costs.is_empty() { None } else { Some(costs.iter().sum()) };
+    (kwh, cost)
+}
+
+/// Assemble a report from already-loaded data
+pub fn summarize(
+    period: ReportPeriod,
+    range: (NaiveDate, NaiveDate),
+    current: &[DailyStats],
+    previous: &[DailyStats],
+    sessions: &[Session],
+    currency: &str,
+    generated_at: i64,
+) -> Report {
+    let (total_kwh, total_cost) = totals(current);
+    let (previous_total_kwh, previous_total_cost) = totals(previous);
+
+    let cost_change_percent = match (total_cost, previous_total_cost) {
+        (Some(cost), Some(prev)) if prev > 0.0 => Some((cost - prev) / prev * 100.0),
+        _ => None,
+    };
+
+    let mut ranked: Vec<&Session> = sessions.iter().collect();
+    ranked.sort_by(|a, b| b.surplus_cost.total_cmp(&a.surplus_cost));
+
+    Report {
+        period,
+        start_date: range.0.format("%Y-%m-%d").to_string(),
+        end_date: range.1.format("%Y-%m-%d").to_string(),
+        total_kwh,
+        total_cost,
+        previous_total_kwh,
+        previous_total_cost,
+        cost_change_percent,
+        currency: currency.to_string(),
+        session_count: sessions.len(),
+        top_sessions: ranked.into_iter().take(TOP_SESSIONS).map(ReportSession::from).collect(),
+        generated_at,
+        exchange_rate: None,
+        converted_total_cost: None,
+        converted_previous_total_cost: None,
+        day_notes: Vec::new(),
+        lifetime: None,
+    }
+}
+
+/// Local-time Unix timestamps spanning whole days `start..=end`
+fn local_day_bounds(start: NaiveDate, end: NaiveDate) -> (i64, i64) {
+    (local_day_start(start, &Local), local_day_start(end + Duration::days(1), &Local) - 1)
+}
+
+/// Build the report for the last complete period before `today`
+pub fn build_report(db: &Database, period: ReportPeriod, today: NaiveDate, currency: &str) -> Result<Report> {
+    let date = |d: NaiveDate| d.format("%Y-%m-%d").to_string();
+
+    let range = period.range(today);
+    let previous_range = period.previous_range(today);
+    let current = db.get_daily_stats(&date(range.0), &date(range.1))?;
+    let previous = db.get_daily_stats(&date(previous_range.0), &date(previous_range.1))?;
+
+    let (start_ts, end_ts) = local_day_bounds(range.0, range.1);
+    // By start time, so a session crossing two periods is reported once
+    let sessions: Vec<Session> = db
+        .get_sessions_started_in_range(start_ts, end_ts)?
+        .into_iter()
+        .filter(|s| s.end_time.is_some())
+        .collect();
+
+    Ok(Report {
+        day_notes: db.get_day_notes(&date(range.0), &date(range.1))?,
+        lifetime: Some(db.get_lifetime_totals()?),
+        ..summarize(period, range, &current, &previous, &sessions, currency, chrono::Utc::now().timestamp())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn stats(date: &str, wh: f64, cost: Option<f64>) -> DailyStats {
+        DailyStats {
+            date: date.to_string(),
+            total_wh: wh,
+            total_cost: cost,
+            avg_watts: 0.0,
+            max_watts: 0.0,
+            pricing_mode: None,
+            usage_seconds: 0,
+            source: None,
+            note: None,
+        }
+    }
+
+    fn session(id: i64, cost: f64) -> Session {
+        let mut s = Session::new(50.0, Some(format!("s{}", id)));
+        s.id = Some(id);
+        s.surplus_cost = cost;
+        s
+    }
+
+    #[test]
+    fn test_period_ranges() {
+        // 2024-03-13 is a Wednesday
+        let today = day("2024-03-13");
+        assert_eq!(ReportPeriod::Daily.range(today), (day("2024-03-12"), day("2024-03-12")));
+        assert_eq!(ReportPeriod::Daily.previous_range(today), (day("2024-03-11"), day("2024-03-11")));
+        assert_eq!(ReportPeriod::Weekly.range(today), (day("2024-03-04"), day("2024-03-10")));
+        assert_eq!(ReportPeriod::Weekly.previous_range(today), (day("2024-02-26"), day("2024-03-03")));
+
+        // On a Monday the week that just ended is reported
+        assert_eq!(ReportPeriod::Weekly.range(day("2024-03-11")), (day("2024-03-04"), day("2024-03-10")));
+        assert_eq!(ReportPeriod::parse("weekly"), Some(ReportPeriod::Weekly));
+        assert_eq!(ReportPeriod::parse("monthly"), None);
+
+        // Last 7 days, before that the 7 days preceding them
+        assert_eq!(ReportPeriod::Days(7).range(today), (day("2024-03-06"), day("2024-03-12")));
+        assert_eq!(ReportPeriod::Days(7).previous_range(today), (day("2024-02-28"), day("2024-03-05")));
+    }
+
+    #[test]
+    fn test_summarize_totals_and_change() {
+        let current = [stats("2024-03-04", 1500.0, Some(0.3)), stats("2024-03-05", 500.0, Some(0.3))];
+        let previous = [stats("2024-02-26", 2500.0, Some(0.5))];
+        let sessions: Vec<Session> = (1..=7).map(|i| session(i, i as f64 * 0.01)).collect();
+
+        let r = summarize(ReportPeriod::Weekly, ReportPeriod::Weekly.range(day("2024-03-13")), &current, &previous, &sessions, "EUR", 0);
+        assert_eq!(r.start_date, "2024-03-04");
+        assert_eq!(r.end_date, "2024-03-10");
+        assert!((r.total_kwh - 2.0).abs() < 1e-9);
+        assert!((r.total_cost.unwrap() - 0.6).abs() < 1e-9);
+        assert!((r.cost_change_percent.unwrap() - 20.0).abs() < 1e-9);
+        assert_eq!(r.session_count, 7);
+        assert_eq!(r.top_sessions.len(), TOP_SESSIONS);
+        assert_eq!(r.top_sessions[0].id, Some(7));
+        assert_eq!(r.top_sessions[4].id, Some(3));
+    }
+
+    #[test]
+    fn test_summarize_without_costs() {
+        let current = [stats("2024-03-12", 800.0, None)];
+        let r = summarize(ReportPeriod::Daily, ReportPeriod::Daily.range(day("2024-03-13")), &current, &[], &[], "USD", 0);
+        assert_eq!(r.total_cost, None);
+        assert_eq!(r.previous_total_cost, None);
+        assert_eq!(r.cost_change_percent, None);
+        assert!(r.top_sessions.is_empty());
+    }
+
+    #[test]
+    fn test_converted_amounts() {
+        let current = [stats("2024-03-12", 800.0, Some(0.5))];
+        let sessions = [session(1, 0.2)];
+        let r = summarize(ReportPeriod::Daily, ReportPeriod::Daily.range(day("2024-03-13")), &current, &[], &sessions, "EUR", 0);
+        assert_eq!(r.clone().with_exchange_rate(None).converted_total_cost, None);
+
+        let rate = ExchangeRate {
+            currency: "USD".to_string(),
+            currency_symbol: "$".to_string(),
+            rate: 1.1,
+            source: crate::core::RateSource::Manual,
+            updated_at: None,
+            stale: false,
+        };
+        let r = r.with_exchange_rate(Some(rate));
+        assert!((r.converted_total_cost.unwrap() - 0.55).abs() < 1e-9);
+        assert_eq!(r.converted_previous_total_cost, None);
+        assert!((r.top_sessions[0].converted_surplus_cost.unwrap() - 0.22).abs() < 1e-9);
+        assert_eq!(r.exchange_rate.unwrap().currency, "USD");
+    }
+}
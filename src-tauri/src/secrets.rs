@@ -0,0 +1,490 @@
+//! Storage for integration credentials (broker passwords, API tokens)
+//!
+//! Secrets go to the OS keychain, keyed by integration name:
+//! - Linux: Secret Service through `secret-tool` (libsecret)
+//! - macOS: login keychain through `security`
+//! - Windows: Credential Manager
+//!
+//! Without a keychain they fall back to an obfuscated file next to the config.
+//! That file is NOT secure (anyone with access to the account can decode it),
+//! which `SecretStoreInfo::secure` reports. Without a config directory either,
+//! the store is unavailable and refuses every secret.
+//!
+//! The config only ever holds a reference (`secret:<name>`), so exporting or
+//! sharing config.toml never leaks a secret value.
+
+use crate::core::{Error, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Service name under which secrets are stored in the keychain
+const SERVICE: &str = "powercost-tracker";
+
+/// Prefix of secret references stored in the config
+const REFERENCE_PREFIX: &str = "secret:";
+
+/// Key for the fallback file obfuscation (this is not encryption)
+const OBFUSCATION_KEY: &[u8] = b"PowerCost-Tracker/secrets";
+
+/// Where secrets are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretBackend {
+    Keychain,
+    ObfuscatedFile,
+    /// Neither a keychain nor a config directory, secrets cannot be stored
+    Unavailable,
+}
+
+/// Secret store status reported to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct SecretStoreInfo {
+    pub backend: SecretBackend,
+    /// False for the obfuscated file fallback
+    pub secure: bool,
+}
+
+/// Secret store, backed by the OS keychain when available
+pub struct SecretStore {
+    backend: SecretBackend,
+    file_path: PathBuf,
+}
+
+impl SecretStore {
+    /// Create the store, using the keychain if this system has one
+    pub fn new() -> Result<Self> {
+        let config_dir = dirs::config_dir()
+            .ok_or_else(|| Error::Config("Could not determine config directory".to_string()))?;
+        let file_path = config_dir.join("powercost-tracker").join("secrets.dat");
+
+        let backend = if keychain::is_available() {
+            SecretBackend::Keychain
+        } else {
+            log::warn!("No OS keychain available, secrets are stored obfuscated (insecure) in {:?}", file_path);
+            SecretBackend::ObfuscatedFile
+        };
+
+        Ok(Self { backend, file_path })
+    }
+
+    /// Store refusing every secret, when `new` fails
+    pub fn unavailable() -> Self {
+        Self {
+            backend: SecretBackend::Unavailable,
+            file_path: PathBuf::new(),
+        }
+    }
+
+    /// Store backed by the obfuscated file at `file_path`
+    #[cfg(test)]
+    pub fn with_file(file_path: PathBuf) -> Self {
+        Self {
+            backend: SecretBackend::ObfuscatedFile,
+            file_path,
+        }
+    }
+
+    pub fn info(&self) -> SecretStoreInfo {
+        SecretStoreInfo {
+            backend: self.backend,
+            secure: self.backend == SecretBackend::Keychain,
+        }
+    }
+
+    /// Config reference for a secret name
+    pub fn reference(name: &str) -> String {
+        format!("{}{}", REFERENCE_PREFIX, name)
+    }
+
+    /// Store or replace a secret, returns the reference to keep in the config
+    pub fn set_secret(&self, name: &str, value: &str) -> Result<String> {
+        validate_name(name)?;
+        match self.backend {
+            SecretBackend::Keychain => keychain::set(name, value)?,
+            SecretBackend::ObfuscatedFile => {
+                let mut secrets = self.read_file()?;
+                secrets.insert(name.to_string(), value.to_string());
+                self.write_file(&secrets)?;
+            }
+            SecretBackend::Unavailable => return Err(unavailable()),
+        }
+        Ok(Self::reference(name))
+    }
+
+    /// Read a secret, None if it was never set
+    pub fn get_secret(&self, name: &str) -> Result<Option<String>> {
+        validate_name(name)?;
+        match self.backend {
+            SecretBackend::Keychain => keychain::get(name),
+            SecretBackend::ObfuscatedFile => Ok(self.read_file()?.remove(name)),
+            SecretBackend::Unavailable => Err(unavailable()),
+        }
+    }
+
+    /// Delete a secret (deleting a missing secret is not an error)
+    pub fn delete_secret(&self, name: &str) -> Result<()> {
+        validate_name(name)?;
+        match self.backend {
+            SecretBackend::Keychain => keychain::delete(name),
+            SecretBackend::ObfuscatedFile => {
+                let mut secrets = self.read_file()?;
+                if secrets.remove(name).is_some() {
+                    self.write_file(&secrets)?;
+                }
+                Ok(())
+            }
+            SecretBackend::Unavailable => Err(unavailable()),
+        }
+    }
+
+    fn read_file(&self) -> Result<BTreeMap<String, String>> {
+        if !self.file_path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let content = std::fs::read_to_string(&self.file_path)?;
+        let encoded: BTreeMap<String, String> = serde_json::from_str(&content)
+            .map_err(|e| Error::Serialization(format!("Invalid secrets file: {}", e)))?;
+        encoded
+            .into_iter()
+            .map(|(name, value)| Ok((name, deobfuscate(&value)?)))
+            .collect()
+    }
+
+    fn write_file(&self, secrets: &BTreeMap<String, String>) -> Result<()> {
+        let encoded: BTreeMap<&String, String> = secrets.iter().map(|(k, v)| (k, obfuscate(v))).collect();
+        let content = serde_json::to_string_pretty(&encoded).map_err(|e| Error::Serialization(e.to_string()))?;
+        if let Some(dir) = self.file_path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // Written to a file created owner-only, then moved over the old one,
+        // so the secrets are never readable by others even briefly
+        let temp_path = self.file_path.with_extension("dat.tmp");
+        let _ = std::fs::remove_file(&temp_path);
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&temp_path)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        drop(file);
+        std::fs::rename(&temp_path, &self.file_path)?;
+        Ok(())
+    }
+}
+
+fn unavailable() -> Error {
+    Error::Config("No secret store is available on this system".to_string())
+}
+
+/// Names double as keychain account names and file keys
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::Config(format!("Invalid secret name '{}'", name)))
+    }
+}
+
+fn obfuscate(value: &str) -> String {
+    value
+        .bytes()
+        .zip(OBFUSCATION_KEY.iter().cycle())
+        .map(|(b, k)| format!("{:02x}", b ^ k))
+        .collect()
+}
+
+fn deobfuscate(encoded: &str) -> Result<String> {
+    let invalid = || Error::Serialization("Invalid secrets file entry".to_string());
+    if encoded.len() % 2 != 0 {
+        return Err(invalid());
+    }
+    let bytes = (0..encoded.len())
+        .step_by(2)
+        .zip(OBFUSCATION_KEY.iter().cycle())
+        .map(|(i, k)| {
+            u8::from_str_radix(encoded.get(i..i + 2).ok_or_else(invalid)?, 16)
+                .map(|b| b ^ k)
+                .map_err(|_| invalid())
+        })
+        .collect::<Result<Vec<u8>>>()?;
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+#[cfg(target_os = "linux")]
+mod keychain {
+    use super::SERVICE;
+    use crate::core::{Error, Result};
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    /// Secret Service needs a session bus and the libsecret CLI
+    pub fn is_available() -> bool {
+        std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some()
+            && Command::new("secret-tool")
+                .arg("--help")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok()
+    }
+
+    pub fn set(name: &str, value: &str) -> Result<()> {
+        let mut child = Command::new("secret-tool")
+            .args(["store", "--label", &format!("{} ({})", SERVICE, name), "service", SERVICE, "account", name])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        // The value goes through stdin so it never shows up in the process list
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(value.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(Error::PermissionDenied(format!(
+                "Keychain refused to store secret: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn get(name: &str) -> Result<Option<String>> {
+        let output = Command::new("secret-tool")
+            .args(["lookup", "service", SERVICE, "account", name])
+            .stderr(Stdio::null())
+            .output()?;
+        // lookup exits with 1 when nothing matches
+        if !output.status.success() || output.stdout.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+    }
+
+    pub fn delete(name: &str) -> Result<()> {
+        Command::new("secret-tool")
+            .args(["clear", "service", SERVICE, "account", name])
+            .stderr(Stdio::null())
+            .status()?;
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod keychain {
+    use super::SERVICE;
+    use crate::core::{Error, Result};
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    pub fn is_available() -> bool {
+        Command::new("security")
+            .arg("list-keychains")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success())
+    }
+
+    pub fn set(name: &str, value: &str) -> Result<()> {
+        if value.contains('\n') {
+            return Err(Error::Config("Secrets cannot contain line breaks".to_string()));
+        }
+        // A trailing -w prompts for the password (twice) instead of taking it
+        // from argv, where the process list would show it
+        let mut child = Command::new("security")
+            .args(["add-generic-password", "-U", "-s", SERVICE, "-a", name, "-w"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(format!("{}\n{}\n", value, value).as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(Error::PermissionDenied(format!(
+                "Keychain refused to store secret: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn get(name: &str) -> Result<Option<String>> {
+        let output = Command::new("security")
+            .args(["find-generic-password", "-s", SERVICE, "-a", name, "-w"])
+            .stderr(Stdio::null())
+            .output()?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        let value = String::from_utf8_lossy(&output.stdout);
+        Ok(Some(value.strip_suffix('\n').unwrap_or(&value).to_string()))
+    }
+
+    pub fn delete(name: &str) -> Result<()> {
+        Command::new("security")
+            .args(["delete-generic-password", "-s", SERVICE, "-a", name])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod keychain {
+    use super::SERVICE;
+    use crate::core::{Error, Result};
+    use windows_sys::Win32::Security::Credentials::{
+        CredDeleteW, CredFree, CredReadW, CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE, CRED_TYPE_GENERIC,
+    };
+
+    fn target(name: &str) -> Vec<u16> {
+        format!("{}/{}", SERVICE, name).encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    /// Credential Manager is always present
+    pub fn is_available() -> bool {
+        true
+    }
+
+    pub fn set(name: &str, value: &str) -> Result<()> {
+        let mut target = target(name);
+        let mut blob = value.as_bytes().to_vec();
+        let credential = CREDENTIALW {
+            Flags: 0,
+            Type: CRED_TYPE_GENERIC,
+            TargetName: target.as_mut_ptr(),
+            Comment: std::ptr::null_mut(),
+            LastWritten: unsafe { std::mem::zeroed() },
+            CredentialBlobSize: blob.len() as u32,
+            CredentialBlob: blob.as_mut_ptr(),
+            Persist: CRED_PERSIST_LOCAL_MACHINE,
+            AttributeCount: 0,
+            Attributes: std::ptr::null_mut(),
+            TargetAlias: std::ptr::null_mut(),
+            UserName: std::ptr::null_mut(),
+        };
+        if unsafe { CredWriteW(&credential, 0) } == 0 {
+            return Err(Error::PermissionDenied(format!(
+                "Credential Manager refused to store secret: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn get(name: &str) -> Result<Option<String>> {
+        let target = target(name);
+        let mut credential: *mut CREDENTIALW = std::ptr::null_mut();
+        if unsafe { CredReadW(target.as_ptr(), CRED_TYPE_GENERIC, 0, &mut credential) } == 0 {
+            return Ok(None);
+        }
+        let value = unsafe {
+            let c = &*credential;
+            let bytes = std::slice::from_raw_parts(c.CredentialBlob, c.CredentialBlobSize as usize);
+            let value = String::from_utf8_lossy(bytes).into_owned();
+            CredFree(credential as *const _);
+            value
+        };
+        Ok(Some(value))
+    }
+
+    pub fn delete(name: &str) -> Result<()> {
+        let target = target(name);
+        unsafe { CredDeleteW(target.as_ptr(), CRED_TYPE_GENERIC, 0) };
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+mod keychain {
+    use crate::core::{Error, Result};
+
+    pub fn is_available() -> bool {
+        false
+    }
+
+    pub fn set(_name: &str, _value: &str) -> Result<()> {
+        Err(Error::HardwareNotSupported("No keychain on this platform".to_string()))
+    }
+
+    pub fn get(_name: &str) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    pub fn delete(_name: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store(test: &str) -> SecretStore {
+        let path = std::env::temp_dir().join(format!("powercost-secrets-{}-{}.dat", test, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        SecretStore::with_file(path)
+    }
+
+    #[test]
+    fn test_obfuscation_roundtrip() {
+        let value = "p@ss w\u{00F6}rd:1234";
+        let encoded = obfuscate(value);
+        assert!(!encoded.contains("p@ss"));
+        assert_eq!(deobfuscate(&encoded).unwrap(), value);
+        assert!(deobfuscate("abc").is_err());
+    }
+
+    #[test]
+    fn test_file_store() {
+        let store = temp_store("file");
+        assert!(!store.info().secure);
+
+        let reference = store.set_secret("mqtt", "hunter2").unwrap();
+        assert_eq!(reference, "secret:mqtt");
+        assert_eq!(store.get_secret("mqtt").unwrap().as_deref(), Some("hunter2"));
+
+        // The value is not readable as-is in the file
+        let content = std::fs::read_to_string(&store.file_path).unwrap();
+        assert!(!content.contains("hunter2"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&store.file_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        store.delete_secret("mqtt").unwrap();
+        assert_eq!(store.get_secret("mqtt").unwrap(), None);
+        let _ = std::fs::remove_file(&store.file_path);
+    }
+
+    #[test]
+    fn test_unavailable_store() {
+        let store = SecretStore::unavailable();
+        assert!(!store.info().secure);
+        assert!(store.set_secret("mqtt", "hunter2").is_err());
+        assert!(store.get_secret("mqtt").is_err());
+    }
+
+    #[test]
+    fn test_invalid_names() {
+        let store = temp_store("names");
+        assert!(store.set_secret("", "x").is_err());
+        assert!(store.set_secret("../escape", "x").is_err());
+        assert!(store.get_secret("with space").is_err());
+    }
+}
@@ -0,0 +1,252 @@
+//! Script run when a session ends (`advanced.session_end_hook`)
+//!
+//! The executable gets the ended session as JSON on stdin, the `Session`
+//! struct as returned by `end_tracking_session`:
+//!
+//! ```json
+//! {"id": 42, "start_time": 1705320000, "end_time": 1705327200, "baseline_watts": 45.0,
+//!  "total_wh": 610.0, "surplus_wh": 520.0, "surplus_cost": 0.1352, "label": "Render",
+//!  "category": "work", "target_cost": null, "target_wh": null, "target_progress_percent": null,
+//!  "target_met": null, "auto_ended": false, "continued_from": null, "baseline_bucket": null,
+//!  "duration_secs": 7200, "avg_watts": 305.0, "avg_surplus_watts": 260.0, "cost_per_hour": 0.0676}
+//! ```
+//!
+//! Timestamps are Unix seconds, energy in Wh, costs in the pricing currency.
+//! The key numbers are also in `POWERCOST_*` environment variables, see
+//! `env_vars`. A session split at midnight only ends when its last part does,
+//! it is then given merged with its earlier parts: the first part's `id` and
+//! `start_time`, the totals summed over every part.
+//!
+//! The hook runs on a blocking thread and is killed after `TIMEOUT`; its exit
+//! status is logged and never affects the session.
+
+use crate::core::{Error, Result, Session};
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Longest run of the hook before it is killed
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Bytes of stdout and stderr kept from the hook
+const MAX_OUTPUT_BYTES: usize = 4096;
+
+/// How long the output is waited for once the hook is gone, a process it
+/// left in the background may hold the pipes open
+const OUTPUT_GRACE: Duration = Duration::from_millis(500);
+
+/// How a hook run ended
+#[derive(Debug, Clone, Serialize)]
+pub struct HookOutcome {
+    /// Exit code, None when killed (timeout or signal)
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    /// Start of the hook's output, for `test_session_hook`
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl HookOutcome {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Check `advanced.session_end_hook`, empty means unset
+pub fn validate_path(path: Option<&str>) -> std::result::Result<(), String> {
+    match path.map(str::trim) {
+        None | Some("") => Ok(()),
+        Some(path) if Path::new(path).is_absolute() => Ok(()),
+        Some(path) => Err(format!("Session end hook must be an absolute path: {}", path)),
+    }
+}
+
+/// Environment variables given to the hook
+pub fn env_vars(session: &Session, currency: &str, test: bool) -> Vec<(&'static str, String)> {
+    let optional = |value: Option<String>| value.unwrap_or_default();
+    vec![
+        ("POWERCOST_SESSION_ID", optional(session.id.map(|id| id.to_string()))),
+        ("POWERCOST_SESSION_LABEL", optional(session.label.clone())),
+        ("POWERCOST_SESSION_CATEGORY", optional(session.category.clone())),
+        ("POWERCOST_START_TIME", session.start_time.to_string()),
+        ("POWERCOST_END_TIME", optional(session.end_time.map(|t| t.to_string()))),
+        ("POWERCOST_DURATION_SECS", session.duration_secs.to_string()),
+        ("POWERCOST_TOTAL_WH", format!("{:.3}", session.total_wh)),
+        ("POWERCOST_SURPLUS_WH", format!("{:.3}", session.surplus_wh)),
+        ("POWERCOST_SURPLUS_COST", format!("{:.4}", session.surplus_cost)),
+        ("POWERCOST_CURRENCY", currency.to_string()),
+        ("POWERCOST_AUTO_ENDED", (session.auto_ended as u8).to_string()),
+        // Lets scripts tell a `test_session_hook` run from a real session
+        ("POWERCOST_HOOK_TEST", (test as u8).to_string()),
+    ]
+}
+
+/// Made-up session ended now, for `test_session_hook`
+pub fn synthetic_session(now: i64) -> Session {
+    let mut session = Session::new(45.0, Some("Test session".to_string()));
+    session.start_time = now - 3600;
+    session.end_time = Some(now);
+    session.total_wh = 300.0;
+    session.surplus_wh = 255.0;
+    session.surplus_cost = 0.0663;
+    session.duration_secs = 3600;
+    session.avg_watts = Some(300.0);
+    session.avg_surplus_watts = Some(255.0);
+    session.cost_per_hour = Some(0.0663);
+    session
+}
+
+/// Run the hook at `path` for `session`, waiting at most `timeout`
+///
+/// Errors are hooks that could not be started; a failing or slow hook is an
+/// outcome.
+pub fn run(path: &Path, session: &Session, currency: &str, test: bool, timeout: Duration) -> Result<HookOutcome> {
+    let json = serde_json::to_vec(session).map_err(|e| Error::Serialization(e.to_string()))?;
+    let mut command = Command::new(path);
+    command
+        .envs(env_vars(session, currency, test))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        // No console window flashing up
+        command.creation_flags(0x08000000);
+    }
+
+    let started = Instant::now();
+    let mut child = command
+        .spawn()
+        .map_err(|e| Error::Config(format!("Failed to start session end hook {}: {}", path.display(), e)))?;
+
+    // A hook that doesn't read its input closes the pipe early, that's fine
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(&json);
+    }
+    // Drained on their own threads so a chatty hook can't block on a full pipe
+    let stdout = child.stdout.take().map(read_truncated);
+    let stderr = child.stderr.take().map(read_truncated);
+
+    let (status, timed_out) = loop {
+        if let Some(status) = child.try_wait()? {
+            break (Some(status), false);
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            break (None, true);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+
+    let output = |reader: Option<mpsc::Receiver<String>>| reader.and_then(|r| r.recv_timeout(OUTPUT_GRACE).ok()).unwrap_or_default();
+    Ok(HookOutcome {
+        exit_code: status.and_then(|s| s.code()),
+        timed_out,
+        duration_ms: started.elapsed().as_millis() as u64,
+        stdout: output(stdout),
+        stderr: output(stderr),
+    })
+}
+
+/// Read a pipe to its end on a new thread, keeping the first `MAX_OUTPUT_BYTES`
+fn read_truncated(mut pipe: impl Read + Send + 'static) -> mpsc::Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut kept = Vec::new();
+        let mut buf = [0u8; 1024];
+        while let Ok(n) = pipe.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            let room = MAX_OUTPUT_BYTES.saturating_sub(kept.len());
+            kept.extend_from_slice(&buf[..n.min(room)]);
+        }
+        let _ = tx.send(String::from_utf8_lossy(&kept).trim_end().to_string());
+    });
+    rx
+}
+
+/// Log how the hook of a session ended
+pub fn log_outcome(session_id: Option<i64>, outcome: &HookOutcome) {
+    if outcome.success() {
+        log::info!("Session end hook for session {:?} finished in {} ms", session_id, outcome.duration_ms);
+    } else if outcome.timed_out {
+        log::warn!("Session end hook for session {:?} killed after {} s", session_id, TIMEOUT.as_secs());
+    } else {
+        log::warn!(
+            "Session end hook for session {:?} exited with {:?}: {}",
+            session_id, outcome.exit_code, outcome.stderr
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_hook_path() {
+        assert!(validate_path(None).is_ok());
+        assert!(validate_path(Some("  ")).is_ok());
+        assert!(validate_path(Some("notify.sh")).is_err());
+        let absolute = std::env::temp_dir().join("notify.sh");
+        assert!(validate_path(absolute.to_str()).is_ok());
+    }
+
+    #[test]
+    fn test_hook_env_and_schema() {
+        let session = synthetic_session(1_705_327_200);
+        let env: std::collections::HashMap<_, _> = env_vars(&session, "EUR", true).into_iter().collect();
+        assert_eq!(env["POWERCOST_SURPLUS_WH"], "255.000");
+        assert_eq!(env["POWERCOST_SURPLUS_COST"], "0.0663");
+        assert_eq!(env["POWERCOST_END_TIME"], "1705327200");
+        assert_eq!(env["POWERCOST_SESSION_ID"], "");
+        assert_eq!(env["POWERCOST_HOOK_TEST"], "1");
+
+        // The keys documented at the top of this file
+        let json = serde_json::to_value(&session).unwrap();
+        let keys: Vec<&str> = json.as_object().unwrap().keys().map(String::as_str).collect();
+        for key in ["id", "start_time", "end_time", "total_wh", "surplus_wh", "surplus_cost", "label", "category", "duration_secs", "auto_ended"] {
+            assert!(keys.contains(&key), "{} missing from the session JSON", key);
+        }
+    }
+
+    #[cfg(unix)]
+    fn script(name: &str, body: &str) -> std::path::PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = std::env::temp_dir().join(format!("powercost-hook-{}-{}.sh", name, std::process::id()));
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_hook() {
+        let session = synthetic_session(1_705_327_200);
+        let echo = script("echo", "cat; echo \" $POWERCOST_CURRENCY\"; exit 3");
+        let outcome = run(&echo, &session, "EUR", false, TIMEOUT).unwrap();
+        assert_eq!(outcome.exit_code, Some(3));
+        assert!(!outcome.timed_out && !outcome.success());
+        let (json, currency) = outcome.stdout.rsplit_once(' ').unwrap();
+        assert_eq!(currency, "EUR");
+        let received: Session = serde_json::from_str(json).unwrap();
+        assert_eq!(received.surplus_wh, 255.0);
+
+        let slow = script("slow", "exec sleep 5");
+        let outcome = run(&slow, &session, "EUR", false, Duration::from_millis(200)).unwrap();
+        assert!(outcome.timed_out);
+        assert_eq!(outcome.exit_code, None);
+        assert!(outcome.duration_ms < 5000);
+
+        assert!(run(Path::new("/nonexistent/hook"), &session, "EUR", false, TIMEOUT).is_err());
+        let _ = std::fs::remove_file(echo);
+        let _ = std::fs::remove_file(slow);
+    }
+}
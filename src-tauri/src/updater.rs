@@ -0,0 +1,471 @@
+//! Update checking against GitHub releases
+//!
+//! The last successful check is cached in the app data directory so that
+//! launches close together don't hit the GitHub API every time, and so a
+//! previous result can still be shown when the machine is offline.
+
+use crate::core::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// GitHub API endpoint for the latest release
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/HLE-C0DE/PowerCost-Tracker/releases/latest";
+
+/// GitHub API endpoint listing all releases (paginated)
+const RELEASES_URL: &str = "https://api.github.com/repos/HLE-C0DE/PowerCost-Tracker/releases";
+
+/// Releases requested per page (GitHub maximum)
+const RELEASES_PER_PAGE: usize = 100;
+
+/// Safety limit on the number of pages fetched
+const MAX_RELEASE_PAGES: usize = 10;
+
+/// Maximum size of the returned changelog (bytes)
+pub const MAX_CHANGELOG_BYTES: usize = 200 * 1024;
+
+/// Minimum time between two network checks (20 hours)
+pub const CHECK_INTERVAL_SECS: i64 = 20 * 3600;
+
+/// First retry delay after a failed check
+const BACKOFF_BASE_SECS: i64 = 60;
+/// Upper bound for the retry delay
+const BACKOFF_MAX_SECS: i64 = 6 * 3600;
+
+/// Result of an update check, as returned to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateCheckResult {
+    pub update_available: bool,
+    pub current_version: String,
+    pub latest_version: String,
+    pub release_url: String,
+    pub release_notes: String,
+    /// True when this result comes from the cache because the network check failed
+    #[serde(default)]
+    pub stale: bool,
+    /// Unix timestamp of the network check that produced this result
+    #[serde(default)]
+    pub checked_at: i64,
+}
+
+/// Compare two semver strings, returns true if `latest` is newer than `current`
+pub fn version_is_newer(current: &str, latest: &str) -> bool {
+    let parse = |v: &str| -> (u64, u64, u64) {
+        let v = v.trim_start_matches('v');
+        let parts: Vec<u64> = v.split('.').filter_map(|p| p.parse().ok()).collect();
+        (
+            parts.first().copied().unwrap_or(0),
+            parts.get(1).copied().unwrap_or(0),
+            parts.get(2).copied().unwrap_or(0),
+        )
+    };
+    parse(latest) > parse(current)
+}
+
+/// Current application version
+pub fn current_version() -> String {
+    env!("CARGO_PKG_VERSION").to_string()
+}
+
+/// Build the HTTP client used for GitHub API requests
+pub(crate) fn http_client() -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .user_agent(format!("PowerCost-Tracker/{}", current_version()))
+        .timeout(std::time::Duration::from_secs(15))
+        .build()
+        .map_err(|e| Error::Network(format!("Failed to create HTTP client: {}", e)))
+}
+
+/// Query GitHub for the latest release (always hits the network)
+pub async fn fetch_latest_release() -> Result<UpdateCheckResult> {
+    let current_version = current_version();
+    let client = http_client()?;
+
+    let resp = client
+        .get(LATEST_RELEASE_URL)
+        .send()
+        .await
+        .map_err(|e| Error::Network(format!("Failed to fetch releases: {}", e)))?;
+
+    if !resp.status().is_success() {
+        return Err(Error::Network(format!("GitHub API returned status {}", resp.status())));
+    }
+
+    let json: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| Error::Serialization(format!("Failed to parse response: {}", e)))?;
+
+    let tag = json["tag_name"].as_str().unwrap_or("").to_string();
+    let release_url = json["html_url"].as_str().unwrap_or("").to_string();
+    let release_notes = json["body"].as_str().unwrap_or("").to_string();
+
+    Ok(UpdateCheckResult {
+        update_available: version_is_newer(&current_version, &tag),
+        current_version,
+        latest_version: tag.trim_start_matches('v').to_string(),
+        release_url,
+        release_notes,
+        stale: false,
+        checked_at: chrono::Utc::now().timestamp(),
+    })
+}
+
+/// Changelog covering every release newer than a given version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangelogResult {
+    /// Version the changelog starts after
+    pub since_version: String,
+    /// Markdown with one `## vX.Y.Z` section per release, newest first
+    pub markdown: String,
+    /// True when this result comes from the cache because the network request failed
+    #[serde(default)]
+    pub stale: bool,
+}
+
+/// Remove `<!-- ... -->` comments (GitHub release templates are full of them)
+pub fn strip_html_comments(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("<!--") {
+        result.push_str(&rest[..start]);
+        match rest[start..].find("-->") {
+            Some(end) => rest = &rest[start + end + 3..],
+            // Unterminated comment: drop the remainder
+            None => return result,
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Build the changelog markdown from `(tag, body)` pairs, keeping releases newer than `since_version`
+pub fn build_changelog(releases: &[(String, String)], since_version: &str) -> String {
+    let mut newer: Vec<&(String, String)> = releases
+        .iter()
+        .filter(|(tag, _)| version_is_newer(since_version, tag))
+        .collect();
+    // Newest first
+    newer.sort_by(|a, b| {
+        if version_is_newer(&a.0, &b.0) {
+            std::cmp::Ordering::Greater
+        } else if version_is_newer(&b.0, &a.0) {
+            std::cmp::Ordering::Less
+        } else {
+            std::cmp::Ordering::Equal
+        }
+    });
+
+    let mut markdown = String::new();
+    for (tag, body) in newer {
+        let section = format!(
+            "## v{}\n\n{}\n\n",
+            tag.trim_start_matches('v'),
+            strip_html_comments(body).trim()
+        );
+        if markdown.len() + section.len() > MAX_CHANGELOG_BYTES {
+            // Cut on a char boundary so the result stays valid UTF-8
+            let mut end = MAX_CHANGELOG_BYTES - markdown.len();
+            while !section.is_char_boundary(end) {
+                end -= 1;
+            }
+            markdown.push_str(&section[..end]);
+            break;
+        }
+        markdown.push_str(&section);
+    }
+    markdown.trim_end().to_string()
+}
+
+/// Fetch `(tag, body)` for every published release, following pagination
+async fn fetch_all_releases() -> Result<Vec<(String, String)>> {
+    let client = http_client()?;
+    let mut releases = Vec::new();
+
+    for page in 1..=MAX_RELEASE_PAGES {
+        let resp = client
+            .get(RELEASES_URL)
+            .query(&[("per_page", RELEASES_PER_PAGE), ("page", page)])
+            .send()
+            .await
+            .map_err(|e| Error::Network(format!("Failed to fetch releases: {}", e)))?;
+
+        if !resp.status().is_success() {
+            return Err(Error::Network(format!("GitHub API returned status {}", resp.status())));
+        }
+
+        let json: Vec<serde_json::Value> = resp
+            .json()
+            .await
+            .map_err(|e| Error::Serialization(format!("Failed to parse response: {}", e)))?;
+        let count = json.len();
+
+        releases.extend(
+            json.iter()
+                .filter(|r| !r["draft"].as_bool().unwrap_or(false))
+                .map(|r| {
+                    (
+                        r["tag_name"].as_str().unwrap_or("").to_string(),
+                        r["body"].as_str().unwrap_or("").to_string(),
+                    )
+                }),
+        );
+
+        if count < RELEASES_PER_PAGE {
+            break;
+        }
+    }
+
+    Ok(releases)
+}
+
+/// Fetch the changelog since `since_version`, falling back to the disk cache when offline
+///
+/// The raw release list is cached rather than the markdown, so that a changelog
+/// for any version can be rebuilt offline.
+pub async fn get_changelog(since_version: &str) -> Result<ChangelogResult> {
+    let cache_path = cache_file_path("changelog_cache.json");
+
+    let (releases, stale) = match fetch_all_releases().await {
+        Ok(releases) => {
+            if let Some(ref path) = cache_path {
+                if let Ok(content) = serde_json::to_string(&releases) {
+                    if let Err(e) = std::fs::write(path, content) {
+                        log::warn!("Failed to write changelog cache: {}", e);
+                    }
+                }
+            }
+            (releases, false)
+        }
+        Err(e) => {
+            log::warn!("Changelog fetch failed: {}", e);
+            let cached = cache_path
+                .and_then(|p| std::fs::read_to_string(p).ok())
+                .and_then(|content| serde_json::from_str::<Vec<(String, String)>>(&content).ok());
+            match cached {
+                Some(releases) => (releases, true),
+                None => return Err(e),
+            }
+        }
+    };
+
+    Ok(ChangelogResult {
+        since_version: since_version.to_string(),
+        markdown: build_changelog(&releases, since_version),
+        stale,
+    })
+}
+
+/// Get the path of a cache file in the app data directory
+pub(crate) fn cache_file_path(file_name: &str) -> Option<PathBuf> {
+    let app_dir = dirs::data_dir()?.join("powercost-tracker");
+    std::fs::create_dir_all(&app_dir).ok()?;
+    Some(app_dir.join(file_name))
+}
+
+/// Tracks the cached update result and retry backoff for the running session
+pub struct UpdateChecker {
+    /// Where the last successful result is persisted (None = memory only)
+    cache_path: Option<PathBuf>,
+    /// Last successful result
+    cached: Option<UpdateCheckResult>,
+    /// Number of failed network checks since the last success
+    consecutive_failures: u32,
+    /// Unix timestamp before which no new network attempt should be made
+    retry_not_before: i64,
+}
+
+impl UpdateChecker {
+    /// Create a checker backed by the default cache file
+    pub fn new() -> Self {
+        Self::with_cache_path(cache_file_path("update_cache.json"))
+    }
+
+    /// Create a checker backed by a specific cache file (None = no persistence)
+    pub fn with_cache_path(cache_path: Option<PathBuf>) -> Self {
+        let cached = cache_path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|content| serde_json::from_str::<UpdateCheckResult>(&content).ok());
+
+        Self {
+            cache_path,
+            cached,
+            consecutive_failures: 0,
+            retry_not_before: 0,
+        }
+    }
+
+    /// Cached result if the last check is recent enough to skip the network
+    pub fn fresh_cached(&self, now: i64) -> Option<UpdateCheckResult> {
+        let cached = self.cached.as_ref()?;
+        // A cache written by another app version says nothing about this one
+        if cached.current_version != current_version() {
+            return None;
+        }
+        let age = now - cached.checked_at;
+        if (0..CHECK_INTERVAL_SECS).contains(&age) {
+            Some(cached.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Cached result flagged as stale (used when the network check fails)
+    pub fn stale_cached(&self) -> Option<UpdateCheckResult> {
+        let mut result = self.cached.clone()?;
+        let current = current_version();
+        result.update_available = version_is_newer(&current, &result.latest_version);
+        result.current_version = current;
+        result.stale = true;
+        Some(result)
+    }
+
+    /// Whether the backoff window allows a new network attempt
+    pub fn can_attempt(&self, now: i64) -> bool {
+        now >= self.retry_not_before
+    }
+
+    /// Delay before the next attempt is allowed, based on the failure count
+    pub fn retry_delay_secs(&self) -> i64 {
+        if self.consecutive_failures == 0 {
+            return 0;
+        }
+        let exponent = (self.consecutive_failures - 1).min(16);
+        (BACKOFF_BASE_SECS << exponent).min(BACKOFF_MAX_SECS)
+    }
+
+    /// Record a successful network check and persist it
+    pub fn record_success(&mut self, result: &UpdateCheckResult) {
+        self.consecutive_failures = 0;
+        self.retry_not_before = 0;
+        self.cached = Some(result.clone());
+
+        if let Some(ref path) = self.cache_path {
+            match serde_json::to_string(result) {
+                Ok(content) => {
+                    if let Err(e) = std::fs::write(path, content) {
+                        log::warn!("Failed to write update cache: {}", e);
+                    }
+                }
+                Err(e) => log::warn!("Failed to serialize update cache: {}", e),
+            }
+        }
+    }
+
+    /// Record a failed network check, returns the delay before the next attempt
+    pub fn record_failure(&mut self, now: i64) -> i64 {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        let delay = self.retry_delay_secs();
+        self.retry_not_before = now + delay;
+        delay
+    }
+}
+
+impl Default for UpdateChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result_checked_at(checked_at: i64, latest: &str) -> UpdateCheckResult {
+        UpdateCheckResult {
+            update_available: false,
+            current_version: current_version(),
+            latest_version: latest.to_string(),
+            release_url: String::new(),
+            release_notes: String::new(),
+            stale: false,
+            checked_at,
+        }
+    }
+
+    #[test]
+    fn test_version_comparison() {
+        assert!(version_is_newer("1.2.0", "v1.3.0"));
+        assert!(version_is_newer("1.2.0", "1.2.1"));
+        assert!(!version_is_newer("1.2.0", "1.2.0"));
+        assert!(!version_is_newer("1.10.0", "1.9.9"));
+    }
+
+    #[test]
+    fn test_cache_expiry() {
+        let now = 1_700_000_000;
+        let mut checker = UpdateChecker::with_cache_path(None);
+        assert!(checker.fresh_cached(now).is_none());
+
+        checker.record_success(&result_checked_at(now, "1.0.0"));
+        assert!(checker.fresh_cached(now + 3600).is_some());
+        assert!(checker.fresh_cached(now + CHECK_INTERVAL_SECS - 1).is_some());
+        assert!(checker.fresh_cached(now + CHECK_INTERVAL_SECS).is_none());
+
+        // Clock moved backwards: don't trust the cache
+        assert!(checker.fresh_cached(now - 10).is_none());
+    }
+
+    #[test]
+    fn test_stale_result_recomputes_availability() {
+        let mut checker = UpdateChecker::with_cache_path(None);
+        assert!(checker.stale_cached().is_none());
+
+        checker.record_success(&result_checked_at(0, "999.0.0"));
+        let stale = checker.stale_cached().unwrap();
+        assert!(stale.stale);
+        assert!(stale.update_available);
+    }
+
+    #[test]
+    fn test_strip_html_comments() {
+        assert_eq!(strip_html_comments("a<!-- hidden -->b"), "ab");
+        assert_eq!(strip_html_comments("<!-- x -->a<!-- y\n -->b"), "ab");
+        assert_eq!(strip_html_comments("keep <!-- unterminated"), "keep ");
+        assert_eq!(strip_html_comments("no comments"), "no comments");
+    }
+
+    #[test]
+    fn test_build_changelog() {
+        let releases = vec![
+            ("v1.1.0".to_string(), "Second".to_string()),
+            ("v1.0.0".to_string(), "First".to_string()),
+            ("v1.2.0".to_string(), "<!-- template -->Third".to_string()),
+        ];
+
+        let markdown = build_changelog(&releases, "1.0.0");
+        assert_eq!(markdown, "## v1.2.0\n\nThird\n\n## v1.1.0\n\nSecond");
+        assert!(build_changelog(&releases, "1.2.0").is_empty());
+    }
+
+    #[test]
+    fn test_changelog_size_limit() {
+        let body = "\u{00E9}".repeat(MAX_CHANGELOG_BYTES);
+        let releases = vec![("v2.0.0".to_string(), body)];
+        let markdown = build_changelog(&releases, "1.0.0");
+        assert!(markdown.len() <= MAX_CHANGELOG_BYTES);
+        assert!(markdown.starts_with("## v2.0.0"));
+    }
+
+    #[test]
+    fn test_exponential_backoff() {
+        let now = 1_000;
+        let mut checker = UpdateChecker::with_cache_path(None);
+        assert!(checker.can_attempt(now));
+
+        assert_eq!(checker.record_failure(now), 60);
+        assert!(!checker.can_attempt(now + 59));
+        assert!(checker.can_attempt(now + 60));
+        assert_eq!(checker.record_failure(now), 120);
+        assert_eq!(checker.record_failure(now), 240);
+
+        for _ in 0..20 {
+            checker.record_failure(now);
+        }
+        assert_eq!(checker.retry_delay_secs(), BACKOFF_MAX_SECS);
+
+        checker.record_success(&result_checked_at(now, "1.0.0"));
+        assert!(checker.can_attempt(now));
+        assert_eq!(checker.retry_delay_secs(), 0);
+    }
+}
@@ -0,0 +1,244 @@
+//! Scheduled report push to a webhook
+//!
+//! Once a daily or weekly period is complete, its report is POSTed as JSON to
+//! the configured URL. The end date of the last delivered period is kept in
+//! the app data directory so each period is sent once, even across restarts.
+
+use crate::core::{Error, Result, WebhookConfig};
+use crate::report::{Report, ReportPeriod};
+use crate::updater::{cache_file_path, http_client};
+use chrono::{NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Interval between two schedule checks of the background task
+pub const CHECK_INTERVAL_SECS: u64 = 5 * 60;
+
+/// Delays before the second and third delivery attempts
+const RETRY_DELAYS_SECS: [u64; 2] = [10, 60];
+
+/// Pause after a delivery failed on every attempt
+const FAILURE_PAUSE_SECS: i64 = 30 * 60;
+
+/// Persisted delivery state
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct DeliveryState {
+    /// End date (YYYY-MM-DD) of the last period delivered
+    last_period_end: Option<String>,
+    /// Unix timestamp of the last successful delivery
+    last_success_at: Option<i64>,
+}
+
+/// Check the webhook settings, returns the report period
+pub fn validate_config(config: &WebhookConfig) -> std::result::Result<ReportPeriod, String> {
+    let period = ReportPeriod::parse(&config.schedule)
+        .ok_or_else(|| format!("Unknown webhook schedule: {}", config.schedule))?;
+    if config.hour > 23 {
+        return Err(format!("Invalid webhook hour: {}", config.hour));
+    }
+    if config.enabled && !(config.url.starts_with("https://") || config.url.starts_with("http://")) {
+        return Err("Webhook URL must start with http:// or https://".to_string());
+    }
+    Ok(period)
+}
+
+/// Tracks deliveries and failures for the running session
+pub struct WebhookNotifier {
+    /// Where the delivery state is persisted (None = memory only)
+    state_path: Option<PathBuf>,
+    state: DeliveryState,
+    /// Failed deliveries since startup (after retries)
+    failures: u64,
+    last_error: Option<String>,
+    /// Unix timestamp before which no new delivery should be attempted
+    retry_not_before: i64,
+}
+
+impl WebhookNotifier {
+    /// Create a notifier backed by the default state file
+    pub fn new() -> Self {
+        Self::with_state_path(cache_file_path("webhook_state.json"))
+    }
+
+    /// Create a notifier backed by a specific state file (None = no persistence)
+    pub fn with_state_path(state_path: Option<PathBuf>) -> Self {
+        let state = state_path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            state_path,
+            state,
+            failures: 0,
+            last_error: None,
+            retry_not_before: 0,
+        }
+    }
+
+    /// Whether the period before `now` is complete, not yet delivered, and the send hour has come
+    pub fn is_due(&self, period: ReportPeriod, hour: u32, now: NaiveDateTime) -> bool {
+        if now.hour() < hour {
+            return false;
+        }
+        let (_, end) = period.range(now.date());
+        let end = end.format("%Y-%m-%d").to_string();
+        !matches!(self.state.last_period_end.as_deref(), Some(last) if last >= end.as_str())
+    }
+
+    /// Whether the pause after a failed delivery is over
+    pub fn can_attempt(&self, now: i64) -> bool {
+        now >= self.retry_not_before
+    }
+
+    /// Record a delivered report and persist it
+    pub fn record_success(&mut self, report: &Report, now: i64) {
+        self.state.last_period_end = Some(report.end_date.clone());
+        self.state.last_success_at = Some(now);
+        self.last_error = None;
+        self.retry_not_before = 0;
+
+        if let Some(ref path) = self.state_path {
+            match serde_json::to_string(&self.state) {
+                Ok(content) => {
+                    if let Err(e) = std::fs::write(path, content) {
+                        log::warn!("Failed to write webhook state: {}", e);
+                    }
+                }
+                Err(e) => log::warn!("Failed to serialize webhook state: {}", e),
+            }
+        }
+    }
+
+    /// Record a delivery that failed after all retries
+    pub fn record_failure(&mut self, error: &Error, now: i64) {
+        self.failures = self.failures.saturating_add(1);
+        self.last_error = Some(error.to_string());
+        self.retry_not_before = now + FAILURE_PAUSE_SECS;
+    }
+
+    pub fn failures(&self) -> u64 {
+        self.failures
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.clone()
+    }
+
+    pub fn last_success_at(&self) -> Option<i64> {
+        self.state.last_success_at
+    }
+}
+
+impl Default for WebhookNotifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// POST a report once
+pub async fn post_report(url: &str, bearer_token: Option<&str>, report: &Report) -> Result<()> {
+    let client = http_client()?;
+    let mut request = client.post(url).json(report);
+    if let Some(token) = bearer_token {
+        request = request.bearer_auth(token);
+    }
+
+    let resp = request
+        .send()
+        .await
+        .map_err(|e| Error::Network(format!("Webhook request failed: {}", e)))?;
+
+    if !resp.status().is_success() {
+        return Err(Error::Network(format!("Webhook returned status {}", resp.status())));
+    }
+    Ok(())
+}
+
+/// POST a report, retrying transient failures
+pub async fn send_with_retry(url: &str, bearer_token: Option<&str>, report: &Report) -> Result<()> {
+    let mut result = post_report(url, bearer_token, report).await;
+    for delay in RETRY_DELAYS_SECS {
+        let Err(ref e) = result else { break };
+        log::warn!("Webhook delivery failed, retrying in {}s: {}", delay, e);
+        tokio::time::sleep(std::time::Duration::from_secs(delay)).await;
+        result = post_report(url, bearer_token, report).await;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(date: &str, hour: u32) -> NaiveDateTime {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap().and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    fn report_ending(end_date: &str) -> Report {
+        let mut report = crate::report::summarize(
+            ReportPeriod::Weekly,
+            ReportPeriod::Weekly.range(NaiveDate::from_ymd_opt(2024, 3, 13).unwrap()),
+            &[],
+            &[],
+            &[],
+            "EUR",
+            0,
+        );
+        report.end_date = end_date.to_string();
+        report
+    }
+
+    #[test]
+    fn test_due_once_per_period() {
+        let mut notifier = WebhookNotifier::with_state_path(None);
+        // Monday 2024-03-11: the week ending Sunday 03-10 is complete
+        assert!(!notifier.is_due(ReportPeriod::Weekly, 9, at("2024-03-11", 8)));
+        assert!(notifier.is_due(ReportPeriod::Weekly, 9, at("2024-03-11", 9)));
+
+        notifier.record_success(&report_ending("2024-03-10"), 0);
+        assert!(!notifier.is_due(ReportPeriod::Weekly, 9, at("2024-03-11", 20)));
+        // Later in the week: the same week is not sent again
+        assert!(!notifier.is_due(ReportPeriod::Weekly, 9, at("2024-03-14", 10)));
+        assert!(notifier.is_due(ReportPeriod::Weekly, 9, at("2024-03-18", 10)));
+
+        // Switching to daily: yesterday was not delivered yet
+        assert!(notifier.is_due(ReportPeriod::Daily, 9, at("2024-03-12", 10)));
+    }
+
+    #[test]
+    fn test_failure_keeps_period_due() {
+        let mut notifier = WebhookNotifier::with_state_path(None);
+        notifier.record_failure(&Error::Network("timeout".to_string()), 1000);
+        assert_eq!(notifier.failures(), 1);
+        assert!(!notifier.can_attempt(1000 + FAILURE_PAUSE_SECS - 1));
+        assert!(notifier.can_attempt(1000 + FAILURE_PAUSE_SECS));
+        assert!(notifier.last_error().unwrap().contains("timeout"));
+        assert!(notifier.is_due(ReportPeriod::Daily, 0, at("2024-03-12", 0)));
+
+        notifier.record_success(&report_ending("2024-03-11"), 42);
+        assert_eq!(notifier.last_error(), None);
+        assert_eq!(notifier.last_success_at(), Some(42));
+        assert!(notifier.can_attempt(0));
+        assert_eq!(notifier.failures(), 1);
+    }
+
+    #[test]
+    fn test_validate_config() {
+        let mut config = WebhookConfig::default();
+        assert_eq!(validate_config(&config), Ok(ReportPeriod::Weekly));
+
+        config.enabled = true;
+        assert!(validate_config(&config).is_err());
+        config.url = "https://example.com/hook".to_string();
+        assert!(validate_config(&config).is_ok());
+
+        config.schedule = "hourly".to_string();
+        assert!(validate_config(&config).is_err());
+        config.schedule = "daily".to_string();
+        config.hour = 24;
+        assert!(validate_config(&config).is_err());
+    }
+}
@@ -0,0 +1,446 @@
+//! Floating widget window: platform capabilities and update payload
+//!
+//! Wayland compositors ignore absolute positioning and usually refuse
+//! always-on-top for regular toplevels, so the widget has to be set up
+//! differently there. X11, Windows and macOS support everything.
+//!
+//! The critical loop pushes the widget its configured items in a
+//! `widget-update` event, the widget does not poll. Its hover tooltip is
+//! fetched on demand and built from the same caches. While a session is
+//! active the update can carry the session items instead (`widget.session_mode`),
+//! and flips back on the first update after the session ends.
+
+use crate::core::format::{format_cost, format_duration, format_number};
+use crate::core::{CriticalMetrics, SystemMetrics, WidgetConfig, WidgetSessionMode};
+use crate::db::DailyStats;
+use crate::i18n::I18n;
+use serde::Serialize;
+
+/// Display session the app runs in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionType {
+    Windows,
+    Macos,
+    X11,
+    Wayland,
+    Unknown,
+}
+
+/// What the widget window can do on the current platform
+#[derive(Debug, Clone, Serialize)]
+pub struct WidgetCapabilities {
+    pub session_type: SessionType,
+    /// Window can be kept above other windows
+    pub always_on_top: bool,
+    /// Window can be placed at absolute screen coordinates
+    pub positioning: bool,
+    /// Window can be hidden from the taskbar
+    pub skip_taskbar: bool,
+    /// A layer-shell overlay is used (true overlay on Wayland)
+    pub layer_shell: bool,
+    /// Explanation shown when the widget falls back to a regular window
+    pub warning: Option<String>,
+}
+
+/// Classify a Linux session from `XDG_SESSION_TYPE`, `WAYLAND_DISPLAY` and `DISPLAY`
+pub fn classify_linux_session(
+    xdg_session_type: Option<&str>,
+    wayland_display: Option<&str>,
+    x11_display: Option<&str>,
+) -> SessionType {
+    match xdg_session_type.map(|s| s.to_ascii_lowercase()).as_deref() {
+        Some("wayland") => return SessionType::Wayland,
+        Some("x11") => return SessionType::X11,
+        _ => {}
+    }
+    if wayland_display.is_some_and(|d| !d.is_empty()) {
+        SessionType::Wayland
+    } else if x11_display.is_some_and(|d| !d.is_empty()) {
+        SessionType::X11
+    } else {
+        SessionType::Unknown
+    }
+}
+
+/// Detect the current session type
+pub fn detect_session_type() -> SessionType {
+    if cfg!(target_os = "windows") {
+        SessionType::Windows
+    } else if cfg!(target_os = "macos") {
+        SessionType::Macos
+    } else {
+        let xdg = std::env::var("XDG_SESSION_TYPE").ok();
+        let wayland = std::env::var("WAYLAND_DISPLAY").ok();
+        let x11 = std::env::var("DISPLAY").ok();
+        classify_linux_session(xdg.as_deref(), wayland.as_deref(), x11.as_deref())
+    }
+}
+
+/// Capabilities for a session type
+///
+/// Layer-shell isn't exposed by Tauri's window builder, so Wayland always
+/// gets the regular undecorated window fallback for now.
+pub fn capabilities_for(session_type: SessionType) -> WidgetCapabilities {
+    match session_type {
+        SessionType::Wayland => WidgetCapabilities {
+            session_type,
+            always_on_top: false,
+            positioning: false,
+            skip_taskbar: false,
+            layer_shell: false,
+            warning: Some(
+                "Wayland does not allow apps to position or pin windows; the widget opens as a regular window"
+                    .to_string(),
+            ),
+        },
+        _ => WidgetCapabilities {
+            session_type,
+            always_on_top: true,
+            positioning: true,
+            skip_taskbar: true,
+            layer_shell: false,
+            warning: None,
+        },
+    }
+}
+
+/// Capabilities for the current platform
+pub fn capabilities() -> WidgetCapabilities {
+    capabilities_for(detect_session_type())
+}
+
+/// Payload of the `widget-update` event
+///
+/// Only the values of the configured `display_items` are sent, in `items` order,
+/// or those of `SESSION_ITEMS` in session mode.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct WidgetUpdate {
+    /// Configured items the widget knows, in display order
+    pub items: Vec<String>,
+    /// Whether `items` are the session items
+    pub session_mode: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub power_watts: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_cost: Option<f64>,
+    /// `current_cost` formatted like on the dashboard
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_cost_text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_usage_percent: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gpu_usage_percent: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ram_usage_percent: Option<f64>,
+    /// CPU temperature, the GPU's when the CPU has none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature_celsius: Option<f64>,
+    /// Surplus cost of the active session
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_surplus_cost: Option<f64>,
+    /// `session_surplus_cost` formatted like on the dashboard
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_surplus_cost_text: Option<String>,
+    /// Time since the active session started
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_elapsed_secs: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_label: Option<String>,
+    pub timestamp: i64,
+}
+
+/// Items the widget can display
+pub const WIDGET_ITEMS: [&str; 6] = ["power", "cost", "cpu", "gpu", "ram", "temp"];
+
+/// Items shown in session mode, in display order
+pub const SESSION_ITEMS: [&str; 3] = ["session_cost", "session_time", "power"];
+
+/// Whether the widget shows the session items, unknown modes behaving like "auto"
+pub fn session_mode_active(mode: &WidgetSessionMode, session_active: bool) -> bool {
+    match mode {
+        WidgetSessionMode::Always => true,
+        WidgetSessionMode::Never => false,
+        WidgetSessionMode::Auto | WidgetSessionMode::Other(_) => session_active,
+    }
+}
+
+/// Items of an update in display order, costs left out when `show_cost` is off
+pub fn select_items(config: &WidgetConfig, session_mode: bool) -> Vec<String> {
+    let is_cost = |item: &str| item == "cost" || item == "session_cost";
+    let items: Vec<&str> = if session_mode {
+        SESSION_ITEMS.to_vec()
+    } else {
+        config.display_items.iter().map(String::as_str).filter(|item| WIDGET_ITEMS.contains(item)).collect()
+    };
+    items.into_iter().filter(|item| !is_cost(item) || config.show_cost).map(str::to_string).collect()
+}
+
+impl WidgetUpdate {
+    /// Payload for the configured items, RAM and temperatures coming from the last detailed update
+    pub fn build(config: &WidgetConfig, critical: &CriticalMetrics, system: Option<&SystemMetrics>) -> Self {
+        let session = critical.active_session.as_ref();
+        let session_mode = session_mode_active(&config.session_mode, session.is_some());
+        let items = select_items(config, session_mode);
+        let shows = |item: &str| items.iter().any(|i| i == item);
+
+        Self {
+            power_watts: shows("power").then_some(critical.power_watts),
+            current_cost: shows("cost").then_some(critical.current_cost),
+            current_cost_text: critical.display.as_ref().filter(|_| shows("cost")).map(|d| d.current_cost.clone()),
+            cpu_usage_percent: shows("cpu").then_some(critical.cpu_usage_percent),
+            gpu_usage_percent: critical.gpu_usage_percent.filter(|_| shows("gpu")),
+            ram_usage_percent: system.map(|s| s.memory.usage_percent).filter(|_| shows("ram")),
+            temperature_celsius: system
+                .and_then(|s| s.cpu.temperature_celsius.or_else(|| s.gpu.as_ref()?.temperature_celsius))
+                .filter(|_| shows("temp")),
+            session_surplus_cost: session.map(|s| s.surplus_cost).filter(|_| shows("session_cost")),
+            session_surplus_cost_text: critical
+                .display
+                .as_ref()
+                .and_then(|d| d.session_surplus_cost.clone())
+                .filter(|_| shows("session_cost")),
+            session_elapsed_secs: session.map(|s| (critical.timestamp - s.start_time).max(0)).filter(|_| shows("session_time")),
+            session_label: session.filter(|_| session_mode).and_then(|s| s.label.clone()),
+            timestamp: critical.timestamp,
+            session_mode,
+            items,
+        }
+    }
+}
+
+/// Localized summary shown when hovering the widget
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WidgetTooltip {
+    /// Today's energy and cost
+    pub today: String,
+    /// Duration and cost of the active session
+    pub session: Option<String>,
+    /// Power source, measured or estimated
+    pub source: String,
+    /// Name and price of the rate in effect
+    pub rate: String,
+    /// Age of the last reading
+    pub freshness: String,
+    pub is_estimated: bool,
+    pub stale: bool,
+}
+
+impl WidgetTooltip {
+    /// Tooltip from the cached critical metrics (already stamped) and today's daily stats
+    pub fn build(
+        i18n: &I18n,
+        critical: &CriticalMetrics,
+        today: Option<&DailyStats>,
+        rate_name_key: &str,
+        rate_per_kwh: f64,
+        currency_symbol: &str,
+    ) -> Self {
+        let lang = i18n.current_language();
+        let kwh = format_number(today.map_or(0.0, |d| d.total_wh) / 1000.0, 2, lang);
+        let today = match today.and_then(|d| d.total_cost) {
+            Some(cost) => i18n.format(
+                "widget.tooltip.today",
+                &[("kwh", kwh), ("cost", format_cost(cost, currency_symbol, lang))],
+            ),
+            None => i18n.format("widget.tooltip.today_energy", &[("kwh", kwh)]),
+        };
+        let session = critical.active_session.as_ref().map(|_| {
+            i18n.format(
+                "widget.tooltip.session",
+                &[
+                    ("duration", format_duration(critical.session_duration_secs as i64, lang)),
+                    ("cost", format_cost(critical.current_cost, currency_symbol, lang)),
+                ],
+            )
+        });
+        let source_key = if critical.is_estimated { "widget.tooltip.source_estimated" } else { "widget.tooltip.source_measured" };
+        let age = format_duration((critical.freshness.age_ms / 1000) as i64, lang);
+        let freshness_key = if critical.freshness.stale { "widget.tooltip.stale" } else { "widget.tooltip.updated" };
+
+        Self {
+            today,
+            session,
+            source: i18n.format(source_key, &[("source", critical.source.clone())]),
+            rate: i18n.format(
+                "widget.tooltip.rate",
+                &[("name", i18n.get(rate_name_key)), ("rate", format_cost(rate_per_kwh, currency_symbol, lang))],
+            ),
+            freshness: i18n.format(freshness_key, &[("age", age)]),
+            is_estimated: critical.is_estimated,
+            stale: critical.freshness.stale,
+        }
+    }
+}
+
+/// Whether a widget update is due on a critical tick, `since_last_ms` None before the first one
+///
+/// Half a tick of slack keeps a widget rate equal to the critical rate from
+/// skipping every other tick on timer jitter.
+pub fn update_due(since_last_ms: Option<u64>, widget_rate_ms: u64, tick_ms: u64) -> bool {
+    since_last_ms.map_or(true, |elapsed| elapsed + tick_ms / 2 >= widget_rate_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Freshness, Session};
+
+    #[test]
+    fn test_classify_linux_session() {
+        assert_eq!(classify_linux_session(Some("wayland"), Some("wayland-0"), Some(":0")), SessionType::Wayland);
+        // XDG_SESSION_TYPE wins over leftover display variables
+        assert_eq!(classify_linux_session(Some("x11"), Some("wayland-0"), None), SessionType::X11);
+        assert_eq!(classify_linux_session(None, Some("wayland-0"), Some(":0")), SessionType::Wayland);
+        assert_eq!(classify_linux_session(Some("tty"), Some(""), Some(":0")), SessionType::X11);
+        assert_eq!(classify_linux_session(Some("tty"), None, None), SessionType::Unknown);
+    }
+
+    #[test]
+    fn test_wayland_capabilities() {
+        let caps = capabilities_for(SessionType::Wayland);
+        assert!(!caps.always_on_top);
+        assert!(!caps.positioning);
+        assert!(caps.warning.is_some());
+
+        let caps = capabilities_for(SessionType::Windows);
+        assert!(caps.always_on_top && caps.positioning && caps.skip_taskbar);
+        assert!(caps.warning.is_none());
+    }
+
+    fn critical_metrics() -> CriticalMetrics {
+        CriticalMetrics {
+            power_watts: 180.0,
+            avg_power_watts: 150.0,
+            cpu_usage_percent: 40.0,
+            gpu_usage_percent: Some(75.0),
+            gpu_power_watts: None,
+            cumulative_wh: 12.0,
+            current_cost: 0.003,
+            hourly_cost_estimate: 0.03,
+            daily_cost_estimate: 0.72,
+            monthly_cost_estimate: 21.6,
+            session_duration_secs: 300,
+            active_session: None,
+            source: "rapl".to_string(),
+            is_estimated: false,
+            timestamp: 1_700_000_000,
+            billing_summary: None,
+            baseline_watts: None,
+            surplus_watts: None,
+            timestamp_ms: 1_700_000_000_000,
+            display: None,
+            freshness: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_widget_update_honors_items() {
+        let mut config = WidgetConfig {
+            display_items: ["gpu", "power", "fans", "cost"].iter().map(|s| s.to_string()).collect(),
+            show_cost: false,
+            ..Default::default()
+        };
+
+        let update = WidgetUpdate::build(&config, &critical_metrics(), None);
+        assert_eq!(update.items, ["gpu", "power"]);
+        let json = serde_json::to_value(&update).unwrap();
+        let mut keys: Vec<&str> = json.as_object().unwrap().keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["gpu_usage_percent", "items", "power_watts", "session_mode", "timestamp"]);
+        assert_eq!(json["gpu_usage_percent"], 75.0);
+
+        // Items without a value yet (no detailed update) are left out
+        config.display_items = vec!["ram".to_string(), "temp".to_string()];
+        let update = WidgetUpdate::build(&config, &critical_metrics(), None);
+        assert_eq!(update.items, ["ram", "temp"]);
+        assert_eq!((update.ram_usage_percent, update.temperature_celsius, update.power_watts), (None, None, None));
+    }
+
+    #[test]
+    fn test_session_item_selection() {
+        let mut config = WidgetConfig {
+            display_items: ["cpu", "cost"].iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+        assert!(session_mode_active(&WidgetSessionMode::Auto, true));
+        assert!(!session_mode_active(&WidgetSessionMode::Auto, false));
+        assert!(session_mode_active(&WidgetSessionMode::Always, false));
+        assert!(!session_mode_active(&WidgetSessionMode::Never, true));
+        assert!(session_mode_active(&WidgetSessionMode::Other("later".to_string()), true));
+
+        assert_eq!(select_items(&config, true), SESSION_ITEMS);
+        assert_eq!(select_items(&config, false), ["cpu", "cost"]);
+        config.show_cost = false;
+        assert_eq!(select_items(&config, true), ["session_time", "power"]);
+        assert_eq!(select_items(&config, false), ["cpu"]);
+    }
+
+    #[test]
+    fn test_widget_session_mode_follows_session() {
+        let config = WidgetConfig::default();
+        let mut critical = critical_metrics();
+        let mut session = Session::new(40.0, Some("Render".to_string()));
+        session.start_time = critical.timestamp - 90;
+        session.surplus_cost = 0.12;
+        critical.active_session = Some(session.clone());
+
+        let update = WidgetUpdate::build(&config, &critical, None);
+        assert!(update.session_mode);
+        assert_eq!(update.items, SESSION_ITEMS);
+        assert_eq!(update.session_surplus_cost, Some(0.12));
+        assert_eq!(update.session_elapsed_secs, Some(90));
+        assert_eq!(update.session_label.as_deref(), Some("Render"));
+        assert_eq!(update.current_cost, None);
+
+        // The first update without the session is back to the configured items
+        critical.active_session = None;
+        let update = WidgetUpdate::build(&config, &critical, None);
+        assert!(!update.session_mode);
+        assert_eq!(update.items, ["power", "cost"]);
+        assert_eq!((update.session_surplus_cost, update.session_elapsed_secs), (None, None));
+
+        // "never" keeps the configured items during a session
+        let config = WidgetConfig { session_mode: WidgetSessionMode::Never, ..Default::default() };
+        critical.active_session = Some(session);
+        assert!(!WidgetUpdate::build(&config, &critical, None).session_mode);
+    }
+
+    #[test]
+    fn test_widget_tooltip() {
+        let i18n = I18n::new("en");
+        let today = DailyStats {
+            date: "2023-11-14".to_string(),
+            total_wh: 1250.0,
+            total_cost: Some(0.3),
+            avg_watts: 150.0,
+            max_watts: 220.0,
+            pricing_mode: None,
+            usage_seconds: 30_000,
+            source: None,
+            note: None,
+        };
+        let tooltip = WidgetTooltip::build(&i18n, &critical_metrics(), Some(&today), "rate.offpeak", 0.2, "$");
+        assert_eq!(tooltip.today, "Today: 1.25 kWh, $0.30");
+        assert_eq!(tooltip.session, None);
+        assert_eq!(tooltip.source, "Source: rapl (measured)");
+        assert_eq!(tooltip.rate, "Off-peak hours: $0.20/kWh");
+        assert!(!tooltip.stale && !tooltip.is_estimated);
+
+        // Before the first daily stats there is no cost, a stale reading says so
+        let mut critical = critical_metrics();
+        critical.freshness = Freshness { age_ms: 12_000, stale: true };
+        let tooltip = WidgetTooltip::build(&i18n, &critical, None, "rate.flat", 0.2, "$");
+        assert_eq!(tooltip.today, "Today: 0.00 kWh");
+        assert_eq!(tooltip.freshness, "No reading for 12s, values may be outdated");
+        assert!(tooltip.stale);
+    }
+
+    #[test]
+    fn test_update_due() {
+        assert!(update_due(None, 5000, 1000));
+        assert!(update_due(Some(990), 1000, 1000));
+        assert!(!update_due(Some(3000), 5000, 1000));
+        assert!(update_due(Some(4600), 5000, 1000));
+    }
+}
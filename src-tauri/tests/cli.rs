@@ -0,0 +1,154 @@
+//! Runs the `powercost-cli` binary against a simulated source and a scratch database
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use powercost_tracker_lib::core::PowerReading;
+use powercost_tracker_lib::db::{DailyStats, Database};
+
+/// Scratch directory removed when dropped
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("powercost-cli-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    fn path(&self, file: &str) -> PathBuf {
+        self.0.join(file)
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Run the CLI on the scratch database, with default settings (the config file does not exist)
+fn cli(scratch: &Scratch, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_powercost-cli"))
+        .arg("--config")
+        .arg(scratch.path("config.toml"))
+        .arg("--db")
+        .arg(scratch.path("data.db"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_watch_records_simulated_readings() {
+    let scratch = Scratch::new("watch");
+    let out = stdout(&cli(&scratch, &["watch", "--simulate", "250", "--count", "3", "--interval", "0.1", "--record"]));
+
+    assert!(out.starts_with("Source: Simulated (estimated)"));
+    assert_eq!(out.lines().filter(|l| l.contains("250.0 W") && l.contains(" Wh ")).count(), 3);
+    assert!(out.contains("Average:      250.0 W"));
+    assert!(out.contains("Readings:     3"));
+
+    // One reading stored per 10 s, the first one right away
+    let db = Database::open(&scratch.path("data.db")).unwrap();
+    let readings = db.get_readings(0, i64::MAX).unwrap();
+    assert_eq!(readings.len(), 1);
+    assert!(readings.iter().all(|r| r.power_watts == 250.0 && r.source == "simulated"));
+}
+
+#[test]
+fn test_dedup_recorded_readings() {
+    let scratch = Scratch::new("dedup");
+    // Stored within the same second or two, like an older version of the app did
+    let db = Database::open(&scratch.path("data.db")).unwrap();
+    let now = chrono::Utc::now().timestamp();
+    for offset in [0, 1, 2] {
+        db.insert_reading(&PowerReading {
+            power_watts: 250.0,
+            timestamp: now + offset,
+            source: "simulated".to_string(),
+            components: None,
+            is_estimated: true,
+        })
+        .unwrap();
+    }
+    drop(db);
+
+    let out = stdout(&cli(&scratch, &["dedup", "--window", "3"]));
+    assert!(out.starts_with("Removed 2 duplicate readings"), "{}", out);
+    let db = Database::open(&scratch.path("data.db")).unwrap();
+    assert_eq!(db.get_readings(0, i64::MAX).unwrap().len(), 1);
+}
+
+#[test]
+fn test_report_last_days() {
+    let scratch = Scratch::new("report");
+    let db = Database::open(&scratch.path("data.db")).unwrap();
+    let yesterday = chrono::Local::now().date_naive() - chrono::Duration::days(1);
+    let eight_days_ago = yesterday - chrono::Duration::days(7);
+    for (date, wh, cost) in [(yesterday, 2000.0, 0.5), (eight_days_ago, 1000.0, 0.25)] {
+        db.upsert_daily_stats(&DailyStats {
+            date: date.format("%Y-%m-%d").to_string(),
+            total_wh: wh,
+            total_cost: Some(cost),
+            avg_watts: 0.0,
+            max_watts: 0.0,
+            pricing_mode: None,
+            usage_seconds: 0,
+            source: None,
+            note: None,
+        })
+        .unwrap();
+    }
+    db.set_day_note(&yesterday.format("%Y-%m-%d").to_string(), "Rendering all day").unwrap();
+    drop(db);
+
+    let json: serde_json::Value = serde_json::from_str(&stdout(&cli(&scratch, &["report", "--days", "7", "--json"]))).unwrap();
+    assert_eq!(json["end_date"], yesterday.format("%Y-%m-%d").to_string());
+    assert_eq!(json["total_kwh"], 2.0);
+    assert_eq!(json["previous_total_kwh"], 1.0);
+    assert_eq!(json["cost_change_percent"], 100.0);
+    assert_eq!(json["day_notes"][0]["note"], "Rendering all day");
+    assert_eq!(json["lifetime"]["total_wh"], 3000.0);
+    assert_eq!(json["lifetime"]["first_date"], eight_days_ago.format("%Y-%m-%d").to_string());
+
+    let text = stdout(&cli(&scratch, &["report", "--days", "7"]));
+    assert!(text.contains("Energy:       2.000 kWh (previous 1.000 kWh)"));
+    assert!(text.contains(&format!("Note {}: Rendering all day", yesterday.format("%Y-%m-%d"))));
+    assert!(text.contains(&format!("You've tracked 3.0 kWh since {}, 0 sessions", eight_days_ago.format("%B %Y"))));
+}
+
+#[test]
+fn test_export_csv() {
+    let scratch = Scratch::new("export");
+    let db = Database::open(&scratch.path("data.db")).unwrap();
+    let id = db.start_session(40.0, Some("Render")).unwrap();
+    db.end_session(id, 120.0, 80.0, 0.02).unwrap();
+    drop(db);
+
+    let csv = scratch.path("sessions.csv");
+    let out = stdout(&cli(&scratch, &["export", "--csv", csv.to_str().unwrap()]));
+    assert!(out.starts_with("Exported 1 sessions"));
+
+    let content = std::fs::read_to_string(&csv).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[1].starts_with(&format!("{},Render,", id)));
+}
+
+#[test]
+fn test_usage_errors() {
+    let scratch = Scratch::new("usage");
+    let output = cli(&scratch, &["export", "--days", "3"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("export needs --csv PATH or --json PATH"));
+
+    assert_eq!(cli(&scratch, &["frobnicate"]).status.code(), Some(2));
+    assert!(stdout(&cli(&scratch, &["help"])).starts_with("Usage: powercost-cli"));
+}
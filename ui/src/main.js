@@ -37,7 +37,8 @@ const WIDGET_REGISTRY = {
         minRowSpan: 1,
         render: (data) => {
             const energyWh = data.cumulative_wh;
-            const display = energyWh >= 1000 ? `${formatNumber(energyWh / 1000, 2)} kWh` : `${formatNumber(energyWh, 1)} Wh`;
+            const display = data.display?.energy
+                ?? (energyWh >= 1000 ? `${formatNumber(energyWh / 1000, 2)} kWh` : `${formatNumber(energyWh, 1)} Wh`);
             return `<div class="widget-value small">${display}</div>`;
         },
     },
@@ -49,7 +50,7 @@ const WIDGET_REGISTRY = {
         defaultSize: 'small',
         minColSpan: 2,
         minRowSpan: 1,
-        render: (data) => `<div class="widget-value small cost-value">${state.currencySymbol}${formatNumber(data.current_cost, 4)}</div>`,
+        render: (data) => `<div class="widget-value small cost-value">${data.display?.current_cost ?? state.currencySymbol + formatNumber(data.current_cost, 4)}</div>`,
     },
     hourly_estimate: {
         id: 'hourly_estimate',
@@ -61,6 +62,7 @@ const WIDGET_REGISTRY = {
         minRowSpan: 1,
         render: (data, displayMode, widgetConfig) => renderEstimationWidget(data, widgetConfig, {
             costValue: data.hourly_cost_estimate,
+            costText: data.display?.hourly_cost,
             costDecimals: 4,
             unitKey: 'unit.per_hour',
             whMultiplier: 1,
@@ -76,6 +78,7 @@ const WIDGET_REGISTRY = {
         minRowSpan: 1,
         render: (data, displayMode, widgetConfig) => renderEstimationWidget(data, widgetConfig, {
             costValue: data.daily_cost_estimate,
+            costText: data.display?.daily_cost,
             costDecimals: 2,
             unitKey: 'unit.per_day',
             whMultiplier: 24,
@@ -91,6 +94,7 @@ const WIDGET_REGISTRY = {
         minRowSpan: 1,
         render: (data, displayMode, widgetConfig) => renderEstimationWidget(data, widgetConfig, {
             costValue: data.monthly_cost_estimate,
+            costText: data.display?.monthly_cost,
             costDecimals: 2,
             unitKey: 'unit.per_month',
             whMultiplier: 720,
@@ -240,6 +244,7 @@ const WIDGET_REGISTRY = {
         minColSpan: 2,
         minRowSpan: 2,
         supportsDisplayModes: true,
+        requires: 'gpu_metrics',
         render: (data, displayMode = 'bar') => {
             const gpu = data.systemMetrics?.gpu;
             if (!gpu) return `<div class="widget-na">${t('widget.no_gpu')}</div>`;
@@ -445,7 +450,14 @@ const WIDGET_REGISTRY = {
         minRowSpan: 1,
         render: (data) => {
             const session = data.activeSession;
+            const aboveBaseline = data.surplus_watts != null ? `
+                <div class="metric-row">
+                    <span class="metric-label">${t(session ? 'widget.above_baseline' : 'widget.above_idle')}</span>
+                    <span class="metric-value surplus-value">${formatNumber(data.surplus_watts, 1)} W</span>
+                </div>
+            ` : '';
             if (!session) return `
+                ${aboveBaseline}
                 <div class="widget-na">${t('widget.start_session_to_track')}</div>
                 <button class="btn btn-sm btn-secondary set-baseline-btn" data-power="${formatNumber(data.power_watts, 1)}">${t('widget.set_baseline')} (${formatNumber(data.power_watts, 1)} W)</button>
             `;
@@ -458,6 +470,7 @@ const WIDGET_REGISTRY = {
                     <span class="metric-label">${t('widget.current')}</span>
                     <span class="metric-value">${formatNumber(data.power_watts, 1)} W</span>
                 </div>
+                ${aboveBaseline}
                 <div class="metric-row">
                     <span class="metric-label">${t('session.surplus')}</span>
                     <span class="metric-value surplus-value">${formatNumber(session.surplus_wh, 2)} Wh</span>
@@ -466,6 +479,14 @@ const WIDGET_REGISTRY = {
                     <span class="metric-label">${t('widget.cost')}</span>
                     <span class="metric-value">${state.currencySymbol}${formatNumber(session.surplus_cost || 0, 4)}</span>
                 </div>
+                <div class="metric-row">
+                    <span class="metric-label">${t('session.avg_surplus_watts')}</span>
+                    <span class="metric-value">${session.avg_surplus_watts != null ? `${formatNumber(session.avg_surplus_watts, 1)} W` : '--'}</span>
+                </div>
+                <div class="metric-row">
+                    <span class="metric-label">${t('session.cost_per_hour')}</span>
+                    <span class="metric-value">${session.cost_per_hour != null ? `${state.currencySymbol}${formatNumber(session.cost_per_hour, 4)}` : '--'}</span>
+                </div>
                 <button class="btn btn-sm btn-secondary set-baseline-btn" data-power="${formatNumber(data.power_watts, 1)}">${t('widget.update_baseline')}</button>
             `;
         },
@@ -480,7 +501,7 @@ const WIDGET_REGISTRY = {
         minRowSpan: 1,
         render: (data) => {
             const session = data.activeSession;
-            const elapsed = session ? Math.floor(Date.now() / 1000) - session.start_time : 0;
+            const elapsed = session ? Math.max(0, Math.floor(Date.now() / 1000) - session.start_time) : 0;
             const duration = session ? formatDuration(elapsed) : '--:--:--';
             const surplusWh = session ? formatNumber(session.surplus_wh, 2) : '--';
             const surplusCost = session ? `${state.currencySymbol}${formatNumber(session.surplus_cost || 0, 4)}` : '--';
@@ -496,7 +517,7 @@ const WIDGET_REGISTRY = {
             const categories = state.sessionCategories || [];
             const currentCategory = session.category || '';
             const categoryOptions = categories.map(c =>
-                `<option value="${c.name}" ${currentCategory === c.name ? 'selected' : ''}>${c.emoji} ${c.name}</option>`
+                `<option value="${c.name}" ${currentCategory === c.name ? 'selected' : ''}>${categoryLabel(c)}</option>`
             ).join('');
 
             return `
@@ -570,18 +591,32 @@ const WIDGET_REGISTRY = {
                             const cpuVal = (proc.cpu_percent != null && !isNaN(proc.cpu_percent)) ? formatNumber(proc.cpu_percent, 1) : '--';
                             const gpuVal = (proc.gpu_percent != null && !isNaN(proc.gpu_percent)) ? formatNumber(proc.gpu_percent, 1) : '--';
                             const ramVal = (proc.memory_percent != null && !isNaN(proc.memory_percent)) ? formatNumber(proc.memory_percent, 1) : '--';
+                            // Sum of the processes past the limit, nothing to pin or kill
+                            if (proc.is_aggregate) {
+                                return `
+                                    <div class="process-row aggregate">
+                                        <span class="process-col-pin"></span>
+                                        <span class="process-name">${t('processes.other')}</span>
+                                        <span class="process-cpu">${cpuVal}%</span>
+                                        <span class="process-gpu">${gpuVal}%</span>
+                                        <span class="process-ram">${ramVal}%</span>
+                                        <span class="process-col-kill"></span>
+                                    </div>
+                                `;
+                            }
                             return `
                                 <div class="process-row ${proc.is_pinned ? 'pinned' : ''}">
                                     <button class="process-pin-btn" data-name="${proc.name}" title="${proc.is_pinned ? t('widget.unpin') : t('widget.pin')}">
                                         ${proc.is_pinned ? pinnedIcon : unpinnedIcon}
                                     </button>
-                                    <span class="process-name" title="${proc.name}">${proc.name.slice(0, 20)}</span>
+                                    <span class="process-name" title="${proc.container && proc.container !== proc.name ? `${proc.name} (${proc.container})` : proc.name}">${proc.name.slice(0, 20)}</span>
                                     <span class="process-cpu">${cpuVal}%</span>
                                     <span class="process-gpu">${gpuVal}%</span>
                                     <span class="process-ram">${ramVal}%</span>
+                                    ${proc.container && proc.container === proc.name ? '<span class="process-col-kill"></span>' : `
                                     <button class="process-kill-btn" data-name="${proc.name}" title="${t('processes.kill_confirm')}: ${proc.name}">
                                         ${killIcon}
-                                    </button>
+                                    </button>`}
                                 </div>
                             `;
                         }).join('')}
@@ -657,8 +692,68 @@ const WIDGET_REGISTRY = {
             `;
         },
     },
+    insights: {
+        id: 'insights',
+        titleKey: 'widget.insights',
+        icon: `<svg viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2"><path d="M9 18h6"/><path d="M10 22h4"/><path d="M12 2a7 7 0 0 0-4 12.7V17h8v-2.3A7 7 0 0 0 12 2z"/></svg>`,
+        defaultSize: 'medium',
+        defaultColSpan: 6,
+        defaultRowSpan: 2,
+        minColSpan: 3,
+        minRowSpan: 2,
+        render: () => {
+            const insights = state.insights || [];
+            if (insights.length === 0) {
+                return `<div class="widget-na">${t('widget.no_insights')}</div>`;
+            }
+            return `
+                <ul class="insights-list">
+                    ${insights.map(i => `<li class="insight-item" data-insight="${i.key}">${i.text}</li>`).join('')}
+                </ul>
+            `;
+        },
+    },
+    pinned: {
+        id: 'pinned',
+        titleKey: 'widget.pinned',
+        icon: `<svg viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2"><path d="M16 12V4h1V2H7v2h1v8l-2 2v2h5v6l1 1 1-1v-6h5v-2l-2-2z"/></svg>`,
+        defaultSize: 'medium',
+        defaultColSpan: 3,
+        defaultRowSpan: 2,
+        minColSpan: 3,
+        minRowSpan: 2,
+        render: () => {
+            const pinned = state.pinnedHistory || [];
+            if (pinned.length === 0) {
+                return `<div class="widget-na">${t('widget.no_pinned')}</div>`;
+            }
+            return `
+                <div class="pinned-list">
+                    ${pinned.map(proc => `
+                        <div class="pinned-row ${proc.running ? '' : 'not-running'}">
+                            <span class="process-name" title="${proc.name}">${proc.name.slice(0, 20)}</span>
+                            ${proc.running ? `
+                                <span class="process-cpu" title="CPU">${formatNumber(proc.cpu_percent, 1)}%</span>
+                                <span class="process-gpu" title="GPU">${proc.gpu_percent != null ? formatNumber(proc.gpu_percent, 1) + '%' : '--'}</span>
+                                <span class="process-ram" title="RAM">${formatNumber(proc.memory_percent, 1)}%</span>
+                            ` : `<span class="pinned-status">${t('widget.not_running')}</span>`}
+                            ${sparklineSvg(proc.cpu)}
+                        </div>
+                    `).join('')}
+                </div>
+            `;
+        },
+    },
 };
 
+// Inline SVG line of a series of percentages (0-100)
+function sparklineSvg(values) {
+    if (!values || values.length < 2) return '<svg class="sparkline"></svg>';
+    const step = 100 / (values.length - 1);
+    const points = values.map((v, i) => `${(i * step).toFixed(1)},${(20 - Math.min(Math.max(v, 0), 100) / 5).toFixed(1)}`).join(' ');
+    return `<svg class="sparkline" viewBox="0 0 100 20" preserveAspectRatio="none"><polyline points="${points}" fill="none" stroke="currentColor" stroke-width="1.5" vector-effect="non-scaling-stroke"/></svg>`;
+}
+
 // Helper function to get widget title from translations
 // Uses short title when widget is 1×1 and a short key exists
 function getWidgetTitle(widgetId, widgetConfig) {
@@ -680,7 +775,7 @@ function getWidgetTitle(widgetId, widgetConfig) {
 
 // Helper to render estimation widgets with both cost and Wh lines
 function renderEstimationWidget(data, widgetConfig, opts) {
-    const { costValue, costDecimals, unitKey, whMultiplier } = opts;
+    const { costValue, costText, costDecimals, unitKey, whMultiplier } = opts;
     const showWh = widgetConfig?.show_wh !== false; // default true
     const whValue = (data.avg_power_watts || data.power_watts) * whMultiplier;
     const whDisplay = whValue >= 1000
@@ -693,7 +788,7 @@ function renderEstimationWidget(data, widgetConfig, opts) {
     return `
         <div class="estimation-lines">
             <div class="estimation-line">
-                <div class="widget-value small cost-value">${state.currencySymbol}${formatNumber(costValue, costDecimals)}<span class="unit">${t(unitKey)}</span></div>
+                <div class="widget-value small cost-value">${costText ?? state.currencySymbol + formatNumber(costValue, costDecimals)}<span class="unit">${t(unitKey)}</span></div>
             </div>
             ${showWh ? `<div class="estimation-line secondary">
                 <div class="widget-value small">${whDisplay}</div>
@@ -714,6 +809,7 @@ const state = {
     maxHistoryPoints: 60,
     currencySymbol: '\u20AC',
     historyData: [],
+    temperatureHistory: [],
     // Tiered update interval IDs
     criticalIntervalId: null,  // Fast updates (power, CPU%, GPU%, cost)
     detailedIntervalId: null,  // Slow updates (processes, temps, VRAM)
@@ -753,6 +849,8 @@ const state = {
     // Layout profiles
     layoutProfiles: [],
     activeProfileName: '',
+    // What the power source can report (null until probed)
+    capabilities: null,
 };
 
 // Widget classification for tiered updates
@@ -766,6 +864,48 @@ function snapshotCanonicalLayout() {
     }
 }
 
+// Whether the power source can ever fill a widget (assumed until probed)
+function widgetSupported(widgetDef) {
+    return !widgetDef.requires || !state.capabilities || state.capabilities[widgetDef.requires] !== false;
+}
+
+// Capabilities shown in the settings, without the battery (not a missing feature)
+const CAPABILITY_KEYS = ['real_power', 'gpu_metrics', 'gpu_per_process', 'cpu_temperature', 'per_core_temperature', 'fans', 'voltages', 'memory_info', 'input_idle'];
+
+// Probing may run the GPU tools, so the dashboard renders first and hides what is missing after
+async function loadCapabilities(reinitialize = false) {
+    try {
+        state.capabilities = await invoke(reinitialize ? 'reinitialize_power_monitor' : 'get_capabilities');
+    } catch (error) {
+        console.error('Failed to get capabilities:', error);
+        return;
+    }
+    renderCapabilities();
+    renderDashboard();
+}
+
+function renderCapabilities() {
+    const list = document.getElementById('capabilities-missing');
+    const capabilities = state.capabilities;
+    if (!list || !capabilities) return;
+
+    const missing = CAPABILITY_KEYS.filter(key => capabilities[key] === false);
+    if (missing.length === 0) {
+        const item = document.createElement('li');
+        item.textContent = t('settings.capabilities_all');
+        list.replaceChildren(item);
+        return;
+    }
+    list.replaceChildren(...missing.map(key => {
+        // Platform-specific hint first, then the generic one
+        const platformKey = `capabilities.hint.${key}.${capabilities.platform}`;
+        const hint = t(platformKey) !== platformKey ? t(platformKey) : t(`capabilities.hint.${key}`);
+        const item = document.createElement('li');
+        item.textContent = `${t('capabilities.' + key)}: ${hint}`;
+        return item;
+    }));
+}
+
 // ===== Initialization =====
 document.addEventListener('DOMContentLoaded', async () => {
     try {
@@ -789,6 +929,9 @@ document.addEventListener('DOMContentLoaded', async () => {
         setupSourceBadgeToggle();
         setupHistoryTabs();
         setupCategorySettings();
+        setupRegionDefaultsBanner();
+        setupPersistenceWarning();
+        loadCapabilities();
 
         startDashboardUpdates();
 
@@ -806,12 +949,75 @@ document.addEventListener('DOMContentLoaded', async () => {
             updatePowerDisplay(event.payload);
         });
 
+        // Strings reloaded after a language change, from any window
+        await listen('language-changed', (event) => {
+            state.translations = event.payload;
+            applyTranslations();
+            refreshInsights();
+        });
+
         // Listen for update-available event from startup check
         await listen('update-available', (event) => {
             const result = event.payload;
             showToast(`${t('settings.updates.available')}: v${result.latest_version}`, 'info');
         });
 
+        // History kept in memory only, the data file could not be opened
+        await listen('persistence-disabled', () => {
+            document.getElementById('persistence-warning')?.classList.remove('hidden');
+        });
+        await listen('persistence-restored', () => {
+            document.getElementById('persistence-warning')?.classList.add('hidden');
+        });
+
+        // The OS switched between light and dark with the "system" theme, or the setting changed
+        await listen('theme-changed', (event) => {
+            document.documentElement.setAttribute('data-theme', event.payload);
+        });
+
+        // Only the panels of the changed config sections are refreshed
+        await listen('config-changed', (event) => {
+            applyConfigChanges(event.payload);
+        });
+
+        // Detailed rate stretched because collections take too long here (sent once)
+        await listen('monitoring-adjusted', (event) => {
+            const { effective_ms, collection_ms } = event.payload;
+            showToast(t('monitoring.adjusted')
+                .replace('{collection}', (collection_ms / 1000).toFixed(1))
+                .replace('{effective}', (effective_ms / 1000).toFixed(0)), 'info');
+        });
+
+        // The widget can also be closed from its own button or by the OS
+        await listen('widget-closed', () => {
+            document.getElementById('toggle-widget-btn').textContent = t('settings.widget.open');
+            if (state.config?.widget?.remember_open_state) {
+                state.config.widget.enabled = false;
+            }
+        });
+
+        // Session ended by the backend, after inactivity or its planned duration
+        await listen('session-auto-ended', (event) => {
+            const { session, reclaimed_minutes } = event.payload;
+            state.activeSession = null;
+            sessionLocalOverrideUntil = Date.now() + 3000;
+            refreshSessionWidget();
+            // The backend notification says so already unless it is turned off
+            if (state.config?.general?.notifications?.enabled?.session_auto_ended !== false) return;
+            const summary = `${formatNumber(session.surplus_wh, 2)} Wh ${t('session.surplus')}`;
+            if (reclaimed_minutes == null) {
+                showToast(`${t('session.ended')}: ${summary}`, 'info');
+            } else {
+                const idle = t('notification.session_idle_body').replace('{minutes}', Math.round(reclaimed_minutes));
+                showToast(`${t('session.auto_ended')}: ${summary} (${idle})`, 'info');
+            }
+        });
+
+        // Alerts routed through the backend's notification settings
+        await listen('notification', (event) => {
+            showNotification(event.payload);
+        });
+
     } catch (error) {
         console.error('Initialization error:', error);
     }
@@ -848,12 +1054,42 @@ function applyTranslations() {
             el.title = state.translations[key];
         }
     });
+    renderCapabilities();
 }
 
 function t(key) {
     return state.translations[key] || key;
 }
 
+// Localized message for a command error (`{ code, message, details }` or a plain string)
+function errorMessage(error) {
+    if (error && error.code) {
+        const key = `error.code.${error.code.toLowerCase()}`;
+        return state.translations[key] || error.message;
+    }
+    return String(error);
+}
+
+// Toast for a failed process command, from its error code and subject
+function showProcessError(error, name, notFoundKey, fallbackKey) {
+    const subject = error?.subject || name;
+    switch (error?.code) {
+        case 'NOT_FOUND':
+            showToast(t(notFoundKey).replace('{name}', subject), 'warning');
+            break;
+        case 'PERMISSION_DENIED': {
+            const message = t('processes.kill_access_denied').replace('{name}', subject);
+            showToast(error.elevation_hint ? `${message}, ${t('processes.elevation_hint')}` : message, 'error');
+            break;
+        }
+        case 'INVALID_INPUT':
+            showToast(t('processes.other_row'), 'warning');
+            break;
+        default:
+            showToast(`${t(fallbackKey)}: ${subject}`, 'error');
+    }
+}
+
 // ===== Navigation =====
 function setupNavigation() {
     const navLinks = document.querySelectorAll('.nav-link');
@@ -1017,6 +1253,74 @@ function setupDashboard() {
 
     // Process search modal handlers
     setupProcessModal();
+
+    // Per-core CPU detail modal handlers
+    setupCpuCoresModal();
+}
+
+// ===== CPU Cores Modal =====
+function setupCpuCoresModal() {
+    const modal = document.getElementById('cpu-cores-modal');
+    document.getElementById('close-cpu-cores-modal').addEventListener('click', closeCpuCoresModal);
+    modal.addEventListener('click', (e) => {
+        if (e.target.classList.contains('modal')) closeCpuCoresModal();
+    });
+}
+
+async function openCpuCoresModal() {
+    document.getElementById('cpu-cores-modal').classList.remove('hidden');
+    await refreshCpuCoresModal();
+}
+
+function closeCpuCoresModal() {
+    document.getElementById('cpu-cores-modal').classList.add('hidden');
+}
+
+function isCpuCoresModalOpen() {
+    return !document.getElementById('cpu-cores-modal').classList.contains('hidden');
+}
+
+async function refreshCpuCoresModal() {
+    try {
+        renderCpuCores(await invoke('get_per_core_history'));
+    } catch (error) {
+        console.error('Failed to get per-core history:', error);
+    }
+}
+
+// Usage series as an SVG polyline, 0-100% mapped to the full height
+function coreSparkline(series) {
+    const width = 100;
+    const height = 30;
+    if (series.length < 2) return `<svg class="cpu-core-sparkline" viewBox="0 0 ${width} ${height}"></svg>`;
+    const step = width / (series.length - 1);
+    const points = series
+        .map((usage, i) => `${(i * step).toFixed(1)},${(height - Math.min(Math.max(usage, 0), 100) / 100 * height).toFixed(1)}`)
+        .join(' ');
+    return `<svg class="cpu-core-sparkline" viewBox="0 0 ${width} ${height}" preserveAspectRatio="none"><polyline points="${points}"/></svg>`;
+}
+
+function renderCpuCores(history) {
+    const grid = document.getElementById('cpu-cores-grid');
+    if (!history || history.core_count === 0) {
+        grid.innerHTML = `<div class="process-modal-empty">${t('cpu_cores.empty')}</div>`;
+        return;
+    }
+
+    grid.innerHTML = history.usage.map((series, i) => {
+        const current = series.length > 0 ? series[series.length - 1] : null;
+        const freq = history.frequency_mhz?.[i];
+        return `
+            <div class="cpu-core-cell">
+                <div class="cpu-core-header">
+                    <span class="cpu-core-name">${t('cpu_cores.core')} ${i}</span>
+                    <span class="cpu-core-usage">${current != null ? `${formatNumber(current, 0)}%` : '--'}</span>
+                </div>
+                ${coreSparkline(series)}
+                <div class="cpu-core-freq">${freq != null ? `${freq} MHz` : ''}</div>
+            </div>
+        `;
+    }).join('');
 }
 
 // ===== Process Search Modal =====
@@ -1055,9 +1359,30 @@ function setupProcessModal() {
 
                 // Refresh the modal list
                 await refreshProcessModalList();
+                refreshPinnedHistory();
             } catch (error) {
                 console.error('Failed to toggle pin:', error);
-                showToast(t('processes.pin_failed'), 'error');
+                showProcessError(error, name, 'processes.pin_not_found', 'processes.pin_failed');
+            }
+        }
+
+        const ignoreBtn = e.target.closest('.process-modal-ignore-btn');
+        if (ignoreBtn) {
+            const name = ignoreBtn.dataset.name;
+            if (!name) return;
+
+            try {
+                if (ignoreBtn.dataset.ignored) {
+                    await invoke('unignore_process', { name });
+                    showToast(`${t('processes.unignored')}: ${name}`, 'info');
+                } else {
+                    await invoke('ignore_process', { name });
+                    showToast(`${t('processes.ignored')}: ${name}`, 'success');
+                }
+                await refreshProcessModalList();
+            } catch (error) {
+                console.error('Failed to toggle ignore:', error);
+                showToast(t('processes.ignore_failed'), 'error');
             }
         }
 
@@ -1074,14 +1399,7 @@ function setupProcessModal() {
                 await refreshProcessModalList();
             } catch (error) {
                 console.error('Failed to kill process:', error);
-                const msg = String(error);
-                if (msg.includes('ACCESS_DENIED')) {
-                    showToast(`${t('processes.kill_access_denied')} (${name})`, 'error');
-                } else if (msg.includes('NOT_FOUND')) {
-                    showToast(`${t('processes.kill_not_found')} (${name})`, 'warning');
-                } else {
-                    showToast(`${t('processes.kill_failed')}: ${name}`, 'error');
-                }
+                showProcessError(error, name, 'processes.kill_not_found', 'processes.kill_failed');
             }
         }
     });
@@ -1144,13 +1462,15 @@ function renderProcessModalList(processes) {
     const pinnedIcon = `<svg viewBox="0 0 24 24" fill="currentColor" stroke="none" width="14" height="14"><path d="M16 12V4h1V2H7v2h1v8l-2 2v2h5v6l1 1 1-1v-6h5v-2l-2-2z"/></svg>`;
     const unpinnedIcon = `<svg viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" width="14" height="14"><path d="M16 12V4h1V2H7v2h1v8l-2 2v2h5v6l1 1 1-1v-6h5v-2l-2-2z"/></svg>`;
     const killIcon = `<svg viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" width="14" height="14"><path d="M18 6L6 18M6 6l12 12"/></svg>`;
+    const visibleIcon = `<svg viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" width="14" height="14"><path d="M1 12s4-8 11-8 11 8 11 8-4 8-11 8-11-8-11-8z"/><circle cx="12" cy="12" r="3"/></svg>`;
+    const hiddenIcon = `<svg viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" width="14" height="14"><path d="M17.94 17.94A10.07 10.07 0 0 1 12 20c-7 0-11-8-11-8a18.45 18.45 0 0 1 5.06-5.94M9.9 4.24A9.12 9.12 0 0 1 12 4c7 0 11 8 11 8a18.5 18.5 0 0 1-2.16 3.19M1 1l22 22"/></svg>`;
 
     list.innerHTML = processes.map(proc => {
         const cpuVal = (proc.cpu_percent != null && !isNaN(proc.cpu_percent)) ? formatNumber(proc.cpu_percent, 1) : '--';
         const gpuVal = (proc.gpu_percent != null && !isNaN(proc.gpu_percent)) ? formatNumber(proc.gpu_percent, 1) : '--';
         const ramVal = (proc.memory_percent != null && !isNaN(proc.memory_percent)) ? formatNumber(proc.memory_percent, 1) : '--';
         return `
-            <div class="process-modal-row ${proc.is_pinned ? 'pinned' : ''}">
+            <div class="process-modal-row ${proc.is_pinned ? 'pinned' : ''} ${proc.is_ignored ? 'ignored' : ''}">
                 <button class="process-modal-pin-btn" data-name="${proc.name}" title="${proc.is_pinned ? t('widget.unpin') : t('widget.pin')}">
                     ${proc.is_pinned ? pinnedIcon : unpinnedIcon}
                 </button>
@@ -1158,6 +1478,9 @@ function renderProcessModalList(processes) {
                 <span class="process-modal-cpu">${cpuVal}%</span>
                 <span class="process-modal-gpu">${gpuVal}%</span>
                 <span class="process-modal-ram">${ramVal}%</span>
+                <button class="process-modal-ignore-btn" data-name="${proc.name}" data-ignored="${proc.is_ignored ? '1' : ''}" title="${proc.is_ignored ? t('processes.unignore') : t('processes.ignore')}">
+                    ${proc.is_ignored ? hiddenIcon : visibleIcon}
+                </button>
                 <button class="process-modal-kill-btn" data-name="${proc.name}" title="${t('processes.kill_confirm')}: ${proc.name}">
                     ${killIcon}
                 </button>
@@ -1198,7 +1521,7 @@ async function handleDashboardClick(e) {
             const wc = state.dashboardConfig?.widgets?.find(w => w.id === widgetId);
             if (wc) {
                 wc.show_wh = !wc.show_wh;
-                saveDashboardConfigQuiet();
+                updateDashboardWidgetQuiet(widgetId, { show_wh: wc.show_wh });
                 // Re-render just this widget
                 const data = state.lastDashboardData || buildDashboardData();
                 const widgetDef = WIDGET_REGISTRY[widgetId];
@@ -1229,6 +1552,12 @@ async function handleDashboardClick(e) {
         }
     }
 
+    // Clicking the CPU widget opens the per-core detail
+    if (!state.isEditMode && e.target.closest('#widget-body-cpu')) {
+        openCpuCoresModal();
+        return;
+    }
+
     // Handle process search button - open modal
     const advancedToggle = e.target.closest('.process-advanced-toggle');
     if (advancedToggle) {
@@ -1287,6 +1616,7 @@ async function handleDashboardClick(e) {
                 showToast(`${t('processes.pinned')}: ${name}`, 'success');
             }
 
+            refreshPinnedHistory();
             // Refresh processes and update widget
             if (state.processAdvancedMode) {
                 state.allProcesses = await invoke('get_all_processes');
@@ -1300,7 +1630,7 @@ async function handleDashboardClick(e) {
             }
         } catch (error) {
             console.error('Failed to toggle pin:', error);
-            showToast(t('processes.pin_failed'), 'error');
+            showProcessError(error, name, 'processes.pin_not_found', 'processes.pin_failed');
         }
     }
 
@@ -1329,14 +1659,7 @@ async function handleDashboardClick(e) {
             }
         } catch (error) {
             console.error('Failed to kill process:', error);
-            const msg = String(error);
-            if (msg.includes('ACCESS_DENIED')) {
-                showToast(`${t('processes.kill_access_denied')} (${name})`, 'error');
-            } else if (msg.includes('NOT_FOUND')) {
-                showToast(`${t('processes.kill_not_found')} (${name})`, 'warning');
-            } else {
-                showToast(`${t('processes.kill_failed')}: ${name}`, 'error');
-            }
+            showProcessError(error, name, 'processes.kill_not_found', 'processes.kill_failed');
         }
     }
 }
@@ -1455,7 +1778,7 @@ function renderDashboard() {
         if (!widgetConfig.visible) continue;
 
         const widgetDef = WIDGET_REGISTRY[widgetConfig.id];
-        if (!widgetDef) continue;
+        if (!widgetDef || !widgetSupported(widgetDef)) continue;
 
         const card = document.createElement('div');
         card.className = `card widget-card`;
@@ -1669,7 +1992,7 @@ function renderVisibilityPanel() {
                 const widget = state.dashboardConfig.widgets.find(w => w.id === widgetId);
                 if (widget) {
                     widget.display_mode = e.target.value;
-                    saveDashboardConfigQuiet();
+                    updateDashboardWidgetQuiet(widgetId, { display_mode: e.target.value });
                     // Re-render the specific widget with new display mode
                     const body = document.getElementById(`widget-body-${widgetId}`);
                     if (body && state.lastDashboardData) {
@@ -2334,6 +2657,26 @@ async function saveDashboardConfigQuiet() {
     }
 }
 
+/**
+ * Saves a change to a single widget without resending the whole layout
+ * @param {string} id - Widget ID
+ * @param {Object} patch - Fields to change (visible, display_mode, show_wh, spans or position)
+ */
+async function updateDashboardWidgetQuiet(id, patch) {
+    try {
+        const updated = await invoke('update_dashboard_widget', { id, patch });
+        for (const widgets of [state.dashboardConfig?.widgets, state.canonicalWidgets]) {
+            const widget = widgets?.find(w => w.id === id);
+            // Only copy the patched fields so a reflowed narrow layout keeps its positions
+            for (const key of Object.keys(patch)) {
+                if (widget && key in updated) widget[key] = updated[key];
+            }
+        }
+    } catch (error) {
+        console.error('Failed to update dashboard widget:', error);
+    }
+}
+
 function openEditModal() {
     const modal = document.getElementById('edit-dashboard-modal');
     const list = document.getElementById('widget-toggle-list');
@@ -2429,9 +2772,17 @@ async function saveDashboardConfig() {
                 widget.col_span = 2;
                 widget.row_span = 1;
             }
+
+            // Keep the per-widget minimum sizes, the backend rejects smaller spans
+            const def = WIDGET_REGISTRY[widgetId];
+            widget.col_span = Math.max(widget.col_span, (def && def.minColSpan) || 2);
+            widget.row_span = Math.max(widget.row_span, (def && def.minRowSpan) || 1);
         }
     });
 
+    // Size and visibility changes may make widgets overlap
+    verifyNoCollisions();
+
     try {
         snapshotCanonicalLayout();
         await invoke('save_dashboard_config', { dashboard: state.dashboardConfig });
@@ -2621,9 +2972,43 @@ async function startDashboardUpdates() {
     // Keep legacy interval for backwards compat (but it does nothing now)
     state.dashboardIntervalId = null;
 
+    // Insights only change with the daily stats, refresh them now and then
+    refreshInsights();
+    if (!state.insightsIntervalId) {
+        state.insightsIntervalId = setInterval(refreshInsights, INSIGHTS_REFRESH_MS);
+    }
+
     console.log(`Dashboard updates started: critical=${fastRate}ms, detailed=${slowRate}ms`);
 }
 
+const INSIGHTS_REFRESH_MS = 10 * 60 * 1000;
+
+async function refreshInsights() {
+    try {
+        state.insights = await invoke('get_insights');
+    } catch (error) {
+        console.error('Failed to get insights:', error);
+        return;
+    }
+    const body = document.getElementById('widget-body-insights');
+    if (body && !state.isEditMode) {
+        body.innerHTML = WIDGET_REGISTRY.insights.render();
+    }
+}
+
+// The pinned widget keeps its own series, fetched along with each detailed update
+async function refreshPinnedHistory() {
+    const body = document.getElementById('widget-body-pinned');
+    if (!body || state.isEditMode) return;
+    try {
+        state.pinnedHistory = await invoke('get_pinned_process_history');
+    } catch (error) {
+        console.error('Failed to get pinned process history:', error);
+        return;
+    }
+    body.innerHTML = WIDGET_REGISTRY.pinned.render();
+}
+
 function restartDashboardUpdates() {
     // Clear all intervals
     if (state.criticalIntervalId) {
@@ -2665,6 +3050,7 @@ function handleCriticalUpdate(metrics) {
 
     // Update only critical widgets
     renderWidgetsByType(data, 'critical');
+    markStaleWidgets('critical', metrics);
 
     // Update power source badge
     const powerSource = document.getElementById('power-source');
@@ -2698,6 +3084,10 @@ function handleDetailedUpdate(metrics) {
     state.systemMetrics = metrics.system_metrics;
     state.topProcesses = metrics.top_processes;
 
+    if (isCpuCoresModalOpen()) {
+        refreshCpuCoresModal();
+    }
+
     // Update metrics history for mini-charts
     updateMetricsHistory(metrics.system_metrics);
 
@@ -2706,11 +3096,26 @@ function handleDetailedUpdate(metrics) {
 
     // Update only detailed widgets
     renderWidgetsByType(data, 'detailed');
+    markStaleWidgets('detailed', metrics);
+    refreshPinnedHistory();
 
     // Draw mini-charts after DOM is updated
     drawMiniCharts();
 }
 
+// Grey out the widgets of a tier when the backend flags its metrics as stale
+function markStaleWidgets(type, metrics) {
+    const isOfType = type === 'critical' ? isCriticalWidget : isDetailedWidget;
+    const title = metrics.stale ? `${t('dashboard.stale')} (${formatDuration(Math.floor((metrics.age_ms || 0) / 1000))})` : '';
+    for (const widgetConfig of state.dashboardConfig?.widgets || []) {
+        if (!isOfType(widgetConfig.id)) continue;
+        const body = document.getElementById(`widget-body-${widgetConfig.id}`);
+        if (!body) continue;
+        body.classList.toggle('stale', !!metrics.stale);
+        body.title = title;
+    }
+}
+
 // Build dashboard data object from cached metrics
 function buildDashboardData() {
     const cm = state.criticalMetrics;
@@ -2724,6 +3129,7 @@ function buildDashboardData() {
         hourly_cost_estimate: cm?.hourly_cost_estimate || 0,
         daily_cost_estimate: cm?.daily_cost_estimate || 0,
         monthly_cost_estimate: cm?.monthly_cost_estimate || 0,
+        display: cm?.display || null,
         session_duration_secs: cm?.session_duration_secs || 0,
         source: cm?.source || '--',
         is_estimated: cm?.is_estimated || false,
@@ -2733,6 +3139,8 @@ function buildDashboardData() {
             memory: dm?.system_metrics?.memory || null,
         },
         activeSession: cm?.active_session || state.activeSession,
+        baseline_watts: cm?.baseline_watts ?? null,
+        surplus_watts: cm?.surplus_watts ?? null,
         topProcesses: dm?.top_processes || state.topProcesses || [],
     };
 }
@@ -2761,7 +3169,7 @@ function renderWidgetsByType(data, type) {
             // This prevents input/select fields from being destroyed while the user is typing or selecting
             if (widgetConfig.id === 'session_controls' && data.activeSession && body.querySelector('.session-widget')) {
                 const session = data.activeSession;
-                const elapsed = Math.floor(Date.now() / 1000) - session.start_time;
+                const elapsed = Math.max(0, Math.floor(Date.now() / 1000) - session.start_time);
                 const durationEl = body.querySelector('.session-widget-duration');
                 if (durationEl) durationEl.textContent = formatDuration(elapsed);
                 const surplusVal = body.querySelector('.session-surplus-wh');
@@ -3045,7 +3453,7 @@ async function startSession() {
     if (state.activeSession) return;
 
     try {
-        await invoke('start_tracking_session', { label: null });
+        await invoke('start_tracking_session', { label: null, category: null });
         // Fetch fresh session data
         state.activeSession = await invoke('get_session_stats').catch(() => null);
         sessionLocalOverrideUntil = Date.now() + 3000;
@@ -3054,7 +3462,7 @@ async function startSession() {
         showToast(t('session.started'), 'success');
     } catch (error) {
         console.error('Failed to start session:', error);
-        showToast(t('session.start_failed'), 'error');
+        showToast(`${t('session.start_failed')}: ${errorMessage(error)}`, 'error');
     }
 }
 
@@ -3235,10 +3643,20 @@ async function loadHistoryData(startDate, endDate) {
         const startStr = formatDate(startDate);
         const endStr = formatDate(endDate);
 
-        const stats = await invoke('get_history', { startDate: startStr, endDate: endStr });
+        const { days: stats, timezone } = await invoke('get_history', { startDate: startStr, endDate: endStr });
+        const rangeLabel = document.getElementById('history-range-label');
+        if (rangeLabel && timezone !== 'local') {
+            rangeLabel.textContent += ` (${timezone})`;
+        }
+        const estimatedSources = await loadEstimatedSources(startDate, endDate);
 
         // Fill date gaps so chart shows every day in range
         const filledStats = fillDateGaps(stats, startDate, endDate);
+        // Days without stats can have a note too
+        const notes = await loadDayNotes(startStr, endStr);
+        for (const day of filledStats) {
+            day.note = notes.get(day.date) || null;
+        }
 
         const breakdownEl = document.getElementById('daily-breakdown');
         if (filledStats.length === 0 || filledStats.every(d => d.total_wh === 0)) {
@@ -3270,28 +3688,106 @@ async function loadHistoryData(startDate, endDate) {
                 rateBadge.textContent = `${state.currencySymbol}${formatNumber(avgRate, 4)}/kWh`;
             }
 
-            // Populate daily breakdown table (only rows with data)
+            // Populate daily breakdown table (only rows with data or a note)
             const tbody = document.getElementById('breakdown-table-body');
             if (tbody) {
-                tbody.innerHTML = nonEmpty.map(day => `
+                tbody.innerHTML = filledStats.filter(d => d.total_wh > 0 || d.note).map(day => `
                     <tr>
-                        <td>${day.date}</td>
+                        <td>${day.date}${estimatedSources.has(day.source) ? ` <span class="setting-description" title="${t('history.estimated_desc')}">(${t('history.estimated')})</span>` : ''}</td>
                         <td class="energy-cell">${formatNumber(day.total_wh / 1000, 3)} kWh</td>
                         <td>${formatNumber(day.avg_watts, 0)} W</td>
                         <td class="peak-cell">${formatNumber(day.max_watts, 0)} W</td>
                         <td class="cost-cell">${day.total_cost != null ? state.currencySymbol + formatNumber(day.total_cost, 4) : '--'}</td>
                         <td>${day.usage_seconds ? formatDuration(day.usage_seconds) : '--'}</td>
+                        <td class="note-cell">
+                            ${day.note ? `<span class="day-note-text" title="${escapeHtml(day.note)}">${escapeHtml(day.note)}</span>` : ''}
+                            <button class="btn-icon day-note-btn" data-date="${day.date}" title="${t(day.note ? 'history.note_edit' : 'history.note_add')}">&#9998;</button>
+                        </td>
                     </tr>
                 `).join('');
+                tbody.onclick = (e) => {
+                    const button = e.target.closest('.day-note-btn');
+                    if (button) editDayNote(button.dataset.date);
+                };
             }
 
             drawHistoryChart();
         }
+
+        await loadTemperatureHistory(startDate, endDate);
     } catch (error) {
         console.error('History load error:', error);
     }
 }
 
+// Notes of the days in the range, by date
+async function loadDayNotes(startDate, endDate) {
+    try {
+        const notes = await invoke('get_day_notes', { startDate, endDate });
+        return new Map(notes.map(n => [n.date, n.note]));
+    } catch (error) {
+        console.error('Failed to load day notes:', error);
+        return new Map();
+    }
+}
+
+// Ask for the note of a day; an empty note deletes it
+async function editDayNote(date) {
+    const current = state.historyData.find(d => d.date === date)?.note || '';
+    const note = prompt(t('history.note_prompt').replace('{date}', date), current);
+    if (note === null || note.trim() === current) return;
+    try {
+        if (note.trim()) {
+            await invoke('set_day_note', { date, note });
+        } else {
+            await invoke('delete_day_note', { date });
+        }
+        loadHistoryForRange();
+    } catch (error) {
+        showToast(`${t('history.note_failed')}: ${errorMessage(error)}`, 'error');
+    }
+}
+
+function escapeHtml(text) {
+    return String(text).replace(/[&<>"']/g, c => ({ '&': '&amp;', '<': '&lt;', '>': '&gt;', '"': '&quot;', "'": '&#39;' }[c]));
+}
+
+// Names of the sources that only produced estimated values in the range
+async function loadEstimatedSources(startDate, endDate) {
+    try {
+        const end = new Date(endDate);
+        end.setHours(23, 59, 59, 999);
+        const changes = await invoke('get_source_history', {
+            startTimestamp: Math.floor(startDate.getTime() / 1000),
+            endTimestamp: Math.floor(end.getTime() / 1000),
+        });
+        return new Set(changes.filter(c => c.is_estimated).map(c => c.new_source));
+    } catch (error) {
+        console.error('Failed to load source history:', error);
+        return new Set();
+    }
+}
+
+async function loadTemperatureHistory(startDate, endDate) {
+    const container = document.getElementById('temperature-chart-container');
+    try {
+        const end = new Date(endDate);
+        end.setHours(23, 59, 59, 999);
+        state.temperatureHistory = await invoke('get_temperature_history', {
+            startTimestamp: Math.floor(startDate.getTime() / 1000),
+            endTimestamp: Math.floor(end.getTime() / 1000),
+            maxPoints: 400,
+        });
+    } catch (error) {
+        console.error('Temperature history load error:', error);
+        state.temperatureHistory = [];
+    }
+
+    const hasData = state.temperatureHistory.some(r => r.cpu_c != null || r.gpu_c != null);
+    container.classList.toggle('hidden', !hasData);
+    if (hasData) drawTemperatureChart();
+}
+
 // Fill missing dates in stats array with zero-value entries
 function fillDateGaps(stats, startDate, endDate) {
     const dateMap = {};
@@ -3418,9 +3914,9 @@ async function loadSessionHistoryView(startDate, endDate) {
             const categories = state.sessionCategories || [];
             list.innerHTML = sessions.map(s => {
                 const sDate = new Date(s.start_time * 1000);
-                const duration = s.end_time ? s.end_time - s.start_time : 0;
+                const duration = s.end_time ? Math.max(0, s.end_time - s.start_time) : 0;
                 const categoryOptions = categories.map(c =>
-                    `<option value="${c.name}" ${s.category === c.name ? 'selected' : ''}>${c.emoji} ${c.name}</option>`
+                    `<option value="${c.name}" ${s.category === c.name ? 'selected' : ''}>${categoryLabel(c)}</option>`
                 ).join('');
                 return `
                     <div class="session-item" data-session-id="${s.id}">
@@ -3463,6 +3959,20 @@ async function loadSessionHistoryView(startDate, endDate) {
                                 <span class="session-stat-label">${tr['history.cost'] || 'Cost'}</span>
                                 <span class="session-stat-value cost">${state.currencySymbol}${formatNumber(s.surplus_cost, 4)}</span>
                             </div>
+                            ${s.avg_watts != null ? `
+                            <div class="session-stat">
+                                <span class="session-stat-label">${tr['session.avg_watts'] || 'Avg power'}</span>
+                                <span class="session-stat-value">${formatNumber(s.avg_watts, 1)} W</span>
+                            </div>
+                            <div class="session-stat">
+                                <span class="session-stat-label">${tr['session.cost_per_hour'] || 'Cost/h'}</span>
+                                <span class="session-stat-value cost">${state.currencySymbol}${formatNumber(s.cost_per_hour, 4)}</span>
+                            </div>` : ''}
+                            ${s.target_met !== null && s.target_met !== undefined ? `
+                            <div class="session-stat">
+                                <span class="session-stat-label">${tr['session.target'] || 'Target'}</span>
+                                <span class="session-stat-value">${s.target_met ? (tr['session.target_met'] || 'Met') : (tr['session.target_missed'] || 'Missed')}</span>
+                            </div>` : ''}
                         </div>
                     </div>
                 `;
@@ -3587,7 +4097,7 @@ function setupSessionListEvents(list, sessions) {
 
 function getCategoryDisplay(categoryName) {
     const cat = (state.sessionCategories || []).find(c => c.name === categoryName);
-    return cat ? `${cat.emoji} ${cat.name}` : categoryName;
+    return cat ? categoryLabel(cat) : categoryName;
 }
 
 function getCategoryColor(categoryName) {
@@ -3643,7 +4153,7 @@ function renderSessionHistogram(sessions, startDate, endDate) {
         let totalCost = 0;
         for (const s of daySessions) {
             const cat = s.category || t('session.no_category');
-            const duration = (s.end_time || Math.floor(Date.now() / 1000)) - s.start_time;
+            const duration = Math.max(0, (s.end_time || Math.floor(Date.now() / 1000)) - s.start_time);
             categoryHours[cat] = (categoryHours[cat] || 0) + duration / 3600;
             totalCost += (s.surplus_cost || 0);
         }
@@ -3840,6 +4350,14 @@ function drawHistoryChart() {
         ctx.roundRect(x, y, barWidth, barHeight, [4, 4, 0, 0]);
         ctx.fill();
 
+        // Annotated day marker
+        if (day.note) {
+            ctx.fillStyle = '#eab308';
+            ctx.beginPath();
+            ctx.arc(x + barWidth / 2, Math.max(y - 8, padding.top - 12), 4, 0, Math.PI * 2);
+            ctx.fill();
+        }
+
         // Date labels
         ctx.fillStyle = labelColor;
         ctx.font = '10px system-ui';
@@ -3893,13 +4411,125 @@ function drawHistoryChart() {
     }
 }
 
+function drawTemperatureChart() {
+    const canvas = document.getElementById('temperature-chart');
+    if (!canvas) return;
+
+    const ctx = canvas.getContext('2d');
+    const container = canvas.parentElement;
+    const rect = container.getBoundingClientRect();
+
+    const dpr = window.devicePixelRatio || 1;
+    const logicalWidth = rect.width - 32;
+    const logicalHeight = rect.height - 64; // Account for chart header
+    canvas.width = logicalWidth * dpr;
+    canvas.height = logicalHeight * dpr;
+    canvas.style.width = logicalWidth + 'px';
+    canvas.style.height = logicalHeight + 'px';
+    ctx.scale(dpr, dpr);
+
+    const data = state.temperatureHistory;
+    if (data.length === 0) return;
+
+    const padding = { top: 20, right: 20, bottom: 40, left: 50 };
+    const width = logicalWidth - padding.left - padding.right;
+    const height = logicalHeight - padding.top - padding.bottom;
+
+    ctx.clearRect(0, 0, logicalWidth, logicalHeight);
+
+    const temps = data.flatMap(r => [r.cpu_c, r.gpu_c]).filter(t => t != null);
+    const minT = Math.max(0, Math.floor(Math.min(...temps) / 10) * 10);
+    const maxT = Math.ceil(Math.max(...temps) / 10) * 10 || 100;
+    const range = Math.max(maxT - minT, 10);
+    const minTs = data[0].timestamp;
+    const spanTs = Math.max(data[data.length - 1].timestamp - minTs, 1);
+
+    const isDark = document.documentElement.getAttribute('data-theme') !== 'light';
+    const gridColor = isDark ? 'rgba(255, 255, 255, 0.08)' : 'rgba(0, 0, 0, 0.08)';
+    const labelColor = isDark ? 'rgba(255, 255, 255, 0.5)' : 'rgba(0, 0, 0, 0.5)';
+
+    ctx.strokeStyle = gridColor;
+    ctx.lineWidth = 1;
+    for (let i = 0; i <= 4; i++) {
+        const y = padding.top + (height / 4) * i;
+        ctx.beginPath();
+        ctx.moveTo(padding.left, y);
+        ctx.lineTo(padding.left + width, y);
+        ctx.stroke();
+
+        ctx.fillStyle = labelColor;
+        ctx.font = '11px system-ui';
+        ctx.textAlign = 'right';
+        ctx.fillText(formatNumber(minT + range - (range / 4) * i, 0) + ' \u00B0C', padding.left - 8, y + 4);
+    }
+
+    // Time labels at start, middle and end
+    ctx.textAlign = 'center';
+    ctx.font = '10px system-ui';
+    [0, 0.5, 1].forEach(f => {
+        const label = new Date((minTs + spanTs * f) * 1000).toLocaleString([], { month: '2-digit', day: '2-digit', hour: '2-digit', minute: '2-digit' });
+        ctx.fillText(label, padding.left + width * f, padding.top + height + 20);
+    });
+
+    // Lines break on missing values instead of dropping to zero
+    const drawSeries = (key, color) => {
+        ctx.strokeStyle = color;
+        ctx.lineWidth = 2;
+        ctx.beginPath();
+        let drawing = false;
+        data.forEach(r => {
+            const t = r[key];
+            if (t == null) {
+                drawing = false;
+                return;
+            }
+            const x = padding.left + ((r.timestamp - minTs) / spanTs) * width;
+            const y = padding.top + height - ((t - minT) / range) * height;
+            if (drawing) ctx.lineTo(x, y);
+            else ctx.moveTo(x, y);
+            drawing = true;
+        });
+        ctx.stroke();
+    };
+    drawSeries('cpu_c', '#f97316');
+    drawSeries('gpu_c', '#6366f1');
+
+    // Legend
+    ctx.font = '11px system-ui';
+    ctx.textAlign = 'left';
+    ctx.fillStyle = '#f97316';
+    ctx.fillText('CPU', padding.left + 5, padding.top - 6);
+    ctx.fillStyle = '#6366f1';
+    ctx.fillText('GPU', padding.left + 45, padding.top - 6);
+}
+
 // ===== Settings =====
 function setupSettings() {
+    document.getElementById('capabilities-refresh-btn').addEventListener('click', () => loadCapabilities(true));
+
     const pricingModeSelect = document.getElementById('setting-pricing-mode');
     pricingModeSelect.addEventListener('change', () => {
         updatePricingModeUI(pricingModeSelect.value);
     });
 
+    const presetSelect = document.getElementById('setting-pricing-preset');
+    invoke('get_pricing_presets').then(presets => {
+        presetSelect.innerHTML = presets
+            .map(p => `<option value="${p.country_code}">${p.country_name} (${p.currency})</option>`)
+            .join('');
+    }).catch(err => console.error('Failed to load pricing presets:', err));
+
+    document.getElementById('apply-pricing-preset-btn').addEventListener('click', async () => {
+        try {
+            state.config = await invoke('apply_pricing_preset', { countryCode: presetSelect.value });
+            applyConfig(state.config);
+            showToast(t('settings.pricing.preset.applied') || 'Preset applied', 'success');
+        } catch (error) {
+            console.error('Failed to apply pricing preset:', error);
+            showToast(String(error), 'error');
+        }
+    });
+
     document.getElementById('save-settings').addEventListener('click', saveSettings);
     document.getElementById('reset-settings').addEventListener('click', async () => {
         state.config = await invoke('get_config');
@@ -3911,14 +4541,66 @@ function setupSettings() {
             const isOpen = await invoke('toggle_widget');
             const btn = document.getElementById('toggle-widget-btn');
             btn.textContent = isOpen ? t('settings.widget.close') : t('settings.widget.open');
+            if (state.config?.widget?.remember_open_state) {
+                state.config.widget.enabled = isOpen;
+            }
         } catch (error) {
             console.error('Widget toggle error:', error);
         }
     });
 
+    // Explain widget limitations on platforms like Wayland
+    invoke('get_widget_capabilities').then(caps => {
+        if (caps.warning) {
+            document.getElementById('widget-capability-warning-text').textContent =
+                t('settings.widget.limited') || caps.warning;
+            document.getElementById('widget-capability-warning').classList.remove('hidden');
+        }
+    }).catch(() => {});
+
+    document.querySelectorAll('.notification-test-btn').forEach(btn => {
+        btn.addEventListener('click', async () => {
+            try {
+                if ('Notification' in window && Notification.permission === 'default') {
+                    await Notification.requestPermission();
+                }
+                await invoke('test_notification', { kind: btn.dataset.kind });
+            } catch (error) {
+                showToast(errorMessage(error), 'error');
+            }
+        });
+    });
+
+    // Only the saved hook runs, a path typed in is saved first
+    document.getElementById('test-session-hook-btn').addEventListener('click', async () => {
+        try {
+            const path = document.getElementById('setting-session-end-hook').value.trim() || null;
+            if (path !== (state.config?.advanced?.session_end_hook || null)) {
+                await saveSettings();
+                if (path !== (state.config?.advanced?.session_end_hook || null)) return;
+            }
+            const outcome = await invoke('test_session_hook');
+            if (outcome.exit_code === 0) {
+                showToast(t('settings.session_end_hook_ok'), 'success');
+            } else if (outcome.timed_out) {
+                showToast(t('settings.session_end_hook_timeout'), 'error');
+            } else {
+                const detail = outcome.stderr ? `: ${outcome.stderr.slice(0, 200)}` : '';
+                showToast(`${t('settings.session_end_hook_failed')} (${outcome.exit_code ?? '--'})${detail}`, 'error');
+            }
+        } catch (error) {
+            showToast(errorMessage(error), 'error');
+        }
+    });
+
     document.getElementById('detect-baseline-btn').addEventListener('click', async () => {
         try {
-            const detection = await invoke('detect_baseline');
+            const report = await invoke('detect_baseline');
+            const detection = report.current;
+            for (const bucket of report.buckets) {
+                const cell = document.querySelector(`.baseline-bucket-row[data-name="${bucket.name}"] .baseline-bucket-detected`);
+                if (cell) cell.textContent = bucket.detection ? `${formatNumber(bucket.detection.detected_watts, 1)} W` : '-- W';
+            }
             if (detection) {
                 document.getElementById('detected-baseline').textContent =
                     `${formatNumber(detection.detected_watts, 1)} W (${Math.round(detection.confidence * 100)}% confidence)`;
@@ -3936,6 +4618,14 @@ function setupSettings() {
         document.getElementById('manual-baseline-row').style.display = e.target.checked ? 'none' : 'flex';
     });
 
+    document.getElementById('setting-baseline-schedule').addEventListener('change', (e) => {
+        document.getElementById('baseline-schedule-buckets').style.display = e.target.checked ? 'block' : 'none';
+    });
+
+    document.getElementById('setting-secondary-currency').addEventListener('change', (e) => {
+        document.getElementById('secondary-currency-config').style.display = e.target.value ? 'block' : 'none';
+    });
+
     // Check for updates button
     document.getElementById('check-updates-btn').addEventListener('click', async () => {
         const btn = document.getElementById('check-updates-btn');
@@ -3949,7 +4639,7 @@ function setupSettings() {
         downloadLink.classList.add('hidden');
 
         try {
-            const result = await invoke('check_for_updates');
+            const result = await invoke('force_check_for_updates');
             resultDiv.classList.remove('hidden');
 
             if (result.update_available) {
@@ -3964,6 +4654,10 @@ function setupSettings() {
                 resultText.textContent = `${t('settings.updates.up_to_date')} (v${result.current_version})`;
                 resultText.style.color = 'var(--color-success, #4caf50)';
             }
+
+            if (result.stale) {
+                resultText.textContent += ` (${t('settings.updates.stale') || 'offline, showing last known result'})`;
+            }
         } catch (error) {
             console.error('Update check error:', error);
             resultDiv.classList.remove('hidden');
@@ -3975,16 +4669,45 @@ function setupSettings() {
         }
     });
 
+    document.getElementById('reset-statistics-btn').addEventListener('click', async () => {
+        const scope = document.getElementById('setting-reset-scope').value;
+        if (!confirm(t('settings.data.reset_confirm') || 'Delete these statistics? This cannot be undone.')) return;
+
+        let force = false;
+        const activeSession = await invoke('get_session_stats').catch(() => null);
+        if (activeSession) {
+            if (!confirm(t('settings.data.reset_session_active') || 'A session is active. Reset anyway?')) return;
+            force = true;
+        }
+
+        try {
+            const token = await invoke('prepare_reset', { scope });
+            const counts = await invoke('reset_statistics', { scope, token, force });
+            const total = counts.readings + counts.daily_stats + counts.sessions;
+            showToast(`${t('settings.data.reset_done') || 'Statistics reset'} (${total})`, 'success');
+            if (force && (scope === 'sessions' || scope === 'all')) {
+                state.activeSession = null;
+            }
+        } catch (error) {
+            console.error('Reset statistics error:', error);
+            showToast(errorMessage(error), 'error');
+        }
+    });
+
     // Show run-as-admin setting on Windows only and display elevation status
     const platform = navigator.userAgent || '';
     const isWindows = platform.includes('Windows') || platform.includes('Win');
     if (isWindows) {
         document.getElementById('run-as-admin-row').style.display = 'flex';
-        invoke('is_elevated').then(elevated => {
+        document.getElementById('hardware-probe-rows').style.display = 'block';
+        invoke('get_elevation_status').then(status => {
             const statusEl = document.getElementById('elevation-status');
-            if (elevated) {
+            if (status === 'elevated') {
                 statusEl.textContent = t('settings.run_as_admin.elevated') || 'Elevated';
                 statusEl.style.color = 'var(--color-success, #4caf50)';
+            } else if (status === 'denied') {
+                statusEl.textContent = t('settings.run_as_admin.denied') || 'Elevation denied, will ask again in 24h';
+                statusEl.style.color = 'var(--color-warning, #ff9800)';
             } else {
                 statusEl.textContent = t('settings.run_as_admin.not_elevated') || 'Not elevated';
                 statusEl.style.color = 'var(--color-warning, #ff9800)';
@@ -3995,26 +4718,61 @@ function setupSettings() {
 
 function applyConfig(config) {
     document.getElementById('setting-language').value = config.general.language;
+    document.getElementById('setting-history-timezone').value = config.general.history_timezone || 'local';
+    document.getElementById('setting-energy-unit').value = config.general.energy_unit || 'auto';
+    document.getElementById('setting-cost-decimals').value = String(config.general.cost_decimals || 4);
     document.getElementById('setting-theme').value = config.general.theme;
     document.getElementById('setting-refresh-rate').value = config.general.refresh_rate_ms;
     document.getElementById('setting-slow-refresh-rate').value = config.general.slow_refresh_rate_ms || 5000;
     document.getElementById('setting-eco-mode').checked = config.general.eco_mode;
     document.getElementById('setting-start-minimized').checked = config.general.start_minimized || false;
+    document.getElementById('setting-use-emoji').checked = config.general.use_emoji !== false;
+    document.getElementById('setting-track-active-window').checked = config.advanced.track_active_window === true;
+    document.getElementById('setting-split-sessions-at-midnight').checked = config.advanced.split_sessions_at_midnight === true;
+    document.getElementById('setting-session-end-hook').value = config.advanced.session_end_hook || '';
+    document.getElementById('setting-session-idle-input').value = config.advanced.session_idle_input || 'or';
     document.getElementById('setting-start-with-system').checked = config.general.start_with_system || false;
     document.getElementById('setting-remember-window-position').checked = config.general.remember_window_position !== false;
     document.getElementById('setting-run-as-admin').checked = config.general.run_as_admin || false;
     document.getElementById('setting-check-updates-startup').checked = config.general.check_updates_at_startup || false;
+    document.getElementById('setting-show-taskbar-badge').checked = config.general.show_taskbar_badge || false;
+    const notifications = config.general.notifications || {};
+    document.querySelectorAll('.notification-kind-toggle').forEach(toggle => {
+        toggle.checked = notifications.enabled?.[toggle.dataset.kind] !== false;
+    });
+    document.getElementById('setting-quiet-hours-start').value = notifications.quiet_hours_start || '';
+    document.getElementById('setting-quiet-hours-end').value = notifications.quiet_hours_end || '';
     invoke('get_app_version').then(v => {
         document.getElementById('current-version').textContent = 'v' + v;
     }).catch(() => {});
+    loadLifetimeTotals();
 
     document.getElementById('setting-baseline-auto').checked = config.advanced.baseline_auto;
     document.getElementById('setting-baseline-watts').value = config.advanced.baseline_watts;
     document.getElementById('setting-process-limit').value = config.advanced.process_list_limit || 10;
+    document.getElementById('setting-process-grouping').value = config.advanced.process_grouping || 'name';
+    document.getElementById('setting-gpu-command-timeout').value = config.advanced.gpu_command_timeout_ms || 1500;
+    document.getElementById('setting-vram-alert-percent').value = config.advanced.vram_alert_percent ?? 0;
+    document.getElementById('setting-disable-gpu-cli').checked = config.advanced.disable_gpu_cli === true;
+    document.getElementById('setting-pause-gpu-polling-in-game').checked = config.advanced.pause_gpu_polling_in_game !== false;
+    document.getElementById('setting-disable-powershell-probes').checked = config.advanced.disable_powershell_probes === true;
+    document.getElementById('setting-gpu-idle-watts').value = config.advanced.gpu_idle_watts ?? '';
+    document.getElementById('setting-gpu-tdp-watts').value = config.advanced.gpu_tdp_watts ?? '';
+    showGpuEstimateSuggestion(config.advanced.gpu_idle_watts == null);
+    document.getElementById('setting-accuracy-tracking').checked = config.advanced.accuracy_tracking || false;
+    document.getElementById('setting-auto-apply-correction').checked = config.advanced.auto_apply_correction || false;
+    showEstimationAccuracy();
     document.getElementById('manual-baseline-row').style.display = config.advanced.baseline_auto ? 'none' : 'flex';
+    renderBaselineSchedule(config.advanced.baseline_schedule);
 
     document.getElementById('setting-pricing-mode').value = config.pricing.mode;
     document.getElementById('setting-currency').value = config.pricing.currency;
+    const secondary = config.pricing.secondary || {};
+    document.getElementById('setting-secondary-currency').value = secondary.currency || '';
+    document.getElementById('setting-manual-rate').value = secondary.manual_rate ?? '';
+    document.getElementById('setting-auto-fetch-rate').checked = secondary.auto_fetch === true;
+    document.getElementById('setting-rate-url').value = secondary.rate_url || '';
+    document.getElementById('secondary-currency-config').style.display = secondary.currency ? 'block' : 'none';
     document.getElementById('setting-rate-kwh').value = config.pricing.simple.rate_per_kwh;
     document.getElementById('setting-peak-rate').value = config.pricing.peak_offpeak.peak_rate;
     document.getElementById('setting-offpeak-rate').value = config.pricing.peak_offpeak.offpeak_rate;
@@ -4036,13 +4794,123 @@ function applyConfig(config) {
     }
 
     document.getElementById('setting-widget-show-cost').checked = config.widget.show_cost;
+    document.getElementById('setting-widget-remember-open-state').checked = config.widget.remember_open_state || false;
     document.getElementById('setting-widget-position').value = config.widget.position;
+    document.getElementById('setting-widget-session-mode').value = config.widget.session_mode || 'auto';
 
-    document.documentElement.setAttribute('data-theme', config.general.theme);
+    applyTheme(config.general.theme);
     state.currencySymbol = config.pricing.currency_symbol;
     updatePricingModeUI(config.pricing.mode);
 }
 
+// "system" is resolved by the backend from the OS preference
+async function applyTheme(setting) {
+    const theme = setting === 'dark' || setting === 'light'
+        ? setting
+        : await invoke('get_resolved_theme').catch(() => 'dark');
+    document.documentElement.setAttribute('data-theme', theme);
+}
+
+// One row per schedule bucket: start time, optional manual watts, last detection
+function renderBaselineSchedule(schedule) {
+    const container = document.getElementById('baseline-schedule-buckets');
+    const enabled = schedule?.enabled === true;
+    document.getElementById('setting-baseline-schedule').checked = enabled;
+    container.style.display = enabled ? 'block' : 'none';
+    container.replaceChildren();
+    for (const bucket of schedule?.buckets || []) {
+        const row = document.createElement('div');
+        row.className = 'setting-row baseline-bucket-row';
+        row.dataset.name = bucket.name;
+
+        const label = document.createElement('label');
+        const key = `settings.baseline.bucket_${bucket.name}`;
+        label.textContent = t(key) === key ? bucket.name : t(key);
+        label.style.flex = '1';
+
+        const start = document.createElement('input');
+        start.type = 'time';
+        start.className = 'baseline-bucket-start';
+        start.value = bucket.start;
+
+        const manual = document.createElement('input');
+        manual.type = 'number';
+        manual.className = 'baseline-bucket-manual';
+        manual.step = '0.1';
+        manual.min = '0';
+        manual.placeholder = t('settings.baseline.bucket_auto');
+        manual.value = bucket.manual_watts ?? '';
+
+        const detected = document.createElement('span');
+        detected.className = 'info-text baseline-bucket-detected';
+        detected.textContent = '-- W';
+
+        row.append(label, start, manual, detected);
+        container.appendChild(row);
+    }
+}
+
+function readBaselineSchedule() {
+    const buckets = [...document.querySelectorAll('.baseline-bucket-row')].map(row => {
+        const manual = parseFloat(row.querySelector('.baseline-bucket-manual').value);
+        return {
+            name: row.dataset.name,
+            start: row.querySelector('.baseline-bucket-start').value,
+            manual_watts: Number.isFinite(manual) ? manual : null,
+        };
+    });
+    return {
+        enabled: document.getElementById('setting-baseline-schedule').checked,
+        buckets: buckets.length ? buckets : (state.config?.advanced?.baseline_schedule?.buckets || []),
+    };
+}
+
+function parseOptionalWatts(id) {
+    const watts = parseFloat(document.getElementById(id).value);
+    return Number.isFinite(watts) ? watts : null;
+}
+
+// Default estimate for a discrete GPU the estimating power source cannot read
+async function showGpuEstimateSuggestion(unset) {
+    const container = document.getElementById('gpu-estimate-suggestion');
+    container.classList.add('hidden');
+    if (!unset) return;
+    try {
+        const suggestion = await invoke('get_gpu_estimate_suggestion');
+        if (!suggestion) return;
+        document.getElementById('gpu-estimate-suggestion-text').textContent = t('settings.gpu_estimate_suggestion')
+            .replace('{name}', suggestion.name)
+            .replace('{idle}', formatNumber(suggestion.idle_watts, 0))
+            .replace('{tdp}', suggestion.tdp_watts != null ? formatNumber(suggestion.tdp_watts, 0) : '--');
+        document.getElementById('gpu-estimate-apply').onclick = () => {
+            document.getElementById('setting-gpu-idle-watts').value = suggestion.idle_watts;
+            document.getElementById('setting-gpu-tdp-watts').value = suggestion.tdp_watts ?? '';
+            container.classList.add('hidden');
+        };
+        container.classList.remove('hidden');
+    } catch (error) {
+        console.error('Failed to get GPU estimate suggestion:', error);
+    }
+}
+
+// Error of the estimator measured by the accuracy tracking
+async function showEstimationAccuracy() {
+    const summary = document.getElementById('estimation-accuracy-summary');
+    summary.textContent = '';
+    try {
+        const accuracy = await invoke('get_estimation_accuracy');
+        if (accuracy.sample_count === 0) return;
+        summary.textContent = t('settings.accuracy_summary')
+            .replace('{count}', accuracy.sample_count)
+            .replace('{error}', formatNumber(accuracy.mean_absolute_error_watts, 1))
+            .replace('{bias}', formatNumber(accuracy.bias_watts, 1))
+            .replace('{factor}', accuracy.suggested_correction != null ? formatNumber(accuracy.suggested_correction, 2) : '--')
+            .replace('{applied}', formatNumber(accuracy.applied_correction, 2));
+    } catch (error) {
+        console.error('Failed to get estimation accuracy:', error);
+    }
+}
+
 function updatePricingModeUI(mode) {
     document.querySelectorAll('.pricing-mode-config').forEach(el => el.classList.add('hidden'));
     const modeMap = {
@@ -4059,7 +4927,6 @@ function updatePricingModeUI(mode) {
 async function saveSettings() {
     try {
         const newStartWithSystem = document.getElementById('setting-start-with-system').checked;
-        const oldStartWithSystem = state.config?.general?.start_with_system || false;
 
         const config = {
             general: {
@@ -4069,10 +4936,17 @@ async function saveSettings() {
                 slow_refresh_rate_ms: parseInt(document.getElementById('setting-slow-refresh-rate').value),
                 eco_mode: document.getElementById('setting-eco-mode').checked,
                 start_minimized: document.getElementById('setting-start-minimized').checked,
+                use_emoji: document.getElementById('setting-use-emoji').checked,
                 start_with_system: newStartWithSystem,
                 remember_window_position: document.getElementById('setting-remember-window-position').checked,
                 run_as_admin: document.getElementById('setting-run-as-admin').checked,
                 check_updates_at_startup: document.getElementById('setting-check-updates-startup').checked,
+                show_taskbar_badge: document.getElementById('setting-show-taskbar-badge').checked,
+                notifications: readNotificationSettings(),
+                history_timezone: document.getElementById('setting-history-timezone').value.trim() || 'local',
+                energy_unit: document.getElementById('setting-energy-unit').value,
+                cost_decimals: parseInt(document.getElementById('setting-cost-decimals').value),
+                elevation_denied_at: state.config?.general?.elevation_denied_at ?? null,
                 window_x: state.config?.general?.window_x ?? null,
                 window_y: state.config?.general?.window_y ?? null,
                 window_width: state.config?.general?.window_width ?? null,
@@ -4082,6 +4956,13 @@ async function saveSettings() {
                 mode: document.getElementById('setting-pricing-mode').value,
                 currency: document.getElementById('setting-currency').value,
                 currency_symbol: getCurrencySymbol(document.getElementById('setting-currency').value),
+                secondary: {
+                    currency: document.getElementById('setting-secondary-currency').value,
+                    currency_symbol: getCurrencySymbol(document.getElementById('setting-secondary-currency').value),
+                    manual_rate: parseFloat(document.getElementById('setting-manual-rate').value) || null,
+                    auto_fetch: document.getElementById('setting-auto-fetch-rate').checked,
+                    rate_url: document.getElementById('setting-rate-url').value.trim() || state.config?.pricing?.secondary?.rate_url,
+                },
                 simple: { rate_per_kwh: parseFloat(document.getElementById('setting-rate-kwh').value) },
                 peak_offpeak: {
                     peak_rate: parseFloat(document.getElementById('setting-peak-rate').value),
@@ -4111,41 +4992,57 @@ async function saveSettings() {
                 display_items: state.config?.widget?.display_items || ['power', 'cost'],
                 size: state.config?.widget?.size || 'normal',
                 theme: state.config?.widget?.theme || 'default',
+                remember_open_state: document.getElementById('setting-widget-remember-open-state').checked,
+                refresh_rate_ms: state.config?.widget?.refresh_rate_ms ?? null,
+                session_mode: document.getElementById('setting-widget-session-mode').value,
             },
             advanced: {
                 baseline_watts: parseFloat(document.getElementById('setting-baseline-watts').value) || 0,
                 baseline_auto: document.getElementById('setting-baseline-auto').checked,
+                baseline_schedule: readBaselineSchedule(),
                 active_profile: state.config?.advanced?.active_profile || 'default',
                 pinned_processes: state.config?.advanced?.pinned_processes || [],
+                ignored_processes: state.config?.advanced?.ignored_processes || [],
                 process_list_limit: parseInt(document.getElementById('setting-process-limit').value) || 10,
+                process_grouping: document.getElementById('setting-process-grouping').value,
                 extended_metrics_threshold: state.config?.advanced?.extended_metrics_threshold || 15.0,
+                max_power_watts: state.config?.advanced?.max_power_watts ?? 2000,
+                spike_filter_factor: state.config?.advanced?.spike_filter_factor ?? 4,
+                duplicate_reading_window_secs: state.config?.advanced?.duplicate_reading_window_secs ?? 2,
+                exclude_self_from_surplus: state.config?.advanced?.exclude_self_from_surplus ?? false,
+                auto_end_after_idle_minutes: state.config?.advanced?.auto_end_after_idle_minutes ?? 0,
+                track_active_window: document.getElementById('setting-track-active-window').checked,
+                split_sessions_at_midnight: document.getElementById('setting-split-sessions-at-midnight').checked,
+                session_end_hook: document.getElementById('setting-session-end-hook').value.trim() || null,
+                session_idle_input: document.getElementById('setting-session-idle-input').value,
+                vram_alert_percent: Math.min(100, Math.max(0, parseInt(document.getElementById('setting-vram-alert-percent').value) || 0)),
+                gpu_command_timeout_ms: parseInt(document.getElementById('setting-gpu-command-timeout').value) || 1500,
+                disable_gpu_cli: document.getElementById('setting-disable-gpu-cli').checked,
+                pause_gpu_polling_in_game: document.getElementById('setting-pause-gpu-polling-in-game').checked,
+                disable_powershell_probes: document.getElementById('setting-disable-powershell-probes').checked,
+                gpu_idle_watts: parseOptionalWatts('setting-gpu-idle-watts'),
+                gpu_tdp_watts: parseOptionalWatts('setting-gpu-tdp-watts'),
+                accuracy_tracking: document.getElementById('setting-accuracy-tracking').checked,
+                auto_apply_correction: document.getElementById('setting-auto-apply-correction').checked,
+                estimation_correction: state.config?.advanced?.estimation_correction ?? 1,
                 session_categories: state.sessionCategories || state.config?.advanced?.session_categories || [],
             },
             dashboard: state.dashboardConfig || state.config?.dashboard,
+            webhook: state.config?.webhook,
         };
 
-        await invoke('set_config', { config });
+        // The stored config, panels of the changed sections refresh on `config-changed`
+        const saved = await invoke('set_config', { config });
 
-        // Update autostart setting if it changed
-        if (newStartWithSystem !== oldStartWithSystem) {
-            try {
-                await invoke('set_autostart', { enabled: newStartWithSystem });
-            } catch (autoErr) {
-                console.error('Failed to set autostart:', autoErr);
-                // Don't fail the whole save if autostart fails
-            }
-        }
+        // Emoji display may have changed
+        await loadSessionCategories();
+        renderCategorySettings();
 
         // If run_as_admin was toggled on and not currently elevated, relaunch
         const oldRunAsAdmin = state.config?.general?.run_as_admin || false;
-        const newRunAsAdmin = config.general.run_as_admin;
+        const newRunAsAdmin = saved.general.run_as_admin;
 
-        state.config = config;
-        state.currencySymbol = config.pricing.currency_symbol;
-        restartDashboardUpdates();
-        document.documentElement.setAttribute('data-theme', config.general.theme);
-        await loadTranslations();
-        renderDashboard();
+        state.config = saved;
         showToast(t('settings.saved') || 'Settings saved successfully', 'success');
 
         if (newRunAsAdmin && !oldRunAsAdmin) {
@@ -4160,15 +5057,51 @@ async function saveSettings() {
         }
     } catch (error) {
         console.error('Save settings error:', error);
-        showToast(t('error.save_failed') || 'Failed to save settings', 'error');
+        showToast(`${t('error.save_failed')}: ${errorMessage(error)}`, 'error');
+    }
+}
+
+async function applyConfigChanges(sections) {
+    const previousZone = state.config?.general?.history_timezone;
+    try {
+        state.config = await invoke('get_config');
+    } catch (error) {
+        console.error('Failed to reload config:', error);
+        return;
+    }
+    const config = state.config;
+    if (sections.includes('general')) {
+        applyTheme(config.general.theme);
+        restartDashboardUpdates();
+        // Days are bucketed differently, drop what was loaded in the old zone
+        if (config.general.history_timezone !== previousZone) {
+            state.historyData = [];
+            if (document.getElementById('history').classList.contains('active')) {
+                loadHistoryForRange();
+            }
+        }
+    }
+    if (sections.includes('pricing')) {
+        state.currencySymbol = config.pricing.currency_symbol;
+        updatePricingModeUI(config.pricing.mode);
+    }
+    if (sections.includes('dashboard')) {
+        state.dashboardConfig = config.dashboard;
+    }
+    if (sections.includes('general') || sections.includes('pricing') || sections.includes('dashboard')) {
+        renderDashboard();
     }
 }
 
 function getCurrencySymbol(currency) {
-    return { 'EUR': '\u20AC', 'USD': '$', 'GBP': '\u00A3', 'CHF': 'CHF' }[currency] || currency;
+    return { 'EUR': '\u20AC', 'USD': '$', 'GBP': '\u00A3', 'CHF': 'CHF', 'CAD': '$', 'AUD': '$', 'JPY': '\u00A5' }[currency] || currency;
 }
 
 // ===== Session Categories =====
+function categoryLabel(category) {
+    return category.emoji ? `${category.emoji} ${category.name}` : category.name;
+}
+
 async function loadSessionCategories() {
     try {
         state.sessionCategories = await invoke('get_session_categories');
@@ -4185,6 +5118,10 @@ function setupCategorySettings() {
     if (addBtn) {
         addBtn.addEventListener('click', addCategory);
     }
+    const bulkBtn = document.getElementById('category-bulk-btn');
+    if (bulkBtn) {
+        bulkBtn.addEventListener('click', assignCategoryToUncategorized);
+    }
 }
 
 function renderCategorySettings() {
@@ -4196,6 +5133,9 @@ function renderCategorySettings() {
         <div class="category-item">
             <span class="category-emoji">${c.emoji}</span>
             <span class="category-name">${c.name}</span>
+            <input type="number" class="category-baseline-field" data-name="${c.name}" min="0" step="1"
+                value="${c.baseline_watts ?? ''}" placeholder="${t('settings.categories.baseline_placeholder')}"
+                title="${t('settings.categories.baseline_hint')}">
             <button class="btn btn-icon btn-sm category-delete-btn" data-name="${c.name}" title="${t('settings.categories.delete')}">
                 <svg viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" width="14" height="14">
                     <line x1="18" y1="6" x2="6" y2="18"/><line x1="6" y1="6" x2="18" y2="18"/>
@@ -4208,6 +5148,40 @@ function renderCategorySettings() {
     list.querySelectorAll('.category-delete-btn').forEach(btn => {
         btn.addEventListener('click', () => removeCategory(btn.dataset.name));
     });
+    list.querySelectorAll('.category-baseline-field').forEach(input => {
+        input.addEventListener('change', () => setCategoryBaseline(input.dataset.name, input.value));
+    });
+
+    const bulkTarget = document.getElementById('category-bulk-target');
+    if (bulkTarget) {
+        bulkTarget.replaceChildren(...categories.map(c => {
+            const option = document.createElement('option');
+            option.value = c.name;
+            option.textContent = categoryLabel(c);
+            return option;
+        }));
+    }
+}
+
+// Give past uncategorized sessions (optionally matching a label) a category, after confirming the count
+async function assignCategoryToUncategorized() {
+    const category = document.getElementById('category-bulk-target').value;
+    if (!category) return;
+    const label = document.getElementById('category-bulk-label').value.trim();
+    const filter = { label_contains: label || null, current_category: 'uncategorized' };
+
+    try {
+        const count = await invoke('bulk_update_session_category', { filter, category, dryRun: true });
+        if (count === 0) {
+            showToast(t('settings.categories.bulk_none'), 'info');
+            return;
+        }
+        if (!confirm(t('settings.categories.bulk_confirm').replace('{count}', count).replace('{category}', category))) return;
+        const updated = await invoke('bulk_update_session_category', { filter, category, dryRun: false });
+        showToast(t('settings.categories.bulk_done').replace('{count}', updated), 'success');
+    } catch (error) {
+        showToast(errorMessage(error), 'error');
+    }
 }
 
 async function addCategory() {
@@ -4216,7 +5190,7 @@ async function addCategory() {
     const emoji = emojiInput.value.trim();
     const name = nameInput.value.trim();
 
-    if (!emoji || !name) return;
+    if (!name) return;
 
     try {
         state.sessionCategories = await invoke('add_session_category', { category: { emoji, name } });
@@ -4228,6 +5202,17 @@ async function addCategory() {
     }
 }
 
+// Empty clears the override, sessions of the category then use the global baseline
+async function setCategoryBaseline(name, value) {
+    const watts = value.trim() === '' ? null : parseFloat(value);
+    try {
+        state.sessionCategories = await invoke('set_session_category_baseline', { name, baselineWatts: watts });
+    } catch (error) {
+        showToast(errorMessage(error), 'error');
+    }
+    renderCategorySettings();
+}
+
 async function removeCategory(name) {
     try {
         state.sessionCategories = await invoke('remove_session_category', { name });
@@ -4252,6 +5237,84 @@ function showToast(message, type = 'info') {
     setTimeout(() => toast.remove(), 3000);
 }
 
+// Warn when history is only kept in memory or the config was recovered, startup events come before the listeners
+async function setupPersistenceWarning() {
+    const banner = document.getElementById('persistence-warning');
+    if (!banner) return;
+
+    document.getElementById('persistence-retry-btn').addEventListener('click', async () => {
+        try {
+            await invoke('retry_database_init');
+            banner.classList.add('hidden');
+            showToast(t('warning.persistence_restored'), 'success');
+        } catch (error) {
+            showToast(errorMessage(error), 'error');
+        }
+    });
+
+    try {
+        const health = await invoke('get_monitoring_health');
+        banner.classList.toggle('hidden', !health.persistence_disabled);
+        // The config file could not be parsed at startup and its backup was loaded
+        if (health.config_recovery?.from_backup) {
+            showToast(t('config.recovered').replace('{path}', health.config_recovery.corrupt_path), 'warning');
+        }
+    } catch (error) {
+        console.error('Failed to get monitoring health:', error);
+    }
+}
+
+// Ask to confirm the pricing picked from the system region on first run
+function setupRegionDefaultsBanner() {
+    const banner = document.getElementById('region-defaults-banner');
+    const pricing = state.config?.pricing;
+    if (!banner || !pricing?.auto_region) return;
+
+    document.getElementById('region-defaults-text').textContent = t('onboarding.region_defaults')
+        .replace('{region}', pricing.auto_region)
+        .replace('{rate}', `${pricing.currency_symbol}${formatNumber(pricing.simple.rate_per_kwh, 4)}`);
+    banner.classList.remove('hidden');
+
+    const confirm = async () => {
+        banner.classList.add('hidden');
+        try {
+            state.config = await invoke('confirm_region_defaults');
+        } catch (error) {
+            console.error('Failed to confirm region defaults:', error);
+        }
+    };
+    document.getElementById('region-defaults-keep-btn').addEventListener('click', confirm);
+    document.getElementById('region-defaults-change-btn').addEventListener('click', async () => {
+        await confirm();
+        document.querySelector('.nav-link[data-view="settings"]')?.click();
+        document.getElementById('setting-pricing-preset')?.scrollIntoView({ behavior: 'smooth', block: 'center' });
+    });
+}
+
+// Show an alert sent by the backend: a system notification while the window is hidden, a toast otherwise
+function showNotification({ kind, title, body }) {
+    if (document.hidden && 'Notification' in window && Notification.permission === 'granted') {
+        new Notification(title, { body });
+        return;
+    }
+    showToast(body ? `${title} (${body})` : title, kind === 'vram_alert' ? 'error' : 'info');
+}
+
+// Notification settings of the settings form, empty quiet hours meaning none
+function readNotificationSettings() {
+    const enabled = {};
+    document.querySelectorAll('.notification-kind-toggle').forEach(toggle => {
+        enabled[toggle.dataset.kind] = toggle.checked;
+    });
+    const start = document.getElementById('setting-quiet-hours-start').value;
+    const end = document.getElementById('setting-quiet-hours-end').value;
+    return {
+        enabled,
+        quiet_hours_start: start && end ? start : null,
+        quiet_hours_end: start && end ? end : null,
+    };
+}
+
 // ===== Utility Functions =====
 function formatNumber(num, decimals = 2) {
     if (num === null || num === undefined || isNaN(num)) return '--';
@@ -4266,6 +5329,30 @@ function formatDuration(seconds) {
     return `${hrs.toString().padStart(2, '0')}:${mins.toString().padStart(2, '0')}:${secs.toString().padStart(2, '0')}`;
 }
 
+// "You've tracked 412 kWh since March 2024", with the sessions and hours in the tooltip
+async function loadLifetimeTotals() {
+    const element = document.getElementById('lifetime-totals');
+    try {
+        const totals = await invoke('get_lifetime_totals');
+        if (!totals.first_date) {
+            element.textContent = t('settings.lifetime_none');
+            return;
+        }
+        const locale = state.config?.general?.language === 'fr' ? 'fr-FR' : 'en-US';
+        const since = new Date(`${totals.first_date}T00:00:00`).toLocaleDateString(locale, { month: 'long', year: 'numeric' });
+        const kwh = totals.total_wh / 1000;
+        element.textContent = t('settings.lifetime_summary')
+            .replace('{energy}', `${formatNumber(kwh, kwh >= 100 ? 0 : 1)} kWh`)
+            .replace('{since}', since);
+        element.title = t('settings.lifetime_detail')
+            .replace('{cost}', `${formatNumber(totals.total_cost, 2)} ${state.currencySymbol || ''}`.trim())
+            .replace('{hours}', formatNumber(totals.usage_seconds / 3600, 0))
+            .replace('{sessions}', totals.session_count);
+    } catch (error) {
+        console.error('Failed to load lifetime totals:', error);
+    }
+}
+
 function formatDate(date) {
     return date.toISOString().split('T')[0];
 }
@@ -4580,6 +5667,9 @@ window.addEventListener('resize', () => {
     if (state.historyData.length > 0) {
         drawHistoryChart();
     }
+    if (state.temperatureHistory.length > 0) {
+        drawTemperatureChart();
+    }
     drawMiniCharts();
 
     // Debounced dashboard grid reflow on column count change